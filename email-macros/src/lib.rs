@@ -1,14 +1,58 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Index, Member};
 
+/// Derive the backend context trait.
+///
+/// The derived `close()` closes all the fields that are backend
+/// contexts, `Option`s of backend contexts included, in declaration
+/// order. Other fields are left untouched. All fields are closed even
+/// if one of them fails, in which case the first error is returned.
 #[proc_macro_derive(BackendContext)]
 pub fn derive_backend_context(input: TokenStream) -> TokenStream {
     let input: DeriveInput = parse_macro_input!(input);
     let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let members: Vec<Member> = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.clone())
+                .map(Member::Named)
+                .collect(),
+            Fields::Unnamed(fields) => (0..fields.unnamed.len())
+                .map(|index| Member::Unnamed(Index::from(index)))
+                .collect(),
+            Fields::Unit => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+
+    let len = members.len();
 
     let output = quote! {
-        impl email::backend::context::BackendContext for #ident {}
+        #[email::backend::context::derive::async_trait]
+        impl #impl_generics email::backend::context::BackendContext for #ident #ty_generics #where_clause {
+            async fn close(&self) -> email::AnyResult<()> {
+                #[allow(unused_imports)]
+                use email::backend::context::derive::{ContextField as _, Field, OtherField as _};
+
+                let contexts: [Option<&dyn email::backend::context::BackendContext>; #len] = [
+                    #( (&&Field(&self.#members)).as_context(), )*
+                ];
+
+                let mut res = Ok(());
+
+                for ctx in contexts.into_iter().flatten() {
+                    let ctx_res = ctx.close().await;
+                    res = res.and(ctx_res);
+                }
+
+                res
+            }
+        }
     };
 
     TokenStream::from(output)
@@ -1,11 +1,23 @@
-use std::sync::Arc;
+use std::{
+    any::Any,
+    error, fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use email::{
     account::config::{passwd::PasswordConfig, AccountConfig},
     backend::{
-        context::BackendContextBuilder, feature::BackendFeature, macros::BackendContext,
-        mapper::SomeBackendContextBuilderMapper, Backend, BackendBuilder,
+        self,
+        context::{BackendContext as _, BackendContextBuilder},
+        feature::{BackendFeature, CheckUp},
+        macros::BackendContext,
+        mapper::SomeBackendContextBuilderMapper,
+        Backend, BackendBuilder,
     },
     folder::{list::ListFolders, Folder, FolderKind},
     imap::{
@@ -14,10 +26,14 @@
     },
     smtp::{SmtpContextBuilder, SmtpContextSync},
     tls::Encryption,
-    AnyResult,
+    AnyError, AnyResult,
 };
 use email_testing_server::with_email_testing_server;
 use secret::Secret;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
 
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn test_dynamic_backend() {
@@ -110,3 +126,182 @@ async fn build(self) -> AnyResult<Self::Context> {
     })
     .await
 }
+
+/// Context built by [`StubContextBuilder`].
+#[derive(BackendContext)]
+struct StubContext {
+    healthy: bool,
+}
+
+/// Context builder connecting to the given address, then waiting for
+/// a server greeting.
+#[derive(Clone)]
+struct StubContextBuilder {
+    addr: String,
+    healthy: bool,
+}
+
+#[derive(Debug)]
+struct StubError;
+
+impl fmt::Display for StubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stub context is unhealthy")
+    }
+}
+
+impl error::Error for StubError {}
+
+impl AnyError for StubError {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct StubCheckUp {
+    healthy: bool,
+}
+
+#[async_trait]
+impl CheckUp for StubCheckUp {
+    async fn check_up(&self) -> AnyResult<()> {
+        if self.healthy {
+            Ok(())
+        } else {
+            Err(Box::new(StubError))
+        }
+    }
+}
+
+#[async_trait]
+impl BackendContextBuilder for StubContextBuilder {
+    type Context = StubContext;
+
+    fn check_up(&self) -> Option<BackendFeature<Self::Context, dyn CheckUp>> {
+        Some(Arc::new(|ctx: &StubContext| -> Option<Box<dyn CheckUp>> {
+            Some(Box::new(StubCheckUp {
+                healthy: ctx.healthy,
+            }))
+        }))
+    }
+
+    async fn build(self) -> AnyResult<Self::Context> {
+        let mut stream = TcpStream::connect(&self.addr).await.unwrap();
+        let mut greeting = [0; 1];
+        stream.read_exact(&mut greeting).await.unwrap();
+
+        Ok(StubContext {
+            healthy: self.healthy,
+        })
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn test_dynamic_backend_build_timeout() {
+    // the server accepts connections, but never greets
+    let server = TcpListener::bind("localhost:0").await.unwrap();
+
+    let account_config = Arc::new(AccountConfig::default());
+    let ctx_builder = StubContextBuilder {
+        addr: server.local_addr().unwrap().to_string(),
+        healthy: true,
+    };
+
+    let err = BackendBuilder::new(account_config, ctx_builder)
+        .with_build_timeout(Duration::from_millis(100))
+        .build()
+        .await
+        .err()
+        .unwrap();
+
+    assert!(matches!(
+        err.as_any().downcast_ref(),
+        Some(backend::Error::BuildContextTimedOutError(_))
+    ));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_dynamic_backend_build_check_up() {
+    let server = TcpListener::bind("localhost:0").await.unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = server.accept().await.unwrap();
+            stream.write_all(b"*").await.unwrap();
+        }
+    });
+
+    let account_config = Arc::new(AccountConfig::default());
+
+    let healthy = StubContextBuilder {
+        addr: addr.clone(),
+        healthy: true,
+    };
+    let unhealthy = StubContextBuilder {
+        addr,
+        healthy: false,
+    };
+
+    // the check up is opt-in
+    BackendBuilder::new(account_config.clone(), unhealthy.clone())
+        .build()
+        .await
+        .unwrap();
+
+    BackendBuilder::new(account_config.clone(), healthy)
+        .with_check_up_on_build(true)
+        .build()
+        .await
+        .unwrap();
+
+    let err = BackendBuilder::new(account_config, unhealthy)
+        .with_check_up_on_build(true)
+        .build()
+        .await
+        .err()
+        .unwrap();
+
+    assert!(matches!(
+        err.as_any().downcast_ref(),
+        Some(backend::Error::CheckUpContextError(_))
+    ));
+}
+
+/// Context counting how many times it has been closed.
+struct ClosableContext(Arc<AtomicUsize>);
+
+#[async_trait]
+impl backend::context::BackendContext for ClosableContext {
+    async fn close(&self) -> AnyResult<()> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Context made of subcontexts and of other fields.
+#[derive(BackendContext)]
+struct CompositeContext {
+    closable: ClosableContext,
+    some: Option<ClosableContext>,
+    none: Option<ClosableContext>,
+    healthy: bool,
+}
+
+#[test_log::test(tokio::test)]
+async fn test_dynamic_backend_close_subcontexts() {
+    let closed = Arc::new(AtomicUsize::new(0));
+
+    let ctx = CompositeContext {
+        closable: ClosableContext(closed.clone()),
+        some: Some(ClosableContext(closed.clone())),
+        none: None,
+        healthy: true,
+    };
+
+    ctx.close().await.unwrap();
+
+    // fields that are not contexts are left untouched
+    assert_eq!(closed.load(Ordering::SeqCst), 2);
+    assert!(ctx.healthy);
+}
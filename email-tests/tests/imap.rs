@@ -9,15 +9,16 @@
     folder::{add::AddFolder, config::FolderConfig, expunge::ExpungeFolder, SENT},
     imap::{
         config::{ImapAuthConfig, ImapConfig},
-        ImapContextBuilder,
+        ImapContextBuilder, ImapMailboxMode,
     },
     message::{
         add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
-        r#move::MoveMessages,
+        peek::PeekMessages, r#move::MoveMessages,
     },
     tls::Encryption,
 };
 use email_testing_server::with_email_testing_server;
+use mail_builder::MessageBuilder;
 use mml::MmlCompilerBuilder;
 use secret::Secret;
 
@@ -194,3 +195,53 @@ async fn test_imap_features() {
     })
     .await
 }
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_imap_select_modes() {
+    with_email_testing_server(|ports| async move {
+        let account_config = Arc::new(AccountConfig::default());
+
+        let imap_config = Arc::new(ImapConfig {
+            host: "localhost".into(),
+            port: ports.imap,
+            encryption: Some(Encryption::None),
+            login: "bob".into(),
+            auth: ImapAuthConfig::Password(PasswordConfig(Secret::new_raw("password"))),
+            ..Default::default()
+        });
+
+        // a single client is used, so that its selected mailbox can
+        // be inspected after each operation
+        let imap_ctx =
+            ImapContextBuilder::new(account_config.clone(), imap_config.clone()).with_pool_size(1);
+        let imap = BackendBuilder::new(account_config.clone(), imap_ctx)
+            .build()
+            .await
+            .unwrap();
+
+        let email = MessageBuilder::new()
+            .from("alice@localhost")
+            .to("bob@localhost")
+            .subject("subject")
+            .text_body("Hello, world!")
+            .write_to_vec()
+            .unwrap();
+
+        let id = Id::from(imap.add_message("INBOX", &email).await.unwrap());
+
+        // peeking messages uses EXAMINE
+        imap.peek_messages("INBOX", &id).await.unwrap();
+        assert_eq!(
+            imap.context.client().await.selected_mailbox(),
+            Some(("INBOX", ImapMailboxMode::ReadOnly)),
+        );
+
+        // adding flags uses SELECT
+        imap.add_flag("INBOX", &id, Flag::Flagged).await.unwrap();
+        assert_eq!(
+            imap.context.client().await.selected_mailbox(),
+            Some(("INBOX", ImapMailboxMode::ReadWrite)),
+        );
+    })
+    .await
+}
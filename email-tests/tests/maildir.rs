@@ -1,18 +1,34 @@
-use std::{collections::HashMap, iter::FromIterator, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    iter::FromIterator,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use concat_with::concat_line;
 use email::{
     account::config::AccountConfig,
-    backend::BackendBuilder,
-    envelope::{list::ListEnvelopes, Id},
-    flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flag},
+    backend::{context::BackendContextBuilder, BackendBuilder},
+    envelope::{
+        config::EnvelopeConfig,
+        get::GetEnvelope,
+        list::{config::EnvelopeListConfig, ListEnvelopes, ListFolderEnvelopes},
+        Id, SingleId,
+    },
+    flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flag, Flags},
     folder::{
         add::AddFolder, config::FolderConfig, delete::DeleteFolder, expunge::ExpungeFolder,
-        list::ListFolders, Folder, FolderKind, Folders,
+        list::ListFolders, rename::RenameFolder, Folder, FolderKind, Folders,
     },
     maildir::{config::MaildirConfig, MaildirContextBuilder},
     message::{
-        add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
+        add::AddMessage,
+        config::MessageConfig,
+        copy::CopyMessages,
+        delete::{config::DeleteMessageConfig, DeleteMessages},
+        get::GetMessages,
+        peek::PeekMessages,
         r#move::MoveMessages,
     },
 };
@@ -39,6 +55,7 @@ async fn test_maildir_features() {
     let mdir_config = Arc::new(MaildirConfig {
         root_dir: tmp_dir.clone(),
         maildirpp: false,
+        ..Default::default()
     });
 
     let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
@@ -319,6 +336,7 @@ async fn test_maildir_features() {
     assert_eq!(0, subdir.len());
     assert_eq!(1, trash.len());
 
+    // check that deleting a message from the trash removes it
     mdir.delete_messages("Trash", &Id::single(&trash[0].id))
         .await
         .unwrap();
@@ -326,6 +344,16 @@ async fn test_maildir_features() {
         .list_envelopes("Trash", Default::default())
         .await
         .unwrap();
+    assert_eq!(0, trash.len());
+
+    // check that messages flagged as deleted can be expunged
+    mdir.add_message_with_flag("Trash", &email, Flag::Deleted)
+        .await
+        .unwrap();
+    let trash = mdir
+        .list_envelopes("Trash", Default::default())
+        .await
+        .unwrap();
     assert_eq!(1, trash.len());
     assert!(trash[0].flags.contains(&Flag::Deleted));
 
@@ -336,3 +364,1148 @@ async fn test_maildir_features() {
         .unwrap();
     assert_eq!(0, trash.len());
 }
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_search_bodies() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    for body in ["First message.", "Second message.", "Third message."] {
+        let email = MessageBuilder::new()
+            .from("alice@localhost")
+            .to("bob@localhost")
+            .subject("Message")
+            .text_body(body)
+            .write_to_vec()
+            .unwrap();
+        mdir.add_message("INBOX", &email).await.unwrap();
+    }
+
+    let email = concat_line!(
+        "From: alice@localhost",
+        "To: bob@localhost",
+        "Subject: Message",
+        "Content-Type: text/plain; charset=utf-8",
+        "Content-Transfer-Encoding: quoted-printable",
+        "",
+        "Let's meet at the caf=C3=A9 near the Old Bridge.",
+    );
+    let id = mdir.add_message("INBOX", email.as_bytes()).await.unwrap();
+
+    let ctx = mdir.context.lock().await;
+
+    let ids = ctx.search_bodies("INBOX", "\"old bridge\"").await.unwrap();
+    assert_eq!(ids, vec![id.clone()]);
+
+    let ids = ctx.search_bodies("INBOX", "café bridge").await.unwrap();
+    assert_eq!(ids, vec![id.clone()]);
+
+    let ids = ctx.search_bodies("INBOX", "bridge first").await.unwrap();
+    assert!(ids.is_empty());
+
+    let ids = ctx.search_bodies("INBOX", "bridge or first").await.unwrap();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&id));
+
+    let ids = ctx.search_bodies("INBOX", "message").await.unwrap();
+    assert_eq!(ids.len(), 3);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_search_attachments() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Invoice")
+        .text_body("Please find the invoice attached.")
+        .attachment("application/pdf", "Invoice-2024.pdf", &b"%PDF-1.4"[..])
+        .write_to_vec()
+        .unwrap();
+    let pdf_id = mdir.add_message("INBOX", &email).await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Invoice")
+        .text_body("Please find the invoice attached.")
+        .attachment("text/csv", "invoice-2024.csv", "amount\n42\n")
+        .write_to_vec()
+        .unwrap();
+    let csv_id = mdir.add_message("INBOX", &email).await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Invoice")
+        .text_body("I will send the invoice tomorrow.")
+        .write_to_vec()
+        .unwrap();
+    mdir.add_message("INBOX", &email).await.unwrap();
+
+    let ctx = mdir.context.lock().await;
+
+    let ids = ctx.search_bodies("INBOX", "filename:*.pdf").await.unwrap();
+    assert_eq!(ids, vec![pdf_id.clone()]);
+
+    let ids = ctx
+        .search_bodies("INBOX", "invoice filename:*.pdf")
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![pdf_id.clone()]);
+
+    let ids = ctx
+        .search_bodies("INBOX", "tomorrow filename:*.pdf")
+        .await
+        .unwrap();
+    assert!(ids.is_empty());
+
+    let ids = ctx.search_bodies("INBOX", "has:attachment").await.unwrap();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&pdf_id));
+    assert!(ids.contains(&csv_id));
+
+    let ids = ctx.search_bodies("INBOX", "invoice").await.unwrap();
+    assert_eq!(ids.len(), 3);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_delete_messages() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+    mdir.add_folder("Trash").await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Delete me")
+        .text_body("Delete me.")
+        .write_to_vec()
+        .unwrap();
+    let id = mdir.add_message("INBOX", &email).await.unwrap();
+
+    // deleting a message from the inbox moves it to the trash
+    mdir.delete_messages("INBOX", &Id::single(id))
+        .await
+        .unwrap();
+
+    let inbox = mdir
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    let trash = mdir
+        .list_envelopes("Trash", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(0, inbox.len());
+    assert_eq!(1, trash.len());
+    assert!(!trash[0].flags.contains(&Flag::Deleted));
+
+    let trash_files = |dir: &str| {
+        fs::read_dir(tmp_dir.join("Trash").join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>()
+    };
+
+    let paths = [trash_files("cur"), trash_files("new")].concat();
+    assert_eq!(1, paths.len());
+
+    // deleting a message from the trash definitely removes it
+    mdir.delete_messages("Trash", &Id::single(&trash[0].id))
+        .await
+        .unwrap();
+
+    let trash = mdir
+        .list_envelopes("Trash", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(0, trash.len());
+
+    // the file itself is gone, not just hidden from the listing
+    assert!(!paths[0].exists());
+    assert!(trash_files("cur").is_empty());
+    assert!(trash_files("new").is_empty());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_rename_folder() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+    mdir.add_folder("Archive").await.unwrap();
+    mdir.add_folder("Archive/2024").await.unwrap();
+    mdir.add_folder("Other").await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Rename me")
+        .text_body("Rename me.")
+        .write_to_vec()
+        .unwrap();
+    mdir.add_message_with_flag("Archive", &email, Flag::Seen)
+        .await
+        .unwrap();
+    mdir.add_message("Archive/2024", &email).await.unwrap();
+
+    // renaming a folder keeps its messages and flags, and moves its
+    // nested folders along with it
+
+    mdir.rename_folder("Archive", "Archives").await.unwrap();
+
+    let folders: BTreeSet<String> = mdir
+        .list_folders()
+        .await
+        .unwrap()
+        .iter()
+        .map(|folder| folder.name.clone())
+        .collect();
+    let expected_folders = BTreeSet::from_iter([
+        "INBOX".to_owned(),
+        "Archives".to_owned(),
+        "Archives/2024".to_owned(),
+        "Other".to_owned(),
+    ]);
+    assert_eq!(folders, expected_folders);
+
+    let envelopes = mdir
+        .list_envelopes("Archives", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(1, envelopes.len());
+    assert_eq!("Rename me", envelopes[0].subject);
+    assert!(envelopes[0].flags.contains(&Flag::Seen));
+
+    let envelopes = mdir
+        .list_envelopes("Archives/2024", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(1, envelopes.len());
+
+    // renaming INBOX, a missing folder or to an existing folder
+    // should fail
+
+    assert!(mdir.rename_folder("INBOX", "Inbox2").await.is_err());
+    assert!(mdir.rename_folder("Other", "INBOX").await.is_err());
+    assert!(mdir.rename_folder("Missing", "Found").await.is_err());
+    assert!(mdir.rename_folder("Other", "Archives").await.is_err());
+
+    let envelopes = mdir
+        .list_envelopes("Archives", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(1, envelopes.len());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildirpp_rename_nested_folder() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: true,
+        ..Default::default()
+    });
+
+    let mut mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    mdir_ctx.configure().await.unwrap();
+
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("Projects").await.unwrap();
+    mdir.add_folder("Projects.Email").await.unwrap();
+    mdir.add_folder("Projects.Email.Old").await.unwrap();
+    mdir.add_folder("ProjectsArchive").await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Nested")
+        .text_body("Nested.")
+        .write_to_vec()
+        .unwrap();
+    mdir.add_message_with_flag("Projects.Email", &email, Flag::Flagged)
+        .await
+        .unwrap();
+
+    // with Maildir++, nested folders are renamed by updating their
+    // prefix, folders only sharing the same prefix are left as is
+
+    mdir.rename_folder("Projects", "Work").await.unwrap();
+
+    let folders: BTreeSet<String> = mdir
+        .list_folders()
+        .await
+        .unwrap()
+        .iter()
+        .map(|folder| folder.name.clone())
+        .collect();
+    let expected_folders = BTreeSet::from_iter([
+        "Work".to_owned(),
+        "Work.Email".to_owned(),
+        "Work.Email.Old".to_owned(),
+        "ProjectsArchive".to_owned(),
+    ]);
+    assert_eq!(folders, expected_folders);
+
+    let envelopes = mdir
+        .list_envelopes("Work.Email", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(1, envelopes.len());
+    assert_eq!("Nested", envelopes[0].subject);
+    assert!(envelopes[0].flags.contains(&Flag::Flagged));
+
+    // the Maildir++ INBOX is the root folder, it cannot be renamed
+    assert!(mdir.rename_folder("INBOX", "Inbox2").await.is_err());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_new_cur_placement() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    let entries = |dir: &str| -> Vec<String> {
+        std::fs::read_dir(tmp_dir.join("INBOX").join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect()
+    };
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Placement")
+        .text_body("Placement.")
+        .write_to_vec()
+        .unwrap();
+
+    // messages without flags are delivered to new, messages with
+    // flags are stored in cur
+
+    let unseen_id = mdir.add_message("INBOX", &email).await.unwrap();
+    assert_eq!(entries("new").len(), 1);
+    assert_eq!(entries("cur").len(), 0);
+
+    let seen_id = mdir
+        .add_message_with_flag("INBOX", &email, Flag::Seen)
+        .await
+        .unwrap();
+    assert_eq!(entries("new").len(), 1);
+    assert_eq!(entries("cur").len(), 1);
+
+    mdir.add_message_with_flag("INBOX", &email, Flag::Flagged)
+        .await
+        .unwrap();
+    assert_eq!(entries("new").len(), 1);
+    assert_eq!(entries("cur").len(), 2);
+
+    // messages from new are listed as unseen
+
+    let envelopes = mdir
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    let unseen = envelopes
+        .iter()
+        .find(|envelope| envelope.id == unseen_id.as_str())
+        .unwrap();
+    assert!(!unseen.flags.contains(&Flag::Seen));
+    let seen = envelopes
+        .iter()
+        .find(|envelope| envelope.id == seen_id.as_str())
+        .unwrap();
+    assert!(seen.flags.contains(&Flag::Seen));
+
+    // adding the seen flag moves the message from new to cur
+
+    mdir.add_flag("INBOX", &Id::single(unseen_id.as_str()), Flag::Seen)
+        .await
+        .unwrap();
+    assert_eq!(entries("new").len(), 0);
+    assert_eq!(entries("cur").len(), 3);
+    assert!(entries("cur").iter().any(|name| name
+        .starts_with(&format!("{}:2,", unseen_id.as_str()))
+        && name.ends_with('S')));
+
+    let envelopes = mdir
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    let unseen = envelopes
+        .iter()
+        .find(|envelope| envelope.id == unseen_id.as_str())
+        .unwrap();
+    assert!(unseen.flags.contains(&Flag::Seen));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_custom_flags() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Custom flags")
+        .text_body("Custom flags.")
+        .write_to_vec()
+        .unwrap();
+
+    let flags_of = |id: SingleId| {
+        let mdir = &mdir;
+        async move {
+            let envelopes = mdir
+                .list_envelopes("INBOX", Default::default())
+                .await
+                .unwrap();
+            envelopes
+                .iter()
+                .find(|envelope| envelope.id == id.as_str())
+                .unwrap()
+                .flags
+                .clone()
+        }
+    };
+
+    // custom flags are stored as keywords, and survive a round trip
+    // through the file name
+
+    let flags = Flags::from_iter([Flag::Seen, Flag::custom("has space")]);
+    let id = mdir
+        .add_message_with_flags("INBOX", &email, &flags)
+        .await
+        .unwrap();
+    assert_eq!(flags_of(id.clone()).await, flags);
+
+    let keywords = fs::read_to_string(tmp_dir.join("INBOX").join("dovecot-keywords")).unwrap();
+    assert_eq!(keywords, "0 has space\n");
+
+    mdir.add_flag("INBOX", &Id::single(id.as_str()), Flag::custom("café"))
+        .await
+        .unwrap();
+    assert_eq!(
+        flags_of(id.clone()).await,
+        Flags::from_iter([Flag::Seen, Flag::custom("has space"), Flag::custom("café")]),
+    );
+
+    mdir.remove_flag("INBOX", &Id::single(id.as_str()), Flag::custom("has space"))
+        .await
+        .unwrap();
+    assert_eq!(
+        flags_of(id.clone()).await,
+        Flags::from_iter([Flag::Seen, Flag::custom("café")]),
+    );
+
+    mdir.set_flags(
+        "INBOX",
+        &Id::single(id.as_str()),
+        &Flags::from_iter([Flag::custom("has space")]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        flags_of(id.clone()).await,
+        Flags::from_iter([Flag::custom("has space")]),
+    );
+
+    // custom flags that cannot be stored are rejected
+
+    let res = mdir
+        .add_flag(
+            "INBOX",
+            &Id::single(id.as_str()),
+            Flag::custom("line\nbreak"),
+        )
+        .await;
+    assert!(res.is_err());
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread", worker_threads = 4))]
+async fn test_maildir_concurrent_deliveries() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        delivery_lock: true,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    // each writer has its own context, like separate processes
+    let writers = (0..4).map(|writer| {
+        let account_config = account_config.clone();
+        let mdir_config = mdir_config.clone();
+
+        tokio::spawn(async move {
+            let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+            let mdir = BackendBuilder::new(account_config, mdir_ctx)
+                .build()
+                .await
+                .unwrap();
+
+            for n in 0..10 {
+                let email = MessageBuilder::new()
+                    .from("alice@localhost")
+                    .to("bob@localhost")
+                    .subject(format!("Message {writer}-{n}"))
+                    .text_body("Hello, world!")
+                    .write_to_vec()
+                    .unwrap();
+                mdir.add_message("INBOX", &email).await.unwrap();
+            }
+        })
+    });
+
+    for writer in writers.collect::<Vec<_>>() {
+        writer.await.unwrap();
+    }
+
+    let envelopes = mdir
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    let subjects: BTreeSet<_> = envelopes.iter().map(|e| e.subject.clone()).collect();
+
+    assert_eq!(envelopes.len(), 40);
+    assert_eq!(subjects.len(), 40);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_expunge_trash_older_than() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        message: Some(MessageConfig {
+            delete: Some(DeleteMessageConfig {
+                trash_retention_days: Some(30),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx.clone())
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+    mdir.add_folder("Trash").await.unwrap();
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let days_ago = |days: u64| now.as_secs() - days * 24 * 60 * 60;
+
+    let add_entry = |folder: &str, name: String| {
+        let path = tmp_dir.join(folder).join("cur").join(name);
+        fs::write(path, "Subject: old\r\n\r\nHello, world!\r\n").unwrap();
+    };
+
+    let entries = |folder: &str| -> BTreeSet<String> {
+        fs::read_dir(tmp_dir.join(folder).join("cur"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect()
+    };
+
+    let very_old = format!("{}.M1P1.localhost:2,S", days_ago(40));
+    let old = format!("{}.M2P1.localhost:2,S", days_ago(10));
+    let recent = format!("{}.M3P1.localhost:2,S", days_ago(1));
+    // no timestamp, so the modification time of the file is used
+    let custom = String::from("custom.localhost:2,S");
+
+    add_entry("Trash", very_old.clone());
+    add_entry("Trash", old.clone());
+    add_entry("Trash", recent.clone());
+    add_entry("Trash", custom.clone());
+    add_entry("INBOX", very_old.clone());
+
+    // expunging the trash applies the retention policy
+
+    mdir.expunge_folder("Trash").await.unwrap();
+    assert_eq!(
+        entries("Trash"),
+        BTreeSet::from_iter([old, recent.clone(), custom.clone()])
+    );
+
+    let ctx = mdir_ctx.build().await.unwrap();
+    let ctx = ctx.lock().await;
+    let count = ctx
+        .expunge_trash_older_than(Duration::from_secs(7 * 24 * 60 * 60))
+        .unwrap();
+
+    assert_eq!(count, 1);
+    assert_eq!(entries("Trash"), BTreeSet::from_iter([recent, custom]));
+
+    // other folders are never touched
+
+    mdir.expunge_folder("INBOX").await.unwrap();
+    assert_eq!(entries("INBOX"), BTreeSet::from_iter([very_old]));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_get_envelopes() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    let mut ids = Vec::new();
+
+    for n in 1..=3 {
+        let email = MessageBuilder::new()
+            .from("alice@localhost")
+            .to("bob@localhost")
+            .subject(format!("Message {n}"))
+            .text_body("Hello, world!")
+            .write_to_vec()
+            .unwrap();
+        ids.push(mdir.add_message("INBOX", &email).await.unwrap());
+    }
+
+    // envelopes are returned in the order of the given ids
+
+    let envelopes = mdir
+        .get_envelopes("INBOX", &[ids[2].clone(), ids[0].clone()])
+        .await
+        .unwrap();
+    let subjects: Vec<_> = envelopes.iter().map(|e| e.subject.as_str()).collect();
+    assert_eq!(subjects, vec!["Message 3", "Message 1"]);
+
+    // missing ids are reported
+
+    let err = mdir
+        .get_envelopes("INBOX", &[ids[1].clone(), SingleId::from("missing")])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("missing"));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_get_moves_new_to_cur() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let entries = |dir: &str| -> Vec<String> {
+        fs::read_dir(tmp_dir.join("INBOX").join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect()
+    };
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("New")
+        .text_body("New.")
+        .write_to_vec()
+        .unwrap();
+
+    // getting messages in read-only scenarios leaves them in new
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        keep_new_on_get: true,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+    let id = mdir.add_message("INBOX", &email).await.unwrap();
+    let id = Id::single(id.as_str());
+
+    mdir.get_messages("INBOX", &id).await.unwrap();
+    assert_eq!(entries("new").len(), 1);
+    assert_eq!(entries("cur").len(), 0);
+
+    // peeking messages leaves them in new
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    let msgs = mdir.peek_messages("INBOX", &id).await.unwrap();
+    assert_eq!(msgs.to_vec().len(), 1);
+    assert_eq!(entries("new").len(), 1);
+    assert_eq!(entries("cur").len(), 0);
+
+    // getting messages moves them from new to cur, as seen
+
+    let msgs = mdir.get_messages("INBOX", &id).await.unwrap();
+    assert_eq!(msgs.to_vec().len(), 1);
+    assert_eq!(entries("new").len(), 0);
+    assert_eq!(entries("cur").len(), 1);
+    assert!(entries("cur")[0].ends_with(":2,S"));
+
+    // the message can still be got from cur
+
+    let msgs = mdir.get_messages("INBOX", &id).await.unwrap();
+    assert_eq!(msgs.to_vec().len(), 1);
+}
+
+#[cfg(unix)]
+#[test_log::test(tokio::test)]
+async fn test_maildir_folder_and_message_modes() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        dir_mode: Some(0o700),
+        file_mode: Some(0o600),
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    let mode = |path: &std::path::Path| fs::metadata(path).unwrap().mode() & 0o777;
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    let inbox = tmp_dir.join("INBOX");
+    assert_eq!(mode(&inbox), 0o700);
+    assert_eq!(mode(&inbox.join("cur")), 0o700);
+    assert_eq!(mode(&inbox.join("new")), 0o700);
+    assert_eq!(mode(&inbox.join("tmp")), 0o700);
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Modes")
+        .text_body("Modes.")
+        .write_to_vec()
+        .unwrap();
+
+    mdir.add_message("INBOX", &email).await.unwrap();
+
+    let entry = fs::read_dir(inbox.join("new"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(mode(&entry.path()), 0o600);
+
+    // copied and moved messages get the file mode as well
+    mdir.add_folder("Archives").await.unwrap();
+    mdir.add_folder("Trash").await.unwrap();
+
+    let id = Id::single(entry.file_name().to_string_lossy().as_ref());
+    fs::set_permissions(entry.path(), fs::Permissions::from_mode(0o644)).unwrap();
+
+    mdir.copy_messages("INBOX", "Archives", &id).await.unwrap();
+    let entry = fs::read_dir(tmp_dir.join("Archives").join("new"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(mode(&entry.path()), 0o600);
+
+    mdir.move_messages("INBOX", "Trash", &id).await.unwrap();
+    let entry = fs::read_dir(tmp_dir.join("Trash").join("new"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(mode(&entry.path()), 0o600);
+}
+
+#[cfg(unix)]
+#[test_log::test(tokio::test)]
+async fn test_maildirpp_root_inbox_mode() {
+    use std::os::unix::fs::MetadataExt;
+
+    let tmp_dir = tempdir().unwrap();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.path().to_owned(),
+        maildirpp: true,
+        dir_mode: Some(0o700),
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+
+    // the inbox is the root folder, not a `.INBOX` subfolder
+    assert!(!tmp_dir.path().join(".INBOX").exists());
+    assert!(tmp_dir.path().join("cur").is_dir());
+
+    let mode = fs::metadata(tmp_dir.path()).unwrap().mode() & 0o777;
+    assert_eq!(mode, 0o700);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_build_with_check_up() {
+    let tmp_dir = tempdir().unwrap();
+
+    let account_config = Arc::new(AccountConfig::default());
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.path().to_owned(),
+        maildirpp: false,
+        check_up_write_access: true,
+        check_up_min_free_space: Some(1),
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let mdir_builder = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .with_build_timeout(Duration::from_secs(5))
+        .with_check_up_on_build(true);
+    let mdir = mdir_builder.clone().build().await.unwrap();
+
+    mdir.add_folder("INBOX").await.unwrap();
+    assert!(tmp_dir.path().join("INBOX").is_dir());
+
+    // the check up writes to the inbox once it exists, and leaves
+    // nothing behind
+    mdir_builder.check_up().await.unwrap();
+    let tmp_entries = fs::read_dir(tmp_dir.path().join("INBOX").join("tmp")).unwrap();
+    assert_eq!(tmp_entries.count(), 0);
+
+    // the check up fails when the root directory does not exist
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.path().join("missing"),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+    let res = BackendBuilder::new(account_config, mdir_ctx)
+        .with_check_up_on_build(true)
+        .build()
+        .await;
+    assert!(res.is_err());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildirpp_inbox_layouts() {
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let email = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Inbox")
+        .text_body("Inbox.")
+        .write_to_vec()
+        .unwrap();
+
+    for inbox_subfolder in [false, true] {
+        let tmp_dir = tempdir().unwrap().path().to_owned();
+
+        let mdir_config = Arc::new(MaildirConfig {
+            root_dir: tmp_dir.clone(),
+            maildirpp: true,
+            inbox_subfolder,
+            ..Default::default()
+        });
+
+        let mut mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config);
+        mdir_ctx.configure().await.unwrap();
+
+        let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+            .build()
+            .await
+            .unwrap();
+
+        // the .INBOX subfolder exists in both layouts, but only the
+        // subfolder layout treats it as the inbox
+        mdir.add_folder("INBOX").await.unwrap();
+        assert!(tmp_dir.join(".INBOX").join("cur").is_dir());
+
+        mdir.add_message("INBOX", &email).await.unwrap();
+
+        let inbox_dir = if inbox_subfolder {
+            tmp_dir.join(".INBOX")
+        } else {
+            tmp_dir.clone()
+        };
+
+        let entries = fs::read_dir(inbox_dir.join("new")).unwrap().count();
+        assert_eq!(entries, 1, "inbox subfolder: {inbox_subfolder}");
+
+        let folders: Vec<_> = mdir
+            .list_folders()
+            .await
+            .unwrap()
+            .iter()
+            .map(|folder| (folder.name.clone(), folder.desc.clone()))
+            .collect();
+
+        if inbox_subfolder {
+            assert_eq!(
+                folders,
+                vec![(
+                    "INBOX".to_owned(),
+                    tmp_dir.join(".INBOX").display().to_string()
+                )]
+            );
+        } else {
+            assert!(folders.is_empty());
+        }
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn test_maildir_list_folder_envelopes() {
+    let tmp_dir = tempdir().unwrap().path().to_owned();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        envelope: Some(EnvelopeConfig {
+            list: Some(EnvelopeListConfig {
+                page_size: Some(3),
+                folder_page_sizes: Some(HashMap::from_iter([("inbox".into(), 2)])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mdir_config = Arc::new(MaildirConfig {
+        root_dir: tmp_dir.clone(),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
+    let mdir = BackendBuilder::new(account_config.clone(), mdir_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    for folder in ["INBOX", "Archives"] {
+        mdir.add_folder(folder).await.unwrap();
+
+        for n in 1..=4 {
+            let email = MessageBuilder::new()
+                .from("alice@localhost")
+                .to("bob@localhost")
+                .subject(format!("Message {n}"))
+                .text_body("Hello, world!")
+                .write_to_vec()
+                .unwrap();
+            mdir.add_message(folder, &email).await.unwrap();
+        }
+    }
+
+    // the inbox uses its own page size
+
+    let envelopes = mdir.list_folder_envelopes("INBOX", 0, None).await.unwrap();
+    assert_eq!(envelopes.len(), 2);
+
+    let envelopes = mdir.list_folder_envelopes("INBOX", 1, None).await.unwrap();
+    assert_eq!(envelopes.len(), 2);
+
+    // other folders use the account page size
+
+    let envelopes = mdir
+        .list_folder_envelopes("Archives", 0, None)
+        .await
+        .unwrap();
+    assert_eq!(envelopes.len(), 3);
+
+    let envelopes = mdir
+        .list_folder_envelopes("Archives", 1, None)
+        .await
+        .unwrap();
+    assert_eq!(envelopes.len(), 1);
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use email::{
+    account::config::AccountConfig,
+    backend::BackendBuilder,
+    envelope::{get::GetEnvelope, list::ListEnvelopes, Id, SingleId},
+    flag::{add::AddFlags, remove::RemoveFlags, Flag, Flags},
+    folder::{add::AddFolder, delete::DeleteFolder, list::ListFolders},
+    memory::InMemoryContextBuilder,
+    message::{
+        add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
+        peek::PeekMessages, r#move::MoveMessages,
+    },
+};
+
+#[test_log::test(tokio::test)]
+async fn test_memory_backend() {
+    let account_config = Arc::new(AccountConfig::default());
+    let ctx = InMemoryContextBuilder::new(account_config.clone());
+
+    let backend = BackendBuilder::new(account_config, ctx)
+        .build()
+        .await
+        .unwrap();
+
+    // folders
+
+    backend.add_folder("Archive").await.unwrap();
+    backend.add_folder("Trash").await.unwrap();
+    backend.add_folder("Tmp").await.unwrap();
+    assert!(backend.add_folder("Archive").await.is_err());
+    backend.delete_folder("Tmp").await.unwrap();
+
+    let folders = backend.list_folders().await.unwrap();
+    let names: Vec<_> = folders.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["Archive", "INBOX", "Trash"]);
+    assert!(folders.iter().any(|f| f.is_inbox()));
+    assert!(folders.iter().any(|f| f.is_trash()));
+
+    // add
+
+    let msg = b"Message-ID: <1@localhost>\r\nSubject: Hello\r\n\r\nHello, world!\r\n";
+    let id = backend.add_message("INBOX", msg).await.unwrap();
+
+    let envelope = backend.get_envelope("INBOX", &id).await.unwrap();
+    assert_eq!(envelope.subject, "Hello");
+    assert!(envelope.flags.is_empty());
+
+    // peeking does not change flags, getting flags as seen
+
+    let id = Id::single(id.as_str());
+    let msgs = backend.peek_messages("INBOX", &id).await.unwrap();
+    assert_eq!(msgs.first().unwrap().raw().unwrap(), msg);
+    let envelopes = backend
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    assert!(envelopes[0].flags.is_empty());
+
+    backend.get_messages("INBOX", &id).await.unwrap();
+    let envelopes = backend
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(envelopes[0].flags, Flags::from_iter([Flag::Seen]));
+
+    // flag
+
+    backend.add_flag("INBOX", &id, Flag::Flagged).await.unwrap();
+    backend.remove_flag("INBOX", &id, Flag::Seen).await.unwrap();
+    let envelopes = backend
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(envelopes[0].flags, Flags::from_iter([Flag::Flagged]));
+
+    // copy and move
+
+    backend
+        .copy_messages("INBOX", "Archive", &id)
+        .await
+        .unwrap();
+    backend
+        .move_messages("INBOX", "Archive", &id)
+        .await
+        .unwrap();
+    assert!(backend
+        .move_messages("INBOX", "Archive", &id)
+        .await
+        .is_err());
+
+    let inbox = backend
+        .list_envelopes("INBOX", Default::default())
+        .await
+        .unwrap();
+    assert!(inbox.is_empty());
+
+    let archive = backend
+        .list_envelopes("Archive", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(archive.len(), 2);
+    assert!(archive.iter().all(|e| e.flags.contains(&Flag::Flagged)));
+
+    // the moved message keeps its id
+    let envelope = backend
+        .get_envelope("Archive", &SingleId::from(id.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(envelope.subject, "Hello");
+
+    // delete moves to trash, then removes from trash
+
+    backend.delete_messages("Archive", &id).await.unwrap();
+    let trash = backend
+        .list_envelopes("Trash", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1);
+
+    backend.delete_messages("Trash", &id).await.unwrap();
+    let trash = backend
+        .list_envelopes("Trash", Default::default())
+        .await
+        .unwrap();
+    assert!(trash.is_empty());
+
+    let archive = backend
+        .list_envelopes("Archive", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(archive.len(), 1);
+}
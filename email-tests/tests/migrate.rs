@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use email::{
+    account::config::{passwd::PasswordConfig, AccountConfig},
+    backend::{context::BackendContextBuilder, BackendBuilder},
+    envelope::list::ListEnvelopes,
+    flag::Flag,
+    folder::{add::AddFolder, list::ListFolders, INBOX},
+    imap::{
+        config::{ImapAuthConfig, ImapConfig},
+        ImapContextBuilder,
+    },
+    maildir::{
+        config::MaildirConfig,
+        migrate::{FolderMigrationReport, MigrateMaildirToImap},
+        MaildirContextBuilder,
+    },
+    message::add::AddMessage,
+    tls::Encryption,
+};
+use email_testing_server::with_email_testing_server;
+use mail_builder::MessageBuilder;
+use secret::Secret;
+use tempfile::tempdir;
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_migrate_maildir_to_imap() {
+    with_email_testing_server(|ports| async move {
+        let tmp = tempdir().unwrap().path().to_owned();
+
+        // set up maildir
+
+        let mdir_account_config = Arc::new(AccountConfig {
+            name: "maildir".into(),
+            ..Default::default()
+        });
+
+        let mdir_config = Arc::new(MaildirConfig {
+            root_dir: tmp.join("maildir"),
+            maildirpp: false,
+            ..Default::default()
+        });
+
+        let mdir_ctx = MaildirContextBuilder::new(mdir_account_config.clone(), mdir_config);
+        let mdir = BackendBuilder::new(mdir_account_config.clone(), mdir_ctx.clone())
+            .build()
+            .await
+            .unwrap();
+
+        mdir.add_folder(INBOX).await.unwrap();
+        mdir.add_folder("Archives").await.unwrap();
+
+        mdir.add_message_with_flag(
+            INBOX,
+            &MessageBuilder::new()
+                .message_id("a@localhost")
+                .from("alice@localhost")
+                .to("bob@localhost")
+                .subject("A")
+                .text_body("A")
+                .write_to_vec()
+                .unwrap(),
+            Flag::Seen,
+        )
+        .await
+        .unwrap();
+
+        mdir.add_message(
+            INBOX,
+            &MessageBuilder::new()
+                .message_id("b@localhost")
+                .from("alice@localhost")
+                .to("bob@localhost")
+                .subject("B")
+                .text_body("B")
+                .write_to_vec()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        mdir.add_message_with_flag(
+            "Archives",
+            &MessageBuilder::new()
+                .message_id("c@localhost")
+                .from("alice@localhost")
+                .to("bob@localhost")
+                .subject("C")
+                .text_body("C")
+                .write_to_vec()
+                .unwrap(),
+            Flag::Flagged,
+        )
+        .await
+        .unwrap();
+
+        // messages without Message-ID sharing the same date are
+        // distinct messages
+        for subject in ["D", "E"] {
+            let msg = format!(
+                "From: alice@localhost\r\nTo: bob@localhost\r\nDate: Thu, 1 Jan 2015 00:00:00 +0000\r\nSubject: {subject}\r\n\r\n{subject}\r\n"
+            );
+            mdir.add_message("Archives", msg.as_bytes()).await.unwrap();
+        }
+
+        // set up imap
+
+        let imap_account_config = Arc::new(AccountConfig {
+            name: "imap".into(),
+            ..Default::default()
+        });
+
+        let imap_config = Arc::new(ImapConfig {
+            host: "localhost".into(),
+            port: ports.imap,
+            encryption: Some(Encryption::None),
+            login: "bob".into(),
+            auth: ImapAuthConfig::Password(PasswordConfig(Secret::new_raw("password"))),
+            ..Default::default()
+        });
+
+        let imap_ctx = ImapContextBuilder::new(imap_account_config.clone(), imap_config);
+        let imap = BackendBuilder::new(imap_account_config.clone(), imap_ctx.clone())
+            .build()
+            .await
+            .unwrap();
+
+        // migrate
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let progress_clone = progress.clone();
+
+        let migration = MigrateMaildirToImap::new(
+            &mdir_ctx.build().await.unwrap(),
+            &imap_ctx.build().await.unwrap(),
+        )
+        .with_progress(move |report| {
+            progress_clone.lock().unwrap().push(report.folder.clone());
+        });
+
+        let mut reports = migration.migrate().await.unwrap();
+        reports.sort_by(|a, b| a.folder.cmp(&b.folder));
+
+        assert_eq!(
+            reports,
+            vec![
+                FolderMigrationReport {
+                    folder: "Archives".into(),
+                    mailbox: "Archives".into(),
+                    created: true,
+                    appended: 3,
+                    skipped: 0,
+                },
+                FolderMigrationReport {
+                    folder: INBOX.into(),
+                    mailbox: INBOX.into(),
+                    created: false,
+                    appended: 2,
+                    skipped: 0,
+                },
+            ]
+        );
+
+        let mut progress = progress.lock().unwrap().clone();
+        progress.sort();
+        assert_eq!(progress, vec![String::from("Archives"), INBOX.into()]);
+
+        let folders = imap.list_folders().await.unwrap();
+        assert!(folders.iter().any(|folder| folder.name == "Archives"));
+
+        let inbox = imap
+            .list_envelopes(INBOX, Default::default())
+            .await
+            .unwrap();
+        let mut inbox: HashMap<_, _> = inbox
+            .iter()
+            .map(|envelope| (envelope.message_id.clone(), envelope.flags.clone()))
+            .collect();
+        assert_eq!(inbox.len(), 2);
+        assert!(inbox.remove("<a@localhost>").unwrap().contains(&Flag::Seen));
+        assert!(!inbox.remove("<b@localhost>").unwrap().contains(&Flag::Seen));
+
+        let archives = imap
+            .list_envelopes("Archives", Default::default())
+            .await
+            .unwrap();
+        assert_eq!(archives.len(), 3);
+        let c = archives
+            .iter()
+            .find(|envelope| envelope.message_id == "<c@localhost>")
+            .unwrap();
+        assert!(c.flags.contains(&Flag::Flagged));
+        let mut subjects: Vec<_> = archives
+            .iter()
+            .filter(|envelope| envelope.has_generated_message_id())
+            .map(|envelope| envelope.subject.as_str())
+            .collect();
+        subjects.sort();
+        assert_eq!(subjects, vec!["D", "E"]);
+
+        // migrate again: messages already present are skipped, but
+        // messages without Message-ID cannot be identified
+
+        let mut reports = migration.migrate().await.unwrap();
+        reports.sort_by(|a, b| a.folder.cmp(&b.folder));
+
+        assert_eq!(
+            reports
+                .iter()
+                .map(|r| (r.created, r.appended, r.skipped))
+                .collect::<Vec<_>>(),
+            vec![(false, 2, 1), (false, 0, 2)]
+        );
+
+        let inbox = imap
+            .list_envelopes(INBOX, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(inbox.len(), 2);
+    })
+    .await
+}
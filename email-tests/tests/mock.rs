@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use email::{
+    account::config::AccountConfig,
+    backend::BackendBuilder,
+    envelope::{get::GetEnvelope, list::ListEnvelopes, Envelope, Envelopes, Id, SingleId},
+    flag::{set::SetFlags, Flag, Flags},
+    message::{get::GetMessages, r#move::MoveMessages},
+    mock::{MockCall, MockContextBuilder},
+};
+
+#[test_log::test(tokio::test)]
+async fn test_mock_records_calls() {
+    let account_config = Arc::new(AccountConfig::default());
+
+    let envelope = Envelope {
+        id: "1".into(),
+        message_id: "<1@localhost>".into(),
+        subject: "Hello".into(),
+        ..Default::default()
+    };
+
+    let mock = MockContextBuilder::new()
+        .with_envelopes("INBOX", Envelopes::from_iter([envelope]))
+        .with_message("INBOX", "1", "Subject: Hello\r\n\r\nHello, world!\r\n");
+
+    let backend = BackendBuilder::new(account_config, mock.clone())
+        .build()
+        .await
+        .unwrap();
+
+    // canned data is returned
+
+    let envelope = backend
+        .get_envelope("INBOX", &SingleId::from("1"))
+        .await
+        .unwrap();
+    assert_eq!(envelope.subject, "Hello");
+
+    let msgs = backend
+        .get_messages("INBOX", &Id::single("1"))
+        .await
+        .unwrap();
+    assert_eq!(msgs.to_vec().len(), 1);
+
+    backend
+        .set_flag("INBOX", &Id::single("1"), Flag::Seen)
+        .await
+        .unwrap();
+    backend
+        .move_messages("INBOX", "Archive", &Id::single("1"))
+        .await
+        .unwrap();
+
+    // missing canned data is an error, but the call is still
+    // recorded
+
+    assert!(backend
+        .get_envelope("INBOX", &SingleId::from("2"))
+        .await
+        .is_err());
+
+    assert_eq!(
+        mock.calls(),
+        vec![
+            MockCall::GetEnvelope {
+                folder: "INBOX".into(),
+                id: SingleId::from("1"),
+            },
+            MockCall::GetMessages {
+                folder: "INBOX".into(),
+                id: Id::single("1"),
+            },
+            MockCall::SetFlags {
+                folder: "INBOX".into(),
+                id: Id::single("1"),
+                flags: Flags::from_iter([Flag::Seen]),
+            },
+            MockCall::MoveMessages {
+                from_folder: "INBOX".into(),
+                to_folder: "Archive".into(),
+                id: Id::single("1"),
+            },
+            MockCall::GetEnvelope {
+                folder: "INBOX".into(),
+                id: SingleId::from("2"),
+            },
+        ]
+    );
+
+    mock.clear_calls();
+
+    let envelopes = backend
+        .list_envelopes("Archive", Default::default())
+        .await
+        .unwrap();
+    assert!(envelopes.is_empty());
+    assert_eq!(
+        mock.calls(),
+        vec![MockCall::ListEnvelopes {
+            folder: "Archive".into(),
+            opts: Default::default(),
+        }]
+    );
+}
@@ -1,19 +1,30 @@
-use std::{collections::HashMap, fs, iter::FromIterator, sync::Arc};
+use std::{collections::HashMap, fs, iter::FromIterator, path::PathBuf, sync::Arc, time::Duration};
 
 use concat_with::concat_line;
 use email::{
     account::config::AccountConfig,
     backend::BackendBuilder,
-    envelope::{list::ListEnvelopes, Id},
+    envelope::{
+        list::ListEnvelopes,
+        watch::{notmuch::WatchNotmuchEnvelopes, WatchEnvelopes, WatchEvent},
+        Id,
+    },
     flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flag, Flags},
     folder::{config::FolderConfig, INBOX},
     message::{add::AddMessage, copy::CopyMessages, get::GetMessages, r#move::MoveMessages},
-    notmuch::{config::NotmuchConfig, NotmuchContextBuilder},
+    notmuch::{
+        config::{NotmuchConfig, NotmuchUserConfig},
+        NotmuchContextBuilder, TagDelta,
+    },
 };
 use mail_builder::MessageBuilder;
 use maildirs::Maildir;
 use notmuch::Database;
 use tempfile::tempdir;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{sleep, timeout},
+};
 
 #[test_log::test(tokio::test(flavor = "multi_thread"))]
 async fn test_notmuch_features() {
@@ -48,6 +59,8 @@ async fn test_notmuch_features() {
 
     let notmuch_config = Arc::new(NotmuchConfig {
         database_path: Some(mdir.path().to_owned()),
+        // prevent new tags to be read from the user Notmuch config
+        new_tags: Some(Vec::new()),
         ..Default::default()
     });
 
@@ -299,3 +312,290 @@ async fn test_notmuch_features() {
     assert_eq!(inbox_envelopes.len(), 2);
     assert_eq!(custom_envelopes.len(), 1);
 }
+
+#[test]
+fn test_notmuch_config_discovery() {
+    let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/notmuch-config");
+    let env = |key: &str| match key {
+        "NOTMUCH_CONFIG" => Some(fixture.into()),
+        _ => None,
+    };
+
+    let path = NotmuchUserConfig::find_path(None, None, env).unwrap();
+    assert_eq!(path, PathBuf::from(fixture));
+
+    // a missing $NOTMUCH_CONFIG falls back to the home config file
+
+    let home = tempdir().unwrap();
+    let home_config = home.path().join(".notmuch-config");
+    fs::copy(fixture, &home_config).unwrap();
+
+    let missing = home.path().join("missing");
+    let env = |key: &str| match key {
+        "NOTMUCH_CONFIG" => Some(missing.clone().into()),
+        "HOME" => Some(home.path().into()),
+        _ => None,
+    };
+
+    assert_eq!(
+        NotmuchUserConfig::find_path(None, None, env),
+        Some(home_config.clone())
+    );
+
+    let env = |key: &str| match key {
+        "NOTMUCH_CONFIG" => Some(missing.clone().into()),
+        _ => None,
+    };
+
+    assert_eq!(NotmuchUserConfig::find_path(None, None, env), None);
+
+    // the parsed file is cached until it changes
+
+    let cached = NotmuchUserConfig::from_path_cached(&home_config).unwrap();
+    assert_eq!(
+        cached.new_tags,
+        Some(vec!["unread".into(), "inbox".into(), "new".into()])
+    );
+
+    fs::remove_file(&home_config).unwrap();
+    assert!(NotmuchUserConfig::from_path_cached(&home_config).is_err());
+
+    let config = NotmuchUserConfig::from_path(path).unwrap();
+    assert_eq!(
+        config.database_path,
+        Some(PathBuf::from("/tmp/notmuch-fixture/mail"))
+    );
+    assert_eq!(
+        config.new_tags,
+        Some(vec!["unread".into(), "inbox".into(), "new".into()])
+    );
+
+    // explicit crate configuration wins
+
+    let notmuch_config = NotmuchConfig {
+        database_path: Some("/tmp/notmuch-explicit".into()),
+        config_path: Some(fixture.into()),
+        new_tags: Some(vec!["explicit".into()]),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        notmuch_config.try_get_database_path().unwrap(),
+        PathBuf::from("/tmp/notmuch-explicit")
+    );
+    assert_eq!(notmuch_config.find_new_tags().unwrap(), vec!["explicit"]);
+
+    // notmuch configuration is used as fallback
+
+    let notmuch_config = NotmuchConfig {
+        config_path: Some(fixture.into()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        notmuch_config.try_get_database_path().unwrap(),
+        PathBuf::from("/tmp/notmuch-fixture/mail")
+    );
+    assert_eq!(
+        notmuch_config.find_new_tags().unwrap(),
+        vec!["unread", "inbox", "new"]
+    );
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_notmuch_apply_tag_changes() {
+    let mdir: Maildir = tempdir().unwrap().path().to_owned().into();
+    _ = fs::remove_dir_all(mdir.path());
+    mdir.create_all().unwrap();
+
+    let inbox = Maildir::from(mdir.path().join("INBOX"));
+    inbox.create_all().unwrap();
+
+    Database::create(mdir.path()).unwrap();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let notmuch_config = Arc::new(NotmuchConfig {
+        database_path: Some(mdir.path().to_owned()),
+        new_tags: Some(vec!["unread".into()]),
+        ..Default::default()
+    });
+
+    let notmuch_ctx = NotmuchContextBuilder::new(account_config.clone(), notmuch_config);
+    let notmuch = BackendBuilder::new(account_config, notmuch_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    let mut ids = Vec::new();
+
+    for subject in ["first", "second", "third"] {
+        let msg = MessageBuilder::new()
+            .from("alice@localhost")
+            .to("bob@localhost")
+            .subject(subject)
+            .text_body(subject)
+            .write_to_vec()
+            .unwrap();
+        let id = notmuch
+            .add_message_with_flag(INBOX, &msg, Flag::custom("todo"))
+            .await
+            .unwrap();
+        ids.push(id.to_string());
+    }
+
+    let applied = notmuch
+        .context
+        .lock()
+        .await
+        .apply_tag_changes(vec![
+            (
+                Id::single(&ids[0]),
+                TagDelta::new().with_add("work").with_remove("unread"),
+            ),
+            (
+                Id::multiple([&ids[1], &ids[2]]),
+                TagDelta::new().with_add("later").with_remove("todo"),
+            ),
+        ])
+        .unwrap();
+
+    assert_eq!(applied.len(), 3);
+
+    let envelopes = notmuch
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+
+    for envelope in envelopes.iter() {
+        if envelope.subject == "first" {
+            assert!(envelope.flags.contains(&Flag::Seen));
+            assert!(envelope.flags.contains(&Flag::custom("work")));
+            assert!(envelope.flags.contains(&Flag::custom("todo")));
+            assert!(!envelope.flags.contains(&Flag::custom("later")));
+        } else {
+            assert!(!envelope.flags.contains(&Flag::Seen));
+            assert!(envelope.flags.contains(&Flag::custom("later")));
+            assert!(!envelope.flags.contains(&Flag::custom("todo")));
+            assert!(!envelope.flags.contains(&Flag::custom("work")));
+        }
+    }
+
+    // an unknown message fails the batch and reports applied changes
+
+    let err = notmuch
+        .context
+        .lock()
+        .await
+        .apply_tag_changes(vec![
+            (Id::single(&ids[0]), TagDelta::new().with_add("done")),
+            (
+                Id::single("unknown@localhost"),
+                TagDelta::new().with_add("done"),
+            ),
+        ])
+        .unwrap_err();
+
+    match err {
+        email::notmuch::Error::ApplyTagChangesError(_, id, applied) => {
+            assert_eq!(id, "unknown@localhost");
+            assert_eq!(applied.len(), 1);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_notmuch_watch_envelopes() {
+    let root = tempdir().unwrap();
+    let mdir: Maildir = root.path().to_owned().into();
+    mdir.create_all().unwrap();
+
+    let inbox = Maildir::from(mdir.path().join("INBOX"));
+    inbox.create_all().unwrap();
+
+    let archive = Maildir::from(mdir.path().join("Archive"));
+    archive.create_all().unwrap();
+
+    Database::create(mdir.path()).unwrap();
+
+    let account_config = Arc::new(AccountConfig {
+        name: "account".into(),
+        ..Default::default()
+    });
+
+    let notmuch_config = Arc::new(NotmuchConfig {
+        database_path: Some(mdir.path().to_owned()),
+        // prevent new tags to be read from the user Notmuch config
+        new_tags: Some(Vec::new()),
+        ..Default::default()
+    });
+
+    let notmuch_ctx = NotmuchContextBuilder::new(account_config.clone(), notmuch_config);
+    let notmuch = BackendBuilder::new(account_config, notmuch_ctx)
+        .build()
+        .await
+        .unwrap();
+
+    let watcher = WatchNotmuchEnvelopes::new(&notmuch.context)
+        .with_debounce(Duration::from_millis(100))
+        .with_poll_interval(Duration::from_millis(100));
+
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let (shutdown_request_tx, shutdown_request_rx) = oneshot::channel();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+    let watch = tokio::spawn(async move {
+        watcher
+            .watch_envelope_events(INBOX, events_tx, shutdown_request_rx, shutdown_tx)
+            .await
+            .unwrap()
+    });
+
+    // let the watcher read the initial state of the folder
+    sleep(Duration::from_millis(500)).await;
+
+    // a message that does not match the watched folder query
+
+    let msg = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Archived message!")
+        .text_body("Archived message!")
+        .write_to_vec()
+        .unwrap();
+    notmuch.add_message("Archive", &msg).await.unwrap();
+
+    // a message that matches the watched folder query
+
+    let msg = MessageBuilder::new()
+        .from("alice@localhost")
+        .to("bob@localhost")
+        .subject("Watched message!")
+        .text_body("Watched message!")
+        .write_to_vec()
+        .unwrap();
+    let id = notmuch.add_message(INBOX, &msg).await.unwrap();
+
+    let event = timeout(Duration::from_secs(10), events_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    match event {
+        WatchEvent::EnvelopeAdded(envelope) => {
+            assert_eq!(envelope.id, id.to_string());
+            assert_eq!(envelope.subject, "Watched message!");
+        }
+        event => panic!("unexpected event: {event:?}"),
+    }
+
+    shutdown_request_tx.send(()).unwrap();
+    shutdown_rx.await.unwrap();
+    watch.await.unwrap();
+
+    assert!(events_rx.try_recv().is_err());
+}
@@ -108,6 +108,7 @@ async fn test_search_emails_query() {
     let mdir_config = Arc::new(MaildirConfig {
         root_dir: tmp.join("maildir"),
         maildirpp: false,
+        ..Default::default()
     });
 
     let mdir_ctx = MaildirContextBuilder::new(account_config.clone(), mdir_config.clone());
@@ -122,6 +123,8 @@ async fn test_search_emails_query() {
     let notmuch_config = Arc::new(NotmuchConfig {
         database_path: Some(notmuch_db_path),
         maildir_path: Some(mdir_config.root_dir.clone()),
+        // prevent new tags to be read from the user Notmuch config
+        new_tags: Some(Vec::new()),
         ..Default::default()
     });
 
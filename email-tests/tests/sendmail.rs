@@ -45,6 +45,7 @@ async fn test_sendmail_features() {
                 ]
                 .join(" "),
             )),
+            ..Default::default()
         });
 
         let imap_ctx = ImapContextBuilder::new(account_config.clone(), imap_config);
@@ -39,6 +39,8 @@ async fn test_smtp_features() {
             encryption: Some(Encryption::None),
             login: "alice".into(),
             auth: SmtpAuthConfig::Password(PasswordConfig(Secret::new_raw("password"))),
+            passwd_cache: None,
+            max_message_size: None,
         });
 
         let imap_ctx = ImapContextBuilder::new(account_config.clone(), imap_config);
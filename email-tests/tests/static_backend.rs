@@ -43,6 +43,8 @@ async fn test_static_backend() {
             encryption: Some(Encryption::None),
             login: "alice".into(),
             auth: SmtpAuthConfig::Password(PasswordConfig(Secret::new_raw("password"))),
+            passwd_cache: None,
+            max_message_size: None,
         });
 
         // 1. define custom context made of subcontexts
@@ -23,7 +23,7 @@
     },
     maildir::{config::MaildirConfig, MaildirContextBuilder},
     message::{add::AddMessage, delete::DeleteMessages, peek::PeekMessages},
-    sync::{SyncBuilder, SyncDestination, SyncEvent},
+    sync::{progress::SyncProgress, SyncBuilder, SyncDestination, SyncEvent},
 };
 use mail_builder::MessageBuilder;
 use once_cell::sync::Lazy;
@@ -39,6 +39,7 @@ async fn test_sync() {
     let left_config = Arc::new(MaildirConfig {
         root_dir: tmp.join("left"),
         maildirpp: true,
+        ..Default::default()
     });
 
     let left_account_config = Arc::new(AccountConfig {
@@ -64,6 +65,7 @@ async fn test_sync() {
     let right_config = Arc::new(MaildirConfig {
         root_dir: tmp.join("right"),
         maildirpp: false,
+        ..Default::default()
     });
 
     let right_account_config = Arc::new(AccountConfig {
@@ -186,8 +188,11 @@ async fn test_sync() {
     let sync_builder = SyncBuilder::new(left_builder.clone(), right_builder.clone())
         .with_cache_dir(tmp.join("cache"))
         .with_handler(|evt| async {
-            let mut stack = EVENTS_STACK.lock().await;
-            stack.insert(evt);
+            // progress is checked by the progress test
+            if !matches!(evt, SyncEvent::Progress(_)) {
+                let mut stack = EVENTS_STACK.lock().await;
+                stack.insert(evt);
+            }
             Ok(())
         });
 
@@ -899,8 +904,62 @@ async fn test_sync() {
         .unwrap();
     right.expunge_folder(INBOX).await.unwrap();
 
+    // check dry sync changes, without anything applied
+
+    let left_envelopes_before = left
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+    let right_envelopes_before = right
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+
+    let dry_report = sync_builder
+        .clone()
+        .with_dry_run(true)
+        .sync()
+        .await
+        .unwrap();
+    let changes = dry_report.changes();
+
+    assert!(changes.left.created_folders.is_empty());
+    assert!(changes.right.created_folders.is_empty());
+    assert!(changes.left.added_emails.is_empty());
+    assert!(changes.right.added_emails.is_empty());
+    assert_eq!(
+        changes.left.deleted_emails,
+        BTreeSet::from_iter([(INBOX.to_owned(), left_envelopes[0].id.clone())])
+    );
+    assert_eq!(
+        changes.right.deleted_emails,
+        BTreeSet::from_iter([(INBOX.to_owned(), right_envelopes[2].id.clone())])
+    );
+    assert!(changes
+        .left
+        .updated_flags
+        .contains_key(&(INBOX.to_owned(), "<b@localhost>".to_owned())));
+    assert!(changes
+        .right
+        .updated_flags
+        .contains_key(&(INBOX.to_owned(), "<b@localhost>".to_owned())));
+
+    let left_envelopes_after = left
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+    let right_envelopes_after = right
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+
+    assert_eq!(left_envelopes_before, left_envelopes_after);
+    assert_eq!(right_envelopes_before, right_envelopes_after);
+
     let report = sync_builder.sync().await.unwrap();
 
+    assert_eq!(report.changes(), changes);
+
     let mut left_envelopes = left
         .list_envelopes(INBOX, Default::default())
         .await
@@ -931,3 +990,377 @@ async fn test_sync() {
     assert_eq!(right_envelopes, right_cached_envelopes);
     assert_eq!(left_envelopes, right_envelopes);
 }
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_sync_progress() {
+    let tmp = tempdir().unwrap().path().to_owned();
+
+    // set up left
+
+    let left_config = Arc::new(MaildirConfig {
+        root_dir: tmp.join("left"),
+        maildirpp: true,
+        ..Default::default()
+    });
+
+    let left_account_config = Arc::new(AccountConfig {
+        name: "left".into(),
+        ..Default::default()
+    });
+
+    let left_ctx = MaildirContextBuilder::new(left_account_config.clone(), left_config);
+    let left_builder = BackendBuilder::new(left_account_config.clone(), left_ctx);
+
+    // set up right
+
+    let right_config = Arc::new(MaildirConfig {
+        root_dir: tmp.join("right"),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let right_account_config = Arc::new(AccountConfig {
+        name: "right".into(),
+        folder: Some(FolderConfig {
+            aliases: Some(HashMap::from_iter([(INBOX.into(), "INBOX".into())])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mut right_ctx = MaildirContextBuilder::new(right_account_config.clone(), right_config);
+    right_ctx.configure().await.unwrap();
+
+    let right_builder = BackendBuilder::new(right_account_config.clone(), right_ctx);
+    let right = right_builder.clone().build().await.unwrap();
+
+    right.add_folder("INBOX").await.unwrap();
+
+    for id in ["a", "b"] {
+        right
+            .add_message_with_flag(
+                INBOX,
+                &MessageBuilder::new()
+                    .message_id(format!("{id}@localhost"))
+                    .from("alice@localhost")
+                    .to("bob@localhost")
+                    .subject(id.to_uppercase())
+                    .text_body(id.to_uppercase())
+                    .write_to_vec()
+                    .unwrap(),
+                Flag::Seen,
+            )
+            .await
+            .unwrap();
+    }
+
+    // prepare sync builder with a handler recording progress events
+
+    let progress = Arc::new(std::sync::Mutex::new(Vec::<SyncProgress>::new()));
+
+    let sync_builder = SyncBuilder::new(left_builder, right_builder)
+        .with_cache_dir(tmp.join("cache"))
+        .with_handler({
+            let progress = progress.clone();
+            move |evt| {
+                if let SyncEvent::Progress(p) = evt {
+                    progress.lock().unwrap().push(p);
+                }
+                async { Ok(()) }
+            }
+        });
+
+    // check progress of the initial sync: the folder is created,
+    // then the two messages are copied left side
+
+    sync_builder.clone().sync().await.unwrap();
+
+    let (phases, messages, flags, hunks) = split_progress(&progress);
+
+    assert_eq!(
+        phases,
+        vec![
+            SyncProgress::StartedFolderSync { total: 3 },
+            SyncProgress::ProcessedFolderHunk { done: 1, total: 3 },
+            SyncProgress::ProcessedFolderHunk { done: 2, total: 3 },
+            SyncProgress::ProcessedFolderHunk { done: 3, total: 3 },
+            SyncProgress::StartedEmailSync { total: 1 },
+            SyncProgress::ListedFolderEnvelopes {
+                folder: INBOX.into(),
+                done: 1,
+                total: 1,
+            },
+            SyncProgress::StartedEmailPatch {
+                total: 2,
+                messages: 2,
+                flags: 0,
+            },
+            SyncProgress::Finished,
+        ]
+    );
+    assert_eq!(
+        messages,
+        vec![
+            SyncProgress::ProcessedMessage {
+                folder: INBOX.into(),
+                done: 1,
+                total: 2,
+            },
+            SyncProgress::ProcessedMessage {
+                folder: INBOX.into(),
+                done: 2,
+                total: 2,
+            },
+        ]
+    );
+    assert!(flags.is_empty());
+    assert_eq!(
+        hunks,
+        vec![
+            SyncProgress::ProcessedEmailHunk { done: 1, total: 2 },
+            SyncProgress::ProcessedEmailHunk { done: 2, total: 2 },
+        ]
+    );
+
+    // check progress of a flag-only sync: flags are pushed left
+    // side, caches are refreshed both sides
+
+    let envelopes = right
+        .list_envelopes(INBOX, Default::default())
+        .await
+        .unwrap();
+    let envelope = envelopes
+        .iter()
+        .find(|e| e.message_id == "<a@localhost>")
+        .unwrap();
+    right
+        .add_flag(INBOX, &Id::single(&envelope.id), Flag::Flagged)
+        .await
+        .unwrap();
+
+    sync_builder.sync().await.unwrap();
+
+    let (phases, messages, flags, hunks) = split_progress(&progress);
+
+    assert_eq!(
+        phases,
+        vec![
+            SyncProgress::StartedFolderSync { total: 0 },
+            SyncProgress::StartedEmailSync { total: 1 },
+            SyncProgress::ListedFolderEnvelopes {
+                folder: INBOX.into(),
+                done: 1,
+                total: 1,
+            },
+            SyncProgress::StartedEmailPatch {
+                total: 3,
+                messages: 0,
+                flags: 1,
+            },
+            SyncProgress::Finished,
+        ]
+    );
+    assert!(messages.is_empty());
+    assert_eq!(
+        flags,
+        vec![SyncProgress::PushedFlags {
+            folder: INBOX.into(),
+            done: 1,
+            total: 1,
+        }]
+    );
+    assert_eq!(
+        hunks,
+        vec![
+            SyncProgress::ProcessedEmailHunk { done: 1, total: 3 },
+            SyncProgress::ProcessedEmailHunk { done: 2, total: 3 },
+            SyncProgress::ProcessedEmailHunk { done: 3, total: 3 },
+        ]
+    );
+}
+
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_sync_resume_from_checkpoint() {
+    let tmp = tempdir().unwrap().path().to_owned();
+
+    // set up left
+
+    let left_config = Arc::new(MaildirConfig {
+        root_dir: tmp.join("left"),
+        maildirpp: true,
+        ..Default::default()
+    });
+
+    let left_account_config = Arc::new(AccountConfig {
+        name: "left".into(),
+        ..Default::default()
+    });
+
+    let left_ctx = MaildirContextBuilder::new(left_account_config.clone(), left_config);
+    let left_builder = BackendBuilder::new(left_account_config.clone(), left_ctx);
+    let left = left_builder.clone().build().await.unwrap();
+
+    // set up right
+
+    let right_config = Arc::new(MaildirConfig {
+        root_dir: tmp.join("right"),
+        maildirpp: false,
+        ..Default::default()
+    });
+
+    let right_account_config = Arc::new(AccountConfig {
+        name: "right".into(),
+        folder: Some(FolderConfig {
+            aliases: Some(HashMap::from_iter([(INBOX.into(), "INBOX".into())])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mut right_ctx = MaildirContextBuilder::new(right_account_config.clone(), right_config);
+    right_ctx.configure().await.unwrap();
+
+    let right_builder = BackendBuilder::new(right_account_config.clone(), right_ctx);
+    let right = right_builder.clone().build().await.unwrap();
+
+    right.add_folder("INBOX").await.unwrap();
+    right.add_folder("Archives").await.unwrap();
+
+    let msg = |id: &str| {
+        MessageBuilder::new()
+            .message_id(format!("{id}@localhost"))
+            .from("alice@localhost")
+            .to("bob@localhost")
+            .subject(id.to_uppercase())
+            .text_body(id.to_uppercase())
+            .write_to_vec()
+            .unwrap()
+    };
+
+    right.add_message(INBOX, &msg("a")).await.unwrap();
+    right.add_message("Archives", &msg("b")).await.unwrap();
+
+    // prepare sync builder with a handler recording progress events
+
+    let progress = Arc::new(std::sync::Mutex::new(Vec::<SyncProgress>::new()));
+
+    let sync_builder = SyncBuilder::new(left_builder, right_builder)
+        .with_cache_dir(tmp.join("cache"))
+        .with_handler({
+            let progress = progress.clone();
+            move |evt| {
+                if let SyncEvent::Progress(p) = evt {
+                    progress.lock().unwrap().push(p);
+                }
+                async { Ok(()) }
+            }
+        });
+
+    let listed_folders = || {
+        let (phases, ..) = split_progress(&progress);
+        phases
+            .into_iter()
+            .filter_map(|p| match p {
+                SyncProgress::ListedFolderEnvelopes { folder, .. } => Some(folder),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>()
+    };
+
+    // sync INBOX only, like a sync interrupted after its first folder
+
+    let report = sync_builder
+        .clone()
+        .with_folder_filters(FolderSyncStrategy::Include(BTreeSet::from_iter([
+            INBOX.into()
+        ])))
+        .sync()
+        .await
+        .unwrap();
+
+    assert!(report.email.skipped_folders.is_empty());
+    assert_eq!(listed_folders(), BTreeSet::from_iter([INBOX.into()]));
+
+    // resume: INBOX is checkpointed, so only Archives is synced
+
+    let report = sync_builder.clone().sync().await.unwrap();
+
+    assert_eq!(
+        report.email.skipped_folders,
+        BTreeSet::from_iter([INBOX.into()])
+    );
+    assert_eq!(listed_folders(), BTreeSet::from_iter(["Archives".into()]));
+    assert_eq!(
+        left.list_envelopes(INBOX, Default::default())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        left.list_envelopes("Archives", Default::default())
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // nothing changed since the last sync, all folders are skipped
+
+    let report = sync_builder.clone().sync().await.unwrap();
+
+    assert_eq!(
+        report.email.skipped_folders,
+        BTreeSet::from_iter([INBOX.into(), "Archives".into()])
+    );
+    assert!(listed_folders().is_empty());
+
+    // a new message moves the cursor of INBOX, which is synced again
+
+    right.add_message(INBOX, &msg("c")).await.unwrap();
+
+    let report = sync_builder.sync().await.unwrap();
+
+    assert_eq!(
+        report.email.skipped_folders,
+        BTreeSet::from_iter(["Archives".into()])
+    );
+    assert_eq!(listed_folders(), BTreeSet::from_iter([INBOX.into()]));
+    assert_eq!(
+        left.list_envelopes(INBOX, Default::default())
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+/// Take the recorded progress, and split it into phases, processed
+/// messages, pushed flags and processed email hunks.
+///
+/// Email hunks are processed concurrently, so only the progress of
+/// the same kind is ordered.
+fn split_progress(
+    progress: &std::sync::Mutex<Vec<SyncProgress>>,
+) -> (
+    Vec<SyncProgress>,
+    Vec<SyncProgress>,
+    Vec<SyncProgress>,
+    Vec<SyncProgress>,
+) {
+    let mut phases = Vec::new();
+    let mut messages = Vec::new();
+    let mut flags = Vec::new();
+    let mut hunks = Vec::new();
+
+    for p in std::mem::take(&mut *progress.lock().unwrap()) {
+        match p {
+            SyncProgress::ProcessedMessage { .. } => messages.push(p),
+            SyncProgress::PushedFlags { .. } => flags.push(p),
+            SyncProgress::ProcessedEmailHunk { .. } => hunks.push(p),
+            _ => phases.push(p),
+        }
+    }
+
+    (phases, messages, flags, hunks)
+}
@@ -13,8 +13,9 @@
     collections::HashMap,
     env::temp_dir,
     ffi::OsStr,
-    fs, io,
+    fs, io, iter,
     path::{Path, PathBuf},
+    time::Duration,
     vec,
 };
 
@@ -39,9 +40,9 @@
 use crate::{
     date::from_mail_parser_to_chrono_datetime,
     email::config::EmailTextPlainFormat,
-    envelope::{config::EnvelopeConfig, Envelope},
+    envelope::{address::Address as EnvelopeAddress, config::EnvelopeConfig, Envelope},
     flag::config::FlagConfig,
-    folder::{config::FolderConfig, FolderKind, DRAFTS, INBOX, SENT, TRASH},
+    folder::{config::FolderConfig, Folder, FolderKind},
     message::config::MessageConfig,
     template::{
         config::TemplateConfig,
@@ -81,6 +82,13 @@ pub struct AccountConfig {
     /// The email address of the user account.
     pub email: String,
 
+    /// The email address aliases of the user account.
+    ///
+    /// Aliases are the other email addresses owned by the user
+    /// account: they are considered as self, like the main email
+    /// address (see [`AccountConfig::is_self`]).
+    pub email_aliases: Option<Vec<String>>,
+
     /// The display name of the user.
     ///
     /// It usually corresponds to the full name of the user.
@@ -129,6 +137,36 @@ pub struct AccountConfig {
 }
 
 impl AccountConfig {
+    /// Return the email address of the account, followed by its
+    /// aliases.
+    pub fn own_emails(&self) -> impl Iterator<Item = &str> {
+        let aliases = self.email_aliases.iter().flatten();
+        iter::once(self.email.as_str()).chain(aliases.map(String::as_str))
+    }
+
+    /// Return `true` if the given email address belongs to the
+    /// account, either as main email address or as alias.
+    ///
+    /// The comparison is case-insensitive, and ignores surrounding
+    /// whitespaces and angle brackets.
+    pub fn is_self_email(&self, email: &str) -> bool {
+        let email = normalize_email(email);
+
+        if email.is_empty() {
+            return false;
+        }
+
+        self.own_emails().any(|own| normalize_email(own) == email)
+    }
+
+    /// Return `true` if the given address belongs to the account.
+    ///
+    /// The display name is ignored, see
+    /// [`AccountConfig::is_self_email`].
+    pub fn is_self(&self, addr: &EnvelopeAddress) -> bool {
+        self.is_self_email(&addr.addr)
+    }
+
     /// Get the signature, including the delimiter.
     ///
     /// Uses the default delimiter `-- \n` in case no delimiter has
@@ -350,24 +388,75 @@ pub fn get_folder_alias(&self, folder: &str) -> String {
             .unwrap_or_else(|| shellexpand_str(folder))
     }
 
+    /// Resolve the folder of the given kind.
+    ///
+    /// The folder alias of the account configuration is used first,
+    /// otherwise the default folder name of the kind. See
+    /// [`AccountConfig::resolve_folder_with_special_use`] to take
+    /// folders advertised by the server into account.
+    pub fn resolve_folder(&self, kind: FolderKind) -> String {
+        self.resolve_folder_with_special_use(kind, std::iter::empty())
+    }
+
+    /// Resolve the folder of the given kind, using the given folders
+    /// when the kind has no alias.
+    ///
+    /// The precedence is the following: the folder alias of the
+    /// account configuration, then the first given folder of this
+    /// kind (for example an IMAP mailbox with the matching
+    /// SPECIAL-USE attribute, as returned by
+    /// [`ListFolders`](crate::folder::list::ListFolders)), then the
+    /// default folder name of the kind.
+    pub fn resolve_folder_with_special_use<'a>(
+        &self,
+        kind: FolderKind,
+        folders: impl IntoIterator<Item = &'a Folder>,
+    ) -> String {
+        let alias = match &kind {
+            FolderKind::UserDefined(name) => self.find_folder_alias(name),
+            kind => self.get_folder_aliases().and_then(|aliases| {
+                aliases.iter().find_map(|(kind_or_name, alias)| {
+                    if FolderKind::from(kind_or_name) == *kind {
+                        Some(shellexpand_str(alias))
+                    } else {
+                        None
+                    }
+                })
+            }),
+        };
+
+        if let Some(alias) = alias {
+            return alias;
+        }
+
+        let special_use = folders
+            .into_iter()
+            .find(|folder| folder.kind.as_ref() == Some(&kind));
+
+        match special_use {
+            Some(folder) => folder.name.clone(),
+            None => shellexpand_str(kind.as_str()),
+        }
+    }
+
     /// Get the inbox folder alias.
     pub fn get_inbox_folder_alias(&self) -> String {
-        self.get_folder_alias(INBOX)
+        self.resolve_folder(FolderKind::Inbox)
     }
 
     /// Get the sent folder alias.
     pub fn get_sent_folder_alias(&self) -> String {
-        self.get_folder_alias(SENT)
+        self.resolve_folder(FolderKind::Sent)
     }
 
     /// Get the drafts folder alias.
     pub fn get_drafts_folder_alias(&self) -> String {
-        self.get_folder_alias(DRAFTS)
+        self.resolve_folder(FolderKind::Drafts)
     }
 
     /// Get the trash folder alias.
     pub fn get_trash_folder_alias(&self) -> String {
-        self.get_folder_alias(TRASH)
+        self.resolve_folder(FolderKind::Trash)
     }
 
     /// Return `true` if the given folder matches the Trash folder.
@@ -386,6 +475,16 @@ pub fn is_delete_message_style_flag(&self) -> bool {
             .is_some()
     }
 
+    /// Get the retention time of messages in the Trash folder, if
+    /// defined.
+    pub fn get_trash_retention(&self) -> Option<Duration> {
+        self.message
+            .as_ref()
+            .and_then(|c| c.delete.as_ref())
+            .and_then(|c| c.trash_retention_days)
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+    }
+
     /// Get all folder aliases.
     pub fn get_folder_aliases(&self) -> Option<&HashMap<String, String>> {
         self.folder.as_ref().and_then(|c| c.aliases.as_ref())
@@ -422,6 +521,40 @@ pub fn get_envelope_list_page_size(&self) -> usize {
             .unwrap_or(DEFAULT_PAGE_SIZE)
     }
 
+    /// Get the envelope listing page size of the given folder.
+    ///
+    /// The page size override of the folder is used first, then the
+    /// account page size, then the default one. Overrides by folder
+    /// name take precedence over overrides by folder kind. When
+    /// several overrides of the same precedence match the folder, the
+    /// first one in lexicographic order wins.
+    pub fn get_folder_envelope_list_page_size(&self, folder: &str) -> usize {
+        let folder = self.get_folder_alias(folder);
+
+        let page_size = self
+            .envelope
+            .as_ref()
+            .and_then(|c| c.list.as_ref())
+            .and_then(|c| c.folder_page_sizes.as_ref())
+            .and_then(|page_sizes| {
+                page_sizes
+                    .iter()
+                    .filter_map(|(kind_or_name, page_size)| {
+                        let kind = FolderKind::from(kind_or_name);
+                        let is_kind = !matches!(kind, FolderKind::UserDefined(_));
+                        (self.resolve_folder(kind) == folder).then_some((
+                            is_kind,
+                            kind_or_name,
+                            *page_size,
+                        ))
+                    })
+                    .min_by_key(|(is_kind, kind_or_name, _)| (*is_kind, *kind_or_name))
+                    .map(|(_, _, page_size)| page_size)
+            });
+
+        page_size.unwrap_or_else(|| self.get_envelope_list_page_size())
+    }
+
     /// Get the envelope threading page size if defined, otherwise
     /// return the default one.
     #[cfg(feature = "thread")]
@@ -495,6 +628,16 @@ pub fn should_save_copy_sent_message(&self) -> bool {
             .unwrap_or(true)
     }
 
+    /// Return `true` if line endings of sent messages should be
+    /// normalized to CRLF.
+    pub fn should_normalize_sent_message_line_endings(&self) -> bool {
+        self.message
+            .as_ref()
+            .and_then(|c| c.send.as_ref())
+            .and_then(|c| c.normalize_line_endings)
+            .unwrap_or(true)
+    }
+
     /// Generate a template interpreter with prefilled options from
     /// the current user account configuration.
     pub fn generate_tpl_interpreter(&self) -> MimeInterpreterBuilder {
@@ -659,6 +802,14 @@ fn from(config: &'a AccountConfig) -> Self {
     }
 }
 
+/// Normalize the given email address for comparison.
+fn normalize_email(email: &str) -> String {
+    let email = email.trim();
+    let email = email.strip_prefix('<').unwrap_or(email);
+    let email = email.strip_suffix('>').unwrap_or(email);
+    email.trim().to_lowercase()
+}
+
 /// Rename duplicated file by adding a auto-incremented counter
 /// suffix.
 ///
@@ -693,7 +844,127 @@ pub(crate) fn rename_file_if_duplicate(
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{collections::HashMap, path::PathBuf};
+
+    use super::{AccountConfig, DEFAULT_PAGE_SIZE};
+    use crate::{
+        envelope::{
+            address::Address, config::EnvelopeConfig, list::config::EnvelopeListConfig, Envelope,
+        },
+        folder::{config::FolderConfig, Folder, FolderKind},
+    };
+
+    fn config_with_aliases(aliases: &[(&str, &str)]) -> AccountConfig {
+        let aliases = aliases
+            .iter()
+            .map(|(kind, alias)| (kind.to_string(), alias.to_string()));
+
+        AccountConfig {
+            folder: Some(FolderConfig {
+                aliases: Some(HashMap::from_iter(aliases)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn config_with_page_sizes(
+        page_size: Option<usize>,
+        overrides: &[(&str, usize)],
+    ) -> AccountConfig {
+        let overrides = overrides
+            .iter()
+            .map(|(folder, page_size)| (folder.to_string(), *page_size));
+
+        AccountConfig {
+            envelope: Some(EnvelopeConfig {
+                list: Some(EnvelopeListConfig {
+                    page_size,
+                    folder_page_sizes: Some(HashMap::from_iter(overrides)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..config_with_aliases(&[("archive", "Archives")])
+        }
+    }
+
+    #[test]
+    fn get_overridden_folder_page_size() {
+        let config = config_with_page_sizes(Some(20), &[("inbox", 50), ("Archives", 5)]);
+
+        assert_eq!(config.get_folder_envelope_list_page_size("INBOX"), 50);
+        assert_eq!(config.get_folder_envelope_list_page_size("archive"), 5);
+
+        // overrides by name win over overrides by kind
+        let config = config_with_page_sizes(None, &[("archive", 5), ("Archives", 15)]);
+        assert_eq!(config.get_folder_envelope_list_page_size("Archives"), 15);
+
+        // overrides of the same precedence are picked in order,
+        // whatever the iteration order of the map
+        for _ in 0..16 {
+            let config = config_with_page_sizes(None, &[("drafts", 5), ("draft", 15)]);
+            assert_eq!(config.get_folder_envelope_list_page_size("drafts"), 15);
+        }
+    }
+
+    #[test]
+    fn get_default_folder_page_size() {
+        let config = config_with_page_sizes(Some(20), &[("inbox", 50)]);
+        assert_eq!(config.get_folder_envelope_list_page_size("Sent"), 20);
+
+        let config = config_with_page_sizes(None, &[("inbox", 50)]);
+        assert_eq!(
+            config.get_folder_envelope_list_page_size("Sent"),
+            DEFAULT_PAGE_SIZE
+        );
+    }
+
+    #[test]
+    fn resolve_overridden_folder() {
+        let config = config_with_aliases(&[("sent", "Sent Items"), ("draft", "Brouillons")]);
+        let folders = [Folder {
+            kind: Some(FolderKind::Sent),
+            name: String::from("[Gmail]/Sent Mail"),
+            desc: String::new(),
+        }];
+
+        // the alias wins over the folder advertised by the server
+        assert_eq!(config.resolve_folder(FolderKind::Sent), "Sent Items");
+        assert_eq!(
+            config.resolve_folder_with_special_use(FolderKind::Sent, &folders),
+            "Sent Items"
+        );
+        assert_eq!(config.get_sent_folder_alias(), "Sent Items");
+
+        // both draft and drafts aliases map to the drafts kind
+        assert_eq!(config.resolve_folder(FolderKind::Drafts), "Brouillons");
+    }
+
+    #[test]
+    fn resolve_folder_fallbacks() {
+        let config = AccountConfig::default();
+        let folders = [Folder {
+            kind: Some(FolderKind::Sent),
+            name: String::from("[Gmail]/Sent Mail"),
+            desc: String::new(),
+        }];
+
+        assert_eq!(
+            config.resolve_folder_with_special_use(FolderKind::Sent, &folders),
+            "[Gmail]/Sent Mail"
+        );
+        assert_eq!(
+            config.resolve_folder_with_special_use(FolderKind::Trash, &folders),
+            "Trash"
+        );
+        assert_eq!(config.resolve_folder(FolderKind::Sent), "Sent");
+        assert_eq!(config.resolve_folder(FolderKind::Inbox), "INBOX");
+        assert_eq!(
+            config.resolve_folder(FolderKind::UserDefined(String::from("Lists"))),
+            "Lists"
+        );
+    }
 
     #[test]
     fn rename_file_if_duplicate() {
@@ -731,4 +1002,38 @@ fn rename_file_if_duplicate() {
             Ok(path) if path == PathBuf::from("downloads/file.ext_5.ext2")
         ));
     }
+
+    #[test]
+    fn is_self_with_aliases() {
+        let config = AccountConfig {
+            email: "me@localhost".into(),
+            email_aliases: Some(vec![
+                "me@alias.localhost".into(),
+                "Me@Work.Localhost".into(),
+            ]),
+            ..Default::default()
+        };
+
+        let own_emails: Vec<_> = config.own_emails().collect();
+        assert_eq!(
+            own_emails,
+            vec!["me@localhost", "me@alias.localhost", "Me@Work.Localhost"]
+        );
+
+        // case and display name are ignored
+        assert!(config.is_self(&Address::new(Some("Me"), "ME@localhost")));
+        assert!(config.is_self(&Address::new(Some("Alias"), "me@ALIAS.localhost")));
+        assert!(config.is_self(&Address::new_nameless("me@work.localhost")));
+        assert!(config.is_self_email(" <me@localhost> "));
+
+        assert!(!config.is_self(&Address::new(Some("Me"), "someone@localhost")));
+        assert!(!config.is_self(&Address::new_nameless("me@other.localhost")));
+        assert!(!config.is_self_email(""));
+
+        let envelope = Envelope {
+            from: Address::new(Some("Me at work"), "me@work.localhost"),
+            ..Default::default()
+        };
+        assert!(envelope.is_from_self(&config));
+    }
 }
@@ -5,14 +5,20 @@
 use std::{
     io,
     ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
 };
 
 use secret::Secret;
+use zeroize::Zeroizing;
 
 #[doc(inline)]
 pub use super::{Error, Result};
 
 /// The password configuration.
+///
+/// Passwords are resolved each time they are accessed. See
+/// [`PasswordCache`] to keep them in memory instead.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
     feature = "derive",
@@ -37,8 +43,68 @@ fn deref_mut(&mut self) -> &mut Self::Target {
     }
 }
 
+impl From<Secret> for PasswordConfig {
+    fn from(secret: Secret) -> Self {
+        Self::new(secret)
+    }
+}
+
 impl PasswordConfig {
-    /// If the current password secret is a keyring entry, delete it.
+    /// Create a new password configuration from the given secret.
+    pub fn new(secret: Secret) -> Self {
+        Self(secret)
+    }
+
+    /// Return `true` if the password can be cached.
+    ///
+    /// Raw secrets are already in memory, and environment variables
+    /// are meant to be read each time the secret is accessed.
+    fn is_cacheable(&self) -> bool {
+        !matches!(self.0, Secret::Empty | Secret::Raw(_) | Secret::EnvVar(_))
+    }
+
+    /// Get the password.
+    ///
+    /// The password is wiped from memory on drop. See
+    /// [`Secret::get_zeroizing`].
+    pub async fn get(&self) -> secret::Result<Zeroizing<String>> {
+        self.0.get_zeroizing().await
+    }
+
+    /// Get the password, from the given cache if it has already been
+    /// resolved.
+    ///
+    /// Without cache, this is the same as [`PasswordConfig::get`].
+    pub async fn get_cached(
+        &self,
+        cache: Option<&PasswordCache>,
+    ) -> secret::Result<Zeroizing<String>> {
+        let Some(cache) = cache.filter(|_| self.is_cacheable()) else {
+            return self.get().await;
+        };
+
+        if let Some(passwd) = cache.load() {
+            return Ok(passwd);
+        }
+
+        let passwd = self.get().await?;
+        cache.store(&passwd);
+        Ok(passwd)
+    }
+
+    /// Find the password.
+    ///
+    /// The password is wiped from memory on drop. See
+    /// [`Secret::find_zeroizing`].
+    pub async fn find(&self) -> secret::Result<Option<Zeroizing<String>>> {
+        self.0.find_zeroizing().await
+    }
+
+    /// Delete the password from the keyring if the current password
+    /// secret is a keyring entry.
+    ///
+    /// Caches holding the password need to be cleared separately, see
+    /// [`PasswordCache::clear`].
     pub async fn reset(&self) -> Result<()> {
         #[cfg(feature = "keyring")]
         self.delete_if_keyring()
@@ -61,10 +127,11 @@ pub async fn configure<F>(
             Ok(None) => {
                 tracing::debug!("cannot find imap password from keyring, setting it");
 
-                let passwd = get_passwd().map_err(Error::GetFromUserError)?;
+                let passwd = Zeroizing::new(get_passwd().map_err(Error::GetFromUserError)?);
 
-                self.set_if_keyring(passwd)
+                self.set_if_keyring(passwd.as_str())
                     .await
+                    .map(Zeroizing::new)
                     .map_err(Error::SetIntoKeyringError)?;
 
                 Ok(())
@@ -74,3 +141,169 @@ pub async fn configure<F>(
         }
     }
 }
+
+/// The in-memory cache of a resolved password.
+///
+/// The cache is opt-in and belongs to the configuration holding it
+/// (see `ImapConfig::passwd_cache` and `SmtpConfig::passwd_cache`),
+/// so that the keyring is not queried (or the command not executed)
+/// on every connection. Clones of the cache share the same password,
+/// which expires after the time-to-live of the cache. The cached
+/// password is zeroed when dropped.
+///
+/// The password is resolved outside of the cache lock: concurrent
+/// accesses to an empty cache may resolve the password more than
+/// once, but never block each other.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "derive",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u64", into = "u64")
+)]
+pub struct PasswordCache {
+    /// The time-to-live of the cached password.
+    ///
+    /// Defaults to `None`, which means that the password is kept for
+    /// the lifetime of the cache.
+    ttl: Option<Duration>,
+
+    /// The cached password, alongside the moment it expires.
+    passwd: Arc<Mutex<Option<(Option<Instant>, Zeroizing<String>)>>>,
+}
+
+impl PasswordCache {
+    /// Create a new empty cache, keeping passwords for the given
+    /// time-to-live.
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            passwd: Default::default(),
+        }
+    }
+
+    /// Return the time-to-live of the cached password.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Load the cached password, unless it expired.
+    fn load(&self) -> Option<Zeroizing<String>> {
+        let mut cache = self.passwd.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match cache.as_ref() {
+            Some((Some(expiry), _)) if *expiry <= Instant::now() => {
+                *cache = None;
+                None
+            }
+            Some((_, passwd)) => Some(passwd.clone()),
+            None => None,
+        }
+    }
+
+    /// Store the given password in the cache.
+    fn store(&self, passwd: &Zeroizing<String>) {
+        let expiry = self.ttl.map(|ttl| Instant::now() + ttl);
+        let mut cache = self.passwd.lock().unwrap_or_else(PoisonError::into_inner);
+        *cache = Some((expiry, passwd.clone()));
+    }
+
+    /// Clear the cached password.
+    pub fn clear(&self) {
+        *self.passwd.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// Caches are equal when they share the same time-to-live, whatever
+/// their cached password.
+impl PartialEq for PasswordCache {
+    fn eq(&self, other: &Self) -> bool {
+        self.ttl == other.ttl
+    }
+}
+
+impl Eq for PasswordCache {}
+
+/// Builds a cache from its time-to-live, in seconds.
+///
+/// A time-to-live of 0 keeps the password for the lifetime of the
+/// cache.
+impl From<u64> for PasswordCache {
+    fn from(ttl: u64) -> Self {
+        Self::new(Some(ttl).filter(|ttl| *ttl > 0).map(Duration::from_secs))
+    }
+}
+
+impl From<PasswordCache> for u64 {
+    fn from(cache: PasswordCache) -> Self {
+        cache.ttl.map(|ttl| ttl.as_secs()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use secret::Secret;
+
+    use super::{PasswordCache, PasswordConfig};
+
+    fn count_runs(runs: &std::path::Path) -> usize {
+        fs::read_to_string(runs).unwrap().lines().count()
+    }
+
+    #[tokio::test]
+    async fn cache_command_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+
+        let cmd = format!("echo run >> {}; echo password", runs.display());
+        let passwd = PasswordConfig(Secret::new_command(&cmd));
+        let cache = PasswordCache::new(None);
+        let cache_clone = cache.clone();
+
+        // without cache, the command runs on every access
+        assert_eq!(*passwd.get().await.unwrap(), "password");
+        assert_eq!(*passwd.get_cached(None).await.unwrap(), "password");
+        assert_eq!(count_runs(&runs), 2);
+
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(
+            *passwd.get_cached(Some(&cache_clone)).await.unwrap(),
+            "password"
+        );
+        assert_eq!(count_runs(&runs), 3);
+
+        // caches are not shared between configurations
+        let other_cache = PasswordCache::new(None);
+        assert_eq!(
+            *passwd.get_cached(Some(&other_cache)).await.unwrap(),
+            "password"
+        );
+        assert_eq!(count_runs(&runs), 4);
+
+        cache.clear();
+
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(count_runs(&runs), 5);
+    }
+
+    #[tokio::test]
+    async fn cache_expires_after_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+
+        let cmd = format!("echo run >> {}; echo password", runs.display());
+        let passwd = PasswordConfig(Secret::new_command(&cmd));
+        let cache = PasswordCache::new(Some(Duration::from_millis(50)));
+
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(count_runs(&runs), 1);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(*passwd.get_cached(Some(&cache)).await.unwrap(), "password");
+        assert_eq!(count_runs(&runs), 2);
+    }
+}
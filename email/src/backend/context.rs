@@ -17,8 +17,8 @@
     envelope::{get::GetEnvelope, list::ListEnvelopes},
     flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags},
     folder::{
-        add::AddFolder, delete::DeleteFolder, expunge::ExpungeFolder, list::ListFolders,
-        purge::PurgeFolder,
+        add::AddFolder, cursor::GetFolderCursor, delete::DeleteFolder, expunge::ExpungeFolder,
+        list::ListFolders, purge::PurgeFolder, rename::RenameFolder,
     },
     message::{
         add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
@@ -29,10 +29,69 @@
 
 /// The backend context.
 ///
-/// This is just a marker for other backend traits. Every backend
+/// This is mostly a marker for other backend traits. Every backend
 /// context needs to implement this trait manually or to derive
-/// [`crate::backend_v2::macros::BackendContextV2`].
-pub trait BackendContext: Send + Sync {}
+/// [`crate::backend::macros::BackendContext`]. The derived
+/// implementation closes the fields that are backend contexts.
+#[async_trait]
+pub trait BackendContext: Send + Sync {
+    /// Release the resources held by the context.
+    ///
+    /// Dropping a context cannot gracefully tear down async
+    /// resources, so long-lived programs should close contexts they
+    /// do not need anymore. The context should not be used once
+    /// closed. The default implementation does nothing.
+    async fn close(&self) -> AnyResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<T: BackendContext> BackendContext for Option<T> {
+    async fn close(&self) -> AnyResult<()> {
+        match self {
+            Some(ctx) => ctx.close().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Helpers used by the [`BackendContext`] derive macro.
+///
+/// Whether a field is a backend context is only known once types are
+/// resolved, so the derived implementation relies on method
+/// resolution: [`derive::ContextField`] applies to fields that are
+/// backend contexts, and [`derive::OtherField`] is the fallback for
+/// the other ones.
+#[doc(hidden)]
+pub mod derive {
+    pub use async_trait::async_trait;
+
+    use super::BackendContext;
+
+    /// A field of a derived backend context.
+    pub struct Field<'a, T>(pub &'a T);
+
+    pub trait ContextField<'a> {
+        fn as_context(&self) -> Option<&'a dyn BackendContext>;
+    }
+
+    impl<'a, T: BackendContext> ContextField<'a> for &Field<'a, T> {
+        fn as_context(&self) -> Option<&'a dyn BackendContext> {
+            Some(self.0)
+        }
+    }
+
+    pub trait OtherField<'a> {
+        fn as_context(&self) -> Option<&'a dyn BackendContext>;
+    }
+
+    impl<'a, T> OtherField<'a> for Field<'a, T> {
+        fn as_context(&self) -> Option<&'a dyn BackendContext> {
+            None
+        }
+    }
+}
 
 /// Macro for defining [`BackendContextBuilder`] features.
 macro_rules! feature {
@@ -82,6 +141,8 @@ async fn configure(&mut self) -> AnyResult<()> {
     feature!(ExpungeFolder);
     feature!(PurgeFolder);
     feature!(DeleteFolder);
+    feature!(RenameFolder);
+    feature!(GetFolderCursor);
     feature!(GetEnvelope);
     feature!(ListEnvelopes);
     #[cfg(feature = "thread")]
@@ -152,6 +213,7 @@ fn try_to_sync_cache_builder(
         let account_config = Arc::new(AccountConfig {
             name: account_config.name.clone(),
             email: account_config.email.clone(),
+            email_aliases: account_config.email_aliases.clone(),
             display_name: account_config.display_name.clone(),
             signature: account_config.signature.clone(),
             signature_delim: account_config.signature_delim.clone(),
@@ -169,6 +231,7 @@ fn try_to_sync_cache_builder(
         let config = Arc::new(MaildirConfig {
             root_dir,
             maildirpp: false,
+            ..Default::default()
         });
 
         let ctx = MaildirContextBuilder::new(account_config.clone(), config);
@@ -1,4 +1,4 @@
-use std::{any::Any, result};
+use std::{any::Any, result, time::Duration};
 
 use thiserror::Error;
 
@@ -20,6 +20,10 @@ pub enum Error {
     PurgeFolderNotAvailableError,
     #[error("cannot delete folder: feature not available, or backend configuration for this functionality is not set")]
     DeleteFolderNotAvailableError,
+    #[error("cannot rename folder: feature not available, or backend configuration for this functionality is not set")]
+    RenameFolderNotAvailableError,
+    #[error("cannot get folder cursor: feature not available, or backend configuration for this functionality is not set")]
+    GetFolderCursorNotAvailableError,
     #[error("cannot list envelopes: feature not available, or backend configuration for this functionality is not set")]
     ListEnvelopesNotAvailableError,
     #[error("cannot thread envelopes: feature not available, or backend configuration for this functionality is not set")]
@@ -52,6 +56,11 @@ pub enum Error {
     DeleteMessagesNotAvailableError,
     #[error("cannot remove messages: feature not available, or backend configuration for this functionality is not set")]
     RemoveMessagesNotAvailableError,
+
+    #[error("cannot build backend context: timed out after {0:?}")]
+    BuildContextTimedOutError(Duration),
+    #[error("cannot check up freshly built backend context")]
+    CheckUpContextError(#[source] AnyBoxedError),
 }
 
 impl AnyError for Error {
@@ -19,8 +19,8 @@
     envelope::{get::GetEnvelope, list::ListEnvelopes},
     flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags},
     folder::{
-        add::AddFolder, delete::DeleteFolder, expunge::ExpungeFolder, list::ListFolders,
-        purge::PurgeFolder,
+        add::AddFolder, cursor::GetFolderCursor, delete::DeleteFolder, expunge::ExpungeFolder,
+        list::ListFolders, purge::PurgeFolder, rename::RenameFolder,
     },
     message::{
         add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
@@ -71,6 +71,8 @@ fn map_feature<T: ?Sized + 'static>(
     some_feature_mapper!(ExpungeFolder);
     some_feature_mapper!(PurgeFolder);
     some_feature_mapper!(DeleteFolder);
+    some_feature_mapper!(RenameFolder);
+    some_feature_mapper!(GetFolderCursor);
     some_feature_mapper!(GetEnvelope);
     some_feature_mapper!(ListEnvelopes);
     #[cfg(feature = "thread")]
@@ -135,6 +137,8 @@ fn map_feature<T: ?Sized + 'static>(
     feature_mapper!(ExpungeFolder);
     feature_mapper!(PurgeFolder);
     feature_mapper!(DeleteFolder);
+    feature_mapper!(RenameFolder);
+    feature_mapper!(GetFolderCursor);
     feature_mapper!(GetEnvelope);
     feature_mapper!(ListEnvelopes);
     #[cfg(feature = "thread")]
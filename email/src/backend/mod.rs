@@ -49,12 +49,17 @@ pub mod macros {
 
 #[cfg(feature = "sync")]
 use std::hash::DefaultHasher;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use paste::paste;
 #[cfg(feature = "watch")]
-use tokio::sync::oneshot::{Receiver, Sender};
+use tokio::sync::{
+    mpsc::UnboundedSender,
+    oneshot::{Receiver, Sender},
+};
+use tokio::time::timeout;
+use tracing::debug;
 
 #[doc(inline)]
 pub use self::error::{Error, Result};
@@ -63,7 +68,7 @@ pub mod macros {
     feature::{BackendFeature, BackendFeatureSource, CheckUp},
 };
 #[cfg(feature = "watch")]
-use crate::envelope::watch::WatchEnvelopes;
+use crate::envelope::watch::{WatchEnvelopes, WatchEvent};
 #[cfg(feature = "thread")]
 use crate::envelope::{thread::ThreadEnvelopes, ThreadedEnvelopes};
 #[cfg(feature = "sync")]
@@ -77,12 +82,18 @@ pub mod macros {
     },
     flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flags},
     folder::{
-        add::AddFolder, delete::DeleteFolder, expunge::ExpungeFolder, list::ListFolders,
-        purge::PurgeFolder, Folders,
+        add::AddFolder, cursor::GetFolderCursor, delete::DeleteFolder, expunge::ExpungeFolder,
+        list::ListFolders, purge::PurgeFolder, rename::RenameFolder, Folders,
     },
     message::{
-        add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
-        peek::PeekMessages, r#move::MoveMessages, remove::RemoveMessages, send::SendMessage,
+        add::AddMessage,
+        copy::CopyMessages,
+        delete::DeleteMessages,
+        get::GetMessages,
+        peek::PeekMessages,
+        r#move::MoveMessages,
+        remove::RemoveMessages,
+        send::{SendMessage, SendMessageResults},
         Messages,
     },
     AnyResult,
@@ -115,6 +126,10 @@ pub struct Backend<C>
     pub purge_folder: Option<BackendFeature<C, dyn PurgeFolder>>,
     /// The delete folder backend feature.
     pub delete_folder: Option<BackendFeature<C, dyn DeleteFolder>>,
+    /// The rename folder backend feature.
+    pub rename_folder: Option<BackendFeature<C, dyn RenameFolder>>,
+    /// The get folder cursor backend feature.
+    pub get_folder_cursor: Option<BackendFeature<C, dyn GetFolderCursor>>,
 
     /// The get envelope backend feature.
     pub get_envelope: Option<BackendFeature<C, dyn GetEnvelope>>,
@@ -152,6 +167,15 @@ pub struct Backend<C>
     pub remove_messages: Option<BackendFeature<C, dyn RemoveMessages>>,
 }
 
+impl<C: BackendContext> Backend<C> {
+    /// Close the backend context.
+    ///
+    /// See [`BackendContext::close`].
+    pub async fn close(&self) -> AnyResult<()> {
+        self.context.close().await
+    }
+}
+
 impl<C: BackendContext> HasAccountConfig for Backend<C> {
     fn account_config(&self) -> &AccountConfig {
         &self.account_config
@@ -218,6 +242,30 @@ async fn delete_folder(&self, folder: &str) -> AnyResult<()> {
     }
 }
 
+#[async_trait]
+impl<C: BackendContext> RenameFolder for Backend<C> {
+    async fn rename_folder(&self, from: &str, to: &str) -> AnyResult<()> {
+        self.rename_folder
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::RenameFolderNotAvailableError)?
+            .rename_folder(from, to)
+            .await
+    }
+}
+
+#[async_trait]
+impl<C: BackendContext> GetFolderCursor for Backend<C> {
+    async fn get_folder_cursor(&self, folder: &str) -> AnyResult<Option<u64>> {
+        self.get_folder_cursor
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::GetFolderCursorNotAvailableError)?
+            .get_folder_cursor(folder)
+            .await
+    }
+}
+
 #[async_trait]
 impl<C: BackendContext> GetEnvelope for Backend<C> {
     async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope> {
@@ -228,6 +276,15 @@ async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope>
             .get_envelope(folder, id)
             .await
     }
+
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        self.get_envelope
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::GetEnvelopeNotAvailableError)?
+            .get_envelopes(folder, ids)
+            .await
+    }
 }
 
 #[async_trait]
@@ -293,6 +350,21 @@ async fn watch_envelopes(
             .watch_envelopes(folder, wait_for_shutdown_request, shutdown)
             .await
     }
+
+    async fn watch_envelope_events(
+        &self,
+        folder: &str,
+        events: UnboundedSender<WatchEvent>,
+        wait_for_shutdown_request: Receiver<()>,
+        shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        self.watch_envelopes
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::WatchEnvelopesNotAvailableError)?
+            .watch_envelope_events(folder, events, wait_for_shutdown_request, shutdown)
+            .await
+    }
 }
 
 #[async_trait]
@@ -358,6 +430,15 @@ async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
             .send_message(msg)
             .await
     }
+
+    async fn send_message_with_results(&self, msg: &[u8]) -> AnyResult<SendMessageResults> {
+        self.send_message
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::SendMessageNotAvailableError)?
+            .send_message_with_results(msg)
+            .await
+    }
 }
 
 #[async_trait]
@@ -370,6 +451,20 @@ async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
             .peek_messages(folder, id)
             .await
     }
+
+    async fn peek_partial_messages(
+        &self,
+        folder: &str,
+        id: &Id,
+        max_body_size: usize,
+    ) -> AnyResult<Messages> {
+        self.peek_messages
+            .as_ref()
+            .and_then(|feature| feature(&self.context))
+            .ok_or(Error::PeekMessagesNotAvailableError)?
+            .peek_partial_messages(folder, id, max_body_size)
+            .await
+    }
 }
 
 #[async_trait]
@@ -504,6 +599,15 @@ pub struct BackendBuilder<CB>
     pub account_config: Arc<AccountConfig>,
     /// The backend context builder.
     pub ctx_builder: CB,
+    /// The maximum duration of the context build.
+    ///
+    /// There is no timeout by default.
+    pub build_timeout: Option<Duration>,
+    /// Whether the check up feature should be run against the context
+    /// right after it is built.
+    ///
+    /// Disabled by default.
+    pub check_up_on_build: bool,
 
     /// The noop backend builder feature.
     pub check_up: BackendFeatureSource<CB::Context, dyn CheckUp>,
@@ -518,6 +622,10 @@ pub struct BackendBuilder<CB>
     pub purge_folder: BackendFeatureSource<CB::Context, dyn PurgeFolder>,
     /// The delete folder backend builder feature.
     pub delete_folder: BackendFeatureSource<CB::Context, dyn DeleteFolder>,
+    /// The rename folder backend builder feature.
+    pub rename_folder: BackendFeatureSource<CB::Context, dyn RenameFolder>,
+    /// The get folder cursor backend builder feature.
+    pub get_folder_cursor: BackendFeatureSource<CB::Context, dyn GetFolderCursor>,
 
     /// The get envelope backend builder feature.
     pub get_envelope: BackendFeatureSource<CB::Context, dyn GetEnvelope>,
@@ -565,6 +673,8 @@ impl<CB> BackendBuilder<CB>
     feature_accessors!(ExpungeFolder);
     feature_accessors!(PurgeFolder);
     feature_accessors!(DeleteFolder);
+    feature_accessors!(RenameFolder);
+    feature_accessors!(GetFolderCursor);
     feature_accessors!(GetEnvelope);
     feature_accessors!(ListEnvelopes);
     #[cfg(feature = "thread")]
@@ -591,6 +701,8 @@ pub fn new(account_config: Arc<AccountConfig>, ctx_builder: CB) -> Self {
         Self {
             account_config,
             ctx_builder,
+            build_timeout: None,
+            check_up_on_build: false,
 
             check_up: BackendFeatureSource::Context,
 
@@ -599,6 +711,8 @@ pub fn new(account_config: Arc<AccountConfig>, ctx_builder: CB) -> Self {
             expunge_folder: BackendFeatureSource::Context,
             purge_folder: BackendFeatureSource::Context,
             delete_folder: BackendFeatureSource::Context,
+            rename_folder: BackendFeatureSource::Context,
+            get_folder_cursor: BackendFeatureSource::Context,
 
             get_envelope: BackendFeatureSource::Context,
             list_envelopes: BackendFeatureSource::Context,
@@ -628,8 +742,52 @@ pub fn without_features(mut self) -> Self {
         self
     }
 
+    /// Set the maximum duration of the context build.
+    pub fn set_build_timeout(&mut self, timeout: Option<Duration>) {
+        self.build_timeout = timeout;
+    }
+
+    /// Set the maximum duration of the context build, using the
+    /// builder pattern.
+    pub fn with_build_timeout(mut self, timeout: Duration) -> Self {
+        self.set_build_timeout(Some(timeout));
+        self
+    }
+
+    /// Enable or disable the check up of the context right after it
+    /// is built.
+    pub fn set_check_up_on_build(&mut self, check: bool) {
+        self.check_up_on_build = check;
+    }
+
+    /// Enable or disable the check up of the context right after it
+    /// is built, using the builder pattern.
+    ///
+    /// When enabled, [`BackendBuilder::build`] fails if the check up
+    /// feature fails, so that the built backend is known to be
+    /// usable.
+    pub fn with_check_up_on_build(mut self, check: bool) -> Self {
+        self.set_check_up_on_build(check);
+        self
+    }
+
+    /// Build the context using the given context builder, within the
+    /// given timeout.
+    async fn build_context(
+        ctx_builder: CB,
+        build_timeout: Option<Duration>,
+    ) -> AnyResult<CB::Context> {
+        match build_timeout {
+            None => ctx_builder.build().await,
+            Some(duration) => match timeout(duration, ctx_builder.build()).await {
+                Ok(ctx) => ctx,
+                Err(_) => Err(Error::BuildContextTimedOutError(duration).into()),
+            },
+        }
+    }
+
     pub async fn check_up(self) -> AnyResult<()> {
-        let ctx = self.ctx_builder.clone().build().await?;
+        let ctx = Self::build_context(self.ctx_builder.clone(), self.build_timeout).await?;
         match self.get_check_up().and_then(move |f| f(&ctx)) {
             Some(f) => f.check_up().await,
             None => Ok(()),
@@ -637,11 +795,15 @@ pub async fn check_up(self) -> AnyResult<()> {
     }
 
     pub async fn build(self) -> AnyResult<Backend<CB::Context>> {
+        let check_up = self.get_check_up();
+
         let add_folder = self.get_add_folder();
         let list_folders = self.get_list_folders();
         let expunge_folder = self.get_expunge_folder();
         let purge_folder = self.get_purge_folder();
         let delete_folder = self.get_delete_folder();
+        let rename_folder = self.get_rename_folder();
+        let get_folder_cursor = self.get_get_folder_cursor();
 
         let get_envelope = self.get_get_envelope();
         let list_envelopes = self.get_list_envelopes();
@@ -663,15 +825,26 @@ pub async fn build(self) -> AnyResult<Backend<CB::Context>> {
         let delete_messages = self.get_delete_messages();
         let remove_messages = self.get_remove_messages();
 
+        let context = Self::build_context(self.ctx_builder, self.build_timeout).await?;
+
+        if self.check_up_on_build {
+            if let Some(f) = check_up.and_then(|f| f(&context)) {
+                debug!("checking up freshly built backend context");
+                f.check_up().await.map_err(Error::CheckUpContextError)?;
+            }
+        }
+
         Ok(Backend {
             account_config: self.account_config,
-            context: Arc::new(self.ctx_builder.build().await?),
+            context: Arc::new(context),
 
             add_folder,
             list_folders,
             expunge_folder,
             purge_folder,
             delete_folder,
+            rename_folder,
+            get_folder_cursor,
 
             get_envelope,
             list_envelopes,
@@ -705,6 +878,8 @@ fn clone(&self) -> Self {
         Self {
             account_config: self.account_config.clone(),
             ctx_builder: self.ctx_builder.clone(),
+            build_timeout: self.build_timeout,
+            check_up_on_build: self.check_up_on_build,
 
             check_up: self.check_up.clone(),
 
@@ -713,6 +888,8 @@ fn clone(&self) -> Self {
             expunge_folder: self.expunge_folder.clone(),
             purge_folder: self.purge_folder.clone(),
             delete_folder: self.delete_folder.clone(),
+            rename_folder: self.rename_folder.clone(),
+            get_folder_cursor: self.get_folder_cursor.clone(),
 
             get_envelope: self.get_envelope.clone(),
             list_envelopes: self.list_envelopes.clone(),
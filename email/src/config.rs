@@ -55,6 +55,7 @@ pub fn account(&self, name: impl AsRef<str>) -> Result<AccountConfig> {
         Ok(AccountConfig {
             name: name.to_owned(),
             email: account_config.email.clone(),
+            email_aliases: account_config.email_aliases.clone(),
             display_name: account_config
                 .display_name
                 .as_ref()
@@ -1,4 +1,4 @@
-use chrono::{FixedOffset, TimeZone};
+use chrono::{DateTime, FixedOffset, TimeZone};
 
 pub fn from_mail_parser_to_chrono_datetime(
     dt: &mail_parser::DateTime,
@@ -17,3 +17,62 @@ pub fn from_mail_parser_to_chrono_datetime(
         )
         .earliest()
 }
+
+/// Parse the given raw Date header value into a fixed offset
+/// [`chrono::DateTime`].
+///
+/// The strict RFC 5322 parser is tried first, since it understands
+/// obsolete zone names (`GMT`, `EST`, `PDT`…). The lenient
+/// [`mail_parser`] parser is used as a fallback, for dates that do
+/// not follow the specification. The original offset is preserved.
+pub fn parse_rfc2822_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let date = date.trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc2822(date) {
+        return Some(date);
+    }
+
+    mail_parser::DateTime::parse_rfc822(date)
+        .filter(mail_parser::DateTime::is_valid)
+        .as_ref()
+        .and_then(from_mail_parser_to_chrono_datetime)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::parse_rfc2822_date;
+
+    fn rfc3339(date: &str) -> DateTime<chrono::FixedOffset> {
+        DateTime::parse_from_rfc3339(date).unwrap()
+    }
+
+    #[test]
+    fn parse_numeric_offsets() {
+        let date = parse_rfc2822_date("Tue, 02 Jan 2024 10:30:00 +0200").unwrap();
+        assert_eq!(date, rfc3339("2024-01-02T10:30:00+02:00"));
+        assert_eq!(date.offset().local_minus_utc(), 2 * 3600);
+
+        let date = parse_rfc2822_date(" 2 Jan 2024 10:30:00 -0530\r\n").unwrap();
+        assert_eq!(date, rfc3339("2024-01-02T10:30:00-05:30"));
+        assert_eq!(date.offset().local_minus_utc(), -(5 * 3600 + 30 * 60));
+    }
+
+    #[test]
+    fn parse_obsolete_zones() {
+        let date = parse_rfc2822_date("Tue, 02 Jan 2024 10:30:00 GMT").unwrap();
+        assert_eq!(date, rfc3339("2024-01-02T10:30:00+00:00"));
+        assert_eq!(date.offset().local_minus_utc(), 0);
+
+        let date = parse_rfc2822_date("Tue, 02 Jan 2024 10:30:00 EST").unwrap();
+        assert_eq!(date, rfc3339("2024-01-02T10:30:00-05:00"));
+        assert_eq!(date.offset().local_minus_utc(), -5 * 3600);
+    }
+
+    #[test]
+    fn parse_invalid_date() {
+        assert_eq!(parse_rfc2822_date(""), None);
+        assert_eq!(parse_rfc2822_date("not a date"), None);
+    }
+}
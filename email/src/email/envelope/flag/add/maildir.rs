@@ -1,11 +1,13 @@
-use std::collections::HashSet;
-
 use async_trait::async_trait;
 use tracing::info;
 
 use super::{AddFlags, Flags};
-use crate::{email::error::Error, envelope::Id, maildir::MaildirContextSync, AnyResult};
-
+use crate::{
+    envelope::Id,
+    flag::maildir::entry_letters,
+    maildir::{keywords::MaildirKeywords, rename_entry_with_flags, MaildirContextSync},
+    AnyResult,
+};
 #[derive(Clone)]
 pub struct AddMaildirFlags {
     ctx: MaildirContextSync,
@@ -33,17 +35,17 @@ async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()>
         let ctx = self.ctx.lock().await;
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
+        let mut keywords = MaildirKeywords::load(mdir.path());
+        let letters = flags.try_to_maildir_letters(&mut keywords)?;
+        keywords.save()?;
+
         id.iter()
-            .filter_map(|id| mdir.find(id).ok().flatten())
-            .try_for_each(|mut entry| {
-                entry.insert_flags(HashSet::from(flags)).map_err(|err| {
-                    Error::AddFlagsMaildirError(
-                        err,
-                        folder.to_owned(),
-                        id.to_string(),
-                        flags.clone(),
-                    )
-                })
+            .filter_map(|id| mdir.find(&id).ok().flatten())
+            .try_for_each(|entry| -> AnyResult<()> {
+                let mut next_letters = entry_letters(&entry);
+                next_letters.extend(&letters);
+                rename_entry_with_flags(&mdir, &entry, &next_letters)?;
+                Ok(())
             })?;
 
         Ok(())
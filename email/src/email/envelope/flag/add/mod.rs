@@ -14,6 +14,10 @@
 pub trait AddFlags: Send + Sync {
     /// Add the given flags to envelope(s) matching the given id from
     /// the given folder.
+    ///
+    /// Fails when a custom flag cannot be represented by the backend
+    /// (for example, non-ASCII IMAP keywords or Maildir custom
+    /// flags).
     async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()>;
 
     /// Add the given flag to envelope(s) matching the given id from
@@ -28,19 +28,53 @@ pub fn from_imap_flag_fetches(fetches: &[FlagFetch<'_>]) -> Self {
         }))
     }
 
+    /// Convert flags into IMAP flags, skipping the custom flags that
+    /// cannot be represented as IMAP keywords.
+    ///
+    /// See [`Flags::try_to_imap_flags`] for the strict version.
     pub fn to_imap_flags_iter(
         &self,
     ) -> impl IntoIterator<Item = ImapFlag<'static>> + fmt::Debug + Clone + '_ {
-        self.iter()
-            .filter_map(|flag| match flag.clone().try_into() {
-                Ok(flag) => Some(flag),
-                Err(_err) => {
-                    debug!("cannot serialize IMAP flag {flag}: {_err}");
-                    trace!("{_err:?}");
-                    None
-                }
-            })
+        self.iter().filter_map(|flag| match flag.to_imap_flag() {
+            Ok(flag) => Some(flag),
+            Err(_err) => {
+                debug!("cannot serialize IMAP flag {flag}: {_err}");
+                trace!("{_err:?}");
+                None
+            }
+        })
+    }
+
+    /// Convert flags into IMAP flags, failing when a custom flag
+    /// cannot be represented as an IMAP keyword.
+    pub fn try_to_imap_flags(&self) -> Result<Vec<ImapFlag<'static>>, Error> {
+        self.iter().map(Flag::to_imap_flag).collect()
+    }
+}
+
+/// Normalize the given custom flag into a valid IMAP keyword.
+///
+/// IMAP keywords are atoms (RFC 3501): ASCII characters that are not
+/// allowed in atoms (spaces, controls, `(`, `)`, `{`, `%`, `*`, `"`,
+/// `\` and `]`) are replaced by `_`. Non-ASCII characters cannot be
+/// represented, they lead to an error.
+pub fn normalize_imap_keyword(flag: &str) -> Result<String, Error> {
+    let flag = flag.trim();
+
+    if flag.is_empty() || !flag.is_ascii() {
+        return Err(Error::InvalidImapKeywordError(flag.to_owned()));
     }
+
+    let keyword = flag
+        .chars()
+        .map(|c| if is_atom_char(c) { c } else { '_' })
+        .collect();
+
+    Ok(keyword)
+}
+
+fn is_atom_char(c: char) -> bool {
+    c.is_ascii_graphic() && !matches!(c, '(' | ')' | '{' | '%' | '*' | '"' | '\\' | ']')
 }
 
 impl Flag {
@@ -55,6 +89,26 @@ pub fn to_imap_string(&self) -> String {
         }
     }
 
+    /// Convert the flag into an IMAP flag.
+    ///
+    /// Custom flags are normalized into IMAP keywords, see
+    /// [`normalize_imap_keyword`].
+    pub fn to_imap_flag(&self) -> Result<ImapFlag<'static>, Error> {
+        match self {
+            Flag::Seen => Ok(ImapFlag::Seen),
+            Flag::Answered => Ok(ImapFlag::Answered),
+            Flag::Flagged => Ok(ImapFlag::Flagged),
+            Flag::Deleted => Ok(ImapFlag::Deleted),
+            Flag::Draft => Ok(ImapFlag::Draft),
+            Flag::Custom(flag) => {
+                let keyword = normalize_imap_keyword(flag)?
+                    .try_into()
+                    .map_err(|_| Error::InvalidImapKeywordError(flag.clone()))?;
+                Ok(ImapFlag::Keyword(keyword))
+            }
+        }
+    }
+
     pub fn try_from_imap_fetch(fetch: &FlagFetch<'_>) -> Result<Self, Error> {
         match fetch {
             FlagFetch::Flag(ImapFlag::Seen) => Ok(Flag::Seen),
@@ -97,3 +151,40 @@ fn try_from(flag: Flag) -> Result<SearchKey<'a>, Self::Error> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use imap_client::imap_next::imap_types::flag::Flag as ImapFlag;
+
+    use super::normalize_imap_keyword;
+    use crate::{
+        email::error::Error,
+        flag::{Flag, Flags},
+    };
+
+    #[test]
+    fn normalize_keyword_with_spaces() {
+        assert_eq!(normalize_imap_keyword("has space").unwrap(), "has_space");
+        assert_eq!(normalize_imap_keyword(" (a)\\b* ").unwrap(), "_a__b_");
+        assert_eq!(normalize_imap_keyword("$Forwarded").unwrap(), "$Forwarded");
+
+        let flag = Flag::custom("has space").to_imap_flag().unwrap();
+        assert_eq!(flag, ImapFlag::Keyword("has_space".try_into().unwrap()));
+    }
+
+    #[test]
+    fn reject_non_ascii_keyword() {
+        assert!(matches!(
+            normalize_imap_keyword("café"),
+            Err(Error::InvalidImapKeywordError(flag)) if flag == "café",
+        ));
+        assert!(normalize_imap_keyword("  ").is_err());
+
+        let flags = Flags::from_iter([Flag::Seen, Flag::custom("café")]);
+        assert!(flags.try_to_imap_flags().is_err());
+
+        // the lenient version skips the invalid flag
+        let flags: Vec<_> = flags.to_imap_flags_iter().into_iter().collect();
+        assert_eq!(flags, vec![ImapFlag::Seen]);
+    }
+}
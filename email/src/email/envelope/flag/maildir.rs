@@ -3,35 +3,184 @@
 //! This module contains flag-related mapping functions from the
 //! [maildirpp] crate types.
 
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
 
 use maildirs::MaildirEntry;
 use tracing::debug;
 
 use super::{Flag, Flags};
-use crate::email::error::{Error, Result};
+use crate::{
+    email::error::{Error, Result},
+    maildir::{is_new_entry, keywords::MaildirKeywords, name::MaildirName},
+};
 
 impl TryFrom<MaildirEntry> for Flags {
     type Error = Error;
 
     fn try_from(entry: MaildirEntry) -> Result<Self> {
-        let flags = entry
-            .flags()
-            .map_err(|err| Error::GetMaildirFlagsError(err, entry.path().to_owned()))?;
+        let letters = entry_letters(&entry);
+        let keywords = entry_keywords(&entry, &letters);
+        Ok(Flags::from_maildir_letters(&letters, &keywords))
+    }
+}
+
+/// Get the flag letters of the info of the given Maildir entry.
+///
+/// Messages from `new` are unseen, whatever their file name says.
+pub(crate) fn entry_letters(entry: &MaildirEntry) -> BTreeSet<char> {
+    let is_new = is_new_entry(entry);
+
+    MaildirName::from_path(entry.path())
+        .map(|name| name.flags())
+        .unwrap_or_default()
+        .chars()
+        .filter(|letter| !(is_new && *letter == 'S'))
+        .collect()
+}
+
+/// Get the flag letters of the given Maildir entry, for another
+/// Maildir folder with the given keywords.
+///
+/// Keyword letters depend on the folder, so they are mapped to the
+/// letters of the same keywords in the other folder, registering
+/// them when needed.
+pub(crate) fn entry_letters_for(
+    entry: &MaildirEntry,
+    keywords: &mut MaildirKeywords,
+) -> BTreeSet<char> {
+    let letters = entry_letters(entry);
+    let entry_keywords = entry_keywords(entry, &letters);
+
+    letters
+        .into_iter()
+        .filter_map(|letter| {
+            if letter.is_ascii_lowercase() {
+                keywords.insert(entry_keywords.keyword(letter)?)
+            } else {
+                Some(letter)
+            }
+        })
+        .collect()
+}
+
+/// Load the keywords of the folder of the given Maildir entry.
+///
+/// Keywords are only read when the given letters of the entry
+/// contain some.
+fn entry_keywords(entry: &MaildirEntry, letters: &BTreeSet<char>) -> MaildirKeywords {
+    if !letters.iter().any(char::is_ascii_lowercase) {
+        return MaildirKeywords::default();
+    }
 
-        let flags = flags
+    let mdir_path = entry.path().parent().and_then(Path::parent);
+    mdir_path.map(MaildirKeywords::load).unwrap_or_default()
+}
+
+impl Flag {
+    /// Get the letter of the standard Maildir flag, if any.
+    fn to_standard_maildir_letter(&self) -> Option<char> {
+        match self {
+            Flag::Answered => Some('R'),
+            Flag::Seen => Some('S'),
+            Flag::Deleted => Some('T'),
+            Flag::Draft => Some('D'),
+            Flag::Flagged => Some('F'),
+            Flag::Custom(_) => None,
+        }
+    }
+
+    /// Get the Maildir letter of the flag.
+    ///
+    /// Custom flags are stored as keyword letters, registered in the
+    /// given keywords of the Maildir folder when needed.
+    fn to_maildir_letter(&self, keywords: &mut MaildirKeywords) -> Option<char> {
+        match self {
+            Flag::Custom(name) => keywords.insert(name),
+            flag => flag.to_standard_maildir_letter(),
+        }
+    }
+
+    /// Get the flag of the given Maildir letter, if any.
+    ///
+    /// Lowercase letters are keywords, resolved using the given
+    /// keywords of the Maildir folder.
+    fn from_maildir_letter(letter: char, keywords: &MaildirKeywords) -> Option<Self> {
+        match letter {
+            'R' => Some(Flag::Answered),
+            'S' => Some(Flag::Seen),
+            'T' => Some(Flag::Deleted),
+            'D' => Some(Flag::Draft),
+            'F' => Some(Flag::Flagged),
+            'a'..='z' => keywords.keyword(letter).map(Flag::custom),
+            _ => None,
+        }
+    }
+}
+
+impl Flags {
+    /// Parse flags from the given Maildir flag letters.
+    ///
+    /// Unknown letters, like `P` (passed) or keywords missing from
+    /// the given keywords, are skipped.
+    pub fn from_maildir_letters(letters: &BTreeSet<char>, keywords: &MaildirKeywords) -> Self {
+        letters
             .iter()
-            .filter_map(|flag| match Flag::try_from(*flag) {
-                Ok(flag) => Some(flag),
-                Err(_err) => {
-                    debug!("cannot parse maildir flag {flag:?}, skipping it: {_err}");
-                    debug!("{_err:?}");
+            .filter_map(
+                |letter| match Flag::from_maildir_letter(*letter, keywords) {
+                    Some(flag) => Some(flag),
+                    None => {
+                        debug!("cannot parse maildir flag {letter:?}, skipping it");
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Convert flags into Maildir flag letters, failing when a
+    /// custom flag cannot be stored.
+    ///
+    /// Standard flags are stored as uppercase letters. Custom flags
+    /// are stored as keyword letters, registered in the given
+    /// keywords when needed: they can only fail when they contain a
+    /// line break, or when all keyword letters are taken. See
+    /// [`Flags::to_maildir_letters`] for the lenient version,
+    /// skipping them.
+    pub fn try_to_maildir_letters(&self, keywords: &mut MaildirKeywords) -> Result<BTreeSet<char>> {
+        self.iter()
+            .map(|flag| {
+                flag.to_maildir_letter(keywords)
+                    .ok_or_else(|| Error::InvalidMaildirFlagError(flag.to_string()))
+            })
+            .collect()
+    }
+
+    /// Convert flags into Maildir flag letters, skipping custom flags
+    /// that cannot be stored.
+    pub fn to_maildir_letters(&self, keywords: &mut MaildirKeywords) -> BTreeSet<char> {
+        self.iter()
+            .filter_map(|flag| match flag.to_maildir_letter(keywords) {
+                Some(letter) => Some(letter),
+                None => {
+                    debug!("cannot store maildir flag {flag} as keyword, skipping it");
                     None
                 }
             })
-            .collect();
+            .collect()
+    }
 
-        Ok(flags)
+    /// Convert flags into the Maildir flag letters they are already
+    /// stored with, without registering new keywords.
+    pub fn to_known_maildir_letters(&self, keywords: &MaildirKeywords) -> BTreeSet<char> {
+        self.iter()
+            .filter_map(|flag| match flag {
+                Flag::Custom(name) => keywords.letter(name),
+                flag => flag.to_standard_maildir_letter(),
+            })
+            .collect()
     }
 }
 
@@ -82,7 +231,7 @@ fn try_from(flag: &Flag) -> Result<Self> {
             Flag::Deleted => Ok(maildirs::Flag::Trashed),
             Flag::Draft => Ok(maildirs::Flag::Draft),
             Flag::Flagged => Ok(maildirs::Flag::Flagged),
-            Flag::Custom(flag) => Err(Error::ParseFlagError(flag.clone())),
+            Flag::Custom(flag) => Err(Error::InvalidMaildirFlagError(flag.clone())),
         }
     }
 }
@@ -97,7 +246,66 @@ fn try_from(flag: Flag) -> Result<Self> {
             Flag::Deleted => Ok(maildirs::Flag::Trashed),
             Flag::Draft => Ok(maildirs::Flag::Draft),
             Flag::Flagged => Ok(maildirs::Flag::Flagged),
-            Flag::Custom(flag) => Err(Error::ParseFlagError(flag)),
+            Flag::Custom(flag) => Err(Error::InvalidMaildirFlagError(flag)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        email::error::Error,
+        flag::{Flag, Flags},
+        maildir::keywords::{MaildirKeywords, MAX_KEYWORDS},
+    };
+
+    #[test]
+    fn custom_flags_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keywords = MaildirKeywords::load(dir.path());
+
+        let flags = Flags::from_iter([
+            Flag::Seen,
+            Flag::Flagged,
+            Flag::custom("has space"),
+            Flag::custom("café"),
+        ]);
+
+        let letters = flags.try_to_maildir_letters(&mut keywords).unwrap();
+        assert_eq!(letters, BTreeSet::from_iter(['F', 'S', 'a', 'b']));
+        keywords.save().unwrap();
+
+        let keywords = MaildirKeywords::load(dir.path());
+        assert_eq!(Flags::from_maildir_letters(&letters, &keywords), flags);
+
+        let flags = Flags::from_iter([Flag::custom("café"), Flag::custom("unknown")]);
+        let letters = flags.to_known_maildir_letters(&keywords);
+        assert_eq!(letters, BTreeSet::from_iter(['b']));
+    }
+
+    #[test]
+    fn reject_unstorable_custom_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut keywords = MaildirKeywords::load(dir.path());
+
+        let flags = Flags::from_iter([Flag::Seen, Flag::custom("line\nbreak")]);
+
+        assert!(matches!(
+            flags.try_to_maildir_letters(&mut keywords),
+            Err(Error::InvalidMaildirFlagError(flag)) if flag == "line\nbreak",
+        ));
+
+        // the lenient version skips the custom flag
+        let letters = flags.to_maildir_letters(&mut keywords);
+        assert_eq!(letters, BTreeSet::from_iter(['S']));
+
+        for i in 0..MAX_KEYWORDS {
+            keywords.insert(&format!("keyword {i}"));
         }
+
+        let flags = Flags::from_iter([Flag::custom("one too many")]);
+        assert!(flags.try_to_maildir_letters(&mut keywords).is_err());
     }
 }
@@ -172,9 +172,21 @@ fn deref_mut(&mut self) -> &mut Self::Target {
     }
 }
 
+/// Split the given string into flag tokens.
+///
+/// Flags can be separated by whitespaces, commas or semicolons (like
+/// `seen answered`, `seen,answered` or `seen; flagged`), so that
+/// flags formatted by other tools can be parsed as well.
+fn split_flags(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|flag| !flag.is_empty())
+}
+
+/// Parse flags from a string, skipping the ones that cannot be
+/// parsed. See [`split_flags`] for the accepted separators.
 impl From<&str> for Flags {
     fn from(s: &str) -> Self {
-        s.split_whitespace()
+        split_flags(s)
             .filter_map(|flag| match flag.parse() {
                 Ok(flag) => Some(flag),
                 Err(err) => {
@@ -193,12 +205,14 @@ fn from(s: String) -> Self {
     }
 }
 
+/// Parse flags from a string. See [`split_flags`] for the accepted
+/// separators.
 impl FromStr for Flags {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Error> {
         Ok(Flags(
-            s.split_whitespace()
+            split_flags(s)
                 .map(|flag| flag.parse())
                 .collect::<Result<_, _>>()?,
         ))
@@ -216,3 +230,35 @@ fn from(val: Flags) -> Self {
         val.iter().map(|flag| flag.to_string()).collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Flag, Flags};
+
+    #[test]
+    fn parse_flags_with_separators() {
+        let expected = Flags::from_iter([Flag::Seen, Flag::Answered, Flag::Flagged]);
+
+        for s in [
+            "seen answered flagged",
+            "seen,answered,flagged",
+            "seen; answered ;flagged",
+            " seen,answered;  flagged, ",
+        ] {
+            assert_eq!(s.parse::<Flags>().unwrap(), expected, "{s}");
+            assert_eq!(Flags::from(s), expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn parse_flags_round_trip() {
+        let flags = Flags::from_iter([Flag::Seen, Flag::Draft]);
+        assert_eq!(flags.to_string().parse::<Flags>().unwrap(), flags);
+    }
+
+    #[test]
+    fn parse_invalid_flags() {
+        assert!("seen,custom".parse::<Flags>().is_err());
+        assert_eq!(Flags::from("seen,custom"), Flags::from_iter([Flag::Seen]));
+    }
+}
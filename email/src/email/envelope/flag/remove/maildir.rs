@@ -1,10 +1,13 @@
-use std::collections::HashSet;
-
 use async_trait::async_trait;
 use tracing::info;
 
 use super::{Flags, RemoveFlags};
-use crate::{email::error::Error, envelope::Id, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    envelope::Id,
+    flag::maildir::entry_letters,
+    maildir::{keywords::MaildirKeywords, rename_entry_with_flags, MaildirContextSync},
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct RemoveMaildirFlags {
@@ -33,17 +36,18 @@ async fn remove_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<
         let ctx = self.ctx.lock().await;
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
+        // custom flags without keyword cannot be carried by any
+        // message, there is nothing to remove
+        let keywords = MaildirKeywords::load(mdir.path());
+        let letters = flags.to_known_maildir_letters(&keywords);
+
         id.iter()
-            .filter_map(|id| mdir.find(id).ok().flatten())
-            .try_for_each(|mut entry| {
-                entry.remove_flags(HashSet::from(flags)).map_err(|err| {
-                    Error::RemoveFlagsMaildirError(
-                        err,
-                        folder.to_owned(),
-                        id.to_string(),
-                        flags.clone(),
-                    )
-                })
+            .filter_map(|id| mdir.find(&id).ok().flatten())
+            .try_for_each(|entry| -> AnyResult<()> {
+                let mut next_letters = entry_letters(&entry);
+                next_letters.retain(|letter| !letters.contains(letter));
+                rename_entry_with_flags(&mdir, &entry, &next_letters)?;
+                Ok(())
             })?;
 
         Ok(())
@@ -14,6 +14,10 @@
 pub trait RemoveFlags: Send + Sync {
     /// Remove the given flags from envelope(s) matching the given id
     /// from the given folder.
+    ///
+    /// Fails when a custom flag cannot be represented by the backend
+    /// (for example, non-ASCII IMAP keywords or Maildir custom
+    /// flags).
     async fn remove_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()>;
 
     /// Remove the given flag from envelope(s) matching the given id
@@ -1,10 +1,12 @@
 use async_trait::async_trait;
-use imap_client::imap_next::imap_types::sequence::{Sequence, SequenceSet};
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::{Flags, SetFlags};
-use crate::{envelope::Id, imap::ImapContext, AnyResult, Error};
+use crate::{
+    envelope::Id,
+    imap::{ImapContext, ImapMailboxMode},
+    AnyResult,
+};
 
 #[derive(Clone, Debug)]
 pub struct SetImapFlags {
@@ -34,30 +36,14 @@ async fn set_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()>
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
 
-        let uids: SequenceSet = match id {
-            Id::Single(id) => Sequence::try_from(id.as_str())
-                .map_err(Error::ParseSequenceError)?
-                .into(),
-            Id::Multiple(ids) => ids
-                .iter()
-                .filter_map(|id| {
-                    let seq = Sequence::try_from(id.as_str());
+        let uids = id.to_imap_sequence_set()?;
 
-                    if let Err(err) = &seq {
-                        debug!(?id, ?err, "skipping invalid sequence");
-                    }
-
-                    seq.ok()
-                })
-                .collect::<Vec<_>>()
-                .try_into()
-                .map_err(Error::ParseSequenceError)?,
-        };
-
-        client.select_mailbox(&folder_encoded).await?;
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadWrite)
+            .await?;
         client.set_flags(uids, flags.to_imap_flags_iter()).await?;
 
         Ok(())
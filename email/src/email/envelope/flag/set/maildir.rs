@@ -1,10 +1,12 @@
-use std::collections::HashSet;
-
 use async_trait::async_trait;
 use tracing::info;
 
 use super::{Flags, SetFlags};
-use crate::{email::error::Error, envelope::Id, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    envelope::Id,
+    maildir::{keywords::MaildirKeywords, rename_entry_with_flags, MaildirContextSync},
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct SetMaildirFlags {
@@ -33,17 +35,15 @@ async fn set_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()>
         let ctx = self.ctx.lock().await;
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
+        let mut keywords = MaildirKeywords::load(mdir.path());
+        let letters = flags.to_maildir_letters(&mut keywords);
+        keywords.save()?;
+
         id.iter()
-            .filter_map(|id| mdir.find(id).ok().flatten())
-            .try_for_each(|mut entry| {
-                entry.update_flags(HashSet::from(flags)).map_err(|err| {
-                    Error::SetFlagsMaildirError(
-                        err,
-                        folder.to_owned(),
-                        id.to_string(),
-                        flags.clone(),
-                    )
-                })
+            .filter_map(|id| mdir.find(&id).ok().flatten())
+            .try_for_each(|entry| -> AnyResult<()> {
+                rename_entry_with_flags(&mdir, &entry, &letters)?;
+                Ok(())
             })?;
 
         Ok(())
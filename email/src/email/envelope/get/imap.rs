@@ -1,9 +1,12 @@
 use async_trait::async_trait;
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::{Envelope, GetEnvelope};
-use crate::{envelope::SingleId, imap::ImapContext, AnyResult};
+use crate::{
+    envelope::{Envelopes, Id, SingleId},
+    imap::{ImapContext, ImapMailboxMode},
+    AnyResult, Error,
+};
 
 #[derive(Clone, Debug)]
 pub struct GetImapEnvelope {
@@ -33,14 +36,58 @@ async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope>
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
 
-        client.select_mailbox(&folder_encoded).await?;
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadOnly)
+            .await?;
 
         let envelope = client.fetch_first_envelope(id.parse().unwrap()).await?;
         debug!("imap envelope: {envelope:#?}");
 
         Ok(envelope)
     }
+
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        info!("getting imap envelopes {ids:?} from folder {folder}");
+
+        if ids.is_empty() {
+            return Ok(Envelopes::default());
+        }
+
+        let mut client = self.ctx.client().await;
+        let config = &client.account_config;
+
+        let folder = config.get_folder_alias(folder);
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
+
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadOnly)
+            .await?;
+
+        let uids = Id::multiple(ids.iter().map(SingleId::as_str)).to_imap_sequence_set()?;
+        let mut envelopes = client.fetch_envelopes_map(uids).await?;
+
+        let mut missing = Vec::new();
+        let envelopes = ids
+            .iter()
+            .filter_map(|id| {
+                let envelope = envelopes.remove(id.as_str());
+                if envelope.is_none() {
+                    missing.push(id.to_string());
+                }
+                envelope
+            })
+            .collect::<Envelopes>();
+
+        if !missing.is_empty() {
+            return Err(Error::GetEnvelopesMissingError(folder, missing).into());
+        }
+
+        debug!("imap envelopes: {envelopes:#?}");
+
+        Ok(envelopes)
+    }
 }
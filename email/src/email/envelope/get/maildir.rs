@@ -2,7 +2,11 @@
 use tracing::{info, trace};
 
 use super::{Envelope, GetEnvelope};
-use crate::{envelope::SingleId, maildir::MaildirContextSync, AnyResult, Error};
+use crate::{
+    envelope::{Envelopes, SingleId},
+    maildir::MaildirContextSync,
+    AnyResult, Error,
+};
 
 #[derive(Clone)]
 pub struct GetMaildirEnvelope {
@@ -37,4 +41,30 @@ async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope>
 
         Ok(envelope)
     }
+
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        info!("getting maildir envelopes {ids:?} from folder {folder}");
+
+        let session = self.ctx.lock().await;
+        let mdir = session.get_maildir_from_folder_alias(folder)?;
+
+        let mut envelopes = Vec::with_capacity(ids.len());
+        let mut missing = Vec::new();
+
+        for id in ids {
+            match mdir.find(id.as_str()).map_err(Error::from)? {
+                Some(entry) => envelopes.push(Envelope::try_from(entry)?),
+                None => missing.push(id.to_string()),
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::GetEnvelopesMissingError(folder.to_owned(), missing).into());
+        }
+
+        let envelopes = Envelopes::from_iter(envelopes);
+        trace!("maildir envelopes: {envelopes:#?}");
+
+        Ok(envelopes)
+    }
 }
@@ -7,11 +7,27 @@
 
 use async_trait::async_trait;
 
-use super::{Envelope, SingleId};
+use super::{Envelope, Envelopes, SingleId};
 use crate::AnyResult;
 
 #[async_trait]
 pub trait GetEnvelope: Send + Sync {
     /// Get the envelope from the given folder matching the given id.
     async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope>;
+
+    /// Get the envelopes from the given folder matching the given
+    /// ids, in the same order.
+    ///
+    /// Fails if an envelope cannot be found. Backends resolve the
+    /// folder once and report all the missing ids, while the default
+    /// implementation gets envelopes one by one.
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        let mut envelopes = Vec::with_capacity(ids.len());
+
+        for id in ids {
+            envelopes.push(self.get_envelope(folder, id).await?);
+        }
+
+        Ok(Envelopes::from_iter(envelopes))
+    }
 }
@@ -2,7 +2,12 @@
 use tracing::{info, trace};
 
 use super::{Envelope, GetEnvelope};
-use crate::{email::error::Error, envelope::SingleId, notmuch::NotmuchContextSync, AnyResult};
+use crate::{
+    email::error::Error,
+    envelope::{Envelopes, SingleId},
+    notmuch::NotmuchContextSync,
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct GetNotmuchEnvelope {
@@ -44,4 +49,36 @@ async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope>
 
         Ok(envelope)
     }
+
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        info!("getting notmuch envelopes {ids:?} from folder {folder}");
+
+        let ctx = self.ctx.lock().await;
+        let db = ctx.open_db()?;
+
+        let mut envelopes = Vec::with_capacity(ids.len());
+        let mut missing = Vec::new();
+
+        for id in ids {
+            let msg = db
+                .find_message(id.as_str())
+                .map_err(Error::NotMuchFailure)?;
+
+            match msg {
+                Some(msg) => envelopes.push(Envelope::from_notmuch_msg(msg)),
+                None => missing.push(id.to_string()),
+            }
+        }
+
+        db.close().map_err(Error::NotMuchFailure)?;
+
+        if !missing.is_empty() {
+            return Err(Error::GetEnvelopesMissingError(folder.to_owned(), missing).into());
+        }
+
+        let envelopes = Envelopes::from_iter(envelopes);
+        trace!("notmuch envelopes: {envelopes:#?}");
+
+        Ok(envelopes)
+    }
 }
@@ -1,12 +1,31 @@
 use std::{
+    borrow::Cow,
+    cmp::Ordering,
     fmt,
+    hash::{Hash, Hasher},
     ops::{Deref, DerefMut},
 };
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+use crate::{Error, Result};
+
+/// The envelope identifier(s).
+///
+/// Two identifiers are equal if they yield the same ids in the same
+/// order, whatever their variant: `Id::range(1, 3)` equals
+/// `Id::multiple(["1", "2", "3"])`. Hashing and ordering are
+/// consistent with this equality, and numeric ids are compared
+/// numerically: `"9"` is lower than `"10"`. Ranges are compared
+/// and hashed using their bounds, without iterating their ids.
+#[derive(Clone, Debug)]
 pub enum Id {
     Single(SingleId),
     Multiple(MultipleIds),
+
+    /// The inclusive range of numeric ids `start:end`.
+    ///
+    /// Ranges are iterated lazily, which avoids allocating one id
+    /// per message for large contiguous selections.
+    Range(IdRange),
 }
 
 impl Id {
@@ -18,10 +37,18 @@ pub fn multiple(ids: impl Into<MultipleIds>) -> Self {
         Self::Multiple(ids.into())
     }
 
+    /// Build the inclusive range of numeric ids `start:end`.
+    ///
+    /// Fails if `start` is greater than `end`.
+    pub fn range(start: u32, end: u32) -> Result<Self> {
+        Ok(Self::Range(IdRange::new(start, end)?))
+    }
+
     pub fn join(&self, sep: impl AsRef<str>) -> String {
         match self {
             Self::Single(id) => id.to_string(),
             Self::Multiple(ids) => ids.join(sep.as_ref()),
+            Self::Range(_) => self.iter().collect::<Vec<_>>().join(sep.as_ref()),
         }
     }
 
@@ -35,7 +62,187 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Single(id) => write!(f, "{}", id.deref()),
             Self::Multiple(ids) => write!(f, "{ids}"),
+            Self::Range(range) => write!(f, "{range}"),
+        }
+    }
+}
+
+impl Id {
+    /// Return the bounds of the range of numeric ids yielded by the
+    /// identifier, if any.
+    ///
+    /// Identifiers yielding canonical numeric ids (without sign nor
+    /// leading zero) in a contiguous ascending order are considered
+    /// as ranges, whatever their variant. This lets ranges be hashed
+    /// using their bounds, consistently with the equality.
+    fn bounds(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Range(range) => Some((range.start, range.end)),
+            Self::Single(id) => parse_canonical_id(id).map(|id| (id, id)),
+            Self::Multiple(ids) => {
+                let mut ids = ids.iter().map(|id| parse_canonical_id(id));
+                let start = ids.next()??;
+
+                ids.try_fold(start, |prev, id| {
+                    id.filter(|id| Some(*id) == prev.checked_add(1))
+                })
+                .map(|end| (start, end))
+            }
+        }
+    }
+}
+
+/// Parse the given id as a canonical numeric id, without sign nor
+/// leading zero, like the ones yielded by ranges.
+fn parse_canonical_id(id: &str) -> Option<u32> {
+    if id.starts_with('+') || (id.len() > 1 && id.starts_with('0')) {
+        return None;
+    }
+
+    id.parse().ok()
+}
+
+impl PartialEq for Id {
+    /// Compare ranges using their bounds, other identifiers using
+    /// their yielded ids.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Range(a), Self::Range(b)) => a == b,
+            (Self::Range(range), Self::Single(id)) | (Self::Single(id), Self::Range(range)) => {
+                range.start == range.end && parse_canonical_id(id) == Some(range.start)
+            }
+            _ => self.iter().eq(other.iter()),
+        }
+    }
+}
+
+impl Eq for Id {}
+
+impl Hash for Id {
+    /// Hash the bounds of identifiers considered as ranges, and the
+    /// yielded ids of the other ones, so that equal identifiers of
+    /// different variants have the same hash.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if let Some((start, end)) = self.bounds() {
+            state.write_u8(0);
+            start.hash(state);
+            end.hash(state);
+            return;
+        }
+
+        state.write_u8(1);
+        let mut len = 0;
+
+        for id in self.iter() {
+            id.hash(state);
+            len += 1;
         }
+
+        state.write_usize(len);
+    }
+}
+
+impl PartialOrd for Id {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Id {
+    /// Compare the yielded ids lexicographically, each id being
+    /// compared using [`cmp_ids`].
+    ///
+    /// Ranges are compared using their bounds, they are only iterated
+    /// when compared to multiple ids.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Range(a), Self::Range(b)) => (a.start, a.end).cmp(&(b.start, b.end)),
+            (Self::Range(range), Self::Single(id)) => cmp_range_single(range, id),
+            (Self::Single(id), Self::Range(range)) => cmp_range_single(range, id).reverse(),
+            _ => {
+                let mut ids = self.iter();
+                let mut other_ids = other.iter();
+
+                loop {
+                    match (ids.next(), other_ids.next()) {
+                        (None, None) => break Ordering::Equal,
+                        (None, Some(_)) => break Ordering::Less,
+                        (Some(_), None) => break Ordering::Greater,
+                        (Some(id), Some(other_id)) => match cmp_ids(&id, &other_id) {
+                            Ordering::Equal => continue,
+                            ordering => break ordering,
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Compare the given range with the given single id, without
+/// iterating the range.
+fn cmp_range_single(range: &IdRange, id: &str) -> Ordering {
+    cmp_ids(&range.start.to_string(), id).then(if range.start < range.end {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    })
+}
+
+/// Compare the given ids.
+///
+/// Numeric ids are compared numerically and come before other ids,
+/// which are compared as strings. Numeric ids with the same value
+/// (like `"1"` and `"01"`) are then compared as strings, so that the
+/// ordering stays consistent with the equality.
+pub fn cmp_ids(a: &str, b: &str) -> Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y).then_with(|| a.cmp(b)),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// The inclusive range of numeric ids `start:end`.
+///
+/// The start of the range is always lower than or equal to its end.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct IdRange {
+    start: u32,
+    end: u32,
+}
+
+impl IdRange {
+    /// Build a new range.
+    ///
+    /// Fails if `start` is greater than `end`.
+    pub fn new(start: u32, end: u32) -> Result<Self> {
+        if start > end {
+            return Err(Error::InvalidIdRangeError(start, end));
+        }
+
+        Ok(Self { start, end })
+    }
+
+    pub fn start(&self) -> u32 {
+        self.start
+    }
+
+    pub fn end(&self) -> u32 {
+        self.end
+    }
+}
+
+impl fmt::Display for IdRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.start, self.end)
+    }
+}
+
+impl From<IdRange> for Id {
+    fn from(range: IdRange) -> Self {
+        Self::Range(range)
     }
 }
 
@@ -63,7 +270,7 @@ fn from(ids: &MultipleIds) -> Self {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct SingleId(String);
 
 impl SingleId {
@@ -92,7 +299,7 @@ fn from(id: T) -> Self {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub struct MultipleIds(Vec<String>);
 
 impl Deref for MultipleIds {
@@ -139,24 +346,161 @@ pub fn new(id: &'a Id) -> Self {
 }
 
 impl<'a> Iterator for IdIterator<'a> {
-    type Item = &'a str;
+    type Item = Cow<'a, str>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.id {
             Id::Single(_) if self.index > 0 => None,
             Id::Single(SingleId(id)) => {
                 self.index = 1;
-                Some(id.as_str())
+                Some(Cow::Borrowed(id.as_str()))
             }
             Id::Multiple(MultipleIds(ids)) => {
                 if self.index < ids.len() {
-                    let id = Some(ids[self.index].as_str());
+                    let id = Some(Cow::Borrowed(ids[self.index].as_str()));
                     self.index += 1;
                     id
                 } else {
                     None
                 }
             }
+            Id::Range(range) => {
+                let id = range.start as u64 + self.index as u64;
+
+                if id > range.end as u64 {
+                    None
+                } else {
+                    self.index += 1;
+                    Some(Cow::Owned(id.to_string()))
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering::*,
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::Id;
+    use crate::Error;
+
+    fn hash(id: &Id) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn range_iter() {
+        let ids: Vec<_> = Id::range(3, 6).unwrap().iter().collect();
+        assert_eq!(ids, vec!["3", "4", "5", "6"]);
+
+        let ids: Vec<_> = Id::range(7, 7).unwrap().iter().collect();
+        assert_eq!(ids, vec!["7"]);
+
+        assert_eq!(Id::range(1, 10_000).unwrap().iter().count(), 10_000);
+        assert_eq!(Id::range(u32::MAX, u32::MAX).unwrap().iter().count(), 1);
+
+        assert_eq!(Id::range(1, 3).unwrap().join(","), "1,2,3");
+        assert_eq!(Id::range(1, 3).unwrap().to_string(), "1:3");
+    }
+
+    #[test]
+    fn invalid_range() {
+        assert!(matches!(
+            Id::range(2, 1),
+            Err(Error::InvalidIdRangeError(2, 1))
+        ));
+    }
+
+    #[test]
+    fn range_eq_multiple() {
+        let range = Id::range(1, 3).unwrap();
+        assert_eq!(range, Id::multiple(["1", "2", "3"]));
+        assert_ne!(range, Id::multiple(["1", "2"]));
+        assert_ne!(range, Id::multiple(["3", "2", "1"]));
+        assert_eq!(Id::range(5, 5).unwrap(), Id::single("5"));
+    }
+
+    #[test]
+    fn eq_ids_have_same_hash() {
+        assert_eq!(hash(&Id::single("1")), hash(&Id::multiple(["1"])));
+        assert_eq!(
+            hash(&Id::range(1, 3).unwrap()),
+            hash(&Id::multiple(["1", "2", "3"]))
+        );
+        assert_ne!(hash(&Id::single("1")), hash(&Id::multiple(["1", "2"])));
+    }
+
+    #[test]
+    fn range_ord_multiple() {
+        let multiple = Id::multiple(["1", "2", "3"]);
+        assert_eq!(Id::range(1, 3).unwrap().cmp(&multiple), Equal);
+        assert_eq!(Id::range(1, 2).unwrap().cmp(&multiple), Less);
+        assert_eq!(Id::range(1, 4).unwrap().cmp(&multiple), Greater);
+        assert_eq!(Id::range(2, 3).unwrap().cmp(&multiple), Greater);
+    }
+
+    #[test]
+    fn range_eq_hash_ord_without_iterating() {
+        // iterating such ranges would take ages
+        let range = Id::range(0, u32::MAX).unwrap();
+        let other = Id::range(0, u32::MAX - 1).unwrap();
+
+        assert_eq!(range, range.clone());
+        assert_ne!(range, other);
+        assert_eq!(hash(&range), hash(&range.clone()));
+        assert_ne!(hash(&range), hash(&other));
+        assert_eq!(range.cmp(&other), Greater);
+        assert_eq!(other.cmp(&range), Less);
+
+        assert_ne!(range, Id::single("0"));
+        assert_eq!(range.cmp(&Id::single("0")), Greater);
+        assert_eq!(range.cmp(&Id::single("1")), Less);
+        assert_eq!(Id::single("1").cmp(&range), Greater);
+
+        // ranges are still only iterated as long as multiple ids
+        assert_ne!(range, Id::multiple(["0", "1"]));
+        assert_eq!(range.cmp(&Id::multiple(["0", "2"])), Less);
+    }
+
+    #[test]
+    fn eq_single_and_multiple_hash_as_range() {
+        let range = Id::range(5, 5).unwrap();
+        assert_eq!(hash(&range), hash(&Id::single("5")));
+        assert_eq!(hash(&range), hash(&Id::multiple(["5"])));
+        assert_eq!(range.cmp(&Id::single("5")), Equal);
+
+        // non-canonical numeric ids are not part of ranges
+        assert_ne!(range, Id::single("05"));
+        assert_ne!(range, Id::single("+5"));
+        assert_ne!(hash(&Id::single("05")), hash(&Id::single("5")));
+
+        // non-contiguous ids are not ranges
+        assert_ne!(
+            hash(&Id::range(1, 3).unwrap()),
+            hash(&Id::multiple(["1", "3"]))
+        );
+    }
+
+    #[test]
+    fn ord_numeric_ids() {
+        assert_eq!(Id::single("9").cmp(&Id::single("10")), Less);
+        assert_eq!(Id::single("10").cmp(&Id::single("9")), Greater);
+        assert_eq!(Id::single("1").cmp(&Id::single("01")), Greater);
+        assert_eq!(Id::single("10").cmp(&Id::single("abc")), Less);
+        assert_eq!(Id::single("abc").cmp(&Id::single("abd")), Less);
+
+        let mut ids = vec![Id::single("10"), Id::single("9"), Id::single("100")];
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec![Id::single("9"), Id::single("10"), Id::single("100")]
+        );
+    }
+}
@@ -9,27 +9,76 @@
     body::{BodyStructure, Disposition},
     core::Vec1,
     fetch::{MacroOrMessageDataItemNames, MessageDataItem, MessageDataItemName},
+    sequence::{Sequence, SequenceSet},
 };
 use once_cell::sync::Lazy;
+use tracing::debug;
 
 use crate::{
-    envelope::{Envelope, Envelopes},
+    envelope::{Envelope, Envelopes, Id},
     flag::Flags,
     message::Message,
+    Error, Result,
 };
 
 /// The IMAP fetch items needed to retrieve everything we need to
-/// build an envelope: UID, flags and envelope (Message-ID, From, To,
-/// Subject, Date).
+/// build an envelope: UID, flags, envelope (Message-ID, From, To,
+/// Subject, Date) and internal date (used when the Date header is
+/// missing or invalid).
 pub static FETCH_ENVELOPES: Lazy<MacroOrMessageDataItemNames<'static>> = Lazy::new(|| {
     MacroOrMessageDataItemNames::MessageDataItemNames(vec![
         MessageDataItemName::Uid,
         MessageDataItemName::Flags,
         MessageDataItemName::Envelope,
+        MessageDataItemName::InternalDate,
         MessageDataItemName::BodyStructure,
     ])
 });
 
+impl Id {
+    /// Build the IMAP sequence set matching the current id(s).
+    ///
+    /// Ranges are translated straight to the `start:end` sequence,
+    /// and invalid sequences are skipped from multiple ids. Fails if
+    /// the id is invalid, or if no valid id remains.
+    pub fn to_imap_sequence_set(&self) -> Result<SequenceSet> {
+        let uids = match self {
+            Id::Single(id) => Sequence::try_from(id.as_str())
+                .map_err(|err| Error::BuildSequenceSetError(err, id.to_string()))?
+                .into(),
+            Id::Multiple(ids) => {
+                let seqs: Vec<_> = ids
+                    .iter()
+                    .filter_map(|id| {
+                        let seq = Sequence::try_from(id.as_str());
+
+                        if let Err(err) = &seq {
+                            debug!(?id, ?err, "skipping invalid sequence");
+                        }
+
+                        seq.ok()
+                    })
+                    .collect();
+
+                if seqs.is_empty() {
+                    return Err(Error::BuildSequenceSetEmptyError);
+                }
+
+                seqs.try_into()
+                    .map_err(|err| Error::BuildSequenceSetError(err, self.to_string()))?
+            }
+            Id::Range(range) => {
+                let range = range.to_string();
+                Sequence::try_from(range.as_str())
+                    .map_err(|err| Error::BuildSequenceSetError(err, range))?
+                    .into()
+            }
+        };
+
+        Ok(uids)
+    }
+}
+
 impl Envelopes {
     pub fn from_imap_data_items(fetches: HashMap<NonZeroU32, Vec1<MessageDataItem>>) -> Self {
         fetches
@@ -54,6 +103,7 @@ pub fn from_imap_data_items(items: &[MessageDataItem]) -> Self {
         let mut flags = Flags::default();
         let mut msg = Vec::default();
         let mut has_attachment = false;
+        let mut internal_date = None;
 
         for item in items {
             match item {
@@ -149,12 +199,15 @@ pub fn from_imap_data_items(items: &[MessageDataItem]) -> Self {
                 MessageDataItem::BodyStructure(body) => {
                     has_attachment = has_at_least_one_attachment([body]);
                 }
+                MessageDataItem::InternalDate(date) => {
+                    internal_date = Some(*date.as_ref());
+                }
                 _ => (),
             }
         }
 
         let msg = Message::from(msg);
-        let mut env = Envelope::from_msg(id, flags, msg);
+        let mut env = Envelope::from_msg_with_internal_date(id, flags, msg, internal_date);
         env.has_attachment = has_attachment;
         env
     }
@@ -205,3 +258,47 @@ fn is_attachment(disp: Option<&Disposition>) -> bool {
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{envelope::Id, Error};
+
+    #[test]
+    fn range_to_imap_sequence_set() {
+        let uids = Id::range(1, 10_000)
+            .unwrap()
+            .to_imap_sequence_set()
+            .unwrap();
+        assert_eq!(uids.0.as_ref().len(), 1);
+        assert_eq!(uids, Id::single("1:10000").to_imap_sequence_set().unwrap());
+
+        let uids = Id::multiple(["1", "2", "3"])
+            .to_imap_sequence_set()
+            .unwrap();
+        assert_eq!(uids.0.as_ref().len(), 3);
+
+        assert!(Id::range(0, 10).unwrap().to_imap_sequence_set().is_err());
+    }
+
+    #[test]
+    fn invalid_id_to_imap_sequence_set() {
+        let err = Id::single("garbage").to_imap_sequence_set().unwrap_err();
+        assert!(matches!(err, Error::BuildSequenceSetError(_, id) if id == "garbage"));
+
+        let err = Id::multiple(Vec::<String>::new())
+            .to_imap_sequence_set()
+            .unwrap_err();
+        assert!(matches!(err, Error::BuildSequenceSetEmptyError));
+
+        let err = Id::multiple(["garbage", "0"])
+            .to_imap_sequence_set()
+            .unwrap_err();
+        assert!(matches!(err, Error::BuildSequenceSetEmptyError));
+
+        // invalid ids are skipped as long as one valid id remains
+        let uids = Id::multiple(["garbage", "42"])
+            .to_imap_sequence_set()
+            .unwrap();
+        assert_eq!(uids.0.as_ref().len(), 1);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
     feature = "derive",
@@ -11,6 +13,13 @@ pub struct EnvelopeListConfig {
     /// available envelopes.
     pub page_size: Option<usize>,
 
+    /// Override the page size of specific folders.
+    ///
+    /// Keys are folder names or folder kinds (inbox, sent, drafts,
+    /// trash, archive and junk), like folder aliases. Folders without
+    /// override use the page size above.
+    pub folder_page_sizes: Option<HashMap<String, usize>>,
+
     /// Customize the format for displaying envelopes date.
     ///
     /// See [`chrono::format::strftime`] for supported
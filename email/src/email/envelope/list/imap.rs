@@ -10,14 +10,13 @@
     sequence::{SeqOrUid, Sequence, SequenceSet},
 };
 use tracing::{debug, info, instrument, trace};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::{Envelopes, ListEnvelopes, ListEnvelopesOptions};
 use crate::{
     email::error::Error,
     envelope::Envelope,
     imap,
-    imap::ImapContext,
+    imap::{ImapContext, ImapMailboxMode},
     search_query::{
         filter::SearchEmailsFilterQuery,
         sort::{SearchEmailsSorter, SearchEmailsSorterKind, SearchEmailsSorterOrder},
@@ -26,8 +25,6 @@
     AnyResult, Result,
 };
 
-static MAX_SEQUENCE_SIZE: u8 = u8::MAX; // 255
-
 #[derive(Clone, Debug)]
 pub struct ListImapEnvelopes {
     ctx: ImapContext,
@@ -61,10 +58,10 @@ async fn list_envelopes(
         let mut client = self.ctx.client().await;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!(name = folder_encoded, "UTF7-encoded mailbox");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!(name = folder_encoded, "encoded mailbox");
 
-        let data = client.select_mailbox(folder_encoded.clone()).await?;
+        let data = client.examine_mailbox(folder_encoded.clone()).await?;
         let folder_size = data.exists.unwrap_or_default() as usize;
         debug!(name = folder_encoded, ?data, "mailbox selected");
 
@@ -102,7 +99,8 @@ async fn list_envelopes(
                 &uids
             };
 
-            let uids_chunks = uids.chunks(MAX_SEQUENCE_SIZE as usize);
+            let chunk_size = self.ctx.imap_config.fetch_chunk_size();
+            let uids_chunks = uids.chunks(chunk_size);
             let uids_chunks_len = uids_chunks.len();
 
             debug!(?uids, "fetching envelopes using {uids_chunks_len} chunks");
@@ -114,7 +112,9 @@ async fn list_envelopes(
 
                 tokio::spawn(async move {
                     let mut client = ctx.client().await;
-                    client.select_mailbox(mbox).await?;
+                    client
+                        .ensure_mailbox(mbox, ImapMailboxMode::ReadOnly)
+                        .await?;
                     client.fetch_envelopes(uids).await
                 })
             }))
@@ -8,7 +8,7 @@
 use crate::{
     email::error::Error,
     envelope::Envelope,
-    maildir::MaildirContextSync,
+    maildir::{cache::EnvelopesCache, MaildirContextSync},
     search_query::{filter::SearchEmailsFilterQuery, SearchEmailsQuery},
     AnyResult,
 };
@@ -49,8 +49,22 @@ async fn list_envelopes(
         let ctx = self.ctx.lock().await;
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
-        let entries = mdir.read().map_err(Error::ListMaildirEntriesError)?;
-        let mut envelopes = Envelopes::from_mdir_entries(entries, opts.query.as_ref());
+        let entries: Vec<_> = mdir
+            .read()
+            .map_err(Error::ListMaildirEntriesError)?
+            .collect();
+
+        let mut envelopes = if ctx.maildir_config.envelopes_cache {
+            let mut cache = EnvelopesCache::load(mdir.path());
+            cache.retain(entries.iter().filter_map(|entry| entry.id().ok()));
+
+            let envelopes =
+                Envelopes::from_mdir_entries_cached(&mut cache, entries, opts.query.as_ref());
+            cache.save();
+            envelopes
+        } else {
+            Envelopes::from_mdir_entries(entries.into_iter(), opts.query.as_ref())
+        };
         debug!("found {} maildir envelopes", envelopes.len());
         trace!("{envelopes:#?}");
 
@@ -169,3 +183,220 @@ pub fn matches_maildir_search_query(&self, envelope: &Envelope, msg_path: &Path)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use chrono::DateTime;
+    use tempfile::TempDir;
+
+    use super::ListMaildirEnvelopes;
+    use crate::{
+        account::config::AccountConfig,
+        backend::context::BackendContextBuilder,
+        envelope::{
+            list::{ListEnvelopes, ListEnvelopesOptions},
+            Envelope,
+        },
+        maildir::{cache::ENVELOPES_CACHE_FILE_NAME, config::MaildirConfig, MaildirContextBuilder},
+    };
+
+    /// Build a Maildir folder of the given number of messages.
+    ///
+    /// Messages are delivered in order, but their `Date` header goes
+    /// back in time, so that the date order differs from the delivery
+    /// order.
+    async fn build_list_envelopes(count: i64) -> (TempDir, ListMaildirEnvelopes) {
+        build_list_envelopes_with_cache(count, true).await
+    }
+
+    async fn build_list_envelopes_with_cache(
+        count: i64,
+        envelopes_cache: bool,
+    ) -> (TempDir, ListMaildirEnvelopes) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_owned();
+        for dir in ["cur", "new", "tmp"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        for i in 0..count {
+            let name = format!("{}.M{i}P1.localhost:2,S", 1_700_000_000 + i);
+            let date = DateTime::from_timestamp(1_800_000_000 - i, 0).unwrap();
+            let date = date.to_rfc2822();
+            let msg =
+                format!("Message-ID: <{i}@localhost>\nDate: {date}\nSubject: s{i:03}\n\nHello!\n");
+            fs::write(root.join("cur").join(name), msg).unwrap();
+        }
+
+        let account_config = Arc::new(AccountConfig::default());
+        let mdir_config = Arc::new(MaildirConfig {
+            root_dir: root,
+            maildirpp: true,
+            envelopes_cache,
+            ..Default::default()
+        });
+        let ctx = MaildirContextBuilder::new(account_config, mdir_config)
+            .build()
+            .await
+            .unwrap();
+
+        (dir, ListMaildirEnvelopes::new(&ctx))
+    }
+
+    fn entry_paths(root: &Path) -> Vec<PathBuf> {
+        fs::read_dir(root.join("cur"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    /// Change the subject of the given message behind the cache back,
+    /// by keeping the size and the modification time of its file.
+    ///
+    /// Listings still showing the previous subject did not parse the
+    /// message again.
+    fn change_subject_silently(path: &Path) {
+        let mtime = fs::metadata(path).unwrap().modified().unwrap();
+        let msg = fs::read_to_string(path)
+            .unwrap()
+            .replace("Subject: s", "Subject: t");
+        fs::write(path, msg).unwrap();
+
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(mtime).unwrap();
+    }
+
+    fn subjects(envelopes: &[Envelope]) -> Vec<String> {
+        envelopes.iter().map(|e| e.subject.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn list_page_sorted_by_date() {
+        let (_dir, list) = build_list_envelopes(100).await;
+
+        let envelopes = list
+            .list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+
+        let opts = ListEnvelopesOptions {
+            page_size: 10,
+            page: 2,
+            query: None,
+        };
+        let page = list.list_envelopes("INBOX", opts).await.unwrap();
+
+        // messages are sorted by date descending, which is the
+        // reverse delivery order, whether the listing is paginated
+        // or not
+        let expected: Vec<_> = (20..30).map(|i| format!("s{i:03}")).collect();
+        assert_eq!(subjects(&page), expected);
+        assert_eq!(*page, envelopes[20..30]);
+    }
+
+    #[tokio::test]
+    async fn list_page_from_cache() {
+        let (dir, list) = build_list_envelopes(100).await;
+        let root = dir.path();
+
+        let opts = ListEnvelopesOptions {
+            page_size: 10,
+            page: 0,
+            query: None,
+        };
+        list.list_envelopes("INBOX", opts).await.unwrap();
+
+        for path in entry_paths(root) {
+            change_subject_silently(&path);
+        }
+
+        // other pages are served from the cache, without parsing
+        // messages again
+
+        let opts = ListEnvelopesOptions {
+            page_size: 10,
+            page: 2,
+            query: None,
+        };
+        let page = list.list_envelopes("INBOX", opts).await.unwrap();
+
+        let expected: Vec<_> = (20..30).map(|i| format!("s{i:03}")).collect();
+        assert_eq!(subjects(&page), expected);
+    }
+
+    #[tokio::test]
+    async fn list_twice_from_cache() {
+        let (dir, list) = build_list_envelopes(20).await;
+        let root = dir.path();
+
+        let envelopes = list
+            .list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+        assert_eq!(envelopes.len(), 20);
+        assert!(root.join(ENVELOPES_CACHE_FILE_NAME).is_file());
+
+        // unchanged messages are not parsed again
+
+        for path in entry_paths(root) {
+            change_subject_silently(&path);
+        }
+
+        let cached_envelopes = list
+            .list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+        assert_eq!(cached_envelopes, envelopes);
+
+        // changed messages are parsed again
+
+        let path = entry_paths(root)
+            .into_iter()
+            .find(|path| path.to_string_lossy().contains(".M7P1."))
+            .unwrap();
+        let date = DateTime::from_timestamp(1_800_000_000 - 7, 0).unwrap();
+        let date = date.to_rfc2822();
+        let msg = format!("Message-ID: <7@localhost>\nDate: {date}\nSubject: changed\n\nHello!\n");
+        fs::write(&path, msg).unwrap();
+
+        let envelopes = list
+            .list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+
+        let mut expected: Vec<_> = (0..20).map(|i| format!("s{i:03}")).collect();
+        expected[7] = String::from("changed");
+        assert_eq!(subjects(&envelopes), expected);
+    }
+
+    #[tokio::test]
+    async fn list_twice_without_cache() {
+        let (dir, list) = build_list_envelopes_with_cache(20, false).await;
+        let root = dir.path();
+
+        list.list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+        assert!(!root.join(ENVELOPES_CACHE_FILE_NAME).exists());
+
+        // messages are parsed again on every listing
+
+        for path in entry_paths(root) {
+            change_subject_silently(&path);
+        }
+
+        let envelopes = list
+            .list_envelopes("INBOX", Default::default())
+            .await
+            .unwrap();
+
+        let expected: Vec<_> = (0..20).map(|i| format!("t{i:03}")).collect();
+        assert_eq!(subjects(&envelopes), expected);
+    }
+}
@@ -10,9 +10,13 @@
 
 use async_trait::async_trait;
 
-use super::{Envelope, Envelopes};
+use tracing::debug;
+
+use super::{preview::preview, Envelope, Envelopes, Id};
 use crate::{
+    account::config::{AccountConfig, HasAccountConfig},
     email::search_query::SearchEmailsQuery,
+    message::peek::PeekMessages,
     search_query::sort::{SearchEmailsSorter, SearchEmailsSorterKind, SearchEmailsSorterOrder},
     AnyResult,
 };
@@ -28,6 +32,86 @@ async fn list_envelopes(
     ) -> AnyResult<Envelopes>;
 }
 
+#[async_trait]
+pub trait ListEnvelopesWithPreview: ListEnvelopes + PeekMessages {
+    /// Same as [`ListEnvelopes::list_envelopes`], with envelope
+    /// previews of at most `preview_len` characters.
+    ///
+    /// Only the beginning of each message body is peeked, see
+    /// [`PeekMessages::peek_partial_messages`].
+    async fn list_envelopes_with_preview(
+        &self,
+        folder: &str,
+        opts: ListEnvelopesOptions,
+        preview_len: usize,
+    ) -> AnyResult<Envelopes> {
+        let mut envelopes = self.list_envelopes(folder, opts).await?;
+
+        if envelopes.is_empty() {
+            return Ok(envelopes);
+        }
+
+        let ids: Vec<_> = envelopes.iter().map(|e| e.id.clone()).collect();
+        let id = Id::multiple(ids);
+
+        // encoded bodies take more bytes than their text
+        let max_body_size = preview_len.saturating_mul(4).max(MIN_PREVIEW_BODY_SIZE);
+        let msgs = self
+            .peek_partial_messages(folder, &id, max_body_size)
+            .await?;
+
+        let msgs = msgs.to_vec();
+
+        // previews cannot be attributed safely when some messages
+        // are missing
+        if msgs.len() != envelopes.len() {
+            debug!("cannot peek all messages, skipping previews");
+            return Ok(envelopes);
+        }
+
+        for (envelope, msg) in envelopes.iter_mut().zip(msgs) {
+            match msg.parsed() {
+                Ok(msg) => envelope.preview = preview(msg, preview_len),
+                Err(err) => debug!(
+                    id = envelope.id.as_str(),
+                    "cannot parse message, skipping preview: {err}"
+                ),
+            }
+        }
+
+        Ok(envelopes)
+    }
+}
+
+impl<T: ListEnvelopes + PeekMessages> ListEnvelopesWithPreview for T {}
+
+#[async_trait]
+pub trait ListFolderEnvelopes: ListEnvelopes + HasAccountConfig {
+    /// Same as [`ListEnvelopes::list_envelopes`], using the page
+    /// size of the folder from the account configuration.
+    ///
+    /// See [`AccountConfig::get_folder_envelope_list_page_size`].
+    async fn list_folder_envelopes(
+        &self,
+        folder: &str,
+        page: usize,
+        query: Option<SearchEmailsQuery>,
+    ) -> AnyResult<Envelopes> {
+        let opts = ListEnvelopesOptions {
+            page,
+            query,
+            ..ListEnvelopesOptions::from_account_config(self.account_config(), folder)
+        };
+
+        self.list_envelopes(folder, opts).await
+    }
+}
+
+impl<T: ListEnvelopes + HasAccountConfig> ListFolderEnvelopes for T {}
+
+/// The minimum number of body bytes peeked to build a preview.
+const MIN_PREVIEW_BODY_SIZE: usize = 1024;
+
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ListEnvelopesOptions {
     pub page_size: usize,
@@ -54,6 +138,16 @@ pub fn cmp_envelopes(&self, a: &Envelope, b: &Envelope) -> Ordering {
 }
 
 impl ListEnvelopesOptions {
+    /// Build the listing options of the first page of the given
+    /// folder, using the page size of the folder from the account
+    /// configuration.
+    pub fn from_account_config(config: &AccountConfig, folder: &str) -> Self {
+        Self {
+            page_size: config.get_folder_envelope_list_page_size(folder),
+            ..Default::default()
+        }
+    }
+
     pub fn sort_envelopes(&self, envelopes: &mut Envelopes) {
         envelopes.sort_by(|a, b| {
             if let Some(sorters) = self.query.as_ref().and_then(|q| q.sort.as_ref()) {
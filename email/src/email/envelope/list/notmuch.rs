@@ -5,7 +5,6 @@
 use super::{Envelopes, ListEnvelopes, ListEnvelopesOptions};
 use crate::{
     email::error::Error,
-    folder::FolderKind,
     notmuch::NotmuchContextSync,
     search_query::{filter::SearchEmailsFilterQuery, SearchEmailsQuery},
     AnyResult,
@@ -43,12 +42,8 @@ async fn list_envelopes(
         let config = &ctx.account_config;
         let db = ctx.open_db()?;
 
+        let mut final_query = ctx.folder_query(folder);
         let ref folder = config.get_folder_alias(folder);
-        let mut final_query = if ctx.maildirpp() && FolderKind::matches_inbox(folder) {
-            String::from("folder:\"\"")
-        } else {
-            format!("folder:{folder:?}")
-        };
 
         if let Some(query) = opts.query.as_ref() {
             let query = query.to_notmuch_search_query();
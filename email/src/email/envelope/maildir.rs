@@ -3,11 +3,16 @@
 //! This module contains envelope-related mapping functions from the
 //! [maildirpp] crate types.
 
+use std::fs;
+
+use chrono::{DateTime, Utc};
 use maildirs::MaildirEntry;
 use rayon::prelude::*;
+use tracing::debug;
 
 use crate::{
     envelope::{Envelope, Envelopes, Flags},
+    maildir::cache::{EntryMetadata, EnvelopesCache},
     message::Message,
     search_query::SearchEmailsQuery,
     Error, Result,
@@ -38,25 +43,101 @@ pub fn from_mdir_entries(
     }
 }
 
+impl Envelopes {
+    /// Build envelopes from the given Maildir entries, using the given
+    /// cache.
+    ///
+    /// Only the entries missing from the cache (or whose cached
+    /// envelope is outdated) are parsed, then added to the cache. It
+    /// is up to the caller to save the cache.
+    pub(crate) fn from_mdir_entries_cached(
+        cache: &mut EnvelopesCache,
+        entries: impl IntoIterator<Item = MaildirEntry>,
+        query: Option<&SearchEmailsQuery>,
+    ) -> Self {
+        let mut envelopes = Vec::new();
+        let mut misses = Vec::new();
+
+        for entry in entries {
+            let Ok(id) = entry.id().map(ToOwned::to_owned) else {
+                continue;
+            };
+            let Ok(meta) = EntryMetadata::read(entry.path()) else {
+                continue;
+            };
+
+            match cache.get(&id, &meta) {
+                Some(cached) => {
+                    let path = entry.path().to_owned();
+                    let Ok(flags) = Flags::try_from(entry) else {
+                        continue;
+                    };
+                    let envelope = Envelope {
+                        id,
+                        flags,
+                        ..cached.clone()
+                    };
+                    envelopes.push(Some((envelope, path)));
+                }
+                None => {
+                    misses.push((envelopes.len(), id, meta, entry));
+                    envelopes.push(None);
+                }
+            }
+        }
+
+        debug!(
+            "found {} cached maildir envelopes, parsing {} others",
+            envelopes.len() - misses.len(),
+            misses.len()
+        );
+
+        let parsed: Vec<_> = misses
+            .into_par_iter()
+            .filter_map(|(idx, id, meta, entry)| {
+                let path = entry.path().to_owned();
+                let envelope = Envelope::try_from(entry).ok()?;
+                Some((idx, id, meta, envelope, path))
+            })
+            .collect();
+
+        for (idx, id, meta, envelope, path) in parsed {
+            cache.insert(id, meta, &envelope);
+            envelopes[idx] = Some((envelope, path));
+        }
+
+        // the order of the given entries is preserved
+        envelopes
+            .into_iter()
+            .flatten()
+            .filter_map(|(envelope, path)| match query {
+                Some(query) => query
+                    .matches_maildir_search_query(&envelope, path.as_ref())
+                    .then_some(envelope),
+                None => Some(envelope),
+            })
+            .collect()
+    }
+}
+
 impl TryFrom<MaildirEntry> for Envelope {
     type Error = Error;
 
     fn try_from(entry: MaildirEntry) -> Result<Self> {
         let id = entry.id()?.to_owned();
         let msg = Message::from(entry.read()?);
-
-        let has_attachment = {
-            let attachments = msg.attachments();
-
-            match attachments {
-                Ok(attachments) => !attachments.is_empty(),
-                Err(_) => false,
-            }
-        };
-
+        // the internal date of a Maildir message is the modification
+        // time of its file
+        let internal_date = fs::metadata(entry.path())
+            .and_then(|meta| meta.modified())
+            .ok()
+            .map(|mtime| DateTime::<Utc>::from(mtime).fixed_offset());
         let flags = Flags::try_from(entry)?;
-        let mut env = Envelope::from_msg(id, flags, msg);
-        env.has_attachment = has_attachment;
-        Ok(env)
+        Ok(Envelope::from_msg_with_internal_date(
+            id,
+            flags,
+            msg,
+            internal_date,
+        ))
     }
 }
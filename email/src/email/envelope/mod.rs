@@ -16,6 +16,7 @@
 pub mod maildir;
 #[cfg(feature = "notmuch")]
 pub mod notmuch;
+pub mod preview;
 #[cfg(feature = "sync")]
 pub mod sync;
 #[cfg(feature = "thread")]
@@ -40,10 +41,12 @@
 pub use self::{
     address::Address,
     flag::{Flag, Flags},
-    id::{Id, MultipleIds, SingleId},
+    id::{Id, IdRange, MultipleIds, SingleId},
 };
 use crate::{
-    account::config::AccountConfig, date::from_mail_parser_to_chrono_datetime, message::Message,
+    account::config::AccountConfig,
+    date::{from_mail_parser_to_chrono_datetime, parse_rfc2822_date},
+    message::{walker::MimeWalker, Message},
 };
 
 /// The email envelope.
@@ -71,6 +74,10 @@ pub struct Envelope {
     /// The Subject header from the email message.
     pub subject: String,
     /// The Date header from the email message.
+    ///
+    /// The original offset of the header is preserved. When the
+    /// header is missing or invalid, the internal date of the
+    /// message is used instead (if the backend provides one).
     pub date: DateTime<FixedOffset>,
 
     /// True if the current envelope contains at least one attachment.
@@ -78,105 +85,122 @@ pub struct Envelope {
     /// An attachment is defined here as a MIME part that is not a
     /// `text/*`.
     pub has_attachment: bool,
+
+    /// The one-line snippet of the message body.
+    ///
+    /// The preview needs the message body, so it is only populated
+    /// on demand, see
+    /// [`ListEnvelopesWithPreview`](self::list::ListEnvelopesWithPreview).
+    pub preview: Option<String>,
 }
 
+/// The suffix of the Message-ID generated for messages without
+/// Message-ID header.
+const GENERATED_MESSAGE_ID_SUFFIX: &str = "@generated>";
+
 impl Envelope {
     /// Build an envelope from an identifier, some
     /// [flags](self::Flags) and a [message](super::Message).
+    ///
+    /// See [`Envelope::from_msg_with_internal_date`].
     pub fn from_msg(id: impl ToString, flags: Flags, msg: Message) -> Envelope {
-        let mut envelope = Envelope {
+        Envelope::from_msg_with_internal_date(id, flags, msg, None)
+    }
+
+    /// Build an envelope from an identifier, some
+    /// [flags](self::Flags), a [message](super::Message) and the
+    /// internal date of the message given by the backend (the
+    /// IMAP INTERNALDATE, the Maildir file modification time…).
+    ///
+    /// The internal date is used as a fallback when the message has
+    /// no Date header, or when it cannot be parsed.
+    pub fn from_msg_with_internal_date(
+        id: impl ToString,
+        flags: Flags,
+        msg: Message,
+        internal_date: Option<DateTime<FixedOffset>>,
+    ) -> Envelope {
+        let envelope = match msg.parsed() {
+            Ok(msg) => Envelope::from_mail_parser(msg, flags, internal_date),
+            Err(_) => {
+                trace!("cannot parse message header, skipping it");
+                Envelope {
+                    flags,
+                    date: internal_date.unwrap_or_default(),
+                    ..Default::default()
+                }
+            }
+        };
+
+        Envelope {
             id: id.to_string(),
+            ..envelope
+        }
+    }
+
+    /// Build an envelope from a parsed [`mail_parser::Message`],
+    /// some [flags](self::Flags) and an optional internal date.
+    ///
+    /// This is where envelope fields are extracted from message
+    /// headers, for all backends. The identifier is left empty, it
+    /// is up to the backend to set it.
+    ///
+    /// The date is parsed from the Date header with its original
+    /// offset preserved, see [`parse_rfc2822_date`]. When the header
+    /// is missing or cannot be parsed, the internal date is used
+    /// instead.
+    pub fn from_mail_parser(
+        msg: &mail_parser::Message,
+        flags: Flags,
+        internal_date: Option<DateTime<FixedOffset>>,
+    ) -> Envelope {
+        let mut envelope = Envelope {
             flags,
             ..Default::default()
         };
 
-        if let Ok(msg) = msg.parsed() {
-            match msg.from() {
-                Some(mail_parser::Address::List(addrs))
-                    if !addrs.is_empty() && addrs[0].address.is_some() =>
-                {
-                    let name = addrs[0].name.as_ref().map(|name| name.to_string());
-                    let email = addrs[0]
-                        .address
-                        .as_ref()
-                        .map(|name| name.to_string())
-                        .unwrap();
-                    envelope.from = Address::new(name, email);
-                }
-                Some(mail_parser::Address::Group(groups))
-                    if !groups.is_empty()
-                        && !groups[0].addresses.is_empty()
-                        && groups[0].addresses[0].address.is_some() =>
-                {
-                    let name = groups[0].name.as_ref().map(|name| name.to_string());
-                    let email = groups[0].addresses[0]
-                        .address
-                        .as_ref()
-                        .map(|name| name.to_string())
-                        .unwrap();
-                    envelope.from = Address::new(name, email)
-                }
-                _ => {
-                    trace!("cannot extract envelope sender from message header, skipping it");
-                }
-            };
-
-            match msg.to() {
-                Some(mail_parser::Address::List(addrs))
-                    if !addrs.is_empty() && addrs[0].address.is_some() =>
-                {
-                    let name = addrs[0].name.as_ref().map(|name| name.to_string());
-                    let email = addrs[0]
-                        .address
-                        .as_ref()
-                        .map(|name| name.to_string())
-                        .unwrap();
-                    envelope.to = Address::new(name, email);
-                }
-                Some(mail_parser::Address::Group(groups))
-                    if !groups.is_empty()
-                        && !groups[0].addresses.is_empty()
-                        && groups[0].addresses[0].address.is_some() =>
-                {
-                    let name = groups[0].name.as_ref().map(|name| name.to_string());
-                    let email = groups[0].addresses[0]
-                        .address
-                        .as_ref()
-                        .map(|name| name.to_string())
-                        .unwrap();
-                    envelope.to = Address::new(name, email)
-                }
-                _ => {
-                    trace!("cannot extract envelope recipient from message header, skipping it");
-                }
-            };
+        match msg.from().and_then(first_address) {
+            Some(addr) => envelope.from = addr,
+            None => trace!("cannot extract envelope sender from message header, skipping it"),
+        };
 
-            envelope.subject = msg.subject().map(ToOwned::to_owned).unwrap_or_default();
+        match msg.to().and_then(first_address) {
+            Some(addr) => envelope.to = addr,
+            None => trace!("cannot extract envelope recipient from message header, skipping it"),
+        };
 
-            match msg.date() {
-                Some(date) => envelope.set_date(date),
-                None => {
-                    trace!("cannot extract envelope date from message header, skipping it")
-                }
-            };
-
-            envelope.message_id = msg
-                .message_id()
-                .map(|mid| format!("<{mid}>"))
-                // NOTE: this is useful for the sync to prevent
-                // messages without Message-ID to still being
-                // synchronized.
-                .unwrap_or_else(|| {
-                    let mut hasher = DefaultHasher::new();
-                    envelope.date.to_string().hash(&mut hasher);
-                    format!("<{:x}@generated>", hasher.finish())
-                });
-
-            envelope.in_reply_to = msg.in_reply_to().as_text().map(|mid| format!("<{mid}>"));
-        } else {
-            trace!("cannot parse message header, skipping it");
+        envelope.subject = msg.subject().map(ToOwned::to_owned).unwrap_or_default();
+
+        let date = msg.header_raw("Date").and_then(parse_rfc2822_date);
+
+        envelope.message_id = msg
+            .message_id()
+            .map(|mid| format!("<{mid}>"))
+            // NOTE: this is useful for the sync to prevent
+            // messages without Message-ID to still being
+            // synchronized. The internal date is not taken into
+            // account, since it may differ from one backend to
+            // another.
+            .unwrap_or_else(|| {
+                let mut hasher = DefaultHasher::new();
+                date.unwrap_or_default().to_string().hash(&mut hasher);
+                format!("<{:x}{GENERATED_MESSAGE_ID_SUFFIX}", hasher.finish())
+            });
+
+        envelope.date = match date {
+            Some(date) => date,
+            None => {
+                trace!("cannot extract envelope date from message header, using internal date");
+                internal_date.unwrap_or_default()
+            }
         };
 
+        envelope.in_reply_to = msg.in_reply_to().as_text().map(|mid| format!("<{mid}>"));
+
+        // like IMAP body structures, only parts explicitly disposed
+        // as attachments count, inline images do not
+        envelope.has_attachment = MimeWalker::new(msg).any(|part| part.is_attachment());
+
         envelope
     }
 
@@ -221,6 +245,22 @@ pub fn format_date(&self, config: &AccountConfig) -> String {
         date.to_string()
     }
 
+    /// Return `true` if the envelope has been sent from one of the
+    /// addresses of the [account configuration](crate::AccountConfig)
+    /// (main email address or aliases).
+    pub fn is_from_self(&self, config: &AccountConfig) -> bool {
+        config.is_self(&self.from)
+    }
+
+    /// Return `true` if the Message-ID of the envelope has been
+    /// generated, because the message has no Message-ID header.
+    ///
+    /// Generated Message-IDs only depend on the Date header, so they
+    /// do not identify messages.
+    pub fn has_generated_message_id(&self) -> bool {
+        self.message_id.ends_with(GENERATED_MESSAGE_ID_SUFFIX)
+    }
+
     /// Build a message from the current envelope.
     ///
     /// The message is just composed of two headers and contains no
@@ -248,6 +288,27 @@ pub fn as_threaded(&self) -> ThreadedEnvelope {
     }
 }
 
+/// Extract the first address (with an email) of the given
+/// [`mail_parser::Address`].
+fn first_address(addr: &mail_parser::Address) -> Option<Address> {
+    let addr = match addr {
+        mail_parser::Address::List(addrs) => addrs.first()?,
+        mail_parser::Address::Group(groups) => {
+            let group = groups.first()?;
+            let addr = group.addresses.first()?;
+            // the name of the group takes precedence over the name
+            // of the address
+            let name = group.name.as_ref().map(ToString::to_string);
+            let email = addr.address.as_ref()?.to_string();
+            return Some(Address::new(name, email));
+        }
+    };
+
+    let name = addr.name.as_ref().map(ToString::to_string);
+    let email = addr.address.as_ref()?.to_string();
+    Some(Address::new(name, email))
+}
+
 // NOTE: this is useful for the sync, not sure how relevant it is for
 // the rest.
 impl PartialEq for Envelope {
@@ -404,3 +465,166 @@ fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use concat_with::concat_line;
+    use mail_parser::MessageParser;
+
+    use super::{Address, Envelope, Flag, Flags};
+
+    #[test]
+    fn from_mail_parser() {
+        let msg = concat_line!(
+            "Message-ID: <id@localhost>",
+            "In-Reply-To: <parent@localhost>",
+            "Date: Tue, 02 Jan 2024 10:30:00 +0200",
+            "From: =?utf-8?q?Jos=C3=A9?= <jose@localhost>, other@localhost",
+            "To: Team: alice@localhost, bob@localhost;",
+            "Subject: =?utf-8?b?SGVsbG8gd29ybGQ=?=",
+            "Content-Type: multipart/mixed; boundary=\"mixed\"",
+            "",
+            "--mixed",
+            "Content-Type: text/plain",
+            "",
+            "Hello!",
+            "--mixed",
+            "Content-Type: application/pdf",
+            "Content-Disposition: attachment; filename=\"doc.pdf\"",
+            "",
+            "pdf",
+            "--mixed--",
+        );
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        let flags = Flags::from_iter([Flag::Seen]);
+
+        let envelope = Envelope::from_mail_parser(&msg, flags.clone(), None);
+
+        assert_eq!(envelope.id, "");
+        assert_eq!(envelope.message_id, "<id@localhost>");
+        assert_eq!(envelope.in_reply_to.as_deref(), Some("<parent@localhost>"));
+        assert_eq!(envelope.flags, flags);
+        assert_eq!(envelope.from, Address::new(Some("José"), "jose@localhost"));
+        assert_eq!(envelope.from.name.as_deref(), Some("José"));
+        assert_eq!(envelope.to, Address::new(Some("Team"), "alice@localhost"));
+        assert_eq!(envelope.to.name.as_deref(), Some("Team"));
+        assert_eq!(envelope.subject, "Hello world");
+        assert_eq!(
+            envelope.date,
+            DateTime::parse_from_rfc3339("2024-01-02T10:30:00+02:00").unwrap()
+        );
+        assert_eq!(envelope.date.offset().local_minus_utc(), 2 * 3600);
+        assert!(envelope.has_attachment);
+    }
+
+    #[test]
+    fn from_mail_parser_without_headers() {
+        let msg = concat_line!("Content-Type: text/plain", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), None);
+
+        assert!(envelope.has_generated_message_id());
+        assert_eq!(envelope.in_reply_to, None);
+        assert_eq!(envelope.from, Address::default());
+        assert_eq!(envelope.to, Address::default());
+        assert_eq!(envelope.subject, "");
+        assert_eq!(envelope.date, DateTime::default());
+        assert!(!envelope.has_attachment);
+    }
+
+    #[test]
+    fn from_mail_parser_with_nested_attachment() {
+        let inline_only = concat_line!(
+            "Content-Type: multipart/related; boundary=\"related\"",
+            "",
+            "--related",
+            "Content-Type: text/html",
+            "",
+            "<img src=\"cid:logo@localhost\">",
+            "--related",
+            "Content-Type: image/png",
+            "Content-Disposition: inline; filename=\"logo.png\"",
+            "Content-ID: <logo@localhost>",
+            "",
+            "png",
+            "--related--",
+        );
+        let msg = MessageParser::new().parse(inline_only.as_bytes()).unwrap();
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), None);
+        assert!(!envelope.has_attachment);
+
+        let nested = concat_line!(
+            "Content-Type: multipart/mixed; boundary=\"mixed\"",
+            "",
+            "--mixed",
+            "Content-Type: multipart/alternative; boundary=\"alternative\"",
+            "",
+            "--alternative",
+            "Content-Type: text/plain",
+            "",
+            "Hello!",
+            "--alternative",
+            "Content-Type: application/pdf",
+            "Content-Disposition: attachment; filename=\"doc.pdf\"",
+            "",
+            "pdf",
+            "--alternative--",
+            "--mixed--",
+        );
+        let msg = MessageParser::new().parse(nested.as_bytes()).unwrap();
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), None);
+        assert!(envelope.has_attachment);
+    }
+
+    #[test]
+    fn from_mail_parser_with_obsolete_zone() {
+        let msg = concat_line!("Date: Tue, 02 Jan 2024 10:30:00 EST", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), None);
+
+        assert_eq!(
+            envelope.date,
+            DateTime::parse_from_rfc3339("2024-01-02T10:30:00-05:00").unwrap()
+        );
+        assert_eq!(envelope.date.offset().local_minus_utc(), -5 * 3600);
+    }
+
+    #[test]
+    fn from_mail_parser_with_internal_date() {
+        let internal_date = DateTime::parse_from_rfc3339("2024-01-03T08:00:00+01:00").unwrap();
+
+        // missing Date header
+        let msg = concat_line!("Message-ID: <id@localhost>", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), Some(internal_date));
+        assert_eq!(envelope.date, internal_date);
+        assert_eq!(envelope.date.offset().local_minus_utc(), 3600);
+
+        // invalid Date header
+        let msg = concat_line!("Date: yesterday", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), Some(internal_date));
+        assert_eq!(envelope.date, internal_date);
+
+        // the Date header takes precedence over the internal date
+        let msg = concat_line!("Date: Tue, 02 Jan 2024 10:30:00 GMT", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        let envelope = Envelope::from_mail_parser(&msg, Flags::default(), Some(internal_date));
+        assert_eq!(
+            envelope.date,
+            DateTime::parse_from_rfc3339("2024-01-02T10:30:00+00:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn from_msg_sets_id() {
+        let msg = concat_line!("Message-ID: <id@localhost>", "", "Hello!");
+        let envelope = Envelope::from_msg("42", Flags::default(), msg.into());
+
+        assert_eq!(envelope.id, "42");
+        assert_eq!(envelope.message_id, "<id@localhost>");
+    }
+}
@@ -0,0 +1,121 @@
+//! # Envelope preview
+//!
+//! Module dedicated to envelope previews, the one-line snippet of the
+//! message body shown by envelope lists. The main function of this
+//! module is [`preview`].
+
+/// The default length of previews, in characters.
+pub const DEFAULT_PREVIEW_LEN: usize = 200;
+
+/// Build the preview of the given parsed message, with at most
+/// `max_len` characters.
+///
+/// The preview is taken from the first text part of the message (or
+/// from the first HTML part converted to text), see
+/// [`preview_from_text`]. Returns `None` if the message has no text
+/// to preview.
+pub fn preview(msg: &mail_parser::Message, max_len: usize) -> Option<String> {
+    let text = msg.body_text(0)?;
+    let preview = preview_from_text(&text, max_len);
+    (!preview.is_empty()).then_some(preview)
+}
+
+/// Build the preview of the given text, with at most `max_len`
+/// characters.
+///
+/// Quoted replies (lines starting with `>`, and the attribution line
+/// introducing them) are stripped, the text stops at the signature
+/// delimiter (`-- `), and whitespaces are collapsed into single
+/// spaces.
+pub fn preview_from_text(text: &str, max_len: usize) -> String {
+    let mut lines = text.lines().map(str::trim).peekable();
+    let mut words = Vec::new();
+
+    while let Some(line) = lines.next() {
+        if line == "--" {
+            break;
+        }
+
+        if line.starts_with('>') {
+            continue;
+        }
+
+        // skips attribution lines like `On Mon, Bob wrote:`, when
+        // followed by the quoted reply
+        if line.ends_with(':') && lines.peek().is_some_and(|next| next.starts_with('>')) {
+            continue;
+        }
+
+        words.extend(line.split_whitespace());
+    }
+
+    words.join(" ").chars().take(max_len).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mail_parser::MessageParser;
+
+    use super::{preview, preview_from_text};
+
+    #[test]
+    fn preview_multipart_message() {
+        let msg = concat!(
+            "From: alice@localhost\r\n",
+            "Subject: Hello\r\n",
+            "Content-Type: multipart/mixed; boundary=\"mixed\"\r\n",
+            "\r\n",
+            "--mixed\r\n",
+            "Content-Type: multipart/alternative; boundary=\"alt\"\r\n",
+            "\r\n",
+            "--alt\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "\r\n",
+            "Hi Bob,\r\n",
+            "\r\n",
+            "The meeting is moved to =C3=A9te.\r\n",
+            "\r\n",
+            "On Mon, Bob wrote:\r\n",
+            "> When is the meeting?\r\n",
+            "\r\n",
+            "-- \r\n",
+            "Alice\r\n",
+            "--alt\r\n",
+            "Content-Type: text/html\r\n",
+            "\r\n",
+            "<p>Hi Bob, HTML version</p>\r\n",
+            "--alt--\r\n",
+            "--mixed\r\n",
+            "Content-Type: application/pdf\r\n",
+            "Content-Disposition: attachment; filename=\"agenda.pdf\"\r\n",
+            "\r\n",
+            "%PDF\r\n",
+            "--mixed--\r\n",
+        );
+
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+
+        assert_eq!(
+            preview(&msg, 100).unwrap(),
+            "Hi Bob, The meeting is moved to éte."
+        );
+        assert_eq!(preview(&msg, 6).unwrap(), "Hi Bob");
+    }
+
+    #[test]
+    fn preview_without_text() {
+        let msg = "Subject: Empty\r\n\r\n> quoted only\r\n";
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        assert_eq!(preview(&msg, 100), None);
+    }
+
+    #[test]
+    fn collapse_whitespaces() {
+        let text = "  Hello,\t\tworld!\n\n\n  How   are\r\nyou?  ";
+        assert_eq!(preview_from_text(text, 100), "Hello, world! How are you?");
+
+        // truncation happens on characters, not bytes
+        assert_eq!(preview_from_text("héhé héhé", 6), "héhé h");
+    }
+}
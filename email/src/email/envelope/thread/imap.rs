@@ -8,7 +8,6 @@
 };
 use petgraph::{graphmap::DiGraphMap, Direction};
 use tracing::{debug, instrument};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::ThreadEnvelopes;
 use crate::{
@@ -50,10 +49,14 @@ async fn thread_envelopes(
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!(folder_encoded, "utf7 encoded folder");
-
-        let folder_size = client.select_mailbox(folder_encoded).await?.exists.unwrap() as usize;
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!(folder_encoded, "encoded folder");
+
+        let folder_size = client
+            .examine_mailbox(folder_encoded)
+            .await?
+            .exists
+            .unwrap() as usize;
         debug!(folder_size, "folder size");
 
         if folder_size == 0 {
@@ -123,10 +126,14 @@ async fn thread_envelope(
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!(folder_encoded, "utf7 encoded folder");
-
-        let _folder_size = client.select_mailbox(folder_encoded).await?.exists.unwrap() as usize;
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!(folder_encoded, "encoded folder");
+
+        let _folder_size = client
+            .examine_mailbox(folder_encoded)
+            .await?
+            .exists
+            .unwrap() as usize;
         debug!(folder_size = _folder_size, "folder size");
 
         let uid = id.parse::<u32>().unwrap();
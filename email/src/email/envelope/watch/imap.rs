@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use tokio::sync::oneshot::{Receiver, Sender};
+use tokio::sync::{
+    mpsc::UnboundedSender,
+    oneshot::{Receiver, Sender},
+};
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
-use super::WatchEnvelopes;
+use super::{send_events, WatchEnvelopes, WatchEvent};
 use crate::{envelope::Envelope, imap::ImapContext, AnyResult};
 
 #[derive(Clone, Debug)]
@@ -30,6 +32,7 @@ pub async fn watch_envelopes_loop(
         &self,
         folder: &str,
         wait_for_shutdown_request: &mut Receiver<()>,
+        events: Option<&UnboundedSender<WatchEvent>>,
     ) -> AnyResult<()> {
         info!("watching imap folder {folder} for envelope changes");
 
@@ -37,8 +40,8 @@ pub async fn watch_envelopes_loop(
         let mut client = self.ctx.client().await;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
 
         let envelopes_count = client
             .examine_mailbox(folder_encoded)
@@ -65,6 +68,7 @@ pub async fn watch_envelopes_loop(
                 HashMap::from_iter(next_envelopes.into_iter().map(|e| (e.id.clone(), e)));
 
             self.exec_hooks(config, &envelopes, &next_envelopes).await;
+            send_events(events, &envelopes, &next_envelopes);
 
             envelopes = next_envelopes;
         }
@@ -80,7 +84,23 @@ async fn watch_envelopes(
         shutdown: Sender<()>,
     ) -> AnyResult<()> {
         let res = self
-            .watch_envelopes_loop(folder, &mut wait_for_shutdown_request)
+            .watch_envelopes_loop(folder, &mut wait_for_shutdown_request, None)
+            .await;
+
+        shutdown.send(()).unwrap();
+
+        res
+    }
+
+    async fn watch_envelope_events(
+        &self,
+        folder: &str,
+        events: UnboundedSender<WatchEvent>,
+        mut wait_for_shutdown_request: Receiver<()>,
+        shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        let res = self
+            .watch_envelopes_loop(folder, &mut wait_for_shutdown_request, Some(&events))
             .await;
 
         shutdown.send(()).unwrap();
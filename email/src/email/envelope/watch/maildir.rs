@@ -1,13 +1,17 @@
 use std::{collections::HashMap, sync::mpsc};
 
 use async_trait::async_trait;
+use maildirs::Maildir;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
-use tokio::sync::oneshot::{Receiver, Sender};
+use tokio::sync::{
+    mpsc::UnboundedSender,
+    oneshot::{Receiver, Sender},
+};
 use tracing::{debug, info, trace};
 
-use super::WatchEnvelopes;
+use super::{send_events, WatchEnvelopes, WatchEvent};
 use crate::{
-    email::error::Error,
+    email::error::{Error, Result},
     envelope::{Envelope, Envelopes},
     maildir::MaildirContextSync,
     AnyResult,
@@ -29,15 +33,11 @@ pub fn new_boxed(ctx: &MaildirContextSync) -> Box<dyn WatchEnvelopes> {
     pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn WatchEnvelopes>> {
         Some(Self::new_boxed(ctx))
     }
-}
 
-#[async_trait]
-impl WatchEnvelopes for WatchMaildirEnvelopes {
-    async fn watch_envelopes(
+    pub async fn watch_envelopes_loop(
         &self,
         folder: &str,
-        _wait_for_shutdown_request: Receiver<()>,
-        _shutdown: Sender<()>,
+        events: Option<&UnboundedSender<WatchEvent>>,
     ) -> AnyResult<()> {
         info!("maildir: watching folder {folder} for email changes");
 
@@ -45,10 +45,7 @@ async fn watch_envelopes(
         let config = &session.account_config;
 
         let mdir = session.get_maildir_from_folder_alias(folder)?;
-        let entries = mdir.read().map_err(Error::MaildirsError)?;
-        let envelopes = Envelopes::from_mdir_entries(entries, None);
-        let mut envelopes: HashMap<String, Envelope> =
-            HashMap::from_iter(envelopes.into_iter().map(|e| (e.id.clone(), e)));
+        let mut envelopes = read_envelopes(&mdir)?;
 
         let (tx, rx) = mpsc::channel();
         let mut watcher =
@@ -63,12 +60,10 @@ async fn watch_envelopes(
                 Ok(_evt) => {
                     trace!("received filesystem change event: {_evt:?}");
 
-                    let entries = mdir.read().map_err(Error::MaildirsError)?;
-                    let next_envelopes = Envelopes::from_mdir_entries(entries, None);
-                    let next_envelopes: HashMap<String, Envelope> =
-                        HashMap::from_iter(next_envelopes.into_iter().map(|e| (e.id.clone(), e)));
+                    let next_envelopes = read_envelopes(&mdir)?;
 
                     self.exec_hooks(config, &envelopes, &next_envelopes).await;
+                    send_events(events, &envelopes, &next_envelopes);
 
                     envelopes = next_envelopes;
                 }
@@ -82,3 +77,86 @@ async fn watch_envelopes(
         Ok(())
     }
 }
+
+#[async_trait]
+impl WatchEnvelopes for WatchMaildirEnvelopes {
+    async fn watch_envelopes(
+        &self,
+        folder: &str,
+        _wait_for_shutdown_request: Receiver<()>,
+        _shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        self.watch_envelopes_loop(folder, None).await
+    }
+
+    async fn watch_envelope_events(
+        &self,
+        folder: &str,
+        events: UnboundedSender<WatchEvent>,
+        _wait_for_shutdown_request: Receiver<()>,
+        _shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        self.watch_envelopes_loop(folder, Some(&events)).await
+    }
+}
+
+/// Read the envelopes of the given Maildir folder, by id.
+///
+/// Maildir ids do not contain the info, so they survive flag
+/// changes.
+fn read_envelopes(mdir: &Maildir) -> Result<HashMap<String, Envelope>> {
+    let entries = mdir.read().map_err(Error::MaildirsError)?;
+    let envelopes = Envelopes::from_mdir_entries(entries, None);
+    Ok(HashMap::from_iter(
+        envelopes.into_iter().map(|e| (e.id.clone(), e)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use maildirs::Maildir;
+
+    use super::read_envelopes;
+    use crate::envelope::{watch::WatchEvent, Flag, Flags};
+
+    #[test]
+    fn watch_flag_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_owned();
+        for dir in ["cur", "new", "tmp"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let unseen = root.join("cur").join("1700000000.M1P1.localhost:2,F");
+        let msg = "Message-ID: <1@localhost>\nSubject: watch\n\nHello!\n";
+        fs::write(&unseen, msg).unwrap();
+
+        let mdir = Maildir::from(root.clone());
+        let prev = read_envelopes(&mdir).unwrap();
+
+        // another client marks the message as seen and unflags it
+        let seen = root.join("cur").join("1700000000.M1P1.localhost:2,S");
+        fs::rename(&unseen, &seen).unwrap();
+        let next = read_envelopes(&mdir).unwrap();
+
+        assert_eq!(
+            WatchEvent::diff(&prev, &next),
+            vec![WatchEvent::FlagsChanged {
+                id: String::from("1700000000.M1P1.localhost"),
+                added: Flags::from_iter([Flag::Seen]),
+                removed: Flags::from_iter([Flag::Flagged]),
+            }]
+        );
+
+        fs::remove_file(&seen).unwrap();
+        let next_next = read_envelopes(&mdir).unwrap();
+        assert_eq!(
+            WatchEvent::diff(&next, &next_next),
+            vec![WatchEvent::EnvelopeRemoved(String::from(
+                "1700000000.M1P1.localhost"
+            ))]
+        );
+    }
+}
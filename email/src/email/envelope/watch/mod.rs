@@ -3,14 +3,73 @@
 pub mod imap;
 #[cfg(feature = "maildir")]
 pub mod maildir;
+#[cfg(feature = "notmuch")]
+pub mod notmuch;
 
 use std::collections::HashMap;
 
 use async_trait::async_trait;
-use tokio::sync::oneshot::{Receiver, Sender};
+use tokio::sync::{
+    mpsc::UnboundedSender,
+    oneshot::{Receiver, Sender},
+};
 use tracing::{debug, info};
 
-use crate::{account::config::AccountConfig, envelope::Envelope, AnyResult};
+use crate::{
+    account::config::AccountConfig,
+    envelope::{Envelope, Flags},
+    AnyResult,
+};
+
+/// A change of a watched folder.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WatchEvent {
+    /// A new envelope has been added to the folder.
+    EnvelopeAdded(Envelope),
+
+    /// The envelope matching the given id has been removed from the
+    /// folder.
+    EnvelopeRemoved(String),
+
+    /// The flags of the envelope matching the given id changed.
+    FlagsChanged {
+        id: String,
+        added: Flags,
+        removed: Flags,
+    },
+}
+
+impl WatchEvent {
+    /// Compute the events between two states of the same folder.
+    ///
+    /// Events are sorted by envelope id, so that the diff of the
+    /// same states always gives the same events.
+    pub fn diff(
+        prev_envelopes: &HashMap<String, Envelope>,
+        next_envelopes: &HashMap<String, Envelope>,
+    ) -> Vec<Self> {
+        let mut ids: Vec<_> = prev_envelopes.keys().chain(next_envelopes.keys()).collect();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+            .filter_map(
+                |id| match (prev_envelopes.get(id), next_envelopes.get(id)) {
+                    (None, Some(next)) => Some(Self::EnvelopeAdded(next.clone())),
+                    (Some(_), None) => Some(Self::EnvelopeRemoved(id.clone())),
+                    (Some(prev), Some(next)) if prev.flags != next.flags => {
+                        Some(Self::FlagsChanged {
+                            id: id.clone(),
+                            added: next.flags.difference(&prev.flags).cloned().collect(),
+                            removed: prev.flags.difference(&next.flags).cloned().collect(),
+                        })
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+}
 
 #[async_trait]
 pub trait WatchEnvelopes: Send + Sync {
@@ -22,6 +81,23 @@ async fn watch_envelopes(
         shutdown: Sender<()>,
     ) -> AnyResult<()>;
 
+    /// Watch the given folder for envelopes changes, and send a
+    /// [`WatchEvent`] for each change to the given channel.
+    ///
+    /// Watch hooks are executed as usual. The default implementation
+    /// does not send any event.
+    async fn watch_envelope_events(
+        &self,
+        folder: &str,
+        events: UnboundedSender<WatchEvent>,
+        wait_for_shutdown_request: Receiver<()>,
+        shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        drop(events);
+        self.watch_envelopes(folder, wait_for_shutdown_request, shutdown)
+            .await
+    }
+
     async fn exec_hooks(
         &self,
         config: &AccountConfig,
@@ -43,3 +119,72 @@ async fn exec_hooks(
         }
     }
 }
+
+/// Send the events between two states of the same folder to the
+/// given channel, if any.
+pub(crate) fn send_events(
+    events: Option<&UnboundedSender<WatchEvent>>,
+    prev_envelopes: &HashMap<String, Envelope>,
+    next_envelopes: &HashMap<String, Envelope>,
+) {
+    let Some(events) = events else {
+        return;
+    };
+
+    for event in WatchEvent::diff(prev_envelopes, next_envelopes) {
+        if events.send(event).is_err() {
+            debug!("watch events receiver dropped, skipping events");
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::WatchEvent;
+    use crate::envelope::{Envelope, Flag, Flags};
+
+    fn envelopes(envelopes: &[(&str, &str)]) -> HashMap<String, Envelope> {
+        HashMap::from_iter(envelopes.iter().map(|(id, flags)| {
+            let envelope = Envelope {
+                id: id.to_string(),
+                flags: Flags::from(*flags),
+                ..Default::default()
+            };
+            (id.to_string(), envelope)
+        }))
+    }
+
+    #[test]
+    fn diff_flags_changed() {
+        let prev = envelopes(&[("1", "flagged"), ("2", "")]);
+        let next = envelopes(&[("1", "seen"), ("2", "")]);
+
+        assert_eq!(
+            WatchEvent::diff(&prev, &next),
+            vec![WatchEvent::FlagsChanged {
+                id: String::from("1"),
+                added: Flags::from_iter([Flag::Seen]),
+                removed: Flags::from_iter([Flag::Flagged]),
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_envelopes_added_and_removed() {
+        let prev = envelopes(&[("1", ""), ("2", "seen")]);
+        let next = envelopes(&[("2", "seen"), ("3", "")]);
+
+        assert_eq!(
+            WatchEvent::diff(&prev, &next),
+            vec![
+                WatchEvent::EnvelopeRemoved(String::from("1")),
+                WatchEvent::EnvelopeAdded(next["3"].clone()),
+            ]
+        );
+
+        assert!(WatchEvent::diff(&next, &next).is_empty());
+    }
+}
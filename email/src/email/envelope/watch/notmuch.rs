@@ -0,0 +1,279 @@
+//! # Notmuch envelopes watcher
+//!
+//! Module dedicated to the Notmuch implementation of the
+//! [`WatchEnvelopes`] feature. The main structure of this module is
+//! [`WatchNotmuchEnvelopes`].
+//!
+//! Changes are detected using the revision of the Notmuch database,
+//! which changes every time the database is modified (for example by
+//! `notmuch new`, or when tags change). The database directory and
+//! the Maildir directory are watched for file changes, so that the
+//! revision is only checked when something happened. When these
+//! directories cannot be watched, the revision is polled instead.
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use async_trait::async_trait;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot::{Receiver, Sender},
+    },
+    time::{self, MissedTickBehavior},
+};
+use tracing::{debug, info, trace, warn};
+
+use super::{send_events, WatchEnvelopes, WatchEvent};
+use crate::{
+    envelope::{Envelope, Envelopes},
+    notmuch::{Error, NotmuchContext, NotmuchContextSync, Result},
+    AnyResult,
+};
+
+/// The default time to wait for file changes to settle before
+/// checking the database.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The default interval between two checks of the database, when
+/// file changes cannot be watched.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct WatchNotmuchEnvelopes {
+    ctx: NotmuchContextSync,
+
+    /// The time to wait for file changes to settle.
+    ///
+    /// A single `notmuch new` writes many files to the database
+    /// directory: the database is checked once no file changed for
+    /// this duration.
+    debounce: Duration,
+
+    /// The interval between two checks of the database, when file
+    /// changes cannot be watched.
+    poll_interval: Duration,
+}
+
+impl WatchNotmuchEnvelopes {
+    pub fn new(ctx: &NotmuchContextSync) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            debounce: DEFAULT_DEBOUNCE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    pub fn new_boxed(ctx: &NotmuchContextSync) -> Box<dyn WatchEnvelopes> {
+        Box::new(Self::new(ctx))
+    }
+
+    pub fn some_new_boxed(ctx: &NotmuchContextSync) -> Option<Box<dyn WatchEnvelopes>> {
+        Some(Self::new_boxed(ctx))
+    }
+
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub async fn watch_envelopes_loop(
+        &self,
+        folder: &str,
+        wait_for_shutdown_request: &mut Receiver<()>,
+        events: Option<&UnboundedSender<WatchEvent>>,
+    ) -> AnyResult<()> {
+        info!("notmuch: watching folder {folder} for email changes");
+
+        let config = &self.ctx.account_config;
+
+        // the database is not `Send`, so it is opened and closed
+        // without holding it across await points
+        let (query, paths, mut revision, mut envelopes) = {
+            let ctx = self.ctx.lock().await;
+            let query = ctx.folder_query(folder);
+            let paths = watched_paths(&ctx)?;
+            let revision = read_revision(&ctx)?;
+            let envelopes = read_envelopes(&ctx, &query)?;
+            (query, paths, revision, envelopes)
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let watcher = match watch_paths(&paths, tx) {
+            Ok(watcher) => {
+                debug!("watching notmuch paths {paths:?}…");
+                Some(watcher)
+            }
+            Err(err) => {
+                warn!("cannot watch notmuch paths, polling database instead: {err}");
+                debug!("{err:?}");
+                None
+            }
+        };
+
+        let polling = watcher.is_none();
+        let mut interval = time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = &mut *wait_for_shutdown_request => {
+                    debug!("shutdown requested, stop watching notmuch folder {folder}");
+                    break Ok(());
+                }
+                Some(()) = rx.recv() => {
+                    self.wait_for_changes_to_settle(&mut rx).await;
+                }
+                _ = interval.tick(), if polling => (),
+            }
+
+            let ctx = self.ctx.lock().await;
+
+            let next_revision = read_revision(&ctx)?;
+            if next_revision == revision {
+                trace!("notmuch database revision did not change, skipping");
+                continue;
+            }
+
+            debug!("notmuch database revision changed: {next_revision:?}");
+            let next_envelopes = read_envelopes(&ctx, &query)?;
+            drop(ctx);
+
+            self.exec_hooks(config, &envelopes, &next_envelopes).await;
+            send_events(events, &envelopes, &next_envelopes);
+
+            revision = next_revision;
+            envelopes = next_envelopes;
+        }
+    }
+
+    /// Wait until no file changed for the debounce duration.
+    async fn wait_for_changes_to_settle(&self, rx: &mut UnboundedReceiver<()>) {
+        while let Ok(Some(())) = time::timeout(self.debounce, rx.recv()).await {
+            trace!("more notmuch file changes received, waiting");
+        }
+    }
+}
+
+#[async_trait]
+impl WatchEnvelopes for WatchNotmuchEnvelopes {
+    async fn watch_envelopes(
+        &self,
+        folder: &str,
+        mut wait_for_shutdown_request: Receiver<()>,
+        shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        let res = self
+            .watch_envelopes_loop(folder, &mut wait_for_shutdown_request, None)
+            .await;
+
+        let _ = shutdown.send(());
+
+        res
+    }
+
+    async fn watch_envelope_events(
+        &self,
+        folder: &str,
+        events: UnboundedSender<WatchEvent>,
+        mut wait_for_shutdown_request: Receiver<()>,
+        shutdown: Sender<()>,
+    ) -> AnyResult<()> {
+        let res = self
+            .watch_envelopes_loop(folder, &mut wait_for_shutdown_request, Some(&events))
+            .await;
+
+        let _ = shutdown.send(());
+
+        res
+    }
+}
+
+/// Get the database revision, as its number and its UUID.
+///
+/// The UUID changes when the database is recreated, in which case
+/// revision numbers cannot be compared.
+fn read_revision(ctx: &NotmuchContext) -> Result<(u64, String)> {
+    let db = ctx.open_db()?;
+    let revision = db.revision();
+    db.close().map_err(Error::CloseDatabaseError)?;
+
+    // the revision type depends on the platform
+    #[allow(clippy::unnecessary_cast)]
+    let number = revision.revision as u64;
+
+    Ok((number, revision.uuid))
+}
+
+/// Read the envelopes matching the given query, by id.
+fn read_envelopes(ctx: &NotmuchContext, query: &str) -> Result<HashMap<String, Envelope>> {
+    let db = ctx.open_db()?;
+
+    let query_builder = db.create_query(query).map_err(Error::CreateQueryError)?;
+    let msgs = query_builder
+        .search_messages()
+        .map_err(Error::ExecuteQueryError)?;
+
+    let envelopes = Envelopes::from_notmuch_msgs(msgs);
+    let envelopes = HashMap::from_iter(envelopes.into_iter().map(|e| (e.id.clone(), e)));
+
+    db.close().map_err(Error::CloseDatabaseError)?;
+
+    Ok(envelopes)
+}
+
+/// Get the paths to watch: the database directory, and the Maildir
+/// directory when the database is not inside it.
+fn watched_paths(ctx: &NotmuchContext) -> Result<Vec<PathBuf>> {
+    let db = ctx.open_db()?;
+    let db_path = db.path().to_owned();
+    db.close().map_err(Error::CloseDatabaseError)?;
+
+    let mdir_path = ctx.mdir_ctx.root.path().to_owned();
+
+    let paths = if db_path.starts_with(&mdir_path) {
+        vec![mdir_path]
+    } else if mdir_path.starts_with(&db_path) {
+        vec![db_path]
+    } else {
+        vec![db_path, mdir_path]
+    };
+
+    Ok(paths)
+}
+
+/// Watch the given paths recursively, sending a message to the given
+/// channel for every file creation, modification or removal.
+///
+/// The returned watcher stops watching once dropped.
+fn watch_paths(paths: &[PathBuf], tx: UnboundedSender<()>) -> notify::Result<RecommendedWatcher> {
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| match res {
+            Ok(evt) => match evt.kind {
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
+                    trace!("received filesystem change event: {evt:?}");
+                    let _ = tx.send(());
+                }
+                // access events are triggered by reads, including the
+                // ones of the database revision check
+                _ => trace!("skipping filesystem event: {evt:?}"),
+            },
+            Err(err) => {
+                debug!("error while receiving filesystem change event: {err}");
+                debug!("{err:?}");
+            }
+        },
+        Default::default(),
+    )?;
+
+    for path in paths {
+        watcher.watch(path, RecursiveMode::Recursive)?;
+    }
+
+    Ok(watcher)
+}
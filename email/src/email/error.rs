@@ -39,9 +39,14 @@ pub enum Error {
         >,
     ),
 
+    #[error("cannot build id range {0}:{1}: start is greater than end")]
+    InvalidIdRangeError(u32, u32),
     #[cfg(feature = "imap")]
-    #[error("cannot parse IMAP sequence")]
-    ParseSequenceError(#[source] ValidationError),
+    #[error("cannot build IMAP sequence set from id {1}")]
+    BuildSequenceSetError(#[source] ValidationError, String),
+    #[cfg(feature = "imap")]
+    #[error("cannot build IMAP sequence set: no valid id given")]
+    BuildSequenceSetEmptyError,
     #[cfg(feature = "maildir")]
     #[error("cannot list maildir entries")]
     ListMaildirEntriesError(#[source] maildirs::Error),
@@ -58,6 +63,11 @@ pub enum Error {
     InterpretMessageAsThreadTemplateError(#[source] mml::Error),
     #[error("cannot run sendmail command")]
     RunSendmailCommandError(#[source] process::Error),
+    #[error(
+        "sendmail dry-run mode enabled, would run: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(" | ")
+    )]
+    SendmailDryRunError(Vec<process::ResolvedCommand>),
     #[cfg(feature = "notmuch")]
     #[error("cannot remove notmuch message(s) {2} from folder {1}")]
     RemoveNotmuchMessageError(#[source] notmuch::Error, String, Id),
@@ -140,6 +150,8 @@ pub enum Error {
     GetEnvelopeMaildirError(PathBuf, SingleId),
     #[error("cannot find imap envelope {1} from folder {0}")]
     GetFirstEnvelopeImapError(String, Id),
+    #[error("cannot find envelopes {} from folder {0}", .1.join(", "))]
+    GetEnvelopesMissingError(String, Vec<String>),
     #[cfg(feature = "maildir")]
     #[error("cannot set flags {3} to envelope(s) {2} from folder {1}")]
     SetFlagsMaildirError(#[source] maildirs::Error, String, String, Flags),
@@ -152,6 +164,12 @@ pub enum Error {
     ParseFlagMaildirError(String),
     #[error("cannot parse imap flag {0}")]
     ParseFlagImapError(String),
+    #[cfg(feature = "imap")]
+    #[error("cannot represent flag {0:?} as an IMAP keyword")]
+    InvalidImapKeywordError(String),
+    #[cfg(feature = "maildir")]
+    #[error("cannot represent custom flag {0:?} as a maildir flag")]
+    InvalidMaildirFlagError(String),
     #[cfg(feature = "maildir")]
     #[error("cannot add maildir flags {3} to envelope(s) {2} from folder {1}")]
     AddFlagsMaildirError(#[source] maildirs::Error, String, String, Flags),
@@ -182,6 +200,16 @@ pub enum Error {
     #[error("cannot list envelopes from right sync backend")]
     ListRightEnvelopesError(#[source] AnyBoxedError),
 
+    #[cfg(feature = "unsubscribe")]
+    #[error("cannot unsubscribe from mailing list: one-click unsubscription not supported")]
+    UnsubscribeOneClickNotSupportedError,
+    #[cfg(feature = "unsubscribe")]
+    #[error("cannot send unsubscribe request to {1}")]
+    SendUnsubscribeRequestError(#[source] http::Error, String),
+    #[cfg(feature = "unsubscribe")]
+    #[error("cannot unsubscribe from mailing list using {1}: server replied with status {0}")]
+    UnsubscribeError(http::ureq::http::StatusCode, String),
+
     #[cfg(feature = "maildir")]
     #[error(transparent)]
     MaildirsError(#[from] maildirs::Error),
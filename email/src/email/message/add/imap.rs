@@ -2,7 +2,6 @@
 
 use async_trait::async_trait;
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::{AddMessage, Flags};
 use crate::{envelope::SingleId, imap::ImapContext, AnyResult};
@@ -40,8 +39,8 @@ async fn add_message_with_flags(
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
 
         let uid = client
             .add_message(
@@ -2,7 +2,13 @@
 use tracing::info;
 
 use super::{AddMessage, Flags};
-use crate::{email::error::Error, envelope::SingleId, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    envelope::SingleId,
+    maildir::{
+        deliver::deliver, keywords::MaildirKeywords, lock::DeliveryLock, MaildirContextSync,
+    },
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct AddMaildirMessage {
@@ -36,17 +42,21 @@ async fn add_message_with_flags(
         let ctx = self.ctx.lock().await;
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
-        let entry = mdir
-            .write_cur(
-                raw_msg,
-                flags
-                    .iter()
-                    .filter_map(|flag| maildirs::Flag::try_from(flag).ok()),
-            )
-            .map_err(|err| {
-                Error::StoreWithFlagsMaildirError(err, folder.to_owned(), flags.clone())
-            })?;
-
-        Ok(SingleId::from(entry.id().unwrap()))
+        let _lock = if ctx.maildir_config.delivery_lock {
+            Some(DeliveryLock::acquire(mdir.path()).await?)
+        } else {
+            None
+        };
+
+        let mut keywords = MaildirKeywords::load(mdir.path());
+        let letters = flags.to_maildir_letters(&mut keywords);
+        keywords.save()?;
+
+        // As per the Maildir specification, messages without flags
+        // (which means unseen) are delivered to `new`. Flags can only
+        // be stored in `cur`, so flagged messages go straight there.
+        let id = deliver(&mdir, &ctx.maildir_config, raw_msg, &letters)?;
+
+        Ok(SingleId::from(id))
     }
 }
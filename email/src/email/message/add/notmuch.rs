@@ -63,6 +63,10 @@ async fn add_message_with_flags(
             .index_file(entry.path(), None)
             .map_err(Error::NotMuchFailure)?;
 
+        for tag in ctx.notmuch_config.find_new_tags()? {
+            msg.add_tag(&tag).map_err(Error::NotMuchFailure)?;
+        }
+
         for flag in flags.iter() {
             match flag {
                 Flag::Seen => {
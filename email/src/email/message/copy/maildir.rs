@@ -1,8 +1,15 @@
+use std::fs;
+
 use async_trait::async_trait;
 use tracing::info;
 
 use super::CopyMessages;
-use crate::{email::error::Error, envelope::Id, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    envelope::Id,
+    flag::maildir::entry_letters_for,
+    maildir::{deliver::deliver, keywords::MaildirKeywords, Error, MaildirContextSync},
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct CopyMaildirMessages {
@@ -32,20 +39,23 @@ async fn copy_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> An
         let from_mdir = ctx.get_maildir_from_folder_alias(from_folder)?;
         let to_mdir = ctx.get_maildir_from_folder_alias(to_folder)?;
 
+        let mut keywords = MaildirKeywords::load(to_mdir.path());
+
+        // copies are delivered like new messages, so that they get
+        // a unique name and the configured file mode
         id.iter()
-            .filter_map(|id| from_mdir.find(id).ok().flatten())
-            .try_for_each(|entry| {
-                entry.copy(&to_mdir).map_err(|err| {
-                    Error::CopyMessagesMaildirError(
-                        err,
-                        from_folder.to_owned(),
-                        to_folder.to_owned(),
-                        entry.path().to_owned(),
-                    )
-                })?;
-                AnyResult::Ok(())
+            .filter_map(|id| from_mdir.find(&id).ok().flatten())
+            .try_for_each(|entry| -> AnyResult<()> {
+                let path = entry.path();
+                let raw_msg =
+                    fs::read(path).map_err(|err| Error::ReadEntryError(err, path.to_owned()))?;
+                let letters = entry_letters_for(&entry, &mut keywords);
+                deliver(&to_mdir, &ctx.maildir_config, &raw_msg, &letters)?;
+                Ok(())
             })?;
 
+        keywords.save()?;
+
         Ok(())
     }
 }
@@ -12,6 +12,14 @@ pub struct DeleteMessageConfig {
     /// the Trash folder or by adding the Deleted flag to their
     /// respective envelopes.
     pub style: Option<DeleteMessageStyle>,
+
+    /// The number of days deleted messages are kept in the Trash
+    /// folder.
+    ///
+    /// When defined, messages of the Trash folder older than this
+    /// number of days are definitely removed when the Trash folder
+    /// is expunged. Only supported by the Maildir backend for now.
+    pub trash_retention_days: Option<u64>,
 }
 
 /// The message deletion style.
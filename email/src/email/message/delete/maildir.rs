@@ -1,29 +1,45 @@
 use async_trait::async_trait;
+use tracing::info;
 
-use super::{DefaultDeleteMessages, DeleteMessages};
+use super::DeleteMessages;
 use crate::{
-    account::config::{AccountConfig, HasAccountConfig},
     envelope::Id,
     flag::{
         add::{maildir::AddMaildirFlags, AddFlags},
-        Flags,
+        Flag,
     },
+    folder::TRASH,
     maildir::MaildirContextSync,
-    message::r#move::{maildir::MoveMaildirMessages, MoveMessages},
+    message::{
+        r#move::{maildir::MoveMaildirMessages, MoveMessages},
+        remove::{maildir::RemoveMaildirMessages, RemoveMessages},
+    },
     AnyResult,
 };
 
+/// The Maildir delete messages feature.
+///
+/// Unlike [`RemoveMaildirMessages`], which definitely removes
+/// messages, deleting messages moves them to the Trash folder. When
+/// messages are deleted from the Trash folder itself, they are
+/// definitely removed. When the delete message style is flag-based,
+/// messages are flagged as deleted instead, and only
+/// [`ExpungeFolder`](crate::folder::ExpungeFolder) removes them.
 #[derive(Clone)]
 pub struct DeleteMaildirMessages {
+    ctx: MaildirContextSync,
     move_messages: MoveMaildirMessages,
     add_flags: AddMaildirFlags,
+    remove_messages: RemoveMaildirMessages,
 }
 
 impl DeleteMaildirMessages {
     pub fn new(ctx: &MaildirContextSync) -> Self {
         Self {
+            ctx: ctx.clone(),
             move_messages: MoveMaildirMessages::new(ctx),
             add_flags: AddMaildirFlags::new(ctx),
+            remove_messages: RemoveMaildirMessages::new(ctx),
         }
     }
 
@@ -36,27 +52,19 @@ pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn DeleteMessages
     }
 }
 
-impl HasAccountConfig for DeleteMaildirMessages {
-    fn account_config(&self) -> &AccountConfig {
-        &self.move_messages.ctx.account_config
-    }
-}
-
 #[async_trait]
-impl MoveMessages for DeleteMaildirMessages {
-    async fn move_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> AnyResult<()> {
-        self.move_messages
-            .move_messages(from_folder, to_folder, id)
-            .await
-    }
-}
+impl DeleteMessages for DeleteMaildirMessages {
+    async fn delete_messages(&self, folder: &str, id: &Id) -> AnyResult<()> {
+        info!("deleting maildir message(s) {id} from folder {folder}");
 
-#[async_trait]
-impl AddFlags for DeleteMaildirMessages {
-    async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
-        self.add_flags.add_flags(folder, id, flags).await
+        let config = &self.ctx.account_config;
+
+        if config.is_delete_message_style_flag() {
+            self.add_flags.add_flag(folder, id, Flag::Deleted).await
+        } else if config.is_trash_folder(folder) {
+            self.remove_messages.remove_messages(folder, id).await
+        } else {
+            self.move_messages.move_messages(folder, TRASH, id).await
+        }
     }
 }
-
-#[async_trait]
-impl DefaultDeleteMessages for DeleteMaildirMessages {}
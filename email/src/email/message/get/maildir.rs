@@ -1,11 +1,12 @@
 use async_trait::async_trait;
+use tracing::debug;
 
-use super::{DefaultGetMessages, GetMessages, Messages};
+use super::{GetMessages, Messages};
 use crate::{
     envelope::Id,
     flag::{
         add::{maildir::AddMaildirFlags, AddFlags},
-        Flags,
+        Flag,
     },
     maildir::MaildirContextSync,
     message::peek::{maildir::PeekMaildirMessages, PeekMessages},
@@ -16,6 +17,7 @@
 pub struct GetMaildirMessages {
     peek_messages: PeekMaildirMessages,
     add_flags: AddMaildirFlags,
+    keep_new: bool,
 }
 
 impl GetMaildirMessages {
@@ -23,6 +25,7 @@ pub fn new(ctx: &MaildirContextSync) -> Self {
         Self {
             peek_messages: PeekMaildirMessages::new(ctx),
             add_flags: AddMaildirFlags::new(ctx),
+            keep_new: ctx.maildir_config.keep_new_on_get,
         }
     }
 
@@ -36,18 +39,17 @@ pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn GetMessages>>
 }
 
 #[async_trait]
-impl PeekMessages for GetMaildirMessages {
-    async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
-        self.peek_messages.peek_messages(folder, id).await
-    }
-}
+impl GetMessages for GetMaildirMessages {
+    async fn get_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
+        let messages = self.peek_messages.peek_messages(folder, id).await?;
+
+        if self.keep_new {
+            debug!("keeping maildir messages {id} from folder {folder} untouched");
+        } else {
+            // adding the seen flag moves messages from `new` to `cur`
+            self.add_flags.add_flag(folder, id, Flag::Seen).await?;
+        }
 
-#[async_trait]
-impl AddFlags for GetMaildirMessages {
-    async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
-        self.add_flags.add_flags(folder, id, flags).await
+        Ok(messages)
     }
 }
-
-#[async_trait]
-impl DefaultGetMessages for GetMaildirMessages {}
@@ -8,7 +8,7 @@
 
 use async_trait::async_trait;
 
-use super::{peek::PeekMessages, Messages};
+use super::{parsed::ParsedMessage, peek::PeekMessages, Messages};
 use crate::{
     envelope::Id,
     flag::{add::AddFlags, Flag},
@@ -26,6 +26,16 @@ pub trait GetMessages: Send + Sync {
     /// envelopes to change, see
     /// [`PeekMessages`](super::peek::PeekMessages).
     async fn get_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages>;
+
+    /// Get email messages from the given folder matching the given
+    /// ids, already parsed.
+    ///
+    /// Same as [`GetMessages::get_messages`], except that messages
+    /// are returned as [`ParsedMessage`]s, so that callers do not
+    /// need to parse them again.
+    async fn get_parsed_messages(&self, folder: &str, id: &Id) -> AnyResult<Vec<ParsedMessage>> {
+        Ok(self.get_messages(folder, id).await?.to_parsed()?)
+    }
 }
 
 /// Default get messages backend feature.
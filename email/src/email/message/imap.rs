@@ -1,5 +1,7 @@
+use std::num::NonZeroU32;
+
 use imap_client::imap_next::imap_types::fetch::{
-    MacroOrMessageDataItemNames, MessageDataItem, MessageDataItemName,
+    MacroOrMessageDataItemNames, MessageDataItem, MessageDataItemName, Section,
 };
 use once_cell::sync::Lazy;
 
@@ -26,6 +28,61 @@
     }])
 });
 
+/// Build the IMAP fetch items needed to peek the headers of
+/// messages, plus at most the first `max_body_size` bytes of their
+/// body (`BODY.PEEK[HEADER] BODY.PEEK[TEXT]<0.max_body_size>`).
+pub fn peek_partial_messages_items(
+    max_body_size: NonZeroU32,
+) -> MacroOrMessageDataItemNames<'static> {
+    MacroOrMessageDataItemNames::MessageDataItemNames(vec![
+        MessageDataItemName::BodyExt {
+            section: Some(Section::Header(None)),
+            partial: None,
+            peek: true,
+        },
+        peek_partial_body_item(Some(Section::Text(None)), 0, max_body_size),
+    ])
+}
+
+/// Build the IMAP fetch item needed to peek at most `length` bytes of
+/// the given section of messages, starting at `offset`
+/// (`BODY.PEEK[section]<offset.length>`).
+pub fn peek_partial_body_item(
+    section: Option<Section<'static>>,
+    offset: u32,
+    length: NonZeroU32,
+) -> MessageDataItemName<'static> {
+    MessageDataItemName::BodyExt {
+        section,
+        partial: Some((offset, length)),
+        peek: true,
+    }
+}
+
+/// Join the header and the partial text fetched with
+/// [`peek_partial_messages_items`] into one raw message.
+pub fn join_partial_message(items: &[MessageDataItem]) -> Vec<u8> {
+    let mut header = Vec::new();
+    let mut text = Vec::new();
+
+    for item in items {
+        if let MessageDataItem::BodyExt { section, data, .. } = item {
+            let Some(data) = data.0.as_ref() else {
+                continue;
+            };
+
+            match section {
+                Some(Section::Header(None)) => header.extend_from_slice(data.as_ref()),
+                Some(Section::Text(None)) => text.extend_from_slice(data.as_ref()),
+                _ => (),
+            }
+        }
+    }
+
+    header.extend(text);
+    header
+}
+
 impl<'a> TryFrom<&'a [MessageDataItem<'_>]> for Message<'a> {
     type Error = Error;
 
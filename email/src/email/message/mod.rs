@@ -15,12 +15,15 @@
 #[cfg(feature = "imap")]
 pub mod imap;
 pub mod r#move;
+pub mod parsed;
 pub mod peek;
 pub mod remove;
 pub mod send;
 #[cfg(feature = "sync")]
 pub mod sync;
 pub mod template;
+pub mod unsubscribe;
+pub mod walker;
 
 use std::{
     borrow::Cow,
@@ -43,9 +46,11 @@
 
 use self::{
     attachment::Attachment,
+    parsed::ParsedMessage,
     template::{
         forward::ForwardTemplateBuilder, new::NewTemplateBuilder, reply::ReplyTemplateBuilder,
     },
+    unsubscribe::ListUnsubscribe,
 };
 use crate::{account::config::AccountConfig, email::error::Error};
 
@@ -248,6 +253,19 @@ pub fn attachments(&self) -> Result<Vec<Attachment>, Error> {
             .collect())
     }
 
+    /// Returns the mailing list unsubscribe methods of the message,
+    /// if any.
+    pub fn list_unsubscribe(&self) -> Result<Option<ListUnsubscribe>, Error> {
+        Ok(ListUnsubscribe::from_mail_parser(self.parsed()?))
+    }
+
+    /// Returns the owned parsed version of the message, containing
+    /// its headers, its bodies and its attachments.
+    pub fn to_parsed(&self) -> Result<ParsedMessage, Error> {
+        let attachments = self.attachments()?;
+        Ok(ParsedMessage::from_mail_parser(self.parsed()?, attachments))
+    }
+
     /// Creates a new template builder from an account configuration.
     pub fn new_tpl_builder(config: Arc<AccountConfig>) -> NewTemplateBuilder {
         NewTemplateBuilder::new(config)
@@ -330,8 +348,7 @@ enum RawMessages {
     Imap(Vec<Vec1<MessageDataItem<'static>>>),
     #[cfg(feature = "maildir")]
     MailEntries(Vec<MaildirEntry>),
-    #[cfg(feature = "notmuch")]
-    Notmuch(Vec<Vec<u8>>),
+    Raw(Vec<Vec<u8>>),
     #[allow(dead_code)]
     None,
 }
@@ -361,8 +378,7 @@ fn emails_builder<'a>(raw: &'a mut RawMessages) -> Vec<Message<'a>> {
                 .collect(),
             #[cfg(feature = "maildir")]
             RawMessages::MailEntries(entries) => entries.iter_mut().map(Message::from).collect(),
-            #[cfg(feature = "notmuch")]
-            RawMessages::Notmuch(raw) => raw
+            RawMessages::Raw(raw) => raw
                 .iter()
                 .map(|raw| Message::from(raw.as_slice()))
                 .collect(),
@@ -377,6 +393,15 @@ pub fn first(&self) -> Option<&Message> {
     pub fn to_vec(&self) -> Vec<&Message> {
         self.borrow_emails().iter().collect()
     }
+
+    /// Returns the owned parsed version of all the messages. See
+    /// [`Message::to_parsed`].
+    pub fn to_parsed(&self) -> Result<Vec<ParsedMessage>, Error> {
+        self.borrow_emails()
+            .iter()
+            .map(Message::to_parsed)
+            .collect()
+    }
 }
 
 #[cfg(feature = "imap")]
@@ -407,11 +432,10 @@ fn try_from(entries: Vec<MaildirEntry>) -> Result<Self, Error> {
     }
 }
 
-#[cfg(feature = "notmuch")]
 impl From<Vec<Vec<u8>>> for Messages {
     fn from(raw: Vec<Vec<u8>>) -> Self {
         MessagesBuilder {
-            raw: RawMessages::Notmuch(raw),
+            raw: RawMessages::Raw(raw),
             emails_builder: Messages::emails_builder,
         }
         .build()
@@ -430,6 +454,50 @@ mod tests {
         template::Template,
     };
 
+    #[test]
+    fn to_parsed() {
+        let email = Message::from(concat_line!(
+            "From: from@localhost",
+            "To: to@localhost",
+            "Subject: =?utf-8?q?Caf=C3=A9?=",
+            " and more",
+            "Content-Type: multipart/mixed; boundary=\"mixed\"",
+            "",
+            "--mixed",
+            "Content-Type: multipart/alternative; boundary=\"alt\"",
+            "",
+            "--alt",
+            "Content-Type: text/plain",
+            "",
+            "Hello!",
+            "--alt",
+            "Content-Type: text/html",
+            "",
+            "<p>Hello!</p>",
+            "--alt--",
+            "--mixed",
+            "Content-Type: application/octet-stream",
+            "Content-Disposition: attachment; filename=\"data.bin\"",
+            "",
+            "data",
+            "--mixed--",
+        ));
+
+        let parsed = email.to_parsed().unwrap();
+
+        assert_eq!(parsed.subject.as_deref(), Some("Café and more"));
+        assert_eq!(parsed.header("from"), Some("from@localhost"));
+        assert_eq!(
+            parsed.header("Subject"),
+            Some("=?utf-8?q?Caf=C3=A9?= and more")
+        );
+        assert_eq!(parsed.text_bodies, vec!["Hello!".to_owned()]);
+        assert_eq!(parsed.html_bodies, vec!["<p>Hello!</p>".to_owned()]);
+        assert_eq!(parsed.attachments.len(), 1);
+        assert_eq!(parsed.attachments[0].filename.as_deref(), Some("data.bin"));
+        assert_eq!(parsed.attachments[0].body, b"data");
+    }
+
     #[tokio::test]
     async fn to_read_tpl() {
         let config = AccountConfig::default();
@@ -1,8 +1,17 @@
+use std::fs;
+
 use async_trait::async_trait;
 use tracing::info;
 
 use super::MoveMessages;
-use crate::{email::error::Error, envelope::Id, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    envelope::Id,
+    flag::maildir::entry_letters_for,
+    maildir::{
+        entry_path, is_new_entry, keywords::MaildirKeywords, perms, Error, MaildirContextSync,
+    },
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct MoveMaildirMessages {
@@ -32,20 +41,25 @@ async fn move_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> An
         let from_mdir = ctx.get_maildir_from_folder_alias(from_folder)?;
         let to_mdir = ctx.get_maildir_from_folder_alias(to_folder)?;
 
+        let mut keywords = MaildirKeywords::load(to_mdir.path());
+
         id.iter()
-            .filter_map(|id| from_mdir.find(id).ok().flatten())
-            .try_for_each(|entry| {
-                entry.r#move(&to_mdir).map_err(|err| {
-                    Error::MoveMessagesMaildirError(
-                        err,
-                        from_folder.to_owned(),
-                        to_folder.to_owned(),
-                        entry.path().to_owned(),
-                    )
-                })?;
-                AnyResult::Ok(())
+            .filter_map(|id| from_mdir.find(&id).ok().flatten())
+            .try_for_each(|entry| -> AnyResult<()> {
+                let letters = entry_letters_for(&entry, &mut keywords);
+                let from = entry.path().to_owned();
+                let id = entry.id().map_err(Error::MaildirError)?;
+                let to = entry_path(&to_mdir, id, &letters, is_new_entry(&entry));
+
+                fs::rename(&from, &to)
+                    .map_err(|err| Error::MoveEntryError(err, from, to.clone()))?;
+                perms::set_message_mode(&ctx.maildir_config, &to)?;
+
+                Ok(())
             })?;
 
+        keywords.save()?;
+
         Ok(())
     }
 }
@@ -0,0 +1,115 @@
+//! Module dedicated to parsed email messages.
+//!
+//! This module contains an owned representation of a parsed
+//! message, for callers that need the content of the message without
+//! parsing it again.
+
+use mail_parser::PartType;
+
+use super::{attachment::Attachment, unsubscribe::ListUnsubscribe};
+
+/// The parsed email message.
+///
+/// Unlike [`super::Message`], which wraps the raw message and its
+/// [`mail_parser::Message`] (borrowing from it), the parsed message
+/// owns all its data: it can be freely stored or sent across
+/// threads.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParsedMessage {
+    /// The headers of the message, as name and raw value pairs, in
+    /// the order they appear in the message.
+    ///
+    /// Values are unfolded but not decoded, see
+    /// [`ParsedMessage::subject`] for a decoded header.
+    pub headers: Vec<(String, String)>,
+
+    /// The decoded subject of the message.
+    pub subject: Option<String>,
+
+    /// The text bodies of the message.
+    ///
+    /// HTML bodies are not converted to text: a message without text
+    /// part has no text body.
+    pub text_bodies: Vec<String>,
+
+    /// The HTML bodies of the message.
+    ///
+    /// Text bodies are not converted to HTML: a message without HTML
+    /// part has no HTML body.
+    pub html_bodies: Vec<String>,
+
+    /// The attachments of the message.
+    pub attachments: Vec<Attachment>,
+}
+
+impl ParsedMessage {
+    /// Get the raw value of the first header matching the given name
+    /// (case-insensitive).
+    pub fn header(&self, name: impl AsRef<str>) -> Option<&str> {
+        let name = name.as_ref();
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| val.as_str())
+    }
+
+    /// Get the mailing list unsubscribe methods of the message, if
+    /// any.
+    pub fn list_unsubscribe(&self) -> Option<ListUnsubscribe> {
+        let list_unsubscribe = self.header("List-Unsubscribe")?;
+        ListUnsubscribe::parse(list_unsubscribe, self.header("List-Unsubscribe-Post"))
+    }
+
+    pub(super) fn from_mail_parser(
+        msg: &mail_parser::Message,
+        attachments: Vec<Attachment>,
+    ) -> Self {
+        let raw = msg.raw_message();
+
+        let headers = msg
+            .headers()
+            .iter()
+            .map(|header| {
+                let val = raw
+                    .get(header.offset_start..header.offset_end)
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                let val = unfold(&val);
+                (header.name.as_str().to_owned(), val)
+            })
+            .collect();
+
+        let text_bodies = msg
+            .text_bodies()
+            .filter_map(|part| match &part.body {
+                PartType::Text(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let html_bodies = msg
+            .html_bodies()
+            .filter_map(|part| match &part.body {
+                PartType::Html(html) => Some(html.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            headers,
+            subject: msg.subject().map(ToOwned::to_owned),
+            text_bodies,
+            html_bodies,
+            attachments,
+        }
+    }
+}
+
+/// Unfold the given raw header value (RFC 5322 §2.2.3).
+fn unfold(val: &str) -> String {
+    val.split(['\r', '\n'])
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -1,10 +1,14 @@
+use std::num::NonZeroU32;
+
 use async_trait::async_trait;
-use imap_client::imap_next::imap_types::sequence::{Sequence, SequenceSet};
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::{Messages, PeekMessages};
-use crate::{envelope::Id, imap::ImapContext, AnyResult};
+use crate::{
+    envelope::Id,
+    imap::{ImapContext, ImapMailboxMode},
+    AnyResult,
+};
 
 #[derive(Clone, Debug)]
 pub struct PeekImapMessages {
@@ -34,22 +38,43 @@ async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
-
-        let uids: SequenceSet = match id {
-            Id::Single(id) => Sequence::try_from(id.as_str()).unwrap().into(),
-            Id::Multiple(ids) => ids
-                .iter()
-                .filter_map(|id| Sequence::try_from(id.as_str()).ok())
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap(),
-        };
-
-        client.select_mailbox(&folder_encoded).await?;
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
+
+        let uids = id.to_imap_sequence_set()?;
+
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadOnly)
+            .await?;
         let msgs = client.peek_messages(uids).await?;
 
         Ok(msgs)
     }
+
+    async fn peek_partial_messages(
+        &self,
+        folder: &str,
+        id: &Id,
+        max_body_size: usize,
+    ) -> AnyResult<Messages> {
+        info!("peeking partial imap messages {id} from folder {folder}");
+
+        let mut client = self.ctx.client().await;
+        let config = &client.account_config;
+
+        let folder = config.get_folder_alias(folder);
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
+
+        let uids = id.to_imap_sequence_set()?;
+        let max_body_size = u32::try_from(max_body_size).unwrap_or(u32::MAX);
+        let max_body_size = NonZeroU32::new(max_body_size).unwrap_or(NonZeroU32::MIN);
+
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadOnly)
+            .await?;
+        let msgs = client.peek_partial_messages(uids, max_body_size).await?;
+
+        Ok(msgs)
+    }
 }
@@ -20,4 +20,21 @@ pub trait PeekMessages: Send + Sync {
     /// automatically added to envelopes, see
     /// [`GetMessages`](super::get::GetMessages).
     async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages>;
+
+    /// Peek email messages from the given folder matching the given
+    /// ids, with their body truncated to at most `max_body_size`
+    /// bytes.
+    ///
+    /// Headers are always complete. This is useful when only the
+    /// beginning of the body is needed, for example to build
+    /// previews. The default implementation peeks whole messages.
+    async fn peek_partial_messages(
+        &self,
+        folder: &str,
+        id: &Id,
+        max_body_size: usize,
+    ) -> AnyResult<Messages> {
+        let _ = max_body_size;
+        self.peek_messages(folder, id).await
+    }
 }
@@ -37,10 +37,10 @@ async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
             .iter()
             .map(|ids| {
                 let path = db
-                    .find_message(ids)
+                    .find_message(&ids)
                     .map_err(Error::NotMuchFailure)?
                     .ok_or_else(|| {
-                        Error::FindEnvelopeEmptyNotmuchError(folder.to_owned(), ids.to_owned())
+                        Error::FindEnvelopeEmptyNotmuchError(folder.to_owned(), ids.to_string())
                     })?
                     .filename()
                     .to_owned();
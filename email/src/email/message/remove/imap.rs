@@ -1,10 +1,12 @@
 use async_trait::async_trait;
-use imap_client::imap_next::imap_types::sequence::{Sequence, SequenceSet};
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::RemoveMessages;
-use crate::{envelope::Id, imap::ImapContext, AnyResult};
+use crate::{
+    envelope::Id,
+    imap::{ImapContext, ImapMailboxMode},
+    AnyResult,
+};
 
 #[derive(Clone)]
 pub struct RemoveImapMessages {
@@ -34,20 +36,14 @@ async fn remove_messages(&self, folder: &str, id: &Id) -> AnyResult<()> {
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded from folder: {folder_encoded}");
-
-        let uids: SequenceSet = match id {
-            Id::Single(id) => Sequence::try_from(id.as_str()).unwrap().into(),
-            Id::Multiple(ids) => ids
-                .iter()
-                .filter_map(|id| Sequence::try_from(id.as_str()).ok())
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap(),
-        };
-
-        client.select_mailbox(&folder_encoded).await?;
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded from folder: {folder_encoded}");
+
+        let uids = id.to_imap_sequence_set()?;
+
+        client
+            .ensure_mailbox(&folder_encoded, ImapMailboxMode::ReadWrite)
+            .await?;
         client.add_deleted_flag(uids).await?;
 
         Ok(())
@@ -32,7 +32,7 @@ async fn remove_messages(&self, folder: &str, id: &Id) -> AnyResult<()> {
         let mdir = ctx.get_maildir_from_folder_alias(folder)?;
 
         id.iter()
-            .filter_map(|id| mdir.find(id).ok().flatten())
+            .filter_map(|id| mdir.find(&id).ok().flatten())
             .try_for_each(|entry| {
                 entry.remove().map_err(|err| {
                     Error::RemoveMaildirMessageError(err, folder.to_owned(), id.to_string())
@@ -0,0 +1,261 @@
+//! # Archiving sender
+//!
+//! Module dedicated to the archiving decorator of the [`SendMessage`]
+//! feature. The main structure of this module is
+//! [`SendAndArchiveSender`].
+
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use super::{SendMessage, SendMessageResults};
+use crate::{envelope::SingleId, flag::Flag, message::add::AddMessage, AnyBoxedError, AnyResult};
+
+/// The result of [`SendAndArchiveSender::send_and_archive`].
+///
+/// Both variants mean that the message has been sent.
+#[derive(Debug)]
+pub enum SendAndArchiveResult {
+    /// The message has been sent, then saved to the Sent folder
+    /// under the given id.
+    Archived(SingleId),
+
+    /// The message has been sent, but it could not be saved to the
+    /// Sent folder.
+    NotArchived(AnyBoxedError),
+}
+
+impl SendAndArchiveResult {
+    /// Return `true` if the message has been saved to the Sent
+    /// folder.
+    pub fn is_archived(&self) -> bool {
+        matches!(self, Self::Archived(_))
+    }
+}
+
+/// The [`SendMessage`] decorator saving a copy of sent messages.
+///
+/// Once the inner sender succeeds, the message is added to the given
+/// folder (usually the Sent folder, see
+/// [`crate::account::config::AccountConfig::resolve_folder`]) with
+/// the `\Seen` flag. Nothing is added when the message cannot be
+/// sent. A message that cannot be added is still sent: this partial
+/// success is surfaced by [`SendAndArchiveResult::NotArchived`],
+/// so that callers never send the message twice.
+pub struct SendAndArchiveSender {
+    sender: Box<dyn SendMessage>,
+    archiver: Box<dyn AddMessage>,
+    folder: String,
+}
+
+impl SendAndArchiveSender {
+    pub fn new(
+        sender: Box<dyn SendMessage>,
+        archiver: Box<dyn AddMessage>,
+        folder: impl ToString,
+    ) -> Self {
+        Self {
+            sender,
+            archiver,
+            folder: folder.to_string(),
+        }
+    }
+
+    pub fn new_boxed(
+        sender: Box<dyn SendMessage>,
+        archiver: Box<dyn AddMessage>,
+        folder: impl ToString,
+    ) -> Box<dyn SendMessage> {
+        Box::new(Self::new(sender, archiver, folder))
+    }
+
+    /// Send the given raw email message, then save a copy to the
+    /// folder.
+    ///
+    /// Fails only if the message cannot be sent.
+    pub async fn send_and_archive(&self, msg: &[u8]) -> AnyResult<SendAndArchiveResult> {
+        self.sender.send_message(msg).await?;
+        Ok(self.archive(msg).await)
+    }
+
+    /// Save a copy of the given sent raw email message to the
+    /// folder.
+    async fn archive(&self, msg: &[u8]) -> SendAndArchiveResult {
+        let res = self
+            .archiver
+            .add_message_with_flag(&self.folder, msg, Flag::Seen)
+            .await;
+
+        match res {
+            Ok(id) => {
+                debug!("saved copy of sent message to {} as {id:?}", self.folder);
+                SendAndArchiveResult::Archived(id)
+            }
+            Err(err) => {
+                warn!(
+                    "message sent, but cannot save copy to {}: {err}",
+                    self.folder
+                );
+                debug!("{err:?}");
+                SendAndArchiveResult::NotArchived(err)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SendMessage for SendAndArchiveSender {
+    /// Send the given raw email message, then save a copy to the
+    /// folder.
+    ///
+    /// The failure to save the copy is only logged, see
+    /// [`SendAndArchiveSender::send_and_archive`] to handle it.
+    async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
+        self.send_and_archive(msg).await?;
+        Ok(())
+    }
+
+    async fn send_message_with_results(&self, msg: &[u8]) -> AnyResult<SendMessageResults> {
+        let results = self.sender.send_message_with_results(msg).await?;
+        self.archive(msg).await;
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+    use thiserror::Error;
+
+    use super::{SendAndArchiveResult, SendAndArchiveSender};
+    use crate::{
+        envelope::SingleId,
+        flag::{Flag, Flags},
+        message::{add::AddMessage, send::SendMessage},
+        AnyError, AnyResult, ErrorKind,
+    };
+
+    #[derive(Debug, Error)]
+    #[error("test error")]
+    struct TestError;
+
+    impl AnyError for TestError {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Permanent
+        }
+    }
+
+    type Sent = Arc<Mutex<Vec<Vec<u8>>>>;
+    type Added = Arc<Mutex<Vec<(String, Vec<u8>, Flags)>>>;
+
+    struct MockSender {
+        fail: bool,
+        sent: Sent,
+    }
+
+    #[async_trait]
+    impl SendMessage for MockSender {
+        async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
+            if self.fail {
+                return Err(Box::new(TestError));
+            }
+
+            self.sent.lock().unwrap().push(msg.to_vec());
+            Ok(())
+        }
+    }
+
+    struct MockArchiver {
+        fail: bool,
+        added: Added,
+    }
+
+    #[async_trait]
+    impl AddMessage for MockArchiver {
+        async fn add_message_with_flags(
+            &self,
+            folder: &str,
+            msg: &[u8],
+            flags: &Flags,
+        ) -> AnyResult<SingleId> {
+            if self.fail {
+                return Err(Box::new(TestError));
+            }
+
+            let mut added = self.added.lock().unwrap();
+            added.push((folder.to_owned(), msg.to_vec(), flags.clone()));
+            Ok(SingleId::from(added.len()))
+        }
+    }
+
+    fn sender(send_fails: bool, add_fails: bool) -> (SendAndArchiveSender, Sent, Added) {
+        let sent = Sent::default();
+        let added = Added::default();
+
+        let sender = SendAndArchiveSender::new(
+            Box::new(MockSender {
+                fail: send_fails,
+                sent: sent.clone(),
+            }),
+            Box::new(MockArchiver {
+                fail: add_fails,
+                added: added.clone(),
+            }),
+            "Sent",
+        );
+
+        (sender, sent, added)
+    }
+
+    #[tokio::test]
+    async fn send_then_archive() {
+        let (sender, sent, added) = sender(false, false);
+
+        let res = sender.send_and_archive(b"msg").await.unwrap();
+
+        assert!(matches!(res, SendAndArchiveResult::Archived(id) if id.as_str() == "1"));
+        assert_eq!(*sent.lock().unwrap(), vec![b"msg".to_vec()]);
+        assert_eq!(
+            *added.lock().unwrap(),
+            vec![(
+                "Sent".to_owned(),
+                b"msg".to_vec(),
+                Flags::from_iter([Flag::Seen])
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn do_not_archive_unsent_message() {
+        let (sender, sent, added) = sender(true, false);
+
+        assert!(sender.send_and_archive(b"msg").await.is_err());
+        assert!(sender.send_message(b"msg").await.is_err());
+
+        assert!(sent.lock().unwrap().is_empty());
+        assert!(added.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn keep_sent_message_when_archive_fails() {
+        let (sender, sent, added) = sender(false, true);
+
+        let res = sender.send_and_archive(b"msg").await.unwrap();
+        assert!(!res.is_archived());
+        assert!(matches!(res, SendAndArchiveResult::NotArchived(_)));
+
+        // the message is sent, so the failure must not be retried
+        sender.send_message(b"msg").await.unwrap();
+
+        assert_eq!(sent.lock().unwrap().len(), 2);
+        assert!(added.lock().unwrap().is_empty());
+    }
+}
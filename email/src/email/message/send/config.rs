@@ -17,4 +17,11 @@ pub struct MessageSendConfig {
     /// (stdin) and returns the modified raw message to the standard
     /// output (stdout).
     pub pre_hook: Option<Command>,
+
+    /// Should normalize line endings of the message being sent.
+    ///
+    /// When enabled, lone `\n` are converted to `\r\n`, as required
+    /// by SMTP. Defaults to `true`, disable it for pipelines that need
+    /// the raw bytes to be sent untouched.
+    pub normalize_line_endings: Option<bool>,
 }
@@ -1,18 +1,38 @@
+pub mod archive;
 pub mod config;
+pub mod retry;
 #[cfg(feature = "sendmail")]
 pub mod sendmail;
 #[cfg(feature = "smtp")]
 pub mod smtp;
 
+use std::borrow::Cow;
+
 use async_trait::async_trait;
 
 use super::add::AddMessage;
-use crate::{account::config::HasAccountConfig, flag::Flag, folder::SENT, AnyResult};
+use crate::{account::config::HasAccountConfig, flag::Flag, folder::FolderKind, AnyResult};
+
+/// The result of a message sending, per recipient.
+///
+/// Each recipient email comes with the result of its delivery: the
+/// message is delivered to the recipients without error only.
+pub type SendMessageResults = Vec<(String, AnyResult<()>)>;
 
 #[async_trait]
 pub trait SendMessage: Send + Sync {
     /// Send the given raw email message.
     async fn send_message(&self, msg: &[u8]) -> AnyResult<()>;
+
+    /// Send the given raw email message, and get the result of the
+    /// delivery per recipient.
+    ///
+    /// Senders that cannot tell recipients apart return no result,
+    /// which means that all recipients accepted the message.
+    async fn send_message_with_results(&self, msg: &[u8]) -> AnyResult<SendMessageResults> {
+        self.send_message(msg).await?;
+        Ok(Vec::new())
+    }
 }
 
 #[async_trait]
@@ -23,7 +43,8 @@ async fn send_message_then_save_copy(&self, msg: &[u8]) -> AnyResult<()> {
         self.send_message(msg).await?;
 
         if self.account_config().should_save_copy_sent_message() {
-            self.add_message_with_flag(SENT, msg, Flag::Seen).await?;
+            let folder = self.account_config().resolve_folder(FolderKind::Sent);
+            self.add_message_with_flag(&folder, msg, Flag::Seen).await?;
         }
 
         Ok(())
@@ -31,3 +52,54 @@ async fn send_message_then_save_copy(&self, msg: &[u8]) -> AnyResult<()> {
 }
 
 impl<T: HasAccountConfig + AddMessage + SendMessage> SendMessageThenSaveCopy for T {}
+
+/// Normalize line endings of the given raw message to CRLF.
+///
+/// Lone `\n` are converted to `\r\n`, existing `\r\n` are kept as
+/// is. The message is borrowed back when it is already normalized.
+pub fn normalize_line_endings(msg: &[u8]) -> Cow<'_, [u8]> {
+    let is_lone_lf = |i: usize| msg[i] == b'\n' && (i == 0 || msg[i - 1] != b'\r');
+    let lone_lfs = (0..msg.len()).filter(|&i| is_lone_lf(i)).count();
+
+    if lone_lfs == 0 {
+        return Cow::Borrowed(msg);
+    }
+
+    let mut normalized = Vec::with_capacity(msg.len() + lone_lfs);
+
+    for (i, &byte) in msg.iter().enumerate() {
+        if is_lone_lf(i) {
+            normalized.push(b'\r');
+        }
+        normalized.push(byte);
+    }
+
+    Cow::Owned(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::normalize_line_endings;
+
+    #[test]
+    fn normalize_mixed_line_endings() {
+        let msg = b"From: alice@localhost\nTo: bob@localhost\r\nSubject: test\n\nline\r\n\nend\n";
+        let expected =
+            b"From: alice@localhost\r\nTo: bob@localhost\r\nSubject: test\r\n\r\nline\r\n\r\nend\r\n";
+
+        assert_eq!(normalize_line_endings(msg).as_ref(), expected);
+        assert_eq!(normalize_line_endings(b"\n").as_ref(), b"\r\n");
+    }
+
+    #[test]
+    fn keep_normalized_message_unchanged() {
+        let msg = b"From: alice@localhost\r\nSubject: test\r\n\r\nbody\r\n";
+
+        let normalized = normalize_line_endings(msg);
+
+        assert!(matches!(normalized, Cow::Borrowed(_)));
+        assert_eq!(normalized.as_ref(), msg);
+    }
+}
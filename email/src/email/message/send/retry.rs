@@ -0,0 +1,247 @@
+//! # Retrying sender
+//!
+//! Module dedicated to the retrying decorator of the [`SendMessage`]
+//! feature. The main structure of this module is [`RetryingSender`].
+
+use std::{cmp, time::Duration};
+
+use async_trait::async_trait;
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+use super::{SendMessage, SendMessageResults};
+use crate::AnyResult;
+
+/// The [`SendMessage`] decorator retrying transient failures.
+///
+/// Only errors classified as [`crate::ErrorKind::Transient`] are
+/// retried (broken connection, timeout, SMTP 4xx reply…). Permanent
+/// and authentication errors are returned straight away. The delay
+/// between two attempts starts at the initial delay, then doubles
+/// after each attempt until it reaches the maximum delay.
+pub struct RetryingSender {
+    inner: Box<dyn SendMessage>,
+    max_attempts: usize,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryingSender {
+    /// The default maximum number of attempts.
+    pub const DEFAULT_MAX_ATTEMPTS: usize = 3;
+
+    /// The default delay before the first retry.
+    pub const DEFAULT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+    /// The default maximum delay between two attempts.
+    pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+    pub fn new(inner: Box<dyn SendMessage>) -> Self {
+        Self {
+            inner,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            initial_delay: Self::DEFAULT_INITIAL_DELAY,
+            max_delay: Self::DEFAULT_MAX_DELAY,
+        }
+    }
+
+    pub fn new_boxed(inner: Box<dyn SendMessage>) -> Box<dyn SendMessage> {
+        Box::new(Self::new(inner))
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    ///
+    /// A value of 0 is treated as 1.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Set the maximum delay between two attempts.
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+}
+
+#[async_trait]
+impl SendMessage for RetryingSender {
+    async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
+        self.send_message_with_results(msg).await?;
+        Ok(())
+    }
+
+    async fn send_message_with_results(&self, msg: &[u8]) -> AnyResult<SendMessageResults> {
+        let max_attempts = cmp::max(self.max_attempts, 1);
+        let mut delay = cmp::min(self.initial_delay, self.max_delay);
+        let mut attempt = 1;
+
+        loop {
+            match self.inner.send_message_with_results(msg).await {
+                Ok(results) => break Ok(results),
+                Err(err) if attempt < max_attempts && err.kind().is_transient() => {
+                    warn!(attempt, "cannot send message, retrying in {delay:?}: {err}");
+                    debug!("{err:?}");
+
+                    sleep(delay).await;
+                    delay = cmp::min(delay * 2, self.max_delay);
+                    attempt += 1;
+                }
+                Err(err) => break Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        any::Any,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use async_trait::async_trait;
+    use thiserror::Error;
+
+    use super::{RetryingSender, SendMessage, SendMessageResults};
+    use crate::{AnyError, AnyResult, ErrorKind};
+
+    #[derive(Debug, Error)]
+    #[error("test error")]
+    struct TestError(ErrorKind);
+
+    impl AnyError for TestError {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            self.0
+        }
+    }
+
+    /// Sender failing with the given errors, then succeeding.
+    struct FailingSender {
+        errors: Mutex<Vec<ErrorKind>>,
+        calls: Arc<Mutex<Vec<Instant>>>,
+    }
+
+    impl FailingSender {
+        fn new_boxed(
+            errors: impl IntoIterator<Item = ErrorKind>,
+            calls: Arc<Mutex<Vec<Instant>>>,
+        ) -> Box<dyn SendMessage> {
+            let mut errors: Vec<_> = errors.into_iter().collect();
+            errors.reverse();
+
+            Box::new(Self {
+                errors: Mutex::new(errors),
+                calls,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl SendMessage for FailingSender {
+        async fn send_message(&self, _msg: &[u8]) -> AnyResult<()> {
+            self.calls.lock().unwrap().push(Instant::now());
+
+            match self.errors.lock().unwrap().pop() {
+                Some(kind) => Err(Box::new(TestError(kind))),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Sender rejecting the second recipient.
+    struct PartialSender;
+
+    #[async_trait]
+    impl SendMessage for PartialSender {
+        async fn send_message(&self, _msg: &[u8]) -> AnyResult<()> {
+            Ok(())
+        }
+
+        async fn send_message_with_results(&self, _msg: &[u8]) -> AnyResult<SendMessageResults> {
+            Ok(vec![
+                ("alice@localhost".into(), Ok(())),
+                (
+                    "bob@localhost".into(),
+                    Err(Box::new(TestError(ErrorKind::Permanent))),
+                ),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn forward_recipient_results() {
+        let sender = RetryingSender::new(Box::new(PartialSender));
+        let results = sender.send_message_with_results(b"").await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "alice@localhost");
+        assert!(results[0].1.is_ok());
+        assert_eq!(results[1].0, "bob@localhost");
+        assert!(results[1].1.is_err());
+
+        // senders that cannot tell recipients apart return no result
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sender = RetryingSender::new(FailingSender::new_boxed([], calls));
+        assert!(sender
+            .send_message_with_results(b"")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_transient_errors_with_backoff() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let inner = FailingSender::new_boxed([ErrorKind::Transient; 2], calls.clone());
+        let sender = RetryingSender::new(inner)
+            .with_initial_delay(Duration::from_millis(10))
+            .with_max_delay(Duration::from_millis(100));
+
+        sender.send_message(b"").await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 3);
+        assert!(calls[1] - calls[0] >= Duration::from_millis(10));
+        assert!(calls[2] - calls[1] >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn stop_after_max_attempts() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let inner = FailingSender::new_boxed([ErrorKind::Transient; 5], calls.clone());
+        let sender = RetryingSender::new(inner)
+            .with_max_attempts(3)
+            .with_initial_delay(Duration::from_millis(1));
+
+        let err = sender.send_message(b"").await.unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::Transient);
+        assert_eq!(calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn do_not_retry_auth_nor_permanent_errors() {
+        for kind in [ErrorKind::Auth, ErrorKind::Permanent] {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let inner = FailingSender::new_boxed([kind], calls.clone());
+            let sender = RetryingSender::new(inner).with_initial_delay(Duration::from_millis(1));
+
+            let err = sender.send_message(b"").await.unwrap_err();
+
+            assert_eq!(err.kind(), kind);
+            assert_eq!(calls.lock().unwrap().len(), 1);
+        }
+    }
+}
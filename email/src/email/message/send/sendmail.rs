@@ -1,8 +1,9 @@
+use std::borrow::Cow;
+
 use async_trait::async_trait;
-use mail_parser::MessageParser;
 use tracing::{debug, info};
 
-use super::SendMessage;
+use super::{normalize_line_endings, SendMessage};
 use crate::{email::error::Error, sendmail::SendmailContextSync, AnyResult};
 
 #[derive(Clone)]
@@ -29,20 +30,30 @@ impl SendMessage for SendSendmailMessage {
     async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
         info!("sending sendmail message");
 
-        let buffer: Vec<u8>;
-        let mut msg = MessageParser::new().parse(msg).unwrap_or_else(|| {
-            debug!("cannot parse raw message");
-            Default::default()
-        });
+        // the message does not need to be parsed, it is sent as is
+        // (apart from line endings normalization)
+        let mut msg = Cow::Borrowed(msg);
+
+        if self.ctx.sendmail_config.is_dry_run() {
+            let mut cmds = Vec::new();
+
+            if let Some(cmd) = self.ctx.account_config.find_message_pre_send_hook() {
+                cmds.push(cmd.resolve());
+            }
+
+            cmds.push(self.ctx.sendmail_config.cmd().resolve());
+
+            for cmd in &cmds {
+                info!("would run: {cmd}");
+            }
+
+            return Err(Error::SendmailDryRunError(cmds).into());
+        }
 
         if let Some(cmd) = self.ctx.account_config.find_message_pre_send_hook() {
-            match cmd.run_with(msg.raw_message()).await {
+            match cmd.run_with(msg.as_ref()).await {
                 Ok(res) => {
-                    buffer = res.into();
-                    msg = MessageParser::new().parse(&buffer).unwrap_or_else(|| {
-                        debug!("cannot parse raw message after pre-send hook");
-                        Default::default()
-                    });
+                    msg = Cow::Owned(res.into());
                 }
                 Err(_err) => {
                     debug!("cannot execute pre-send hook: {_err}");
@@ -51,13 +62,108 @@ async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
             }
         };
 
+        if self
+            .ctx
+            .account_config
+            .should_normalize_sent_message_line_endings()
+        {
+            let normalized = match normalize_line_endings(&msg) {
+                Cow::Owned(normalized) => Some(normalized),
+                Cow::Borrowed(_) => None,
+            };
+
+            if let Some(normalized) = normalized {
+                msg = Cow::Owned(normalized);
+            }
+        }
+
         self.ctx
             .sendmail_config
             .cmd()
-            .run_with(msg.raw_message())
+            .run_with(msg.as_ref())
             .await
             .map_err(Error::RunSendmailCommandError)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use process::Command;
+
+    use super::{SendMessage, SendSendmailMessage};
+    use crate::{
+        account::config::AccountConfig,
+        email::error::Error,
+        message::{config::MessageConfig, send::config::MessageSendConfig},
+        sendmail::{config::SendmailConfig, SendmailContext},
+    };
+
+    #[tokio::test]
+    async fn send_unparsable_message_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+
+        let sendmail_config = Arc::new(SendmailConfig {
+            cmd: Some(Command::new(format!("cat > {}", out.display()))),
+            ..Default::default()
+        });
+        let ctx = SendmailContext::new(Default::default(), sendmail_config);
+
+        let msg = b"\xff\xfe:\r\n\x00 not really a message";
+        SendSendmailMessage::new(&ctx)
+            .send_message(msg)
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(&out).unwrap(), msg);
+    }
+
+    #[tokio::test]
+    async fn send_message_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let hook = dir.path().join("hook");
+        let out = dir.path().join("out");
+
+        let account_config = Arc::new(AccountConfig {
+            message: Some(MessageConfig {
+                send: Some(MessageSendConfig {
+                    pre_hook: Some(Command::new(format!("tee {}", hook.display()))),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        let sendmail_config = Arc::new(SendmailConfig {
+            cmd: Some(Command::new(format!("cat > {}", out.display()))),
+            dry_run: Some(true),
+        });
+
+        let ctx = SendmailContext::new(account_config, sendmail_config);
+
+        let err = SendSendmailMessage::new(&ctx)
+            .send_message(b"Subject: dry-run\r\n\r\nHello!")
+            .await
+            .unwrap_err();
+
+        match err.as_any().downcast_ref() {
+            Some(Error::SendmailDryRunError(cmds)) => {
+                let cmds: Vec<_> = cmds.iter().map(ToString::to_string).collect();
+                let expected = [
+                    format!("tee {}", hook.display()),
+                    format!("cat > {}", out.display()),
+                ];
+                assert_eq!(cmds, expected);
+            }
+            err => panic!("unexpected error: {err:?}"),
+        }
+
+        assert!(!hook.exists());
+        assert!(!out.exists());
+    }
+}
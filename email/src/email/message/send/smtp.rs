@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use tracing::info;
 
-use super::SendMessage;
+use super::{SendMessage, SendMessageResults};
 use crate::{smtp::SmtpContextSync, AnyResult};
 
 #[derive(Clone)]
@@ -25,12 +25,29 @@ pub fn some_new_boxed(ctx: &SmtpContextSync) -> Option<Box<dyn SendMessage>> {
 
 #[async_trait]
 impl SendMessage for SendSmtpMessage {
+    /// Send the given raw email message.
+    ///
+    /// A message delivered to some of its recipients only is
+    /// considered sent, so that it is never sent twice to the
+    /// accepted recipients. See
+    /// [`SendMessage::send_message_with_results`] for the results
+    /// per recipient.
     async fn send_message(&self, msg: &[u8]) -> AnyResult<()> {
+        self.send_message_with_results(msg).await?;
+        Ok(())
+    }
+
+    async fn send_message_with_results(&self, msg: &[u8]) -> AnyResult<SendMessageResults> {
         info!("sending smtp message");
 
         let mut ctx = self.ctx.lock().await;
-        ctx.send(msg).await?;
+        let results = ctx.send(msg).await?;
 
-        Ok(())
+        let results = results
+            .into_iter()
+            .map(|(rcpt, res)| (rcpt, res.map_err(Into::into)))
+            .collect();
+
+        Ok(results)
     }
 }
@@ -4,6 +4,7 @@
 //! which helps you to build template in order to reply to a message.
 
 pub mod config;
+pub mod recipients;
 
 use std::{borrow::Cow, collections::HashSet, sync::Arc};
 
@@ -16,11 +17,15 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use self::config::{ReplyTemplatePostingStyle, ReplyTemplateSignatureStyle};
+use self::{
+    config::{ReplyTemplatePostingStyle, ReplyTemplateSignatureStyle},
+    recipients::ReplyAllRecipients,
+};
 use super::{Template, TemplateBody, TemplateCursor};
 use crate::{
     account::config::AccountConfig,
     email::{address, error::Error},
+    envelope::address::Address as EnvelopeAddress,
     message::Message,
 };
 
@@ -38,6 +43,16 @@ fn trim_prefix(subject: &str) -> &str {
     }
 }
 
+/// Convert the given envelope addresses into builder addresses,
+/// skipping no-reply ones.
+fn to_builder_addresses(addrs: Vec<EnvelopeAddress>) -> Vec<Address<'static>> {
+    addrs
+        .into_iter()
+        .filter(|addr| !address::is_no_reply(&addr.addr))
+        .map(|addr| Address::new_address(addr.name, addr.addr))
+        .collect()
+}
+
 /// The message reply template builder.
 ///
 /// This builder helps you to create a template in order to reply to
@@ -264,39 +279,40 @@ pub async fn build(self) -> Result<Template, Error> {
         builder = builder.from(self.config.as_ref());
         cursor.row += 1;
 
-        // To
-
-        let mut curr_rcpts = Vec::<Address>::default();
-        let mut all_rcpts_email = HashSet::<Cow<str>>::default();
-        all_rcpts_email.insert(me.address.clone().unwrap());
-
-        if !address::is_empty(reply_to) {
-            address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &reply_to);
-        } else {
-            let from = if !address::is_empty(from) {
-                from
-            } else {
-                sender
-            };
-            address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &from);
-            address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &to);
-        }
-
-        builder = builder.to(Address::new_list(curr_rcpts.clone()));
-        cursor.row += 1;
-
-        // Cc
+        // To and Cc
 
         if self.reply_all {
-            let cc = parsed.header("Cc").unwrap_or(&HeaderValue::Empty);
+            let rcpts = ReplyAllRecipients::new(parsed, self.config.own_emails());
 
-            curr_rcpts.clear();
-            address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &cc);
+            builder = builder.to(Address::new_list(to_builder_addresses(rcpts.to)));
+            cursor.row += 1;
+
+            let cc = to_builder_addresses(rcpts.cc);
 
-            if !curr_rcpts.is_empty() {
-                builder = builder.cc(curr_rcpts);
+            if !cc.is_empty() {
+                builder = builder.cc(cc);
                 cursor.row += 1;
             }
+        } else {
+            let mut curr_rcpts = Vec::<Address>::default();
+            let mut all_rcpts_email = HashSet::<Cow<str>>::default();
+            all_rcpts_email.insert(me.address.clone().unwrap());
+            all_rcpts_email.extend(self.config.own_emails().map(Cow::Borrowed));
+
+            if !address::is_empty(reply_to) {
+                address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &reply_to);
+            } else {
+                let from = if !address::is_empty(from) {
+                    from
+                } else {
+                    sender
+                };
+                address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &from);
+                address::push_builder_address(&mut all_rcpts_email, &mut curr_rcpts, &to);
+            }
+
+            builder = builder.to(Address::new_list(curr_rcpts));
+            cursor.row += 1;
         }
 
         // Subject
@@ -486,6 +502,46 @@ async fn default() {
         );
     }
 
+    #[tokio::test]
+    async fn reply_all_excluding_aliases() {
+        let config = Arc::new(AccountConfig {
+            display_name: Some("Me".into()),
+            email: "me@localhost".into(),
+            email_aliases: Some(vec!["me@alias.localhost".into()]),
+            ..Default::default()
+        });
+
+        let msg = &Message::from(concat_line!(
+            "Content-Type: text/plain",
+            "From: sender@localhost",
+            "To: ME@localhost, bob@localhost",
+            "Cc: Me <me@alias.localhost>, carol@localhost",
+            "Subject: subject",
+            "",
+            "",
+            "",
+        ));
+
+        assert_eq!(
+            ReplyTemplateBuilder::new(msg, config)
+                .with_reply_all(true)
+                .build()
+                .await
+                .unwrap(),
+            Template::new_with_cursor(
+                concat_line!(
+                    "From: Me <me@localhost>",
+                    "To: sender@localhost",
+                    "Cc: bob@localhost, carol@localhost",
+                    "Subject: Re: subject",
+                    "",
+                    "", // cursor here
+                ),
+                (6, 0),
+            ),
+        );
+    }
+
     #[tokio::test]
     async fn with_body() {
         let config = Arc::new(AccountConfig {
@@ -1184,8 +1240,8 @@ async fn reply_mailing_list_using_sender() {
         let expected_tpl = Template::new_with_cursor(
             concat_line!(
                 "From: me@localhost",
-                "To: sender@localhost, mlist@localhost, other@localhost",
-                "Cc: cc@localhost, cc2@localhost",
+                "To: sender@localhost",
+                "Cc: mlist@localhost, other@localhost, cc@localhost, cc2@localhost",
                 "Subject: Re: subject",
                 "",
                 "",
@@ -1251,8 +1307,8 @@ async fn reply_mailing_list_using_from() {
         let expected_tpl = Template::new_with_cursor(
             concat_line!(
                 "From: me@localhost",
-                "To: from@localhost, mlist@localhost, other@localhost",
-                "Cc: cc@localhost, cc2@localhost",
+                "To: from@localhost",
+                "Cc: mlist@localhost, other@localhost, cc@localhost, cc2@localhost",
                 "Subject: Re: subject",
                 "",
                 "",
@@ -1320,7 +1376,7 @@ async fn reply_mailing_list_using_reply_to() {
             concat_line!(
                 "From: me@localhost",
                 "To: reply-to@localhost",
-                "Cc: from@localhost, cc@localhost, cc2@localhost",
+                "Cc: mlist@localhost, other@localhost, from@localhost, cc@localhost, cc2@localhost",
                 "Subject: Re: subject",
                 "",
                 "",
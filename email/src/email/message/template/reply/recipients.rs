@@ -0,0 +1,233 @@
+//! # Reply recipients
+//!
+//! Module dedicated to the computation of reply recipients. The main
+//! structure of this module is [`ReplyAllRecipients`].
+
+use std::collections::HashSet;
+
+use mail_parser::{Address as ParsedAddress, HeaderValue, Message, MessageParser};
+
+use crate::envelope::address::Address;
+
+/// The recipients of a reply to all.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReplyAllRecipients {
+    /// The main recipients of the reply.
+    pub to: Vec<Address>,
+
+    /// The carbon copy recipients of the reply.
+    pub cc: Vec<Address>,
+}
+
+impl ReplyAllRecipients {
+    /// Compute the recipients of a reply to all to the given original
+    /// message.
+    ///
+    /// When the original message contains a `Mail-Followup-To`
+    /// header, its addresses are the only recipients of the reply.
+    /// Otherwise the author (`Reply-To`, or `From`, or `Sender`) goes
+    /// to `To`, while the original `To` and `Cc` go to `Cc`, and so
+    /// does the mailing list posting address (`List-Post`). When the
+    /// author is one of the given own email addresses, the original
+    /// `To` goes to `To` instead.
+    ///
+    /// Own email addresses are excluded, and addresses appear only
+    /// once (case insensitive).
+    pub fn new(msg: &Message, own_emails: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        let mut emails: HashSet<String> = own_emails
+            .into_iter()
+            .map(|email| email.as_ref().to_lowercase())
+            .collect();
+
+        let mut rcpts = Self::default();
+
+        if let Some(followup_to) = msg.header_raw("Mail-Followup-To") {
+            let followup_to = parse_raw_addresses(followup_to);
+            push_addresses(&mut emails, &mut rcpts.to, followup_to);
+            return rcpts;
+        }
+
+        let author = ["Reply-To", "From", "Sender"]
+            .into_iter()
+            .map(|header| addresses(msg.header(header)))
+            .find(|addrs| !addrs.is_empty())
+            .unwrap_or_default();
+        push_addresses(&mut emails, &mut rcpts.to, author);
+
+        let to = addresses(msg.header("To"));
+
+        if rcpts.to.is_empty() {
+            // the author is oneself, so the reply follows up the
+            // original message
+            push_addresses(&mut emails, &mut rcpts.to, to);
+        } else {
+            push_addresses(&mut emails, &mut rcpts.cc, to);
+        }
+
+        push_addresses(&mut emails, &mut rcpts.cc, addresses(msg.header("Cc")));
+
+        if let Some(list) = msg.header_raw("List-Post").and_then(parse_list_post) {
+            push_addresses(&mut emails, &mut rcpts.cc, [list]);
+        }
+
+        rcpts
+    }
+}
+
+/// Push the given addresses that have not been seen yet.
+fn push_addresses(
+    emails: &mut HashSet<String>,
+    rcpts: &mut Vec<Address>,
+    addrs: impl IntoIterator<Item = Address>,
+) {
+    for addr in addrs {
+        if emails.insert(addr.addr.to_lowercase()) {
+            rcpts.push(addr)
+        }
+    }
+}
+
+/// Collect the addresses of the given parsed header, including the
+/// addresses of groups.
+fn addresses(header: Option<&HeaderValue>) -> Vec<Address> {
+    let addrs: Vec<_> = match header {
+        Some(HeaderValue::Address(ParsedAddress::List(addrs))) => addrs.iter().collect(),
+        Some(HeaderValue::Address(ParsedAddress::Group(groups))) => groups
+            .iter()
+            .flat_map(|group| group.addresses.iter())
+            .collect(),
+        _ => return Vec::new(),
+    };
+
+    addrs
+        .into_iter()
+        .filter_map(|addr| {
+            let email = addr.address.as_ref()?;
+            Some(Address::new(addr.name.as_ref(), email))
+        })
+        .collect()
+}
+
+/// Parse the addresses of the given raw header value.
+///
+/// Headers like `Mail-Followup-To` are not known by the parser, so
+/// their value is parsed as if it was the one of a `To` header.
+fn parse_raw_addresses(value: &str) -> Vec<Address> {
+    let header = format!("To:{value}\r\n\r\n");
+
+    match MessageParser::new().parse(header.as_bytes()) {
+        Some(msg) => addresses(msg.header("To")),
+        None => Vec::new(),
+    }
+}
+
+/// Parse the email address of the given raw `List-Post` header
+/// value, like `<mailto:list@localhost>` (RFC 2369).
+///
+/// Returns `None` when posting is not allowed (`NO`) or when the
+/// header contains no `mailto` URL.
+fn parse_list_post(value: &str) -> Option<Address> {
+    let (_, url) = value.split_once("<mailto:")?;
+    let (url, _) = url.split_once('>')?;
+    let email = url.split('?').next()?.trim();
+    (!email.is_empty()).then(|| Address::new_nameless(email))
+}
+
+#[cfg(test)]
+mod tests {
+    use concat_with::concat_line;
+    use mail_parser::MessageParser;
+
+    use super::ReplyAllRecipients;
+    use crate::envelope::address::Address;
+
+    fn rcpts(msg: &str, own_emails: &[&str]) -> ReplyAllRecipients {
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        ReplyAllRecipients::new(&msg, own_emails)
+    }
+
+    fn emails(addrs: &[Address]) -> Vec<&str> {
+        addrs.iter().map(|addr| addr.addr.as_str()).collect()
+    }
+
+    #[test]
+    fn reply_all() {
+        let rcpts = rcpts(
+            concat_line!(
+                "From: Alice <alice@localhost>",
+                "To: me@localhost, bob@localhost",
+                "Cc: carol@localhost, Alice <ALICE@localhost>",
+                "Subject: subject",
+                "",
+                "Hello, world!",
+            ),
+            &["me@localhost"],
+        );
+
+        assert_eq!(
+            rcpts.to,
+            vec![Address::new(Some("Alice"), "alice@localhost")]
+        );
+        assert_eq!(emails(&rcpts.cc), vec!["bob@localhost", "carol@localhost"]);
+    }
+
+    #[test]
+    fn reply_all_excluding_own_cc() {
+        let rcpts = rcpts(
+            concat_line!(
+                "From: alice@localhost",
+                "Reply-To: alice@work.localhost",
+                "To: bob@localhost",
+                "Cc: Me <ME@localhost>, me@alias.localhost, carol@localhost",
+                "List-Post: <mailto:list@localhost?subject=list>",
+                "Subject: subject",
+                "",
+                "Hello, world!",
+            ),
+            &["me@localhost", "me@alias.localhost"],
+        );
+
+        assert_eq!(emails(&rcpts.to), vec!["alice@work.localhost"]);
+        assert_eq!(
+            emails(&rcpts.cc),
+            vec!["bob@localhost", "carol@localhost", "list@localhost"]
+        );
+    }
+
+    #[test]
+    fn reply_all_to_own_message() {
+        let rcpts = rcpts(
+            concat_line!(
+                "From: me@localhost",
+                "To: bob@localhost",
+                "Cc: carol@localhost",
+                "Subject: subject",
+                "",
+                "Hello, world!",
+            ),
+            &["me@localhost"],
+        );
+
+        assert_eq!(emails(&rcpts.to), vec!["bob@localhost"]);
+        assert_eq!(emails(&rcpts.cc), vec!["carol@localhost"]);
+    }
+
+    #[test]
+    fn reply_all_using_mail_followup_to() {
+        let rcpts = rcpts(
+            concat_line!(
+                "From: alice@localhost",
+                "To: list@localhost",
+                "Cc: carol@localhost",
+                "Mail-Followup-To: list@localhost, \"Me\" <me@localhost>",
+                "Subject: subject",
+                "",
+                "Hello, world!",
+            ),
+            &["me@localhost"],
+        );
+
+        assert_eq!(emails(&rcpts.to), vec!["list@localhost"]);
+        assert!(rcpts.cc.is_empty());
+    }
+}
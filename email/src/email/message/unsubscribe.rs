@@ -0,0 +1,179 @@
+//! # List unsubscribe
+//!
+//! Module dedicated to mailing list unsubscription. Mailing lists
+//! advertise how to unsubscribe using the `List-Unsubscribe` header
+//! (RFC 2369), and whether one-click unsubscription is supported
+//! using the `List-Unsubscribe-Post` header (RFC 8058). The main
+//! structure of this module is [`ListUnsubscribe`].
+
+/// The value of the `List-Unsubscribe-Post` header enabling
+/// one-click unsubscription, which is also the body of the one-click
+/// POST request.
+pub const ONE_CLICK: &str = "List-Unsubscribe=One-Click";
+
+/// The unsubscribe methods of a mailing list message.
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ListUnsubscribe {
+    /// The `mailto:` URLs, in order of preference.
+    pub mailto: Vec<String>,
+
+    /// The `https:` (or `http:`) URLs, in order of preference.
+    pub https: Vec<String>,
+
+    /// Whether one-click unsubscription is supported (RFC 8058).
+    ///
+    /// One-click requires the `List-Unsubscribe-Post` header and at
+    /// least one HTTPS URL.
+    pub one_click: bool,
+}
+
+impl ListUnsubscribe {
+    /// Parse the given raw `List-Unsubscribe` header value, with the
+    /// optional raw `List-Unsubscribe-Post` header value.
+    ///
+    /// URLs are enclosed in angle brackets and separated by commas,
+    /// like `<mailto:leave@localhost>, <https://localhost/leave>`.
+    /// Returns `None` if no supported URL is found.
+    pub fn parse(list_unsubscribe: &str, list_unsubscribe_post: Option<&str>) -> Option<Self> {
+        let mut methods = Self::default();
+
+        for url in list_unsubscribe.split(',') {
+            let Some(url) = url
+                .trim()
+                .strip_prefix('<')
+                .and_then(|url| url.strip_suffix('>'))
+            else {
+                continue;
+            };
+
+            let url = url.trim();
+            let scheme = url.split_once(':').map(|(scheme, _)| scheme.to_lowercase());
+
+            match scheme.as_deref() {
+                Some("mailto") => methods.mailto.push(url.to_owned()),
+                Some("https" | "http") => methods.https.push(url.to_owned()),
+                _ => continue,
+            }
+        }
+
+        if methods.mailto.is_empty() && methods.https.is_empty() {
+            return None;
+        }
+
+        let one_click = list_unsubscribe_post.is_some_and(|post| post.trim() == ONE_CLICK);
+        methods.one_click = one_click && methods.https.iter().any(|url| is_https(url));
+
+        Some(methods)
+    }
+
+    /// Parse the unsubscribe methods of the given parsed message.
+    pub fn from_mail_parser(msg: &mail_parser::Message) -> Option<Self> {
+        let list_unsubscribe = msg.header_raw("List-Unsubscribe")?;
+        let list_unsubscribe_post = msg.header_raw("List-Unsubscribe-Post");
+        Self::parse(list_unsubscribe, list_unsubscribe_post)
+    }
+
+    /// Return the URL to send the one-click POST request to, if
+    /// one-click unsubscription is supported.
+    pub fn one_click_url(&self) -> Option<&str> {
+        if !self.one_click {
+            return None;
+        }
+
+        self.https
+            .iter()
+            .find(|url| is_https(url))
+            .map(String::as_str)
+    }
+
+    /// Unsubscribe using the one-click POST request (RFC 8058).
+    #[cfg(feature = "unsubscribe")]
+    pub async fn unsubscribe_one_click(&self, client: &http::Client) -> crate::email::Result<()> {
+        use crate::email::Error;
+
+        let url = self
+            .one_click_url()
+            .ok_or(Error::UnsubscribeOneClickNotSupportedError)?
+            .to_owned();
+
+        let url_clone = url.clone();
+        let res = client
+            .send(move |agent| {
+                agent
+                    .post(url_clone)
+                    .header("Content-Type", "application/x-www-form-urlencoded")
+                    .send(ONE_CLICK)
+            })
+            .await
+            .map_err(|err| Error::SendUnsubscribeRequestError(err, url.clone()))?;
+
+        let status = res.status();
+
+        if !status.is_success() {
+            return Err(Error::UnsubscribeError(status, url));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_https(url: &str) -> bool {
+    url.get(..6)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https:"))
+}
+
+#[cfg(test)]
+mod tests {
+    use concat_with::concat_line;
+    use mail_parser::MessageParser;
+
+    use super::ListUnsubscribe;
+
+    #[test]
+    fn parse_mailto_and_https_with_one_click() {
+        let msg = concat_line!(
+            "From: list@localhost",
+            "List-Unsubscribe: <mailto:leave@localhost?subject=unsubscribe>,",
+            " <https://localhost/leave?id=42>",
+            "List-Unsubscribe-Post: List-Unsubscribe=One-Click",
+            "Subject: News",
+            "",
+            "Hello, world!",
+        );
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+        let methods = ListUnsubscribe::from_mail_parser(&msg).unwrap();
+
+        assert_eq!(
+            methods.mailto,
+            vec!["mailto:leave@localhost?subject=unsubscribe"]
+        );
+        assert_eq!(methods.https, vec!["https://localhost/leave?id=42"]);
+        assert!(methods.one_click);
+        assert_eq!(
+            methods.one_click_url(),
+            Some("https://localhost/leave?id=42")
+        );
+    }
+
+    #[test]
+    fn parse_without_one_click() {
+        // no List-Unsubscribe-Post header
+        let methods = ListUnsubscribe::parse("<https://localhost/leave>", None).unwrap();
+        assert!(!methods.one_click);
+        assert_eq!(methods.one_click_url(), None);
+
+        // one-click requires an HTTPS URL
+        let methods = ListUnsubscribe::parse(
+            "<mailto:leave@localhost>, <http://localhost/leave>",
+            Some("List-Unsubscribe=One-Click"),
+        )
+        .unwrap();
+        assert!(!methods.one_click);
+
+        // unsupported or malformed URLs are skipped
+        assert_eq!(
+            ListUnsubscribe::parse("<ftp://localhost>, leave", None),
+            None
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! # MIME walker
+//!
+//! Module dedicated to the traversal of the MIME tree of a parsed
+//! message. The main structure of this module is [`MimeWalker`].
+//!
+//! [`mail_parser::Message`] stores parts as a flat list, where
+//! multiparts reference their children by index, and where
+//! `message/rfc822` parts hold a whole nested message. The walker
+//! hides these details, so that features walking the MIME tree
+//! (attachments, previews, signed or encrypted parts detection…)
+//! handle nesting the same way.
+
+use mail_parser::{Message, MessagePart, MimeHeaders, PartType};
+
+/// A part visited by the [`MimeWalker`].
+#[derive(Clone, Copy, Debug)]
+pub struct MimeWalkerPart<'a, 'x> {
+    /// The depth of the part in the MIME tree.
+    ///
+    /// The root part has a depth of 0. Children of a multipart, and
+    /// the root part of a `message/rfc822` part, are one level
+    /// deeper than their parent.
+    pub depth: usize,
+
+    /// The message the part belongs to.
+    ///
+    /// Parts inside a `message/rfc822` part belong to the nested
+    /// message.
+    pub msg: &'a Message<'x>,
+
+    /// The part itself.
+    pub part: &'a MessagePart<'x>,
+}
+
+impl<'a, 'x> MimeWalkerPart<'a, 'x> {
+    /// Get the lowercase content type of the part, like
+    /// `text/plain`.
+    ///
+    /// Parts without content type default to `text/plain`, as
+    /// defined by RFC 2045, unless they are multiparts or nested
+    /// messages.
+    pub fn content_type(&self) -> String {
+        let Some(ctype) = self.part.content_type() else {
+            let ctype = match self.part.body {
+                PartType::Multipart(_) => "multipart/mixed",
+                PartType::Message(_) => "message/rfc822",
+                _ => "text/plain",
+            };
+            return ctype.to_owned();
+        };
+
+        let ctype = match ctype.subtype() {
+            Some(stype) => format!("{}/{stype}", ctype.ctype()),
+            None => ctype.ctype().to_owned(),
+        };
+
+        ctype.to_ascii_lowercase()
+    }
+
+    /// Get the lowercase content disposition of the part, like
+    /// `attachment` or `inline`, if any.
+    pub fn disposition(&self) -> Option<String> {
+        self.part
+            .content_disposition()
+            .map(|disposition| disposition.ctype().to_ascii_lowercase())
+    }
+
+    /// Get the filename of the part, if any.
+    pub fn filename(&self) -> Option<&'a str> {
+        self.part.attachment_name()
+    }
+
+    /// Return `true` if the part is a multipart.
+    pub fn is_multipart(&self) -> bool {
+        matches!(self.part.body, PartType::Multipart(_))
+    }
+
+    /// Return `true` if the part is explicitly disposed as an
+    /// attachment.
+    pub fn is_attachment(&self) -> bool {
+        self.part
+            .content_disposition()
+            .is_some_and(|disposition| disposition.ctype().eq_ignore_ascii_case("attachment"))
+    }
+
+    /// Get the decoded body of the part.
+    ///
+    /// The body is not copied. Multiparts have an empty body, and
+    /// nested messages have their raw message as body.
+    pub fn body(&self) -> &'a [u8] {
+        match &self.part.body {
+            PartType::Multipart(_) => &[],
+            PartType::Message(msg) => msg.raw_message(),
+            _ => self.part.contents(),
+        }
+    }
+
+    /// Get the decoded text of the part, if the part is a text or an
+    /// HTML part.
+    pub fn text(&self) -> Option<&'a str> {
+        match &self.part.body {
+            PartType::Text(text) | PartType::Html(text) => Some(text.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The MIME walker.
+///
+/// The walker is an iterator over all the parts of a message,
+/// multiparts and nested messages included, in depth-first order:
+/// every part is visited before its children, and children are
+/// visited in the order they appear in the message.
+#[derive(Clone, Debug)]
+pub struct MimeWalker<'a, 'x> {
+    /// The parts left to visit, the next one last.
+    stack: Vec<MimeWalkerPart<'a, 'x>>,
+}
+
+impl<'a, 'x> MimeWalker<'a, 'x> {
+    pub fn new(msg: &'a Message<'x>) -> Self {
+        let stack = msg
+            .part(0)
+            .map(|part| MimeWalkerPart {
+                depth: 0,
+                msg,
+                part,
+            })
+            .into_iter()
+            .collect();
+
+        Self { stack }
+    }
+}
+
+impl<'a, 'x> Iterator for MimeWalker<'a, 'x> {
+    type Item = MimeWalkerPart<'a, 'x>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let visited = self.stack.pop()?;
+        let depth = visited.depth + 1;
+
+        match &visited.part.body {
+            PartType::Multipart(ids) => {
+                let msg = visited.msg;
+                let children = ids
+                    .iter()
+                    .rev()
+                    .filter_map(|id| msg.part(*id))
+                    .map(|part| MimeWalkerPart { depth, msg, part });
+                self.stack.extend(children);
+            }
+            PartType::Message(msg) => {
+                if let Some(part) = msg.part(0) {
+                    self.stack.push(MimeWalkerPart { depth, msg, part });
+                }
+            }
+            _ => (),
+        }
+
+        Some(visited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use concat_with::concat_line;
+    use mail_parser::MessageParser;
+
+    use super::MimeWalker;
+
+    #[test]
+    fn walk_nested_message() {
+        let msg = concat_line!(
+            "From: alice@localhost",
+            "Subject: nested",
+            "Content-Type: multipart/mixed; boundary=mixed",
+            "",
+            "--mixed",
+            "Content-Type: multipart/alternative; boundary=alternative",
+            "",
+            "--alternative",
+            "Content-Type: text/plain",
+            "",
+            "Hello!",
+            "--alternative",
+            "Content-Type: multipart/related; boundary=related",
+            "",
+            "--related",
+            "Content-Type: text/html",
+            "",
+            "<p>Hello!</p>",
+            "--related",
+            "Content-Type: image/png",
+            "Content-Disposition: inline",
+            "Content-ID: <logo@localhost>",
+            "Content-Transfer-Encoding: base64",
+            "",
+            "iVBORw0KGgo=",
+            "--related--",
+            "--alternative--",
+            "--mixed",
+            "Content-Type: message/rfc822",
+            "Content-Disposition: attachment; filename=forwarded.eml",
+            "",
+            "From: bob@localhost",
+            "Subject: forwarded",
+            "Content-Type: multipart/mixed; boundary=forwarded",
+            "",
+            "--forwarded",
+            "",
+            "Forwarded!",
+            "--forwarded",
+            "Content-Type: application/pdf",
+            "Content-Disposition: attachment; filename=doc.pdf",
+            "",
+            "pdf",
+            "--forwarded--",
+            "--mixed",
+            "Content-Type: application/octet-stream",
+            "Content-Disposition: ATTACHMENT; filename=data.bin",
+            "",
+            "data",
+            "--mixed--",
+        );
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+
+        let parts: Vec<_> = MimeWalker::new(&msg)
+            .map(|part| {
+                (
+                    part.depth,
+                    part.content_type(),
+                    part.disposition(),
+                    part.filename(),
+                )
+            })
+            .collect();
+
+        let attachment = Some(String::from("attachment"));
+        let expected: Vec<(usize, String, Option<String>, Option<&str>)> = vec![
+            (0, "multipart/mixed".into(), None, None),
+            (1, "multipart/alternative".into(), None, None),
+            (2, "text/plain".into(), None, None),
+            (2, "multipart/related".into(), None, None),
+            (3, "text/html".into(), None, None),
+            (3, "image/png".into(), Some("inline".into()), None),
+            (
+                1,
+                "message/rfc822".into(),
+                attachment.clone(),
+                Some("forwarded.eml"),
+            ),
+            (2, "multipart/mixed".into(), None, None),
+            (3, "text/plain".into(), None, None),
+            (
+                3,
+                "application/pdf".into(),
+                attachment.clone(),
+                Some("doc.pdf"),
+            ),
+            (
+                1,
+                "application/octet-stream".into(),
+                attachment,
+                Some("data.bin"),
+            ),
+        ];
+
+        assert_eq!(parts, expected);
+
+        let parts: Vec<_> = MimeWalker::new(&msg).collect();
+
+        // the forwarded message parts belong to the nested message
+        assert_eq!(parts[0].msg.subject(), Some("nested"));
+        assert_eq!(parts[8].msg.subject(), Some("forwarded"));
+
+        assert!(parts[0].is_multipart());
+        assert!(parts[0].body().is_empty());
+        assert_eq!(parts[2].text(), Some("Hello!"));
+        assert_eq!(parts[4].text(), Some("<p>Hello!</p>"));
+        assert_eq!(parts[5].body(), b"\x89PNG\r\n\x1a\n");
+        assert!(!parts[5].is_attachment());
+        assert!(parts[6].body().starts_with(b"From: bob@localhost"));
+        assert_eq!(parts[8].text(), Some("Forwarded!"));
+        assert!(parts[10].is_attachment());
+        assert_eq!(parts[10].body(), b"data");
+    }
+
+    #[test]
+    fn walk_single_part_message() {
+        let msg = concat_line!("Subject: single", "", "Hello!");
+        let msg = MessageParser::new().parse(msg.as_bytes()).unwrap();
+
+        let parts: Vec<_> = MimeWalker::new(&msg).collect();
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].depth, 0);
+        assert_eq!(parts[0].content_type(), "text/plain");
+        assert_eq!(parts[0].disposition(), None);
+        assert_eq!(parts[0].text(), Some("Hello!"));
+    }
+}
@@ -12,39 +12,99 @@
     sync::Arc,
 };
 
-use futures::{stream::FuturesUnordered, StreamExt};
+use futures::{lock::Mutex, stream::FuturesUnordered, StreamExt};
 use tracing::{debug, trace};
 
 use self::{hunk::EmailSyncHunk, report::EmailSyncReport};
 #[doc(inline)]
 pub use super::{Error, Result};
 use crate::{
-    backend::context::BackendContextBuilder,
+    backend::context::{BackendContext, BackendContextBuilder},
     envelope::{
         get::GetEnvelope,
         list::{ListEnvelopes, ListEnvelopesOptions},
         Envelope, Id, SingleId,
     },
     flag::{add::AddFlags, set::SetFlags, Flag},
+    folder::cursor::GetFolderCursor,
     message::{add::AddMessage, peek::PeekMessages},
     search_query::SearchEmailsQuery,
-    sync::{pool::SyncPoolContext, SyncDestination, SyncEvent},
+    sync::{
+        checkpoint::SyncCheckpoint,
+        pool::SyncPoolContext,
+        progress::{SyncProgress, SyncProgressCounter},
+        SyncDestination, SyncEvent,
+    },
     AnyBoxedError,
 };
 
-/// Errors related to email synchronization.
+/// The progress of a folder towards its checkpoint.
+struct FolderCheckpoint {
+    /// The cursors of the folder before its envelopes were listed.
+    cursors: (Option<u64>, Option<u64>),
+
+    /// The number of hunks left to process.
+    pending: usize,
+
+    /// Whether one of the hunks failed.
+    failed: bool,
+
+    /// Whether hunks change the left folder.
+    left_changed: bool,
 
+    /// Whether hunks change the right folder.
+    right_changed: bool,
+}
+
+/// Synchronize emails of the given folders.
+///
+/// When a checkpoint is given, folders whose cursors did not move
+/// since the checkpoint are skipped, and folders are checkpointed as
+/// soon as all their hunks are processed.
 pub(crate) async fn sync<L, R>(
     ctx_ref: Arc<SyncPoolContext<L::Context, R::Context>>,
     folders: &HashSet<String>,
+    checkpoint: Option<SyncCheckpoint>,
 ) -> Result<EmailSyncReport>
 where
     L: BackendContextBuilder + 'static,
     R: BackendContextBuilder + 'static,
 {
     let mut report = EmailSyncReport::default();
-    let patch = FuturesUnordered::from_iter(folders.iter().map(|folder| {
+
+    let mut cursors = HashMap::new();
+    if let Some(checkpoint) = &checkpoint {
+        cursors = FuturesUnordered::from_iter(folders.iter().map(|folder| {
+            let ctx = ctx_ref.clone();
+            async move { (folder.clone(), get_folder_cursors(&ctx, folder).await) }
+        }))
+        .collect::<HashMap<_, _>>()
+        .await;
+
+        for (folder, (left, right)) in &cursors {
+            if checkpoint.is_synced(folder, *left, *right) {
+                debug!("folder {folder} did not change since last checkpoint, skipping");
+                report.skipped_folders.insert(folder.clone());
+            }
+        }
+    }
+
+    let folders: Vec<_> = folders
+        .iter()
+        .filter(|folder| !report.skipped_folders.contains(*folder))
+        .collect();
+
+    SyncProgress::StartedEmailSync {
+        total: folders.len(),
+    }
+    .emit(&ctx_ref.handler)
+    .await;
+    let counter = Arc::new(SyncProgressCounter::new(folders.len()));
+
+    let patch = FuturesUnordered::from_iter(folders.into_iter().map(|folder| {
         let ctx = ctx_ref.clone();
+        let counter = counter.clone();
+        let handler = ctx_ref.handler.clone();
         let folder_ref = folder.clone();
 
         let left_cached_envelopes = tokio::spawn(async move {
@@ -193,6 +253,16 @@ pub(crate) async fn sync<L, R>(
                 right_envelopes
             );
 
+            counter
+                .tick(&handler, |done, total| {
+                    SyncProgress::ListedFolderEnvelopes {
+                        folder: folder.clone(),
+                        done,
+                        total,
+                    }
+                })
+                .await;
+
             Result::Ok((folder.clone(), envelopes))
         }
     }))
@@ -225,11 +295,62 @@ pub(crate) async fn sync<L, R>(
         .emit(&ctx_ref.handler)
         .await;
 
+    let checkpoint = checkpoint.map(|checkpoint| Arc::new(Mutex::new(checkpoint)));
+    let mut folder_checkpoints = HashMap::new();
+
+    if let Some(checkpoint) = &checkpoint {
+        for (folder, hunks) in &patch {
+            let folder_checkpoint = FolderCheckpoint {
+                cursors: cursors.get(folder).copied().unwrap_or_default(),
+                pending: hunks.len(),
+                failed: false,
+                left_changed: hunks
+                    .iter()
+                    .any(|h| changes_folder(h, SyncDestination::Left)),
+                right_changed: hunks
+                    .iter()
+                    .any(|h| changes_folder(h, SyncDestination::Right)),
+            };
+
+            if folder_checkpoint.pending == 0 {
+                let (left, right) = folder_checkpoint.cursors;
+                save_checkpoint(checkpoint, folder, left, right).await;
+            } else {
+                folder_checkpoints.insert(folder.clone(), folder_checkpoint);
+            }
+        }
+    }
+
+    let folder_checkpoints = Arc::new(Mutex::new(folder_checkpoints));
+
+    let hunks = patch.values().flatten();
+    let total = hunks.clone().count();
+    let messages = hunks.clone().filter(|hunk| is_message_hunk(hunk)).count();
+    let flags = hunks.filter(|hunk| is_flags_hunk(hunk)).count();
+
+    SyncProgress::StartedEmailPatch {
+        total,
+        messages,
+        flags,
+    }
+    .emit(&ctx_ref.handler)
+    .await;
+
+    let counter = Arc::new(SyncProgressCounter::new(total));
+    let messages_counter = Arc::new(SyncProgressCounter::new(messages));
+    let flags_counter = Arc::new(SyncProgressCounter::new(flags));
+
     report.patch = FuturesUnordered::from_iter(patch.into_values().flatten().map(|hunk| {
         let ctx = ctx_ref.clone();
+        let counter = counter.clone();
+        let messages_counter = messages_counter.clone();
+        let flags_counter = flags_counter.clone();
+        let checkpoint = checkpoint.clone();
+        let folder_checkpoints = folder_checkpoints.clone();
         tokio::spawn(async move {
             let hunk_clone = hunk.clone();
             let handler = ctx.handler.clone();
+            let checkpoint_ctx = ctx.clone();
 
             let task = async move {
                 if ctx.dry_run {
@@ -369,6 +490,72 @@ pub(crate) async fn sync<L, R>(
                 .emit(&handler)
                 .await;
 
+            if is_message_hunk(&hunk) {
+                messages_counter
+                    .tick(&handler, |done, total| SyncProgress::ProcessedMessage {
+                        folder: hunk.folder().to_owned(),
+                        done,
+                        total,
+                    })
+                    .await;
+            }
+
+            if is_flags_hunk(&hunk) {
+                flags_counter
+                    .tick(&handler, |done, total| SyncProgress::PushedFlags {
+                        folder: hunk.folder().to_owned(),
+                        done,
+                        total,
+                    })
+                    .await;
+            }
+
+            counter
+                .tick(&handler, |done, total| SyncProgress::ProcessedEmailHunk {
+                    done,
+                    total,
+                })
+                .await;
+
+            if let Some(checkpoint) = &checkpoint {
+                let folder = hunk.folder();
+                let mut folder_checkpoints = folder_checkpoints.lock().await;
+
+                let done = match folder_checkpoints.get_mut(folder) {
+                    Some(folder_checkpoint) => {
+                        folder_checkpoint.pending -= 1;
+                        folder_checkpoint.failed |= output.is_err();
+                        folder_checkpoint.pending == 0
+                    }
+                    None => false,
+                };
+
+                let folder_checkpoint = if done {
+                    folder_checkpoints.remove(folder)
+                } else {
+                    None
+                };
+
+                drop(folder_checkpoints);
+
+                if let Some(folder_checkpoint) = folder_checkpoint.filter(|fc| !fc.failed) {
+                    // cursors of changed folders moved because of the
+                    // hunks, they need to be read again
+                    let (mut left, mut right) = folder_checkpoint.cursors;
+                    if folder_checkpoint.left_changed || folder_checkpoint.right_changed {
+                        let cursors = get_folder_cursors(&checkpoint_ctx, folder).await;
+                        if folder_checkpoint.left_changed {
+                            left = cursors.0;
+                        }
+                        if folder_checkpoint.right_changed {
+                            right = cursors.1;
+                        }
+                    }
+
+                    save_checkpoint(checkpoint, folder, left, right).await;
+                }
+            }
+
             match output {
                 Ok(()) => (hunk, None),
                 Err(err) => (hunk, Some(err)),
@@ -394,3 +581,75 @@ pub(crate) async fn sync<L, R>(
 
     Ok(report)
 }
+
+/// Get the left and right cursors of the given folder.
+///
+/// Backends that cannot give a cursor, or that fail to, give `None`.
+async fn get_folder_cursors<L: BackendContext, R: BackendContext>(
+    ctx: &Arc<SyncPoolContext<L, R>>,
+    folder: &str,
+) -> (Option<u64>, Option<u64>) {
+    let (left, right) = tokio::join!(
+        ctx.left.get_folder_cursor(folder),
+        ctx.right.get_folder_cursor(folder),
+    );
+
+    let left = left.unwrap_or_else(|err| {
+        debug!("cannot get left cursor of folder {folder}: {err}");
+        trace!("{err:?}");
+        None
+    });
+
+    let right = right.unwrap_or_else(|err| {
+        debug!("cannot get right cursor of folder {folder}: {err}");
+        trace!("{err:?}");
+        None
+    });
+
+    (left, right)
+}
+
+/// Mark the given folder as synchronized in the given checkpoint,
+/// then save it.
+///
+/// A checkpoint that cannot be saved only makes the next
+/// synchronization process the folder again, so errors are logged
+/// and ignored.
+async fn save_checkpoint(
+    checkpoint: &Mutex<SyncCheckpoint>,
+    folder: &str,
+    left: Option<u64>,
+    right: Option<u64>,
+) {
+    let mut checkpoint = checkpoint.lock().await;
+    checkpoint.insert(folder, left, right);
+
+    if let Err(err) = checkpoint.save() {
+        debug!("cannot save sync checkpoint after folder {folder}: {err}");
+        trace!("{err:?}");
+    }
+}
+
+/// Return `true` if the given hunk changes the folder of the given
+/// destination, its cache excluded.
+fn changes_folder(hunk: &EmailSyncHunk, dest: SyncDestination) -> bool {
+    match hunk {
+        EmailSyncHunk::CopyThenCache(_, _, _, target, _) => *target == dest,
+        EmailSyncHunk::UpdateFlags(_, _, target) => *target == dest,
+        EmailSyncHunk::Delete(_, _, target) => *target == dest,
+        _ => false,
+    }
+}
+
+/// Return `true` if the given hunk copies or deletes a message.
+fn is_message_hunk(hunk: &EmailSyncHunk) -> bool {
+    matches!(
+        hunk,
+        EmailSyncHunk::CopyThenCache(..) | EmailSyncHunk::Delete(..)
+    )
+}
+
+/// Return `true` if the given hunk pushes flags to a backend.
+fn is_flags_hunk(hunk: &EmailSyncHunk) -> bool {
+    matches!(hunk, EmailSyncHunk::UpdateFlags(..))
+}
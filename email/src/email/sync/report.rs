@@ -3,12 +3,18 @@
 //! Module dedicated to email synchronization reporting. The main
 //! structure of this module is [`EmailSyncReport`].
 
+use std::collections::BTreeSet;
+
 use super::hunk::EmailSyncHunk;
-use crate::AnyBoxedError;
+use crate::{folder::sync::hunk::FolderName, AnyBoxedError};
 
 /// The email synchronization report.
 #[derive(Debug, Default)]
 pub struct EmailSyncReport {
     /// The list of processed hunks associated with an optional error.
     pub patch: Vec<(EmailSyncHunk, Option<AnyBoxedError>)>,
+
+    /// The folders skipped because they did not change since the
+    /// last checkpoint.
+    pub skipped_folders: BTreeSet<FolderName>,
 }
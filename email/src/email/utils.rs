@@ -33,6 +33,11 @@ pub(crate) mod address {
     /// `do-not.reply`.
     static NO_REPLY: Lazy<Regex> = Lazy::new(|| Regex::new("(?i:not?[_\\-\\.]?reply)").unwrap());
 
+    /// Return `true` if the given email address is a no-reply one.
+    pub(crate) fn is_no_reply(email: &str) -> bool {
+        NO_REPLY.is_match(email)
+    }
+
     pub(crate) fn is_empty(header: &parser::HeaderValue) -> bool {
         match header {
             parser::HeaderValue::Address(parser::Address::List(addrs)) => addrs.is_empty(),
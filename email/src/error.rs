@@ -16,6 +16,40 @@
 /// features.
 pub trait AnyError: error::Error + Any + Send + Sync {
     fn as_any(&self) -> &dyn Any;
+
+    /// Classify the current error.
+    ///
+    /// Errors are considered permanent by default, which means that
+    /// retrying the operation that failed would not help.
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Permanent
+    }
+}
+
+/// The kind of an error.
+///
+/// The kind is used to decide whether the operation that failed can
+/// be retried, see [`AnyError::kind`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The error is temporary, the operation may succeed if retried
+    /// later (broken connection, timeout, SMTP 4xx reply…).
+    Transient,
+
+    /// The error is related to authentication, the operation should
+    /// not be retried before the credentials are fixed.
+    Auth,
+
+    /// The error is definitive, the operation should not be retried
+    /// (SMTP 5xx reply, invalid message…).
+    Permanent,
+}
+
+impl ErrorKind {
+    /// Return `true` if the error kind is transient.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Transient)
+    }
 }
 
 impl AnyError for JoinError {
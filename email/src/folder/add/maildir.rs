@@ -2,7 +2,11 @@
 use tracing::info;
 
 use super::AddFolder;
-use crate::{folder::error::Error, maildir::MaildirContextSync, AnyResult};
+use crate::{
+    folder::{error::Error, FolderKind},
+    maildir::{perms, MaildirContextSync},
+    AnyResult,
+};
 
 pub struct AddMaildirFolder {
     ctx: MaildirContextSync,
@@ -30,9 +34,22 @@ async fn add_folder(&self, folder: &str) -> AnyResult<()> {
         let ctx = self.ctx.lock().await;
         let config = &ctx.account_config;
 
-        ctx.root
-            .create(config.get_folder_alias(folder))
-            .map_err(|e| Error::CreateFolderStructureMaildirError(e, ctx.root.path().to_owned()))?;
+        let folder = config.get_folder_alias(folder);
+
+        // the inbox of the Maildir++ layout is the root folder, which
+        // must not be created as a `.INBOX` subfolder
+        if ctx.maildir_config.is_inbox_root() && FolderKind::matches_inbox(&folder) {
+            let mdir = ctx.get_maildir_from_folder_alias(&folder)?;
+            mdir.create_all()
+                .map_err(|e| Error::CreateFolderStructureMaildirError(e, mdir.path().to_owned()))?;
+        } else {
+            ctx.root.create(&folder).map_err(|e| {
+                Error::CreateFolderStructureMaildirError(e, ctx.root.path().to_owned())
+            })?;
+        }
+
+        let mdir = ctx.get_maildir_from_folder_alias(&folder)?;
+        perms::set_folder_mode(&ctx.maildir_config, mdir.path())?;
 
         Ok(())
     }
@@ -15,8 +15,9 @@ pub struct FolderConfig {
     /// Define custom folder aliases.
     ///
     /// Aliases are resolved when calling backend features. There are
-    /// 4 special aliases that map to [`super::FolderKind`]: inbox,
-    /// draft(s), sent and trash. Other aliases map to folder names.
+    /// 6 special aliases that map to [`super::FolderKind`]: inbox,
+    /// draft(s), sent, trash, archive and junk. Other aliases map to
+    /// folder names.
     ///
     /// Note: folder aliases are case-insensitive.
     pub aliases: Option<HashMap<String, String>>,
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use imap_client::imap_next::imap_types::status::StatusDataItemName;
+use tracing::{debug, info};
+
+use super::GetFolderCursor;
+use crate::{imap::ImapContext, AnyResult};
+
+/// The IMAP implementation of [`GetFolderCursor`].
+///
+/// The cursor is the `HIGHESTMODSEQ` of the mailbox, as defined by
+/// the CONDSTORE extension (RFC 7162). Servers that do not advertise
+/// the extension have no cursor.
+#[derive(Debug)]
+pub struct GetImapFolderCursor {
+    ctx: ImapContext,
+}
+
+impl GetImapFolderCursor {
+    pub fn new(ctx: &ImapContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    pub fn new_boxed(ctx: &ImapContext) -> Box<dyn GetFolderCursor> {
+        Box::new(Self::new(ctx))
+    }
+
+    pub fn some_new_boxed(ctx: &ImapContext) -> Option<Box<dyn GetFolderCursor>> {
+        Some(Self::new_boxed(ctx))
+    }
+}
+
+#[async_trait]
+impl GetFolderCursor for GetImapFolderCursor {
+    async fn get_folder_cursor(&self, folder: &str) -> AnyResult<Option<u64>> {
+        info!("getting cursor of imap folder {folder}");
+
+        let mut client = self.ctx.client().await;
+
+        if !client.ext_condstore_supported() {
+            debug!("condstore extension not supported, cannot get cursor");
+            return Ok(None);
+        }
+
+        let folder = client.account_config.get_folder_alias(folder);
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
+
+        let status = client
+            .status_mailbox(folder_encoded, [StatusDataItemName::HighestModSeq])
+            .await?;
+
+        Ok(status.highest_modseq)
+    }
+}
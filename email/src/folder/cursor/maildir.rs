@@ -0,0 +1,144 @@
+use std::{
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+use super::GetFolderCursor;
+use crate::{
+    folder::error::{Error, Result},
+    maildir::MaildirContextSync,
+    AnyResult,
+};
+
+/// The resolution of file modification times.
+///
+/// File systems take modification times from a coarse clock, which
+/// only ticks every few milliseconds: a change made within the same
+/// tick as the previous one does not move the cursor.
+const MTIME_RESOLUTION: Duration = Duration::from_millis(20);
+
+/// The Maildir implementation of [`GetFolderCursor`].
+///
+/// Messages are added, removed and flagged by creating, removing or
+/// renaming files in the `new` and `cur` directories, which updates
+/// their modification time. The cursor is the most recent of these
+/// modification times, in nanoseconds.
+///
+/// A cursor is only given once its modification time is a few
+/// milliseconds old, so that any later change moves it. File
+/// systems with a coarser resolution, like FAT, may still miss
+/// changes made within the same second.
+pub struct GetMaildirFolderCursor {
+    ctx: MaildirContextSync,
+}
+
+impl GetMaildirFolderCursor {
+    pub fn new(ctx: &MaildirContextSync) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    pub fn new_boxed(ctx: &MaildirContextSync) -> Box<dyn GetFolderCursor> {
+        Box::new(Self::new(ctx))
+    }
+
+    pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn GetFolderCursor>> {
+        Some(Self::new_boxed(ctx))
+    }
+}
+
+#[async_trait]
+impl GetFolderCursor for GetMaildirFolderCursor {
+    async fn get_folder_cursor(&self, folder: &str) -> AnyResult<Option<u64>> {
+        info!("getting cursor of maildir folder {folder}");
+
+        let ctx = self.ctx.lock().await;
+        let mdir = ctx.get_maildir_from_folder_alias(folder)?;
+        drop(ctx);
+
+        loop {
+            let mtime = read_mtime(mdir.path())?;
+
+            // a modification time in the future cannot be trusted
+            let Ok(elapsed) = SystemTime::now().duration_since(mtime) else {
+                debug!("maildir folder {folder} modified in the future, cannot get cursor");
+                return Ok(None);
+            };
+
+            if elapsed >= MTIME_RESOLUTION {
+                let cursor = mtime.duration_since(UNIX_EPOCH).ok();
+                return Ok(cursor.map(|mtime| mtime.as_nanos() as u64));
+            }
+
+            // like other Maildir operations, this blocks for a few
+            // milliseconds at most
+            thread::sleep(MTIME_RESOLUTION - elapsed);
+        }
+    }
+}
+
+/// Get the most recent modification time of the `new` and `cur`
+/// directories of the given Maildir folder.
+fn read_mtime(root: &Path) -> Result<SystemTime> {
+    let mut mtime = UNIX_EPOCH;
+
+    for dir in ["new", "cur"] {
+        let path = root.join(dir);
+        let modified = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .map_err(|err| Error::GetMaildirFolderCursorError(err, path))?;
+        mtime = mtime.max(modified);
+    }
+
+    Ok(mtime)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use super::GetMaildirFolderCursor;
+    use crate::{
+        account::config::AccountConfig,
+        backend::context::BackendContextBuilder,
+        folder::cursor::GetFolderCursor,
+        maildir::{config::MaildirConfig, MaildirContextBuilder},
+    };
+
+    #[tokio::test]
+    async fn cursor_grows_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_owned();
+        for dir in ["cur", "new", "tmp"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let account_config = Arc::new(AccountConfig::default());
+        let mdir_config = Arc::new(MaildirConfig {
+            root_dir: root.clone(),
+            maildirpp: true,
+            ..Default::default()
+        });
+        let ctx = MaildirContextBuilder::new(account_config, mdir_config)
+            .build()
+            .await
+            .unwrap();
+        let cursor = GetMaildirFolderCursor::new(&ctx);
+
+        let before = cursor.get_folder_cursor("INBOX").await.unwrap();
+        assert!(before.is_some());
+        assert_eq!(cursor.get_folder_cursor("INBOX").await.unwrap(), before);
+
+        // the cursor waits for the modification time to settle, so
+        // this change is guaranteed to move it
+        let name = "1700000000.M1P1.localhost:2,S";
+        fs::write(root.join("cur").join(name), "Subject: 1\n\nHello!\n").unwrap();
+
+        let after = cursor.get_folder_cursor("INBOX").await.unwrap();
+        assert!(after > before);
+    }
+}
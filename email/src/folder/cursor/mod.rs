@@ -0,0 +1,26 @@
+//! # Folder cursor
+//!
+//! Module dedicated to folder cursors. A cursor is a number that
+//! grows every time the content of a folder changes, which allows
+//! the synchronization to skip folders that did not change since the
+//! last run. The main trait of this module is [`GetFolderCursor`].
+
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(feature = "maildir")]
+pub mod maildir;
+
+use async_trait::async_trait;
+
+use crate::AnyResult;
+
+#[async_trait]
+pub trait GetFolderCursor: Send + Sync {
+    /// Get the cursor of the given folder.
+    ///
+    /// The cursor grows every time a message is added to or removed
+    /// from the folder, or when the flags of a message change. `None`
+    /// is returned when the backend cannot track changes of the given
+    /// folder.
+    async fn get_folder_cursor(&self, folder: &str) -> AnyResult<Option<u64>>;
+}
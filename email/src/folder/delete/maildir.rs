@@ -30,11 +30,11 @@ impl DeleteFolder for DeleteMaildirFolder {
     async fn delete_folder(&self, folder: &str) -> AnyResult<()> {
         let ctx = self.ctx.lock().await;
         let config = &ctx.account_config;
-        let maildirpp = ctx.maildir_config.maildirpp;
+        let inbox_root = ctx.maildir_config.is_inbox_root();
 
         let folder = config.get_folder_alias(folder);
 
-        if maildirpp && FolderKind::matches_inbox(&folder) {
+        if inbox_root && FolderKind::matches_inbox(&folder) {
             let path = ctx.root.path().to_owned();
             return Err(Error::DeleteMaildirInboxForbiddenError(path).into());
         }
@@ -24,9 +24,28 @@ pub enum Error {
     #[error("cannot delete maildir INBOX at {0}")]
     DeleteMaildirInboxForbiddenError(std::path::PathBuf),
     #[cfg(feature = "maildir")]
+    #[error("cannot rename maildir folder {0} to {1}: renaming INBOX is forbidden")]
+    RenameMaildirInboxForbiddenError(String, String),
+    #[cfg(feature = "maildir")]
+    #[error("cannot rename maildir folder {0}: folder not found")]
+    RenameMaildirFolderNotFoundError(String),
+    #[cfg(feature = "maildir")]
+    #[error("cannot rename maildir folder to {0}: folder already exists")]
+    RenameMaildirFolderAlreadyExistsError(String),
+    #[cfg(feature = "maildir")]
+    #[error("cannot rename maildir folder at {1} to {2}")]
+    RenameMaildirFolderError(
+        #[source] std::io::Error,
+        std::path::PathBuf,
+        std::path::PathBuf,
+    ),
+    #[cfg(feature = "maildir")]
     #[error("maildir: cannot list current folder from {1}")]
     ListCurrentFolderMaildirError(#[source] maildirs::Error, std::path::PathBuf),
     #[cfg(feature = "maildir")]
+    #[error("cannot get cursor of maildir folder at {1}")]
+    GetMaildirFolderCursorError(#[source] std::io::Error, std::path::PathBuf),
+    #[cfg(feature = "maildir")]
     #[error("cannot remove maildir entry at {1}")]
     RemoveMaildirEntryError(#[source] maildirs::Error, std::path::PathBuf),
     #[error("cannot parse folder kind {0}")]
@@ -42,6 +42,10 @@ async fn expunge_folder(&self, folder: &str) -> AnyResult<()> {
                     .map_err(|err| Error::RemoveMaildirEntryError(err, entry.path().to_owned()))
             })?;
 
+        if ctx.account_config.is_trash_folder(folder) {
+            ctx.expunge_expired_trash()?;
+        }
+
         Ok(())
     }
 }
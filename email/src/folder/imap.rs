@@ -16,15 +16,29 @@
 
 impl Folders {
     pub fn from_imap_mailboxes(config: &AccountConfig, mboxes: ImapMailboxes) -> Self {
+        Self::from_imap_mailboxes_with_utf8(config, mboxes, false)
+    }
+
+    /// Build folders from the given IMAP mailboxes.
+    ///
+    /// Mailbox names are decoded from modified UTF-7, unless the
+    /// UTF8=ACCEPT extension is enabled: they are then already UTF-8.
+    pub fn from_imap_mailboxes_with_utf8(
+        config: &AccountConfig,
+        mboxes: ImapMailboxes,
+        utf8_enabled: bool,
+    ) -> Self {
         mboxes
             .into_iter()
-            .filter_map(|mbox| match Folder::try_from_imap_mailbox(config, &mbox) {
-                Ok(folder) => Some(folder),
-                Err(_err) => {
-                    debug!("skipping IMAP mailbox {:?}: {_err}", mbox.0.clone());
-                    None
-                }
-            })
+            .filter_map(
+                |mbox| match Folder::try_from_imap_mailbox(config, &mbox, utf8_enabled) {
+                    Ok(folder) => Some(folder),
+                    Err(_err) => {
+                        debug!("skipping IMAP mailbox {:?}: {_err}", mbox.0.clone());
+                        None
+                    }
+                },
+            )
             .collect()
     }
 }
@@ -39,6 +53,7 @@ impl Folder {
     fn try_from_imap_mailbox(
         config: &AccountConfig,
         (mbox, _delim, attrs): &ImapMailbox,
+        utf8_enabled: bool,
     ) -> Result<Self> {
         let mbox = match mbox {
             Mailbox::Inbox => String::from("INBOX"),
@@ -51,7 +66,11 @@ fn try_from_imap_mailbox(
             return Err(Error::ParseImapFolderNotSelectableError(mbox.clone()));
         }
 
-        let name = decode_utf7(mbox.into());
+        let name = if utf8_enabled {
+            mbox
+        } else {
+            decode_utf7(mbox.into())
+        };
 
         let kind = config
             .find_folder_kind_from_alias(&name)
@@ -78,6 +97,10 @@ pub fn find_folder_kind_from_imap_attrs(attrs: &[FlagNameAttribute]) -> Option<F
             Some(FolderKind::Drafts)
         } else if attr == &FlagNameAttribute::from(Atom::try_from("Trash").unwrap()) {
             Some(FolderKind::Trash)
+        } else if attr == &FlagNameAttribute::from(Atom::try_from("Archive").unwrap()) {
+            Some(FolderKind::Archive)
+        } else if attr == &FlagNameAttribute::from(Atom::try_from("Junk").unwrap()) {
+            Some(FolderKind::Junk)
         } else {
             None
         }
@@ -7,7 +7,7 @@
 
 use crate::{
     account::config::AccountConfig,
-    folder::{Folder, Folders},
+    folder::{Folder, FolderKind, Folders},
     maildir::MaildirContext,
 };
 
@@ -18,16 +18,27 @@ impl Folders {
     ///
     /// Folders are parsed in parallel, using [`rayon`]. Only parses
     /// direct submaildirs (no recursion).
+    ///
+    /// When the INBOX is the root Maildir (see
+    /// [`crate::maildir::config::MaildirConfig::is_inbox_root`]), a
+    /// `.INBOX` subfolder is not listed, since it is shadowed by the
+    /// root one.
     pub fn from_maildir_context(ctx: &MaildirContext) -> Self {
-        Folders::from_iter(ctx.root.iter().map(|entry| {
-            Folder {
+        let inbox_root = ctx.maildir_config.is_inbox_root();
+
+        Folders::from_iter(ctx.root.iter().filter_map(|entry| {
+            if inbox_root && FolderKind::matches_inbox(&entry.name) {
+                return None;
+            }
+
+            Some(Folder {
                 kind: ctx
                     .account_config
                     .find_folder_kind_from_alias(&entry.name)
                     .or_else(|| entry.name.parse().ok()),
                 name: entry.name,
                 desc: entry.maildir.path().display().to_string(),
-            }
+            })
         }))
     }
 }
@@ -10,10 +10,14 @@
 //! Backend features reside in their own module as well: [`add`],
 //! [`list`], [`expunge`], [`purge`], [`delete`].
 //!
+//! The [`tree`] module nests the flat list of folders into a tree,
+//! using the hierarchy delimiter.
+//!
 //! Finally, the [`sync`] module contains everything needed to
 //! synchronize a remote folder with a local one.
 pub mod add;
 pub mod config;
+pub mod cursor;
 pub mod delete;
 mod error;
 pub mod expunge;
@@ -23,16 +27,20 @@
 #[cfg(feature = "maildir")]
 pub mod maildir;
 pub mod purge;
+pub mod rename;
 #[cfg(feature = "sync")]
 pub mod sync;
+pub mod tree;
 
 use std::{
+    collections::HashMap,
     fmt,
     hash::Hash,
     ops::{Deref, DerefMut},
     str::FromStr,
 };
 
+use shellexpand_utils::shellexpand_str;
 #[cfg(feature = "sync")]
 pub(crate) use sync::sync;
 
@@ -44,6 +52,8 @@
 pub const DRAFT: &str = "Drafts";
 pub const DRAFTS: &str = "Drafts";
 pub const TRASH: &str = "Trash";
+pub const ARCHIVE: &str = "Archive";
+pub const JUNK: &str = "Junk";
 
 /// The folder kind enumeration.
 ///
@@ -80,6 +90,18 @@ pub enum FolderKind {
     /// in this folder are supposed to be deleted.
     Trash,
 
+    /// The kind of folder that contains archived emails.
+    ///
+    /// This kind of folder is used to store emails that should be
+    /// kept, but that do not need to stay in the inbox.
+    Archive,
+
+    /// The kind of folder that contains junk emails.
+    ///
+    /// This kind of folder is used to store emails detected as spam
+    /// or as unsolicited.
+    Junk,
+
     /// The user-defined kind of folder.
     ///
     /// This kind of folder represents the alias as defined by the
@@ -112,46 +134,92 @@ pub fn is_trash(&self) -> bool {
         matches!(self, FolderKind::Trash)
     }
 
+    /// Return `true` if the current folder kind matches the Archive
+    /// variant.
+    pub fn is_archive(&self) -> bool {
+        matches!(self, FolderKind::Archive)
+    }
+
+    /// Return `true` if the current folder kind matches the Junk
+    /// variant.
+    pub fn is_junk(&self) -> bool {
+        matches!(self, FolderKind::Junk)
+    }
+
     /// Return `true` if the current folder kind matches the
     /// UserDefined variant.
     pub fn is_user_defined(&self) -> bool {
         matches!(self, FolderKind::UserDefined(_))
     }
 
+    /// Return `true` if the given folder name matches the current
+    /// folder kind.
+    ///
+    /// The folder name matches if it equals the default name of the
+    /// kind (see [`FolderKind::as_str`]), or the alias defined for
+    /// the kind in the given folder aliases (see
+    /// [`AccountConfig::get_folder_aliases`]). Comparisons are
+    /// case-insensitive, and aliases are shell expanded.
+    ///
+    /// [`AccountConfig::get_folder_aliases`]: crate::account::config::AccountConfig::get_folder_aliases
+    pub fn matches(
+        &self,
+        folder: impl AsRef<str>,
+        aliases: Option<&HashMap<String, String>>,
+    ) -> bool {
+        let folder = folder.as_ref().trim();
+
+        let matches_name = match self {
+            Self::Drafts => {
+                folder.eq_ignore_ascii_case(DRAFT) || folder.eq_ignore_ascii_case(DRAFTS)
+            }
+            kind => folder.eq_ignore_ascii_case(kind.as_str()),
+        };
+
+        if matches_name {
+            return true;
+        }
+
+        let Some(aliases) = aliases else {
+            return false;
+        };
+
+        aliases.iter().any(|(kind, alias)| {
+            &FolderKind::from(kind.trim()) == self
+                && shellexpand_str(alias).eq_ignore_ascii_case(&shellexpand_str(folder))
+        })
+    }
+
     /// Return `true` if the give string matches the Inbox variant.
+    ///
+    /// Folder aliases are not taken into account, see
+    /// [`FolderKind::matches`].
     pub fn matches_inbox(folder: impl AsRef<str>) -> bool {
-        folder
-            .as_ref()
-            .parse::<FolderKind>()
-            .map(|kind| kind.is_inbox())
-            .unwrap_or_default()
+        FolderKind::Inbox.matches(folder, None)
     }
 
     /// Return `true` if the given string matches the Sent variant.
+    ///
+    /// Folder aliases are not taken into account, see
+    /// [`FolderKind::matches`].
     pub fn matches_sent(folder: impl AsRef<str>) -> bool {
-        folder
-            .as_ref()
-            .parse::<FolderKind>()
-            .map(|kind| kind.is_sent())
-            .unwrap_or_default()
+        FolderKind::Sent.matches(folder, None)
     }
 
     /// Return `true` if the given string matches the Drafts variant.
+    ///
+    /// Folder aliases are not taken into account, see
+    /// [`FolderKind::matches`].
     pub fn matches_drafts(folder: impl AsRef<str>) -> bool {
-        folder
-            .as_ref()
-            .parse::<FolderKind>()
-            .map(|kind| kind.is_drafts())
-            .unwrap_or_default()
+        FolderKind::Drafts.matches(folder, None)
     }
 
     /// Return `true` if the given string matches the Trash variant.
+    ///
+    /// Folder aliases are not taken into account, see
+    /// [`FolderKind::matches`].
     pub fn matches_trash(folder: impl AsRef<str>) -> bool {
-        folder
-            .as_ref()
-            .parse::<FolderKind>()
-            .map(|kind| kind.is_trash())
-            .unwrap_or_default()
+        FolderKind::Trash.matches(folder, None)
     }
 
     /// Return the folder kind as string slice.
@@ -161,6 +229,8 @@ pub fn as_str(&self) -> &str {
             Self::Sent => SENT,
             Self::Drafts => DRAFTS,
             Self::Trash => TRASH,
+            Self::Archive => ARCHIVE,
+            Self::Junk => JUNK,
             Self::UserDefined(alias) => alias.as_str(),
         }
     }
@@ -176,6 +246,8 @@ fn from_str(kind: &str) -> Result<Self> {
             kind if kind.eq_ignore_ascii_case(DRAFT) => Ok(Self::Drafts),
             kind if kind.eq_ignore_ascii_case(DRAFTS) => Ok(Self::Drafts),
             kind if kind.eq_ignore_ascii_case(TRASH) => Ok(Self::Trash),
+            kind if kind.eq_ignore_ascii_case(ARCHIVE) => Ok(Self::Archive),
+            kind if kind.eq_ignore_ascii_case(JUNK) => Ok(Self::Junk),
             kind => Err(Error::ParseFolderKindError(kind.to_owned())),
         }
     }
@@ -249,6 +321,22 @@ pub fn is_trash(&self) -> bool {
             .unwrap_or_default()
     }
 
+    /// Return `true` if the folder kind matches the Archive variant.
+    pub fn is_archive(&self) -> bool {
+        self.kind
+            .as_ref()
+            .map(|kind| kind.is_archive())
+            .unwrap_or_default()
+    }
+
+    /// Return `true` if the folder kind matches the Junk variant.
+    pub fn is_junk(&self) -> bool {
+        self.kind
+            .as_ref()
+            .map(|kind| kind.is_junk())
+            .unwrap_or_default()
+    }
+
     /// Return the folder kind as string slice if existing, otherwise
     /// return the folder name as string slice.
     pub fn get_kind_or_name(&self) -> &str {
@@ -400,4 +488,70 @@ fn folder_none_foo_not_equals_none_bar_test() {
     fn folder_none_foo_not_equals_none_bar_test_hash() {
         assert_ne!(hash(folder_none_foo()), hash(folder_none_bar()));
     }
+
+    #[test]
+    fn folder_kind_matches_default_names() {
+        let kinds = [
+            (FolderKind::Inbox, "INBOX"),
+            (FolderKind::Sent, "Sent"),
+            (FolderKind::Drafts, "Drafts"),
+            (FolderKind::Trash, "Trash"),
+            (FolderKind::Archive, "Archive"),
+            (FolderKind::Junk, "Junk"),
+        ];
+
+        for (kind, name) in kinds.iter() {
+            assert!(kind.matches(name, None), "{kind:?} should match {name}");
+            assert!(kind.matches(name.to_lowercase(), None));
+            assert!(kind.matches(name.to_uppercase(), None));
+
+            for (other_kind, _) in kinds.iter().filter(|(other, _)| other != kind) {
+                assert!(!other_kind.matches(name, None));
+            }
+        }
+
+        assert!(FolderKind::Drafts.matches("draft", None));
+        assert!(!FolderKind::Inbox.matches("foo", None));
+    }
+
+    #[test]
+    fn folder_kind_matches_localized_aliases() {
+        let aliases = HashMap::from_iter([
+            ("inbox".to_owned(), "Posteingang".to_owned()),
+            ("Sent".to_owned(), "Gesendet".to_owned()),
+            ("draft".to_owned(), "Entwürfe".to_owned()),
+            ("TRASH".to_owned(), "Papierkorb".to_owned()),
+            ("archive".to_owned(), "Archiv".to_owned()),
+            ("junk".to_owned(), "[Gmail]/Spam".to_owned()),
+            ("foo".to_owned(), "Bar".to_owned()),
+        ]);
+        let aliases = Some(&aliases);
+
+        assert!(FolderKind::Inbox.matches("posteingang", aliases));
+        assert!(FolderKind::Sent.matches("Gesendet", aliases));
+        assert!(FolderKind::Drafts.matches("Entwürfe", aliases));
+        assert!(FolderKind::Trash.matches("PAPIERKORB", aliases));
+        assert!(FolderKind::Archive.matches("Archiv", aliases));
+        assert!(FolderKind::Junk.matches("[gmail]/spam", aliases));
+        assert!(FolderKind::UserDefined("foo".into()).matches("bar", aliases));
+
+        // default names still match
+        assert!(FolderKind::Inbox.matches("INBOX", aliases));
+        assert!(FolderKind::Sent.matches("sent", aliases));
+
+        // aliases of other kinds do not match
+        assert!(!FolderKind::Inbox.matches("Gesendet", aliases));
+        assert!(!FolderKind::Trash.matches("Archiv", aliases));
+        assert!(!FolderKind::Sent.matches("Gesendet", None));
+    }
+
+    #[test]
+    fn folder_kind_matches_inbox() {
+        assert!(FolderKind::matches_inbox("INBOX"));
+        assert!(FolderKind::matches_inbox("inbox"));
+        assert!(!FolderKind::matches_inbox("Sent"));
+        assert!(FolderKind::matches_sent("sent"));
+        assert!(FolderKind::matches_drafts("Draft"));
+        assert!(FolderKind::matches_trash("trash"));
+    }
 }
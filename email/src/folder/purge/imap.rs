@@ -1,6 +1,5 @@
 use async_trait::async_trait;
 use tracing::{debug, info};
-use utf7_imap::encode_utf7_imap as encode_utf7;
 
 use super::PurgeFolder;
 use crate::{imap::ImapContext, AnyResult};
@@ -33,8 +32,8 @@ async fn purge_folder(&self, folder: &str) -> AnyResult<()> {
         let config = &client.account_config;
 
         let folder = config.get_folder_alias(folder);
-        let folder_encoded = encode_utf7(folder.clone());
-        debug!("utf7 encoded folder: {folder_encoded}");
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
 
         client.purge_mailbox(&folder_encoded).await?;
 
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use tracing::{debug, info};
+
+use super::RenameFolder;
+use crate::{imap::ImapContext, AnyResult};
+
+/// The IMAP rename folder feature.
+///
+/// Folder names are given as IMAP mailbox names, using the
+/// hierarchy delimiter of the server. Nested mailboxes are moved by
+/// the server along with their parent.
+///
+/// Renaming INBOX is special: the server moves all its messages to
+/// the new mailbox, but keeps INBOX (empty).
+#[derive(Debug)]
+pub struct RenameImapFolder {
+    ctx: ImapContext,
+}
+
+impl RenameImapFolder {
+    pub fn new(ctx: &ImapContext) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    pub fn new_boxed(ctx: &ImapContext) -> Box<dyn RenameFolder> {
+        Box::new(Self::new(ctx))
+    }
+
+    pub fn some_new_boxed(ctx: &ImapContext) -> Option<Box<dyn RenameFolder>> {
+        Some(Self::new_boxed(ctx))
+    }
+}
+
+#[async_trait]
+impl RenameFolder for RenameImapFolder {
+    async fn rename_folder(&self, from: &str, to: &str) -> AnyResult<()> {
+        info!("renaming imap folder {from} to {to}");
+
+        let mut client = self.ctx.client().await;
+        let config = &client.account_config;
+
+        let from = config.get_folder_alias(from);
+        let from_encoded = client.encode_mailbox(&from);
+        debug!("encoded source folder: {from_encoded}");
+
+        let to = config.get_folder_alias(to);
+        let to_encoded = client.encode_mailbox(&to);
+        debug!("encoded target folder: {to_encoded}");
+
+        client.rename_mailbox(&from_encoded, &to_encoded).await?;
+
+        Ok(())
+    }
+}
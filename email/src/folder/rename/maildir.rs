@@ -0,0 +1,102 @@
+use std::fs;
+
+use async_trait::async_trait;
+use tracing::info;
+
+use super::RenameFolder;
+use crate::{
+    folder::{error::Error, FolderKind},
+    maildir::MaildirContextSync,
+    AnyResult,
+};
+
+pub struct RenameMaildirFolder {
+    ctx: MaildirContextSync,
+}
+
+impl RenameMaildirFolder {
+    pub fn new(ctx: &MaildirContextSync) -> Self {
+        Self { ctx: ctx.clone() }
+    }
+
+    pub fn new_boxed(ctx: &MaildirContextSync) -> Box<dyn RenameFolder> {
+        Box::new(Self::new(ctx))
+    }
+
+    pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn RenameFolder>> {
+        Some(Self::new_boxed(ctx))
+    }
+}
+
+#[async_trait]
+impl RenameFolder for RenameMaildirFolder {
+    async fn rename_folder(&self, from: &str, to: &str) -> AnyResult<()> {
+        info!("renaming maildir folder {from} to {to}");
+
+        let ctx = self.ctx.lock().await;
+        let config = &ctx.account_config;
+        let maildirpp = ctx.maildir_config.maildirpp;
+
+        let from = config.get_folder_alias(from);
+        let to = config.get_folder_alias(to);
+
+        if FolderKind::matches_inbox(&from) || FolderKind::matches_inbox(&to) {
+            return Err(Error::RenameMaildirInboxForbiddenError(from, to).into());
+        }
+
+        // with Maildir++, nested folders are siblings of their parent
+        // prefixed by its name; otherwise they are subdirectories of
+        // their parent, and move along with it
+        let separator = if maildirpp { '.' } else { '/' };
+        let is_nested_in = |name: &str, parent: &str| {
+            name == parent
+                || name
+                    .strip_prefix(parent)
+                    .is_some_and(|name| name.starts_with(separator))
+        };
+
+        let names: Vec<String> = ctx.root.iter().map(|entry| entry.name).collect();
+
+        if !names.iter().any(|name| *name == from) {
+            return Err(Error::RenameMaildirFolderNotFoundError(from).into());
+        }
+
+        if names.iter().any(|name| is_nested_in(name, &to)) {
+            return Err(Error::RenameMaildirFolderAlreadyExistsError(to).into());
+        }
+
+        let renames: Vec<(String, String)> = if maildirpp {
+            names
+                .iter()
+                .filter(|name| is_nested_in(name, &from))
+                .map(|name| {
+                    let renamed = format!("{to}{}", &name[from.len()..]);
+                    (format!(".{name}"), format!(".{renamed}"))
+                })
+                .collect()
+        } else {
+            vec![(from.clone(), to.clone())]
+        };
+
+        for (from, to) in renames {
+            let from = ctx.root.path().join(from);
+            let to = ctx.root.path().join(to);
+
+            if to.exists() {
+                return Err(
+                    Error::RenameMaildirFolderAlreadyExistsError(to.display().to_string()).into(),
+                );
+            }
+
+            if let Some(parent) = to.parent() {
+                fs::create_dir_all(parent).map_err(|err| {
+                    Error::RenameMaildirFolderError(err, from.clone(), to.clone())
+                })?;
+            }
+
+            fs::rename(&from, &to).map_err(|err| Error::RenameMaildirFolderError(err, from, to))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "imap")]
+pub mod imap;
+#[cfg(feature = "maildir")]
+pub mod maildir;
+
+use async_trait::async_trait;
+
+use crate::AnyResult;
+
+#[async_trait]
+pub trait RenameFolder: Send + Sync {
+    /// Rename the given folder.
+    ///
+    /// All emails contained in the given folder are kept, and nested
+    /// folders are moved along with it.
+    async fn rename_folder(&self, from: &str, to: &str) -> AnyResult<()>;
+}
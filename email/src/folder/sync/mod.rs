@@ -21,7 +21,11 @@
 pub use super::{Error, Result};
 use crate::{
     backend::context::BackendContextBuilder,
-    sync::{pool::SyncPoolContext, SyncDestination, SyncEvent},
+    sync::{
+        pool::SyncPoolContext,
+        progress::{SyncProgress, SyncProgressCounter},
+        SyncDestination, SyncEvent,
+    },
 };
 
 pub(crate) async fn sync<L, R>(
@@ -194,9 +198,15 @@ pub(crate) async fn sync<L, R>(
         },
     );
 
+    SyncProgress::StartedFolderSync { total: patch.len() }
+        .emit(&ctx_ref.handler)
+        .await;
+    let counter = Arc::new(SyncProgressCounter::new(patch.len()));
+
     report.names = folders;
     report.patch = FuturesUnordered::from_iter(patch.into_iter().map(|hunk| {
         let ctx = ctx_ref.clone();
+        let counter = counter.clone();
         tokio::spawn(async move {
             let hunk_clone = hunk.clone();
             let handler = ctx.handler.clone();
@@ -241,6 +251,13 @@ pub(crate) async fn sync<L, R>(
                 .emit(&handler)
                 .await;
 
+            counter
+                .tick(&handler, |done, total| SyncProgress::ProcessedFolderHunk {
+                    done,
+                    total,
+                })
+                .await;
+
             match output {
                 Ok(()) => (hunk, None),
                 Err(err) => (hunk, Some(err)),
@@ -0,0 +1,250 @@
+//! # Folder tree
+//!
+//! Module dedicated to the folder tree. Backends list folders as a
+//! flat list of full paths, where the hierarchy is given by a
+//! delimiter (like `A/B/C` for IMAP, or `.A.B.C` for Maildir++). The
+//! main structure of this module is [`FolderNode`], built from
+//! [`Folders::to_tree`].
+
+use super::{Folder, FolderKind, Folders, INBOX};
+
+/// A node of the folder tree.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FolderNode {
+    /// The last segment of the folder path.
+    pub name: String,
+
+    /// The full path of the folder, as known by the backend.
+    pub full_path: String,
+
+    /// The folder kind, if any.
+    pub kind: Option<FolderKind>,
+
+    /// Whether the folder is part of the listed folders.
+    ///
+    /// Intermediate folders that only exist as parents of listed
+    /// folders (maildir does not need them, and IMAP servers may not
+    /// list them) are `false`.
+    pub exists: bool,
+
+    /// The sub-folders, sorted by name.
+    pub children: Vec<FolderNode>,
+}
+
+impl FolderNode {
+    fn new(name: &str, full_path: String) -> Self {
+        Self {
+            name: name.to_owned(),
+            full_path,
+            ..Default::default()
+        }
+    }
+}
+
+impl Folders {
+    /// Build the folder tree, using the given hierarchy delimiter.
+    ///
+    /// A leading delimiter is ignored, so that Maildir++ folders like
+    /// `.A.B` are nested the same way as IMAP folders like `A.B`.
+    /// Missing intermediate folders are added to the tree (see
+    /// [`FolderNode::exists`]). The inbox is matched case-insensitive
+    /// (so `inbox/Sub` is nested into `INBOX`), and comes first.
+    pub fn to_tree(&self, delim: char) -> Vec<FolderNode> {
+        let mut roots = Vec::new();
+
+        for folder in self.iter() {
+            insert_folder(&mut roots, folder, delim);
+        }
+
+        sort_nodes(&mut roots);
+        roots
+    }
+}
+
+/// Insert the given folder into the given nodes, creating missing
+/// intermediate nodes along the way.
+fn insert_folder(roots: &mut Vec<FolderNode>, folder: &Folder, delim: char) {
+    let path = folder.name.as_str();
+    let prefix = if path.starts_with(delim) {
+        &path[..delim.len_utf8()]
+    } else {
+        ""
+    };
+    let segments: Vec<_> = path.split(delim).filter(|seg| !seg.is_empty()).collect();
+
+    let Some((last, parents)) = segments.split_last() else {
+        return;
+    };
+
+    let mut nodes = roots;
+    let mut full_path = String::from(prefix);
+
+    for (depth, seg) in parents.iter().enumerate() {
+        if depth > 0 {
+            full_path.push(delim);
+        }
+        full_path.push_str(seg);
+
+        let is_root = depth == 0;
+        let pos = match nodes
+            .iter()
+            .position(|node| matches_segment(node, seg, is_root))
+        {
+            Some(pos) => pos,
+            None => {
+                nodes.push(FolderNode::new(
+                    &normalize_segment(seg, is_root),
+                    full_path.clone(),
+                ));
+                nodes.len() - 1
+            }
+        };
+
+        nodes = &mut nodes[pos].children;
+    }
+
+    let is_root = parents.is_empty();
+    let node = match nodes
+        .iter()
+        .position(|node| matches_segment(node, last, is_root))
+    {
+        Some(pos) => &mut nodes[pos],
+        None => {
+            nodes.push(FolderNode::default());
+            nodes.last_mut().unwrap()
+        }
+    };
+
+    node.name = normalize_segment(last, is_root);
+    node.full_path = folder.name.clone();
+    node.kind = folder.kind.clone();
+    node.exists = true;
+}
+
+/// Return `true` if the given node matches the given path segment.
+///
+/// The inbox is matched case-insensitive at the root level.
+fn matches_segment(node: &FolderNode, seg: &str, is_root: bool) -> bool {
+    if is_root && seg.eq_ignore_ascii_case(INBOX) {
+        node.name == INBOX
+    } else {
+        node.name == seg
+    }
+}
+
+/// Normalize the given path segment, so that the inbox always
+/// appears as `INBOX`.
+fn normalize_segment(seg: &str, is_root: bool) -> String {
+    if is_root && seg.eq_ignore_ascii_case(INBOX) {
+        INBOX.to_owned()
+    } else {
+        seg.to_owned()
+    }
+}
+
+/// Sort the given nodes recursively by name, the inbox first.
+fn sort_nodes(nodes: &mut [FolderNode]) {
+    nodes.sort_by(|a, b| (a.name != INBOX, &a.name).cmp(&(b.name != INBOX, &b.name)));
+
+    for node in nodes {
+        sort_nodes(&mut node.children);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FolderNode;
+    use crate::folder::{Folder, FolderKind, Folders};
+
+    fn folders(names: &[&str]) -> Folders {
+        Folders::from_iter(names.iter().map(|name| {
+            Folder {
+                kind: name
+                    .parse()
+                    .ok()
+                    .filter(|kind: &FolderKind| !kind.is_user_defined()),
+                name: name.to_string(),
+                desc: String::new(),
+            }
+        }))
+    }
+
+    /// Flatten the given tree as `(depth, name, full path, exists)`.
+    fn flatten(nodes: &[FolderNode], depth: usize) -> Vec<(usize, &str, &str, bool)> {
+        nodes
+            .iter()
+            .flat_map(|node| {
+                let mut nodes = vec![(
+                    depth,
+                    node.name.as_str(),
+                    node.full_path.as_str(),
+                    node.exists,
+                )];
+                nodes.extend(flatten(&node.children, depth + 1));
+                nodes
+            })
+            .collect()
+    }
+
+    #[test]
+    fn imap_tree_with_gaps() {
+        let tree = folders(&[
+            "Work/2024/Q1",
+            "inbox/Lists",
+            "Trash",
+            "Work",
+            "INBOX",
+            "A/B/C",
+        ])
+        .to_tree('/');
+
+        assert_eq!(
+            flatten(&tree, 0),
+            vec![
+                (0, "INBOX", "INBOX", true),
+                (1, "Lists", "inbox/Lists", true),
+                (0, "A", "A", false),
+                (1, "B", "A/B", false),
+                (2, "C", "A/B/C", true),
+                (0, "Trash", "Trash", true),
+                (0, "Work", "Work", true),
+                (1, "2024", "Work/2024", false),
+                (2, "Q1", "Work/2024/Q1", true),
+            ]
+        );
+
+        assert_eq!(tree[0].kind, Some(FolderKind::Inbox));
+        assert_eq!(tree[2].kind, Some(FolderKind::Trash));
+        assert_eq!(tree[1].kind, None);
+    }
+
+    #[test]
+    fn maildir_tree_with_gaps() {
+        let tree = folders(&[".A.B.C", ".A", "INBOX", ".Sent"]).to_tree('.');
+
+        assert_eq!(
+            flatten(&tree, 0),
+            vec![
+                (0, "INBOX", "INBOX", true),
+                (0, "A", ".A", true),
+                (1, "B", ".A.B", false),
+                (2, "C", ".A.B.C", true),
+                (0, "Sent", ".Sent", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn imap_tree_with_dot_delimiter() {
+        let tree = folders(&["INBOX.Archive.2024", "INBOX"]).to_tree('.');
+
+        assert_eq!(
+            flatten(&tree, 0),
+            vec![
+                (0, "INBOX", "INBOX", true),
+                (1, "Archive", "INBOX.Archive", false),
+                (2, "2024", "INBOX.Archive.2024", true),
+            ]
+        );
+    }
+}
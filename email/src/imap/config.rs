@@ -7,9 +7,14 @@
 use super::{Error, Result};
 #[cfg(feature = "oauth2")]
 use crate::account::config::oauth2::OAuth2Config;
-use crate::{account::config::passwd::PasswordConfig, tls::Encryption};
+use crate::{
+    account::config::passwd::{PasswordCache, PasswordConfig},
+    tls::Encryption,
+};
 
-/// Errors related to the IMAP backend configuration.
+/// The default maximum number of UIDs sent in a single FETCH
+/// command.
+pub const DEFAULT_FETCH_CHUNK_SIZE: usize = 500;
 
 /// The IMAP backend configuration.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -42,6 +47,14 @@ pub struct ImapConfig {
     /// See [ImapAuthConfig].
     pub auth: ImapAuthConfig,
 
+    /// The IMAP password cache, with the time-to-live of the cached
+    /// password in seconds (0 keeps it for the lifetime of the
+    /// configuration).
+    ///
+    /// Disabled by default: the password is resolved on every
+    /// connection. See [`PasswordCache`].
+    pub passwd_cache: Option<PasswordCache>,
+
     /// The IMAP extensions configuration.
     pub extensions: Option<ImapExtensionsConfig>,
 
@@ -56,6 +69,14 @@ pub struct ImapConfig {
     /// Defines the number of clients that are created and managed
     /// simultaneously by the IMAP context. Defaults to 1.
     pub clients_pool_size: Option<u8>,
+
+    /// The IMAP FETCH chunk size.
+    ///
+    /// Defines the maximum number of UIDs sent in a single FETCH
+    /// command. Larger sets are split into multiple FETCH commands,
+    /// which prevents exceeding server command-length limits.
+    /// Defaults to 500.
+    pub fetch_chunk_size: Option<usize>,
 }
 
 impl ImapConfig {
@@ -63,6 +84,12 @@ pub fn clients_pool_size(&self) -> u8 {
         self.clients_pool_size.unwrap_or(1)
     }
 
+    pub fn fetch_chunk_size(&self) -> usize {
+        self.fetch_chunk_size
+            .filter(|size| *size > 0)
+            .unwrap_or(DEFAULT_FETCH_CHUNK_SIZE)
+    }
+
     pub fn send_id_after_auth(&self) -> bool {
         self.extensions
             .as_ref()
@@ -71,6 +98,16 @@ pub fn send_id_after_auth(&self) -> bool {
             .unwrap_or_default()
     }
 
+    /// Return `true` if the UTF8=ACCEPT extension should be enabled
+    /// when advertised by the server. Defaults to `true`.
+    pub fn enable_utf8_accept(&self) -> bool {
+        self.extensions
+            .as_ref()
+            .and_then(|ext| ext.utf8.as_ref())
+            .and_then(|utf8| utf8.accept)
+            .unwrap_or(true)
+    }
+
     /// Return `true` if TLS or StartTLS is enabled.
     pub fn is_encryption_enabled(&self) -> bool {
         matches!(
@@ -94,7 +131,20 @@ pub fn is_encryption_disabled(&self) -> bool {
     /// Authentication credentials can be either a password or an
     /// OAuth 2.0 access token.
     pub async fn build_credentials(&self) -> Result<String> {
-        self.auth.build_credentials().await
+        self.auth
+            .build_credentials_with_cache(self.passwd_cache.as_ref())
+            .await
+    }
+
+    /// Reset IMAP secrets, and clear the password cache.
+    ///
+    /// See [`ImapAuthConfig::reset`].
+    pub async fn reset(&self) -> Result<()> {
+        if let Some(cache) = &self.passwd_cache {
+            cache.clear();
+        }
+
+        self.auth.reset().await
     }
 
     /// Find the IMAP watch timeout.
@@ -152,9 +202,18 @@ pub async fn reset(&self) -> Result<()> {
     /// Authentication credentials can be either a password or an
     /// OAuth 2.0 access token.
     pub async fn build_credentials(&self) -> Result<String> {
+        self.build_credentials_with_cache(None).await
+    }
+
+    /// Builds authentication credentials, using the given password
+    /// cache.
+    async fn build_credentials_with_cache(&self, cache: Option<&PasswordCache>) -> Result<String> {
         match self {
             ImapAuthConfig::Password(passwd) => {
-                let passwd = passwd.get().await.map_err(Error::GetPasswdImapError)?;
+                let passwd = passwd
+                    .get_cached(cache)
+                    .await
+                    .map_err(Error::GetPasswdImapError)?;
                 let passwd = passwd
                     .lines()
                     .next()
@@ -275,6 +334,7 @@ pub fn find_timeout(&self) -> Option<u64> {
 )]
 pub struct ImapExtensionsConfig {
     id: Option<ImapIdExtensionConfig>,
+    utf8: Option<ImapUtf8ExtensionConfig>,
 }
 
 /// The IMAP configuration dedicated to the ID extension.
@@ -291,3 +351,21 @@ pub struct ImapIdExtensionConfig {
     /// authentication.
     send_after_auth: Option<bool>,
 }
+
+/// The IMAP configuration dedicated to the UTF8=ACCEPT extension.
+///
+/// https://www.rfc-editor.org/rfc/rfc6855.html
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "derive",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct ImapUtf8ExtensionConfig {
+    /// Enables the UTF8=ACCEPT extension straight after
+    /// authentication, when advertised by the server. Mailbox names
+    /// are then exchanged in UTF-8 instead of modified UTF-7.
+    ///
+    /// Defaults to `true`.
+    accept: Option<bool>,
+}
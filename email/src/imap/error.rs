@@ -1,4 +1,4 @@
-use std::{any::Any, collections::HashSet, result};
+use std::{any::Any, collections::HashSet, num::NonZeroU32, result};
 
 use imap_client::{
     client::tokio::ClientError,
@@ -11,7 +11,11 @@
 use thiserror::Error;
 use tokio::task::JoinError;
 
-use crate::{account, AnyBoxedError, AnyError};
+use crate::{
+    account,
+    sasl::{self, scram::ScramMechanism},
+    AnyBoxedError, AnyError,
+};
 
 /// The global `Result` alias of the module.
 pub type Result<T> = result::Result<T, Error>;
@@ -76,6 +80,8 @@ pub enum Error {
     LoginError(#[source] ClientError),
     #[error("cannot authenticate to IMAP server using SASL PLAIN mechanism")]
     AuthenticatePlainError(#[source] ClientError),
+    #[error("cannot authenticate to IMAP server using SASL {1} mechanism")]
+    AuthenticateScramSaslError(#[source] sasl::Error, ScramMechanism),
     #[error("cannot authenticate to IMAP server using SASL XOAUTH2 mechanism")]
     AuthenticateXOauth2Error(#[source] ClientError),
     #[error("cannot authenticate to IMAP server using SASL OAUTHBEARER mechanism")]
@@ -85,12 +91,28 @@ pub enum Error {
     CreateMailboxError(#[source] ClientError),
     #[error("cannot create IMAP mailbox: request timed out")]
     CreateMailboxTimedOutError,
+    #[error("cannot get status of IMAP mailbox")]
+    StatusMailboxError(#[source] ClientError),
+    #[error("cannot get status of IMAP mailbox: request timed out")]
+    StatusMailboxTimedOutError,
 
     #[error("cannot select IMAP mailbox")]
     SelectMailboxError(#[source] ClientError),
     #[error("cannot select IMAP mailbox: request timed out")]
     SelectMailboxTimedOutError,
 
+    #[error("cannot unselect IMAP mailbox")]
+    UnselectMailboxError(#[source] ClientError),
+    #[error("cannot unselect IMAP mailbox: request timed out")]
+    UnselectMailboxTimedOutError,
+    #[error("cannot unselect IMAP mailbox: UNSELECT extension not supported")]
+    UnselectMailboxNotSupportedError,
+
+    #[error("cannot close IMAP mailbox")]
+    CloseMailboxError(#[source] ClientError),
+    #[error("cannot close IMAP mailbox: request timed out")]
+    CloseMailboxTimedOutError,
+
     #[error("cannot examine IMAP mailbox")]
     ExamineMailboxError(#[source] ClientError),
     #[error("cannot examine IMAP mailbox: request timed out")]
@@ -111,10 +133,19 @@ pub enum Error {
     #[error("cannot delete IMAP mailbox: request timed out")]
     DeleteMailboxTimedOutError,
 
+    #[error("cannot rename IMAP mailbox")]
+    RenameMailboxError(#[source] ClientError),
+    #[error("cannot rename IMAP mailbox: request timed out")]
+    RenameMailboxTimedOutError,
+
     #[error("cannot fetch IMAP messages")]
     FetchMessagesError(#[source] ClientError),
     #[error("cannot fetch IMAP messages: request timed out")]
     FetchMessagesTimedOutError,
+    #[error("cannot fetch IMAP envelope: invalid UID {0}")]
+    FetchEnvelopeInvalidUidError(u32),
+    #[error("cannot fetch partial body of IMAP message {0}: body not found")]
+    FetchPartialBodyNotFoundError(NonZeroU32),
 
     #[error("cannot thread IMAP messages")]
     ThreadMessagesError(#[source] ClientError),
@@ -129,6 +160,10 @@ pub enum Error {
     AddMessageError(#[source] ClientError),
     #[error("cannot add IMAP message: request timed out")]
     AddMessageTimedOutError,
+    #[error("cannot parse internal date of IMAP message")]
+    ParseMessageDateError(#[source] ValidationError),
+    #[error("cannot parse IMAP message literal")]
+    ParseMessageLiteralError(#[source] ValidationError),
     #[error("cannot copy IMAP message(s)")]
     CopyMessagesError(#[source] ClientError),
     #[error("cannot copy IMAP message(s): request timed out")]
@@ -141,6 +176,10 @@ pub enum Error {
     NoOpError(#[source] ClientError),
     #[error("cannot execute no-operation: request timed out")]
     NoOpTimedOutError,
+    #[error("cannot log out from IMAP server")]
+    LogoutError(#[source] ClientError),
+    #[error("cannot log out from IMAP server: request timed out")]
+    LogoutTimedOutError,
 
     #[error("cannot exchange IMAP client/server ids")]
     ExchangeIdsError(#[source] ClientError),
@@ -1,5 +1,8 @@
 pub mod config;
 mod error;
+pub mod redact;
+pub mod status;
+mod tasks;
 
 use std::{
     collections::HashMap, env, fmt, io::ErrorKind::ConnectionReset, num::NonZeroU32, sync::Arc,
@@ -7,20 +10,28 @@
 };
 
 use async_trait::async_trait;
-use futures::{stream::FuturesUnordered, StreamExt};
+use chrono::{DateTime, FixedOffset};
+use futures::{
+    stream::{self, FuturesUnordered},
+    Stream, StreamExt,
+};
 use imap_client::{
     client::tokio::{Client, ClientError},
     imap_next::imap_types::{
         auth::AuthMechanism,
-        core::{IString, NString, Vec1},
+        core::{IString, Literal, LiteralOrLiteral8, NString, Vec1},
+        datetime::DateTime as ImapDateTime,
         extensions::{
+            enable::{CapabilityEnable, Utf8Kind},
             sort::SortCriterion,
             thread::{Thread, ThreadingAlgorithm},
         },
-        fetch::MessageDataItem,
+        fetch::{MacroOrMessageDataItemNames, MessageDataItem, MessageDataItemName, Section},
         flag::{Flag, StoreType},
+        mailbox::Mailbox,
+        response::Capability,
         search::SearchKey,
-        sequence::SequenceSet,
+        sequence::{SeqOrUid, Sequence, SequenceSet},
     },
     stream::Error as StreamError,
     tasks::{tasks::select::SelectDataUnvalidated, SchedulerError},
@@ -32,16 +43,18 @@
     time::sleep,
 };
 use tracing::{debug, instrument, trace, warn};
+use utf7_imap::encode_utf7_imap as encode_utf7;
+use zeroize::Zeroizing;
 
 use self::config::{ImapAuthConfig, ImapConfig};
 #[doc(inline)]
 pub use self::error::{Error, Result};
-#[cfg(feature = "oauth2")]
-use crate::account::config::oauth2::OAuth2Method;
 #[cfg(feature = "thread")]
 use crate::envelope::thread::{imap::ThreadImapEnvelopes, ThreadEnvelopes};
 #[cfg(feature = "watch")]
 use crate::envelope::watch::{imap::WatchImapEnvelopes, WatchEnvelopes};
+#[cfg(feature = "oauth2")]
+use crate::{account::config::oauth2::OAuth2Method, sasl};
 use crate::{
     account::config::AccountConfig,
     backend::{
@@ -61,10 +74,12 @@
     },
     folder::{
         add::{imap::AddImapFolder, AddFolder},
+        cursor::{imap::GetImapFolderCursor, GetFolderCursor},
         delete::{imap::DeleteImapFolder, DeleteFolder},
         expunge::{imap::ExpungeImapFolder, ExpungeFolder},
         list::{imap::ListImapFolders, ListFolders},
         purge::{imap::PurgeImapFolder, PurgeFolder},
+        rename::{imap::RenameImapFolder, RenameFolder},
         Folders,
     },
     message::{
@@ -72,13 +87,17 @@
         copy::{imap::CopyImapMessages, CopyMessages},
         delete::{imap::DeleteImapMessages, DeleteMessages},
         get::{imap::GetImapMessages, GetMessages},
-        imap::{FETCH_MESSAGES, PEEK_MESSAGES},
+        imap::{
+            join_partial_message, peek_partial_body_item, peek_partial_messages_items,
+            FETCH_MESSAGES, PEEK_MESSAGES,
+        },
         peek::{imap::PeekImapMessages, PeekMessages},
         r#move::{imap::MoveImapMessages, MoveMessages},
         remove::{imap::RemoveImapMessages, RemoveMessages},
         Messages,
     },
     retry::{self, Retry, RetryState},
+    sasl::scram::{ScramClient, ScramMechanism},
     tls::{Encryption, Tls, TlsProvider},
     AnyResult,
 };
@@ -120,6 +139,17 @@
     ]
 });
 
+/// The mode used to select an IMAP mailbox.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImapMailboxMode {
+    /// The mailbox is selected using SELECT, and can be modified.
+    ReadWrite,
+
+    /// The mailbox is selected using EXAMINE, and cannot be
+    /// modified. The server does not clear the `\Recent` flag.
+    ReadOnly,
+}
+
 enum ImapRetryState<T> {
     Retry,
     TimedOut,
@@ -145,8 +175,8 @@ pub struct ImapClient {
     /// The next gen IMAP client.
     inner: Client,
 
-    /// The selected mailbox.
-    mailbox: Option<String>,
+    /// The selected mailbox, with the mode used to select it.
+    mailbox: Option<(String, ImapMailboxMode)>,
 
     retry: Retry,
 }
@@ -188,13 +218,29 @@ async fn retry<T>(
 
                 self.inner = self.client_builder.build().await?;
 
-                if let Some(mbox) = &self.mailbox {
-                    self.inner
-                        .select(mbox.clone())
-                        .await
-                        .map_err(Error::SelectMailboxError)?;
+                // the new connection has no mailbox selected yet, the
+                // selected mailbox is restored only if the selection
+                // succeeds
+                let mailbox = self.mailbox.take();
+
+                match &mailbox {
+                    Some((mbox, ImapMailboxMode::ReadWrite)) => {
+                        self.inner
+                            .select(mbox.clone())
+                            .await
+                            .map_err(Error::SelectMailboxError)?;
+                    }
+                    Some((mbox, ImapMailboxMode::ReadOnly)) => {
+                        self.inner
+                            .examine(mbox.clone())
+                            .await
+                            .map_err(Error::ExamineMailboxError)?;
+                    }
+                    None => (),
                 }
 
+                self.mailbox = mailbox;
+
                 self.retry.attempts = 0;
                 Ok(ImapRetryState::Retry)
             }
@@ -204,10 +250,42 @@ async fn retry<T>(
         }
     }
 
+    /// Return `true` if the UTF8=ACCEPT extension (RFC 6855) is
+    /// enabled.
+    pub fn utf8_enabled(&self) -> bool {
+        self.client_builder.utf8_enabled()
+    }
+
+    /// Encode the given mailbox name for this client.
+    ///
+    /// Mailbox names are encoded using modified UTF-7, unless the
+    /// UTF8=ACCEPT extension is enabled.
+    pub fn encode_mailbox(&self, mbox: impl ToString) -> String {
+        encode_mailbox(mbox.to_string(), self.utf8_enabled())
+    }
+
     pub fn ext_sort_supported(&self) -> bool {
         self.inner.state.ext_sort_supported()
     }
 
+    /// Return `true` if the server advertises the UNSELECT extension
+    /// (RFC 3691).
+    pub fn ext_unselect_supported(&self) -> bool {
+        self.inner
+            .state
+            .capabilities_iter()
+            .any(|capability| matches!(capability, Capability::Unselect))
+    }
+
+    /// Return `true` if the server advertises the CONDSTORE extension
+    /// (RFC 7162), which QRESYNC implies.
+    pub fn ext_condstore_supported(&self) -> bool {
+        self.inner
+            .state
+            .capabilities_iter()
+            .any(|capability| matches!(capability, Capability::CondStore | Capability::QResync))
+    }
+
     #[instrument(skip_all, fields(client = self.id))]
     pub async fn noop(&mut self) -> Result<()> {
         self.retry.reset();
@@ -223,8 +301,53 @@ pub async fn noop(&mut self) -> Result<()> {
         }
     }
 
+    /// Log out from the server, which then closes the connection.
+    ///
+    /// Unlike other requests, the client does not re-connect on
+    /// failure: a disconnected client is already logged out.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn logout(&mut self) -> Result<()> {
+        self.mailbox = None;
+
+        match self.retry.timeout(tasks::logout(&mut self.inner)).await {
+            Ok(res) => res.map_err(Error::LogoutError),
+            Err(_) => Err(Error::LogoutTimedOutError),
+        }
+    }
+
+    /// Return the selected mailbox, with the mode used to select it.
+    pub fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)> {
+        self.mailbox
+            .as_ref()
+            .map(|(mbox, mode)| (mbox.as_str(), *mode))
+    }
+
+    /// Select the given mailbox using the given mode, unless it is
+    /// already selected in a compatible mode.
+    ///
+    /// A mailbox selected in read-write mode is compatible with
+    /// both modes. This saves one round-trip for operations that do
+    /// not need fresh mailbox data: use [`ImapClient::select_mailbox`]
+    /// or [`ImapClient::examine_mailbox`] to get up-to-date data
+    /// (like the number of messages).
+    pub async fn ensure_mailbox(
+        &mut self,
+        mbox: impl ToString,
+        mode: ImapMailboxMode,
+    ) -> Result<()> {
+        ensure_mailbox(self, &mbox.to_string(), mode).await
+    }
+
+    /// Select the given mailbox in read-write mode, using SELECT.
+    ///
+    /// See [`ImapClient::examine_mailbox`] for operations that do not
+    /// modify the mailbox.
     #[instrument(skip_all, fields(client = self.id))]
     pub async fn select_mailbox(&mut self, mbox: impl ToString) -> Result<SelectDataUnvalidated> {
+        leave_mailbox(self, &mbox.to_string()).await?;
+
+        // a failed selection leaves no mailbox selected
+        self.mailbox = None;
         self.retry.reset();
 
         let data = loop {
@@ -240,16 +363,76 @@ pub async fn select_mailbox(&mut self, mbox: impl ToString) -> Result<SelectData
             }
         }?;
 
-        self.mailbox = Some(mbox.to_string());
+        self.mailbox = Some((mbox.to_string(), ImapMailboxMode::ReadWrite));
 
         Ok(data)
     }
 
+    /// Leave the selected mailbox without expunging it.
+    ///
+    /// This requires the UNSELECT extension. See
+    /// [`ImapClient::close_mailbox`] to leave the selected mailbox
+    /// while expunging it.
     #[instrument(skip_all, fields(client = self.id))]
-    pub async fn examine_mailbox(&mut self, mbox: impl ToString) -> Result<SelectDataUnvalidated> {
+    pub async fn unselect_mailbox(&mut self) -> Result<()> {
+        if !self.ext_unselect_supported() {
+            return Err(Error::UnselectMailboxNotSupportedError);
+        }
+
+        self.retry.reset();
+
+        loop {
+            let res = self.retry.timeout(tasks::unselect(&mut self.inner)).await;
+
+            match self.retry(res).await? {
+                ImapRetryState::Retry => continue,
+                ImapRetryState::TimedOut => break Err(Error::UnselectMailboxTimedOutError),
+                ImapRetryState::Ok(res) => break res.map_err(Error::UnselectMailboxError),
+            }
+        }?;
+
+        self.mailbox = None;
+
+        Ok(())
+    }
+
+    /// Leave the selected mailbox, permanently removing messages
+    /// flagged as `\Deleted`.
+    ///
+    /// See [`ImapClient::unselect_mailbox`] to leave the selected
+    /// mailbox without expunging it.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn close_mailbox(&mut self) -> Result<()> {
         self.retry.reset();
 
         loop {
+            let res = self.retry.timeout(tasks::close(&mut self.inner)).await;
+
+            match self.retry(res).await? {
+                ImapRetryState::Retry => continue,
+                ImapRetryState::TimedOut => break Err(Error::CloseMailboxTimedOutError),
+                ImapRetryState::Ok(res) => break res.map_err(Error::CloseMailboxError),
+            }
+        }?;
+
+        self.mailbox = None;
+
+        Ok(())
+    }
+
+    /// Select the given mailbox in read-only mode, using EXAMINE.
+    ///
+    /// This lets the server know that no modification is coming, and
+    /// prevents the `\Recent` flag from being cleared.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn examine_mailbox(&mut self, mbox: impl ToString) -> Result<SelectDataUnvalidated> {
+        leave_mailbox(self, &mbox.to_string()).await?;
+
+        // a failed examination leaves no mailbox selected
+        self.mailbox = None;
+        self.retry.reset();
+
+        let data = loop {
             let res = self
                 .retry
                 .timeout(self.inner.examine(mbox.to_string()))
@@ -260,7 +443,11 @@ pub async fn examine_mailbox(&mut self, mbox: impl ToString) -> Result<SelectDat
                 ImapRetryState::TimedOut => break Err(Error::ExamineMailboxTimedOutError),
                 ImapRetryState::Ok(res) => break res.map_err(Error::ExamineMailboxError),
             }
-        }
+        }?;
+
+        self.mailbox = Some((mbox.to_string(), ImapMailboxMode::ReadOnly));
+
+        Ok(data)
     }
 
     #[instrument(skip_all, fields(client = self.id))]
@@ -295,7 +482,7 @@ pub async fn list_all_mailboxes(&mut self, config: &AccountConfig) -> Result<Fol
             }
         }?;
 
-        let folders = Folders::from_imap_mailboxes(config, mboxes);
+        let folders = Folders::from_imap_mailboxes_with_utf8(config, mboxes, self.utf8_enabled());
 
         Ok(folders)
     }
@@ -354,7 +541,48 @@ pub async fn delete_mailbox(&mut self, mbox: impl ToString) -> Result<()> {
                 ImapRetryState::TimedOut => break Err(Error::DeleteMailboxTimedOutError),
                 ImapRetryState::Ok(res) => break res.map_err(Error::DeleteMailboxError),
             }
+        }?;
+
+        if matches!(self.selected_mailbox(), Some((selected, _)) if selected == mbox.to_string()) {
+            self.mailbox = None;
+        }
+
+        Ok(())
+    }
+
+    /// Rename the given mailbox, using the RENAME command.
+    ///
+    /// Renaming INBOX is special (RFC 3501): all its messages are
+    /// moved to the new mailbox, but INBOX itself is kept (empty).
+    /// In both cases, if the renamed mailbox is selected, it is
+    /// considered as unselected since its content changed.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn rename_mailbox(&mut self, from: impl ToString, to: impl ToString) -> Result<()> {
+        let from = from.to_string();
+        let from_mailbox = Mailbox::try_from(from.clone())
+            .map_err(|err| Error::ParseMailboxError(err, from.clone()))?;
+        let to = to.to_string();
+        let to_mailbox =
+            Mailbox::try_from(to.clone()).map_err(|err| Error::ParseMailboxError(err, to))?;
+
+        self.retry.reset();
+
+        loop {
+            let task = tasks::rename(&mut self.inner, from_mailbox.clone(), to_mailbox.clone());
+            let res = self.retry.timeout(task).await;
+
+            match self.retry(res).await? {
+                ImapRetryState::Retry => continue,
+                ImapRetryState::TimedOut => break Err(Error::RenameMailboxTimedOutError),
+                ImapRetryState::Ok(res) => break res.map_err(Error::RenameMailboxError),
+            }
+        }?;
+
+        if matches!(self.selected_mailbox(), Some((selected, _)) if selected == from) {
+            self.mailbox = None;
         }
+
+        Ok(())
     }
 
     #[instrument(skip_all, fields(client = self.id))]
@@ -408,10 +636,10 @@ pub async fn fetch_envelopes_map(
 
     #[instrument(skip_all, fields(client = self.id))]
     pub async fn fetch_first_envelope(&mut self, uid: u32) -> Result<Envelope> {
+        let uid = NonZeroU32::new(uid).ok_or(Error::FetchEnvelopeInvalidUidError(uid))?;
+
         let items = loop {
-            let task = self
-                .inner
-                .uid_fetch_first(uid.try_into().unwrap(), FETCH_ENVELOPES.clone());
+            let task = self.inner.uid_fetch_first(uid, FETCH_ENVELOPES.clone());
 
             let res = self.retry.timeout(task).await;
 
@@ -741,12 +969,62 @@ pub async fn add_message(
         id.ok_or(Error::FindAppendedMessageUidError)
     }
 
+    /// Add the given message to the given mailbox, using the given
+    /// flags and internal date.
+    ///
+    /// Unlike [`ImapClient::add_message`], the UID of the appended
+    /// message is not returned.
     #[instrument(skip_all, fields(client = self.id))]
-    pub async fn fetch_messages(&mut self, uids: SequenceSet) -> Result<Messages> {
+    pub async fn add_message_with_date(
+        &mut self,
+        mbox: impl ToString,
+        flags: impl IntoIterator<Item = Flag<'static>>,
+        msg: impl AsRef<[u8]>,
+        date: DateTime<FixedOffset>,
+    ) -> Result<()> {
+        let mbox = mbox.to_string();
+        let mailbox =
+            Mailbox::try_from(mbox.clone()).map_err(|err| Error::ParseMailboxError(err, mbox))?;
+        let flags: Vec<_> = flags.into_iter().collect();
+        let date = ImapDateTime::try_from(date).map_err(Error::ParseMessageDateError)?;
+        let msg = Literal::try_from(msg.as_ref().to_vec())
+            .map(LiteralOrLiteral8::Literal)
+            .map_err(Error::ParseMessageLiteralError)?;
+
+        self.retry.reset();
+
+        loop {
+            let task = tasks::append(
+                &mut self.inner,
+                mailbox.clone(),
+                flags.clone(),
+                date.clone(),
+                msg.clone(),
+            );
+
+            let res = self.retry.timeout(task).await;
+
+            match self.retry(res).await? {
+                ImapRetryState::Retry => continue,
+                ImapRetryState::TimedOut => break Err(Error::AddMessageTimedOutError),
+                ImapRetryState::Ok(res) => break res.map_err(Error::AddMessageError),
+            }
+        }
+    }
+
+    /// Fetch the given UIDs using the given FETCH items, in a single
+    /// FETCH command.
+    ///
+    /// The returned items follow the order of the given UIDs.
+    async fn uid_fetch_messages(
+        &mut self,
+        uids: SequenceSet,
+        items: &MacroOrMessageDataItemNames<'static>,
+    ) -> Result<Vec<Vec1<MessageDataItem<'static>>>> {
         let mut fetches = loop {
             let res = self
                 .retry
-                .timeout(self.inner.uid_fetch(uids.clone(), FETCH_MESSAGES.clone()))
+                .timeout(self.inner.uid_fetch(uids.clone(), items.clone()))
                 .await;
 
             match self.retry(res).await? {
@@ -756,35 +1034,95 @@ pub async fn fetch_messages(&mut self, uids: SequenceSet) -> Result<Messages> {
             }
         }?;
 
+        // unbounded UIDs cannot be expanded, so fetches are then
+        // sorted by UID
+        if is_unbounded(&uids) {
+            let mut fetches: Vec<_> = fetches.into_iter().collect();
+            fetches.sort_by_key(|(uid, _)| *uid);
+            return Ok(fetches.into_iter().map(|(_, items)| items).collect());
+        }
+
         let fetches: Vec<_> = uids
             .iter(NonZeroU32::MAX)
             .filter_map(|ref uid| fetches.remove(uid))
             .collect();
 
+        Ok(fetches)
+    }
+
+    /// Fetch messages matching the given UIDs.
+    ///
+    /// Large sets of UIDs are split into multiple FETCH commands, see
+    /// [`ImapConfig::fetch_chunk_size`].
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn fetch_messages(&mut self, uids: SequenceSet) -> Result<Messages> {
+        let chunk_size = self.imap_config.fetch_chunk_size();
+        let fetches = fetch_chunks(self, &uids, chunk_size, &FETCH_MESSAGES).await?;
         Ok(Messages::from(fetches))
     }
 
+    /// Same as [`ImapClient::fetch_messages`], without setting the
+    /// `\Seen` flag.
     #[instrument(skip_all, fields(client = self.id))]
     pub async fn peek_messages(&mut self, uids: SequenceSet) -> Result<Messages> {
-        let mut fetches = loop {
-            let res = self
-                .retry
-                .timeout(self.inner.uid_fetch(uids.clone(), PEEK_MESSAGES.clone()))
-                .await;
-
-            match self.retry(res).await? {
-                ImapRetryState::Retry => continue,
-                ImapRetryState::TimedOut => break Err(Error::FetchMessagesTimedOutError),
-                ImapRetryState::Ok(res) => break res.map_err(Error::FetchMessagesError),
-            }
-        }?;
+        let chunk_size = self.imap_config.fetch_chunk_size();
+        let fetches = fetch_chunks(self, &uids, chunk_size, &PEEK_MESSAGES).await?;
+        Ok(Messages::from(fetches))
+    }
 
-        let fetches: Vec<_> = uids
-            .iter(NonZeroU32::MAX)
-            .filter_map(|ref uid| fetches.remove(uid))
+    /// Peek the headers and at most the first `max_body_size` bytes
+    /// of the body of messages matching the given UIDs.
+    ///
+    /// Useful to build previews of large messages without
+    /// downloading their attachments. Like
+    /// [`ImapClient::fetch_messages`], large sets of UIDs are split
+    /// into multiple FETCH commands.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn peek_partial_messages(
+        &mut self,
+        uids: SequenceSet,
+        max_body_size: NonZeroU32,
+    ) -> Result<Messages> {
+        let chunk_size = self.imap_config.fetch_chunk_size();
+        let items = peek_partial_messages_items(max_body_size);
+        let fetches = fetch_chunks(self, &uids, chunk_size, &items).await?;
+        let msgs: Vec<_> = fetches
+            .iter()
+            .map(|items| join_partial_message(items.as_ref()))
             .collect();
+        Ok(Messages::from(msgs))
+    }
 
-        Ok(Messages::from(fetches))
+    /// Fetch at most `length` bytes of the given section of the
+    /// message matching the given UID, starting at `offset`
+    /// (`BODY.PEEK[section]<offset.length>`).
+    ///
+    /// The whole message is fetched when no section is given. This
+    /// does not set the `\Seen` flag.
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn fetch_partial_body(
+        &mut self,
+        uid: NonZeroU32,
+        section: Option<Section<'static>>,
+        offset: u32,
+        length: NonZeroU32,
+    ) -> Result<PartialBody> {
+        fetch_partial_body(self, uid, section, offset, length).await
+    }
+
+    /// Fetch messages matching the given UIDs as a stream.
+    ///
+    /// Unlike [`ImapClient::fetch_messages`], messages are not
+    /// collected all at once: UIDs are fetched by batches of the
+    /// given size, and each batch is yielded as soon as it has been
+    /// received from the server. Memory usage is then bounded by the
+    /// size of one batch. The stream ends after the first error.
+    pub fn fetch_messages_stream(
+        &mut self,
+        uids: SequenceSet,
+        batch_size: usize,
+    ) -> impl Stream<Item = Result<Messages>> + '_ {
+        stream_chunks(self, &uids, batch_size).map(|fetches| fetches.map(Messages::from))
     }
 
     #[instrument(skip_all, fields(client = self.id))]
@@ -820,6 +1158,334 @@ pub async fn move_messages(&mut self, uids: SequenceSet, mbox: impl ToString) ->
     }
 }
 
+/// Trait used to fetch one chunk of messages, in a single FETCH
+/// command.
+///
+/// This abstraction exists so that the chunking logic of
+/// [`ImapClient::fetch_messages`] and
+/// [`ImapClient::fetch_messages_stream`] can be tested without a real
+/// IMAP server.
+#[async_trait]
+trait FetchMessagesChunk: Send {
+    type Item: Send;
+
+    async fn fetch_messages_chunk(
+        &mut self,
+        uids: SequenceSet,
+        items: &MacroOrMessageDataItemNames<'static>,
+    ) -> Result<Vec<Self::Item>>;
+}
+
+#[async_trait]
+impl FetchMessagesChunk for ImapClient {
+    type Item = Vec1<MessageDataItem<'static>>;
+
+    async fn fetch_messages_chunk(
+        &mut self,
+        uids: SequenceSet,
+        items: &MacroOrMessageDataItemNames<'static>,
+    ) -> Result<Vec<Self::Item>> {
+        self.uid_fetch_messages(uids, items).await
+    }
+}
+
+/// Trait used to switch between mailboxes.
+///
+/// This abstraction exists so that mailbox transitions of
+/// [`ImapClient`] can be tested without a real IMAP server.
+#[async_trait]
+trait SelectMailbox: Send {
+    fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)>;
+
+    fn clear_selected_mailbox(&mut self);
+
+    fn unselect_supported(&self) -> bool;
+
+    async fn select(&mut self, mbox: &str, mode: ImapMailboxMode) -> Result<()>;
+
+    async fn unselect(&mut self) -> Result<()>;
+}
+
+#[async_trait]
+impl SelectMailbox for ImapClient {
+    fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)> {
+        ImapClient::selected_mailbox(self)
+    }
+
+    fn clear_selected_mailbox(&mut self) {
+        self.mailbox = None;
+    }
+
+    fn unselect_supported(&self) -> bool {
+        self.ext_unselect_supported()
+    }
+
+    async fn select(&mut self, mbox: &str, mode: ImapMailboxMode) -> Result<()> {
+        match mode {
+            ImapMailboxMode::ReadWrite => self.select_mailbox(mbox).await?,
+            ImapMailboxMode::ReadOnly => self.examine_mailbox(mbox).await?,
+        };
+
+        Ok(())
+    }
+
+    async fn unselect(&mut self) -> Result<()> {
+        self.unselect_mailbox().await
+    }
+}
+
+/// Select the given mailbox, unless it is already selected in a
+/// compatible mode.
+async fn ensure_mailbox<C: SelectMailbox>(
+    client: &mut C,
+    mbox: &str,
+    mode: ImapMailboxMode,
+) -> Result<()> {
+    match client.selected_mailbox() {
+        Some((selected, ImapMailboxMode::ReadWrite)) if selected == mbox => {
+            debug!(selected, "mailbox already selected");
+            Ok(())
+        }
+        Some((selected, ImapMailboxMode::ReadOnly))
+            if selected == mbox && mode == ImapMailboxMode::ReadOnly =>
+        {
+            debug!(selected, "mailbox already examined");
+            Ok(())
+        }
+        _ => {
+            // the mailbox is forgotten first, so that a failed
+            // selection is not mistaken for a selected mailbox
+            client.clear_selected_mailbox();
+            client.select(mbox, mode).await
+        }
+    }
+}
+
+/// Leave the selected mailbox if it differs from the given one.
+///
+/// Selecting another mailbox implicitly leaves the selected one
+/// without expunging it, but this relies on the server behaviour.
+/// When the UNSELECT extension is advertised, the selected mailbox is
+/// explicitly left first, so that messages flagged as `\Deleted` are
+/// never expunged by a folder switch.
+async fn leave_mailbox<C: SelectMailbox>(client: &mut C, mbox: &str) -> Result<()> {
+    match client.selected_mailbox() {
+        Some((selected, _)) if selected != mbox && client.unselect_supported() => {
+            debug!(selected, "unselecting mailbox before selecting {mbox}");
+            client.unselect().await
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Split the given UIDs into sequence sets of at most `chunk_size`
+/// UIDs.
+///
+/// Ranges are split arithmetically, without being expanded. Ranges
+/// involving `*` cannot be counted, so they are kept as they are.
+fn split_sequence_set(uids: &SequenceSet, chunk_size: usize) -> Vec<SequenceSet> {
+    let chunk_size = u32::try_from(chunk_size.max(1)).unwrap_or(u32::MAX);
+
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_len = 0;
+
+    for seq in uids.0.as_ref() {
+        let (mut start, end) = match seq {
+            Sequence::Single(SeqOrUid::Value(uid)) => (uid.get(), uid.get()),
+            Sequence::Range(SeqOrUid::Value(a), SeqOrUid::Value(b)) => {
+                (a.get().min(b.get()), a.get().max(b.get()))
+            }
+            seq => {
+                chunk.push(seq.clone());
+                continue;
+            }
+        };
+
+        loop {
+            let len = (end - start).saturating_add(1).min(chunk_size - chunk_len);
+            let last = start + (len - 1);
+
+            chunk.push(uid_range(start, last));
+            chunk_len += len;
+
+            if chunk_len == chunk_size {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_len = 0;
+            }
+
+            if last == end {
+                break;
+            }
+
+            start = last + 1;
+        }
+    }
+
+    chunks.push(chunk);
+
+    chunks
+        .into_iter()
+        .filter_map(|chunk| chunk.try_into().ok())
+        .collect()
+}
+
+/// Build the sequence of the given range of non-zero UIDs.
+fn uid_range(start: u32, end: u32) -> Sequence {
+    let start = SeqOrUid::Value(NonZeroU32::new(start).unwrap_or(NonZeroU32::MIN));
+    let end = SeqOrUid::Value(NonZeroU32::new(end).unwrap_or(NonZeroU32::MIN));
+
+    if start == end {
+        Sequence::Single(start)
+    } else {
+        Sequence::Range(start, end)
+    }
+}
+
+/// Return `true` if the given UIDs involve `*`, which means that they
+/// cannot be expanded.
+fn is_unbounded(uids: &SequenceSet) -> bool {
+    uids.0.as_ref().iter().any(|seq| match seq {
+        Sequence::Single(uid) => matches!(uid, SeqOrUid::Asterisk),
+        Sequence::Range(a, b) => matches!(a, SeqOrUid::Asterisk) || matches!(b, SeqOrUid::Asterisk),
+    })
+}
+
+/// Fetch the given UIDs chunk by chunk, then concatenate results in
+/// order.
+async fn fetch_chunks<C: FetchMessagesChunk>(
+    client: &mut C,
+    uids: &SequenceSet,
+    chunk_size: usize,
+    items: &MacroOrMessageDataItemNames<'static>,
+) -> Result<Vec<C::Item>> {
+    let chunks = split_sequence_set(uids, chunk_size);
+    let chunks_len = chunks.len();
+    let mut fetches = Vec::new();
+
+    for (n, chunk) in chunks.into_iter().enumerate() {
+        debug!("fetching messages chunk {}/{chunks_len}", n + 1);
+        fetches.extend(client.fetch_messages_chunk(chunk, items).await?);
+    }
+
+    Ok(fetches)
+}
+
+/// Fetch the given UIDs chunk by chunk, lazily.
+///
+/// The next chunk is requested only when the previous one has been
+/// consumed by the caller.
+fn stream_chunks<'a, C: FetchMessagesChunk>(
+    client: &'a mut C,
+    uids: &SequenceSet,
+    chunk_size: usize,
+) -> impl Stream<Item = Result<Vec<C::Item>>> + 'a {
+    let chunks = split_sequence_set(uids, chunk_size).into_iter();
+
+    stream::unfold((client, chunks), |(client, mut chunks)| async move {
+        let chunk = chunks.next()?;
+        let output = client.fetch_messages_chunk(chunk, &FETCH_MESSAGES).await;
+
+        if output.is_err() {
+            // stop fetching remaining chunks after the first error
+            chunks = Vec::new().into_iter();
+        }
+
+        Some((output, (client, chunks)))
+    })
+}
+
+/// A byte range of a message section, fetched with
+/// [`ImapClient::fetch_partial_body`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PartialBody {
+    /// The offset of the first byte of the range.
+    pub offset: u32,
+
+    /// The bytes of the range.
+    ///
+    /// There are less bytes than requested when the range goes
+    /// beyond the end of the section.
+    pub data: Vec<u8>,
+
+    /// The total size of the message, in bytes, as reported by the
+    /// server (`RFC822.SIZE`).
+    pub total_size: Option<u32>,
+}
+
+/// Trait used to fetch arbitrary items of messages.
+///
+/// Like [`FetchMessagesChunk`], this abstraction exists so that
+/// [`ImapClient::fetch_partial_body`] can be tested without a real
+/// IMAP server.
+#[async_trait]
+trait FetchItems: Send {
+    async fn fetch_items(
+        &mut self,
+        uids: SequenceSet,
+        items: MacroOrMessageDataItemNames<'static>,
+    ) -> Result<Vec<Vec1<MessageDataItem<'static>>>>;
+}
+
+#[async_trait]
+impl FetchItems for ImapClient {
+    async fn fetch_items(
+        &mut self,
+        uids: SequenceSet,
+        items: MacroOrMessageDataItemNames<'static>,
+    ) -> Result<Vec<Vec1<MessageDataItem<'static>>>> {
+        self.uid_fetch_messages(uids, &items).await
+    }
+}
+
+/// Fetch a byte range of the given section of a message, together
+/// with the total size of the message.
+async fn fetch_partial_body<C: FetchItems>(
+    client: &mut C,
+    uid: NonZeroU32,
+    section: Option<Section<'static>>,
+    offset: u32,
+    length: NonZeroU32,
+) -> Result<PartialBody> {
+    let uids = SequenceSet(Vec1::from(Sequence::Single(SeqOrUid::Value(uid))));
+    let items = MacroOrMessageDataItemNames::MessageDataItemNames(vec![
+        MessageDataItemName::Rfc822Size,
+        peek_partial_body_item(section.clone(), offset, length),
+    ]);
+
+    let fetches = client.fetch_items(uids, items).await?;
+    let items = fetches
+        .into_iter()
+        .next()
+        .ok_or(Error::FetchPartialBodyNotFoundError(uid))?;
+
+    let mut total_size = None;
+    let mut body = None;
+
+    for item in items.as_ref() {
+        match item {
+            MessageDataItem::Rfc822Size(size) => total_size = Some(*size),
+            MessageDataItem::BodyExt {
+                section: fetched_section,
+                origin,
+                data,
+            } if *fetched_section == section => {
+                let data = data.0.as_ref().map(|data| data.as_ref().to_vec());
+                body = Some((origin.unwrap_or(offset), data.unwrap_or_default()));
+            }
+            _ => (),
+        }
+    }
+
+    let (offset, data) = body.ok_or(Error::FetchPartialBodyNotFoundError(uid))?;
+
+    Ok(PartialBody {
+        offset,
+        data,
+        total_size,
+    })
+}
+
 impl fmt::Debug for ImapClient {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("ImapContext")
@@ -866,7 +1532,43 @@ pub async fn client(&self) -> MutexGuard<'_, ImapClient> {
     }
 }
 
-impl BackendContext for ImapContext {}
+#[async_trait]
+impl BackendContext for ImapContext {
+    /// Log out all the clients of the pool.
+    ///
+    /// Busy clients are logged out once their current request
+    /// completes.
+    async fn close(&self) -> AnyResult<()> {
+        debug!("closing imap context");
+        Ok(logout_clients(&self.clients).await?)
+    }
+}
+
+/// Log out the given clients, waiting for busy ones.
+///
+/// All clients are logged out even if some fail, in which case the
+/// first error is returned.
+async fn logout_clients(clients: &[Arc<Mutex<ImapClient>>]) -> Result<()> {
+    let total = clients.len();
+    let mut res = Ok(());
+
+    for client in clients {
+        let mut client = client.lock().await;
+        let id = client.id;
+        debug!("logging out client {id}/{total}");
+
+        if let Err(err) = client.logout().await {
+            warn!("cannot log out client {id}/{total}: {err}");
+            debug!("{err:?}");
+
+            if res.is_ok() {
+                res = Err(err);
+            }
+        }
+    }
+
+    res
+}
 
 /// The IMAP backend context builder.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -946,6 +1648,14 @@ fn delete_folder(&self) -> Option<BackendFeature<Self::Context, dyn DeleteFolder
         Some(Arc::new(DeleteImapFolder::some_new_boxed))
     }
 
+    fn rename_folder(&self) -> Option<BackendFeature<Self::Context, dyn RenameFolder>> {
+        Some(Arc::new(RenameImapFolder::some_new_boxed))
+    }
+
+    fn get_folder_cursor(&self) -> Option<BackendFeature<Self::Context, dyn GetFolderCursor>> {
+        Some(Arc::new(GetImapFolderCursor::some_new_boxed))
+    }
+
     fn get_envelope(&self) -> Option<BackendFeature<Self::Context, dyn GetEnvelope>> {
         Some(Arc::new(GetImapEnvelope::some_new_boxed))
     }
@@ -1075,6 +1785,10 @@ async fn check_up(&self) -> AnyResult<()> {
 pub struct ImapClientBuilder {
     pub config: Arc<ImapConfig>,
     pub credentials: Option<String>,
+
+    /// Whether the UTF8=ACCEPT extension is enabled on the last built
+    /// client.
+    utf8_enabled: bool,
 }
 
 impl ImapClientBuilder {
@@ -1082,9 +1796,16 @@ pub fn new(config: Arc<ImapConfig>, credentials: Option<String>) -> Self {
         Self {
             config,
             credentials,
+            utf8_enabled: false,
         }
     }
 
+    /// Return `true` if the UTF8=ACCEPT extension (RFC 6855) is
+    /// enabled on the last built client.
+    pub fn utf8_enabled(&self) -> bool {
+        self.utf8_enabled
+    }
+
     /// Creates a new session from an IMAP configuration and optional
     /// pre-built credentials.
     ///
@@ -1162,15 +1883,17 @@ pub async fn build(&mut self) -> Result<Client> {
                 debug!("using password authentication");
 
                 let passwd = match self.credentials.as_ref() {
-                    Some(passwd) => passwd.to_string(),
-                    None => passwd
-                        .get()
-                        .await
-                        .map_err(Error::GetPasswdImapError)?
-                        .lines()
-                        .next()
-                        .ok_or(Error::GetPasswdEmptyImapError)?
-                        .to_owned(),
+                    Some(passwd) => Zeroizing::new(passwd.to_string()),
+                    None => Zeroizing::new(
+                        passwd
+                            .get()
+                            .await
+                            .map_err(Error::GetPasswdImapError)?
+                            .lines()
+                            .next()
+                            .ok_or(Error::GetPasswdEmptyImapError)?
+                            .to_owned(),
+                    ),
                 };
 
                 let mechanisms: Vec<_> =
@@ -1179,14 +1902,50 @@ pub async fn build(&mut self) -> Result<Client> {
 
                 debug!(?mechanisms, "supported auth mechanisms");
 
+                // SCRAM is preferred since the password is never sent
+                // to the server
+                let scram = ScramMechanism::select(mechanisms.iter().map(ToString::to_string));
+
+                if let Some(mechanism) = scram {
+                    debug!(%mechanism, "trying auth mechanism…");
+
+                    let login = self.config.login.as_str();
+                    let scram = ScramClient::new(mechanism, login, &passwd);
+
+                    match tasks::authenticate_scram(&mut client, scram).await {
+                        Ok(Ok(())) => {
+                            debug!(%mechanism, "authentication succeeded!");
+                            authenticated = true;
+                        }
+                        // the server could not prove that it knows the
+                        // password, so the password must not be sent
+                        // using other mechanisms
+                        Ok(Err(err)) => {
+                            return Err(Error::AuthenticateScramSaslError(err, mechanism));
+                        }
+                        Err(err) => {
+                            warn!(%mechanism, ?err, "authentication failed");
+                        }
+                    }
+                }
+
                 for mechanism in mechanisms {
+                    if authenticated {
+                        break;
+                    }
+
                     debug!(?mechanism, "trying auth mechanism…");
 
                     let auth = match mechanism {
                         AuthMechanism::Plain => {
-                            client
-                                .authenticate_plain(self.config.login.as_str(), passwd.as_str())
-                                .await
+                            let login = self.config.login.as_str();
+                            let response = Zeroizing::new(format!("\x00{login}\x00{}", *passwd));
+                            tasks::authenticate(
+                                &mut client,
+                                AuthMechanism::Plain,
+                                response.as_bytes().to_vec(),
+                            )
+                            .await
                         }
                         // TODO
                         // AuthMechanism::Login => {
@@ -1217,6 +1976,9 @@ pub async fn build(&mut self) -> Result<Client> {
 
                     debug!("trying login…");
 
+                    let cmd = redact::login_command(&self.config.login);
+                    trace!("sending IMAP command: {cmd}");
+
                     client
                         .login(self.config.login.as_str(), passwd.as_str())
                         .await
@@ -1229,95 +1991,57 @@ pub async fn build(&mut self) -> Result<Client> {
             ImapAuthConfig::OAuth2(oauth2) => {
                 debug!("using OAuth 2.0 authentication");
 
-                match oauth2.method {
+                let method = sasl::oauth2::select_method(&oauth2.method, |method| match method {
                     OAuth2Method::XOAuth2 => {
-                        if !client.state.supports_auth_mechanism(AuthMechanism::XOAuth2) {
-                            let auth = client.state.supported_auth_mechanisms().cloned().collect();
-                            return Err(Error::AuthenticateXOAuth2NotSupportedError(auth));
-                        }
-
-                        debug!("using XOAUTH2 auth mechanism");
-
-                        let access_token = match self.credentials.as_ref() {
-                            Some(access_token) => access_token.to_string(),
-                            None => oauth2
-                                .access_token()
-                                .await
-                                .map_err(Error::RefreshAccessTokenError)?,
-                        };
-
-                        let auth = client
-                            .authenticate_xoauth2(self.config.login.as_str(), access_token.as_str())
-                            .await;
-
-                        if auth.is_err() {
-                            warn!("authentication failed, refreshing access token and retrying…");
-
-                            let access_token = oauth2
-                                .refresh_access_token()
-                                .await
-                                .map_err(Error::RefreshAccessTokenError)?;
-
-                            client
-                                .authenticate_xoauth2(
-                                    self.config.login.as_str(),
-                                    access_token.as_str(),
-                                )
-                                .await
-                                .map_err(Error::AuthenticateXOauth2Error)?;
-
-                            self.credentials = Some(access_token);
-                        }
+                        client.state.supports_auth_mechanism(AuthMechanism::XOAuth2)
                     }
-                    OAuth2Method::OAuthBearer => {
-                        if !client
-                            .state
-                            .supports_auth_mechanism("OAUTHBEARER".try_into().unwrap())
-                        {
-                            let auth = client.state.supported_auth_mechanisms().cloned().collect();
-                            return Err(Error::AuthenticateOAuthBearerNotSupportedError(auth));
+                    OAuth2Method::OAuthBearer => client
+                        .state
+                        .supports_auth_mechanism("OAUTHBEARER".try_into().unwrap()),
+                });
+
+                let Some(method) = method else {
+                    let auth = client.state.supported_auth_mechanisms().cloned().collect();
+                    return Err(match oauth2.method {
+                        OAuth2Method::XOAuth2 => Error::AuthenticateXOAuth2NotSupportedError(auth),
+                        OAuth2Method::OAuthBearer => {
+                            Error::AuthenticateOAuthBearerNotSupportedError(auth)
                         }
+                    });
+                };
 
-                        debug!("using OAUTHBEARER auth mechanism");
-
-                        let access_token = match self.credentials.as_ref() {
-                            Some(access_token) => access_token.to_string(),
-                            None => oauth2
-                                .access_token()
-                                .await
-                                .map_err(Error::RefreshAccessTokenError)?,
-                        };
-
-                        let auth = client
-                            .authenticate_oauthbearer(
-                                self.config.login.as_str(),
-                                self.config.host.as_str(),
-                                self.config.port,
-                                access_token.as_str(),
-                            )
-                            .await;
-
-                        if auth.is_err() {
-                            warn!("authentication failed, refreshing access token and retrying");
-
-                            let access_token = oauth2
-                                .refresh_access_token()
-                                .await
-                                .map_err(Error::RefreshAccessTokenError)?;
-
-                            client
-                                .authenticate_oauthbearer(
-                                    self.config.login.as_str(),
-                                    self.config.host.as_str(),
-                                    self.config.port,
-                                    access_token.as_str(),
-                                )
-                                .await
-                                .map_err(Error::AuthenticateOAuthBearerError)?;
-
-                            self.credentials = Some(access_token);
-                        }
-                    }
+                debug!(%method, "using OAuth 2.0 auth mechanism");
+
+                let access_token = match self.credentials.as_ref() {
+                    Some(access_token) => Zeroizing::new(access_token.to_string()),
+                    None => Zeroizing::new(
+                        oauth2
+                            .access_token()
+                            .await
+                            .map_err(Error::RefreshAccessTokenError)?,
+                    ),
+                };
+
+                let mut session = ImapOAuth2Session {
+                    client: &mut client,
+                    config: &self.config,
+                };
+
+                let refreshed_access_token = sasl::oauth2::authenticate_with_refresh(
+                    &mut session,
+                    &method,
+                    &access_token,
+                    || async {
+                        oauth2
+                            .refresh_access_token()
+                            .await
+                            .map_err(Error::RefreshAccessTokenError)
+                    },
+                )
+                .await?;
+
+                if let Some(access_token) = refreshed_access_token {
+                    self.credentials = Some(access_token);
                 }
             }
         };
@@ -1334,15 +2058,595 @@ pub async fn build(&mut self) -> Result<Client> {
             debug!(?params, "server identity");
         }
 
-        // TODO: make it customizable
-        //
-        // debug!("enabling UTF8 capability…");
-        //
-        // client
-        //     .enable(Some(CapabilityEnable::Utf8(Utf8Kind::Accept)))
-        //     .await
-        //     .map_err(Error::EnableCapabilityError)?;
+        self.utf8_enabled = false;
+
+        let capabilities =
+            find_capabilities_to_enable(&self.config, client.state.capabilities_iter());
+
+        if !capabilities.is_empty() {
+            debug!(?capabilities, "enabling capabilities…");
+
+            let enabled = client
+                .enable(capabilities)
+                .await
+                .map_err(Error::EnableCapabilityError)?;
+
+            debug!(?enabled, "enabled capabilities");
+
+            self.utf8_enabled = enabled
+                .into_iter()
+                .flatten()
+                .any(|capability| matches!(capability, CapabilityEnable::Utf8(Utf8Kind::Accept)));
+        }
 
         Ok(client)
     }
 }
+
+/// Find the advertised capabilities that should be enabled straight
+/// after authentication, using the ENABLE command (RFC 5161).
+fn find_capabilities_to_enable<'a, 'b: 'a>(
+    config: &ImapConfig,
+    capabilities: impl IntoIterator<Item = &'a Capability<'b>>,
+) -> Vec<CapabilityEnable<'static>> {
+    let mut enable = Vec::new();
+
+    for capability in capabilities {
+        if let Capability::Utf8(Utf8Kind::Accept) = capability {
+            if config.enable_utf8_accept() {
+                enable.push(CapabilityEnable::Utf8(Utf8Kind::Accept));
+            }
+        }
+    }
+
+    enable
+}
+
+/// Encode the given mailbox name.
+///
+/// Mailbox names are encoded using modified UTF-7, unless the
+/// UTF8=ACCEPT extension is enabled: they are then sent as they are.
+fn encode_mailbox(mbox: String, utf8_enabled: bool) -> String {
+    if utf8_enabled {
+        mbox
+    } else {
+        encode_utf7(mbox)
+    }
+}
+
+/// IMAP client wrapper used to authenticate using OAuth 2.0 SASL
+/// mechanisms.
+#[cfg(feature = "oauth2")]
+struct ImapOAuth2Session<'a> {
+    client: &'a mut Client,
+    config: &'a ImapConfig,
+}
+
+#[cfg(feature = "oauth2")]
+#[async_trait]
+impl sasl::oauth2::OAuth2Authenticate for ImapOAuth2Session<'_> {
+    type Error = Error;
+
+    async fn authenticate_oauth2(
+        &mut self,
+        method: &OAuth2Method,
+        access_token: &str,
+    ) -> Result<()> {
+        let login = self.config.login.as_str();
+
+        match method {
+            OAuth2Method::XOAuth2 => {
+                let response =
+                    Zeroizing::new(sasl::oauth2::xoauth2_initial_response(login, access_token));
+
+                tasks::authenticate(
+                    self.client,
+                    AuthMechanism::XOAuth2,
+                    response.as_bytes().to_vec(),
+                )
+                .await
+                .map_err(Error::AuthenticateXOauth2Error)?;
+            }
+            OAuth2Method::OAuthBearer => {
+                let host = self.config.host.as_str();
+                let port = self.config.port;
+                let response = Zeroizing::new(sasl::oauth2::oauthbearer_initial_response(
+                    login,
+                    host,
+                    port,
+                    access_token,
+                ));
+
+                tasks::authenticate(
+                    self.client,
+                    "OAUTHBEARER".try_into().unwrap(),
+                    response.as_bytes().to_vec(),
+                )
+                .await
+                .map_err(Error::AuthenticateOAuthBearerError)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::NonZeroU32,
+        pin::pin,
+        sync::{Arc, Mutex},
+    };
+
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use imap_client::imap_next::imap_types::{
+        auth::AuthMechanism,
+        command::{Command, CommandBody},
+        core::{IString, Literal, NString, Vec1},
+        extensions::enable::{CapabilityEnable, Utf8Kind},
+        fetch::{MacroOrMessageDataItemNames, MessageDataItem, MessageDataItemName, Section},
+        mailbox::Mailbox,
+        response::Capability,
+        sequence::{Sequence, SequenceSet},
+    };
+    use imap_codec::{encode::Encoder, CommandCodec};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use crate::{
+        account::config::AccountConfig, backend::context::BackendContext, folder::Folders,
+        imap::config::ImapConfig,
+    };
+
+    use super::{
+        encode_mailbox, ensure_mailbox, fetch_chunks, fetch_partial_body,
+        find_capabilities_to_enable, leave_mailbox, peek_partial_messages_items,
+        split_sequence_set, stream_chunks, Client, Error, FetchItems, FetchMessagesChunk,
+        ImapClient, ImapClientBuilder, ImapContext,
+        ImapMailboxMode::{self, ReadOnly, ReadWrite},
+        PartialBody, Result, SelectMailbox,
+    };
+
+    #[derive(Default)]
+    struct MockClient {
+        requests: Arc<Mutex<Vec<Vec<u32>>>>,
+    }
+
+    #[async_trait]
+    impl FetchMessagesChunk for MockClient {
+        type Item = u32;
+
+        async fn fetch_messages_chunk(
+            &mut self,
+            uids: SequenceSet,
+            _items: &MacroOrMessageDataItemNames<'static>,
+        ) -> Result<Vec<Self::Item>> {
+            let uids: Vec<u32> = uids.iter(NonZeroU32::MAX).map(u32::from).collect();
+            self.requests.lock().unwrap().push(uids.clone());
+            Ok(uids)
+        }
+    }
+
+    #[derive(Default)]
+    struct MockFetchClient {
+        requests: Vec<(SequenceSet, MacroOrMessageDataItemNames<'static>)>,
+        responses: Vec<Vec1<MessageDataItem<'static>>>,
+    }
+
+    #[async_trait]
+    impl FetchItems for MockFetchClient {
+        async fn fetch_items(
+            &mut self,
+            uids: SequenceSet,
+            items: MacroOrMessageDataItemNames<'static>,
+        ) -> Result<Vec<Vec1<MessageDataItem<'static>>>> {
+            self.requests.push((uids, items));
+            Ok(std::mem::take(&mut self.responses))
+        }
+    }
+
+    #[derive(Default)]
+    struct MockSelectClient {
+        mailbox: Option<(String, ImapMailboxMode)>,
+        unselect_supported: bool,
+        reject_selects: bool,
+        selects: Vec<(String, ImapMailboxMode)>,
+        unselects: usize,
+    }
+
+    #[async_trait]
+    impl SelectMailbox for MockSelectClient {
+        fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)> {
+            self.mailbox
+                .as_ref()
+                .map(|(mbox, mode)| (mbox.as_str(), *mode))
+        }
+
+        fn clear_selected_mailbox(&mut self) {
+            self.mailbox = None;
+        }
+
+        fn unselect_supported(&self) -> bool {
+            self.unselect_supported
+        }
+
+        async fn select(&mut self, mbox: &str, mode: ImapMailboxMode) -> Result<()> {
+            self.selects.push((mbox.to_owned(), mode));
+
+            if self.reject_selects {
+                return Err(Error::SelectMailboxTimedOutError);
+            }
+
+            self.mailbox = Some((mbox.to_owned(), mode));
+            Ok(())
+        }
+
+        async fn unselect(&mut self) -> Result<()> {
+            self.mailbox = None;
+            self.unselects += 1;
+            Ok(())
+        }
+    }
+
+    fn uids(seq: &str) -> SequenceSet {
+        vec![Sequence::try_from(seq).unwrap()].try_into().unwrap()
+    }
+
+    #[tokio::test]
+    async fn fetch_messages_by_chunks() {
+        let mut client = MockClient::default();
+
+        let items = peek_partial_messages_items(NonZeroU32::new(1024).unwrap());
+        let fetches = fetch_chunks(&mut client, &uids("1:1200"), 500, &items)
+            .await
+            .unwrap();
+
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0], (1..=500).collect::<Vec<_>>());
+        assert_eq!(requests[1], (501..=1000).collect::<Vec<_>>());
+        assert_eq!(requests[2], (1001..=1200).collect::<Vec<_>>());
+
+        assert_eq!(fetches, (1..=1200).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn split_sequence_set_arithmetically() {
+        let split = |seq: &str, chunk_size| -> Vec<SequenceSet> {
+            split_sequence_set(&SequenceSet::try_from(seq).unwrap(), chunk_size)
+        };
+        let sets = |seqs: &[&str]| -> Vec<SequenceSet> {
+            seqs.iter()
+                .map(|seq| SequenceSet::try_from(*seq).unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            split("1:1200", 500),
+            sets(&["1:500", "501:1000", "1001:1200"])
+        );
+        assert_eq!(split("1,3:5,7", 2), sets(&["1,3", "4:5", "7"]));
+        assert_eq!(split("5:3", 10), sets(&["3:5"]));
+
+        // huge ranges are not expanded
+        assert_eq!(
+            split("1:4294967295", 2147483648),
+            sets(&["1:2147483648", "2147483649:4294967295"])
+        );
+
+        // ranges involving `*` cannot be counted
+        assert_eq!(split("1:2,10:*", 2), sets(&["1:2", "10:*"]));
+    }
+
+    #[tokio::test]
+    async fn fetch_messages_stream_incrementally() {
+        let mut client = MockClient::default();
+        let requests = client.requests.clone();
+
+        let mut stream = pin!(stream_chunks(&mut client, &uids("1:5"), 2));
+
+        // nothing is fetched until the stream is polled
+        assert!(requests.lock().unwrap().is_empty());
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), vec![1, 2]);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), vec![3, 4]);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), vec![5]);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_partial_body_range() {
+        // the server returns less bytes than requested
+        let literal = Literal::try_from(b"Hello, world!".to_vec()).unwrap();
+        let items = vec![
+            MessageDataItem::Rfc822Size(4096),
+            MessageDataItem::BodyExt {
+                section: Some(Section::Text(None)),
+                origin: Some(1024),
+                data: NString(Some(IString::Literal(literal))),
+            },
+        ];
+
+        let mut client = MockFetchClient {
+            responses: vec![Vec1::try_from(items).unwrap()],
+            ..Default::default()
+        };
+
+        let length = NonZeroU32::new(2048).unwrap();
+        let uid = NonZeroU32::new(42).unwrap();
+        let body = fetch_partial_body(&mut client, uid, Some(Section::Text(None)), 1024, length)
+            .await
+            .unwrap();
+
+        assert_eq!(client.requests.len(), 1);
+        let (uids, items) = &client.requests[0];
+        assert_eq!(*uids, self::uids("42"));
+        assert_eq!(
+            *items,
+            MacroOrMessageDataItemNames::MessageDataItemNames(vec![
+                MessageDataItemName::Rfc822Size,
+                MessageDataItemName::BodyExt {
+                    section: Some(Section::Text(None)),
+                    partial: Some((1024, length)),
+                    peek: true,
+                },
+            ])
+        );
+
+        assert_eq!(
+            body,
+            PartialBody {
+                offset: 1024,
+                data: b"Hello, world!".to_vec(),
+                total_size: Some(4096),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_partial_body_not_found() {
+        let mut client = MockFetchClient::default();
+
+        let length = NonZeroU32::new(2048).unwrap();
+        let uid = NonZeroU32::new(42).unwrap();
+        let res = fetch_partial_body(&mut client, uid, None, 0, length).await;
+
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn forget_mailbox_on_failed_select() {
+        let mut client = MockSelectClient {
+            mailbox: Some(("INBOX".into(), ReadOnly)),
+            reject_selects: true,
+            ..Default::default()
+        };
+
+        let res = ensure_mailbox(&mut client, "INBOX", ReadWrite).await;
+        assert!(res.is_err());
+        assert_eq!(client.selected_mailbox(), None);
+
+        // the mailbox is selected again, even in a compatible mode
+        client.reject_selects = false;
+        ensure_mailbox(&mut client, "INBOX", ReadOnly)
+            .await
+            .unwrap();
+        assert_eq!(client.selects.len(), 2);
+        assert_eq!(client.selected_mailbox(), Some(("INBOX", ReadOnly)));
+    }
+
+    #[tokio::test]
+    async fn unselect_mailbox_on_switch() {
+        let mut client = MockSelectClient {
+            mailbox: Some(("INBOX".into(), ReadWrite)),
+            unselect_supported: true,
+            ..Default::default()
+        };
+
+        // selecting the same mailbox does not leave it
+        leave_mailbox(&mut client, "INBOX").await.unwrap();
+        assert_eq!(client.unselects, 0);
+
+        leave_mailbox(&mut client, "Archives").await.unwrap();
+        assert_eq!(client.unselects, 1);
+        assert_eq!(client.mailbox, None);
+
+        // nothing to leave when no mailbox is selected
+        leave_mailbox(&mut client, "Archives").await.unwrap();
+        assert_eq!(client.unselects, 1);
+    }
+
+    #[tokio::test]
+    async fn select_mailbox_without_unselect() {
+        let mut client = MockSelectClient {
+            mailbox: Some(("INBOX".into(), ReadWrite)),
+            ..Default::default()
+        };
+
+        leave_mailbox(&mut client, "Archives").await.unwrap();
+        assert_eq!(client.unselects, 0);
+        assert_eq!(client.selected_mailbox(), Some(("INBOX", ReadWrite)));
+    }
+
+    #[tokio::test]
+    async fn select_mailbox_once() {
+        let mut client = MockSelectClient::default();
+
+        ensure_mailbox(&mut client, "INBOX", ReadWrite)
+            .await
+            .unwrap();
+        ensure_mailbox(&mut client, "INBOX", ReadWrite)
+            .await
+            .unwrap();
+        assert_eq!(client.selects, vec![("INBOX".into(), ReadWrite)]);
+
+        // a read-write mailbox is compatible with read-only operations
+        ensure_mailbox(&mut client, "INBOX", ReadOnly)
+            .await
+            .unwrap();
+        assert_eq!(client.selects.len(), 1);
+
+        ensure_mailbox(&mut client, "Archives", ReadOnly)
+            .await
+            .unwrap();
+        ensure_mailbox(&mut client, "Archives", ReadOnly)
+            .await
+            .unwrap();
+        assert_eq!(client.selects.len(), 2);
+        assert_eq!(client.selects[1], ("Archives".into(), ReadOnly));
+
+        // a read-only mailbox needs to be selected again for
+        // read-write operations
+        ensure_mailbox(&mut client, "Archives", ReadWrite)
+            .await
+            .unwrap();
+        assert_eq!(client.selects.len(), 3);
+        assert_eq!(client.selects[2], ("Archives".into(), ReadWrite));
+    }
+
+    #[test]
+    fn enable_utf8_accept_when_advertised() {
+        let config = ImapConfig::default();
+
+        // the mock server advertises UTF8=ACCEPT
+        let capabilities = [
+            Capability::Imap4Rev1,
+            Capability::Auth(AuthMechanism::Plain),
+            Capability::Utf8(Utf8Kind::Accept),
+        ];
+        let enable = find_capabilities_to_enable(&config, &capabilities);
+        assert_eq!(enable, vec![CapabilityEnable::Utf8(Utf8Kind::Accept)]);
+
+        let body = CommandBody::Enable {
+            capabilities: enable.try_into().unwrap(),
+        };
+        let cmd = Command::new("A1", body).unwrap();
+        assert_eq!(
+            CommandCodec::default().encode(&cmd).dump(),
+            b"A1 ENABLE UTF8=ACCEPT\r\n"
+        );
+
+        // nothing to enable when the server does not advertise it
+        let capabilities = [Capability::Imap4Rev1];
+        assert!(find_capabilities_to_enable(&config, &capabilities).is_empty());
+    }
+
+    #[test]
+    fn use_raw_utf8_mailbox_names_when_enabled() {
+        assert_eq!(
+            encode_mailbox("Archives/Été".into(), false),
+            "Archives/&AMk-t&AOk-"
+        );
+        assert_eq!(encode_mailbox("Archives/Été".into(), true), "Archives/Été");
+
+        let config = AccountConfig::default();
+        let mbox = Mailbox::try_from("Archives/Été & co").unwrap();
+
+        let folders =
+            Folders::from_imap_mailboxes_with_utf8(&config, vec![(mbox, None, vec![])], true);
+        assert_eq!(folders[0].name, "Archives/Été & co");
+    }
+
+    /// Serve IMAP clients connecting to the given listener, greeting
+    /// them then replying to their CAPABILITY and LOGOUT commands.
+    ///
+    /// The LOGOUT command of the given connection is rejected. The
+    /// commands received are recorded along the connection number,
+    /// without their tag.
+    async fn serve_logout(listener: TcpListener, reject: usize, cmds: Arc<Mutex<Vec<String>>>) {
+        for conn in 1.. {
+            let (stream, _) = listener.accept().await.unwrap();
+            let cmds = cmds.clone();
+
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let greeting = b"* OK [CAPABILITY IMAP4rev1] ready\r\n";
+                stream.get_mut().write_all(greeting).await.unwrap();
+
+                loop {
+                    let mut line = String::new();
+                    if stream.read_line(&mut line).await.unwrap() == 0 {
+                        break;
+                    }
+
+                    let (tag, cmd) = line.trim_end().split_once(' ').unwrap();
+                    cmds.lock().unwrap().push(format!("{conn} {cmd}"));
+
+                    let reply = match cmd {
+                        "CAPABILITY" => "* CAPABILITY IMAP4rev1\r\n{tag} OK done",
+                        "LOGOUT" if conn == reject => "{tag} NO cannot log out",
+                        "LOGOUT" => "* BYE logging out\r\n{tag} OK done",
+                        _ => "{tag} BAD unexpected command",
+                    };
+                    let reply = reply.replace("{tag}", tag) + "\r\n";
+
+                    stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn logout_all_clients_on_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        tokio::spawn(serve_logout(listener, 2, commands.clone()));
+
+        let imap_config = Arc::new(ImapConfig {
+            host: "127.0.0.1".into(),
+            port,
+            ..Default::default()
+        });
+
+        let mut clients = Vec::new();
+
+        // clients are connected one by one, so that the client ids
+        // match the connection numbers
+        for id in 1..=3 {
+            let inner = Client::insecure("127.0.0.1", port).await.unwrap();
+
+            clients.push(Arc::new(tokio::sync::Mutex::new(ImapClient {
+                id,
+                account_config: Default::default(),
+                imap_config: imap_config.clone(),
+                client_builder: ImapClientBuilder::new(imap_config.clone(), None),
+                inner,
+                mailbox: None,
+                retry: Default::default(),
+            })));
+        }
+
+        let ctx = ImapContext {
+            account_config: Default::default(),
+            imap_config,
+            clients,
+        };
+
+        let err = ctx.close().await.unwrap_err();
+        assert!(matches!(
+            err.as_any().downcast_ref(),
+            Some(Error::LogoutError(_))
+        ));
+
+        // a failing client does not prevent others to log out
+        let logouts: Vec<_> = commands
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|cmd| cmd.ends_with(" LOGOUT"))
+            .cloned()
+            .collect();
+        assert_eq!(logouts, vec!["1 LOGOUT", "2 LOGOUT", "3 LOGOUT"]);
+    }
+}
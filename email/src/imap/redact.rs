@@ -0,0 +1,57 @@
+//! # IMAP log redaction
+//!
+//! Module dedicated to the logging of credential-bearing IMAP
+//! commands (`LOGIN` and `AUTHENTICATE`). Secrets are never formatted
+//! into logs: logged commands only keep their name and their
+//! non-secret argument (the login or the mechanism), while secrets
+//! are replaced by [`REDACTED`].
+
+use imap_client::imap_next::imap_types::auth::AuthMechanism;
+
+/// The placeholder replacing secrets in logs.
+pub const REDACTED: &str = "***";
+
+/// Build the loggable line of the `LOGIN` command of the given
+/// login.
+///
+/// The line looks like `LOGIN "<login>" ***`.
+pub fn login_command(login: &str) -> String {
+    format!("LOGIN {login:?} {REDACTED}")
+}
+
+/// Build the loggable line of the `AUTHENTICATE` command of the given
+/// mechanism.
+///
+/// The line looks like `AUTHENTICATE <mechanism> ***` when the client
+/// response is sent inline with the command (SASL-IR), otherwise
+/// like `AUTHENTICATE <mechanism>`.
+pub fn authenticate_command(mechanism: &AuthMechanism, ir: bool) -> String {
+    if ir {
+        format!("AUTHENTICATE {mechanism} {REDACTED}")
+    } else {
+        format!("AUTHENTICATE {mechanism}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_client::imap_next::imap_types::auth::AuthMechanism;
+
+    use super::{authenticate_command, login_command};
+
+    #[test]
+    fn redact_login() {
+        assert_eq!(login_command("user"), r#"LOGIN "user" ***"#);
+        assert_eq!(login_command(r#"us"er"#), r#"LOGIN "us\"er" ***"#);
+    }
+
+    #[test]
+    fn redact_authenticate() {
+        let line = authenticate_command(&AuthMechanism::Plain, true);
+        assert_eq!(line, "AUTHENTICATE PLAIN ***");
+
+        // the response is sent after a continuation request
+        let line = authenticate_command(&AuthMechanism::XOAuth2, false);
+        assert_eq!(line, "AUTHENTICATE XOAUTH2");
+    }
+}
@@ -0,0 +1,310 @@
+//! # IMAP mailbox status
+//!
+//! Module dedicated to the IMAP `STATUS` command, which gives the
+//! counts of a mailbox without selecting it. This is much cheaper than
+//! `SELECT` followed by `SEARCH UNSEEN`, for example to display the
+//! unseen counts of a whole folder tree. The main structure of this
+//! module is [`ImapMailboxStatus`].
+
+use async_trait::async_trait;
+use imap_client::imap_next::imap_types::{
+    mailbox::Mailbox,
+    search::SearchKey,
+    status::{StatusDataItem, StatusDataItemName},
+};
+use tracing::{debug, instrument};
+
+use super::{tasks, Error, ImapClient, ImapContext, ImapMailboxMode, ImapRetryState, Result};
+
+/// All the status items supported by [`ImapMailboxStatus`].
+pub const STATUS_ITEMS: [StatusDataItemName; 5] = [
+    StatusDataItemName::Messages,
+    StatusDataItemName::Unseen,
+    StatusDataItemName::Recent,
+    StatusDataItemName::UidNext,
+    StatusDataItemName::UidValidity,
+];
+
+/// The status of an IMAP mailbox.
+///
+/// Items that were not requested, or not returned by the server, are
+/// `None`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImapMailboxStatus {
+    /// The number of messages in the mailbox.
+    pub messages: Option<u32>,
+
+    /// The number of messages without the `\Seen` flag.
+    pub unseen: Option<u32>,
+
+    /// The number of messages with the `\Recent` flag.
+    pub recent: Option<u32>,
+
+    /// The next UID to be assigned to a new message.
+    pub uid_next: Option<u32>,
+
+    /// The UID validity of the mailbox.
+    pub uid_validity: Option<u32>,
+
+    /// The highest mod-sequence of the mailbox, when the server
+    /// supports the CONDSTORE extension.
+    pub highest_modseq: Option<u64>,
+}
+
+impl FromIterator<StatusDataItem> for ImapMailboxStatus {
+    fn from_iter<T: IntoIterator<Item = StatusDataItem>>(items: T) -> Self {
+        let mut status = Self::default();
+
+        for item in items {
+            match item {
+                StatusDataItem::Messages(count) => status.messages = Some(count),
+                StatusDataItem::Unseen(count) => status.unseen = Some(count),
+                StatusDataItem::Recent(count) => status.recent = Some(count),
+                StatusDataItem::UidNext(uid) => status.uid_next = Some(uid.get()),
+                StatusDataItem::UidValidity(uid) => status.uid_validity = Some(uid.get()),
+                // the mod-sequence type depends on the imap-types version
+                #[allow(clippy::useless_conversion)]
+                StatusDataItem::HighestModSeq(modseq) => {
+                    status.highest_modseq = Some(u64::from(modseq))
+                }
+                _ => (),
+            }
+        }
+
+        status
+    }
+}
+
+impl ImapClient {
+    /// Get the status of the given mailbox.
+    ///
+    /// See [`ImapContext::status`].
+    #[instrument(skip_all, fields(client = self.id))]
+    pub async fn status_mailbox(
+        &mut self,
+        mbox: impl ToString,
+        items: impl IntoIterator<Item = StatusDataItemName>,
+    ) -> Result<ImapMailboxStatus> {
+        let items: Vec<_> = items.into_iter().collect();
+        mailbox_status(self, &mbox.to_string(), items).await
+    }
+}
+
+impl ImapContext {
+    /// Get the status of the given folder, without selecting it.
+    ///
+    /// The `STATUS` command should not be used on the selected
+    /// mailbox, and some servers reject it. In this case, the counts
+    /// are computed by searching the selected mailbox instead, and
+    /// UIDs are not returned.
+    pub async fn status(
+        &self,
+        folder: &str,
+        items: impl IntoIterator<Item = StatusDataItemName>,
+    ) -> Result<ImapMailboxStatus> {
+        let mut client = self.client().await;
+
+        let folder = client.account_config.get_folder_alias(folder);
+        let folder_encoded = client.encode_mailbox(&folder);
+        debug!("encoded folder: {folder_encoded}");
+
+        client.status_mailbox(folder_encoded, items).await
+    }
+}
+
+/// Trait used to get the status of mailboxes.
+///
+/// This abstraction exists so that the selected mailbox fallback of
+/// [`ImapClient::status_mailbox`] can be tested without a real IMAP
+/// server.
+#[async_trait]
+trait MailboxStatus: Send {
+    fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)>;
+
+    async fn status(
+        &mut self,
+        mbox: &str,
+        items: Vec<StatusDataItemName>,
+    ) -> Result<Vec<StatusDataItem>>;
+
+    async fn count(&mut self, key: SearchKey<'static>) -> Result<u32>;
+}
+
+#[async_trait]
+impl MailboxStatus for ImapClient {
+    fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)> {
+        ImapClient::selected_mailbox(self)
+    }
+
+    async fn status(
+        &mut self,
+        mbox: &str,
+        items: Vec<StatusDataItemName>,
+    ) -> Result<Vec<StatusDataItem>> {
+        let mailbox = Mailbox::try_from(mbox.to_owned())
+            .map_err(|err| Error::ParseMailboxError(err, mbox.to_owned()))?;
+
+        self.retry.reset();
+
+        loop {
+            let task = tasks::status(&mut self.inner, mailbox.clone(), items.clone());
+            let res = self.retry.timeout(task).await;
+
+            match self.retry(res).await? {
+                ImapRetryState::Retry => continue,
+                ImapRetryState::TimedOut => break Err(Error::StatusMailboxTimedOutError),
+                ImapRetryState::Ok(res) => break res.map_err(Error::StatusMailboxError),
+            }
+        }
+    }
+
+    async fn count(&mut self, key: SearchKey<'static>) -> Result<u32> {
+        let uids = self.search_uids([key]).await?;
+        Ok(uids.len() as u32)
+    }
+}
+
+/// Get the status of the given mailbox, falling back to searches when
+/// the server rejects the `STATUS` command on the selected mailbox.
+async fn mailbox_status<C: MailboxStatus>(
+    client: &mut C,
+    mbox: &str,
+    items: Vec<StatusDataItemName>,
+) -> Result<ImapMailboxStatus> {
+    let selected = matches!(client.selected_mailbox(), Some((selected, _)) if selected == mbox);
+
+    let err = match client.status(mbox, items.clone()).await {
+        Ok(items) => return Ok(ImapMailboxStatus::from_iter(items)),
+        Err(err) if selected => err,
+        Err(err) => return Err(err),
+    };
+
+    debug!(
+        ?err,
+        "cannot get status of selected mailbox {mbox}, searching instead"
+    );
+
+    let mut status = ImapMailboxStatus::default();
+
+    for item in items {
+        match item {
+            StatusDataItemName::Messages => {
+                status.messages = Some(client.count(SearchKey::All).await?)
+            }
+            StatusDataItemName::Unseen => {
+                status.unseen = Some(client.count(SearchKey::Unseen).await?)
+            }
+            StatusDataItemName::Recent => {
+                status.recent = Some(client.count(SearchKey::Recent).await?)
+            }
+            _ => (),
+        }
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use async_trait::async_trait;
+    use imap_client::imap_next::imap_types::{
+        search::SearchKey,
+        status::{StatusDataItem, StatusDataItemName},
+    };
+
+    use super::{mailbox_status, ImapMailboxStatus, MailboxStatus, STATUS_ITEMS};
+    use crate::imap::{Error, ImapMailboxMode, Result};
+
+    #[derive(Default)]
+    struct MockStatusClient {
+        selected: Option<String>,
+        /// Whether STATUS is rejected on the selected mailbox.
+        reject_selected: bool,
+        searches: Vec<SearchKey<'static>>,
+    }
+
+    #[async_trait]
+    impl MailboxStatus for MockStatusClient {
+        fn selected_mailbox(&self) -> Option<(&str, ImapMailboxMode)> {
+            self.selected
+                .as_deref()
+                .map(|mbox| (mbox, ImapMailboxMode::ReadOnly))
+        }
+
+        async fn status(
+            &mut self,
+            mbox: &str,
+            _items: Vec<StatusDataItemName>,
+        ) -> Result<Vec<StatusDataItem>> {
+            if self.reject_selected && self.selected.as_deref() == Some(mbox) {
+                return Err(Error::StatusMailboxTimedOutError);
+            }
+
+            Ok(vec![
+                StatusDataItem::Messages(42),
+                StatusDataItem::Unseen(3),
+                StatusDataItem::Recent(1),
+                StatusDataItem::UidNext(NonZeroU32::new(100).unwrap()),
+                StatusDataItem::UidValidity(NonZeroU32::new(7).unwrap()),
+            ])
+        }
+
+        async fn count(&mut self, key: SearchKey<'static>) -> Result<u32> {
+            let count = match key {
+                SearchKey::Unseen => 2,
+                _ => 10,
+            };
+            self.searches.push(key);
+            Ok(count)
+        }
+    }
+
+    #[tokio::test]
+    async fn parse_status_response() {
+        let mut client = MockStatusClient::default();
+
+        let status = mailbox_status(&mut client, "Archive", STATUS_ITEMS.to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(status.unseen, Some(3));
+        assert_eq!(
+            status,
+            ImapMailboxStatus {
+                messages: Some(42),
+                unseen: Some(3),
+                recent: Some(1),
+                uid_next: Some(100),
+                uid_validity: Some(7),
+                highest_modseq: None,
+            }
+        );
+        assert!(client.searches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_selected_mailbox_when_status_rejected() {
+        let mut client = MockStatusClient {
+            selected: Some("INBOX".into()),
+            reject_selected: true,
+            ..Default::default()
+        };
+
+        let items = vec![StatusDataItemName::Messages, StatusDataItemName::Unseen];
+        let status = mailbox_status(&mut client, "INBOX", items).await.unwrap();
+
+        assert_eq!(status.messages, Some(10));
+        assert_eq!(status.unseen, Some(2));
+        assert_eq!(status.uid_next, None);
+        assert_eq!(client.searches, vec![SearchKey::All, SearchKey::Unseen]);
+
+        // the status of other mailboxes never needs searches
+        let status = mailbox_status(&mut client, "Archive", STATUS_ITEMS.to_vec())
+            .await
+            .unwrap();
+        assert_eq!(status.unseen, Some(3));
+        assert_eq!(client.searches.len(), 2);
+    }
+}
@@ -0,0 +1,756 @@
+//! Module dedicated to IMAP tasks not covered by the IMAP client.
+
+use imap_client::{
+    client::tokio::{Client, ClientError},
+    imap_next::imap_types::{
+        auth::{AuthMechanism, AuthenticateData},
+        command::CommandBody,
+        core::LiteralOrLiteral8,
+        datetime::DateTime,
+        flag::Flag,
+        mailbox::Mailbox,
+        response::{Bye, Capability, CommandContinuationRequest, Data, StatusBody, StatusKind},
+        status::{StatusDataItem, StatusDataItemName},
+    },
+    tasks::{tasks::TaskError, Task},
+};
+use tracing::{debug, trace};
+use zeroize::Zeroizing;
+
+use super::redact;
+use crate::sasl::{self, scram::ScramClient};
+
+/// Leave the selected mailbox without expunging it, using the
+/// UNSELECT extension (RFC 3691).
+pub async fn unselect(client: &mut Client) -> Result<(), ClientError> {
+    Ok(client.resolve(UnselectTask).await??)
+}
+
+/// Leave the selected mailbox, permanently removing messages flagged
+/// as `\Deleted`.
+pub async fn close(client: &mut Client) -> Result<(), ClientError> {
+    Ok(client.resolve(CloseTask).await??)
+}
+
+/// Log out from the server.
+///
+/// The server closes the connection once the command completes.
+pub async fn logout(client: &mut Client) -> Result<(), ClientError> {
+    Ok(client.resolve(LogoutTask).await??)
+}
+
+/// Append the given message to the given mailbox, using the given
+/// flags and internal date.
+///
+/// The APPEND task of the IMAP client does not allow to set the
+/// internal date of the message.
+pub async fn append(
+    client: &mut Client,
+    mailbox: Mailbox<'static>,
+    flags: Vec<Flag<'static>>,
+    date: DateTime,
+    message: LiteralOrLiteral8<'static>,
+) -> Result<(), ClientError> {
+    let task = AppendTask {
+        mailbox,
+        flags,
+        date,
+        message,
+    };
+
+    Ok(client.resolve(task).await??)
+}
+
+/// Rename the given mailbox.
+pub async fn rename(
+    client: &mut Client,
+    from: Mailbox<'static>,
+    to: Mailbox<'static>,
+) -> Result<(), ClientError> {
+    Ok(client.resolve(RenameTask { from, to }).await??)
+}
+
+/// Get the status of the given mailbox, without selecting it.
+pub async fn status(
+    client: &mut Client,
+    mailbox: Mailbox<'static>,
+    item_names: Vec<StatusDataItemName>,
+) -> Result<Vec<StatusDataItem>, ClientError> {
+    Ok(client
+        .resolve(StatusTask::new(mailbox, item_names))
+        .await??)
+}
+
+/// Authenticate using the given SASL mechanism and its raw (non
+/// base64-encoded) client response.
+///
+/// When the server advertises the SASL-IR extension (RFC 4959), the
+/// response is sent inline with the AUTHENTICATE command. Otherwise
+/// the command is sent alone, and the response is sent once the
+/// server requests it with a continuation request.
+///
+/// Capabilities are refreshed once authenticated, since servers
+/// usually advertise more of them to authenticated clients.
+pub async fn authenticate(
+    client: &mut Client,
+    mechanism: AuthMechanism<'static>,
+    response: Vec<u8>,
+) -> Result<(), ClientError> {
+    let ir = sasl_ir_supported(client.state.capabilities_iter());
+    debug!(%mechanism, ir, "authenticating using SASL…");
+
+    let cmd = redact::authenticate_command(&mechanism, ir);
+    trace!("sending IMAP command: {cmd}");
+
+    client
+        .resolve(AuthenticateTask::new(mechanism, response, ir))
+        .await??;
+
+    client.refresh_capabilities().await
+}
+
+/// Authenticate using the given SCRAM client.
+///
+/// SCRAM is a multi-step mechanism: the client-first message is sent
+/// (inline when the server advertises SASL-IR), then the client-final
+/// message is sent in response to the server-first message, and the
+/// server-final message is finally verified.
+///
+/// The outer error is the IMAP error, the inner one is the SCRAM
+/// error, which takes precedence since the exchange is cancelled
+/// when it occurs.
+pub async fn authenticate_scram(
+    client: &mut Client,
+    scram: ScramClient,
+) -> Result<sasl::Result<()>, ClientError> {
+    let ir = sasl_ir_supported(client.state.capabilities_iter());
+    debug!(mechanism = %scram.mechanism(), ir, "authenticating using SASL…");
+
+    if let Err(err) = client
+        .resolve(ScramAuthenticateTask::new(scram, ir))
+        .await??
+    {
+        return Ok(Err(err));
+    }
+
+    client.refresh_capabilities().await?;
+
+    Ok(Ok(()))
+}
+
+/// Return `true` if the given capabilities contain the SASL-IR
+/// extension (RFC 4959).
+fn sasl_ir_supported<'a, 'b: 'a>(
+    capabilities: impl IntoIterator<Item = &'a Capability<'b>>,
+) -> bool {
+    capabilities
+        .into_iter()
+        .any(|capability| matches!(capability, Capability::SaslIr))
+}
+
+#[derive(Clone, Debug, Default)]
+struct UnselectTask;
+
+impl Task for UnselectTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Unselect
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct CloseTask;
+
+impl Task for CloseTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Close
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct LogoutTask;
+
+impl Task for LogoutTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Logout
+    }
+
+    /// The server always sends a `BYE` before completing the
+    /// command, which is expected here.
+    fn process_bye(&mut self, _bye: Bye<'static>) -> Option<Bye<'static>> {
+        None
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct AppendTask {
+    mailbox: Mailbox<'static>,
+    flags: Vec<Flag<'static>>,
+    date: DateTime,
+    message: LiteralOrLiteral8<'static>,
+}
+
+impl Task for AppendTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Append {
+            mailbox: self.mailbox.clone(),
+            flags: self.flags.clone(),
+            date: Some(self.date.clone()),
+            message: self.message.clone(),
+        }
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RenameTask {
+    from: Mailbox<'static>,
+    to: Mailbox<'static>,
+}
+
+impl Task for RenameTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Rename {
+            from: self.from.clone(),
+            to: self.to.clone(),
+        }
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StatusTask {
+    mailbox: Mailbox<'static>,
+    item_names: Vec<StatusDataItemName>,
+    /// The status items sent by the server, if any.
+    items: Option<Vec<StatusDataItem>>,
+}
+
+impl StatusTask {
+    fn new(mailbox: Mailbox<'static>, item_names: Vec<StatusDataItemName>) -> Self {
+        Self {
+            mailbox,
+            item_names,
+            items: None,
+        }
+    }
+}
+
+impl Task for StatusTask {
+    type Output = Result<Vec<StatusDataItem>, TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        CommandBody::Status {
+            mailbox: self.mailbox.clone(),
+            item_names: self.item_names.clone().into(),
+        }
+    }
+
+    fn process_data(&mut self, data: Data<'static>) -> Option<Data<'static>> {
+        match data {
+            Data::Status { mailbox, items } if mailbox == self.mailbox => {
+                self.items = Some(items.into_owned());
+                None
+            }
+            data => Some(data),
+        }
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)?;
+        Ok(self.items.unwrap_or_default())
+    }
+}
+
+/// The task of single-step SASL mechanisms (PLAIN, XOAUTH2,
+/// OAUTHBEARER…).
+#[derive(Clone, Debug)]
+struct AuthenticateTask {
+    mechanism: AuthMechanism<'static>,
+    /// The client response, taken once sent.
+    response: Option<Zeroizing<Vec<u8>>>,
+    /// Whether the response is sent inline with the command.
+    ir: bool,
+}
+
+impl AuthenticateTask {
+    fn new(mechanism: AuthMechanism<'static>, response: Vec<u8>, ir: bool) -> Self {
+        Self {
+            mechanism,
+            response: Some(Zeroizing::new(response)),
+            ir,
+        }
+    }
+}
+
+impl Task for AuthenticateTask {
+    type Output = Result<(), TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        match &self.response {
+            Some(response) if self.ir => {
+                CommandBody::authenticate_with_ir(self.mechanism.clone(), response.to_vec())
+            }
+            _ => CommandBody::authenticate(self.mechanism.clone()),
+        }
+    }
+
+    fn process_continuation_request_authenticate(
+        &mut self,
+        _continuation: CommandContinuationRequest<'static>,
+    ) -> Result<AuthenticateData<'static>, CommandContinuationRequest<'static>> {
+        // the response is sent after the first continuation request,
+        // unless it was already sent inline. Any other continuation
+        // request means that the server rejected the response (for
+        // example an XOAUTH2 error challenge): the exchange is then
+        // cancelled, so that the server sends its final response.
+        match self.response.take() {
+            Some(response) if !self.ir => {
+                trace!("sending IMAP authenticate data: {}", redact::REDACTED);
+                Ok(AuthenticateData::r#continue(response.to_vec()))
+            }
+            _ => Ok(AuthenticateData::Cancel),
+        }
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        process_tagged(status_body)
+    }
+}
+
+/// The step of the SCRAM exchange, as expected by the client.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ScramStep {
+    /// The client-first message needs to be sent.
+    ClientFirst,
+    /// The server-first message is expected.
+    ServerFirst,
+    /// The server-final message is expected.
+    ServerFinal,
+    /// The server-final message has been verified.
+    Done,
+}
+
+/// The task of the SCRAM SASL mechanisms.
+#[derive(Debug)]
+struct ScramAuthenticateTask {
+    scram: ScramClient,
+    step: ScramStep,
+    /// Whether the client-first message is sent inline with the
+    /// command.
+    ir: bool,
+    /// The SCRAM error that led to the cancellation of the exchange.
+    error: Option<sasl::Error>,
+}
+
+impl ScramAuthenticateTask {
+    fn new(scram: ScramClient, ir: bool) -> Self {
+        let step = if ir {
+            ScramStep::ServerFirst
+        } else {
+            ScramStep::ClientFirst
+        };
+
+        Self {
+            scram,
+            step,
+            ir,
+            error: None,
+        }
+    }
+
+    fn mechanism(&self) -> AuthMechanism<'static> {
+        self.scram.mechanism().name().try_into().unwrap()
+    }
+
+    fn cancel(&mut self, err: sasl::Error) -> AuthenticateData<'static> {
+        self.error = Some(err);
+        self.step = ScramStep::Done;
+        AuthenticateData::Cancel
+    }
+}
+
+impl Task for ScramAuthenticateTask {
+    type Output = Result<sasl::Result<()>, TaskError>;
+
+    fn command_body(&self) -> CommandBody<'static> {
+        if self.ir {
+            let msg = self.scram.client_first_message().into_bytes();
+            CommandBody::authenticate_with_ir(self.mechanism(), msg)
+        } else {
+            CommandBody::authenticate(self.mechanism())
+        }
+    }
+
+    fn process_continuation_request_authenticate(
+        &mut self,
+        continuation: CommandContinuationRequest<'static>,
+    ) -> Result<AuthenticateData<'static>, CommandContinuationRequest<'static>> {
+        let challenge = match &continuation {
+            CommandContinuationRequest::Base64(data) => String::from_utf8_lossy(data).into_owned(),
+            CommandContinuationRequest::Basic(_) => String::new(),
+        };
+
+        let data = match self.step {
+            ScramStep::ClientFirst => {
+                self.step = ScramStep::ServerFirst;
+                let msg = self.scram.client_first_message();
+                AuthenticateData::r#continue(msg.into_bytes())
+            }
+            ScramStep::ServerFirst => match self.scram.handle_server_first_message(&challenge) {
+                Ok(msg) => {
+                    self.step = ScramStep::ServerFinal;
+                    AuthenticateData::r#continue(msg.into_bytes())
+                }
+                Err(err) => self.cancel(err),
+            },
+            ScramStep::ServerFinal => match self.scram.verify_server_final_message(&challenge) {
+                Ok(()) => {
+                    self.step = ScramStep::Done;
+                    AuthenticateData::r#continue(Vec::new())
+                }
+                Err(err) => self.cancel(err),
+            },
+            ScramStep::Done => AuthenticateData::Cancel,
+        };
+
+        Ok(data)
+    }
+
+    fn process_tagged(self, status_body: StatusBody<'static>) -> Self::Output {
+        if let Some(err) = self.error {
+            return Ok(Err(err));
+        }
+
+        process_tagged(status_body)?;
+
+        // a server accepting the authentication without proving that
+        // it knows the password cannot be trusted
+        if self.step != ScramStep::Done {
+            return Ok(Err(sasl::Error::ScramMissingServerFinalMessageError));
+        }
+
+        Ok(Ok(()))
+    }
+}
+
+fn process_tagged(status_body: StatusBody<'static>) -> Result<(), TaskError> {
+    match status_body.kind {
+        StatusKind::Ok => Ok(()),
+        StatusKind::No => Err(TaskError::UnexpectedNoResponse(status_body)),
+        StatusKind::Bad => Err(TaskError::UnexpectedBadResponse(status_body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use imap_client::{
+        imap_next::imap_types::{
+            auth::AuthMechanism,
+            command::Command,
+            mailbox::Mailbox,
+            response::{Bye, Capability, CommandContinuationRequest, Data, StatusBody, StatusKind},
+            status::{StatusDataItem, StatusDataItemName},
+        },
+        tasks::Task,
+    };
+    use imap_codec::{encode::Encoder, AuthenticateDataCodec, CommandCodec};
+    use utf7_imap::encode_utf7_imap as encode_utf7;
+
+    use super::{
+        sasl_ir_supported, AuthenticateTask, LogoutTask, RenameTask, ScramAuthenticateTask,
+        StatusTask,
+    };
+    use crate::sasl::{
+        scram::{ScramClient, ScramMechanism},
+        Error as SaslError,
+    };
+
+    /// Mock server running the AUTHENTICATE PLAIN exchange with the
+    /// given capabilities, returning the lines sent by the client.
+    ///
+    /// The server sends continuation requests until the client sends
+    /// something else than the command itself, like real servers do.
+    fn authenticate_plain(capabilities: &[Capability<'static>]) -> Vec<Vec<u8>> {
+        let ir = sasl_ir_supported(capabilities);
+        let mut task = AuthenticateTask::new(AuthMechanism::Plain, b"\0user\0pass".to_vec(), ir);
+
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        let mut lines = vec![CommandCodec::default().encode(&cmd).dump()];
+
+        if !capabilities.contains(&Capability::SaslIr) {
+            let continuation = CommandContinuationRequest::basic(None, "ready").unwrap();
+            let data = task
+                .process_continuation_request_authenticate(continuation)
+                .unwrap();
+            lines.push(AuthenticateDataCodec::default().encode(&data).dump());
+        }
+
+        lines
+    }
+
+    #[test]
+    fn authenticate_with_initial_response() {
+        let capabilities = [
+            Capability::Imap4Rev1,
+            Capability::SaslIr,
+            Capability::Auth(AuthMechanism::Plain),
+        ];
+
+        assert_eq!(
+            authenticate_plain(&capabilities),
+            vec![b"A1 AUTHENTICATE PLAIN AHVzZXIAcGFzcw==\r\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn authenticate_with_continuation() {
+        let capabilities = [
+            Capability::Imap4Rev1,
+            Capability::Auth(AuthMechanism::Plain),
+        ];
+
+        assert_eq!(
+            authenticate_plain(&capabilities),
+            vec![
+                b"A1 AUTHENTICATE PLAIN\r\n".to_vec(),
+                b"AHVzZXIAcGFzcw==\r\n".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn cancel_authenticate_on_unexpected_continuation() {
+        let mut task = AuthenticateTask::new(AuthMechanism::XOAuth2, b"token".to_vec(), true);
+        let continuation = CommandContinuationRequest::basic(None, "error").unwrap();
+        let data = task
+            .process_continuation_request_authenticate(continuation)
+            .unwrap();
+
+        assert_eq!(
+            AuthenticateDataCodec::default().encode(&data).dump(),
+            b"*\r\n"
+        );
+    }
+
+    fn ok() -> StatusBody<'static> {
+        StatusBody {
+            kind: StatusKind::Ok,
+            code: None,
+            text: "done".try_into().unwrap(),
+        }
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc5802#section-5
+    fn scram_task(ir: bool) -> ScramAuthenticateTask {
+        let scram = ScramClient::new_with_nonce(
+            ScramMechanism::Sha1,
+            "user",
+            "pencil",
+            "fyko+d2lbbFgONRv9qkxdawL",
+        );
+        ScramAuthenticateTask::new(scram, ir)
+    }
+
+    fn process_challenge(task: &mut ScramAuthenticateTask, challenge: &[u8]) -> Vec<u8> {
+        let continuation = CommandContinuationRequest::base64(challenge.to_vec());
+        let data = task
+            .process_continuation_request_authenticate(continuation)
+            .unwrap();
+        AuthenticateDataCodec::default().encode(&data).dump()
+    }
+
+    const SERVER_FIRST: &[u8] =
+        b"r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096";
+
+    #[test]
+    fn authenticate_scram_with_continuation() {
+        let mut task = scram_task(false);
+
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        assert_eq!(
+            CommandCodec::default().encode(&cmd).dump(),
+            b"A1 AUTHENTICATE SCRAM-SHA-1\r\n"
+        );
+
+        let continuation = CommandContinuationRequest::basic(None, "ready").unwrap();
+        let data = task
+            .process_continuation_request_authenticate(continuation)
+            .unwrap();
+        assert_eq!(
+            AuthenticateDataCodec::default().encode(&data).dump(),
+            b"biwsbj11c2VyLHI9ZnlrbytkMmxiYkZnT05Sdjlxa3hkYXdM\r\n"
+        );
+
+        assert_eq!(
+            process_challenge(&mut task, SERVER_FIRST),
+            b"Yz1iaXdzLHI9ZnlrbytkMmxiYkZnT05Sdjlxa3hkYXdMM3JmY05IWUpZMVpWdldWczdqLHA9djBYOHYzQnoyVDBDSkdiSlF5RjBYK0hJNFRzPQ==\r\n"
+        );
+
+        // the server-final message is acknowledged with an empty
+        // response
+        assert_eq!(
+            process_challenge(&mut task, b"v=rmF9pqV8S7suAoZWja4dJRkFsKQ="),
+            b"\r\n"
+        );
+
+        assert!(matches!(task.process_tagged(ok()), Ok(Ok(()))));
+    }
+
+    #[test]
+    fn authenticate_scram_with_initial_response() {
+        let task = scram_task(true);
+
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        assert_eq!(
+            CommandCodec::default().encode(&cmd).dump(),
+            b"A1 AUTHENTICATE SCRAM-SHA-1 biwsbj11c2VyLHI9ZnlrbytkMmxiYkZnT05Sdjlxa3hkYXdM\r\n"
+        );
+    }
+
+    #[test]
+    fn cancel_authenticate_scram_on_invalid_server_signature() {
+        let mut task = scram_task(true);
+
+        process_challenge(&mut task, SERVER_FIRST);
+
+        assert_eq!(
+            process_challenge(&mut task, b"v=AAAAAAAAAAAAAAAAAAAAAAAAAAA="),
+            b"*\r\n"
+        );
+
+        // the SCRAM error takes precedence over the server response
+        let bad = StatusBody {
+            kind: StatusKind::Bad,
+            code: None,
+            text: "cancelled".try_into().unwrap(),
+        };
+        assert!(matches!(
+            task.process_tagged(bad),
+            Ok(Err(SaslError::ScramInvalidServerSignatureError)),
+        ));
+    }
+
+    #[test]
+    fn reject_authenticate_scram_without_server_final_message() {
+        let mut task = scram_task(true);
+
+        process_challenge(&mut task, SERVER_FIRST);
+
+        assert!(matches!(
+            task.process_tagged(ok()),
+            Ok(Err(SaslError::ScramMissingServerFinalMessageError)),
+        ));
+    }
+
+    fn encode_rename(from: &str, to: &str) -> Vec<u8> {
+        let task = RenameTask {
+            from: Mailbox::try_from(encode_utf7(from.to_owned())).unwrap(),
+            to: Mailbox::try_from(encode_utf7(to.to_owned())).unwrap(),
+        };
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        CommandCodec::default().encode(&cmd).dump()
+    }
+
+    #[test]
+    fn rename_nested_utf7_mailbox() {
+        assert_eq!(
+            encode_rename("Archive/Été", "Archives/Été 2024"),
+            b"A1 RENAME Archive/&AMk-t&AOk- \"Archives/&AMk-t&AOk- 2024\"\r\n"
+        );
+    }
+
+    #[test]
+    fn rename_inbox() {
+        assert_eq!(encode_rename("inbox", "Old"), b"A1 RENAME INBOX Old\r\n");
+    }
+
+    #[test]
+    fn status_mailbox() {
+        let mut task = StatusTask::new(
+            Mailbox::try_from("Archive").unwrap(),
+            vec![StatusDataItemName::Messages, StatusDataItemName::Unseen],
+        );
+
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        assert_eq!(
+            CommandCodec::default().encode(&cmd).dump(),
+            b"A1 STATUS Archive (MESSAGES UNSEEN)\r\n"
+        );
+
+        // status data of other mailboxes is left to the client
+        let other = Data::Status {
+            mailbox: Mailbox::Inbox,
+            items: vec![StatusDataItem::Messages(1)].into(),
+        };
+        assert!(task.process_data(other).is_some());
+
+        let data = Data::Status {
+            mailbox: Mailbox::try_from("Archive").unwrap(),
+            items: vec![StatusDataItem::Messages(42), StatusDataItem::Unseen(3)].into(),
+        };
+        assert!(task.process_data(data).is_none());
+
+        let ok = StatusBody {
+            kind: StatusKind::Ok,
+            code: None,
+            text: "done".try_into().unwrap(),
+        };
+        assert_eq!(
+            task.process_tagged(ok).unwrap(),
+            vec![StatusDataItem::Messages(42), StatusDataItem::Unseen(3)]
+        );
+    }
+
+    #[test]
+    fn logout() {
+        let mut task = LogoutTask;
+
+        let cmd = Command::new("A1", task.command_body()).unwrap();
+        assert_eq!(
+            CommandCodec::default().encode(&cmd).dump(),
+            b"A1 LOGOUT\r\n"
+        );
+
+        // the bye sent before completing the command is expected
+        let bye = Bye {
+            code: None,
+            text: "logging out".try_into().unwrap(),
+        };
+        assert!(task.process_bye(bye).is_none());
+
+        let ok = StatusBody {
+            kind: StatusKind::Ok,
+            code: None,
+            text: "done".try_into().unwrap(),
+        };
+        assert!(task.process_tagged(ok).is_ok());
+    }
+}
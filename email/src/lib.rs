@@ -12,7 +12,8 @@
 //! build a custom backend.
 //!
 //! The library also exposes pre-configured backend features for
-//! Maildir, IMAP, Notmuch, SMTP and Sendmail.
+//! Maildir, IMAP, Notmuch, SMTP and Sendmail, as well as an
+//! in-memory backend and a mock backend for testing purposes.
 //!
 //! See examples in the `/tests` folder.
 //!
@@ -25,6 +26,8 @@
 //! - [`ExpungeFolder`](crate::folder::expunge::ExpungeFolder)
 //! - [`PurgeFolder`](crate::folder::purge::PurgeFolder)
 //! - [`DeleteFolder`](crate::folder::delete::DeleteFolder)
+//! - [`RenameFolder`](crate::folder::rename::RenameFolder)
+//! - [`GetFolderCursor`](crate::folder::cursor::GetFolderCursor)
 //!
 //! ### Envelope
 //!
@@ -60,9 +63,15 @@
 pub mod imap;
 #[cfg(feature = "maildir")]
 pub mod maildir;
+#[cfg(feature = "memory")]
+pub mod memory;
+#[cfg(feature = "mock")]
+pub mod mock;
 #[cfg(feature = "notmuch")]
 pub mod notmuch;
 pub mod retry;
+#[cfg(any(feature = "imap", feature = "smtp"))]
+pub mod sasl;
 #[cfg(feature = "sendmail")]
 pub mod sendmail;
 #[cfg(feature = "derive")]
@@ -78,5 +87,5 @@
 #[doc(inline)]
 pub use crate::{
     email::{envelope::flag, message::template, *},
-    error::{AnyBoxedError, AnyError, AnyResult},
+    error::{AnyBoxedError, AnyError, AnyResult, ErrorKind},
 };
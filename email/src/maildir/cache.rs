@@ -0,0 +1,357 @@
+//! # Maildir envelopes cache
+//!
+//! Module dedicated to the on-disk cache of Maildir envelopes. The
+//! cache prevents messages from being parsed again on every listing:
+//! only new or changed messages are parsed. The main structure of
+//! this module is [`EnvelopesCache`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use chrono::DateTime;
+use tracing::{debug, trace};
+
+use crate::envelope::{Address, Envelope};
+
+/// The name of the cache file, located at the root of each Maildir
+/// folder.
+pub const ENVELOPES_CACHE_FILE_NAME: &str = ".envelopes.cache";
+
+/// The version of the cache file format.
+///
+/// Cache files with a different version are discarded.
+const VERSION: &str = "envelopes-cache-v1";
+
+/// The number of cache saves of the current process.
+///
+/// The counter is part of the temporary file name, so that concurrent
+/// saves of the same process never write to the same file.
+static SAVES: AtomicU64 = AtomicU64::new(0);
+
+/// The file metadata used to invalidate a cached envelope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct EntryMetadata {
+    mtime_secs: u64,
+    mtime_nanos: u32,
+    size: u64,
+}
+
+impl EntryMetadata {
+    /// Read the metadata of the Maildir message at the given path.
+    pub(crate) fn read(path: &Path) -> io::Result<Self> {
+        let meta = fs::metadata(path)?;
+        let mtime = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Ok(Self {
+            mtime_secs: mtime.as_secs(),
+            mtime_nanos: mtime.subsec_nanos(),
+            size: meta.len(),
+        })
+    }
+}
+
+/// The Maildir envelopes cache.
+///
+/// Envelopes are keyed by the unique name of their Maildir entry,
+/// and invalidated as soon as the modification time or the size of
+/// the entry file changes. Flags are not cached, since they are
+/// always read from the entry file name.
+///
+/// The cache is best effort: a missing or corrupted cache file is
+/// just considered empty. The cache file is written to a temporary
+/// file first, then atomically renamed, so that concurrent readers
+/// never see a partially written cache.
+#[derive(Debug)]
+pub(crate) struct EnvelopesCache {
+    path: PathBuf,
+    entries: HashMap<String, (EntryMetadata, Envelope)>,
+    dirty: bool,
+}
+
+impl EnvelopesCache {
+    /// Load the envelopes cache of the Maildir folder located at the
+    /// given path.
+    pub(crate) fn load(mdir_path: &Path) -> Self {
+        let path = mdir_path.join(ENVELOPES_CACHE_FILE_NAME);
+
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => parse(&contents),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(_err) => {
+                debug!("cannot read maildir envelopes cache at {path:?}, ignoring it");
+                trace!("{_err:?}");
+                HashMap::new()
+            }
+        };
+
+        debug!("loaded {} envelopes from cache {path:?}", entries.len());
+
+        Self {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Get the cached envelope of the given entry unique name, if it
+    /// is still valid.
+    ///
+    /// The returned envelope has neither id nor flags.
+    pub(crate) fn get(&self, id: &str, meta: &EntryMetadata) -> Option<&Envelope> {
+        self.entries
+            .get(id)
+            .filter(|(cached_meta, _)| cached_meta == meta)
+            .map(|(_, envelope)| envelope)
+    }
+
+    /// Insert the envelope of the given entry unique name.
+    pub(crate) fn insert(&mut self, id: String, meta: EntryMetadata, envelope: &Envelope) {
+        let envelope = Envelope {
+            id: String::new(),
+            flags: Default::default(),
+            ..envelope.clone()
+        };
+
+        self.entries.insert(id, (meta, envelope));
+        self.dirty = true;
+    }
+
+    /// Remove cached envelopes whose entry does not exist anymore.
+    pub(crate) fn retain<'a>(&mut self, ids: impl IntoIterator<Item = &'a str>) {
+        let ids: HashSet<&str> = ids.into_iter().collect();
+        let len = self.entries.len();
+
+        self.entries.retain(|id, _| ids.contains(id.as_str()));
+
+        if self.entries.len() != len {
+            self.dirty = true;
+        }
+    }
+
+    /// Write the cache back to disk, if it changed.
+    ///
+    /// Errors are logged and ignored, the cache being best effort.
+    pub(crate) fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let count = SAVES.fetch_add(1, Ordering::Relaxed);
+        let tmp_name = format!("{ENVELOPES_CACHE_FILE_NAME}.{}.{count}.tmp", process::id());
+        let tmp_path = self.path.with_file_name(tmp_name);
+
+        let res = fs::write(&tmp_path, serialize(&self.entries))
+            .and_then(|()| fs::rename(&tmp_path, &self.path));
+
+        match res {
+            Ok(()) => {
+                debug!(
+                    "saved {} envelopes to cache {:?}",
+                    self.entries.len(),
+                    self.path
+                );
+                self.dirty = false;
+            }
+            Err(_err) => {
+                debug!("cannot save maildir envelopes cache {:?}", self.path);
+                trace!("{_err:?}");
+                let _ = fs::remove_file(&tmp_path);
+            }
+        }
+    }
+}
+
+fn serialize(entries: &HashMap<String, (EntryMetadata, Envelope)>) -> String {
+    let mut contents = String::from(VERSION);
+    contents.push('\n');
+
+    for (id, (meta, envelope)) in entries {
+        let fields = [
+            escape(id),
+            meta.mtime_secs.to_string(),
+            meta.mtime_nanos.to_string(),
+            meta.size.to_string(),
+            escape(&envelope.message_id),
+            escape_option(envelope.in_reply_to.as_deref()),
+            escape_option(envelope.from.name.as_deref()),
+            escape(&envelope.from.addr),
+            escape_option(envelope.to.name.as_deref()),
+            escape(&envelope.to.addr),
+            escape(&envelope.subject),
+            envelope.date.to_rfc3339(),
+            (envelope.has_attachment as u8).to_string(),
+        ];
+
+        contents.push_str(&fields.join("\t"));
+        contents.push('\n');
+    }
+
+    contents
+}
+
+fn parse(contents: &str) -> HashMap<String, (EntryMetadata, Envelope)> {
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(VERSION) {
+        debug!("invalid maildir envelopes cache version, ignoring it");
+        return HashMap::new();
+    }
+
+    lines.filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<(String, (EntryMetadata, Envelope))> {
+    let mut fields = line.split('\t');
+    let mut next = || fields.next();
+
+    let id = unescape(next()?);
+
+    let meta = EntryMetadata {
+        mtime_secs: next()?.parse().ok()?,
+        mtime_nanos: next()?.parse().ok()?,
+        size: next()?.parse().ok()?,
+    };
+
+    let envelope = Envelope {
+        message_id: unescape(next()?),
+        in_reply_to: unescape_option(next()?),
+        from: Address {
+            name: unescape_option(next()?),
+            addr: unescape(next()?),
+        },
+        to: Address {
+            name: unescape_option(next()?),
+            addr: unescape(next()?),
+        },
+        subject: unescape(next()?),
+        date: DateTime::parse_from_rfc3339(next()?).ok()?,
+        has_attachment: next()? == "1",
+        ..Default::default()
+    };
+
+    if next().is_some() {
+        trace!("invalid maildir envelopes cache line {line:?}, skipping it");
+        return None;
+    }
+
+    Some((id, (meta, envelope)))
+}
+
+/// Escape the given field, so that it fits on a single line and
+/// does not contain any field separator.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Escape the given optional field. [`None`] is represented by a
+/// single backslash, which cannot be produced by [`escape`].
+fn escape_option(field: Option<&str>) -> String {
+    match field {
+        Some(field) => escape(field),
+        None => String::from("\\"),
+    }
+}
+
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(c) => unescaped.push(c),
+            None => (),
+        }
+    }
+
+    unescaped
+}
+
+fn unescape_option(field: &str) -> Option<String> {
+    if field == "\\" {
+        None
+    } else {
+        Some(unescape(field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::DateTime;
+
+    use super::{parse, serialize, EntryMetadata};
+    use crate::envelope::{Address, Envelope};
+
+    #[test]
+    fn serialize_then_parse() {
+        let meta = EntryMetadata {
+            mtime_secs: 1_700_000_000,
+            mtime_nanos: 42,
+            size: 1024,
+        };
+
+        let envelope = Envelope {
+            message_id: "<id@localhost>".into(),
+            in_reply_to: None,
+            from: Address::new(Some("Tab\tName"), "from@localhost"),
+            to: Address::new(Some(""), "to@localhost"),
+            subject: "Multi\nline \\ subject\r".into(),
+            date: DateTime::parse_from_rfc3339("2024-01-02T10:30:00+02:00").unwrap(),
+            has_attachment: true,
+            ..Default::default()
+        };
+
+        let entries = HashMap::from_iter([("id".to_owned(), (meta, envelope.clone()))]);
+        let contents = serialize(&entries);
+        assert_eq!(contents.lines().count(), 2);
+
+        let (parsed_meta, parsed) = parse(&contents).remove("id").unwrap();
+        assert_eq!(parsed_meta, meta);
+        assert_eq!(parsed.message_id, envelope.message_id);
+        assert_eq!(parsed.in_reply_to, None);
+        assert_eq!(parsed.from.name.as_deref(), Some("Tab\tName"));
+        assert_eq!(parsed.from.addr, "from@localhost");
+        assert_eq!(parsed.to.name.as_deref(), Some(""));
+        assert_eq!(parsed.subject, envelope.subject);
+        assert_eq!(parsed.date, envelope.date);
+        assert_eq!(parsed.date.offset(), envelope.date.offset());
+        assert!(parsed.has_attachment);
+    }
+
+    #[test]
+    fn parse_invalid_cache() {
+        assert!(parse("").is_empty());
+        assert!(parse("envelopes-cache-v0\nid\t1\t2\t3").is_empty());
+        assert!(parse("envelopes-cache-v1\nid\tinvalid").is_empty());
+    }
+}
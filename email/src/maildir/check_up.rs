@@ -0,0 +1,205 @@
+//! # Maildir check up
+//!
+//! Module dedicated to the checks run by the Maildir check up
+//! feature, see [`CheckUpMaildir`](super::CheckUpMaildir). Reading
+//! the Maildir is always checked. Writing to it and the available
+//! disk space are only checked when enabled in
+//! [`MaildirConfig`](super::config::MaildirConfig), since a Maildir
+//! mounted read-only or with a full disk can still be read.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use tracing::debug;
+
+use super::{Error, MaildirContext, Result};
+use crate::folder::INBOX;
+
+/// Check up the given Maildir context.
+pub(crate) fn check_up(ctx: &MaildirContext) -> Result<()> {
+    let config = &ctx.maildir_config;
+    let root = ctx.root.path();
+    check_read_access(root)?;
+
+    if config.check_up_write_access {
+        let inbox = ctx.get_maildir_from_folder_alias(INBOX)?;
+        let tmp = inbox.path().join("tmp");
+        let dir = if tmp.is_dir() { &tmp } else { root };
+        check_write_access(dir)?;
+    }
+
+    if let Some(min) = config.check_up_min_free_space {
+        check_free_space(root, min)?;
+    }
+
+    Ok(())
+}
+
+/// Check that the given directory can be read.
+pub(crate) fn check_read_access(dir: &Path) -> Result<()> {
+    debug!("check read access of maildir directory {}", dir.display());
+
+    fs::read_dir(dir)
+        .and_then(|mut entries| entries.try_for_each(|entry| entry.map(|_| ())))
+        .map_err(|err| Error::CheckUpReadAccessError(err, dir.to_owned()))
+}
+
+/// Check that the given directory can be written, by creating then
+/// removing a temporary file.
+pub(crate) fn check_write_access(dir: &Path) -> Result<()> {
+    debug!("check write access of maildir directory {}", dir.display());
+
+    let path = dir.join(format!(".check-up.{}", uuid::Uuid::new_v4().simple()));
+
+    let res = fs::File::create_new(&path)
+        .and_then(|mut file| file.write_all(b"check up"))
+        .and_then(|()| fs::remove_file(&path));
+
+    match res {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            // the file may have been created before the write failed
+            let _ = fs::remove_file(&path);
+
+            if is_read_only_error(&err) {
+                Err(Error::CheckUpReadOnlyFilesystemError(err, dir.to_owned()))
+            } else {
+                Err(Error::CheckUpWriteAccessError(err, dir.to_owned()))
+            }
+        }
+    }
+}
+
+/// Check that the disk holding the given directory has at least the
+/// given available space, in bytes.
+///
+/// Does nothing if the available space cannot be known on the
+/// current platform.
+pub(crate) fn check_free_space(dir: &Path, min: u64) -> Result<()> {
+    let Some(available) = available_space(dir)
+        .map_err(|err| Error::CheckUpAvailableSpaceError(err, dir.to_owned()))?
+    else {
+        debug!("cannot get available disk space on this platform, skipping check");
+        return Ok(());
+    };
+
+    debug!(
+        "{available} bytes available for maildir directory {}",
+        dir.display()
+    );
+
+    if available < min {
+        return Err(Error::CheckUpDiskFullError(dir.to_owned(), available, min));
+    }
+
+    Ok(())
+}
+
+/// Return `true` if the given error means that the file system (or
+/// the directory) cannot be written.
+fn is_read_only_error(err: &io::Error) -> bool {
+    if err.kind() == io::ErrorKind::PermissionDenied {
+        return true;
+    }
+
+    #[cfg(unix)]
+    if err.raw_os_error() == Some(libc::EROFS) {
+        return true;
+    }
+
+    false
+}
+
+/// Get the available space of the disk holding the given path, in
+/// bytes.
+#[cfg(unix)]
+fn available_space(path: &Path) -> io::Result<Option<u64>> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: the path is a valid C string, and the stat buffer is
+    // only read once initialized by a successful call
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+
+    // the field types depend on the platform
+    #[allow(clippy::unnecessary_cast)]
+    let available = stat.f_bavail as u64 * stat.f_frsize as u64;
+
+    Ok(Some(available))
+}
+
+#[cfg(not(unix))]
+fn available_space(_path: &Path) -> io::Result<Option<u64>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{check_free_space, check_read_access, check_write_access};
+    use crate::maildir::Error;
+
+    #[test]
+    fn writable_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        check_read_access(dir).unwrap();
+        check_write_access(dir).unwrap();
+
+        // the temporary file is removed
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // permissions do not apply to root
+        if unsafe { libc::geteuid() } == 0 {
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o500)).unwrap();
+
+        check_read_access(dir).unwrap();
+        let res = check_write_access(dir);
+
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).unwrap();
+
+        assert!(matches!(
+            res,
+            Err(Error::CheckUpReadOnlyFilesystemError(_, _))
+        ));
+        assert_eq!(fs::read_dir(dir).unwrap().count(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn disk_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        check_free_space(dir, 0).unwrap();
+
+        let res = check_free_space(dir, u64::MAX);
+        assert!(matches!(
+            res,
+            Err(Error::CheckUpDiskFullError(_, _, u64::MAX))
+        ));
+    }
+}
@@ -23,6 +23,102 @@ pub struct MaildirConfig {
 
     #[cfg_attr(feature = "derive", serde(default))]
     pub maildirpp: bool,
+
+    /// Store the Maildir++ INBOX in a `.INBOX` subfolder.
+    ///
+    /// With Maildir++, the INBOX is usually the root Maildir. Some
+    /// setups (like Dovecot with a separate INBOX) keep it in a
+    /// `.INBOX` subfolder instead, next to other folders. This option
+    /// has no effect without Maildir++, where the INBOX is always
+    /// the `INBOX` subfolder.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub inbox_subfolder: bool,
+
+    /// Serialize message deliveries using a lock file.
+    ///
+    /// Maildir is lock-free by design, but concurrent deliveries
+    /// from multiple processes can still race in edge cases. When
+    /// enabled, adding a message first acquires the delivery lock of
+    /// the Maildir, see [`DeliveryLock`](super::lock::DeliveryLock).
+    /// Single-writer setups can leave it disabled.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub delivery_lock: bool,
+
+    /// Leave messages in `new` when getting them.
+    ///
+    /// As per the Maildir specification, getting a message moves it
+    /// from the `new` directory to the `cur` one and marks it as
+    /// seen, so that other Maildir clients stop seeing it as new.
+    /// When enabled, getting a message behaves like peeking it: the
+    /// Maildir is left untouched, which is useful for read-only
+    /// scenarios. Peeking never moves messages.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub keep_new_on_get: bool,
+
+    /// Cache envelopes on disk when listing them.
+    ///
+    /// When enabled, listed envelopes are cached in a
+    /// `.envelopes.cache` file at the root of each Maildir folder, so
+    /// that only new or changed messages are parsed on subsequent
+    /// listings. Since the cache file is written into the Maildir,
+    /// the cache is disabled by default.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub envelopes_cache: bool,
+
+    /// The mode of the Maildir folders (unix only).
+    ///
+    /// When defined, the mode is applied to the folders (and their
+    /// `cur`, `new` and `tmp` directories) created by the backend,
+    /// like `0o700`. Defaults to `None`, which keeps the permissions
+    /// given by the umask of the current process.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub dir_mode: Option<u32>,
+
+    /// The mode of the Maildir messages (unix only).
+    ///
+    /// When defined, the mode is applied to the messages delivered by
+    /// the backend, like `0o600`. Defaults to `None`, which keeps the
+    /// permissions given by the umask of the current process.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub file_mode: Option<u32>,
+
+    /// Check write access when checking up the Maildir.
+    ///
+    /// By default, the check up only reads the Maildir, which is
+    /// safe for non-destructive environments. When enabled, it also
+    /// creates then removes a temporary file in the `tmp` directory
+    /// of the INBOX (or in the root directory if the INBOX does not
+    /// exist yet), so that a read-only Maildir is detected before the
+    /// first delivery.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub check_up_write_access: bool,
+
+    /// The minimum available disk space, in bytes, required by the
+    /// check up (unix only).
+    ///
+    /// Defaults to `None`, which skips the disk space check.
+    #[cfg_attr(feature = "derive", serde(default))]
+    pub check_up_min_free_space: Option<u64>,
+}
+
+impl MaildirConfig {
+    /// Return `true` if the INBOX is the root Maildir.
+    ///
+    /// This is the case for Maildir++, unless the INBOX is stored in
+    /// a subfolder (see [`MaildirConfig::inbox_subfolder`]).
+    pub fn is_inbox_root(&self) -> bool {
+        self.maildirpp && !self.inbox_subfolder
+    }
 }
 
 #[cfg(feature = "sync")]
@@ -0,0 +1,139 @@
+//! # Maildir delivery
+//!
+//! Module dedicated to the delivery of messages to Maildir folders.
+//! The main function of this module is [`deliver`].
+//!
+//! As per the Maildir specification, messages are written to `tmp`
+//! under a unique name, then renamed to `new`, or to `cur` when they
+//! carry flags. The file mode of the [`MaildirConfig`] is applied to
+//! the temporary file before its content is written, so that
+//! delivered messages are never visible with looser permissions.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use maildirs::Maildir;
+use tracing::debug;
+
+use super::{config::MaildirConfig, entry_path, perms, Error, Result};
+
+/// The number of messages delivered by the current process, used to
+/// generate unique names.
+static DELIVERIES: AtomicU64 = AtomicU64::new(0);
+
+/// Deliver the given raw message to the given Maildir folder, with
+/// the given flag letters.
+///
+/// Returns the unique name of the delivered message.
+pub(crate) fn deliver(
+    mdir: &Maildir,
+    config: &MaildirConfig,
+    raw_msg: &[u8],
+    letters: &BTreeSet<char>,
+) -> Result<String> {
+    let id = unique_name();
+    let tmp = mdir.path().join("tmp").join(&id);
+
+    let res = write_tmp(config, &tmp, raw_msg).and_then(|()| {
+        let path = entry_path(mdir, &id, letters, true);
+        debug!("deliver maildir message to {}", path.display());
+        fs::rename(&tmp, &path).map_err(|err| Error::DeliverMessageError(err, path))
+    });
+
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    res.map(|()| id)
+}
+
+/// Write the given raw message to the given temporary path.
+fn write_tmp(config: &MaildirConfig, path: &Path, raw_msg: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|err| Error::DeliverMessageError(err, path.to_owned()))?;
+
+    perms::set_message_mode(config, path)?;
+
+    file.write_all(raw_msg)
+        .and_then(|()| file.sync_all())
+        .map_err(|err| Error::DeliverMessageError(err, path.to_owned()))
+}
+
+/// Generate a unique name, following the `<time>.M<usec>P<pid>Q<n>.<host>`
+/// convention of the Maildir specification.
+///
+/// Slashes and colons of the host name are escaped, as they cannot
+/// be part of the name.
+fn unique_name() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let count = DELIVERIES.fetch_add(1, Ordering::Relaxed);
+    let host = gethostname::gethostname()
+        .to_string_lossy()
+        .replace('/', "\\057")
+        .replace(':', "\\072");
+
+    format!(
+        "{}.M{}P{}Q{count}.{host}",
+        now.as_secs(),
+        now.subsec_micros(),
+        process::id(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs};
+
+    use maildirs::Maildir;
+
+    use super::{deliver, unique_name};
+    use crate::maildir::{config::MaildirConfig, name::MaildirName};
+
+    #[test]
+    fn generate_unique_names() {
+        let name = unique_name();
+        assert_ne!(name, unique_name());
+        assert!(!name.contains(['/', ':']));
+        assert!(MaildirName::parse(&name).time.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deliver_with_file_mode() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mdir = Maildir::from(dir.path());
+        mdir.create_all().unwrap();
+
+        let config = MaildirConfig {
+            file_mode: Some(0o600),
+            ..Default::default()
+        };
+
+        let id = deliver(&mdir, &config, b"new", &BTreeSet::new()).unwrap();
+        let path = dir.path().join("new").join(&id);
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o600);
+
+        let letters = BTreeSet::from_iter(['S', 'a']);
+        let id = deliver(&mdir, &config, b"cur", &letters).unwrap();
+        let path = dir.path().join("cur").join(format!("{id}:2,Sa"));
+        assert_eq!(fs::read(&path).unwrap(), b"cur");
+
+        // nothing is left behind in tmp
+        assert_eq!(fs::read_dir(dir.path().join("tmp")).unwrap().count(), 0);
+    }
+}
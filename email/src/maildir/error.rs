@@ -16,6 +16,62 @@ pub enum Error {
     CheckUpCurrentDirectoryError(#[source] maildirs::Error),
     #[error("cannot create maildir folder structure at {0}")]
     CreateFolderStructureError(#[source] maildirs::Error, PathBuf),
+    #[error("cannot parse maildir search query: {0}")]
+    ParseSearchQueryError(String),
+    #[error("cannot search maildir message bodies")]
+    SearchBodiesTaskError(#[source] tokio::task::JoinError),
+    #[error("cannot deliver maildir message to {1}")]
+    DeliverMessageError(#[source] std::io::Error, PathBuf),
+    #[error("cannot read maildir message at {1}")]
+    ReadEntryError(#[source] std::io::Error, PathBuf),
+    #[error("cannot move maildir message from {1} to {2}")]
+    MoveEntryError(#[source] std::io::Error, PathBuf, PathBuf),
+    #[error("cannot rename maildir message from {1} to {2} to update its flags")]
+    RenameEntryWithFlagsError(#[source] std::io::Error, PathBuf, PathBuf),
+    #[error("cannot save maildir keywords at {1}")]
+    SaveKeywordsError(#[source] std::io::Error, PathBuf),
+    #[error("cannot acquire maildir delivery lock at {1}")]
+    AcquireDeliveryLockError(#[source] std::io::Error, PathBuf),
+    #[error("cannot acquire maildir delivery lock at {0}: request timed out")]
+    AcquireDeliveryLockTimedOutError(PathBuf),
+    #[error("cannot list maildir trash messages from {1}")]
+    ListTrashEntriesError(#[source] maildirs::Error, PathBuf),
+    #[error("cannot remove maildir trash message at {1}")]
+    RemoveTrashEntryError(#[source] maildirs::Error, PathBuf),
+    #[error("cannot set permissions of maildir path {1}")]
+    SetPermissionsError(#[source] std::io::Error, PathBuf),
+    #[error("cannot check up maildir: cannot read directory at {1}")]
+    CheckUpReadAccessError(#[source] std::io::Error, PathBuf),
+    #[error("cannot check up maildir: directory at {1} is read-only")]
+    CheckUpReadOnlyFilesystemError(#[source] std::io::Error, PathBuf),
+    #[error("cannot check up maildir: cannot write to directory at {1}")]
+    CheckUpWriteAccessError(#[source] std::io::Error, PathBuf),
+    #[error("cannot check up maildir: cannot get available disk space at {1}")]
+    CheckUpAvailableSpaceError(#[source] std::io::Error, PathBuf),
+    #[error("cannot check up maildir: disk at {0} is full ({1} bytes available, {2} required)")]
+    CheckUpDiskFullError(PathBuf, u64, u64),
+
+    #[cfg(feature = "imap")]
+    #[error("cannot list imap mailboxes for the maildir migration")]
+    MigrateListImapMailboxesError(#[source] crate::imap::Error),
+    #[cfg(feature = "imap")]
+    #[error("cannot examine imap mailbox for the maildir migration")]
+    MigrateExamineImapMailboxError(#[source] crate::imap::Error),
+    #[cfg(feature = "imap")]
+    #[error("cannot fetch imap envelopes for the maildir migration")]
+    MigrateFetchImapEnvelopesError(#[source] crate::imap::Error),
+    #[cfg(feature = "imap")]
+    #[error("cannot create imap mailbox for the maildir migration")]
+    MigrateCreateImapMailboxError(#[source] crate::imap::Error),
+    #[cfg(feature = "imap")]
+    #[error("cannot read maildir message at {1} for the migration")]
+    MigrateReadMessageError(#[source] std::io::Error, PathBuf),
+    #[cfg(feature = "imap")]
+    #[error("cannot parse maildir message at {1} for the migration")]
+    MigrateParseMessageError(#[source] Box<crate::Error>, PathBuf),
+    #[cfg(feature = "imap")]
+    #[error("cannot append maildir message at {1} to imap mailbox")]
+    MigrateAppendMessageError(#[source] crate::imap::Error, PathBuf),
 
     #[error(transparent)]
     ExpandPathError(#[from] shellexpand_utils::Error),
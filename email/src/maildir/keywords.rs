@@ -0,0 +1,205 @@
+//! # Maildir keywords
+//!
+//! Module dedicated to Maildir keywords, which store custom flags in
+//! file names. The main structure of this module is
+//! [`MaildirKeywords`].
+//!
+//! The Maildir info part only defines a few uppercase flag letters.
+//! Like Dovecot, custom flags are stored as lowercase letters, from
+//! `a` to `z`, mapped to their name by a `dovecot-keywords` file at
+//! the root of the Maildir folder. Each line of this file contains
+//! the index of the letter and the name of the keyword, separated by
+//! a space, so names can contain spaces and non-ASCII characters.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+use super::{Error, Result};
+
+/// The name of the keywords file.
+pub const KEYWORDS_FILE_NAME: &str = "dovecot-keywords";
+
+/// The maximum number of keywords of a Maildir folder, one per
+/// lowercase letter.
+pub const MAX_KEYWORDS: usize = 26;
+
+/// The keywords of a Maildir folder.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MaildirKeywords {
+    /// The path of the keywords file.
+    path: PathBuf,
+
+    /// The keyword names, indexed by letter.
+    keywords: [Option<String>; MAX_KEYWORDS],
+
+    /// Whether keywords were added since the last save.
+    changed: bool,
+}
+
+impl MaildirKeywords {
+    /// Load the keywords of the Maildir folder at the given path.
+    ///
+    /// A missing or unreadable keywords file gives no keyword, and
+    /// invalid lines are skipped.
+    pub fn load(mdir_path: impl AsRef<Path>) -> Self {
+        let mut keywords = Self {
+            path: mdir_path.as_ref().join(KEYWORDS_FILE_NAME),
+            ..Default::default()
+        };
+
+        let content = match fs::read_to_string(&keywords.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return keywords,
+            Err(err) => {
+                debug!(?err, "cannot read maildir keywords at {:?}", keywords.path);
+                return keywords;
+            }
+        };
+
+        for line in content.lines() {
+            let Some((index, name)) = line.split_once(' ') else {
+                continue;
+            };
+
+            match index.parse::<usize>() {
+                Ok(index) if index < MAX_KEYWORDS && !name.is_empty() => {
+                    keywords.keywords[index] = Some(name.to_owned());
+                }
+                _ => debug!("skipping invalid maildir keyword line {line:?}"),
+            }
+        }
+
+        keywords
+    }
+
+    /// Get the keyword stored with the given letter, if any.
+    pub fn keyword(&self, letter: char) -> Option<&str> {
+        let index = (letter as usize).checked_sub('a' as usize)?;
+        self.keywords.get(index)?.as_deref()
+    }
+
+    /// Get the letter of the given keyword, if any.
+    pub fn letter(&self, keyword: &str) -> Option<char> {
+        self.keywords
+            .iter()
+            .position(|name| name.as_deref() == Some(keyword))
+            .map(to_letter)
+    }
+
+    /// Get the letter of the given keyword, registering the keyword
+    /// if needed.
+    ///
+    /// Returns `None` if the keyword cannot be stored, because it
+    /// is empty or contains a line break, or because all letters are
+    /// taken.
+    pub fn insert(&mut self, keyword: &str) -> Option<char> {
+        if let Some(letter) = self.letter(keyword) {
+            return Some(letter);
+        }
+
+        if keyword.is_empty() || keyword.contains(['\r', '\n']) {
+            return None;
+        }
+
+        let index = self.keywords.iter().position(Option::is_none)?;
+        self.keywords[index] = Some(keyword.to_owned());
+        self.changed = true;
+
+        Some(to_letter(index))
+    }
+
+    /// Save the keywords to their file, if keywords were added.
+    ///
+    /// The file is written next to its final location then renamed,
+    /// so that concurrent readers never see a partial file.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        let mut content = String::new();
+        for (index, name) in self.keywords.iter().enumerate() {
+            if let Some(name) = name {
+                content.push_str(&format!("{index} {name}\n"));
+            }
+        }
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        fs::write(&tmp, content)
+            .and_then(|()| fs::rename(&tmp, &self.path))
+            .map_err(|err| Error::SaveKeywordsError(err, self.path.clone()))?;
+
+        self.changed = false;
+        Ok(())
+    }
+}
+
+/// Get the letter of the given keyword index.
+fn to_letter(index: usize) -> char {
+    char::from(b'a' + index as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{MaildirKeywords, KEYWORDS_FILE_NAME, MAX_KEYWORDS};
+
+    #[test]
+    fn save_then_load() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut keywords = MaildirKeywords::load(dir.path());
+        assert_eq!(keywords.insert("has space"), Some('a'));
+        assert_eq!(keywords.insert("café"), Some('b'));
+        assert_eq!(keywords.insert("has space"), Some('a'));
+        assert_eq!(keywords.insert("line\nbreak"), None);
+        assert_eq!(keywords.insert(""), None);
+        keywords.save().unwrap();
+
+        let content = fs::read_to_string(dir.path().join(KEYWORDS_FILE_NAME)).unwrap();
+        assert_eq!(content, "0 has space\n1 café\n");
+
+        let keywords = MaildirKeywords::load(dir.path());
+        assert_eq!(keywords.keyword('a'), Some("has space"));
+        assert_eq!(keywords.keyword('b'), Some("café"));
+        assert_eq!(keywords.keyword('c'), None);
+        assert_eq!(keywords.keyword('S'), None);
+        assert_eq!(keywords.letter("café"), Some('b'));
+        assert_eq!(keywords.letter("unknown"), None);
+    }
+
+    #[test]
+    fn load_dovecot_keywords() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "0 $Junk\n3 work stuff\n26 out of range\ninvalid\n";
+        fs::write(dir.path().join(KEYWORDS_FILE_NAME), content).unwrap();
+
+        let mut keywords = MaildirKeywords::load(dir.path());
+        assert_eq!(keywords.keyword('a'), Some("$Junk"));
+        assert_eq!(keywords.keyword('d'), Some("work stuff"));
+
+        // free letters are reused
+        assert_eq!(keywords.insert("new"), Some('b'));
+    }
+
+    #[test]
+    fn reject_keywords_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut keywords = MaildirKeywords::load(dir.path());
+        for i in 0..MAX_KEYWORDS {
+            assert!(keywords.insert(&format!("keyword {i}")).is_some());
+        }
+
+        assert_eq!(keywords.insert("one too many"), None);
+        assert_eq!(keywords.insert("keyword 25"), Some('z'));
+    }
+}
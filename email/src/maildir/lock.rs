@@ -0,0 +1,271 @@
+//! # Maildir delivery lock
+//!
+//! Module dedicated to the Maildir delivery lock. Maildir is designed
+//! to be lock-free, but processes delivering to the same Maildir at
+//! the same time can still race in edge cases (colliding unique
+//! names, concurrent `tmp` to `new` renames). The delivery lock
+//! serializes deliveries across processes, using the dot-locking
+//! convention: the lock is held by whoever manages to exclusively
+//! create the lock file. The main structure of this module is
+//! [`DeliveryLock`].
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::time::{sleep, Instant};
+use tracing::{debug, warn};
+
+use super::{Error, Result};
+
+/// The name of the lock file, placed in the `tmp` directory of the
+/// Maildir so that it is never mistaken for a message or a folder.
+pub const LOCK_FILE_NAME: &str = ".delivery.lock";
+
+/// The name of the file guarding the removal of stale lock files,
+/// placed next to the lock file.
+const BREAK_LOCK_FILE_NAME: &str = ".delivery.lock.break";
+
+/// The maximum amount of time to wait for the lock.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The amount of time to wait between two acquisition attempts.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The age after which a lock file is considered stale, in case its
+/// owner died without removing it.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// The number of locks acquired by the current process, used to make
+/// lock tokens unique.
+static LOCKS_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// The Maildir delivery lock.
+///
+/// The lock is released (the lock file is removed) when dropped.
+#[derive(Debug)]
+pub struct DeliveryLock {
+    path: PathBuf,
+
+    /// The token written to the lock file.
+    ///
+    /// The token identifies the owner of the lock file, so that a
+    /// lock file that has been taken over (after being considered
+    /// stale) is never removed by its previous owner.
+    token: String,
+}
+
+impl DeliveryLock {
+    /// Acquire the delivery lock of the Maildir at the given path.
+    ///
+    /// Waits for the lock to be released by its current owner, up to
+    /// 10 seconds. Lock files older than 5 minutes are considered
+    /// stale and are removed.
+    pub async fn acquire(mdir: impl AsRef<Path>) -> Result<Self> {
+        let path = mdir.as_ref().join("tmp").join(LOCK_FILE_NAME);
+        let token = new_token();
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    if let Err(err) = file.write_all(token.as_bytes()) {
+                        let _ = fs::remove_file(&path);
+                        return Err(Error::AcquireDeliveryLockError(err, path));
+                    }
+                    debug!(path = ?path, "maildir delivery lock acquired");
+                    return Ok(Self { path, token });
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if remove_stale_lock(&path) {
+                        continue;
+                    }
+                }
+                Err(err) => return Err(Error::AcquireDeliveryLockError(err, path)),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::AcquireDeliveryLockTimedOutError(path));
+            }
+
+            sleep(LOCK_RETRY_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for DeliveryLock {
+    fn drop(&mut self) {
+        // the lock file may have been taken over by another owner, in
+        // which case it must be left untouched
+        match fs::read_to_string(&self.path) {
+            Ok(token) if token == self.token => (),
+            Ok(_) => {
+                warn!(path = ?self.path, "maildir delivery lock taken over, not releasing it");
+                return;
+            }
+            Err(err) => {
+                warn!(path = ?self.path, "cannot release maildir delivery lock: {err}");
+                return;
+            }
+        }
+
+        match fs::remove_file(&self.path) {
+            Ok(()) => debug!(path = ?self.path, "maildir delivery lock released"),
+            Err(err) => warn!(path = ?self.path, "cannot release maildir delivery lock: {err}"),
+        }
+    }
+}
+
+/// Build a token unique to the current lock acquisition.
+fn new_token() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let count = LOCKS_COUNT.fetch_add(1, Ordering::Relaxed);
+
+    format!("{}.{nanos}.{count}", process::id())
+}
+
+/// Remove the lock file at the given path if it is stale, and
+/// return `true` if it was removed.
+///
+/// Checking the age of the lock file then removing it is not atomic:
+/// another process could remove the same stale lock file then
+/// acquire a fresh one in between, which would then be removed. Stale
+/// lock files are therefore only removed by the process that managed
+/// to exclusively create the break lock file, after checking again
+/// that the lock file is stale.
+fn remove_stale_lock(path: &Path) -> bool {
+    if !is_stale(path) {
+        return false;
+    }
+
+    let break_path = path.with_file_name(BREAK_LOCK_FILE_NAME);
+
+    if let Err(err) = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&break_path)
+    {
+        // the break lock file can only be left behind by a process
+        // that died while removing a stale lock file
+        if err.kind() == ErrorKind::AlreadyExists && is_stale(&break_path) {
+            warn!(path = ?break_path, "removing stale maildir delivery break lock");
+            let _ = fs::remove_file(&break_path);
+        }
+        return false;
+    }
+
+    let removed = is_stale(path) && {
+        warn!(path = ?path, "removing stale maildir delivery lock");
+        fs::remove_file(path).is_ok()
+    };
+
+    let _ = fs::remove_file(&break_path);
+
+    removed
+}
+
+/// Return `true` if the lock file at the given path is older than
+/// [`STALE_LOCK_AGE`].
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|mtime| SystemTime::now().duration_since(mtime).ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, SystemTime},
+    };
+
+    use tokio::time::sleep;
+
+    use super::{DeliveryLock, LOCK_FILE_NAME};
+
+    fn build_maildir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("tmp")).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn serialize_lock_owners() {
+        let dir = build_maildir();
+        let dir = dir.path().to_owned();
+
+        // every owner checks that it is alone in the critical
+        // section, and gives others a chance to enter it
+        let owners = Arc::new(AtomicUsize::new(0));
+        let max_owners = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let dir = dir.clone();
+                let owners = owners.clone();
+                let max_owners = max_owners.clone();
+                tokio::spawn(async move {
+                    let _lock = DeliveryLock::acquire(dir).await.unwrap();
+                    let n = owners.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_owners.fetch_max(n, Ordering::SeqCst);
+                    sleep(Duration::from_millis(20)).await;
+                    owners.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(max_owners.load(Ordering::SeqCst), 1);
+        assert!(!dir.join("tmp").join(LOCK_FILE_NAME).exists());
+    }
+
+    #[tokio::test]
+    async fn remove_stale_lock() {
+        let dir = build_maildir();
+        let path = dir.path().join("tmp").join(LOCK_FILE_NAME);
+
+        fs::write(&path, "dead owner").unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(60 * 60);
+        let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_modified(an_hour_ago).unwrap();
+
+        let lock = DeliveryLock::acquire(dir.path()).await.unwrap();
+        assert_ne!(fs::read_to_string(&path).unwrap(), "dead owner");
+
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn keep_lock_taken_over() {
+        let dir = build_maildir();
+        let path = dir.path().join("tmp").join(LOCK_FILE_NAME);
+
+        let lock = DeliveryLock::acquire(dir.path()).await.unwrap();
+
+        // the lock was considered stale, then acquired by another
+        // owner
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, "other owner").unwrap();
+
+        drop(lock);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "other owner");
+    }
+}
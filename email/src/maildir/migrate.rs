@@ -0,0 +1,269 @@
+//! # Maildir to IMAP migration
+//!
+//! Module dedicated to the one-shot migration of a whole Maildir to
+//! an IMAP account, as opposed to the continuous synchronization of
+//! the [`crate::sync`] module. The main structure of this module is
+//! [`MigrateMaildirToImap`].
+
+use std::{collections::HashSet, fmt, fs, path::Path, sync::Arc};
+
+use chrono::{DateTime, FixedOffset, SubsecRound, Utc};
+use tracing::{debug, info};
+
+use super::{Error, MaildirContextSync, Result};
+use crate::{
+    envelope::Envelope,
+    folder::{Folder, FolderKind, Folders, INBOX},
+    imap::ImapContext,
+};
+
+/// The migration progress callback.
+///
+/// The callback receives the report of each folder, as soon as the
+/// folder is migrated.
+pub type MigrateMaildirToImapProgress = Arc<dyn Fn(&FolderMigrationReport) + Send + Sync>;
+
+/// The migration report of a Maildir folder.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FolderMigrationReport {
+    /// The name of the Maildir folder.
+    pub folder: String,
+
+    /// The name of the IMAP mailbox the folder was migrated to.
+    pub mailbox: String,
+
+    /// Whether the IMAP mailbox had to be created.
+    pub created: bool,
+
+    /// The number of messages appended to the IMAP mailbox.
+    pub appended: usize,
+
+    /// The number of messages skipped because they were already
+    /// present in the IMAP mailbox.
+    pub skipped: usize,
+}
+
+/// The Maildir to IMAP migration.
+///
+/// Walks all the Maildir folders, creates the matching IMAP
+/// mailboxes, then appends each message with its flags and its
+/// internal date (taken from the modification time of the message
+/// file).
+///
+/// Messages are identified by their Message-ID: messages already
+/// present in the IMAP mailbox are skipped, which makes an
+/// interrupted migration resumable. Messages without Message-ID
+/// cannot be identified, so they are always appended.
+#[derive(Clone)]
+pub struct MigrateMaildirToImap {
+    mdir: MaildirContextSync,
+    imap: ImapContext,
+    delimiter: char,
+    progress: Option<MigrateMaildirToImapProgress>,
+}
+
+impl MigrateMaildirToImap {
+    pub fn new(mdir: &MaildirContextSync, imap: &ImapContext) -> Self {
+        Self {
+            mdir: mdir.clone(),
+            imap: imap.clone(),
+            delimiter: '/',
+            progress: None,
+        }
+    }
+
+    /// Set the hierarchy delimiter of the IMAP server, used to
+    /// translate nested Maildir folder names. Defaults to `/`.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set the progress callback, called after each migrated folder.
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(&FolderMigrationReport) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    fn report_progress(&self, report: &FolderMigrationReport) {
+        if let Some(progress) = &self.progress {
+            progress(report)
+        }
+    }
+
+    /// Migrate all the Maildir folders to the IMAP account.
+    pub async fn migrate(&self) -> Result<Vec<FolderMigrationReport>> {
+        info!("migrating maildir folders to imap");
+
+        let folders = self.list_maildir_folders().await;
+        debug!("found {} maildir folders", folders.len());
+
+        let mailboxes: HashSet<String> = {
+            let mut client = self.imap.client().await;
+            let config = client.account_config.clone();
+            client
+                .list_all_mailboxes(&config)
+                .await
+                .map_err(Error::MigrateListImapMailboxesError)?
+                .iter()
+                .map(|folder| folder.name.clone())
+                .collect()
+        };
+
+        let mut reports = Vec::with_capacity(folders.len());
+
+        for folder in folders.iter() {
+            let report = self.migrate_folder(folder, &mailboxes).await?;
+            self.report_progress(&report);
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+
+    async fn list_maildir_folders(&self) -> Folders {
+        let ctx = self.mdir.lock().await;
+        let mut folders = Folders::from_maildir_context(&ctx);
+
+        // with Maildir++, the inbox is usually the root folder, which
+        // is not listed as a subfolder
+        if ctx.maildir_config.is_inbox_root() && !folders.iter().any(Folder::is_inbox) {
+            folders.insert(
+                0,
+                Folder {
+                    kind: Some(FolderKind::Inbox),
+                    name: INBOX.to_owned(),
+                    desc: ctx.root.path().display().to_string(),
+                },
+            );
+        }
+
+        folders
+    }
+
+    /// Translate the given Maildir folder to its IMAP mailbox name.
+    fn to_imap_mailbox(&self, folder: &Folder) -> String {
+        if let Some(kind) = &folder.kind {
+            return self.imap.account_config.get_folder_alias(kind.as_str());
+        }
+
+        let delimiter = if self.mdir.maildir_config.maildirpp {
+            '.'
+        } else {
+            '/'
+        };
+
+        folder.name.replace(delimiter, &self.delimiter.to_string())
+    }
+
+    async fn migrate_folder(
+        &self,
+        folder: &Folder,
+        mailboxes: &HashSet<String>,
+    ) -> Result<FolderMigrationReport> {
+        let mailbox = self.to_imap_mailbox(folder);
+        info!(
+            "migrating maildir folder {} to imap mailbox {mailbox}",
+            folder.name
+        );
+
+        let mut report = FolderMigrationReport {
+            folder: folder.name.clone(),
+            mailbox: mailbox.clone(),
+            ..Default::default()
+        };
+
+        let entries: Vec<_> = {
+            let ctx = self.mdir.lock().await;
+            let mdir = ctx.get_maildir_from_folder_alias(&folder.name)?;
+            mdir.read()?.collect()
+        };
+
+        let mut client = self.imap.client().await;
+        let mailbox_encoded = client.encode_mailbox(&mailbox);
+        let mut message_ids = HashSet::new();
+
+        if mailboxes.contains(&mailbox) || FolderKind::matches_inbox(&mailbox) {
+            let data = client
+                .examine_mailbox(&mailbox_encoded)
+                .await
+                .map_err(Error::MigrateExamineImapMailboxError)?;
+
+            if data.exists.unwrap_or_default() > 0 {
+                let envelopes = client
+                    .fetch_all_envelopes()
+                    .await
+                    .map_err(Error::MigrateFetchImapEnvelopesError)?;
+                let envelopes = envelopes.iter().filter(|e| !e.has_generated_message_id());
+                message_ids.extend(envelopes.map(|e| e.message_id.clone()));
+            }
+        } else {
+            client
+                .create_mailbox(&mailbox_encoded)
+                .await
+                .map_err(Error::MigrateCreateImapMailboxError)?;
+            report.created = true;
+        }
+
+        for entry in entries {
+            let path = entry.path().to_owned();
+
+            let msg =
+                fs::read(&path).map_err(|err| Error::MigrateReadMessageError(err, path.clone()))?;
+            let date = internal_date(&path)?;
+            let envelope = Envelope::try_from(entry)
+                .map_err(|err| Error::MigrateParseMessageError(Box::new(err), path.clone()))?;
+
+            // generated Message-IDs only depend on the Date header,
+            // they cannot tell messages apart
+            if !envelope.has_generated_message_id()
+                && !message_ids.insert(envelope.message_id.clone())
+            {
+                debug!(
+                    "message {} already migrated, skipping it",
+                    envelope.message_id
+                );
+                report.skipped += 1;
+                continue;
+            }
+
+            client
+                .add_message_with_date(
+                    &mailbox_encoded,
+                    envelope.flags.to_imap_flags_iter(),
+                    &msg,
+                    date,
+                )
+                .await
+                .map_err(|err| Error::MigrateAppendMessageError(err, path))?;
+
+            report.appended += 1;
+        }
+
+        Ok(report)
+    }
+}
+
+impl fmt::Debug for MigrateMaildirToImap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MigrateMaildirToImap")
+            .field("imap", &self.imap)
+            .field("delimiter", &self.delimiter)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Get the internal date of the given message file, which is the
+/// date the message was delivered to the Maildir.
+fn internal_date(path: &Path) -> Result<DateTime<FixedOffset>> {
+    let mtime = fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .map_err(|err| Error::MigrateReadMessageError(err, path.to_owned()))?;
+
+    // IMAP internal dates do not have sub-second precision
+    Ok(DateTime::<Utc>::from(mtime).trunc_subsecs(0).fixed_offset())
+}
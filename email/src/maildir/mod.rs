@@ -1,10 +1,27 @@
+pub mod cache;
+pub(crate) mod check_up;
 pub mod config;
+pub(crate) mod deliver;
 mod error;
-
-use std::{ops::Deref, path::PathBuf, sync::Arc};
+pub mod keywords;
+pub mod lock;
+#[cfg(feature = "imap")]
+pub mod migrate;
+pub mod name;
+pub(crate) mod perms;
+pub mod search;
+pub mod trash;
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
-use maildirs::{Maildir, Maildirs};
+use maildirs::{Maildir, MaildirEntry, Maildirs};
 use shellexpand_utils::{shellexpand_path, try_shellexpand_path};
 use tokio::sync::Mutex;
 use tracing::info;
@@ -33,9 +50,11 @@
     },
     folder::{
         add::{maildir::AddMaildirFolder, AddFolder},
+        cursor::{maildir::GetMaildirFolderCursor, GetFolderCursor},
         delete::{maildir::DeleteMaildirFolder, DeleteFolder},
         expunge::{maildir::ExpungeMaildirFolder, ExpungeFolder},
         list::{maildir::ListMaildirFolders, ListFolders},
+        rename::{maildir::RenameMaildirFolder, RenameFolder},
         FolderKind,
     },
     message::{
@@ -71,8 +90,9 @@ pub fn get_maildir_from_folder_alias(&self, folder: &str) -> Result<Maildir> {
         let folder = self.account_config.get_folder_alias(folder);
 
         // If the folder matches to the inbox folder kind, create a
-        // maildir instance from the root folder.
-        if self.maildir_config.maildirpp && FolderKind::matches_inbox(&folder) {
+        // maildir instance from the root folder, unless the inbox is
+        // stored in a subfolder.
+        if self.maildir_config.is_inbox_root() && FolderKind::matches_inbox(&folder) {
             return Ok(Maildir::from(try_shellexpand_path(self.root.path())?));
         }
 
@@ -81,6 +101,55 @@ pub fn get_maildir_from_folder_alias(&self, folder: &str) -> Result<Maildir> {
     }
 }
 
+/// Return `true` if the given Maildir entry is located in the `new`
+/// directory.
+///
+/// As per the Maildir specification, messages in `new` have not been
+/// seen yet, and carry no flag.
+pub(crate) fn is_new_entry(entry: &MaildirEntry) -> bool {
+    entry
+        .path()
+        .parent()
+        .and_then(Path::file_name)
+        .map(|dir| dir == "new")
+        .unwrap_or_default()
+}
+
+/// Get the path of the Maildir entry with the given unique name and
+/// flag letters.
+///
+/// Flags can only be stored in `cur`, so only new entries without
+/// flags are located in `new`.
+pub(crate) fn entry_path(mdir: &Maildir, id: &str, letters: &BTreeSet<char>, new: bool) -> PathBuf {
+    if new && letters.is_empty() {
+        return mdir.path().join("new").join(id);
+    }
+
+    let letters: String = letters.iter().collect();
+    mdir.path().join("cur").join(format!("{id}:2,{letters}"))
+}
+
+/// Rename the given Maildir entry so that its info carries the given
+/// flag letters.
+///
+/// Entries from `new` are moved to `cur`, unless they have no flag
+/// to carry. Returns the new path of the entry.
+pub(crate) fn rename_entry_with_flags(
+    mdir: &Maildir,
+    entry: &MaildirEntry,
+    letters: &BTreeSet<char>,
+) -> Result<PathBuf> {
+    let from = entry.path().to_owned();
+    let to = entry_path(mdir, entry.id()?, letters, is_new_entry(entry));
+
+    if from != to {
+        fs::rename(&from, &to)
+            .map_err(|err| Error::RenameEntryWithFlagsError(err, from, to.clone()))?;
+    }
+
+    Ok(to)
+}
+
 /// The sync version of the Maildir backend context.
 ///
 /// This is just a Maildir session wrapped into a mutex, so the same
@@ -151,6 +220,7 @@ async fn configure(&mut self) -> AnyResult<()> {
             Maildir::from(mdir.path())
                 .create_all()
                 .map_err(|err| Error::CreateFolderStructureError(err, mdir.path().to_owned()))?;
+            perms::set_folder_mode(&self.mdir_config, mdir.path())?;
         }
 
         Ok(())
@@ -188,6 +258,14 @@ fn delete_folder(&self) -> Option<BackendFeature<Self::Context, dyn DeleteFolder
         Some(Arc::new(DeleteMaildirFolder::some_new_boxed))
     }
 
+    fn rename_folder(&self) -> Option<BackendFeature<Self::Context, dyn RenameFolder>> {
+        Some(Arc::new(RenameMaildirFolder::some_new_boxed))
+    }
+
+    fn get_folder_cursor(&self) -> Option<BackendFeature<Self::Context, dyn GetFolderCursor>> {
+        Some(Arc::new(GetMaildirFolderCursor::some_new_boxed))
+    }
+
     fn get_envelope(&self) -> Option<BackendFeature<Self::Context, dyn GetEnvelope>> {
         Some(Arc::new(GetMaildirEnvelope::some_new_boxed))
     }
@@ -285,14 +363,10 @@ pub fn some_new_boxed(ctx: &MaildirContextSync) -> Option<Box<dyn CheckUp>> {
 #[async_trait]
 impl CheckUp for CheckUpMaildir {
     async fn check_up(&self) -> AnyResult<()> {
-        // FIXME
-        //
-        // let ctx = self.ctx.lock().await;
-
-        // ctx.root
-        //     .list_cur()
-        //     .try_for_each(|e| e.map(|_| ()))
-        //     .map_err(Error::CheckUpCurrentDirectoryError)?;
+        info!("checking up maildir");
+
+        let ctx = self.ctx.lock().await;
+        check_up::check_up(&ctx)?;
 
         Ok(())
     }
@@ -0,0 +1,172 @@
+//! # Maildir file names
+//!
+//! Module dedicated to Maildir file names. Unique names usually carry
+//! hints about the message they name, which can be read without
+//! opening (or even stating) the file. The main structure of this
+//! module is [`MaildirName`].
+
+use std::{fs, path::Path, time::UNIX_EPOCH};
+
+/// The separator between the unique name and the info.
+const INFO_SEPARATOR: char = ':';
+
+/// The prefix of the info containing flags.
+const FLAGS_INFO_PREFIX: &str = "2,";
+
+/// The parsed Maildir file name.
+///
+/// Maildir file names follow the `<time>.<unique>.<host>` convention,
+/// optionally followed by Maildir++ fields like `,S=<size>`, then by
+/// the info (`:2,<flags>`). Parsing is lenient: hints that cannot be
+/// found are just left empty.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MaildirName<'a> {
+    /// The delivery time, in seconds since the Unix epoch.
+    pub time: Option<u64>,
+
+    /// The size of the message, in bytes (Maildir++ `,S=` field).
+    pub size: Option<u64>,
+
+    /// The info of the file name, after the `:` separator.
+    pub info: Option<&'a str>,
+}
+
+impl<'a> MaildirName<'a> {
+    /// Parse the given Maildir file name.
+    pub fn parse(name: &'a str) -> Self {
+        let (unique, info) = match name.split_once(INFO_SEPARATOR) {
+            Some((unique, info)) => (unique, Some(info)),
+            None => (name, None),
+        };
+
+        let time = unique.split('.').next().and_then(|time| time.parse().ok());
+
+        let size = unique
+            .split(',')
+            .skip(1)
+            .find_map(|field| field.strip_prefix("S="))
+            .and_then(|size| size.parse().ok());
+
+        Self { time, size, info }
+    }
+
+    /// Parse the file name of the given path.
+    ///
+    /// Returns `None` if the path has no valid UTF-8 file name.
+    pub fn from_path(path: &'a Path) -> Option<Self> {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(Self::parse)
+    }
+
+    /// Return the flags of the info, as a string of flag characters.
+    ///
+    /// The string is empty if the info does not contain flags.
+    pub fn flags(&self) -> &'a str {
+        self.info
+            .and_then(|info| info.strip_prefix(FLAGS_INFO_PREFIX))
+            .unwrap_or_default()
+    }
+}
+
+/// Get the delivery time of the Maildir message at the given path,
+/// in seconds.
+///
+/// The time is taken from the file name when possible, otherwise
+/// from the modification time of the file.
+pub fn delivery_time(path: &Path) -> u64 {
+    let time = MaildirName::from_path(path).and_then(|name| name.time);
+
+    time.unwrap_or_else(|| {
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .map(|mtime| mtime.as_secs())
+            .unwrap_or_default()
+    })
+}
+
+/// Get the size of the Maildir message at the given path, in bytes.
+///
+/// The size is taken from the file name when possible, otherwise
+/// from the metadata of the file.
+pub fn size(path: &Path) -> u64 {
+    let size = MaildirName::from_path(path).and_then(|name| name.size);
+
+    size.unwrap_or_else(|| {
+        fs::metadata(path)
+            .map(|meta| meta.len())
+            .unwrap_or_default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{delivery_time, size, MaildirName};
+
+    #[test]
+    fn parse_name_with_size() {
+        let name = MaildirName::parse("1700000000.M42P1.localhost,S=1234,W=1270:2,RS");
+
+        assert_eq!(name.time, Some(1_700_000_000));
+        assert_eq!(name.size, Some(1234));
+        assert_eq!(name.info, Some("2,RS"));
+        assert_eq!(name.flags(), "RS");
+    }
+
+    #[test]
+    fn parse_name_without_size() {
+        let name = MaildirName::parse("1700000000.M42P1.localhost:2,");
+
+        assert_eq!(name.time, Some(1_700_000_000));
+        assert_eq!(name.size, None);
+        assert_eq!(name.info, Some("2,"));
+        assert_eq!(name.flags(), "");
+    }
+
+    #[test]
+    fn parse_name_with_various_infos() {
+        // new messages have no info
+        let name = MaildirName::parse("1700000000.M42P1.localhost,S=12");
+        assert_eq!(name.size, Some(12));
+        assert_eq!(name.info, None);
+        assert_eq!(name.flags(), "");
+
+        // experimental info
+        let name = MaildirName::parse("1700000000.M42P1.localhost:1,data");
+        assert_eq!(name.info, Some("1,data"));
+        assert_eq!(name.flags(), "");
+
+        let name = MaildirName::parse("1700000000.M42P1.localhost:2,DFPRST");
+        assert_eq!(name.flags(), "DFPRST");
+    }
+
+    #[test]
+    fn parse_name_without_hints() {
+        let name = MaildirName::parse("message,Sfoo:2,S");
+
+        assert_eq!(name.time, None);
+        assert_eq!(name.size, None);
+        assert_eq!(name.flags(), "S");
+    }
+
+    #[test]
+    fn fall_back_to_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        // hints are used, even if they do not match the file
+        let path = dir.join("1700000000.M1P1.localhost,S=1234:2,S");
+        fs::write(&path, "Subject: hinted\n\n").unwrap();
+        assert_eq!(delivery_time(&path), 1_700_000_000);
+        assert_eq!(size(&path), 1234);
+
+        let path = dir.join("message:2,S");
+        fs::write(&path, "Subject: unhinted\n\n").unwrap();
+        assert!(delivery_time(&path) > 1_700_000_000);
+        assert_eq!(size(&path), 19);
+    }
+}
@@ -0,0 +1,74 @@
+//! # Maildir permissions
+//!
+//! Module dedicated to the permissions of Maildir folders and
+//! messages. By default, they are created using the permissions
+//! given by the umask of the current process. On shared systems, the
+//! directory and file modes of [`MaildirConfig`] can enforce stricter
+//! (or looser) permissions, like `0700` and `0600`. Modes are only
+//! applied on unix systems.
+
+use std::path::Path;
+#[cfg(unix)]
+use std::{fs, os::unix::fs::PermissionsExt};
+
+#[cfg(unix)]
+use tracing::debug;
+
+#[cfg(unix)]
+use super::Error;
+use super::{config::MaildirConfig, Result};
+
+/// The sub-directories of a Maildir folder.
+#[cfg(unix)]
+const SUBDIRS: [&str; 3] = ["cur", "new", "tmp"];
+
+/// Apply the configured directory mode to the given Maildir folder,
+/// including its `cur`, `new` and `tmp` sub-directories.
+///
+/// Does nothing if no directory mode is configured.
+#[cfg(unix)]
+pub(crate) fn set_folder_mode(config: &MaildirConfig, path: &Path) -> Result<()> {
+    let Some(mode) = config.dir_mode else {
+        return Ok(());
+    };
+
+    set_mode(path, mode)?;
+
+    for subdir in SUBDIRS {
+        let path = path.join(subdir);
+        if path.is_dir() {
+            set_mode(&path, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply the configured file mode to the given Maildir message.
+///
+/// Does nothing if no file mode is configured.
+#[cfg(unix)]
+pub(crate) fn set_message_mode(config: &MaildirConfig, path: &Path) -> Result<()> {
+    match config.file_mode {
+        Some(mode) => set_mode(path, mode),
+        None => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    debug!("set mode {mode:o} to maildir path {}", path.display());
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|err| Error::SetPermissionsError(err, path.to_owned()))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_folder_mode(_config: &MaildirConfig, _path: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_message_mode(_config: &MaildirConfig, _path: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,466 @@
+//! Module dedicated to Maildir full-text search.
+//!
+//! This module allows searching through message bodies of a Maildir
+//! folder without relying on an external indexer like notmuch. The
+//! query grammar is intentionally simple: whitespace-separated terms
+//! are combined with AND, the `or` keyword combines terms with OR,
+//! and double quotes group words into a phrase.
+//!
+//! Structured predicates inspect the MIME structure instead of the
+//! body text: `has:attachment` matches messages having at least one
+//! attachment, and `filename:<glob>` matches messages having an
+//! attachment whose filename matches the given glob (`*` and `?`
+//! wildcards are supported, for example `filename:*.pdf`).
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use tracing::{debug, trace, warn};
+
+use super::{Error, MaildirContext, Result};
+use crate::{envelope::SingleId, message::Message};
+
+/// The maildir search query.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MaildirSearchQuery {
+    /// Match messages having a text body that contains the given
+    /// term, case-insensitively.
+    Term(String),
+
+    /// Match messages having at least one attachment.
+    HasAttachment,
+
+    /// Match messages having an attachment whose filename matches
+    /// the given lowercased glob pattern.
+    Filename(String),
+
+    /// Match messages matching both queries.
+    And(Box<MaildirSearchQuery>, Box<MaildirSearchQuery>),
+
+    /// Match messages matching at least one of the queries.
+    Or(Box<MaildirSearchQuery>, Box<MaildirSearchQuery>),
+}
+
+impl MaildirSearchQuery {
+    /// Return `true` if the given message matches the query.
+    ///
+    /// The given bodies are the lowercased text bodies of the
+    /// message, see [`extract_text_bodies`].
+    pub fn matches(&self, msg: &Message, bodies: &[String]) -> bool {
+        match self {
+            Self::Term(term) => bodies.iter().any(|body| body.contains(term.as_str())),
+            Self::HasAttachment => msg
+                .attachments()
+                .map(|attachments| !attachments.is_empty())
+                .unwrap_or(false),
+            Self::Filename(pattern) => msg
+                .attachments()
+                .map(|attachments| {
+                    attachments
+                        .iter()
+                        .any(|attachment| match &attachment.filename {
+                            Some(filename) => matches_glob(pattern, &filename.to_lowercase()),
+                            None => false,
+                        })
+                })
+                .unwrap_or(false),
+            Self::And(left, right) => left.matches(msg, bodies) && right.matches(msg, bodies),
+            Self::Or(left, right) => left.matches(msg, bodies) || right.matches(msg, bodies),
+        }
+    }
+
+    /// Return `true` if the given raw message matches the query.
+    pub fn matches_raw(&self, contents: &[u8]) -> bool {
+        let msg = Message::from(contents);
+        match msg.parsed() {
+            Ok(parsed) => self.matches(&msg, &extract_text_bodies(parsed)),
+            Err(_) => false,
+        }
+    }
+
+    fn parse_or(tokens: &mut Vec<Token>) -> Result<Self> {
+        let mut query = Self::parse_and(tokens)?;
+
+        while let Some(Token::Or) = tokens.last() {
+            tokens.pop();
+            let right = Self::parse_and(tokens)?;
+            query = Self::Or(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_and(tokens: &mut Vec<Token>) -> Result<Self> {
+        let mut query = Self::parse_term(tokens)?;
+
+        loop {
+            match tokens.last() {
+                Some(Token::And) => {
+                    tokens.pop();
+                }
+                Some(Token::Query(_)) => (),
+                _ => break,
+            }
+
+            let right = Self::parse_term(tokens)?;
+            query = Self::And(Box::new(query), Box::new(right));
+        }
+
+        Ok(query)
+    }
+
+    fn parse_term(tokens: &mut Vec<Token>) -> Result<Self> {
+        match tokens.pop() {
+            Some(Token::Query(query)) => Ok(query),
+            Some(Token::And) => Err(Error::ParseSearchQueryError("unexpected and".into())),
+            Some(Token::Or) => Err(Error::ParseSearchQueryError("unexpected or".into())),
+            None => Err(Error::ParseSearchQueryError("missing search term".into())),
+        }
+    }
+}
+
+impl FromStr for MaildirSearchQuery {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self> {
+        let mut tokens = tokenize(query)?;
+
+        // tokens are consumed from the end
+        tokens.reverse();
+
+        let query = Self::parse_or(&mut tokens)?;
+
+        if !tokens.is_empty() {
+            return Err(Error::ParseSearchQueryError("trailing tokens".into()));
+        }
+
+        Ok(query)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Query(MaildirSearchQuery),
+    And,
+    Or,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.peek().copied() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c == '"' {
+            chars.next();
+
+            let phrase = read_phrase(&mut chars)?;
+
+            if !phrase.is_empty() {
+                tokens.push(Token::Query(MaildirSearchQuery::Term(phrase)));
+            }
+
+            continue;
+        }
+
+        let mut word = String::new();
+
+        while let Some(c) = chars.peek().copied() {
+            if c.is_whitespace() || c == '"' {
+                break;
+            }
+            word.push(c);
+            chars.next();
+        }
+
+        let word = word.to_lowercase();
+
+        let token = if word == "and" {
+            Token::And
+        } else if word == "or" {
+            Token::Or
+        } else if let Some(value) = word.strip_prefix("has:") {
+            match value {
+                "attachment" => Token::Query(MaildirSearchQuery::HasAttachment),
+                value => {
+                    let reason = format!("unknown predicate has:{value}");
+                    return Err(Error::ParseSearchQueryError(reason));
+                }
+            }
+        } else if let Some(pattern) = word.strip_prefix("filename:") {
+            // the pattern may be quoted, for filenames containing
+            // whitespaces
+            let pattern = if pattern.is_empty() && chars.peek() == Some(&'"') {
+                chars.next();
+                read_phrase(&mut chars)?
+            } else {
+                pattern.to_owned()
+            };
+
+            if pattern.is_empty() {
+                let reason = String::from("missing filename pattern");
+                return Err(Error::ParseSearchQueryError(reason));
+            }
+
+            Token::Query(MaildirSearchQuery::Filename(pattern))
+        } else {
+            Token::Query(MaildirSearchQuery::Term(word))
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Read a lowercased phrase until the closing double quote.
+fn read_phrase(chars: &mut impl Iterator<Item = char>) -> Result<String> {
+    let mut phrase = String::new();
+
+    for c in chars {
+        if c == '"' {
+            return Ok(phrase.to_lowercase());
+        }
+        phrase.push(c);
+    }
+
+    Err(Error::ParseSearchQueryError("unterminated quote".into()))
+}
+
+/// Return `true` if the given name matches the given glob pattern.
+///
+/// Only the `*` (any sequence of characters) and `?` (any single
+/// character) wildcards are supported.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bn)) => {
+                    backtrack = Some((bp, bn + 1));
+                    p = bp + 1;
+                    n = bn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Extract the lowercased text bodies of the given message.
+///
+/// Only text and HTML body parts are taken into account, binary
+/// parts are skipped. Transfer encodings are already decoded by the
+/// parser.
+pub fn extract_text_bodies(msg: &mail_parser::Message) -> Vec<String> {
+    msg.text_bodies()
+        .chain(msg.html_bodies())
+        .filter_map(|part| part.text_contents())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl MaildirContext {
+    /// Search messages of the given folder.
+    ///
+    /// Returns the identifiers of messages matching the given query,
+    /// see [`MaildirSearchQuery`] for the grammar. Messages are
+    /// scanned in parallel using blocking tasks.
+    pub async fn search_bodies(&self, folder: &str, query: &str) -> Result<Vec<SingleId>> {
+        let query = MaildirSearchQuery::from_str(query)?;
+        debug!("searching maildir bodies of folder {folder} with query {query:?}");
+
+        let mdir = self.get_maildir_from_folder_alias(folder)?;
+
+        let entries: Vec<(String, PathBuf)> = mdir
+            .read()?
+            .filter_map(|entry| match entry.id() {
+                Ok(id) => Some((id.to_owned(), entry.path().to_owned())),
+                Err(err) => {
+                    warn!("cannot get maildir entry id, skipping it: {err}");
+                    trace!("{err:?}");
+                    None
+                }
+            })
+            .collect();
+
+        let workers = std::thread::available_parallelism()
+            .map(usize::from)
+            .unwrap_or(1);
+        let chunk_size = entries.len().div_ceil(workers).max(1);
+
+        let tasks: Vec<_> = entries
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                let query = query.clone();
+
+                tokio::task::spawn_blocking(move || {
+                    chunk
+                        .into_iter()
+                        .filter(|(_, path)| match fs::read(path) {
+                            Ok(contents) => query.matches_raw(&contents),
+                            Err(err) => {
+                                warn!("cannot read message at {path:?}, skipping it: {err}");
+                                trace!("{err:?}");
+                                false
+                            }
+                        })
+                        .map(|(id, _)| SingleId::from(id))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+
+        for task in tasks {
+            ids.extend(task.await.map_err(Error::SearchBodiesTaskError)?);
+        }
+
+        debug!("found {} maildir messages matching query", ids.len());
+
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{
+        matches_glob,
+        MaildirSearchQuery::{self, *},
+    };
+
+    fn term(term: &str) -> Box<MaildirSearchQuery> {
+        Box::new(Term(term.into()))
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            MaildirSearchQuery::from_str("Hello").unwrap(),
+            Term("hello".into())
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("hello world").unwrap(),
+            And(term("hello"), term("world"))
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("hello and world").unwrap(),
+            And(term("hello"), term("world"))
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("\"Hello World\" OR bye").unwrap(),
+            Or(term("hello world"), term("bye"))
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("a b or c").unwrap(),
+            Or(Box::new(And(term("a"), term("b"))), term("c"))
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("invoice Filename:*.PDF").unwrap(),
+            And(term("invoice"), Box::new(Filename("*.pdf".into())))
+        );
+
+        assert_eq!(
+            MaildirSearchQuery::from_str("has:attachment or filename:\"my file.txt\"").unwrap(),
+            Or(
+                Box::new(HasAttachment),
+                Box::new(Filename("my file.txt".into()))
+            )
+        );
+
+        assert!(MaildirSearchQuery::from_str("has:unknown").is_err());
+        assert!(MaildirSearchQuery::from_str("filename:").is_err());
+        assert!(MaildirSearchQuery::from_str("").is_err());
+        assert!(MaildirSearchQuery::from_str("hello or").is_err());
+        assert!(MaildirSearchQuery::from_str("\"hello").is_err());
+    }
+
+    #[test]
+    fn matches_decoded_bodies() {
+        let msg = concat!(
+            "From: alice@localhost\r\n",
+            "To: bob@localhost\r\n",
+            "Subject: Base64\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            // "The Quick Brown Fox\r\n"
+            "VGhlIFF1aWNrIEJyb3duIEZveA0K\r\n",
+        );
+
+        let query = MaildirSearchQuery::from_str("\"quick brown\"").unwrap();
+        assert!(query.matches_raw(msg.as_bytes()));
+
+        let query = MaildirSearchQuery::from_str("quick lazy").unwrap();
+        assert!(!query.matches_raw(msg.as_bytes()));
+
+        let query = MaildirSearchQuery::from_str("lazy or fox").unwrap();
+        assert!(query.matches_raw(msg.as_bytes()));
+    }
+
+    #[test]
+    fn skips_binary_parts() {
+        let msg = concat!(
+            "From: alice@localhost\r\n",
+            "To: bob@localhost\r\n",
+            "Subject: Binary\r\n",
+            "Content-Type: multipart/mixed; boundary=\"boundary\"\r\n",
+            "\r\n",
+            "--boundary\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "See attached.\r\n",
+            "--boundary\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "Content-Disposition: attachment; filename=\"secret.bin\"\r\n",
+            "\r\n",
+            "hidden treasure\r\n",
+            "--boundary--\r\n",
+        );
+
+        let query = MaildirSearchQuery::from_str("attached").unwrap();
+        assert!(query.matches_raw(msg.as_bytes()));
+
+        let query = MaildirSearchQuery::from_str("treasure").unwrap();
+        assert!(!query.matches_raw(msg.as_bytes()));
+    }
+
+    #[test]
+    fn glob() {
+        assert!(matches_glob("*.pdf", "invoice.pdf"));
+        assert!(matches_glob("*.pdf", ".pdf"));
+        assert!(matches_glob("invoice-??.pdf", "invoice-42.pdf"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("in*ce*", "invoice.pdf"));
+        assert!(!matches_glob("*.pdf", "invoice.pdf.txt"));
+        assert!(!matches_glob("invoice-?.pdf", "invoice-42.pdf"));
+        assert!(!matches_glob("invoice", "invoice.pdf"));
+    }
+}
@@ -0,0 +1,69 @@
+//! # Maildir trash retention
+//!
+//! Module dedicated to the automatic removal of old messages from the
+//! Maildir Trash folder. The age of a message is taken from the
+//! delivery timestamp its file name starts with, as per the Maildir
+//! naming convention, or from the modification time of its file
+//! otherwise.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::{debug, info};
+
+use super::{name, Error, MaildirContext, Result};
+
+impl MaildirContext {
+    /// Definitely remove the messages of the Trash folder older than
+    /// the given age.
+    ///
+    /// Only the Trash folder of the account is touched. Returns the
+    /// number of removed messages.
+    pub fn expunge_trash_older_than(&self, age: Duration) -> Result<usize> {
+        let folder = self.account_config.get_trash_folder_alias();
+        let mdir = self.get_maildir_from_folder_alias(&folder)?;
+
+        if !mdir.path().is_dir() {
+            debug!("maildir trash folder {folder} does not exist, skipping expunge");
+            return Ok(0);
+        }
+
+        let entries = mdir
+            .read()
+            .map_err(|err| Error::ListTrashEntriesError(err, mdir.path().to_owned()))?;
+
+        let now = SystemTime::now();
+        let mut count = 0;
+
+        for entry in entries {
+            // entries whose time cannot be found at all are kept
+            let time = match name::delivery_time(entry.path()) {
+                0 => continue,
+                secs => UNIX_EPOCH + Duration::from_secs(secs),
+            };
+
+            if now.duration_since(time).unwrap_or_default() <= age {
+                continue;
+            }
+
+            entry
+                .remove()
+                .map_err(|err| Error::RemoveTrashEntryError(err, entry.path().to_owned()))?;
+            count += 1;
+        }
+
+        info!("removed {count} maildir messages older than {age:?} from {folder}");
+        Ok(count)
+    }
+
+    /// Definitely remove the messages of the Trash folder older than
+    /// the retention time of the account configuration.
+    ///
+    /// Does nothing if no retention time is configured. Returns the
+    /// number of removed messages.
+    pub fn expunge_expired_trash(&self) -> Result<usize> {
+        match self.account_config.get_trash_retention() {
+            Some(age) => self.expunge_trash_older_than(age),
+            None => Ok(0),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::{any::Any, result};
+
+use thiserror::Error;
+
+use crate::{AnyBoxedError, AnyError};
+
+/// The global `Result` alias of the module.
+pub type Result<T> = result::Result<T, Error>;
+
+/// The global `Error` enum of the module.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cannot add in-memory folder {0}: folder already exists")]
+    AddFolderAlreadyExistsError(String),
+    #[error("cannot find in-memory folder {0}")]
+    FolderNotFoundError(String),
+    #[error("cannot find in-memory envelope {1} in folder {0}")]
+    GetEnvelopeNotFoundError(String, String),
+    #[error("cannot find in-memory envelopes {} in folder {0}", .1.join(", "))]
+    GetEnvelopesNotFoundError(String, Vec<String>),
+    #[error("cannot find in-memory message {1} in folder {0}")]
+    MessageNotFoundError(String, String),
+}
+
+impl AnyError for Error {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<Error> for AnyBoxedError {
+    fn from(err: Error) -> Self {
+        Box::new(err)
+    }
+}
@@ -0,0 +1,521 @@
+//! # In-memory backend
+//!
+//! Module dedicated to the in-memory backend. Folders and messages
+//! are kept in memory only, without any filesystem nor network
+//! access, which makes it a zero-dependency target for tests and
+//! demos. Unlike the [mock backend](crate::mock), calls actually
+//! change the state of the backend. The main structure of this
+//! module is [`InMemoryContextBuilder`].
+//!
+//! ```rust,ignore
+//! let ctx = InMemoryContextBuilder::new(account_config.clone()).with_folder("Archive");
+//! let backend = BackendBuilder::new(account_config, ctx).build().await?;
+//!
+//! let id = backend.add_message("INBOX", b"Subject: Hello\r\n\r\n").await?;
+//! backend.move_messages("INBOX", "Archive", &Id::single(id)).await?;
+//! ```
+
+mod error;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use paste::paste;
+
+#[doc(inline)]
+pub use self::error::{Error, Result};
+use crate::{
+    account::config::AccountConfig,
+    backend::{
+        context::{BackendContext, BackendContextBuilder},
+        feature::BackendFeature,
+    },
+    envelope::{
+        get::GetEnvelope,
+        list::{ListEnvelopes, ListEnvelopesOptions},
+        Envelope, Envelopes, Id, SingleId,
+    },
+    flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flag, Flags},
+    folder::{add::AddFolder, delete::DeleteFolder, list::ListFolders, Folder, Folders, TRASH},
+    message::{
+        add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
+        peek::PeekMessages, r#move::MoveMessages, Message, Messages,
+    },
+    AnyResult,
+};
+
+/// A message kept in memory, with its flags.
+#[derive(Clone, Debug, Default)]
+struct InMemoryMessage {
+    raw: Vec<u8>,
+    flags: Flags,
+}
+
+/// The folders and messages of the in-memory backend.
+///
+/// Messages are indexed by folder, then by id. Ids are unique across
+/// folders, and messages keep their id when they are moved.
+#[derive(Debug, Default)]
+struct InMemoryStore {
+    folders: HashMap<String, HashMap<String, InMemoryMessage>>,
+    next_id: usize,
+}
+
+impl InMemoryStore {
+    fn folder(&self, folder: &str) -> Result<&HashMap<String, InMemoryMessage>> {
+        self.folders
+            .get(folder)
+            .ok_or_else(|| Error::FolderNotFoundError(folder.to_owned()))
+    }
+
+    fn folder_mut(&mut self, folder: &str) -> Result<&mut HashMap<String, InMemoryMessage>> {
+        self.folders
+            .get_mut(folder)
+            .ok_or_else(|| Error::FolderNotFoundError(folder.to_owned()))
+    }
+
+    fn message_mut(&mut self, folder: &str, id: &str) -> Result<&mut InMemoryMessage> {
+        self.folder_mut(folder)?
+            .get_mut(id)
+            .ok_or_else(|| Error::MessageNotFoundError(folder.to_owned(), id.to_owned()))
+    }
+
+    fn add_message(&mut self, folder: &str, msg: InMemoryMessage) -> Result<String> {
+        self.next_id += 1;
+        let id = self.next_id.to_string();
+        self.folder_mut(folder)?.insert(id.clone(), msg);
+        Ok(id)
+    }
+
+    /// Take the messages matching the given id out of the given
+    /// folder.
+    ///
+    /// Nothing is taken if one of the messages cannot be found.
+    fn take_messages(&mut self, folder: &str, id: &Id) -> Result<Vec<(String, InMemoryMessage)>> {
+        let messages = self.folder_mut(folder)?;
+
+        if let Some(id) = id.iter().find(|id| !messages.contains_key(id.as_ref())) {
+            return Err(Error::MessageNotFoundError(folder.to_owned(), id.into()));
+        }
+
+        let taken = id
+            .iter()
+            .filter_map(|id| messages.remove_entry(id.as_ref()))
+            .collect();
+
+        Ok(taken)
+    }
+
+    fn update_flags(&mut self, folder: &str, id: &Id, f: impl Fn(&mut Flags)) -> Result<()> {
+        for id in id.iter() {
+            f(&mut self.message_mut(folder, &id)?.flags);
+        }
+
+        Ok(())
+    }
+}
+
+/// The in-memory backend context builder.
+///
+/// Clones share the same folders and messages. The inbox folder
+/// always exists, other folders need to be added first.
+#[derive(Clone, Debug)]
+pub struct InMemoryContextBuilder {
+    account_config: Arc<AccountConfig>,
+    store: Arc<Mutex<InMemoryStore>>,
+}
+
+impl InMemoryContextBuilder {
+    pub fn new(account_config: Arc<AccountConfig>) -> Self {
+        let mut store = InMemoryStore::default();
+        let inbox = account_config.get_inbox_folder_alias();
+        store.folders.insert(inbox, HashMap::new());
+
+        Self {
+            account_config,
+            store: Arc::new(Mutex::new(store)),
+        }
+    }
+
+    /// Add the given folder, unless it already exists.
+    pub fn with_folder(self, folder: impl AsRef<str>) -> Self {
+        let folder = self.account_config.get_folder_alias(folder.as_ref());
+        self.store
+            .lock()
+            .unwrap()
+            .folders
+            .entry(folder)
+            .or_default();
+        self
+    }
+}
+
+/// Macro for defining [`InMemoryContextBuilder`] features.
+macro_rules! memory_feature {
+    ($feat:ident) => {
+        paste! {
+            fn [<$feat:snake>](&self) -> Option<BackendFeature<Self::Context, dyn $feat>> {
+                Some(Arc::new(|ctx: &InMemoryContext| -> Option<Box<dyn $feat>> {
+                    Some(Box::new(ctx.clone()))
+                }))
+            }
+        }
+    };
+}
+
+#[async_trait]
+impl BackendContextBuilder for InMemoryContextBuilder {
+    type Context = InMemoryContext;
+
+    memory_feature!(AddFolder);
+    memory_feature!(ListFolders);
+    memory_feature!(DeleteFolder);
+    memory_feature!(GetEnvelope);
+    memory_feature!(ListEnvelopes);
+    memory_feature!(AddFlags);
+    memory_feature!(SetFlags);
+    memory_feature!(RemoveFlags);
+    memory_feature!(AddMessage);
+    memory_feature!(PeekMessages);
+    memory_feature!(GetMessages);
+    memory_feature!(CopyMessages);
+    memory_feature!(MoveMessages);
+    memory_feature!(DeleteMessages);
+
+    async fn build(self) -> AnyResult<Self::Context> {
+        Ok(InMemoryContext {
+            account_config: self.account_config,
+            store: self.store,
+        })
+    }
+}
+
+/// The in-memory backend context.
+///
+/// The context implements all the in-memory backend features itself.
+/// Folder aliases are resolved using the account configuration.
+#[derive(Clone, Debug)]
+pub struct InMemoryContext {
+    account_config: Arc<AccountConfig>,
+    store: Arc<Mutex<InMemoryStore>>,
+}
+
+impl InMemoryContext {
+    fn folder(&self, folder: &str) -> String {
+        self.account_config.get_folder_alias(folder)
+    }
+
+    fn envelope(id: &str, msg: &InMemoryMessage) -> Envelope {
+        Envelope::from_msg(id, msg.flags.clone(), Message::from(msg.raw.as_slice()))
+    }
+
+    fn peek_messages_from_store(&self, folder: &str, id: &Id) -> Result<Messages> {
+        let store = self.store.lock().unwrap();
+        let messages = store.folder(folder)?;
+
+        let msgs = id
+            .iter()
+            .map(|id| {
+                messages
+                    .get(id.as_ref())
+                    .map(|msg| msg.raw.clone())
+                    .ok_or_else(|| Error::MessageNotFoundError(folder.to_owned(), id.into()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Messages::from(msgs))
+    }
+}
+
+impl BackendContext for InMemoryContext {}
+
+#[async_trait]
+impl AddFolder for InMemoryContext {
+    async fn add_folder(&self, folder: &str) -> AnyResult<()> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        if store.folders.contains_key(&folder) {
+            return Err(Error::AddFolderAlreadyExistsError(folder).into());
+        }
+
+        store.folders.insert(folder, HashMap::new());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ListFolders for InMemoryContext {
+    async fn list_folders(&self) -> AnyResult<Folders> {
+        let store = self.store.lock().unwrap();
+
+        let mut folders: Vec<_> = store
+            .folders
+            .keys()
+            .map(|name| Folder {
+                kind: self
+                    .account_config
+                    .find_folder_kind_from_alias(name)
+                    .or_else(|| name.parse().ok()),
+                name: name.clone(),
+                desc: String::new(),
+            })
+            .collect();
+        folders.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Folders::from_iter(folders))
+    }
+}
+
+#[async_trait]
+impl DeleteFolder for InMemoryContext {
+    async fn delete_folder(&self, folder: &str) -> AnyResult<()> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        match store.folders.remove(&folder) {
+            Some(_) => Ok(()),
+            None => Err(Error::FolderNotFoundError(folder).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl GetEnvelope for InMemoryContext {
+    async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope> {
+        let folder = self.folder(folder);
+        let store = self.store.lock().unwrap();
+
+        let envelope = store
+            .folder(&folder)?
+            .get(id.as_str())
+            .map(|msg| Self::envelope(id.as_str(), msg))
+            .ok_or_else(|| Error::GetEnvelopeNotFoundError(folder.clone(), id.to_string()))?;
+
+        Ok(envelope)
+    }
+
+    async fn get_envelopes(&self, folder: &str, ids: &[SingleId]) -> AnyResult<Envelopes> {
+        let folder = self.folder(folder);
+        let store = self.store.lock().unwrap();
+        let messages = store.folder(&folder)?;
+
+        let mut envelopes = Vec::with_capacity(ids.len());
+        let mut missing = Vec::new();
+
+        for id in ids {
+            match messages.get(id.as_str()) {
+                Some(msg) => envelopes.push(Self::envelope(id.as_str(), msg)),
+                None => missing.push(id.to_string()),
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(Error::GetEnvelopesNotFoundError(folder, missing).into());
+        }
+
+        Ok(Envelopes::from_iter(envelopes))
+    }
+}
+
+#[async_trait]
+impl ListEnvelopes for InMemoryContext {
+    /// List envelopes of the given folder.
+    ///
+    /// Envelopes are sorted using the sorters of the query, but the
+    /// filters of the query are not applied.
+    async fn list_envelopes(
+        &self,
+        folder: &str,
+        opts: ListEnvelopesOptions,
+    ) -> AnyResult<Envelopes> {
+        let folder = self.folder(folder);
+        let store = self.store.lock().unwrap();
+
+        let mut envelopes: Envelopes = store
+            .folder(&folder)?
+            .iter()
+            .map(|(id, msg)| Self::envelope(id, msg))
+            .collect();
+        opts.sort_envelopes(&mut envelopes);
+
+        if opts.page_size == 0 {
+            return Ok(envelopes);
+        }
+
+        let page = envelopes
+            .iter()
+            .skip(opts.page * opts.page_size)
+            .take(opts.page_size)
+            .cloned()
+            .collect();
+
+        Ok(page)
+    }
+}
+
+#[async_trait]
+impl AddFlags for InMemoryContext {
+    async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        store.update_flags(&folder, id, |msg_flags| {
+            msg_flags.extend(flags.iter().cloned());
+        })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SetFlags for InMemoryContext {
+    async fn set_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        store.update_flags(&folder, id, |msg_flags| {
+            *msg_flags = flags.clone();
+        })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RemoveFlags for InMemoryContext {
+    async fn remove_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        store.update_flags(&folder, id, |msg_flags| {
+            msg_flags.retain(|flag| !flags.contains(flag));
+        })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AddMessage for InMemoryContext {
+    async fn add_message_with_flags(
+        &self,
+        folder: &str,
+        msg: &[u8],
+        flags: &Flags,
+    ) -> AnyResult<SingleId> {
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+
+        let msg = InMemoryMessage {
+            raw: msg.to_vec(),
+            flags: flags.clone(),
+        };
+        let id = store.add_message(&folder, msg)?;
+
+        Ok(SingleId::from(id))
+    }
+}
+
+#[async_trait]
+impl PeekMessages for InMemoryContext {
+    async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
+        let folder = self.folder(folder);
+        Ok(self.peek_messages_from_store(&folder, id)?)
+    }
+}
+
+#[async_trait]
+impl GetMessages for InMemoryContext {
+    /// Get the messages matching the given id, then flag them as
+    /// seen.
+    async fn get_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
+        let folder = self.folder(folder);
+        let msgs = self.peek_messages_from_store(&folder, id)?;
+
+        let mut store = self.store.lock().unwrap();
+        store.update_flags(&folder, id, |flags| {
+            flags.insert(Flag::Seen);
+        })?;
+
+        Ok(msgs)
+    }
+}
+
+#[async_trait]
+impl CopyMessages for InMemoryContext {
+    /// Copy the messages matching the given id to the given folder.
+    ///
+    /// Copies get new ids.
+    async fn copy_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> AnyResult<()> {
+        let from_folder = self.folder(from_folder);
+        let to_folder = self.folder(to_folder);
+        let mut store = self.store.lock().unwrap();
+
+        store.folder(&to_folder)?;
+
+        let messages = store.folder(&from_folder)?;
+        let copies = id
+            .iter()
+            .map(|id| {
+                messages
+                    .get(id.as_ref())
+                    .cloned()
+                    .ok_or_else(|| Error::MessageNotFoundError(from_folder.clone(), id.into()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for msg in copies {
+            store.add_message(&to_folder, msg)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MoveMessages for InMemoryContext {
+    /// Move the messages matching the given id to the given folder.
+    ///
+    /// Moved messages keep their id.
+    async fn move_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> AnyResult<()> {
+        let from_folder = self.folder(from_folder);
+        let to_folder = self.folder(to_folder);
+        let mut store = self.store.lock().unwrap();
+
+        store.folder(&to_folder)?;
+
+        let moved = store.take_messages(&from_folder, id)?;
+        store.folder_mut(&to_folder)?.extend(moved);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DeleteMessages for InMemoryContext {
+    /// Delete the messages matching the given id.
+    ///
+    /// Messages are deleted the same way as the Maildir backend: they
+    /// are flagged as deleted when the delete message style is
+    /// flag-based, definitely removed from the Trash folder, and
+    /// moved to the Trash folder otherwise.
+    async fn delete_messages(&self, folder: &str, id: &Id) -> AnyResult<()> {
+        if self.account_config.is_delete_message_style_flag() {
+            return self.add_flag(folder, id, Flag::Deleted).await;
+        }
+
+        if !self.account_config.is_trash_folder(folder) {
+            return self.move_messages(folder, TRASH, id).await;
+        }
+
+        let folder = self.folder(folder);
+        let mut store = self.store.lock().unwrap();
+        store.take_messages(&folder, id)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use std::{any::Any, result};
+
+use thiserror::Error;
+
+use crate::{AnyBoxedError, AnyError};
+
+/// The global `Result` alias of the module.
+pub type Result<T> = result::Result<T, Error>;
+
+/// The global `Error` enum of the module.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cannot find mock envelope {1} in folder {0}")]
+    GetEnvelopeNotFoundError(String, String),
+    #[error("cannot find mock message {1} in folder {0}")]
+    GetMessageNotFoundError(String, String),
+}
+
+impl AnyError for Error {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<Error> for AnyBoxedError {
+    fn from(err: Error) -> Self {
+        Box::new(err)
+    }
+}
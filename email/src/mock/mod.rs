@@ -0,0 +1,438 @@
+//! # Mock backend
+//!
+//! Module dedicated to the mock backend, used to test application
+//! logic without a real server. The mock backend records every
+//! feature call into a shared log, and answers with canned data
+//! seeded beforehand. The main structure of this module is
+//! [`MockContextBuilder`].
+//!
+//! ```rust,ignore
+//! let mock = MockContextBuilder::new().with_envelopes("INBOX", envelopes);
+//! let backend = BackendBuilder::new(account_config, mock.clone()).build().await?;
+//!
+//! // run the application logic against the backend
+//!
+//! assert_eq!(mock.calls(), vec![MockCall::ListEnvelopes { .. }]);
+//! ```
+
+mod error;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use paste::paste;
+
+#[doc(inline)]
+pub use self::error::{Error, Result};
+use crate::{
+    backend::{
+        context::{BackendContext, BackendContextBuilder},
+        feature::BackendFeature,
+    },
+    envelope::{
+        get::GetEnvelope,
+        list::{ListEnvelopes, ListEnvelopesOptions},
+        Envelope, Envelopes, Id, SingleId,
+    },
+    flag::{add::AddFlags, remove::RemoveFlags, set::SetFlags, Flags},
+    folder::{add::AddFolder, delete::DeleteFolder, list::ListFolders, Folders},
+    message::{
+        add::AddMessage, copy::CopyMessages, delete::DeleteMessages, get::GetMessages,
+        peek::PeekMessages, r#move::MoveMessages, Messages,
+    },
+    AnyResult,
+};
+
+/// A backend feature call recorded by the mock backend.
+///
+/// Arguments are recorded as given, folder aliases are not resolved.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MockCall {
+    AddFolder {
+        folder: String,
+    },
+    ListFolders,
+    DeleteFolder {
+        folder: String,
+    },
+    GetEnvelope {
+        folder: String,
+        id: SingleId,
+    },
+    ListEnvelopes {
+        folder: String,
+        opts: ListEnvelopesOptions,
+    },
+    AddFlags {
+        folder: String,
+        id: Id,
+        flags: Flags,
+    },
+    SetFlags {
+        folder: String,
+        id: Id,
+        flags: Flags,
+    },
+    RemoveFlags {
+        folder: String,
+        id: Id,
+        flags: Flags,
+    },
+    AddMessage {
+        folder: String,
+        msg: Vec<u8>,
+        flags: Flags,
+    },
+    PeekMessages {
+        folder: String,
+        id: Id,
+    },
+    GetMessages {
+        folder: String,
+        id: Id,
+    },
+    CopyMessages {
+        from_folder: String,
+        to_folder: String,
+        id: Id,
+    },
+    MoveMessages {
+        from_folder: String,
+        to_folder: String,
+        id: Id,
+    },
+    DeleteMessages {
+        folder: String,
+        id: Id,
+    },
+}
+
+/// The canned data returned by the mock backend.
+#[derive(Clone, Debug, Default)]
+pub struct MockData {
+    /// The folders returned when listing folders.
+    pub folders: Folders,
+
+    /// The envelopes returned when listing or getting envelopes, by
+    /// folder.
+    pub envelopes: HashMap<String, Envelopes>,
+
+    /// The raw messages returned when getting or peeking messages,
+    /// by folder and by id.
+    pub messages: HashMap<(String, String), Vec<u8>>,
+}
+
+/// The mock backend context builder.
+///
+/// Clones share the same log of calls, so a clone can be kept to
+/// inspect the calls made by the backend built from the builder.
+#[derive(Clone, Debug, Default)]
+pub struct MockContextBuilder {
+    data: Arc<MockData>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+}
+
+impl MockContextBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the canned data returned by the mock backend.
+    pub fn with_data(mut self, data: MockData) -> Self {
+        self.data = Arc::new(data);
+        self
+    }
+
+    /// Set the folders returned when listing folders.
+    pub fn with_folders(mut self, folders: Folders) -> Self {
+        Arc::make_mut(&mut self.data).folders = folders;
+        self
+    }
+
+    /// Set the envelopes of the given folder.
+    pub fn with_envelopes(mut self, folder: impl ToString, envelopes: Envelopes) -> Self {
+        Arc::make_mut(&mut self.data)
+            .envelopes
+            .insert(folder.to_string(), envelopes);
+        self
+    }
+
+    /// Set the raw message of the given folder and id.
+    pub fn with_message(
+        mut self,
+        folder: impl ToString,
+        id: impl ToString,
+        msg: impl Into<Vec<u8>>,
+    ) -> Self {
+        Arc::make_mut(&mut self.data)
+            .messages
+            .insert((folder.to_string(), id.to_string()), msg.into());
+        self
+    }
+
+    /// Get the calls recorded so far, in order.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Clear the calls recorded so far.
+    pub fn clear_calls(&self) {
+        self.calls.lock().unwrap().clear()
+    }
+}
+
+/// Macro for defining [`MockContextBuilder`] features.
+macro_rules! mock_feature {
+    ($feat:ident) => {
+        paste! {
+            fn [<$feat:snake>](&self) -> Option<BackendFeature<Self::Context, dyn $feat>> {
+                Some(Arc::new(|ctx: &MockContext| -> Option<Box<dyn $feat>> {
+                    Some(Box::new(ctx.clone()))
+                }))
+            }
+        }
+    };
+}
+
+#[async_trait]
+impl BackendContextBuilder for MockContextBuilder {
+    type Context = MockContext;
+
+    mock_feature!(AddFolder);
+    mock_feature!(ListFolders);
+    mock_feature!(DeleteFolder);
+    mock_feature!(GetEnvelope);
+    mock_feature!(ListEnvelopes);
+    mock_feature!(AddFlags);
+    mock_feature!(SetFlags);
+    mock_feature!(RemoveFlags);
+    mock_feature!(AddMessage);
+    mock_feature!(PeekMessages);
+    mock_feature!(GetMessages);
+    mock_feature!(CopyMessages);
+    mock_feature!(MoveMessages);
+    mock_feature!(DeleteMessages);
+
+    async fn build(self) -> AnyResult<Self::Context> {
+        Ok(MockContext {
+            data: self.data,
+            calls: self.calls,
+            next_id: Default::default(),
+        })
+    }
+}
+
+/// The mock backend context.
+///
+/// The context implements all the mock backend features itself.
+#[derive(Clone, Debug)]
+pub struct MockContext {
+    data: Arc<MockData>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl MockContext {
+    fn record(&self, call: MockCall) {
+        self.calls.lock().unwrap().push(call)
+    }
+
+    fn get_messages_from_data(&self, folder: &str, id: &Id) -> Result<Messages> {
+        let msgs = id
+            .iter()
+            .map(|id| {
+                self.data
+                    .messages
+                    .get(&(folder.to_owned(), id.to_string()))
+                    .cloned()
+                    .ok_or_else(|| Error::GetMessageNotFoundError(folder.to_owned(), id.into()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Messages::from(msgs))
+    }
+}
+
+impl BackendContext for MockContext {}
+
+#[async_trait]
+impl AddFolder for MockContext {
+    async fn add_folder(&self, folder: &str) -> AnyResult<()> {
+        self.record(MockCall::AddFolder {
+            folder: folder.to_owned(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ListFolders for MockContext {
+    async fn list_folders(&self) -> AnyResult<Folders> {
+        self.record(MockCall::ListFolders);
+        Ok(self.data.folders.clone())
+    }
+}
+
+#[async_trait]
+impl DeleteFolder for MockContext {
+    async fn delete_folder(&self, folder: &str) -> AnyResult<()> {
+        self.record(MockCall::DeleteFolder {
+            folder: folder.to_owned(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GetEnvelope for MockContext {
+    async fn get_envelope(&self, folder: &str, id: &SingleId) -> AnyResult<Envelope> {
+        self.record(MockCall::GetEnvelope {
+            folder: folder.to_owned(),
+            id: id.clone(),
+        });
+
+        let envelope = self
+            .data
+            .envelopes
+            .get(folder)
+            .and_then(|envelopes| envelopes.iter().find(|e| e.id == id.as_str()))
+            .cloned()
+            .ok_or_else(|| Error::GetEnvelopeNotFoundError(folder.to_owned(), id.to_string()))?;
+
+        Ok(envelope)
+    }
+}
+
+#[async_trait]
+impl ListEnvelopes for MockContext {
+    async fn list_envelopes(
+        &self,
+        folder: &str,
+        opts: ListEnvelopesOptions,
+    ) -> AnyResult<Envelopes> {
+        self.record(MockCall::ListEnvelopes {
+            folder: folder.to_owned(),
+            opts,
+        });
+
+        Ok(self.data.envelopes.get(folder).cloned().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl AddFlags for MockContext {
+    async fn add_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        self.record(MockCall::AddFlags {
+            folder: folder.to_owned(),
+            id: id.clone(),
+            flags: flags.clone(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SetFlags for MockContext {
+    async fn set_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        self.record(MockCall::SetFlags {
+            folder: folder.to_owned(),
+            id: id.clone(),
+            flags: flags.clone(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RemoveFlags for MockContext {
+    async fn remove_flags(&self, folder: &str, id: &Id, flags: &Flags) -> AnyResult<()> {
+        self.record(MockCall::RemoveFlags {
+            folder: folder.to_owned(),
+            id: id.clone(),
+            flags: flags.clone(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AddMessage for MockContext {
+    async fn add_message_with_flags(
+        &self,
+        folder: &str,
+        msg: &[u8],
+        flags: &Flags,
+    ) -> AnyResult<SingleId> {
+        self.record(MockCall::AddMessage {
+            folder: folder.to_owned(),
+            msg: msg.to_vec(),
+            flags: flags.clone(),
+        });
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        Ok(SingleId::from(id))
+    }
+}
+
+#[async_trait]
+impl PeekMessages for MockContext {
+    async fn peek_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
+        self.record(MockCall::PeekMessages {
+            folder: folder.to_owned(),
+            id: id.clone(),
+        });
+        Ok(self.get_messages_from_data(folder, id)?)
+    }
+}
+
+#[async_trait]
+impl GetMessages for MockContext {
+    async fn get_messages(&self, folder: &str, id: &Id) -> AnyResult<Messages> {
+        self.record(MockCall::GetMessages {
+            folder: folder.to_owned(),
+            id: id.clone(),
+        });
+        Ok(self.get_messages_from_data(folder, id)?)
+    }
+}
+
+#[async_trait]
+impl CopyMessages for MockContext {
+    async fn copy_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> AnyResult<()> {
+        self.record(MockCall::CopyMessages {
+            from_folder: from_folder.to_owned(),
+            to_folder: to_folder.to_owned(),
+            id: id.clone(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MoveMessages for MockContext {
+    async fn move_messages(&self, from_folder: &str, to_folder: &str, id: &Id) -> AnyResult<()> {
+        self.record(MockCall::MoveMessages {
+            from_folder: from_folder.to_owned(),
+            to_folder: to_folder.to_owned(),
+            id: id.clone(),
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DeleteMessages for MockContext {
+    async fn delete_messages(&self, folder: &str, id: &Id) -> AnyResult<()> {
+        self.record(MockCall::DeleteMessages {
+            folder: folder.to_owned(),
+            id: id.clone(),
+        });
+        Ok(())
+    }
+}
@@ -3,14 +3,30 @@
 //! This module contains the configuration specific to the Notmuch
 //! backend.
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
 
 use notmuch::{Database, DatabaseMode};
+use once_cell::sync::Lazy;
 use shellexpand_utils::shellexpand_path;
+use tracing::debug;
 
 #[doc(inline)]
 pub use super::{Error, Result};
 
+/// The parsed Notmuch configuration files, by path.
+///
+/// Files are parsed again only when their modification time changes.
+static USER_CONFIGS: Lazy<Mutex<HashMap<PathBuf, (Option<SystemTime>, NotmuchUserConfig)>>> =
+    Lazy::new(Default::default);
+
 /// The Notmuch backend config.
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 #[cfg_attr(
@@ -44,6 +60,12 @@ pub struct NotmuchConfig {
     /// Override the default Notmuch profile name.
     pub profile: Option<String>,
 
+    /// The tags added to messages added to the Notmuch database.
+    ///
+    /// Defaults to the `new.tags` from the Notmuch configuration file
+    /// if omitted.
+    pub new_tags: Option<Vec<String>>,
+
     #[cfg_attr(feature = "derive", serde(default))]
     pub maildirpp: bool,
 }
@@ -63,11 +85,20 @@ pub fn get_default_database_path() -> Result<PathBuf> {
     }
 
     /// Try to get the reference to the Notmuch database path.
+    ///
+    /// Tries `database_path` first, then the `database.path` from the
+    /// Notmuch configuration file, otherwise falls back to the default
+    /// Notmuch database path.
     pub fn try_get_database_path(&self) -> Result<PathBuf> {
-        match self.database_path.as_ref() {
-            Some(path) => Ok(shellexpand_path(path)),
-            None => Self::get_default_database_path(),
+        if let Some(path) = self.database_path.as_ref() {
+            return Ok(shellexpand_path(path));
+        }
+
+        if let Some(path) = self.find_notmuch_user_config()?.database_path {
+            return Ok(path);
         }
+
+        Self::get_default_database_path()
     }
 
     /// Try to get the reference to the Maildir path.
@@ -90,4 +121,193 @@ pub fn find_config_path(&self) -> Option<&Path> {
     pub fn find_profile(&self) -> Option<&str> {
         self.profile.as_deref()
     }
+
+    /// Find the tags added to new messages.
+    ///
+    /// Tries `new_tags` first, otherwise falls back to the `new.tags`
+    /// from the Notmuch configuration file.
+    pub fn find_new_tags(&self) -> Result<Vec<String>> {
+        if let Some(tags) = self.new_tags.as_ref() {
+            return Ok(tags.clone());
+        }
+
+        Ok(self
+            .find_notmuch_user_config()?
+            .new_tags
+            .unwrap_or_default())
+    }
+
+    /// Find and read the Notmuch configuration file.
+    ///
+    /// Returns an empty configuration if no file can be found. The
+    /// parsed file is cached, see [`NotmuchUserConfig::from_path_cached`].
+    pub fn find_notmuch_user_config(&self) -> Result<NotmuchUserConfig> {
+        let config_path = self.find_config_path();
+        let profile = self.find_profile();
+        let path = NotmuchUserConfig::find_path(config_path, profile, |key| env::var_os(key));
+
+        match path {
+            Some(path) => NotmuchUserConfig::from_path_cached(path),
+            None => Ok(NotmuchUserConfig::default()),
+        }
+    }
+}
+
+/// The Notmuch configuration file.
+///
+/// This structure contains the subset of the file used by the Notmuch
+/// backend. See `notmuch-config(1)`.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct NotmuchUserConfig {
+    /// The `database.path` entry.
+    pub database_path: Option<PathBuf>,
+
+    /// The `new.tags` entry.
+    pub new_tags: Option<Vec<String>>,
+}
+
+impl NotmuchUserConfig {
+    /// Find the Notmuch configuration file path.
+    ///
+    /// The lookup order follows Notmuch: the given config path, then
+    /// `$NOTMUCH_CONFIG`, then `$XDG_CONFIG_HOME/notmuch/<profile>/config`
+    /// and finally `~/.notmuch-config[.<profile>]`. The given config
+    /// path is returned as it is, other paths are returned only if
+    /// they exist: like Notmuch, a `$NOTMUCH_CONFIG` pointing to a
+    /// missing file falls back to the next paths. Environment
+    /// variables are read using the given `env` function.
+    pub fn find_path(
+        config_path: Option<&Path>,
+        profile: Option<&str>,
+        env: impl Fn(&str) -> Option<OsString>,
+    ) -> Option<PathBuf> {
+        if let Some(path) = config_path {
+            return Some(shellexpand_path(path));
+        }
+
+        if let Some(path) = env("NOTMUCH_CONFIG").map(PathBuf::from) {
+            if path.is_file() {
+                return Some(path);
+            }
+
+            debug!(?path, "$NOTMUCH_CONFIG file not found, falling back");
+        }
+
+        let profile = profile
+            .map(ToOwned::to_owned)
+            .or_else(|| env("NOTMUCH_PROFILE").and_then(|p| p.into_string().ok()));
+
+        let xdg_config_dir = env("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+        if let Some(dir) = xdg_config_dir {
+            let profile = profile.as_deref().unwrap_or("default");
+            let path = dir.join("notmuch").join(profile).join("config");
+
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        if let Some(home) = env("HOME") {
+            let name = match profile.as_ref() {
+                Some(profile) => format!(".notmuch-config.{profile}"),
+                None => String::from(".notmuch-config"),
+            };
+
+            let path = PathBuf::from(home).join(name);
+
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        None
+    }
+
+    /// Read and parse the Notmuch configuration file at the given
+    /// path.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        debug!(?path, "reading notmuch configuration file");
+
+        let contents =
+            fs::read_to_string(path).map_err(|err| Error::ReadConfigError(err, path.to_owned()))?;
+
+        Ok(Self::parse(&contents))
+    }
+
+    /// Read and parse the Notmuch configuration file at the given
+    /// path, or get it from the cache.
+    ///
+    /// The cache is invalidated when the modification time of the
+    /// file changes.
+    pub fn from_path_cached(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mtime = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+
+        let mut configs = USER_CONFIGS.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some((cached_mtime, config)) = configs.get(path) {
+            if mtime.is_some() && *cached_mtime == mtime {
+                return Ok(config.clone());
+            }
+        }
+
+        let config = Self::from_path(path)?;
+        configs.insert(path.to_owned(), (mtime, config.clone()));
+
+        Ok(config)
+    }
+
+    /// Parse the content of a Notmuch configuration file.
+    ///
+    /// The file follows the GLib key file format: `[group]` headers
+    /// followed by `key=value` entries, lists being separated by
+    /// semicolons. Relative database paths are relative to the home
+    /// directory.
+    pub fn parse(contents: &str) -> Self {
+        let mut config = Self::default();
+        let mut group = "";
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                group = name.trim();
+                continue;
+            }
+
+            let Some((key, val)) = line.split_once('=') else {
+                continue;
+            };
+
+            match (group, key.trim(), val.trim()) {
+                ("database", "path", path) if !path.is_empty() => {
+                    let path = shellexpand_path(path);
+
+                    config.database_path = Some(match dirs::home_dir() {
+                        Some(home) if path.is_relative() => home.join(path),
+                        _ => path,
+                    });
+                }
+                ("new", "tags", tags) => {
+                    let tags = tags
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(ToOwned::to_owned)
+                        .collect();
+
+                    config.new_tags = Some(tags);
+                }
+                _ => continue,
+            }
+        }
+
+        config
+    }
 }
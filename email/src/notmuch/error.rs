@@ -1,8 +1,8 @@
-use std::{any::Any, result};
+use std::{any::Any, io, path::PathBuf, result};
 
 use thiserror::Error;
 
-use crate::{AnyBoxedError, AnyError};
+use crate::{envelope::SingleId, AnyBoxedError, AnyError};
 
 /// The global `Result` alias of the module.
 pub type Result<T> = result::Result<T, Error>;
@@ -18,6 +18,20 @@ pub enum Error {
     ExecuteQueryError(#[source] notmuch::Error),
     #[error("cannot close notmuch database")]
     CloseDatabaseError(#[source] notmuch::Error),
+    #[error("cannot find notmuch message")]
+    FindMessageError(#[source] notmuch::Error),
+    #[error("cannot find notmuch message {0}")]
+    FindMessageNotFoundError(String),
+    #[error("cannot update notmuch message tags")]
+    UpdateTagsError(#[source] notmuch::Error),
+    #[error("cannot begin notmuch atomic section")]
+    BeginAtomicError(#[source] notmuch::Error),
+    #[error("cannot end notmuch atomic section")]
+    EndAtomicError(#[source] notmuch::Error),
+    #[error("cannot apply notmuch tag changes to message {1} ({} message(s) changed)", .2.len())]
+    ApplyTagChangesError(#[source] Box<Error>, String, Vec<SingleId>),
+    #[error("cannot read notmuch configuration file at {1}")]
+    ReadConfigError(#[source] io::Error, PathBuf),
 }
 
 impl AnyError for Error {
@@ -8,11 +8,13 @@
 use notmuch::{Database, DatabaseMode};
 use shellexpand_utils::shellexpand_path;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{debug, info};
 
 use self::config::NotmuchConfig;
 #[doc(inline)]
 pub use self::error::{Error, Result};
+#[cfg(feature = "watch")]
+use crate::envelope::watch::{notmuch::WatchNotmuchEnvelopes, WatchEnvelopes};
 use crate::{
     account::config::AccountConfig,
     backend::{
@@ -22,6 +24,7 @@
     envelope::{
         get::{notmuch::GetNotmuchEnvelope, GetEnvelope},
         list::{notmuch::ListNotmuchEnvelopes, ListEnvelopes},
+        Id, SingleId,
     },
     flag::{
         add::{notmuch::AddNotmuchFlags, AddFlags},
@@ -31,6 +34,7 @@
     folder::{
         add::{notmuch::AddNotmuchFolder, AddFolder},
         list::{notmuch::ListNotmuchFolders, ListFolders},
+        FolderKind,
     },
     maildir::{config::MaildirConfig, MaildirContext},
     message::{
@@ -83,6 +87,143 @@ pub fn open_db(&self) -> Result<Database> {
     pub fn maildirpp(&self) -> bool {
         self.notmuch_config.maildirpp
     }
+
+    /// Build the Notmuch query matching messages of the given folder.
+    ///
+    /// The folder alias is resolved first. The Maildir++ inbox is the
+    /// root Maildir folder.
+    pub fn folder_query(&self, folder: &str) -> String {
+        let folder = self.account_config.get_folder_alias(folder);
+
+        if self.maildirpp() && FolderKind::matches_inbox(&folder) {
+            String::from("folder:\"\"")
+        } else {
+            format!("folder:{folder:?}")
+        }
+    }
+
+    /// Apply the given tag changes in one batch.
+    ///
+    /// The database is opened once, and all changes are applied
+    /// inside a single atomic section: if the process is interrupted,
+    /// none of the changes is committed. Notmuch does not support
+    /// rolling back an atomic section, so in case of failure the
+    /// returned error contains the identifiers of messages that were
+    /// successfully changed before the failure.
+    ///
+    /// Returns the identifiers of changed messages.
+    pub fn apply_tag_changes(&self, changes: Vec<(Id, TagDelta)>) -> Result<Vec<SingleId>> {
+        let db = self.open_db()?;
+        let atomic = AtomicSection::begin(&db)?;
+
+        let mut applied = Vec::new();
+
+        for (id, delta) in changes {
+            for id in id.iter() {
+                debug!(%id, ?delta, "applying notmuch tag changes");
+
+                if let Err(err) = delta.apply(&db, &id) {
+                    // the atomic section is ended by the guard
+                    return Err(Error::ApplyTagChangesError(
+                        Box::new(err),
+                        id.into_owned(),
+                        applied,
+                    ));
+                }
+
+                applied.push(SingleId::from(id));
+            }
+        }
+
+        atomic.end()?;
+        db.close().map_err(Error::CloseDatabaseError)?;
+
+        Ok(applied)
+    }
+}
+
+/// Guard of a Notmuch atomic section.
+///
+/// The atomic section is ended when the guard is dropped, so that it
+/// is ended on every exit path, errors included. Use
+/// [`AtomicSection::end`] to handle the error of the happy path.
+struct AtomicSection<'a> {
+    db: &'a Database,
+    ended: bool,
+}
+
+impl<'a> AtomicSection<'a> {
+    fn begin(db: &'a Database) -> Result<Self> {
+        db.begin_atomic().map_err(Error::BeginAtomicError)?;
+        Ok(Self { db, ended: false })
+    }
+
+    fn end(mut self) -> Result<()> {
+        self.ended = true;
+        self.db.end_atomic().map_err(Error::EndAtomicError)
+    }
+}
+
+impl Drop for AtomicSection<'_> {
+    fn drop(&mut self) {
+        if self.ended {
+            return;
+        }
+
+        if let Err(err) = self.db.end_atomic() {
+            debug!("cannot end notmuch atomic section: {err}");
+            debug!("{err:?}");
+        }
+    }
+}
+
+/// The tag changes to apply to a Notmuch message.
+///
+/// Tags are removed first, then added.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TagDelta {
+    /// The tags to add.
+    pub add: Vec<String>,
+
+    /// The tags to remove.
+    pub remove: Vec<String>,
+}
+
+impl TagDelta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_add(mut self, tag: impl ToString) -> Self {
+        self.add.push(tag.to_string());
+        self
+    }
+
+    pub fn with_remove(mut self, tag: impl ToString) -> Self {
+        self.remove.push(tag.to_string());
+        self
+    }
+
+    fn apply(&self, db: &Database, id: &str) -> Result<()> {
+        let msg = db
+            .find_message(id)
+            .map_err(Error::FindMessageError)?
+            .ok_or_else(|| Error::FindMessageNotFoundError(id.to_owned()))?;
+
+        msg.freeze().map_err(Error::UpdateTagsError)?;
+
+        let res = self
+            .remove
+            .iter()
+            .try_for_each(|tag| msg.remove_tag(tag))
+            .and_then(|()| self.add.iter().try_for_each(|tag| msg.add_tag(tag)))
+            .map_err(Error::UpdateTagsError);
+
+        // the message is thawed even when a tag cannot be changed
+        msg.thaw().map_err(Error::UpdateTagsError)?;
+
+        res
+    }
 }
 
 /// The sync version of the Notmuch backend context.
@@ -109,7 +250,19 @@ fn deref(&self) -> &Self::Target {
     }
 }
 
-impl BackendContext for NotmuchContextSync {}
+#[async_trait]
+impl BackendContext for NotmuchContextSync {
+    /// Wait for the running action to complete.
+    ///
+    /// The database is opened and closed by every action, so once
+    /// the running action completes, no database handle is left
+    /// open and pending changes are flushed.
+    async fn close(&self) -> AnyResult<()> {
+        let _ctx = self.lock().await;
+        debug!("closing notmuch context");
+        Ok(())
+    }
+}
 
 /// The Notmuch context builder.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -178,10 +331,10 @@ fn list_envelopes(&self) -> Option<BackendFeature<Self::Context, dyn ListEnvelop
         Some(Arc::new(ListNotmuchEnvelopes::some_new_boxed))
     }
 
-    // TODO
-    // fn watch_envelopes(&self) -> Option<BackendFeature<Self::Context, dyn WatchEnvelopes>> {
-    //     Some(Arc::new(WatchNotmuchEnvelopes::some_new_boxed))
-    // }
+    #[cfg(feature = "watch")]
+    fn watch_envelopes(&self) -> Option<BackendFeature<Self::Context, dyn WatchEnvelopes>> {
+        Some(Arc::new(WatchNotmuchEnvelopes::some_new_boxed))
+    }
 
     fn add_flags(&self) -> Option<BackendFeature<Self::Context, dyn AddFlags>> {
         Some(Arc::new(AddNotmuchFlags::some_new_boxed))
@@ -231,6 +384,7 @@ async fn build(self) -> AnyResult<Self::Context> {
         let maildir_config = Arc::new(MaildirConfig {
             root_dir: root.path().to_owned(),
             maildirpp: self.notmuch_config.maildirpp,
+            ..Default::default()
         });
 
         let mdir_ctx = MaildirContext {
@@ -0,0 +1,47 @@
+use std::{any::Any, result};
+
+use thiserror::Error;
+
+use crate::{AnyBoxedError, AnyError};
+
+/// The global `Result` alias of the module.
+pub type Result<T> = result::Result<T, Error>;
+
+/// The global `Error` enum of the module.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cannot authenticate using SCRAM: missing attribute {0}")]
+    ScramMissingAttributeError(&'static str),
+    #[error("cannot authenticate using SCRAM: unsupported mandatory extension")]
+    ScramMandatoryExtensionError,
+    #[error("cannot authenticate using SCRAM: invalid server nonce {0}")]
+    ScramInvalidNonceError(String),
+    #[error("cannot authenticate using SCRAM: cannot decode salt {1}")]
+    ScramDecodeSaltError(#[source] base64::DecodeError, String),
+    #[error("cannot authenticate using SCRAM: invalid iteration count {0}")]
+    ScramInvalidIterationsError(String),
+    #[error("cannot authenticate using SCRAM: iteration count {0} exceeds the maximum of {1}")]
+    ScramTooManyIterationsError(u32, u32),
+    #[error("cannot authenticate using SCRAM: server-first message not handled")]
+    ScramServerFirstMessageNotHandledError,
+    #[error("cannot authenticate using SCRAM: cannot decode server signature")]
+    ScramDecodeServerSignatureError(#[source] base64::DecodeError),
+    #[error("cannot authenticate using SCRAM: invalid server signature")]
+    ScramInvalidServerSignatureError,
+    #[error("cannot authenticate using SCRAM: missing server-final message")]
+    ScramMissingServerFinalMessageError,
+    #[error("cannot authenticate using SCRAM: server replied {0}")]
+    ScramServerError(String),
+}
+
+impl AnyError for Error {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<Error> for AnyBoxedError {
+    fn from(err: Error) -> Self {
+        Box::new(err)
+    }
+}
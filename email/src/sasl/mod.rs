@@ -0,0 +1,12 @@
+//! # SASL
+//!
+//! Module dedicated to SASL authentication mechanisms. Mechanisms
+//! defined here are shared between the IMAP and the SMTP backends.
+
+mod error;
+#[cfg(feature = "oauth2")]
+pub mod oauth2;
+pub mod scram;
+
+#[doc(inline)]
+pub use self::error::{Error, Result};
@@ -0,0 +1,238 @@
+//! # SASL OAuth 2.0
+//!
+//! Module dedicated to the XOAUTH2 and OAUTHBEARER SASL mechanisms.
+//!
+//! See <https://developers.google.com/gmail/imap/xoauth2-protocol>
+//! and <https://www.rfc-editor.org/rfc/rfc7628>.
+
+use std::future::Future;
+
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use crate::account::config::oauth2::OAuth2Method;
+
+/// Build the raw (non base64-encoded) XOAUTH2 initial client
+/// response.
+pub fn xoauth2_initial_response(login: &str, access_token: &str) -> String {
+    format!("user={login}\x01auth=Bearer {access_token}\x01\x01")
+}
+
+/// Build the raw (non base64-encoded) OAUTHBEARER initial client
+/// response.
+pub fn oauthbearer_initial_response(
+    login: &str,
+    host: &str,
+    port: u16,
+    access_token: &str,
+) -> String {
+    format!("n,a={login},\x01host={host}\x01port={port}\x01auth=Bearer {access_token}\x01\x01")
+}
+
+/// Select the OAuth 2.0 SASL mechanism to use.
+///
+/// The preferred method is used if advertised by the server,
+/// otherwise the first other advertised OAuth 2.0 method is
+/// used. Returns `None` if none of them is supported.
+pub fn select_method(
+    preferred: &OAuth2Method,
+    is_supported: impl Fn(&OAuth2Method) -> bool,
+) -> Option<OAuth2Method> {
+    if is_supported(preferred) {
+        return Some(preferred.clone());
+    }
+
+    [OAuth2Method::XOAuth2, OAuth2Method::OAuthBearer]
+        .into_iter()
+        .filter(|method| method != preferred)
+        .find(|method| is_supported(method))
+}
+
+/// Client able to authenticate using an OAuth 2.0 SASL mechanism.
+#[async_trait]
+pub trait OAuth2Authenticate: Send {
+    type Error: Send;
+
+    /// Authenticate using the given method and access token.
+    async fn authenticate_oauth2(
+        &mut self,
+        method: &OAuth2Method,
+        access_token: &str,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Authenticate using the given method and access token.
+///
+/// If the server rejects the access token (which usually means that
+/// it expired), the access token is refreshed using the given
+/// `refresh` function and the authentication is retried once.
+///
+/// Returns the refreshed access token, if any.
+pub async fn authenticate_with_refresh<A, F, Fut>(
+    client: &mut A,
+    method: &OAuth2Method,
+    access_token: &str,
+    refresh: F,
+) -> Result<Option<String>, A::Error>
+where
+    A: OAuth2Authenticate,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String, A::Error>>,
+{
+    if client
+        .authenticate_oauth2(method, access_token)
+        .await
+        .is_ok()
+    {
+        debug!(%method, "authentication succeeded!");
+        return Ok(None);
+    }
+
+    warn!(%method, "authentication failed, refreshing access token and retrying…");
+
+    let access_token = refresh().await?;
+    client.authenticate_oauth2(method, &access_token).await?;
+
+    debug!(%method, "authentication succeeded!");
+    Ok(Some(access_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mock server accepting only one access token, and recording
+    /// initial responses it receives.
+    struct MockServer {
+        valid_access_token: &'static str,
+        responses: Vec<String>,
+    }
+
+    #[async_trait]
+    impl OAuth2Authenticate for MockServer {
+        type Error = String;
+
+        async fn authenticate_oauth2(
+            &mut self,
+            method: &OAuth2Method,
+            access_token: &str,
+        ) -> Result<(), Self::Error> {
+            let response = match method {
+                OAuth2Method::XOAuth2 => xoauth2_initial_response("user@localhost", access_token),
+                OAuth2Method::OAuthBearer => {
+                    oauthbearer_initial_response("user@localhost", "localhost", 993, access_token)
+                }
+            };
+
+            self.responses.push(response);
+
+            if access_token == self.valid_access_token {
+                Ok(())
+            } else {
+                Err(String::from(
+                    "NO [AUTHENTICATIONFAILED] invalid credentials",
+                ))
+            }
+        }
+    }
+
+    #[test]
+    fn xoauth2_initial_response_encoding() {
+        assert_eq!(
+            xoauth2_initial_response("user@localhost", "token"),
+            "user=user@localhost\x01auth=Bearer token\x01\x01",
+        );
+    }
+
+    #[test]
+    fn oauthbearer_initial_response_encoding() {
+        assert_eq!(
+            oauthbearer_initial_response("user@localhost", "localhost", 993, "token"),
+            "n,a=user@localhost,\x01host=localhost\x01port=993\x01auth=Bearer token\x01\x01",
+        );
+    }
+
+    #[test]
+    fn select_advertised_method() {
+        let xoauth2 = OAuth2Method::XOAuth2;
+        let oauthbearer = OAuth2Method::OAuthBearer;
+
+        assert_eq!(select_method(&xoauth2, |_| true), Some(xoauth2.clone()));
+        assert_eq!(
+            select_method(&xoauth2, |m| m == &oauthbearer),
+            Some(oauthbearer.clone()),
+        );
+        assert_eq!(
+            select_method(&oauthbearer, |m| m == &xoauth2),
+            Some(xoauth2.clone()),
+        );
+        assert_eq!(select_method(&xoauth2, |_| false), None);
+    }
+
+    #[tokio::test]
+    async fn authenticate_without_refresh() {
+        let mut server = MockServer {
+            valid_access_token: "token",
+            responses: Vec::new(),
+        };
+
+        let refreshed =
+            authenticate_with_refresh(&mut server, &OAuth2Method::XOAuth2, "token", || async {
+                Err(String::from("should not refresh"))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(refreshed, None);
+        assert_eq!(
+            server.responses,
+            vec!["user=user@localhost\x01auth=Bearer token\x01\x01"],
+        );
+    }
+
+    #[tokio::test]
+    async fn authenticate_with_refresh_then_retry() {
+        let mut server = MockServer {
+            valid_access_token: "fresh-token",
+            responses: Vec::new(),
+        };
+
+        let refreshed = authenticate_with_refresh(
+            &mut server,
+            &OAuth2Method::XOAuth2,
+            "expired-token",
+            || async { Ok(String::from("fresh-token")) },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(refreshed, Some(String::from("fresh-token")));
+        assert_eq!(
+            server.responses,
+            vec![
+                "user=user@localhost\x01auth=Bearer expired-token\x01\x01",
+                "user=user@localhost\x01auth=Bearer fresh-token\x01\x01",
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn authenticate_with_refresh_retries_once() {
+        let mut server = MockServer {
+            valid_access_token: "other-token",
+            responses: Vec::new(),
+        };
+
+        let err = authenticate_with_refresh(
+            &mut server,
+            &OAuth2Method::OAuthBearer,
+            "expired-token",
+            || async { Ok(String::from("fresh-token")) },
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.starts_with("NO"));
+        assert_eq!(server.responses.len(), 2);
+    }
+}
@@ -0,0 +1,428 @@
+//! # SASL SCRAM
+//!
+//! Module dedicated to the SCRAM-SHA-1 and SCRAM-SHA-256 SASL
+//! mechanisms, as defined in [RFC 5802] and [RFC 7677].
+//!
+//! The password is never sent to the server: the client proves that
+//! it knows the password, and the server proves that it knows the
+//! salted password as well.
+//!
+//! Channel binding is not supported, and the password is used as it
+//! is (SASLprep normalization is not applied). The password and the
+//! keys derived from it are wiped from memory on drop.
+//!
+//! [RFC 5802]: https://www.rfc-editor.org/rfc/rfc5802
+//! [RFC 7677]: https://www.rfc-editor.org/rfc/rfc7677
+
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use zeroize::Zeroizing;
+
+use super::{Error, Result};
+
+/// The GS2 header used when channel binding is not supported.
+const GS2_HEADER: &str = "n,,";
+
+/// The maximum iteration count accepted from the server.
+///
+/// The iteration count is chosen by the server, a larger one would
+/// let a malicious server make the client compute the salted
+/// password for an arbitrarily long time.
+pub const MAX_ITERATIONS: u32 = 100_000;
+
+/// The SCRAM mechanism, defined by its hash function.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScramMechanism {
+    Sha1,
+    Sha256,
+}
+
+impl ScramMechanism {
+    /// Select the strongest SCRAM mechanism from the given advertised
+    /// SASL mechanism names.
+    pub fn select<M: AsRef<str>>(advertised: impl IntoIterator<Item = M>) -> Option<Self> {
+        let mut selected = None;
+
+        for mechanism in advertised {
+            let mechanism = mechanism.as_ref();
+
+            if mechanism.eq_ignore_ascii_case(Self::Sha256.name()) {
+                return Some(Self::Sha256);
+            }
+
+            if mechanism.eq_ignore_ascii_case(Self::Sha1.name()) {
+                selected = Some(Self::Sha1);
+            }
+        }
+
+        selected
+    }
+
+    /// Return the SASL name of the mechanism.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sha1 => "SCRAM-SHA-1",
+            Self::Sha256 => "SCRAM-SHA-256",
+        }
+    }
+
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+
+    fn hmac(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts any key size");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+            Self::Sha256 => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size");
+                mac.update(data);
+                mac.finalize().into_bytes().to_vec()
+            }
+        }
+    }
+
+    /// The `Hi` function, which is PBKDF2 using HMAC as pseudorandom
+    /// function and the hash output size as derived key length.
+    fn hi(&self, password: &[u8], salt: &[u8], iterations: u32) -> Zeroizing<Vec<u8>> {
+        let mut salt = salt.to_vec();
+        salt.extend_from_slice(&1u32.to_be_bytes());
+
+        let mut u = Zeroizing::new(self.hmac(password, &salt));
+        let mut hi = u.clone();
+
+        for _ in 1..iterations {
+            u = Zeroizing::new(self.hmac(password, &u));
+            hi.iter_mut().zip(u.iter()).for_each(|(hi, u)| *hi ^= u);
+        }
+
+        hi
+    }
+}
+
+impl fmt::Display for ScramMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The SCRAM client.
+///
+/// The exchange goes as follow:
+///
+/// 1. send [`ScramClient::client_first_message`]
+/// 2. pass the server-first message to
+///    [`ScramClient::handle_server_first_message`], then send the
+///    returned client-final message
+/// 3. pass the server-final message to
+///    [`ScramClient::verify_server_final_message`]
+pub struct ScramClient {
+    mechanism: ScramMechanism,
+    login: String,
+    password: Zeroizing<String>,
+    client_nonce: String,
+    server_signature: Option<Vec<u8>>,
+}
+
+impl ScramClient {
+    /// Create a new SCRAM client using a random client nonce.
+    pub fn new(mechanism: ScramMechanism, login: impl ToString, password: impl ToString) -> Self {
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        Self::new_with_nonce(mechanism, login, password, nonce)
+    }
+
+    /// Create a new SCRAM client using the given client nonce.
+    pub fn new_with_nonce(
+        mechanism: ScramMechanism,
+        login: impl ToString,
+        password: impl ToString,
+        client_nonce: impl ToString,
+    ) -> Self {
+        Self {
+            mechanism,
+            login: login.to_string(),
+            password: Zeroizing::new(password.to_string()),
+            client_nonce: client_nonce.to_string(),
+            server_signature: None,
+        }
+    }
+
+    /// Return the SCRAM mechanism used by the client.
+    pub fn mechanism(&self) -> ScramMechanism {
+        self.mechanism
+    }
+
+    fn client_first_message_bare(&self) -> String {
+        let login = self.login.replace('=', "=3D").replace(',', "=2C");
+        format!("n={login},r={}", self.client_nonce)
+    }
+
+    /// Build the client-first message.
+    pub fn client_first_message(&self) -> String {
+        format!("{GS2_HEADER}{}", self.client_first_message_bare())
+    }
+
+    /// Handle the server-first message and build the client-final
+    /// message.
+    pub fn handle_server_first_message(&mut self, msg: &str) -> Result<String> {
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+
+        for attr in msg.split(',') {
+            match attr.split_once('=') {
+                Some(("r", val)) => nonce = Some(val),
+                Some(("s", val)) => salt = Some(val),
+                Some(("i", val)) => iterations = Some(val),
+                Some(("m", _)) => return Err(Error::ScramMandatoryExtensionError),
+                _ => continue,
+            }
+        }
+
+        let nonce = nonce.ok_or(Error::ScramMissingAttributeError("r"))?;
+        if !nonce.starts_with(&self.client_nonce) || nonce.len() == self.client_nonce.len() {
+            return Err(Error::ScramInvalidNonceError(nonce.to_owned()));
+        }
+
+        let salt = salt.ok_or(Error::ScramMissingAttributeError("s"))?;
+        let salt = BASE64
+            .decode(salt)
+            .map_err(|err| Error::ScramDecodeSaltError(err, salt.to_owned()))?;
+
+        let iterations = iterations.ok_or(Error::ScramMissingAttributeError("i"))?;
+        let iterations = match iterations.parse::<u32>() {
+            Ok(0) | Err(_) => {
+                return Err(Error::ScramInvalidIterationsError(iterations.to_owned()));
+            }
+            Ok(iterations) if iterations > MAX_ITERATIONS => {
+                return Err(Error::ScramTooManyIterationsError(
+                    iterations,
+                    MAX_ITERATIONS,
+                ));
+            }
+            Ok(iterations) => iterations,
+        };
+
+        debug!(mechanism = %self.mechanism, iterations, "computing SCRAM client proof");
+
+        let channel_binding = BASE64.encode(GS2_HEADER);
+        let client_final_without_proof = format!("c={channel_binding},r={nonce}");
+        let auth_msg = format!(
+            "{},{msg},{client_final_without_proof}",
+            self.client_first_message_bare()
+        );
+
+        let mech = self.mechanism;
+        let salted_password = mech.hi(self.password.as_bytes(), &salt, iterations);
+
+        let client_key = Zeroizing::new(mech.hmac(&salted_password, b"Client Key"));
+        let stored_key = Zeroizing::new(mech.hash(&client_key));
+        let client_signature = mech.hmac(&stored_key, auth_msg.as_bytes());
+        let client_proof: Vec<u8> = client_key
+            .iter()
+            .zip(&client_signature)
+            .map(|(key, sig)| key ^ sig)
+            .collect();
+
+        let server_key = Zeroizing::new(mech.hmac(&salted_password, b"Server Key"));
+        self.server_signature = Some(mech.hmac(&server_key, auth_msg.as_bytes()));
+
+        let client_proof = BASE64.encode(client_proof);
+        Ok(format!("{client_final_without_proof},p={client_proof}"))
+    }
+
+    /// Verify the server-final message, which proves that the server
+    /// knows the salted password.
+    pub fn verify_server_final_message(&self, msg: &str) -> Result<()> {
+        let expected = self
+            .server_signature
+            .as_ref()
+            .ok_or(Error::ScramServerFirstMessageNotHandledError)?;
+
+        for attr in msg.split(',') {
+            match attr.split_once('=') {
+                Some(("e", err)) => {
+                    return Err(Error::ScramServerError(err.to_owned()));
+                }
+                Some(("v", sig)) => {
+                    let sig = BASE64
+                        .decode(sig)
+                        .map_err(Error::ScramDecodeServerSignatureError)?;
+
+                    if sig != *expected {
+                        return Err(Error::ScramInvalidServerSignatureError);
+                    }
+
+                    return Ok(());
+                }
+                _ => continue,
+            }
+        }
+
+        Err(Error::ScramMissingAttributeError("v"))
+    }
+}
+
+impl fmt::Debug for ScramClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScramClient")
+            .field("mechanism", &self.mechanism)
+            .field("login", &self.login)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_mechanism() {
+        let select = |mechs: &[&str]| ScramMechanism::select(mechs.iter());
+
+        assert_eq!(select(&["PLAIN", "LOGIN"]), None);
+        assert_eq!(
+            select(&["PLAIN", "SCRAM-SHA-1"]),
+            Some(ScramMechanism::Sha1)
+        );
+        assert_eq!(
+            select(&["SCRAM-SHA-1", "scram-sha-256"]),
+            Some(ScramMechanism::Sha256),
+        );
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc5802#section-5
+    #[test]
+    fn scram_sha1_rfc5802() {
+        let mut client = ScramClient::new_with_nonce(
+            ScramMechanism::Sha1,
+            "user",
+            "pencil",
+            "fyko+d2lbbFgONRv9qkxdawL",
+        );
+
+        assert_eq!(
+            client.client_first_message(),
+            "n,,n=user,r=fyko+d2lbbFgONRv9qkxdawL",
+        );
+
+        let client_final = client
+            .handle_server_first_message(
+                "r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096",
+            )
+            .unwrap();
+
+        assert_eq!(
+            client_final,
+            "c=biws,r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,p=v0X8v3Bz2T0CJGbJQyF0X+HI4Ts=",
+        );
+
+        client
+            .verify_server_final_message("v=rmF9pqV8S7suAoZWja4dJRkFsKQ=")
+            .unwrap();
+    }
+
+    // https://www.rfc-editor.org/rfc/rfc7677#section-3
+    #[test]
+    fn scram_sha256_rfc7677() {
+        let mut client = ScramClient::new_with_nonce(
+            ScramMechanism::Sha256,
+            "user",
+            "pencil",
+            "rOprNGfwEbeRWgbNEkqO",
+        );
+
+        assert_eq!(
+            client.client_first_message(),
+            "n,,n=user,r=rOprNGfwEbeRWgbNEkqO",
+        );
+
+        let client_final = client
+            .handle_server_first_message(
+                "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096",
+            )
+            .unwrap();
+
+        assert_eq!(
+            client_final,
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+        );
+
+        client
+            .verify_server_final_message("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+            .unwrap();
+    }
+
+    #[test]
+    fn scram_invalid_server_signature() {
+        let mut client = ScramClient::new_with_nonce(
+            ScramMechanism::Sha1,
+            "user",
+            "pencil",
+            "fyko+d2lbbFgONRv9qkxdawL",
+        );
+
+        client
+            .handle_server_first_message(
+                "r=fyko+d2lbbFgONRv9qkxdawL3rfcNHYJY1ZVvWVs7j,s=QSXCR+Q6sek8bf92,i=4096",
+            )
+            .unwrap();
+
+        assert!(matches!(
+            client.verify_server_final_message("v=AAAAAAAAAAAAAAAAAAAAAAAAAAA="),
+            Err(Error::ScramInvalidServerSignatureError),
+        ));
+        assert!(matches!(
+            client.verify_server_final_message("e=invalid-proof"),
+            Err(Error::ScramServerError(_)),
+        ));
+    }
+
+    #[test]
+    fn scram_invalid_nonce() {
+        let mut client =
+            ScramClient::new_with_nonce(ScramMechanism::Sha1, "user", "pencil", "client-nonce");
+
+        assert!(matches!(
+            client.handle_server_first_message("r=other-nonce,s=QSXCR+Q6sek8bf92,i=4096"),
+            Err(Error::ScramInvalidNonceError(_)),
+        ));
+    }
+
+    #[test]
+    fn scram_invalid_iterations() {
+        let mut client =
+            ScramClient::new_with_nonce(ScramMechanism::Sha1, "user", "pencil", "nonce");
+        let mut handle =
+            |i: u32| client.handle_server_first_message(&format!("r=nonce2,s=c2FsdA==,i={i}"));
+
+        assert!(matches!(
+            handle(0),
+            Err(Error::ScramInvalidIterationsError(_)),
+        ));
+        assert!(matches!(
+            handle(MAX_ITERATIONS + 1),
+            Err(Error::ScramTooManyIterationsError(100_001, MAX_ITERATIONS)),
+        ));
+        assert!(handle(4096).is_ok());
+    }
+
+    #[test]
+    fn scram_escape_login() {
+        let client = ScramClient::new_with_nonce(ScramMechanism::Sha256, "a=b,c", "pass", "nonce");
+
+        assert_eq!(client.client_first_message(), "n,,n=a=3Db=2Cc,r=nonce");
+    }
+}
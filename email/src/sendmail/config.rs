@@ -19,10 +19,23 @@
 pub struct SendmailConfig {
     /// The sendmail command.
     pub cmd: Option<Command>,
+
+    /// Whenever messages should be sent in dry-run mode.
+    ///
+    /// In dry-run mode, neither the message pre-send hook nor the
+    /// sendmail command are executed: the commands that would run are
+    /// logged, and sending fails with the resolved commands. Defaults
+    /// to `false`.
+    pub dry_run: Option<bool>,
 }
 
 impl SendmailConfig {
     pub fn cmd(&self) -> &Command {
         self.cmd.as_ref().unwrap_or(&*SENDMAIL_DEFAULT_COMMAND)
     }
+
+    /// Return `true` if messages should be sent in dry-run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.unwrap_or_default()
+    }
 }
@@ -12,7 +12,10 @@
 pub use super::{Error, Result};
 #[cfg(feature = "oauth2")]
 use crate::account::config::oauth2::{OAuth2Config, OAuth2Method};
-use crate::{account::config::passwd::PasswordConfig, tls::Encryption};
+use crate::{
+    account::config::passwd::{PasswordCache, PasswordConfig},
+    tls::Encryption,
+};
 
 /// The SMTP sender configuration.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -44,9 +47,40 @@ pub struct SmtpConfig {
     /// Authentication can be done using password or OAuth 2.0.
     /// See [SmtpAuthConfig].
     pub auth: SmtpAuthConfig,
+
+    /// The SMTP password cache, with the time-to-live of the cached
+    /// password in seconds (0 keeps it for the lifetime of the
+    /// configuration).
+    ///
+    /// Disabled by default: the password is resolved on every
+    /// connection. See [`PasswordCache`].
+    pub passwd_cache: Option<PasswordCache>,
+
+    /// The maximum size of sent messages, in bytes.
+    ///
+    /// Messages bigger than this size, or bigger than the size
+    /// advertised by the server using the SIZE extension, are
+    /// rejected before being uploaded.
+    pub max_message_size: Option<usize>,
 }
 
 impl SmtpConfig {
+    /// Reset SMTP secrets, and clear the password cache.
+    ///
+    /// See [`SmtpAuthConfig::reset`].
+    pub async fn reset(&mut self) -> Result<()> {
+        if let Some(cache) = &self.passwd_cache {
+            cache.clear();
+        }
+
+        self.auth.reset().await
+    }
+
+    /// Find the maximum size of sent messages, in bytes.
+    pub fn find_max_message_size(&self) -> Option<usize> {
+        self.max_message_size.filter(|size| *size > 0)
+    }
+
     /// Return `true` if TLS or StartTLS is enabled.
     pub fn is_encryption_enabled(&self) -> bool {
         matches!(
@@ -73,7 +107,10 @@ pub fn is_encryption_disabled(&self) -> bool {
     pub async fn credentials(&self) -> Result<Credentials<String>> {
         Ok(match &self.auth {
             SmtpAuthConfig::Password(passwd) => {
-                let passwd = passwd.get().await.map_err(Error::GetPasswdSmtpError)?;
+                let passwd = passwd
+                    .get_cached(self.passwd_cache.as_ref())
+                    .await
+                    .map_err(Error::GetPasswdSmtpError)?;
                 let passwd = passwd
                     .lines()
                     .next()
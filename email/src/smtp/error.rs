@@ -1,8 +1,12 @@
 use std::{any::Any, result};
 
+use smtp_proto::Response;
 use thiserror::Error;
 
-use crate::{AnyBoxedError, AnyError};
+use crate::{
+    sasl::{self, scram::ScramMechanism},
+    AnyBoxedError, AnyError, ErrorKind,
+};
 
 /// The global `Result` alias of the module.
 pub type Result<T> = result::Result<T, Error>;
@@ -16,12 +20,20 @@ pub enum Error {
     SendMessageMissingRecipientError,
     #[error("cannot send message: request timed out")]
     SendMessageTimedOutError,
+    #[error("cannot send message of {0} bytes: limit is {1} bytes")]
+    SendMessageTooLargeError(usize, usize),
     #[error("cannot send message")]
     SendMessageError(#[source] mail_send::Error),
+    #[error("cannot send message to {0}: server replied with code {}", .1.code)]
+    SendMessageRecipientRejectedError(String, Response<String>),
     #[error("cannot connect to smtp server using tcp")]
     ConnectTcpSmtpError(#[source] mail_send::Error),
     #[error("cannot connect to smtp server using tls")]
     ConnectTlsSmtpError(#[source] mail_send::Error),
+    #[error("cannot authenticate to smtp server using SASL {1} mechanism")]
+    AuthenticateScramError(#[source] mail_send::Error, ScramMechanism),
+    #[error("cannot authenticate to smtp server using SASL {1} mechanism")]
+    AuthenticateScramSaslError(#[source] sasl::Error, ScramMechanism),
     #[error("cannot get smtp password")]
     GetPasswdSmtpError(#[source] secret::Error),
     #[error("cannot get smtp password: password is empty")]
@@ -38,12 +50,45 @@ pub enum Error {
     ReplacingKeyringFailed(#[source] secret::Error),
     #[error("mail send noop failed: {0}")]
     MailSendNoOpFailed(#[source] mail_send::Error),
+    #[error("cannot quit smtp session")]
+    QuitError(#[source] mail_send::Error),
 }
 
 impl AnyError for Error {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::SendMessageTimedOutError => ErrorKind::Transient,
+            Self::SendMessageError(err) => match err {
+                mail_send::Error::Io(_) | mail_send::Error::Timeout => ErrorKind::Transient,
+                // 4xx replies are transient negative completion
+                // replies, see RFC 5321 §4.2.1
+                mail_send::Error::UnexpectedReply(reply) if (400..500).contains(&reply.code) => {
+                    ErrorKind::Transient
+                }
+                mail_send::Error::Auth(_)
+                | mail_send::Error::AuthenticationFailed(_)
+                | mail_send::Error::MissingCredentials
+                | mail_send::Error::UnsupportedAuthMechanism => ErrorKind::Auth,
+                _ => ErrorKind::Permanent,
+            },
+            Self::SendMessageRecipientRejectedError(_, reply)
+                if (400..500).contains(&reply.code) =>
+            {
+                ErrorKind::Transient
+            }
+            Self::AuthenticateScramError(..)
+            | Self::AuthenticateScramSaslError(..)
+            | Self::GetPasswdSmtpError(_)
+            | Self::GetPasswdEmptySmtpError
+            | Self::AccessTokenWasNotAvailable
+            | Self::RefreshingAccessTokenFailed => ErrorKind::Auth,
+            _ => ErrorKind::Permanent,
+        }
+    }
 }
 
 impl From<Error> for AnyBoxedError {
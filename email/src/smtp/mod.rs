@@ -1,16 +1,26 @@
 pub mod config;
 mod error;
 
-use std::{collections::HashSet, sync::Arc};
+use std::{borrow::Cow, collections::HashSet, sync::Arc};
 
 use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use futures::lock::Mutex;
 use mail_parser::{Addr, Address, HeaderName, HeaderValue, Message, MessageParser};
 use mail_send::{
-    smtp::message::{Address as SmtpAddress, IntoMessage, Message as SmtpMessage},
-    SmtpClientBuilder,
+    smtp::{
+        message::{Address as SmtpAddress, IntoMessage, Message as SmtpMessage, Parameters},
+        AssertReply,
+    },
+    Credentials, SmtpClientBuilder,
+};
+use smtp_proto::{
+    EhloResponse, Response, AUTH_SCRAM_SHA_1, AUTH_SCRAM_SHA_256, EXT_CHUNKING, EXT_PIPELINING,
+    EXT_SIZE,
 };
 #[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+#[cfg(feature = "tokio")]
 use tokio::net::TcpStream;
 #[cfg(feature = "tokio-native-tls")]
 use tokio_native_tls::TlsStream;
@@ -27,8 +37,12 @@
         context::{BackendContext, BackendContextBuilder},
         feature::{BackendFeature, CheckUp},
     },
-    message::send::{smtp::SendSmtpMessage, SendMessage},
+    message::send::{normalize_line_endings, smtp::SendSmtpMessage, SendMessage},
     retry::{Retry, RetryState},
+    sasl::{
+        self,
+        scram::{ScramClient, ScramMechanism},
+    },
     AnyResult,
 };
 
@@ -48,25 +62,33 @@ pub struct SmtpContext {
 
     /// The SMTP client.
     client: SmtpClientStream,
+
+    /// The extensions advertised by the SMTP server.
+    extensions: SmtpExtensions,
 }
 
-impl SmtpContext {
-    pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
-        let buffer: Vec<u8>;
+/// The result of a message sending, per recipient.
+///
+/// Each recipient email comes with the result of its `RCPT TO`
+/// command: the message is delivered to the accepted ones only.
+pub type SmtpRecipientResults = Vec<(String, Result<()>)>;
 
-        let mut msg = MessageParser::new().parse(msg).unwrap_or_else(|| {
-            debug!("cannot parse raw email message");
-            Default::default()
-        });
+impl SmtpContext {
+    /// Send the given raw email message.
+    ///
+    /// The message is sent as long as at least one recipient is
+    /// accepted by the server, the returned results tell which
+    /// recipients are rejected. It fails if all recipients are
+    /// rejected.
+    pub async fn send(&mut self, msg: &[u8]) -> Result<SmtpRecipientResults> {
+        // the pre-send hook receives the original bytes, even when
+        // they cannot be parsed
+        let mut msg = Cow::Borrowed(msg);
 
         if let Some(cmd) = self.account_config.find_message_pre_send_hook() {
-            match cmd.run_with(msg.raw_message()).await {
+            match cmd.run_with(msg.as_ref()).await {
                 Ok(res) => {
-                    buffer = res.into();
-                    msg = MessageParser::new().parse(&buffer).unwrap_or_else(|| {
-                        debug!("cannot parse email raw message");
-                        Default::default()
-                    });
+                    msg = Cow::Owned(res.into());
                 }
                 Err(_err) => {
                     debug!("cannot execute pre-send hook: {_err}");
@@ -75,13 +97,49 @@ pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
             }
         };
 
+        if self
+            .account_config
+            .should_normalize_sent_message_line_endings()
+        {
+            let normalized = match normalize_line_endings(&msg) {
+                Cow::Owned(normalized) => Some(normalized),
+                Cow::Borrowed(_) => None,
+            };
+
+            if let Some(normalized) = normalized {
+                msg = Cow::Owned(normalized);
+            }
+        }
+
+        let msg = MessageParser::new().parse(msg.as_ref()).unwrap_or_else(|| {
+            debug!("cannot parse raw email message");
+            Default::default()
+        });
+
+        // the message size is checked before sending anything, so
+        // that big messages are not uploaded for nothing
+        let size = msg.raw_message.len();
+        let max_size = self.smtp_config.find_max_message_size();
+        self.extensions.check_message_size(size, max_size)?;
+
         let mut retry = Retry::default();
 
         loop {
             // NOTE: cannot clone the final message
-            let msg = into_smtp_msg(msg.clone())?;
+            let mut msg = into_smtp_msg(msg.clone())?;
+
+            if let Some(size) = self.extensions.mail_from_size(size) {
+                let mut params = Parameters::default();
+                params.add(("SIZE".to_owned(), size.to_string()));
+                msg.mail_from.parameters = params;
+            }
 
-            match retry.next(retry.timeout(self.client.send(msg)).await) {
+            let extensions = &self.extensions;
+            let res = retry
+                .timeout(self.client.send_message(msg, extensions))
+                .await;
+
+            match retry.next(res) {
                 RetryState::Retry => {
                     debug!(attempt = retry.attempts, "request timed out");
                     continue;
@@ -92,7 +150,7 @@ pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
                 RetryState::Ok(Ok(res)) => {
                     break Ok(res);
                 }
-                RetryState::Ok(Err(err)) => {
+                RetryState::Ok(Err(Error::SendMessageError(err))) => {
                     match err {
                         mail_send::Error::Timeout => {
                             warn!("connection timed out");
@@ -101,10 +159,14 @@ pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
                             let reason = err.to_string();
                             warn!(reason, "connection broke");
                         }
-                        mail_send::Error::UnexpectedReply(reply) => {
-                            let reason = reply.message;
+                        mail_send::Error::UnexpectedReply(ref reply) => {
+                            let reason = reply.message.as_str();
                             let code = reply.code;
                             warn!(reason, "server replied with code {code}");
+                            // the reply is classified by the error
+                            // kind, so that transient ones can be
+                            // retried by the caller
+                            break Err(Error::SendMessageError(err));
                         }
                         err => {
                             break Err(Error::SendMessageError(err));
@@ -119,9 +181,14 @@ pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
                         build_tcp_client(&self.client_builder).await
                     }?;
 
+                    self.extensions = self.client.extensions(&self.client_builder).await;
+
                     retry.reset();
                     continue;
                 }
+                RetryState::Ok(Err(err)) => {
+                    break Err(err);
+                }
             }
         }
     }
@@ -129,6 +196,11 @@ pub async fn send(&mut self, msg: &[u8]) -> Result<()> {
     pub async fn noop(&mut self) -> Result<()> {
         self.client.noop().await
     }
+
+    /// End the SMTP session.
+    pub async fn quit(&mut self) -> Result<()> {
+        self.client.quit().await
+    }
 }
 
 /// The sync version of the SMTP backend context.
@@ -137,7 +209,16 @@ pub async fn noop(&mut self) -> Result<()> {
 /// client can be shared and updated across multiple threads.
 pub type SmtpContextSync = Arc<Mutex<SmtpContext>>;
 
-impl BackendContext for SmtpContextSync {}
+#[async_trait]
+impl BackendContext for SmtpContextSync {
+    /// Send the QUIT command, then close the connection.
+    ///
+    /// A message being sent is sent before the session ends.
+    async fn close(&self) -> AnyResult<()> {
+        debug!("closing smtp context");
+        Ok(self.lock().await.quit().await?)
+    }
+}
 
 /// The SMTP client builder.
 #[derive(Clone)]
@@ -187,13 +268,15 @@ async fn build(self) -> AnyResult<Self::Context> {
             client_builder = client_builder.allow_invalid_certs();
         }
 
-        let (client_builder, client) = build_client(&self.smtp_config, client_builder).await?;
+        let (client_builder, mut client) = build_client(&self.smtp_config, client_builder).await?;
+        let extensions = client.extensions(&client_builder).await;
 
         let ctx = SmtpContext {
             account_config: self.account_config,
             smtp_config: self.smtp_config,
             client_builder,
             client,
+            extensions,
         };
 
         Ok(Arc::new(Mutex::new(ctx)))
@@ -213,12 +296,450 @@ pub async fn send(&mut self, msg: impl IntoMessage<'_>) -> mail_send::Result<()>
         }
     }
 
+    /// Send the given message, using the BDAT command when the
+    /// server advertises the CHUNKING extension, or the DATA command
+    /// otherwise.
+    ///
+    /// The envelope commands are pipelined when the server
+    /// advertises the PIPELINING extension.
+    pub async fn send_message(
+        &mut self,
+        msg: SmtpMessage<'_>,
+        extensions: &SmtpExtensions,
+    ) -> Result<SmtpRecipientResults> {
+        let pipelining = extensions.pipelining;
+
+        if !extensions.chunking {
+            return match self {
+                Self::Tcp(client) => send_with_data(client, msg, pipelining).await,
+                Self::Tls(client) => send_with_data(client, msg, pipelining).await,
+            };
+        }
+
+        debug!("sending message using BDAT");
+
+        match self {
+            Self::Tcp(client) => send_with_bdat(client, msg, pipelining, BDAT_CHUNK_SIZE).await,
+            Self::Tls(client) => send_with_bdat(client, msg, pipelining, BDAT_CHUNK_SIZE).await,
+        }
+    }
+
     pub async fn noop(&mut self) -> Result<()> {
         match self {
             Self::Tcp(client) => client.noop().await.map_err(Error::MailSendNoOpFailed),
             Self::Tls(client) => client.noop().await.map_err(Error::MailSendNoOpFailed),
         }
     }
+
+    pub async fn quit(&mut self) -> Result<()> {
+        match self {
+            Self::Tcp(client) => quit(client).await,
+            Self::Tls(client) => quit(client).await,
+        }
+    }
+
+    /// Get the extensions advertised by the server.
+    ///
+    /// The EHLO command is sent again, since the client builder does
+    /// not expose the response it received. Extensions are best
+    /// effort: if the server does not reply, none are considered
+    /// advertised.
+    pub async fn extensions(
+        &mut self,
+        client_builder: &mail_send::SmtpClientBuilder<String>,
+    ) -> SmtpExtensions {
+        let local_host = client_builder.local_host.as_str();
+
+        let ehlo = match self {
+            Self::Tcp(client) => client.ehlo(local_host).await,
+            Self::Tls(client) => client.ehlo(local_host).await,
+        };
+
+        match ehlo {
+            Ok(ehlo) => {
+                let extensions = SmtpExtensions::from_ehlo(&ehlo);
+                debug!(?extensions, "smtp server extensions");
+                extensions
+            }
+            Err(err) => {
+                warn!("cannot get smtp server extensions: {err}");
+                debug!("{err:?}");
+                SmtpExtensions::default()
+            }
+        }
+    }
+}
+
+/// The SMTP server extensions relevant to message sending, as
+/// advertised in the EHLO response.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SmtpExtensions {
+    /// The maximum message size accepted by the server, in bytes,
+    /// when the SIZE extension (RFC 1870) is advertised. A size of 0
+    /// means that the server has no fixed limit.
+    pub size: Option<usize>,
+
+    /// Whether the server advertises the CHUNKING extension (RFC
+    /// 3030), which allows messages to be sent using BDAT.
+    pub chunking: bool,
+
+    /// Whether the server advertises the PIPELINING extension (RFC
+    /// 2920), which allows envelope commands to be sent without
+    /// waiting for their replies.
+    pub pipelining: bool,
+}
+
+impl SmtpExtensions {
+    fn from_ehlo(ehlo: &EhloResponse<String>) -> Self {
+        Self {
+            size: ehlo.has_capability(EXT_SIZE).then_some(ehlo.size),
+            chunking: ehlo.has_capability(EXT_CHUNKING),
+            pipelining: ehlo.has_capability(EXT_PIPELINING),
+        }
+    }
+
+    /// Check the size of the message to send against the given
+    /// maximum size and the size advertised by the server.
+    pub fn check_message_size(&self, size: usize, max_size: Option<usize>) -> Result<()> {
+        let limit = [max_size, self.size.filter(|size| *size > 0)]
+            .into_iter()
+            .flatten()
+            .min();
+
+        match limit {
+            Some(limit) if size > limit => Err(Error::SendMessageTooLargeError(size, limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return the size to declare in the `MAIL FROM` command, if the
+    /// server advertises the SIZE extension.
+    pub fn mail_from_size(&self, size: usize) -> Option<usize> {
+        self.size.map(|_| size)
+    }
+}
+
+/// The size of the chunks sent using BDAT, in bytes.
+const BDAT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A command of the SMTP envelope.
+///
+/// Commands are kept along their arguments, so that a negative reply
+/// can be attributed to the sender or to the recipient it concerns.
+enum EnvelopeCommand<'a> {
+    MailFrom(&'a SmtpAddress<'a>),
+    RcptTo(&'a SmtpAddress<'a>),
+    Data,
+}
+
+impl EnvelopeCommand<'_> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let cmd = match self {
+            Self::MailFrom(from) => format!("MAIL FROM:<{}>{}\r\n", from.email, from.parameters),
+            Self::RcptTo(rcpt) => format!("RCPT TO:<{}>{}\r\n", rcpt.email, rcpt.parameters),
+            Self::Data => String::from("DATA\r\n"),
+        };
+
+        cmd.into_bytes()
+    }
+
+    fn check_reply(&self, reply: Response<String>) -> Result<()> {
+        match self {
+            Self::Data if reply.code == 354 => Ok(()),
+            Self::MailFrom(_) | Self::RcptTo(_) if (200..300).contains(&reply.code) => Ok(()),
+            Self::RcptTo(rcpt) => Err(Error::SendMessageRecipientRejectedError(
+                rcpt.email.to_string(),
+                reply,
+            )),
+            _ => Err(Error::SendMessageError(mail_send::Error::UnexpectedReply(
+                reply,
+            ))),
+        }
+    }
+}
+
+/// Send the envelope of the given message: the MAIL FROM command,
+/// then a RCPT TO command per recipient, then the DATA command when
+/// `data` is set.
+///
+/// When `pipelining` is set, the MAIL FROM, RCPT TO and DATA
+/// commands are sent as a single group before reading their replies
+/// in order (RFC 2920).
+///
+/// A rejected recipient does not fail the transaction: the message
+/// is delivered to the accepted recipients, and the results of all
+/// recipients are returned. Without pipelining, the DATA command is
+/// only sent once at least one recipient is accepted: its 354 reply
+/// cannot be taken back. With pipelining, the server rejects the
+/// DATA command itself, and if it accepts it anyway, an empty
+/// message is sent to end the data.
+///
+/// If the sender or all the recipients are rejected, the
+/// transaction is reset.
+async fn send_envelope<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    msg: &SmtpMessage<'_>,
+    pipelining: bool,
+    data: bool,
+) -> Result<SmtpRecipientResults> {
+    let res = send_envelope_cmds(client, msg, pipelining, data).await;
+
+    let rejected = matches!(
+        res,
+        Err(Error::SendMessageRecipientRejectedError(..))
+            | Err(Error::SendMessageError(mail_send::Error::UnexpectedReply(
+                _
+            )))
+    );
+
+    if rejected {
+        if let Err(err) = client.cmd(b"RSET\r\n").await {
+            debug!("cannot reset smtp transaction: {err}");
+            debug!("{err:?}");
+        }
+    }
+
+    res
+}
+
+async fn send_envelope_cmds<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    msg: &SmtpMessage<'_>,
+    pipelining: bool,
+    data: bool,
+) -> Result<SmtpRecipientResults> {
+    let mut cmds = vec![EnvelopeCommand::MailFrom(&msg.mail_from)];
+    cmds.extend(msg.rcpt_to.iter().map(EnvelopeCommand::RcptTo));
+
+    // with pipelining, DATA is queued along the last RCPT TO
+    let pipelined_data = pipelining && data;
+
+    if pipelined_data {
+        cmds.push(EnvelopeCommand::Data);
+    }
+
+    let mut replies = if pipelining {
+        send_pipelined_cmds(client, &cmds).await?
+    } else {
+        send_cmds(client, &cmds).await?
+    };
+
+    let data_reply = if pipelined_data { replies.pop() } else { None };
+    let results = check_envelope_replies(msg, replies);
+
+    match data_reply {
+        Some(Ok(())) if results.is_err() => {
+            // the server accepted the data of a rejected envelope,
+            // it needs to be ended with an empty message
+            if let Err(err) = client.cmd(b".\r\n").await {
+                debug!("cannot end smtp data: {err}");
+                debug!("{err:?}");
+            }
+        }
+        Some(Err(err)) if results.is_ok() => return Err(err),
+        _ => (),
+    }
+
+    let results = results?;
+
+    if data && !pipelined_data {
+        for res in send_cmds(client, &[EnvelopeCommand::Data]).await? {
+            res?;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Check the replies of the MAIL FROM and RCPT TO commands of the
+/// given message, returning the results of all recipients.
+///
+/// Fails if the sender or all the recipients are rejected.
+fn check_envelope_replies(
+    msg: &SmtpMessage<'_>,
+    replies: Vec<Result<()>>,
+) -> Result<SmtpRecipientResults> {
+    let mut replies = replies.into_iter();
+
+    if let Some(Err(err)) = replies.next() {
+        return Err(err);
+    }
+
+    let results: SmtpRecipientResults = msg
+        .rcpt_to
+        .iter()
+        .map(|rcpt| rcpt.email.to_string())
+        .zip(replies)
+        .collect();
+
+    if results.iter().all(|(_, res)| res.is_err()) {
+        let err = results.into_iter().find_map(|(_, res)| res.err());
+        return Err(err.unwrap_or(Error::SendMessageMissingRecipientError));
+    }
+
+    for (rcpt, res) in &results {
+        if let Err(err) = res {
+            warn!("smtp recipient {rcpt} rejected, sending to other recipients: {err}");
+        }
+    }
+
+    Ok(results)
+}
+
+/// Send the given commands one by one, waiting for the reply of each
+/// command before sending the next one.
+///
+/// The result of each command is returned. A rejected recipient does
+/// not stop the next commands, other rejected commands do.
+async fn send_cmds<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    cmds: &[EnvelopeCommand<'_>],
+) -> Result<Vec<Result<()>>> {
+    let mut results = Vec::with_capacity(cmds.len());
+
+    for cmd in cmds {
+        let reply = client
+            .cmd(cmd.to_bytes())
+            .await
+            .map_err(Error::SendMessageError)?;
+
+        let res = cmd.check_reply(reply);
+        let stop = res.is_err() && !matches!(cmd, EnvelopeCommand::RcptTo(_));
+        results.push(res);
+
+        if stop {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Send the given commands as a single group, then read their
+/// replies in order.
+///
+/// All the replies are read, even after a negative one, so that the
+/// connection stays in sync with the server. The result of each
+/// command is returned.
+async fn send_pipelined_cmds<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    cmds: &[EnvelopeCommand<'_>],
+) -> Result<Vec<Result<()>>> {
+    let group: Vec<u8> = cmds.iter().flat_map(EnvelopeCommand::to_bytes).collect();
+
+    client
+        .stream
+        .write_all(&group)
+        .await
+        .map_err(|err| Error::SendMessageError(mail_send::Error::Io(err)))?;
+    client
+        .stream
+        .flush()
+        .await
+        .map_err(|err| Error::SendMessageError(mail_send::Error::Io(err)))?;
+
+    let mut results = Vec::with_capacity(cmds.len());
+
+    for cmd in cmds {
+        let reply = client.read().await.map_err(Error::SendMessageError)?;
+        let res = cmd.check_reply(reply);
+
+        if let Err(err) = &res {
+            warn!("smtp command rejected: {err}");
+        }
+
+        results.push(res);
+    }
+
+    Ok(results)
+}
+
+/// Send the given message using the DATA command.
+async fn send_with_data<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    msg: SmtpMessage<'_>,
+    pipelining: bool,
+) -> Result<SmtpRecipientResults> {
+    let results = send_envelope(client, &msg, pipelining, true).await?;
+
+    client
+        .write_message(&msg.body)
+        .await
+        .map_err(|err| Error::SendMessageError(mail_send::Error::Io(err)))?;
+
+    client
+        .read()
+        .await
+        .and_then(|reply| reply.assert_positive_completion())
+        .map_err(Error::SendMessageError)?;
+
+    Ok(results)
+}
+
+/// Send the given message using the BDAT command (RFC 3030).
+///
+/// Unlike DATA, BDAT sends the message as it is: there is no need to
+/// scan the message for dot-stuffing.
+async fn send_with_bdat<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    msg: SmtpMessage<'_>,
+    pipelining: bool,
+    chunk_size: usize,
+) -> Result<SmtpRecipientResults> {
+    let results = send_envelope(client, &msg, pipelining, false).await?;
+
+    for chunk in bdat_chunks(&msg.body, chunk_size) {
+        client
+            .cmd(chunk)
+            .await
+            .and_then(|reply| reply.assert_positive_completion())
+            .map_err(Error::SendMessageError)?;
+    }
+
+    Ok(results)
+}
+
+/// Send the QUIT command, then shut the connection down.
+///
+/// The connection is shut down even if the server does not reply
+/// positively to the QUIT command.
+async fn quit<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+) -> Result<()> {
+    let res = client
+        .cmd(b"QUIT\r\n")
+        .await
+        .and_then(|reply| reply.assert_positive_completion())
+        .map_err(Error::QuitError);
+
+    client
+        .stream
+        .shutdown()
+        .await
+        .map_err(|err| Error::QuitError(mail_send::Error::Io(err)))?;
+
+    res
+}
+
+/// Split the given message body into BDAT chunks.
+///
+/// Each chunk is made of the BDAT command followed by the chunk
+/// data. The last chunk carries the LAST marker, even if the body
+/// is empty.
+fn bdat_chunks(body: &[u8], chunk_size: usize) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let chunk_size = chunk_size.max(1);
+    let count = body.len().div_ceil(chunk_size).max(1);
+
+    (0..count).map(move |i| {
+        let begin = (i * chunk_size).min(body.len());
+        let end = (begin + chunk_size).min(body.len());
+        let chunk = &body[begin..end];
+
+        let last = if i + 1 == count { " LAST" } else { "" };
+        let mut cmd = format!("BDAT {}{last}\r\n", chunk.len()).into_bytes();
+        cmd.extend_from_slice(chunk);
+        cmd
+    })
 }
 
 #[derive(Clone)]
@@ -302,19 +823,186 @@ pub async fn build_client(
 pub async fn build_tcp_client(
     client_builder: &mail_send::SmtpClientBuilder<String>,
 ) -> Result<SmtpClientStream> {
-    match client_builder.connect_plain().await {
-        Ok(client) => Ok(SmtpClientStream::Tcp(client)),
-        Err(err) => Err(Error::ConnectTcpSmtpError(err)),
+    // the authentication is handled separately, since the client
+    // builder does not support SCRAM mechanisms
+    let mut client_builder = client_builder.clone();
+    let credentials = client_builder.credentials.take();
+
+    let mut client = client_builder
+        .connect_plain()
+        .await
+        .map_err(Error::ConnectTcpSmtpError)?;
+
+    if let Some(credentials) = &credentials {
+        let local_host = client_builder.local_host.as_str();
+        authenticate(
+            &mut client,
+            local_host,
+            credentials,
+            Error::ConnectTcpSmtpError,
+        )
+        .await?;
     }
+
+    Ok(SmtpClientStream::Tcp(client))
 }
 
 pub async fn build_tls_client(
     client_builder: &mail_send::SmtpClientBuilder<String>,
 ) -> Result<SmtpClientStream> {
-    match client_builder.connect().await {
-        Ok(client) => Ok(SmtpClientStream::Tls(client)),
-        Err(err) => Err(Error::ConnectTlsSmtpError(err)),
+    // the authentication is handled separately, since the client
+    // builder does not support SCRAM mechanisms
+    let mut client_builder = client_builder.clone();
+    let credentials = client_builder.credentials.take();
+
+    let mut client = client_builder
+        .connect()
+        .await
+        .map_err(Error::ConnectTlsSmtpError)?;
+
+    if let Some(credentials) = &credentials {
+        let local_host = client_builder.local_host.as_str();
+        authenticate(
+            &mut client,
+            local_host,
+            credentials,
+            Error::ConnectTlsSmtpError,
+        )
+        .await?;
     }
+
+    Ok(SmtpClientStream::Tls(client))
+}
+
+/// Authenticate using the given credentials.
+///
+/// SCRAM mechanisms are preferred for password credentials when the
+/// server advertises them, since the password is never sent to the
+/// server. Other mechanisms are left to the SMTP client.
+///
+/// Errors of the SMTP client are mapped using the given function.
+async fn authenticate<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    local_host: &str,
+    credentials: &Credentials<String>,
+    map_err: fn(mail_send::Error) -> Error,
+) -> Result<()> {
+    let ehlo = client.ehlo(local_host).await.map_err(map_err)?;
+
+    if let Credentials::Plain { username, secret } = credentials {
+        if let Some(mechanism) = select_scram_mechanism(&ehlo) {
+            let scram = ScramClient::new(mechanism, username, secret);
+
+            match authenticate_scram(client, scram).await {
+                Err(Error::AuthenticateScramError(err, mechanism)) => {
+                    warn!(%mechanism, ?err, "authentication failed");
+                }
+                res => return res,
+            }
+        }
+    }
+
+    client
+        .authenticate(credentials, &ehlo)
+        .await
+        .map_err(map_err)?;
+
+    Ok(())
+}
+
+/// Select the SCRAM mechanism to use among the mechanisms advertised
+/// in the given EHLO response.
+fn select_scram_mechanism(ehlo: &EhloResponse<String>) -> Option<ScramMechanism> {
+    let advertised = [
+        (AUTH_SCRAM_SHA_1, ScramMechanism::Sha1),
+        (AUTH_SCRAM_SHA_256, ScramMechanism::Sha256),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| ehlo.auth_mechanisms & flag != 0)
+    .map(|(_, mechanism)| mechanism.name());
+
+    ScramMechanism::select(advertised)
+}
+
+/// Authenticate using the given SCRAM client (RFC 4954).
+///
+/// The client-first message is sent as initial response of the AUTH
+/// command, the client-final message is sent in response to the
+/// server-first challenge, then the server-final challenge is
+/// verified and acknowledged with an empty response.
+///
+/// If the server-final message cannot be verified, the exchange is
+/// cancelled: the server could not prove that it knows the password.
+async fn authenticate_scram<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    mut scram: ScramClient,
+) -> Result<()> {
+    let mechanism = scram.mechanism();
+    debug!(%mechanism, "authenticating using SASL…");
+
+    let client_err = |err| Error::AuthenticateScramError(err, mechanism);
+    let sasl_err = |err| Error::AuthenticateScramSaslError(err, mechanism);
+
+    let msg = BASE64.encode(scram.client_first_message());
+    let cmd = format!("AUTH {mechanism} {msg}\r\n");
+    let reply = client.cmd(cmd).await.map_err(client_err)?;
+    let challenge = decode_scram_challenge(reply).map_err(client_err)?;
+
+    let msg = match scram.handle_server_first_message(&challenge) {
+        Ok(msg) => BASE64.encode(msg),
+        Err(err) => return Err(cancel_scram(client, sasl_err(err)).await),
+    };
+    let reply = client.cmd(format!("{msg}\r\n")).await.map_err(client_err)?;
+
+    // a server accepting the authentication without proving that it
+    // knows the password cannot be trusted
+    if reply.code == 235 {
+        return Err(sasl_err(sasl::Error::ScramMissingServerFinalMessageError));
+    }
+
+    let challenge = decode_scram_challenge(reply).map_err(client_err)?;
+
+    if let Err(err) = scram.verify_server_final_message(&challenge) {
+        return Err(cancel_scram(client, sasl_err(err)).await);
+    }
+
+    client
+        .cmd(b"\r\n")
+        .await
+        .and_then(|reply| match reply.code {
+            235 => Ok(()),
+            _ => Err(mail_send::Error::AuthenticationFailed(reply)),
+        })
+        .map_err(client_err)?;
+
+    debug!(%mechanism, "authentication succeeded!");
+    Ok(())
+}
+
+/// Decode the given SCRAM challenge, sent along a 334 reply.
+fn decode_scram_challenge(reply: Response<String>) -> mail_send::Result<String> {
+    if reply.code != 334 {
+        return Err(mail_send::Error::AuthenticationFailed(reply));
+    }
+
+    let challenge = BASE64
+        .decode(reply.message.trim())
+        .map_err(mail_send::Error::Base64)?;
+
+    Ok(String::from_utf8_lossy(&challenge).into_owned())
+}
+
+/// Cancel the current SASL exchange, then return the given error.
+async fn cancel_scram<T: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut mail_send::SmtpClient<T>,
+    err: Error,
+) -> Error {
+    if let Err(err) = client.cmd(b"*\r\n").await {
+        debug!("cannot cancel smtp authentication: {err}");
+        debug!("{err:?}");
+    }
+
+    err
 }
 
 /// Transform a [`mail_parser::Message`] into a
@@ -398,3 +1086,547 @@ fn find_valid_email(addr: &Addr) -> Option<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use base64::Engine;
+    use mail_send::smtp::message::{Address as SmtpAddress, Message as SmtpMessage};
+    use smtp_proto::{
+        EhloResponse, AUTH_PLAIN, AUTH_SCRAM_SHA_1, AUTH_SCRAM_SHA_256, EXT_CHUNKING,
+        EXT_PIPELINING, EXT_SIZE,
+    };
+    use tokio::{
+        io::{duplex, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        time::timeout,
+    };
+
+    use super::{
+        authenticate_scram, bdat_chunks, quit, select_scram_mechanism, send_with_bdat,
+        send_with_data, Error, SmtpExtensions,
+    };
+    use crate::sasl::{
+        scram::{ScramClient, ScramMechanism},
+        Error as SaslError,
+    };
+
+    #[test]
+    fn select_scram_mechanism_from_ehlo() {
+        let ehlo = |auth_mechanisms| EhloResponse {
+            hostname: String::from("localhost"),
+            auth_mechanisms,
+            ..Default::default()
+        };
+
+        assert_eq!(select_scram_mechanism(&ehlo(AUTH_PLAIN)), None);
+        assert_eq!(
+            select_scram_mechanism(&ehlo(AUTH_PLAIN | AUTH_SCRAM_SHA_1)),
+            Some(ScramMechanism::Sha1)
+        );
+        assert_eq!(
+            select_scram_mechanism(&ehlo(AUTH_SCRAM_SHA_1 | AUTH_SCRAM_SHA_256)),
+            Some(ScramMechanism::Sha256)
+        );
+    }
+
+    /// Run the SCRAM-SHA-1 exchange of RFC 5802 against a mock server
+    /// sending the given server-final message, returning the
+    /// authentication result and the lines sent by the client.
+    async fn authenticate_scram_sha1(
+        server_final: &'static str,
+    ) -> (super::Result<()>, Vec<String>) {
+        let (client_stream, server_stream) = duplex(1024);
+
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+            let mut lines = Vec::new();
+
+            // base64 of the RFC 5802 server-first message, then of
+            // the given server-final message
+            let server_first = "334 cj1meWtvK2QybGJiRmdPTlJ2OXFreGRhd0wzcmZjTkhZSlkxWlZ2V1ZzN2oscz1RU1hDUitRNnNlazhiZjkyLGk9NDA5Ng==\r\n";
+            let server_final = format!("334 {}\r\n", super::BASE64.encode(server_final));
+            let replies = [server_first.to_owned(), server_final];
+
+            for reply in replies
+                .iter()
+                .map(String::as_str)
+                .chain(["235 2.7.0 OK\r\n"])
+            {
+                let mut line = String::new();
+                if server.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+
+                let cancelled = line == "*\r\n";
+                lines.push(line.trim_end().to_owned());
+
+                if cancelled {
+                    server
+                        .get_mut()
+                        .write_all(b"501 5.7.0 cancelled\r\n")
+                        .await
+                        .unwrap();
+                    break;
+                }
+
+                server.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+
+            lines
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        let scram = ScramClient::new_with_nonce(
+            ScramMechanism::Sha1,
+            "user",
+            "pencil",
+            "fyko+d2lbbFgONRv9qkxdawL",
+        );
+
+        let res = authenticate_scram(&mut client, scram).await;
+        drop(client);
+
+        (res, server.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn authenticate_using_scram() {
+        let (res, lines) = authenticate_scram_sha1("v=rmF9pqV8S7suAoZWja4dJRkFsKQ=").await;
+
+        res.unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                "AUTH SCRAM-SHA-1 biwsbj11c2VyLHI9ZnlrbytkMmxiYkZnT05Sdjlxa3hkYXdM",
+                "Yz1iaXdzLHI9ZnlrbytkMmxiYkZnT05Sdjlxa3hkYXdMM3JmY05IWUpZMVpWdldWczdqLHA9djBYOHYzQnoyVDBDSkdiSlF5RjBYK0hJNFRzPQ==",
+                "",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancel_scram_on_invalid_server_signature() {
+        let (res, lines) = authenticate_scram_sha1("v=AAAAAAAAAAAAAAAAAAAAAAAAAAA=").await;
+
+        assert!(matches!(
+            res,
+            Err(Error::AuthenticateScramSaslError(
+                SaslError::ScramInvalidServerSignatureError,
+                ScramMechanism::Sha1,
+            ))
+        ));
+        assert_eq!(lines.last().map(String::as_str), Some("*"));
+    }
+
+    #[test]
+    fn frame_bdat_chunks() {
+        let chunks: Vec<_> = bdat_chunks(b"Hello, world!", 5).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                b"BDAT 5\r\nHello".to_vec(),
+                b"BDAT 5\r\n, wor".to_vec(),
+                b"BDAT 3 LAST\r\nld!".to_vec(),
+            ]
+        );
+
+        let chunks: Vec<_> = bdat_chunks(b"", 5).collect();
+        assert_eq!(chunks, vec![b"BDAT 0 LAST\r\n".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn send_message_using_bdat() {
+        // the mock server advertises `250-CHUNKING`
+        let ehlo = EhloResponse {
+            hostname: String::from("localhost"),
+            capabilities: EXT_CHUNKING,
+            ..Default::default()
+        };
+        assert!(SmtpExtensions::from_ehlo(&ehlo).chunking);
+
+        let (client_stream, server_stream) = duplex(1024);
+
+        // the mock server accepts every command, and records them
+        // with their BDAT data
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+            let mut cmds = Vec::new();
+
+            loop {
+                let mut line = String::new();
+                if server.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+
+                let cmd = line.trim_end().to_owned();
+                let mut data = Vec::new();
+
+                if let Some(args) = cmd.strip_prefix("BDAT ") {
+                    let size = args.split(' ').next().unwrap().parse().unwrap();
+                    data.resize(size, 0);
+                    server.read_exact(&mut data).await.unwrap();
+                }
+
+                server.get_mut().write_all(b"250 OK\r\n").await.unwrap();
+
+                let last = cmd.ends_with(" LAST");
+                cmds.push((cmd, data));
+                if last {
+                    break;
+                }
+            }
+
+            cmds
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        let msg = SmtpMessage {
+            mail_from: SmtpAddress {
+                email: "from@localhost".into(),
+                ..Default::default()
+            },
+            rcpt_to: vec![SmtpAddress {
+                email: "to@localhost".into(),
+                ..Default::default()
+            }],
+            body: b"Subject: BDAT\r\n\r\n.\r\n".as_slice().into(),
+        };
+
+        send_with_bdat(&mut client, msg, false, 8).await.unwrap();
+
+        let cmds = server.await.unwrap();
+        let names: Vec<_> = cmds.iter().map(|(cmd, _)| cmd.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "MAIL FROM:<from@localhost>",
+                "RCPT TO:<to@localhost>",
+                "BDAT 8",
+                "BDAT 8",
+                "BDAT 3 LAST",
+            ]
+        );
+
+        // the data is sent as it is, without dot-stuffing
+        let data: Vec<_> = cmds.into_iter().flat_map(|(_, data)| data).collect();
+        assert_eq!(data, b"Subject: BDAT\r\n\r\n.\r\n");
+    }
+
+    #[tokio::test]
+    async fn send_to_accepted_recipients_using_pipelining() {
+        // the mock server advertises `250-PIPELINING`
+        let ehlo = EhloResponse {
+            hostname: String::from("localhost"),
+            capabilities: EXT_PIPELINING,
+            ..Default::default()
+        };
+        assert!(SmtpExtensions::from_ehlo(&ehlo).pipelining);
+
+        let (client_stream, server_stream) = duplex(1024);
+
+        // the mock server reads the whole envelope before replying,
+        // a lock-step client would never receive any reply
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+            let mut cmds = Vec::new();
+
+            for _ in 0..5 {
+                let mut line = String::new();
+                let read = timeout(Duration::from_secs(1), server.read_line(&mut line));
+                read.await.expect("envelope should be pipelined").unwrap();
+                cmds.push(line.trim_end().to_owned());
+            }
+
+            let replies = [
+                "250 OK\r\n",
+                "250 OK\r\n",
+                "550 5.1.1 Unknown user\r\n",
+                "250 OK\r\n",
+                "354 Go ahead\r\n",
+            ];
+            server
+                .get_mut()
+                .write_all(replies.concat().as_bytes())
+                .await
+                .unwrap();
+
+            // the message data ends with a single dot line
+            loop {
+                let mut line = String::new();
+                server.read_line(&mut line).await.unwrap();
+                if line == ".\r\n" {
+                    break;
+                }
+            }
+
+            server.get_mut().write_all(b"250 OK\r\n").await.unwrap();
+
+            cmds
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        let rcpt = |email: &'static str| SmtpAddress {
+            email: email.into(),
+            ..Default::default()
+        };
+
+        let msg = SmtpMessage {
+            mail_from: rcpt("from@localhost"),
+            rcpt_to: vec![
+                rcpt("a@localhost"),
+                rcpt("b@localhost"),
+                rcpt("c@localhost"),
+            ],
+            body: b"Subject: PIPELINING\r\n\r\n".as_slice().into(),
+        };
+
+        let results = send_with_data(&mut client, msg, true).await.unwrap();
+        let rcpts: Vec<_> = results.iter().map(|(rcpt, _)| rcpt.as_str()).collect();
+        assert_eq!(rcpts, vec!["a@localhost", "b@localhost", "c@localhost"]);
+
+        assert!(results[0].1.is_ok());
+        assert!(results[2].1.is_ok());
+
+        let Err(Error::SendMessageRecipientRejectedError(rcpt, reply)) = &results[1].1 else {
+            panic!("expected rejected recipient error, got {:?}", results[1].1);
+        };
+        assert_eq!(rcpt, "b@localhost");
+        assert_eq!(reply.code, 550);
+
+        // the message is still sent to the accepted recipients
+        let cmds = server.await.unwrap();
+        assert_eq!(
+            cmds,
+            vec![
+                "MAIL FROM:<from@localhost>",
+                "RCPT TO:<a@localhost>",
+                "RCPT TO:<b@localhost>",
+                "RCPT TO:<c@localhost>",
+                "DATA",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reset_when_all_pipelined_recipients_rejected() {
+        let (client_stream, server_stream) = duplex(1024);
+
+        // the mock server reads the whole envelope, rejects every
+        // recipient and thus the data
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+            let mut cmds = Vec::new();
+
+            for _ in 0..3 {
+                let mut line = String::new();
+                let read = timeout(Duration::from_secs(1), server.read_line(&mut line));
+                read.await.expect("envelope should be pipelined").unwrap();
+                cmds.push(line.trim_end().to_owned());
+            }
+
+            let replies = [
+                "250 OK\r\n",
+                "550 5.1.1 Unknown user\r\n",
+                "554 5.5.1 No valid recipients\r\n",
+            ];
+            server
+                .get_mut()
+                .write_all(replies.concat().as_bytes())
+                .await
+                .unwrap();
+
+            let mut line = String::new();
+            server.read_line(&mut line).await.unwrap();
+            cmds.push(line.trim_end().to_owned());
+            server.get_mut().write_all(b"250 OK\r\n").await.unwrap();
+
+            cmds
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        let msg = SmtpMessage {
+            mail_from: SmtpAddress {
+                email: "from@localhost".into(),
+                ..Default::default()
+            },
+            rcpt_to: vec![SmtpAddress {
+                email: "to@localhost".into(),
+                ..Default::default()
+            }],
+            body: b"Subject: PIPELINING\r\n\r\n".as_slice().into(),
+        };
+
+        let res = send_with_data(&mut client, msg, true).await;
+        let Err(Error::SendMessageRecipientRejectedError(rcpt, _)) = &res else {
+            panic!("expected rejected recipient error, got {res:?}");
+        };
+        assert_eq!(rcpt, "to@localhost");
+
+        let cmds = server.await.unwrap();
+        assert_eq!(
+            cmds,
+            vec![
+                "MAIL FROM:<from@localhost>",
+                "RCPT TO:<to@localhost>",
+                "DATA",
+                "RSET",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reset_when_all_recipients_rejected() {
+        let (client_stream, server_stream) = duplex(1024);
+
+        // the mock server accepts the sender, but rejects every
+        // recipient
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+            let mut cmds = Vec::new();
+
+            loop {
+                let mut line = String::new();
+                server.read_line(&mut line).await.unwrap();
+                let cmd = line.trim_end().to_owned();
+
+                let reply: &[u8] = if cmd.starts_with("RCPT TO") {
+                    b"550 5.1.1 Unknown user\r\n"
+                } else {
+                    b"250 OK\r\n"
+                };
+                server.get_mut().write_all(reply).await.unwrap();
+
+                let reset = cmd == "RSET";
+                cmds.push(cmd);
+                if reset {
+                    break;
+                }
+            }
+
+            cmds
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        let rcpt = |email: &'static str| SmtpAddress {
+            email: email.into(),
+            ..Default::default()
+        };
+
+        let msg = SmtpMessage {
+            mail_from: rcpt("from@localhost"),
+            rcpt_to: vec![rcpt("a@localhost"), rcpt("b@localhost")],
+            body: b"Subject: Rejected\r\n\r\n".as_slice().into(),
+        };
+
+        let err = send_with_data(&mut client, msg, false).await.unwrap_err();
+        let Error::SendMessageRecipientRejectedError(rcpt, _) = err else {
+            panic!("expected rejected recipient error, got {err:?}");
+        };
+        assert_eq!(rcpt, "a@localhost");
+
+        // DATA is never sent, the transaction is reset instead
+        let cmds = server.await.unwrap();
+        assert_eq!(
+            cmds,
+            vec![
+                "MAIL FROM:<from@localhost>",
+                "RCPT TO:<a@localhost>",
+                "RCPT TO:<b@localhost>",
+                "RSET",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn quit_then_shut_connection_down() {
+        let (client_stream, server_stream) = duplex(1024);
+
+        // the mock server replies to QUIT, then records what it
+        // receives until the connection is shut down
+        let server = tokio::spawn(async move {
+            let mut server = BufReader::new(server_stream);
+
+            let mut line = String::new();
+            server.read_line(&mut line).await.unwrap();
+            server.get_mut().write_all(b"221 Bye\r\n").await.unwrap();
+
+            let mut rest = Vec::new();
+            server.read_to_end(&mut rest).await.unwrap();
+
+            (line, rest)
+        });
+
+        let mut client = mail_send::SmtpClient {
+            stream: client_stream,
+            timeout: Duration::from_secs(5),
+        };
+
+        quit(&mut client).await.unwrap();
+
+        let (line, rest) = timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(line, "QUIT\r\n");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn reject_message_over_advertised_size() {
+        // the mock server advertises `250-SIZE 1000`
+        let ehlo = EhloResponse {
+            hostname: String::from("localhost"),
+            capabilities: EXT_SIZE,
+            size: 1000,
+            ..Default::default()
+        };
+        let extensions = SmtpExtensions::from_ehlo(&ehlo);
+        assert_eq!(extensions.size, Some(1000));
+
+        let err = extensions.check_message_size(2000, None).unwrap_err();
+        assert!(matches!(err, Error::SendMessageTooLargeError(2000, 1000)));
+
+        extensions.check_message_size(1000, None).unwrap();
+        assert_eq!(extensions.mail_from_size(1000), Some(1000));
+
+        // the configured maximum size applies when lower
+        let err = extensions.check_message_size(800, Some(500)).unwrap_err();
+        assert!(matches!(err, Error::SendMessageTooLargeError(800, 500)));
+    }
+
+    #[test]
+    fn accept_any_size_without_limit() {
+        let extensions = SmtpExtensions::default();
+        extensions.check_message_size(usize::MAX, None).unwrap();
+        assert_eq!(extensions.mail_from_size(2000), None);
+
+        // SIZE without value means no fixed limit
+        let extensions = SmtpExtensions {
+            size: Some(0),
+            ..Default::default()
+        };
+        extensions.check_message_size(usize::MAX, None).unwrap();
+        assert_eq!(extensions.mail_from_size(2000), Some(2000));
+    }
+}
@@ -0,0 +1,178 @@
+//! # Synchronization checkpoint
+//!
+//! Module dedicated to the synchronization checkpoint, which allows
+//! an interrupted synchronization to resume where it stopped. The
+//! main structure of this module is [`SyncCheckpoint`].
+//!
+//! Once all the changes of a folder are applied, the cursors of the
+//! folder on both sides are saved to a small state file (see
+//! [`GetFolderCursor`](crate::folder::cursor::GetFolderCursor)). The
+//! next synchronization skips folders whose cursors did not move
+//! since then, so that only unprocessed folders are handled.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+/// The synchronization checkpoint.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncCheckpoint {
+    /// The path of the state file.
+    path: PathBuf,
+
+    /// The key of the checkpoint.
+    ///
+    /// The key identifies the synchronization options that change
+    /// the outcome of a folder synchronization, like envelope filters
+    /// or permissions. A checkpoint saved with a different key is
+    /// discarded.
+    key: String,
+
+    /// The left and right cursors of synchronized folders.
+    folders: BTreeMap<String, (u64, u64)>,
+}
+
+impl SyncCheckpoint {
+    /// Load the checkpoint from the given state file.
+    ///
+    /// A missing or invalid state file, or a state file saved with a
+    /// different key, gives an empty checkpoint.
+    pub fn load(path: impl Into<PathBuf>, key: impl ToString) -> Self {
+        let mut checkpoint = Self {
+            path: path.into(),
+            key: key.to_string(),
+            folders: BTreeMap::new(),
+        };
+
+        match fs::read_to_string(&checkpoint.path) {
+            Ok(state) => match parse(&state, &checkpoint.key) {
+                Some(folders) => checkpoint.folders = folders,
+                None => debug!("discarding sync checkpoint at {:?}", checkpoint.path),
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => debug!(?err, "cannot read sync checkpoint at {:?}", checkpoint.path),
+        }
+
+        checkpoint
+    }
+
+    /// Return `true` if the given folder was synchronized with the
+    /// given cursors.
+    ///
+    /// Folders without cursor on one side are never considered
+    /// synchronized.
+    pub fn is_synced(&self, folder: &str, left: Option<u64>, right: Option<u64>) -> bool {
+        match (left, right) {
+            (Some(left), Some(right)) => self.folders.get(folder) == Some(&(left, right)),
+            _ => false,
+        }
+    }
+
+    /// Mark the given folder as synchronized with the given cursors.
+    ///
+    /// The folder is forgotten when a cursor is missing.
+    pub fn insert(&mut self, folder: impl ToString, left: Option<u64>, right: Option<u64>) {
+        let folder = folder.to_string();
+
+        match (left, right) {
+            (Some(left), Some(right)) if !folder.contains('\n') => {
+                self.folders.insert(folder, (left, right));
+            }
+            _ => {
+                self.folders.remove(&folder);
+            }
+        }
+    }
+
+    /// Save the checkpoint to its state file.
+    ///
+    /// The state file is written next to its final location then
+    /// renamed, so that an interruption never leaves a partial state
+    /// file behind.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut state = format!("key\t{}\n", self.key);
+        for (folder, (left, right)) in &self.folders {
+            state.push_str(&format!("{left}\t{right}\t{folder}\n"));
+        }
+
+        let tmp = tmp_path(&self.path);
+        fs::write(&tmp, state)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// Parse the given state file content, if it matches the given key.
+fn parse(state: &str, key: &str) -> Option<BTreeMap<String, (u64, u64)>> {
+    let mut lines = state.lines();
+
+    if lines.next()?.strip_prefix("key\t")? != key {
+        return None;
+    }
+
+    lines
+        .map(|line| {
+            let mut cols = line.splitn(3, '\t');
+            let left = cols.next()?.parse().ok()?;
+            let right = cols.next()?.parse().ok()?;
+            let folder = cols.next()?.to_owned();
+            Some((folder, (left, right)))
+        })
+        .collect()
+}
+
+/// Get the temporary path used to save the given state file.
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::SyncCheckpoint;
+
+    #[test]
+    fn save_then_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sync.checkpoint");
+
+        let mut checkpoint = SyncCheckpoint::load(&path, "key");
+        assert!(!checkpoint.is_synced("INBOX", Some(1), Some(2)));
+
+        checkpoint.insert("INBOX", Some(1), Some(2));
+        checkpoint.insert("Folder\twith tab", Some(3), Some(4));
+        checkpoint.insert("Archives", Some(5), None);
+        checkpoint.save().unwrap();
+
+        let checkpoint = SyncCheckpoint::load(&path, "key");
+        assert!(checkpoint.is_synced("INBOX", Some(1), Some(2)));
+        assert!(checkpoint.is_synced("Folder\twith tab", Some(3), Some(4)));
+        assert!(!checkpoint.is_synced("INBOX", Some(1), Some(3)));
+        assert!(!checkpoint.is_synced("INBOX", Some(1), None));
+        assert!(!checkpoint.is_synced("Archives", Some(5), None));
+
+        // a checkpoint saved with other options is discarded
+        let checkpoint = SyncCheckpoint::load(&path, "other-key");
+        assert!(!checkpoint.is_synced("INBOX", Some(1), Some(2)));
+    }
+
+    #[test]
+    fn discard_invalid_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sync.checkpoint");
+        fs::write(&path, "key\tkey\n1\tnot a number\tINBOX\n").unwrap();
+
+        let checkpoint = SyncCheckpoint::load(&path, "key");
+        assert!(!checkpoint.is_synced("INBOX", Some(1), Some(2)));
+    }
+}
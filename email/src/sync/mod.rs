@@ -4,9 +4,11 @@
 //! two backends. The main structure of this module is
 //! [`SyncBuilder`].
 
+pub mod checkpoint;
 mod error;
 pub mod hash;
 pub mod pool;
+pub mod progress;
 pub mod report;
 
 use std::{
@@ -27,7 +29,9 @@
 
 #[doc(inline)]
 pub use self::error::{Error, Result};
-use self::{hash::SyncHash, report::SyncReport};
+use self::{
+    checkpoint::SyncCheckpoint, hash::SyncHash, progress::SyncProgress, report::SyncReport,
+};
 use crate::{
     backend::{context::BackendContextBuilder, BackendBuilder},
     email::{self, sync::hunk::EmailSyncHunk},
@@ -384,6 +388,7 @@ pub fn get_left_cache_builder(&self) -> Result<BackendBuilder<MaildirContextBuil
             Arc::new(MaildirConfig {
                 root_dir,
                 maildirpp: false,
+                ..Default::default()
             }),
         );
         let left_cache_builder = BackendBuilder::new(left_config, ctx);
@@ -398,6 +403,7 @@ pub fn get_right_cache_builder(&self) -> Result<BackendBuilder<MaildirContextBui
             Arc::new(MaildirConfig {
                 root_dir,
                 maildirpp: false,
+                ..Default::default()
             }),
         );
         let right_cache_builder = BackendBuilder::new(right_config, ctx);
@@ -463,6 +469,10 @@ pub async fn sync(self) -> Result<SyncReport> {
             }
         }?;
 
+        let checkpoint_path = self
+            .get_cache_dir()?
+            .join(format!("{}-{}.checkpoint", self.left_hash, self.right_hash));
+
         let mut right_cache_builder = self.get_right_cache_builder()?;
         let right_cache_check = right_cache_builder.ctx_builder.check_configuration();
 
@@ -513,12 +523,21 @@ pub async fn sync(self) -> Result<SyncReport> {
         report.folder = folder::sync::<L, R>(ctx.clone())
             .await
             .map_err(Error::SyncFoldersError)?;
-        report.email = email::sync::<L, R>(ctx.clone(), &report.folder.names)
+        // dry runs change nothing, so there is nothing to checkpoint
+        let checkpoint = if ctx.dry_run {
+            None
+        } else {
+            Some(SyncCheckpoint::load(checkpoint_path, ctx.checkpoint_key()))
+        };
+
+        report.email = email::sync::<L, R>(ctx.clone(), &report.folder.names, checkpoint)
             .await
             .map_err(Error::SyncEmailsError)?;
 
         folder::sync::expunge::<L, R>(ctx.clone(), &report.folder.names).await;
 
+        SyncProgress::Finished.emit(&ctx.handler).await;
+
         debug!("unlocking sync files");
         left_lock_file
             .unlock()
@@ -557,6 +576,7 @@ pub enum SyncEvent {
     ProcessedEmailHunk(EmailSyncHunk),
     ProcessedAllEmailHunks,
     ExpungedAllFolders,
+    Progress(SyncProgress),
 }
 
 impl SyncEvent {
@@ -626,6 +646,9 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             SyncEvent::ExpungedAllFolders => {
                 write!(f, "Expunged all folders")
             }
+            SyncEvent::Progress(progress) => {
+                write!(f, "{progress}")
+            }
         }
     }
 }
@@ -1,4 +1,8 @@
-use std::{collections::BTreeSet, sync::Arc};
+use std::{
+    collections::BTreeSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
 
 #[doc(inline)]
 pub use super::{Error, Result};
@@ -227,6 +231,28 @@ pub struct SyncPoolContext<L: BackendContext, R: BackendContext> {
 }
 
 impl<L: BackendContext, R: BackendContext> SyncPoolContext<L, R> {
+    /// Get the key of the synchronization checkpoint.
+    ///
+    /// The key changes whenever an option that changes the outcome
+    /// of a folder synchronization changes, so that folders skipped
+    /// thanks to the checkpoint would not have needed any change.
+    pub fn checkpoint_key(&self) -> String {
+        let options = format!(
+            "{:?}",
+            (
+                &self.envelope_filters,
+                &self.left_flag_permissions,
+                &self.left_message_permissions,
+                &self.right_flag_permissions,
+                &self.right_message_permissions,
+            )
+        );
+
+        let mut hasher = DefaultHasher::new();
+        options.hash(&mut hasher);
+        format!("{:x}", hasher.finish())
+    }
+
     pub fn apply_folder_permissions(&self, patch: &mut FolderSyncPatches) {
         use FolderSyncHunk::*;
         use SyncDestination::*;
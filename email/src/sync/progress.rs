@@ -0,0 +1,161 @@
+//! # Sync progress
+//!
+//! Module dedicated to synchronization progress reporting. The main
+//! structure of this module is [`SyncProgress`].
+//!
+//! Progress is sent to the synchronization event handler (see
+//! [`SyncBuilder::with_handler`](super::SyncBuilder::with_handler))
+//! as [`SyncEvent::Progress`] events.
+
+use std::{fmt, sync::Arc};
+
+use futures::lock::Mutex;
+
+use super::{SyncEvent, SyncEventHandler};
+use crate::folder::sync::hunk::FolderName;
+
+/// The synchronization progress.
+///
+/// Every per-item progress carries the number of items done so far
+/// and the total number of items, so that percentages can be
+/// computed.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SyncProgress {
+    /// The folders synchronization started, with the total number
+    /// of folder hunks to process.
+    StartedFolderSync { total: usize },
+
+    /// A folder hunk has been processed.
+    ProcessedFolderHunk { done: usize, total: usize },
+
+    /// The emails synchronization started, with the total number of
+    /// folders to synchronize.
+    StartedEmailSync { total: usize },
+
+    /// The envelopes of the given folder have been listed and its
+    /// patch generated.
+    ListedFolderEnvelopes {
+        folder: FolderName,
+        done: usize,
+        total: usize,
+    },
+
+    /// The email patch is about to be applied, with the total number
+    /// of email hunks to process, the number of messages to copy or
+    /// delete and the number of flags to push.
+    StartedEmailPatch {
+        total: usize,
+        messages: usize,
+        flags: usize,
+    },
+
+    /// A message of the given folder has been copied or deleted.
+    ProcessedMessage {
+        folder: FolderName,
+        done: usize,
+        total: usize,
+    },
+
+    /// The flags of a message of the given folder have been pushed.
+    PushedFlags {
+        folder: FolderName,
+        done: usize,
+        total: usize,
+    },
+
+    /// An email hunk has been processed.
+    ProcessedEmailHunk { done: usize, total: usize },
+
+    /// The synchronization is finished.
+    Finished,
+}
+
+impl SyncProgress {
+    pub async fn emit(self, handler: &Option<Arc<SyncEventHandler>>) {
+        SyncEvent::Progress(self).emit(handler).await
+    }
+}
+
+impl fmt::Display for SyncProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StartedFolderSync { total } => {
+                write!(f, "Processing {total} folder hunks")
+            }
+            Self::ProcessedFolderHunk { done, total } => {
+                write!(f, "Processed {done}/{total} folder hunks")
+            }
+            Self::StartedEmailSync { total } => {
+                write!(f, "Listing envelopes of {total} folders")
+            }
+            Self::ListedFolderEnvelopes {
+                folder,
+                done,
+                total,
+            } => {
+                write!(f, "Listed envelopes of {folder} ({done}/{total} folders)")
+            }
+            Self::StartedEmailPatch {
+                total,
+                messages,
+                flags,
+            } => {
+                write!(
+                    f,
+                    "Processing {total} email hunks ({messages} messages, {flags} flags)"
+                )
+            }
+            Self::ProcessedMessage {
+                folder,
+                done,
+                total,
+            } => {
+                write!(f, "Processed {done}/{total} messages ({folder})")
+            }
+            Self::PushedFlags {
+                folder,
+                done,
+                total,
+            } => {
+                write!(f, "Pushed {done}/{total} flags ({folder})")
+            }
+            Self::ProcessedEmailHunk { done, total } => {
+                write!(f, "Processed {done}/{total} email hunks")
+            }
+            Self::Finished => {
+                write!(f, "Finished")
+            }
+        }
+    }
+}
+
+/// The progress counter, shared between concurrent synchronization
+/// tasks.
+pub(crate) struct SyncProgressCounter {
+    done: Mutex<usize>,
+    total: usize,
+}
+
+impl SyncProgressCounter {
+    pub fn new(total: usize) -> Self {
+        Self {
+            done: Mutex::new(0),
+            total,
+        }
+    }
+
+    /// Increment the counter, then emit the progress built from the
+    /// number of items done and the total.
+    ///
+    /// The counter stays locked while the progress is emitted, so
+    /// that progress is always emitted in order.
+    pub async fn tick(
+        &self,
+        handler: &Option<Arc<SyncEventHandler>>,
+        progress: impl FnOnce(usize, usize) -> SyncProgress,
+    ) {
+        let mut done = self.done.lock().await;
+        *done += 1;
+        progress(*done, self.total).emit(handler).await;
+    }
+}
@@ -3,7 +3,17 @@
 //! Module dedicated to synchronization reporting. The main structure
 //! of thi module is [`SyncReport`].
 
-use crate::{email::sync::report::EmailSyncReport, folder::sync::report::FolderSyncReport};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::SyncDestination;
+use crate::{
+    email::sync::{hunk::EmailSyncHunk, report::EmailSyncReport},
+    flag::Flags,
+    folder::sync::{
+        hunk::{FolderName, FolderSyncHunk},
+        report::FolderSyncReport,
+    },
+};
 
 /// The synchronization report.
 ///
@@ -17,3 +27,121 @@ pub struct SyncReport {
     /// The report of email synchronization.
     pub email: EmailSyncReport,
 }
+
+impl SyncReport {
+    /// Summarize the changes of the report, by side.
+    ///
+    /// Cache hunks are left aside, only changes that affect the
+    /// backends themselves are kept. Combined with a dry run, this
+    /// gives the list of changes the synchronization would apply.
+    pub fn changes(&self) -> SyncChangeSet {
+        let mut changes = SyncChangeSet::default();
+
+        for (hunk, _) in &self.folder.patch {
+            match hunk {
+                FolderSyncHunk::Create(folder, dest) => {
+                    changes.get_mut(dest).created_folders.insert(folder.clone());
+                }
+                FolderSyncHunk::Delete(folder, dest) => {
+                    changes.get_mut(dest).deleted_folders.insert(folder.clone());
+                }
+                FolderSyncHunk::Cache(..) | FolderSyncHunk::Uncache(..) => (),
+            }
+        }
+
+        for (hunk, _) in &self.email.patch {
+            match hunk {
+                EmailSyncHunk::CopyThenCache(folder, envelope, _, target, _) => {
+                    changes
+                        .get_mut(target)
+                        .added_emails
+                        .insert((folder.clone(), envelope.message_id.clone()));
+                }
+                EmailSyncHunk::UpdateFlags(folder, envelope, dest) => {
+                    changes.get_mut(dest).updated_flags.insert(
+                        (folder.clone(), envelope.message_id.clone()),
+                        envelope.flags.clone(),
+                    );
+                }
+                EmailSyncHunk::Delete(folder, id, dest) => {
+                    changes
+                        .get_mut(dest)
+                        .deleted_emails
+                        .insert((folder.clone(), id.clone()));
+                }
+                EmailSyncHunk::GetThenCache(..)
+                | EmailSyncHunk::UpdateCachedFlags(..)
+                | EmailSyncHunk::Uncache(..) => (),
+            }
+        }
+
+        changes
+    }
+}
+
+/// The changes of a synchronization, by side.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncChangeSet {
+    /// The changes applied (or to apply) to the left backend.
+    pub left: SyncChanges,
+
+    /// The changes applied (or to apply) to the right backend.
+    pub right: SyncChanges,
+}
+
+impl SyncChangeSet {
+    /// Get the changes of the given side.
+    pub fn get(&self, dest: &SyncDestination) -> &SyncChanges {
+        match dest {
+            SyncDestination::Left => &self.left,
+            SyncDestination::Right => &self.right,
+        }
+    }
+
+    fn get_mut(&mut self, dest: &SyncDestination) -> &mut SyncChanges {
+        match dest {
+            SyncDestination::Left => &mut self.left,
+            SyncDestination::Right => &mut self.right,
+        }
+    }
+
+    /// Return `true` if the synchronization does not change
+    /// anything.
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty() && self.right.is_empty()
+    }
+}
+
+/// The changes of a synchronization for one side.
+///
+/// Emails are identified by their folder and their Message-ID,
+/// except deleted ones which are identified by their folder and
+/// their backend identifier.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SyncChanges {
+    /// The folders created.
+    pub created_folders: BTreeSet<FolderName>,
+
+    /// The folders deleted.
+    pub deleted_folders: BTreeSet<FolderName>,
+
+    /// The emails added.
+    pub added_emails: BTreeSet<(FolderName, String)>,
+
+    /// The emails deleted.
+    pub deleted_emails: BTreeSet<(FolderName, String)>,
+
+    /// The emails whose flags were updated, with their new flags.
+    pub updated_flags: BTreeMap<(FolderName, String), Flags>,
+}
+
+impl SyncChanges {
+    /// Return `true` if the side is not changed.
+    pub fn is_empty(&self) -> bool {
+        self.created_folders.is_empty()
+            && self.deleted_folders.is_empty()
+            && self.added_emails.is_empty()
+            && self.deleted_emails.is_empty()
+            && self.updated_flags.is_empty()
+    }
+}
@@ -31,6 +31,11 @@
 ///
 /// This struct is a simple wrapper around [`native::Entry`] that
 /// holds a keyring entry key.
+///
+/// Native keyring calls are blocking (they may wait for the user to
+/// unlock their keyring), therefore every secret operation is run on
+/// the blocking thread pool of the async runtime, so that it never
+/// stalls the executor.
 #[derive(Clone, Debug)]
 #[cfg_attr(
     feature = "derive",
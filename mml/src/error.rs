@@ -20,6 +20,12 @@ pub enum Error {
     #[cfg(feature = "compiler")]
     #[error("cannot read attachment at {1:?}")]
     ReadAttachmentError(#[source] io::Error, PathBuf),
+    #[cfg(feature = "compiler")]
+    #[error("cannot compile template: {0} parts exceed the limit of {1} parts")]
+    PartsLimitExceededError(usize, usize),
+    #[cfg(feature = "compiler")]
+    #[error("cannot compile template: {0} bytes of attachments exceed the limit of {1} bytes")]
+    AttachmentsSizeLimitExceededError(u64, u64),
 
     #[cfg(feature = "pgp")]
     #[error("cannot sign part using pgp: missing sender")]
@@ -95,6 +101,9 @@ pub enum Error {
     CompileMmlMessageToVecError(#[source] io::Error),
     #[error("cannot compile MML message to string")]
     CompileMmlMessageToStringError(#[source] io::Error),
+    #[cfg(feature = "compiler")]
+    #[error("cannot compile plain message: unexpected MML directive {0} at line {1}")]
+    CompilePlainUnexpectedMmlDirectiveError(String, usize),
 
     #[error("cannot parse raw email")]
     ParseRawEmailError,
@@ -9,6 +9,7 @@
 
 use async_recursion::async_recursion;
 use mail_builder::{
+    headers::text::Text,
     mime::{BodyPart, MimePart},
     MessageBuilder,
 };
@@ -21,16 +22,100 @@
 use crate::{Error, Result};
 
 use super::{
-    ALTERNATIVE, ATTACHMENT, DISPOSITION, ENCODING, ENCODING_7BIT, ENCODING_8BIT, ENCODING_BASE64,
-    ENCODING_QUOTED_PRINTABLE, FILENAME, INLINE, MIXED, MULTIPART_BEGIN, MULTIPART_BEGIN_ESCAPED,
-    MULTIPART_END, MULTIPART_END_ESCAPED, NAME, PART_BEGIN, PART_BEGIN_ESCAPED, PART_END,
-    PART_END_ESCAPED, RECIPIENT_FILENAME, RELATED, TYPE,
+    unescape_mml_markup, ALTERNATIVE, ATTACHMENT, CID, DESCRIPTION, DISPOSITION, ENCODING,
+    ENCODING_7BIT, ENCODING_8BIT, ENCODING_BASE64, ENCODING_QUOTED_PRINTABLE, FILENAME, INLINE,
+    LANGUAGE, MIXED, NAME, RECIPIENT_FILENAME, RELATED, TYPE,
 };
 #[cfg(feature = "pgp")]
 use super::{ENCRYPT, PGP_MIME, SIGN};
 
 use self::{parsers::prelude::*, tokens::Part};
 
+/// The content disposition of a compiled single part.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Disposition {
+    /// The part is meant to be displayed inline, as part of the
+    /// message body.
+    Inline,
+
+    /// The part is meant to be downloaded by the recipient.
+    Attachment,
+}
+
+/// The limits of the compiler.
+///
+/// Limits are checked before compiling any part, so that a body
+/// referencing too many parts or too big attachments fails early,
+/// instead of exhausting memory or building a message no server
+/// would accept.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MmlCompilerLimits {
+    /// The maximum number of parts, multiparts included.
+    ///
+    /// Defaults to 1000.
+    pub max_parts: usize,
+
+    /// The maximum total size of the attachments read from files, in
+    /// bytes.
+    ///
+    /// Defaults to 100 MiB.
+    pub max_attachments_size: u64,
+}
+
+impl Default for MmlCompilerLimits {
+    fn default() -> Self {
+        Self {
+            max_parts: 1000,
+            max_attachments_size: 100 * 1024 * 1024,
+        }
+    }
+}
+
+impl MmlCompilerLimits {
+    /// Check the given parts against the limits.
+    fn check(&self, parts: &[Part]) -> Result<()> {
+        let mut count = 0;
+        let mut size = 0;
+        Self::measure(parts, &mut count, &mut size);
+
+        if count > self.max_parts {
+            return Err(Error::PartsLimitExceededError(count, self.max_parts));
+        }
+
+        if size > self.max_attachments_size {
+            return Err(Error::AttachmentsSizeLimitExceededError(
+                size,
+                self.max_attachments_size,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Count the given parts recursively, and sum the size of their
+    /// attachments.
+    ///
+    /// Attachments that cannot be read are ignored here, they fail
+    /// later during the compilation.
+    fn measure(parts: &[Part], count: &mut usize, size: &mut u64) {
+        for part in parts {
+            *count += 1;
+
+            match part {
+                Part::Multi(_, parts) => Self::measure(parts, count, size),
+                Part::Single(props, _) => {
+                    if let Some(fpath) = props.get(FILENAME).map(shellexpand_path) {
+                        if let Ok(meta) = fs::metadata(fpath) {
+                            *size = size.saturating_add(meta.len());
+                        }
+                    }
+                }
+                Part::PlainText(_) => (),
+            }
+        }
+    }
+}
+
 /// MML → MIME message body compiler.
 ///
 /// The compiler follows the builder pattern, where the build function
@@ -43,6 +128,16 @@ pub struct MmlBodyCompiler {
     pgp_sender: Option<String>,
     #[cfg(feature = "pgp")]
     pgp_recipients: Vec<String>,
+
+    /// The disposition applied to single parts that do not define a
+    /// `disposition` property.
+    ///
+    /// When `None`, parts having a `filename` are compiled as
+    /// attachments, and other parts have no disposition at all.
+    default_disposition: Option<Disposition>,
+
+    /// The limits checked before compiling parts.
+    limits: MmlCompilerLimits,
 }
 
 impl<'a> MmlBodyCompiler {
@@ -85,6 +180,33 @@ pub fn with_pgp_recipients(mut self, recipients: Vec<String>) -> Self {
         self
     }
 
+    pub fn set_default_disposition(&mut self, disposition: Disposition) {
+        self.default_disposition = Some(disposition);
+    }
+
+    pub fn with_default_disposition(mut self, disposition: Disposition) -> Self {
+        self.set_default_disposition(disposition);
+        self
+    }
+
+    pub fn set_some_default_disposition(&mut self, disposition: Option<Disposition>) {
+        self.default_disposition = disposition;
+    }
+
+    pub fn with_some_default_disposition(mut self, disposition: Option<Disposition>) -> Self {
+        self.set_some_default_disposition(disposition);
+        self
+    }
+
+    pub fn set_limits(&mut self, limits: MmlCompilerLimits) {
+        self.limits = limits;
+    }
+
+    pub fn with_limits(mut self, limits: MmlCompilerLimits) -> Self {
+        self.set_limits(limits);
+        self
+    }
+
     /// Encrypt the given MIME part using PGP.
     #[cfg(feature = "pgp")]
     async fn encrypt_part(&self, clear_part: &MimePart<'a>) -> Result<MimePart<'a>> {
@@ -207,39 +329,75 @@ async fn try_sign_part(&self, clear_part: MimePart<'a>) -> MimePart<'a> {
         }
     }
 
-    /// Replace escaped opening and closing tags by normal opening and
-    /// closing tags.
-    fn unescape_mml_markup(text: impl AsRef<str>) -> String {
-        text.as_ref()
-            .replace(PART_BEGIN_ESCAPED, PART_BEGIN)
-            .replace(PART_END_ESCAPED, PART_END)
-            .replace(MULTIPART_BEGIN_ESCAPED, MULTIPART_BEGIN)
-            .replace(MULTIPART_END_ESCAPED, MULTIPART_END)
-    }
-
     /// Compile given parts parsed from a MML body to a
     /// [MessageBuilder].
+    ///
+    /// Fails if the parts exceed the limits of the compiler, see
+    /// [MmlCompilerLimits].
     async fn compile_parts(&'a self, parts: Vec<Part<'a>>) -> Result<MessageBuilder> {
+        self.limits.check(&parts)?;
+
         let mut builder = MessageBuilder::new();
 
         builder = match parts.len() {
             0 => builder.text_body(String::new()),
             1 => builder.body(self.compile_part(parts.into_iter().next().unwrap()).await?),
             _ => {
-                let mut compiled_parts = Vec::new();
+                let mut compiled_parts = self.compile_related_parts(parts).await?;
 
-                for part in parts {
-                    let part = self.compile_part(part).await?;
-                    compiled_parts.push(part);
+                if compiled_parts.len() == 1 {
+                    builder.body(compiled_parts.remove(0))
+                } else {
+                    builder.body(MimePart::new("multipart/mixed", compiled_parts))
                 }
-
-                builder.body(MimePart::new("multipart/mixed", compiled_parts))
             }
         };
 
         Ok(builder)
     }
 
+    /// Compile the given sibling parts, grouping parts having a
+    /// content ID with their preceding part under a
+    /// `multipart/related` part.
+    ///
+    /// This way, a HTML part followed by images having a `cid`
+    /// property can reference them using the `cid:` URL scheme.
+    async fn compile_related_parts(&'a self, parts: Vec<Part<'a>>) -> Result<Vec<MimePart<'a>>> {
+        let mut compiled_parts = Vec::new();
+        let mut related_parts = Vec::new();
+
+        for part in parts {
+            let is_related = matches!(&part, Part::Single(props, _) if props.contains_key(CID));
+            let part = self.compile_part(part).await?;
+
+            if !is_related || related_parts.is_empty() {
+                Self::push_related_parts(&mut compiled_parts, &mut related_parts);
+            }
+
+            related_parts.push(part);
+        }
+
+        Self::push_related_parts(&mut compiled_parts, &mut related_parts);
+
+        Ok(compiled_parts)
+    }
+
+    /// Move the given related parts into the given compiled parts,
+    /// wrapped in a `multipart/related` part if needed.
+    fn push_related_parts(
+        compiled_parts: &mut Vec<MimePart<'a>>,
+        related_parts: &mut Vec<MimePart<'a>>,
+    ) {
+        match related_parts.len() {
+            0 => (),
+            1 => compiled_parts.push(related_parts.remove(0)),
+            _ => {
+                let parts = std::mem::take(related_parts);
+                compiled_parts.push(MimePart::new("multipart/related", parts));
+            }
+        }
+    }
+
     /// Compile the given part parsed from MML body to a [MimePart].
     #[async_recursion]
     async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
@@ -257,8 +415,14 @@ async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
                     }
                 };
 
-                for part in parts {
-                    multi_part.add_part(self.compile_part(part).await?)
+                if let Some(&RELATED) = props.get(TYPE) {
+                    for part in parts {
+                        multi_part.add_part(self.compile_part(part).await?)
+                    }
+                } else {
+                    for part in self.compile_related_parts(parts).await? {
+                        multi_part.add_part(part)
+                    }
                 }
 
                 #[cfg(feature = "pgp")]
@@ -290,6 +454,7 @@ async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
                         MimePart::new(ctype, contents)
                     }
                     None => {
+                        let body = unescape_mml_markup(body);
                         let mut ctype =
                             Part::get_or_guess_content_type(props, body.as_bytes()).into();
                         if let Some(name) = props.get(NAME) {
@@ -309,9 +474,20 @@ async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
                     _ => part,
                 };
 
-                part = match props.get(DISPOSITION) {
-                    Some(&INLINE) => part.inline(),
-                    Some(&ATTACHMENT) => part.attachment(
+                let disposition = match props.get(DISPOSITION) {
+                    Some(&INLINE) => Some(Disposition::Inline),
+                    Some(&ATTACHMENT) => Some(Disposition::Attachment),
+                    _ if props.contains_key(CID) => Some(Disposition::Inline),
+                    _ => match &self.default_disposition {
+                        Some(disposition) => Some(disposition.clone()),
+                        None if fpath.is_some() => Some(Disposition::Attachment),
+                        None => None,
+                    },
+                };
+
+                part = match disposition {
+                    Some(Disposition::Inline) => part.inline(),
+                    Some(Disposition::Attachment) => part.attachment(
                         props
                             .get(RECIPIENT_FILENAME)
                             .map(Deref::deref)
@@ -322,22 +498,21 @@ async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
                             .unwrap_or("noname")
                             .to_owned(),
                     ),
-                    _ if fpath.is_some() => part.attachment(
-                        props
-                            .get(RECIPIENT_FILENAME)
-                            .map(ToString::to_string)
-                            .or_else(|| {
-                                fpath
-                                    .unwrap()
-                                    .file_name()
-                                    .and_then(OsStr::to_str)
-                                    .map(ToString::to_string)
-                            })
-                            .unwrap_or_else(|| "noname".to_string()),
-                    ),
-                    _ => part,
+                    None => part,
                 };
 
+                if let Some(cid) = props.get(CID) {
+                    part = part.cid(*cid);
+                }
+
+                if let Some(lang) = props.get(LANGUAGE) {
+                    part = part.header("Content-Language", Text::new(*lang));
+                }
+
+                if let Some(desc) = props.get(DESCRIPTION) {
+                    part = part.header("Content-Description", Text::new(*desc));
+                }
+
                 #[cfg(feature = "pgp")]
                 {
                     part = match props.get(SIGN) {
@@ -354,7 +529,7 @@ async fn compile_part(&'a self, part: Part<'a>) -> Result<MimePart> {
                 Ok(part)
             }
             Part::PlainText(body) => {
-                let body = Self::unescape_mml_markup(body);
+                let body = unescape_mml_markup(body);
                 let part = MimePart::new("text/plain", body);
                 Ok(part)
             }
@@ -379,7 +554,8 @@ mod tests {
     use std::io::prelude::*;
     use tempfile::Builder;
 
-    use super::MmlBodyCompiler;
+    use super::{Disposition, MmlBodyCompiler, MmlCompilerLimits};
+    use crate::Error;
 
     #[tokio::test]
     async fn plain() {
@@ -476,4 +652,219 @@ async fn attachment() {
 
         assert_eq!(msg, expected_msg);
     }
+
+    #[tokio::test]
+    async fn default_disposition() {
+        let mml_body = concat_line!("<#part type=text/plain>", "Hello, world!", "<#/part>",);
+
+        let msg = MmlBodyCompiler::new()
+            .with_default_disposition(Disposition::Inline)
+            .compile(mml_body)
+            .await
+            .unwrap()
+            .message_id("id@localhost")
+            .date(0_u64)
+            .write_to_string()
+            .unwrap();
+
+        let expected_msg = concat_line!(
+            "Message-ID: <id@localhost>\r",
+            "Date: Thu, 1 Jan 1970 00:00:00 +0000\r",
+            "MIME-Version: 1.0\r",
+            "Content-Type: text/plain; charset=\"utf-8\"\r",
+            "Content-Disposition: inline\r",
+            "Content-Transfer-Encoding: 7bit\r",
+            "\r",
+            "Hello, world!\r",
+            "",
+        );
+
+        assert_eq!(msg, expected_msg);
+    }
+
+    #[tokio::test]
+    async fn default_disposition_overridden() {
+        let mut attachment = Builder::new()
+            .prefix("image")
+            .suffix(".png")
+            .rand_bytes(0)
+            .tempfile()
+            .unwrap();
+        write!(attachment, "Hello, world!").unwrap();
+        let attachment_path = attachment.path().to_string_lossy();
+
+        let mml_body = format!(
+            "<#part filename={attachment_path} type=image/png disposition=attachment recipient-filename=image.png encoding=base64><#/part>"
+        );
+
+        let msg = MmlBodyCompiler::new()
+            .with_default_disposition(Disposition::Inline)
+            .compile(&mml_body)
+            .await
+            .unwrap()
+            .message_id("id@localhost")
+            .date(0_u64)
+            .write_to_string()
+            .unwrap();
+
+        let expected_msg = concat_line!(
+            "Message-ID: <id@localhost>\r",
+            "Date: Thu, 1 Jan 1970 00:00:00 +0000\r",
+            "MIME-Version: 1.0\r",
+            "Content-Type: image/png\r",
+            "Content-Transfer-Encoding: base64\r",
+            "Content-Disposition: attachment; filename=\"image.png\"\r",
+            "\r",
+            "Hello, world!",
+        );
+
+        assert_eq!(msg, expected_msg);
+    }
+
+    #[tokio::test]
+    async fn related_cid() {
+        let mut image = Builder::new()
+            .prefix("logo")
+            .suffix(".png")
+            .rand_bytes(0)
+            .tempfile()
+            .unwrap();
+        write!(image, "logo").unwrap();
+        let image_path = image.path().to_string_lossy();
+
+        let mml_body = format!(
+            concat_line!(
+                "<#part type=text/html>",
+                "<img src=\"cid:logo\">",
+                "<#/part>",
+                "<#part type=image/png filename={} cid=logo><#/part>",
+            ),
+            image_path
+        );
+
+        let msg = MmlBodyCompiler::new()
+            .compile(&mml_body)
+            .await
+            .unwrap()
+            .message_id("id@localhost")
+            .date(0_u64)
+            .write_to_string()
+            .unwrap();
+
+        assert!(!msg.contains("multipart/mixed"));
+
+        let related = msg.find("Content-Type: multipart/related").unwrap();
+        let html = msg.find("Content-Type: text/html").unwrap();
+        let image = msg.find("Content-Type: image/png").unwrap();
+        assert!(related < html);
+        assert!(html < image);
+
+        let image_part = &msg[image..];
+        assert!(image_part.contains("Content-ID: <logo>\r\n"));
+        assert!(image_part.contains("Content-Disposition: inline\r\n"));
+    }
+
+    #[tokio::test]
+    async fn language_and_description() {
+        let mml_body = concat_line!(
+            "<#part type=text/plain language=fr description=\"French version\">",
+            "Bonjour le monde !",
+            "<#/part>",
+            "<#part type=text/plain>",
+            "Hello, world!",
+            "<#/part>",
+        );
+
+        let msg = MmlBodyCompiler::new()
+            .compile(mml_body)
+            .await
+            .unwrap()
+            .message_id("id@localhost")
+            .date(0_u64)
+            .write_to_string()
+            .unwrap();
+
+        let fr = msg.find("Bonjour le monde !").unwrap();
+        let lang = msg.find("Content-Language: fr\r\n").unwrap();
+        let desc = msg.find("Content-Description: French version\r\n").unwrap();
+        assert!(lang < fr);
+        assert!(desc < fr);
+
+        // the part without properties has none of the headers
+        assert_eq!(msg.matches("Content-Language").count(), 1);
+        assert_eq!(msg.matches("Content-Description").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn parts_limit_exceeded() {
+        let mml_body = concat_line!(
+            "<#multipart type=alternative>",
+            "<#part type=text/plain>",
+            "Hello, world!",
+            "<#/part>",
+            "<#part type=text/html>",
+            "<h1>Hello, world!</h1>",
+            "<#/part>",
+            "<#/multipart>",
+        );
+
+        let limits = MmlCompilerLimits {
+            max_parts: 2,
+            ..Default::default()
+        };
+
+        let err = MmlBodyCompiler::new()
+            .with_limits(limits)
+            .compile(mml_body)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::PartsLimitExceededError(3, 2)));
+    }
+
+    #[tokio::test]
+    async fn under_limits() {
+        let mut attachment = Builder::new()
+            .prefix("attachment")
+            .suffix(".txt")
+            .rand_bytes(0)
+            .tempfile()
+            .unwrap();
+        write!(attachment, "Hello, world!").unwrap();
+        let attachment_path = attachment.path().to_string_lossy();
+
+        let mml_body = format!(
+            "<#part type=text/plain>Hello!<#/part><#part filename={attachment_path} type=text/plain disposition=attachment><#/part>"
+        );
+
+        let limits = MmlCompilerLimits {
+            max_parts: 2,
+            max_attachments_size: 13,
+        };
+
+        let msg = MmlBodyCompiler::new()
+            .with_limits(limits.clone())
+            .compile(&mml_body)
+            .await
+            .unwrap()
+            .write_to_string()
+            .unwrap();
+        assert!(msg.contains("Content-Disposition: attachment"));
+
+        // one byte less is enough to exceed the attachments limit
+        let limits = MmlCompilerLimits {
+            max_attachments_size: 12,
+            ..limits
+        };
+
+        let err = MmlBodyCompiler::new()
+            .with_limits(limits)
+            .compile(&mml_body)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::AttachmentsSizeLimitExceededError(13, 12)
+        ));
+    }
 }
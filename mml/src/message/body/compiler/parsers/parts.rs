@@ -6,8 +6,8 @@
 };
 
 use super::{
-    creation_date, data_encoding, description, disposition, encoding, filename, modification_date,
-    multipart_type, name, part_type, prelude::*, read_date, recipient_filename,
+    cid, creation_date, data_encoding, description, disposition, encoding, filename,
+    modification_date, multipart_type, name, part_type, prelude::*, read_date, recipient_filename,
 };
 #[cfg(feature = "pgp")]
 use super::{encrypt, sign};
@@ -76,7 +76,9 @@ pub(crate) fn part<'a>() -> impl Parser<'a, &'a str, Part<'a>, ParserError<'a>>
                 modification_date(),
                 read_date(),
                 description(),
+                language(),
                 disposition(),
+                cid(),
                 #[cfg(feature = "pgp")]
                 encrypt(),
                 #[cfg(feature = "pgp")]
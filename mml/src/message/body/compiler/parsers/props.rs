@@ -6,9 +6,9 @@
 //! [Emacs MML definition]: https://www.gnu.org/software/emacs/manual/html_node/emacs-mime/MML-Definition.html
 
 use crate::message::body::{
-    compiler::tokens::Prop, ALTERNATIVE, CHARSET, CREATION_DATE, DATA_ENCODING, DESCRIPTION,
-    DISPOSITION, ENCODING, FILENAME, MIXED, MODIFICATION_DATE, NAME, READ_DATE, RECIPIENT_FILENAME,
-    RELATED, SIZE, TYPE,
+    compiler::tokens::Prop, ALTERNATIVE, CHARSET, CID, CREATION_DATE, DATA_ENCODING, DESCRIPTION,
+    DISPOSITION, ENCODING, FILENAME, LANGUAGE, MIXED, MODIFICATION_DATE, NAME, READ_DATE,
+    RECIPIENT_FILENAME, RELATED, SIZE, TYPE,
 };
 #[cfg(feature = "pgp")]
 use crate::message::body::{ENCRYPT, RECIPIENTS, SENDER, SIGN};
@@ -92,6 +92,19 @@ pub(crate) fn name<'a>() -> impl Parser<'a, &'a str, Prop<'a>, ParserError<'a>>
         .padded()
 }
 
+/// The content ID property parser.
+///
+/// Content ID of the part (Content-ID), so it can be referenced from
+/// a HTML part using the `cid:` URL scheme. The part is displayed
+/// inline, as part of a `multipart/related` group.
+pub(crate) fn cid<'a>() -> impl Parser<'a, &'a str, Prop<'a>, ParserError<'a>> + Clone {
+    just(CID)
+        .labelled(CID)
+        .then_ignore(just('=').padded())
+        .then(choice((quoted_val(), val().to_slice())))
+        .padded()
+}
+
 /// The disposition property parser.
 ///
 /// > Valid values are ‘inline’ and ‘attachment’
@@ -149,6 +162,18 @@ pub(crate) fn description<'a>() -> impl Parser<'a, &'a str, Prop<'a>, ParserErro
         .padded()
 }
 
+/// The language property parser.
+///
+/// Language tag of the part contents, like `en` or `fr-CA`
+/// (Content-Language).
+pub(crate) fn language<'a>() -> impl Parser<'a, &'a str, Prop<'a>, ParserError<'a>> + Clone {
+    just(LANGUAGE)
+        .labelled(LANGUAGE)
+        .then_ignore(just('=').padded())
+        .then(choice((quoted_val(), val().to_slice())))
+        .padded()
+}
+
 /// The creation date property parser.
 ///
 /// > Date when the part was created (Content-Disposition). This uses
@@ -2,7 +2,9 @@
 //!
 //! Module dedicated to MIME → MML message body interpretation.
 
-use std::{env, fs, path::PathBuf};
+#[cfg(feature = "pgp")]
+use std::fmt;
+use std::{borrow::Cow, env, fs, path::PathBuf};
 
 use async_recursion::async_recursion;
 use mail_builder::MessageBuilder;
@@ -15,10 +17,7 @@
 use crate::pgp::Pgp;
 use crate::{Error, Result};
 
-use super::{
-    MULTIPART_BEGIN, MULTIPART_BEGIN_ESCAPED, MULTIPART_END, MULTIPART_END_ESCAPED, PART_BEGIN,
-    PART_BEGIN_ESCAPED, PART_END, PART_END_ESCAPED,
-};
+use super::{escape_mml_markup, HtmlSanitizer};
 
 /// Filters parts to show by MIME type.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -66,6 +65,124 @@ pub fn contains(&self, that: impl ToString + AsRef<str>) -> bool {
     }
 }
 
+/// The outcome of a PGP signature verification.
+#[cfg(feature = "pgp")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SignatureStatus {
+    /// The signature matches the signed part and the public key of
+    /// the given signer.
+    Valid(String),
+
+    /// The signature does not match the signed part or the public
+    /// key of the given signer.
+    Invalid(String),
+
+    /// The public key of the given signer could not be found.
+    MissingKey(String),
+
+    /// The signature could not be verified, for example because PGP
+    /// is not configured or because the signer is unknown.
+    Unverifiable,
+}
+
+#[cfg(feature = "pgp")]
+impl SignatureStatus {
+    fn from_verify_result(signer: &str, res: Result<()>) -> Self {
+        let signer = signer.to_owned();
+
+        match res {
+            Ok(()) => Self::Valid(signer),
+            Err(Error::FindPgpPublicKeyError(_)) => Self::MissingKey(signer),
+            #[cfg(feature = "pgp-native")]
+            Err(Error::VerifyNativePgpSignatureError(_)) => Self::Invalid(signer),
+            #[cfg(feature = "pgp-commands")]
+            Err(Error::VerifyCommandError(_)) => Self::Invalid(signer),
+            #[cfg(feature = "pgp-gpg")]
+            Err(Error::VerifyGpgError(_)) => Self::Invalid(signer),
+            Err(err) => {
+                debug!("cannot verify email part using pgp: {err}");
+                trace!("{err:?}");
+                Self::Unverifiable
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pgp")]
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Valid(signer) => write!(f, "valid; signed-by={signer}"),
+            Self::Invalid(signer) => write!(f, "invalid; signed-by={signer}"),
+            Self::MissingKey(signer) => write!(f, "missing-key; signed-by={signer}"),
+            Self::Unverifiable => write!(f, "unverifiable"),
+        }
+    }
+}
+
+/// The outcome of a PGP decryption.
+#[cfg(feature = "pgp")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DecryptionStatus {
+    /// The part has been decrypted, and its clear content has been
+    /// interpreted.
+    Decrypted,
+
+    /// The secret key of the given recipient could not be found.
+    MissingKey(String),
+
+    /// The part could not be decrypted using the secret key of the
+    /// given recipient, for example because of a wrong passphrase.
+    Failed(String),
+
+    /// The part could not be decrypted, for example because PGP is
+    /// not configured or because the recipient is unknown.
+    Undecryptable,
+}
+
+#[cfg(feature = "pgp")]
+impl DecryptionStatus {
+    fn from_decrypt_err(recipient: &str, err: &Error) -> Self {
+        let recipient = recipient.to_owned();
+
+        match err {
+            Error::PgpMissingConfigurationError => Self::Undecryptable,
+            Error::GetNativePgpSecretKeyNoneError(_) => Self::MissingKey(recipient),
+            #[cfg(feature = "pgp-native")]
+            Error::ReadNativePgpSecretKeyError(_) => Self::MissingKey(recipient),
+            #[cfg(all(feature = "pgp-native", feature = "keyring"))]
+            Error::GetPgpSecretKeyFromKeyringError(_) => Self::MissingKey(recipient),
+            _ => Self::Failed(recipient),
+        }
+    }
+}
+
+#[cfg(feature = "pgp")]
+impl fmt::Display for DecryptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decrypted => write!(f, "decrypted"),
+            Self::MissingKey(recipient) => write!(f, "missing-key; recipient={recipient}"),
+            Self::Failed(recipient) => write!(f, "failed; recipient={recipient}"),
+            Self::Undecryptable => write!(f, "undecryptable"),
+        }
+    }
+}
+
+/// Metadata collected while interpreting a MIME message body.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterpretStatus {
+    /// The verification outcome of each `multipart/signed` part, in
+    /// order of appearance.
+    #[cfg(feature = "pgp")]
+    pub signatures: Vec<SignatureStatus>,
+
+    /// The decryption outcome of each `multipart/encrypted` part, in
+    /// order of appearance.
+    #[cfg(feature = "pgp")]
+    pub decryptions: Vec<DecryptionStatus>,
+}
+
 /// MIME → MML message body interpreter.
 ///
 /// The interpreter follows the builder pattern, where the build function
@@ -149,6 +266,14 @@ pub struct MimeBodyInterpreter {
     /// [`std::env::temp_dir()`].
     save_attachments_dir: PathBuf,
 
+    /// Defines the sanitization of `text/html` parts.
+    ///
+    /// When defined, HTML parts are sanitized (scripts, remote images
+    /// and tracking pixels are removed) before being converted to
+    /// text or shown as is. See [`HtmlSanitizer`] to allow remote
+    /// content from some hosts.
+    html_sanitizer: Option<HtmlSanitizer>,
+
     #[cfg(feature = "pgp")]
     pgp: Option<Pgp>,
     #[cfg(feature = "pgp")]
@@ -168,6 +293,7 @@ fn default() -> Self {
             show_plain_texts_signature: true,
             save_attachments: Default::default(),
             save_attachments_dir: Self::default_save_attachments_dir(),
+            html_sanitizer: Default::default(),
             #[cfg(feature = "pgp")]
             pgp: Default::default(),
             #[cfg(feature = "pgp")]
@@ -227,6 +353,16 @@ pub fn with_save_attachments_dir(mut self, dir: impl Into<PathBuf>) -> Self {
         self
     }
 
+    pub fn with_sanitize_html(mut self, sanitize: bool) -> Self {
+        self.html_sanitizer = sanitize.then(HtmlSanitizer::default);
+        self
+    }
+
+    pub fn with_html_sanitizer(mut self, sanitizer: HtmlSanitizer) -> Self {
+        self.html_sanitizer = Some(sanitizer);
+        self
+    }
+
     #[cfg(feature = "pgp")]
     pub fn set_pgp(&mut self, pgp: impl Into<Pgp>) {
         self.pgp = Some(pgp.into());
@@ -261,65 +397,74 @@ pub fn with_pgp_recipient(mut self, recipient: Option<String>) -> Self {
         self
     }
 
-    /// Replace normal opening and closing tags by escaped opening and
-    /// closing tags.
-    fn escape_mml_markup(text: String) -> String {
-        text.replace(PART_BEGIN, PART_BEGIN_ESCAPED)
-            .replace(PART_END, PART_END_ESCAPED)
-            .replace(MULTIPART_BEGIN, MULTIPART_BEGIN_ESCAPED)
-            .replace(MULTIPART_END, MULTIPART_END_ESCAPED)
-    }
-
     /// Decrypt the given [MessagePart] using PGP.
+    ///
+    /// The decryption outcome is pushed to the given status, then the
+    /// decrypted MIME part is interpreted.
     #[cfg(feature = "pgp")]
-    async fn decrypt_part(&self, encrypted_part: &MessagePart<'_>) -> Result<String> {
-        match &self.pgp {
-            None => {
-                debug!("cannot decrypt part: pgp not configured");
-                Ok(String::from_utf8_lossy(encrypted_part.contents()).to_string())
-            }
-            Some(pgp) => {
-                let recipient = self
-                    .pgp_recipient
-                    .as_ref()
-                    .ok_or(Error::PgpDecryptMissingRecipientError)?;
-                let encrypted_bytes = encrypted_part.contents().to_owned();
-                let decrypted_part = pgp.decrypt(recipient, encrypted_bytes).await?;
-                let clear_part = MessageParser::new()
-                    .parse(&decrypted_part)
-                    .ok_or(Error::ParsePgpDecryptedPartError)?;
-                let tpl = self.interpret_msg(&clear_part).await?;
-                Ok(tpl)
+    async fn decrypt_part(
+        &self,
+        encrypted_part: &MessagePart<'_>,
+        status: &mut InterpretStatus,
+    ) -> Result<String> {
+        let Some(pgp) = &self.pgp else {
+            debug!("cannot decrypt part: pgp not configured");
+            status.decryptions.push(DecryptionStatus::Undecryptable);
+            return Ok(String::from_utf8_lossy(encrypted_part.contents()).to_string());
+        };
+
+        let Some(recipient) = &self.pgp_recipient else {
+            status.decryptions.push(DecryptionStatus::Undecryptable);
+            return Err(Error::PgpDecryptMissingRecipientError);
+        };
+
+        let encrypted_bytes = encrypted_part.contents().to_owned();
+        let decrypted_part = match pgp.decrypt(recipient, encrypted_bytes).await {
+            Ok(decrypted_part) => decrypted_part,
+            Err(err) => {
+                let decryption = DecryptionStatus::from_decrypt_err(recipient, &err);
+                status.decryptions.push(decryption);
+                return Err(err);
             }
-        }
+        };
+
+        status.decryptions.push(DecryptionStatus::Decrypted);
+
+        let clear_part = MessageParser::new()
+            .parse(&decrypted_part)
+            .ok_or(Error::ParsePgpDecryptedPartError)?;
+        let tpl = self.interpret_msg_status(&clear_part, status).await?;
+        Ok(tpl)
     }
 
     /// Verify the given [Message] using PGP.
+    ///
+    /// The signature is verified against the public key of the
+    /// sender.
     #[cfg(feature = "pgp")]
-    async fn verify_msg(&self, msg: &Message<'_>, ids: &[usize]) -> Result<()> {
-        match &self.pgp {
-            None => {
-                debug!("cannot verify message: pgp not configured");
-            }
-            Some(pgp) => {
-                let signed_part = msg.part(ids[0]).unwrap();
-                let signed_part_bytes = msg.raw_message
-                    [signed_part.raw_header_offset()..signed_part.raw_end_offset()]
-                    .to_owned();
-
-                let signature_part = msg.part(ids[1]).unwrap();
-                let signature_bytes = signature_part.contents().to_owned();
-
-                let recipient = self
-                    .pgp_recipient
-                    .as_ref()
-                    .ok_or(Error::PgpDecryptMissingRecipientError)?;
-                pgp.verify(recipient, signature_bytes, signed_part_bytes)
-                    .await?;
-            }
+    async fn verify_msg(&self, msg: &Message<'_>, ids: &[usize]) -> SignatureStatus {
+        let Some(pgp) = &self.pgp else {
+            debug!("cannot verify message: pgp not configured");
+            return SignatureStatus::Unverifiable;
         };
 
-        Ok(())
+        let Some(sender) = &self.pgp_sender else {
+            debug!("cannot verify message: missing sender");
+            return SignatureStatus::Unverifiable;
+        };
+
+        let (Some(signed_part), Some(signature_part)) = (msg.part(ids[0]), msg.part(ids[1])) else {
+            debug!("cannot verify message: missing signed or signature part");
+            return SignatureStatus::Unverifiable;
+        };
+
+        let signed_part_bytes = msg.raw_message
+            [signed_part.raw_header_offset()..signed_part.raw_end_offset()]
+            .to_owned();
+        let signature_bytes = signature_part.contents().to_owned();
+
+        let res = pgp.verify(sender, signature_bytes, signed_part_bytes).await;
+        SignatureStatus::from_verify_result(sender, res)
     }
 
     fn interpret_attachment(&self, ctype: &str, part: &MessagePart, data: &[u8]) -> Result<String> {
@@ -375,7 +520,7 @@ fn interpret_text(&self, ctype: &str, text: &str) -> String {
 
         if self.filter_parts.contains(ctype) {
             let text = text.replace('\r', "");
-            let text = Self::escape_mml_markup(text);
+            let text = escape_mml_markup(&text);
 
             if !self.show_parts || self.filter_parts.only(ctype) {
                 tpl.push_str(&text);
@@ -394,7 +539,7 @@ fn interpret_text_plain(&self, plain: &str) -> String {
 
         if self.filter_parts.contains("text/plain") {
             let plain = plain.replace('\r', "");
-            let mut plain = Self::escape_mml_markup(plain);
+            let mut plain = escape_mml_markup(&plain);
 
             if !self.show_plain_texts_signature {
                 plain = plain
@@ -413,13 +558,18 @@ fn interpret_text_html(&self, html: &str) -> String {
         let mut tpl = String::new();
 
         if self.filter_parts.contains("text/html") {
+            let html = match &self.html_sanitizer {
+                Some(sanitizer) => Cow::Owned(sanitizer.sanitize(html)),
+                None => Cow::Borrowed(html),
+            };
+
             if self.filter_parts.only("text/html") {
                 let html = html.replace('\r', "");
-                let html = Self::escape_mml_markup(html);
+                let html = escape_mml_markup(&html);
                 tpl.push_str(&html);
             } else {
                 let html = html2text(&html);
-                let html = Self::escape_mml_markup(html);
+                let html = escape_mml_markup(&html);
 
                 if self.show_parts {
                     tpl.push_str("<#part type=text/html>\n");
@@ -437,7 +587,12 @@ fn interpret_text_html(&self, html: &str) -> String {
     }
 
     #[async_recursion]
-    async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Result<String> {
+    async fn interpret_part(
+        &self,
+        msg: &Message<'_>,
+        part: &MessagePart<'_>,
+        status: &mut InterpretStatus,
+    ) -> Result<String> {
         let mut tpl = String::new();
         let ctype = get_ctype(part);
 
@@ -458,7 +613,7 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
                 tpl.push_str(&self.interpret_inline_attachment(&ctype, part, data)?);
             }
             PartType::Message(msg) => {
-                tpl.push_str(&self.interpret_msg(msg).await?);
+                tpl.push_str(&self.interpret_msg_status(msg, status).await?);
             }
             PartType::Multipart(ids) if ctype == "multipart/alternative" => {
                 let mut parts = ids.iter().filter_map(|id| msg.part(*id));
@@ -498,7 +653,7 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
                         match part {
                             Some(part) => Some(part),
                             None => match parts.next() {
-                                Some(part) => Some(self.interpret_part(msg, part).await),
+                                Some(part) => Some(self.interpret_part(msg, part, status).await),
                                 None => None,
                             },
                         }
@@ -508,13 +663,13 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
                             .clone()
                             .find(|part| get_ctype(part).starts_with(ctype))
                         {
-                            Some(part) => Some(self.interpret_part(msg, part).await),
+                            Some(part) => Some(self.interpret_part(msg, part, status).await),
                             None => None,
                         }
                     }
                     FilterParts::Include(ctypes) => {
                         match parts.clone().find(|part| ctypes.contains(&get_ctype(part))) {
-                            Some(part) => Some(self.interpret_part(msg, part).await),
+                            Some(part) => Some(self.interpret_part(msg, part, status).await),
                             None => None,
                         }
                     }
@@ -523,7 +678,7 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
                             .clone()
                             .find(|part| !ctypes.contains(&get_ctype(part)))
                         {
-                            Some(part) => Some(self.interpret_part(msg, part).await),
+                            Some(part) => Some(self.interpret_part(msg, part, status).await),
                             None => None,
                         }
                     }
@@ -535,7 +690,7 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
             }
             #[cfg(feature = "pgp")]
             PartType::Multipart(ids) if ctype == "multipart/encrypted" => {
-                match self.decrypt_part(msg.part(ids[1]).unwrap()).await {
+                match self.decrypt_part(msg.part(ids[1]).unwrap(), status).await {
                     Ok(ref clear_part) => tpl.push_str(clear_part),
                     Err(err) => {
                         debug!("cannot decrypt email part using pgp: {err}");
@@ -545,18 +700,12 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
             }
             #[cfg(feature = "pgp")]
             PartType::Multipart(ids) if ctype == "multipart/signed" => {
-                match self.verify_msg(msg, ids).await {
-                    Ok(()) => {
-                        debug!("email part successfully verified using pgp");
-                    }
-                    Err(err) => {
-                        debug!("cannot verify email part using pgp: {err}");
-                        trace!("{err:?}");
-                    }
-                }
+                let signature = self.verify_msg(msg, ids).await;
+                debug!("email part verified using pgp: {signature}");
+                status.signatures.push(signature);
 
                 let signed_part = msg.part(ids[0]).unwrap();
-                let clear_part = &self.interpret_part(msg, signed_part).await?;
+                let clear_part = &self.interpret_part(msg, signed_part, status).await?;
                 tpl.push_str(clear_part);
             }
             PartType::Multipart(_) if ctype == "application/pgp-encrypted" => {
@@ -576,7 +725,7 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
 
                 for id in ids {
                     if let Some(part) = msg.part(*id) {
-                        tpl.push_str(&self.interpret_part(msg, part).await?);
+                        tpl.push_str(&self.interpret_part(msg, part, status).await?);
                     } else {
                         debug!("cannot find part {id}, skipping it");
                     }
@@ -591,9 +740,31 @@ async fn interpret_part(&self, msg: &Message<'_>, part: &MessagePart<'_>) -> Res
         Ok(tpl)
     }
 
+    /// Interpret the given MIME [Message] as a MML message string,
+    /// collecting metadata into the given status.
+    async fn interpret_msg_status(
+        &self,
+        msg: &Message<'_>,
+        status: &mut InterpretStatus,
+    ) -> Result<String> {
+        self.interpret_part(msg, msg.root_part(), status).await
+    }
+
     /// Interpret the given MIME [Message] as a MML message string.
     pub async fn interpret_msg<'a>(&self, msg: &Message<'a>) -> Result<String> {
-        self.interpret_part(msg, msg.root_part()).await
+        Ok(self.interpret_msg_with_status(msg).await?.0)
+    }
+
+    /// Interpret the given MIME [Message] as a MML message string,
+    /// alongside metadata collected during the interpretation like
+    /// signatures verification and decryption outcomes.
+    pub async fn interpret_msg_with_status<'a>(
+        &self,
+        msg: &Message<'a>,
+    ) -> Result<(String, InterpretStatus)> {
+        let mut status = InterpretStatus::default();
+        let tpl = self.interpret_msg_status(msg, &mut status).await?;
+        Ok((tpl, status))
     }
 
     /// Interpret the given MIME message bytes as a MML message
@@ -632,7 +803,7 @@ mod tests {
     use concat_with::concat_line;
     use mail_builder::{mime::MimePart, MessageBuilder};
 
-    use super::{FilterParts, MimeBodyInterpreter};
+    use super::{FilterParts, HtmlSanitizer, MimeBodyInterpreter};
 
     #[tokio::test]
     async fn nested_multiparts() {
@@ -790,6 +961,32 @@ async fn only_text_html() {
         assert_eq!(tpl, expected_tpl);
     }
 
+    #[tokio::test]
+    async fn sanitize_html() {
+        let builder = MessageBuilder::new().html_body(concat!(
+            "<p>Hello<script>alert(1)</script></p>",
+            "<img src=\"https://localhost/logo.png\">",
+        ));
+
+        let interpreter =
+            MimeBodyInterpreter::new().with_filter_parts(FilterParts::Only("text/html".into()));
+
+        let tpl = interpreter
+            .clone()
+            .with_sanitize_html(true)
+            .interpret_msg_builder(builder.clone())
+            .await
+            .unwrap();
+        assert_eq!(tpl, "<p>Hello</p>");
+
+        let tpl = interpreter
+            .with_html_sanitizer(HtmlSanitizer::new().with_allowed_remote_hosts(["localhost"]))
+            .interpret_msg_builder(builder)
+            .await
+            .unwrap();
+        assert_eq!(tpl, "<p>Hello</p><img src=\"https://localhost/logo.png\">");
+    }
+
     #[tokio::test]
     async fn only_text_other() {
         let builder = MessageBuilder::new().body(MimePart::new(
@@ -10,27 +10,32 @@
 pub mod compiler;
 #[cfg(feature = "interpreter")]
 pub mod interpreter;
+#[cfg(feature = "interpreter")]
+pub mod sanitizer;
 
 #[cfg(feature = "compiler")]
 #[doc(inline)]
-pub use self::compiler::MmlBodyCompiler;
+pub use self::compiler::{Disposition, MmlBodyCompiler, MmlCompilerLimits};
+#[cfg(all(feature = "interpreter", feature = "pgp"))]
+#[doc(inline)]
+pub use self::interpreter::{DecryptionStatus, SignatureStatus};
+#[cfg(feature = "interpreter")]
+#[doc(inline)]
+pub use self::interpreter::{FilterParts, InterpretStatus, MimeBodyInterpreter};
 #[cfg(feature = "interpreter")]
 #[doc(inline)]
-pub use self::interpreter::{FilterParts, MimeBodyInterpreter};
+pub use self::sanitizer::HtmlSanitizer;
 
 pub(crate) const PART_BEGIN: &str = "<#part";
-pub(crate) const PART_BEGIN_ESCAPED: &str = "<#!part";
 pub(crate) const PART_END: &str = "<#/part>";
-pub(crate) const PART_END_ESCAPED: &str = "<#!/part>";
 
 pub(crate) const MULTIPART_BEGIN: &str = "<#multipart";
-pub(crate) const MULTIPART_BEGIN_ESCAPED: &str = "<#!multipart";
 pub(crate) const MULTIPART_END: &str = "<#/multipart>";
-pub(crate) const MULTIPART_END_ESCAPED: &str = "<#!/multipart>";
 
 pub(crate) const ALTERNATIVE: &str = "alternative";
 pub(crate) const ATTACHMENT: &str = "attachment";
 pub(crate) const CHARSET: &str = "charset";
+pub(crate) const CID: &str = "cid";
 pub(crate) const CREATION_DATE: &str = "creation-date";
 pub(crate) const DATA_ENCODING: &str = "data-encoding";
 pub(crate) const DESCRIPTION: &str = "description";
@@ -44,6 +49,7 @@
 pub(crate) const ENCRYPT: &str = "encrypt";
 pub(crate) const FILENAME: &str = "filename";
 pub(crate) const INLINE: &str = "inline";
+pub(crate) const LANGUAGE: &str = "language";
 pub(crate) const MIXED: &str = "mixed";
 pub(crate) const MODIFICATION_DATE: &str = "modification-date";
 pub(crate) const NAME: &str = "name";
@@ -66,3 +72,82 @@
 pub(crate) const GREATER_THAN: char = '>';
 pub(crate) const NEW_LINE: char = '\n';
 pub(crate) const SPACE: char = ' ';
+
+/// The MML tag names, as they appear right after the `<#` prefix.
+const MML_TAGS: [&str; 4] = ["part", "/part>", "multipart", "/multipart>"];
+
+/// Add one level of escaping to MML tags.
+///
+/// Each MML tag `<#part`, `<#/part>`, `<#multipart` and
+/// `<#/multipart>` gets one more `!` after `<#`, whatever the number
+/// of `!` it already contains: `<#part>` becomes `<#!part>`, and
+/// `<#!part>` becomes `<#!!part>`. This makes the escaping lossless
+/// when combined with [`unescape_mml_markup`].
+pub(crate) fn escape_mml_markup(text: &str) -> String {
+    map_mml_markup(text, |bangs| bangs + 1)
+}
+
+/// Remove one level of escaping from MML tags.
+///
+/// This is the reverse of [`escape_mml_markup`]: `<#!part>` becomes
+/// the literal text `<#part>`, and `<#!!part>` becomes `<#!part>`.
+/// Unescaped tags are left untouched.
+pub(crate) fn unescape_mml_markup(text: &str) -> String {
+    map_mml_markup(text, |bangs| bangs.saturating_sub(1))
+}
+
+/// Change the number of `!` of each MML tag found in the given text.
+fn map_mml_markup(text: &str, f: impl Fn(usize) -> usize) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find("<#") {
+        output.push_str(&rest[..pos + 2]);
+        rest = &rest[pos + 2..];
+
+        let bangs = rest.len() - rest.trim_start_matches('!').len();
+        let tag = &rest[bangs..];
+
+        if MML_TAGS.iter().any(|name| tag.starts_with(name)) {
+            output.extend(std::iter::repeat('!').take(f(bangs)));
+            rest = tag;
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_mml_markup, unescape_mml_markup};
+
+    #[test]
+    fn escape() {
+        assert_eq!(escape_mml_markup("<#part>a<#/part>"), "<#!part>a<#!/part>");
+        assert_eq!(escape_mml_markup("<#!part>"), "<#!!part>");
+        assert_eq!(
+            escape_mml_markup("<#multipart><#/multipart>"),
+            "<#!multipart><#!/multipart>"
+        );
+        assert_eq!(escape_mml_markup("<#other> <# <#"), "<#other> <# <#");
+    }
+
+    #[test]
+    fn unescape() {
+        assert_eq!(
+            unescape_mml_markup("<#!part>a<#!/part>"),
+            "<#part>a<#/part>"
+        );
+        assert_eq!(unescape_mml_markup("<#!!part>"), "<#!part>");
+        assert_eq!(unescape_mml_markup("<#part>"), "<#part>");
+        assert_eq!(unescape_mml_markup("<#!other>"), "<#!other>");
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in ["<#part>", "<#!part>", "<#!!/multipart>", "a <#!x <#"] {
+            assert_eq!(unescape_mml_markup(&escape_mml_markup(text)), text);
+        }
+    }
+}
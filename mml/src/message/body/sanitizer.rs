@@ -0,0 +1,858 @@
+//! # HTML sanitizer module
+//!
+//! Module dedicated to HTML sanitization, applied to HTML parts
+//! before they get interpreted. The main structure of this module is
+//! [`HtmlSanitizer`].
+
+use std::borrow::Cow;
+
+use tracing::debug;
+
+/// Elements removed together with their content.
+const REMOVED_ELEMENTS: [&str; 9] = [
+    "script", "noscript", "iframe", "object", "applet", "frameset", "svg", "math", "portal",
+];
+
+/// Raw text and RCDATA elements, removed together with their
+/// content.
+///
+/// Browsers do not parse markup inside these elements, so their
+/// content cannot be sanitized like the rest of the HTML: something
+/// looking like an attribute value inside them becomes markup once
+/// the element is closed. `<style>` is handled apart, see
+/// [`HtmlSanitizer::is_css_allowed`].
+const RAW_TEXT_ELEMENTS: [&str; 5] = ["xmp", "noembed", "noframes", "textarea", "title"];
+
+/// The element turning the rest of the document into raw text, which
+/// is removed together with the rest of the document.
+const PLAINTEXT_ELEMENT: &str = "plaintext";
+
+/// Void elements removed because they load or embed remote content,
+/// or because they change how the document is loaded.
+const REMOVED_VOID_ELEMENTS: [&str; 5] = ["embed", "frame", "link", "base", "meta"];
+
+/// Elements removed while their content is kept.
+///
+/// Forms are removed so that messages cannot submit data anywhere,
+/// their fields are kept as inert content.
+const UNWRAPPED_ELEMENTS: [&str; 1] = ["form"];
+
+/// Attributes holding URLs followed on user action, which must use
+/// one of the [`LINK_SCHEMES`].
+const LINK_ATTRIBUTES: [&str; 6] = [
+    "href",
+    "action",
+    "formaction",
+    "xlink:href",
+    "cite",
+    "longdesc",
+];
+
+/// Attributes holding URLs of content loaded with the message, which
+/// must be local or come from an allowed remote host.
+const RESOURCE_ATTRIBUTES: [&str; 6] = ["src", "background", "poster", "lowsrc", "dynsrc", "data"];
+
+/// The schemes allowed in [`LINK_ATTRIBUTES`], besides remote URLs.
+const LINK_SCHEMES: [&str; 3] = ["mailto", "tel", "cid"];
+
+/// The schemes allowed in [`RESOURCE_ATTRIBUTES`], besides remote
+/// URLs from an allowed host.
+const LOCAL_SCHEMES: [&str; 2] = ["cid", "data"];
+
+/// The schemes of remote URLs.
+///
+/// Like browsers do for these schemes, slashes following the scheme
+/// are optional, and backslashes are considered as slashes.
+const REMOTE_SCHEMES: [&str; 3] = ["http", "https", "ftp"];
+
+/// CSS constructs refused in styles, since they run code, load
+/// content without `url()`, or cannot be checked.
+const UNSAFE_CSS: [&str; 8] = [
+    "expression(",
+    "javascript:",
+    "vbscript:",
+    "behavior:",
+    "-moz-binding",
+    "@import",
+    "image-set(",
+    "image(",
+];
+
+/// The HTML sanitizer.
+///
+/// The sanitizer strips scripts (script elements, event handler
+/// attributes and URLs with a scheme other than the allowed ones),
+/// embedded frames and objects, forms, meta refreshes, tracking
+/// pixels and remote content (images, `srcset` candidates and CSS
+/// `url()`). Remote content is kept when its host is part of the
+/// allowlist.
+///
+/// Attribute values are checked once their character references are
+/// decoded, and URLs once normalized like browsers do (whitespaces
+/// and control characters removed, backslashes read as slashes).
+///
+/// The sanitizer is lenient: it does not validate the HTML, it only
+/// removes what it recognizes as unsafe and keeps everything else as
+/// is.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HtmlSanitizer {
+    /// The hosts remote content is allowed from.
+    ///
+    /// A host also allows its sub-domains: `example.com` allows
+    /// `img.example.com`.
+    allowed_remote_hosts: Vec<String>,
+}
+
+impl HtmlSanitizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_allowed_remote_hosts(
+        mut self,
+        hosts: impl IntoIterator<Item = impl ToString>,
+    ) -> Self {
+        self.allowed_remote_hosts = hosts
+            .into_iter()
+            .map(|host| host.to_string().to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    /// Sanitize the given HTML.
+    pub fn sanitize(&self, html: &str) -> String {
+        let mut sanitized = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(pos) = rest.find('<') {
+            sanitized.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                rest = match comment.find("-->") {
+                    Some(end) => &comment[end + 3..],
+                    None => "",
+                };
+                continue;
+            }
+
+            let Some(tag) = Tag::parse(rest) else {
+                sanitized.push('<');
+                rest = &rest[1..];
+                continue;
+            };
+
+            rest = &rest[tag.len..];
+
+            let name = tag.name.as_str();
+
+            if tag.closing {
+                // attributes of closing tags are meaningless, so they
+                // are not kept
+                if !REMOVED_ELEMENTS.contains(&name)
+                    && !RAW_TEXT_ELEMENTS.contains(&name)
+                    && !REMOVED_VOID_ELEMENTS.contains(&name)
+                    && !UNWRAPPED_ELEMENTS.contains(&name)
+                    && name != PLAINTEXT_ELEMENT
+                    && name != "style"
+                {
+                    sanitized.push_str(&format!("</{name}>"));
+                }
+                continue;
+            }
+
+            if REMOVED_ELEMENTS.contains(&name) || RAW_TEXT_ELEMENTS.contains(&name) {
+                debug!("remove html element <{name}> and its content");
+                rest = split_element_content(rest, name).1;
+                continue;
+            }
+
+            if name == PLAINTEXT_ELEMENT {
+                debug!("remove html element <{name}> and the rest of the document");
+                rest = "";
+                break;
+            }
+
+            if REMOVED_VOID_ELEMENTS.contains(&name) || UNWRAPPED_ELEMENTS.contains(&name) {
+                debug!("remove html element <{name}>");
+                continue;
+            }
+
+            if name == "style" {
+                let (css, next) = split_element_content(rest, name);
+                rest = next;
+
+                if self.is_css_allowed(css) {
+                    sanitized.push_str(&self.to_safe_html(&tag));
+                    sanitized.push_str(css);
+                    sanitized.push_str("</style>");
+                } else {
+                    debug!("remove html element <style> and its content");
+                }
+
+                continue;
+            }
+
+            if name == "img" && !self.is_image_allowed(&tag) {
+                continue;
+            }
+
+            sanitized.push_str(&self.to_safe_html(&tag));
+        }
+
+        sanitized.push_str(rest);
+        sanitized
+    }
+
+    fn is_image_allowed(&self, img: &Tag) -> bool {
+        let size = |name| {
+            img.attr(name)
+                .map(|value| value.trim_end_matches("px").trim())
+        };
+
+        if matches!(size("width"), Some("0" | "1")) || matches!(size("height"), Some("0" | "1")) {
+            debug!("remove html tracking pixel");
+            return false;
+        }
+
+        match img.attr("src") {
+            Some(src) => self.is_resource_allowed(src),
+            None => true,
+        }
+    }
+
+    /// Return `true` if the given URL of content loaded with the
+    /// message is local, or comes from an allowed remote host.
+    fn is_resource_allowed(&self, url: &str) -> bool {
+        match Url::parse(url) {
+            Url::Relative => true,
+            Url::Remote(host) if self.is_remote_host_allowed(&host) => true,
+            Url::Remote(host) => {
+                debug!("remove html remote content from {host}");
+                false
+            }
+            Url::Other(scheme) => LOCAL_SCHEMES.contains(&scheme.as_str()),
+        }
+    }
+
+    /// Return `true` if all the candidates of the given `srcset` are
+    /// allowed resources.
+    fn is_srcset_allowed(&self, srcset: &str) -> bool {
+        decode_entities(srcset).split(',').all(|candidate| {
+            let url = candidate.split_whitespace().next().unwrap_or_default();
+            self.is_resource_allowed(url)
+        })
+    }
+
+    /// Return `true` if the given CSS cannot run code, and only loads
+    /// allowed resources.
+    fn is_css_allowed(&self, css: &str) -> bool {
+        let css = decode_entities(css);
+
+        // escapes can hide any keyword, they are not worth the risk
+        if css.contains('\\') {
+            debug!("remove html style containing css escapes");
+            return false;
+        }
+
+        let css = strip_css_comments(&css).to_ascii_lowercase();
+        let css: String = css.chars().filter(|c| !c.is_whitespace()).collect();
+
+        if let Some(unsafe_css) = UNSAFE_CSS
+            .iter()
+            .find(|unsafe_css| css.contains(*unsafe_css))
+        {
+            debug!("remove html style containing {unsafe_css}");
+            return false;
+        }
+
+        css.split("url(").skip(1).all(|url| {
+            let url = url.split(')').next().unwrap_or_default();
+            self.is_resource_allowed(url.trim_matches(['"', '\'']))
+        })
+    }
+
+    fn is_remote_host_allowed(&self, host: &str) -> bool {
+        self.allowed_remote_hosts.iter().any(|allowed| {
+            host == allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    /// Rebuild the given tag without its unsafe attributes.
+    fn to_safe_html(&self, tag: &Tag) -> String {
+        let mut html = format!("<{}", tag.name);
+
+        for (name, raw, value) in &tag.attrs {
+            let name = name.as_str();
+            let value = value.unwrap_or_default();
+
+            if name.starts_with("on") {
+                debug!("remove html event handler attribute {name}");
+                continue;
+            }
+
+            // names with quotes or other unexpected characters may be
+            // read differently by browsers, they are not worth the
+            // risk
+            if !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+            {
+                debug!("remove html attribute with unexpected name {name:?}");
+                continue;
+            }
+
+            let allowed = if LINK_ATTRIBUTES.contains(&name) {
+                is_link_allowed(value)
+            } else if RESOURCE_ATTRIBUTES.contains(&name) {
+                self.is_resource_allowed(value)
+            } else if name == "srcset" {
+                self.is_srcset_allowed(value)
+            } else if name == "style" {
+                self.is_css_allowed(value)
+            } else {
+                true
+            };
+
+            if !allowed {
+                debug!("remove html attribute {name}");
+                continue;
+            }
+
+            html.push(' ');
+            html.push_str(raw);
+        }
+
+        if tag.self_closing {
+            html.push_str(" /");
+        }
+
+        html.push('>');
+        html
+    }
+}
+
+/// An HTML tag, like `<a href="…">` or `</a>`.
+struct Tag<'a> {
+    /// The length of the raw tag, in bytes.
+    len: usize,
+
+    /// The lowercase tag name.
+    name: String,
+
+    /// Whether the tag is a closing one, like `</a>`.
+    closing: bool,
+
+    /// Whether the tag is self-closing, like `<br/>`.
+    self_closing: bool,
+
+    /// The attributes, as lowercase name, raw attribute and unquoted
+    /// value.
+    attrs: Vec<(String, &'a str, Option<&'a str>)>,
+}
+
+impl<'a> Tag<'a> {
+    /// Parse the tag the given HTML starts with.
+    ///
+    /// Returns `None` if the HTML does not start with a tag, like a
+    /// lone `<` or a `<!DOCTYPE>` declaration.
+    fn parse(html: &'a str) -> Option<Self> {
+        let bytes = html.as_bytes();
+        let mut i = 1;
+
+        let closing = bytes.get(i) == Some(&b'/');
+        if closing {
+            i += 1;
+        }
+
+        let name_start = i;
+        while bytes.get(i).is_some_and(|b| b.is_ascii_alphanumeric()) {
+            i += 1;
+        }
+
+        if i == name_start || !bytes[name_start].is_ascii_alphabetic() {
+            return None;
+        }
+
+        let name = html[name_start..i].to_ascii_lowercase();
+        let mut attrs = Vec::new();
+        let mut self_closing = false;
+
+        loop {
+            while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+                i += 1;
+            }
+
+            match bytes.get(i) {
+                // an unterminated tag takes the rest of the HTML
+                None => break,
+                Some(b'>') => {
+                    i += 1;
+                    break;
+                }
+                Some(b'/') => {
+                    self_closing = true;
+                    i += 1;
+                    continue;
+                }
+                Some(_) => self_closing = false,
+            }
+
+            let attr_start = i;
+            while bytes
+                .get(i)
+                .is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b, b'=' | b'>' | b'/'))
+            {
+                i += 1;
+            }
+            let attr_name = html[attr_start..i].to_ascii_lowercase();
+
+            let mut j = i;
+            while bytes.get(j).is_some_and(|b| b.is_ascii_whitespace()) {
+                j += 1;
+            }
+
+            let mut value = None;
+
+            if bytes.get(j) == Some(&b'=') {
+                j += 1;
+                while bytes.get(j).is_some_and(|b| b.is_ascii_whitespace()) {
+                    j += 1;
+                }
+
+                match bytes.get(j) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let value_start = j + 1;
+                        let value_end = html[value_start..]
+                            .find(quote as char)
+                            .map(|end| value_start + end)
+                            .unwrap_or(html.len());
+                        value = Some(&html[value_start..value_end]);
+                        j = (value_end + 1).min(html.len());
+                    }
+                    _ => {
+                        let value_start = j;
+                        while bytes
+                            .get(j)
+                            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'>')
+                        {
+                            j += 1;
+                        }
+                        value = Some(&html[value_start..j]);
+                    }
+                }
+
+                i = j;
+            }
+
+            if !attr_name.is_empty() {
+                attrs.push((attr_name, &html[attr_start..i], value));
+            }
+
+            if i == attr_start {
+                // skips unexpected characters (like a stray `=`)
+                i += 1;
+            }
+        }
+
+        Some(Self {
+            len: i,
+            name,
+            closing,
+            self_closing,
+            attrs,
+        })
+    }
+
+    /// Get the unquoted value of the given attribute.
+    fn attr(&self, name: &str) -> Option<&'a str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _, _)| attr == name)
+            .and_then(|(_, _, value)| *value)
+    }
+}
+
+/// Split the given HTML at the closing tag of the given element,
+/// giving the content of the element and the HTML after its closing
+/// tag.
+fn split_element_content<'a>(html: &'a str, name: &str) -> (&'a str, &'a str) {
+    let closing = format!("</{name}");
+    let lowercase = html.to_ascii_lowercase();
+
+    match lowercase.find(&closing) {
+        Some(pos) => {
+            let rest = &html[pos..];
+            match rest.find('>') {
+                Some(end) => (&html[..pos], &rest[end + 1..]),
+                None => (&html[..pos], ""),
+            }
+        }
+        None => (html, ""),
+    }
+}
+
+/// Return `true` if the given URL followed on user action is remote,
+/// relative, or uses one of the [`LINK_SCHEMES`].
+fn is_link_allowed(url: &str) -> bool {
+    match Url::parse(url) {
+        Url::Relative | Url::Remote(_) => true,
+        Url::Other(scheme) => LINK_SCHEMES.contains(&scheme.as_str()),
+    }
+}
+
+/// A URL, classified by what it points to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Url {
+    /// A URL without scheme nor host.
+    Relative,
+
+    /// A remote URL, with its lowercase host.
+    Remote(String),
+
+    /// A URL with any other lowercase scheme, like `cid` or
+    /// `javascript`.
+    Other(String),
+}
+
+impl Url {
+    /// Parse the given attribute value as a URL.
+    ///
+    /// The value is normalized like browsers do: character references
+    /// are decoded, whitespaces and control characters are removed,
+    /// and backslashes are read as slashes. This prevents bypasses
+    /// like `java&#x09;script:` or `\\host\image.png`.
+    fn parse(value: &str) -> Self {
+        let url: String = decode_entities(value)
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .map(|c| {
+                if c == '\\' {
+                    '/'
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+
+        if let Some(authority) = url.strip_prefix("//") {
+            return Self::Remote(host(authority));
+        }
+
+        match url.split_once(':') {
+            Some((scheme, rest)) if is_scheme(scheme) => {
+                if REMOTE_SCHEMES.contains(&scheme) {
+                    Self::Remote(host(rest.trim_start_matches('/')))
+                } else {
+                    Self::Other(scheme.to_owned())
+                }
+            }
+            _ => Self::Relative,
+        }
+    }
+}
+
+/// Return `true` if the given string is a valid URL scheme.
+fn is_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Get the host of the given URL authority, followed by the rest of
+/// the URL.
+fn host(authority: &str) -> String {
+    let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+
+    host.trim_end_matches('.').to_owned()
+}
+
+/// Remove the comments of the given CSS.
+fn strip_css_comments(css: &str) -> Cow<'_, str> {
+    if !css.contains("/*") {
+        return Cow::Borrowed(css);
+    }
+
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+
+    stripped.push_str(rest);
+    Cow::Owned(stripped)
+}
+
+/// Decode the character references of the given attribute value,
+/// like `&#106;` or `&colon;`.
+///
+/// Numeric references and the named references useful to write URLs
+/// are decoded, with or without their trailing semicolon. Other
+/// named references are kept as is.
+fn decode_entities(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(pos) = rest.find('&') {
+        decoded.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        match decode_entity(rest) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => decoded.push('&'),
+        }
+    }
+
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Decode the character reference the given text starts with, right
+/// after its `&`.
+///
+/// Returns the decoded character and the length of the reference.
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    if let Some(num) = text.strip_prefix('#') {
+        let (digits, radix, prefix_len) = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16, 2),
+            None => (num, 10, 1),
+        };
+
+        let len = digits.chars().take_while(|c| c.is_digit(radix)).count();
+        if len == 0 {
+            return None;
+        }
+
+        // like browsers, invalid code points give the replacement
+        // character
+        let c = u32::from_str_radix(&digits[..len], radix)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        let semicolon_len = usize::from(digits[len..].starts_with(';'));
+
+        return Some((c, prefix_len + len + semicolon_len));
+    }
+
+    let len = text.bytes().take_while(u8::is_ascii_alphanumeric).count();
+
+    let c = match text[..len].to_ascii_lowercase().as_str() {
+        "tab" => '\t',
+        "newline" => '\n',
+        "colon" => ':',
+        "sol" => '/',
+        "bsol" => '\\',
+        "period" => '.',
+        "comma" => ',',
+        "lpar" => '(',
+        "rpar" => ')',
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => return None,
+    };
+
+    let semicolon_len = usize::from(text[len..].starts_with(';'));
+    Some((c, len + semicolon_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HtmlSanitizer;
+
+    #[test]
+    fn strip_scripts() {
+        let html = concat!(
+            "<p onclick=\"alert(1)\" class=title>Hello</p>",
+            "<script type=\"text/javascript\">alert(\"<b>\");</SCRIPT>",
+            "<a href=\" javascript:alert(1)\" title='link'>link</a>",
+            "<!-- comment -->",
+            "<iframe src=\"https://localhost\">frame</iframe>",
+            "1 < 2",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            "<p class=title>Hello</p><a title='link'>link</a>1 < 2"
+        );
+    }
+
+    #[test]
+    fn strip_remote_images_unless_allowed() {
+        let html = concat!(
+            "<img src=\"https://img.example.com/logo.png\" alt=logo>",
+            "<img src='http://tracker.localhost/logo.png'/>",
+            "<img src=\"cid:logo@localhost\">",
+            "<img width=1 height=\"1px\" src=\"https://example.com/pixel.gif\">",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            "<img src=\"cid:logo@localhost\">"
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new()
+                .with_allowed_remote_hosts(["example.com"])
+                .sanitize(html),
+            concat!(
+                "<img src=\"https://img.example.com/logo.png\" alt=logo>",
+                "<img src=\"cid:logo@localhost\">",
+            )
+        );
+    }
+
+    #[test]
+    fn strip_encoded_script_urls() {
+        let html = concat!(
+            "<a href=\"&#106;avascript:alert(1)\">a</a>",
+            "<a href=\"java&#x09;script:alert(1)\">b</a>",
+            "<a href=\"javascript&colon;alert(1)\">c</a>",
+            "<a href=\"&#x6A&#x61vascript:alert(1)\">d</a>",
+            "<a href=\"vbscript:msgbox(1)\">e</a>",
+            "<a href=\"data:text/html,<script>alert(1)</script>\">f</a>",
+            "<a href=\"https://localhost\">g</a>",
+            "<a href=\"mailto:alice@localhost\">h</a>",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            concat!(
+                "<a>a</a><a>b</a><a>c</a><a>d</a><a>e</a><a>f</a>",
+                "<a href=\"https://localhost\">g</a>",
+                "<a href=\"mailto:alice@localhost\">h</a>",
+            )
+        );
+    }
+
+    #[test]
+    fn strip_remote_content_bypasses() {
+        let html = concat!(
+            "<img src=\"\\\\tracker.localhost\\x.png\">",
+            "<img src=\"https:\\\\tracker.localhost/x.png\">",
+            "<img src=\"http:tracker.localhost/x.png\">",
+            "<img src=\"&#104;ttps://tracker.localhost/x.png\">",
+            "<img src=\"cid:logo@localhost\" srcset=\"cid:logo@localhost 1x, https://tracker.localhost/x.png 2x\">",
+            "<p style=\"background: url('https://tracker.localhost/x.png')\">styled</p>",
+            "<p style=\"background: u\\72l(https://tracker.localhost/x.png)\">escaped</p>",
+            "<p style=\"color: red\">red</p>",
+            "<style>@import 'https://tracker.localhost/x.css';</style>",
+            "<style>p { color: red; }</style>",
+            "<meta http-equiv=\"refresh\" content=\"0; url=https://tracker.localhost\">",
+            "<form action=\"https://tracker.localhost\"><input name=q></form>",
+            "<table background=\"https://tracker.localhost/x.png\"></table>",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            concat!(
+                "<img src=\"cid:logo@localhost\">",
+                "<p>styled</p>",
+                "<p>escaped</p>",
+                "<p style=\"color: red\">red</p>",
+                "<style>p { color: red; }</style>",
+                "<input name=q>",
+                "<table></table>",
+            )
+        );
+    }
+
+    #[test]
+    fn strip_raw_text_elements() {
+        let sanitizer = HtmlSanitizer::new();
+
+        // browsers do not parse markup inside these elements, so the
+        // attribute value becomes an image once the element closes
+        for name in [
+            "xmp", "noembed", "noframes", "textarea", "title", "noscript", "iframe",
+        ] {
+            let html = format!(
+                "<{name}><p title=\"</{name}><img src=x onerror=alert(1)>\"></p></{name}>after"
+            );
+
+            assert_eq!(
+                sanitizer.sanitize(&html),
+                "<img src=x>\"></p>after",
+                "raw text element <{name}>"
+            );
+        }
+
+        let html = "<XMP>\n<script>alert(1)</script></XmP >after";
+        assert_eq!(sanitizer.sanitize(html), "after");
+
+        // plaintext never ends
+        let html = "before<plaintext><img src=x onerror=alert(1)></plaintext>after";
+        assert_eq!(sanitizer.sanitize(html), "before");
+    }
+
+    #[test]
+    fn strip_style_raw_text() {
+        let html = "<style>p{}</style x=\"<img src=x onerror=alert(1)>\"><p>after</p>";
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            "<style>p{}</style>\"><p>after</p>"
+        );
+    }
+
+    #[test]
+    fn strip_closing_tag_attributes_and_unexpected_names() {
+        let html = concat!(
+            "<p>a</p onmouseover=alert(1)>",
+            "<img \"onerror=alert(1)\" src=cid:logo>",
+            "<a href=\"https://localhost\" ='x'>b</a>",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new().sanitize(html),
+            concat!(
+                "<p>a</p>",
+                "<img src=cid:logo>",
+                "<a href=\"https://localhost\">b</a>",
+            )
+        );
+    }
+
+    #[test]
+    fn match_remote_hosts_after_normalization() {
+        let html = concat!(
+            "<img src=\"https://example.com@tracker.localhost/x.png\">",
+            "<img src=\"\\\\img.example.com\\x.png\">",
+            "<p style=\"background: url(HTTPS://IMG.EXAMPLE.COM/x.png)\">styled</p>",
+        );
+
+        assert_eq!(
+            HtmlSanitizer::new()
+                .with_allowed_remote_hosts(["example.com"])
+                .sanitize(html),
+            concat!(
+                "<img src=\"\\\\img.example.com\\x.png\">",
+                "<p style=\"background: url(HTTPS://IMG.EXAMPLE.COM/x.png)\">styled</p>",
+            )
+        );
+    }
+}
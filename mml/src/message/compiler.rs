@@ -3,11 +3,17 @@
 //! Module dedicated to MML → MIME message compilation.
 
 use mail_builder::{headers::text::Text, MessageBuilder};
-use mail_parser::{Message, MessageParser};
+use mail_parser::{HeaderName, Message};
 
 #[cfg(feature = "pgp")]
 use crate::{message::header, pgp::Pgp};
-use crate::{message::MmlBodyCompiler, Error, Result};
+use crate::{
+    message::{
+        body::{MULTIPART_BEGIN, MULTIPART_END, PART_BEGIN, PART_END},
+        parser, Disposition, MmlBodyCompiler, MmlCompilerLimits,
+    },
+    Error, Result,
+};
 
 /// MML → MIME message compiler builder.
 ///
@@ -51,11 +57,48 @@ pub fn with_some_pgp(mut self, pgp: Option<impl Into<Pgp>>) -> Self {
         self
     }
 
+    /// Customize the disposition of single parts that do not define
+    /// a `disposition` property.
+    pub fn set_default_disposition(&mut self, disposition: Disposition) {
+        self.mml_body_compiler.set_default_disposition(disposition);
+    }
+
+    /// Customize the disposition of single parts that do not define
+    /// a `disposition` property.
+    pub fn with_default_disposition(mut self, disposition: Disposition) -> Self {
+        self.mml_body_compiler.set_default_disposition(disposition);
+        self
+    }
+
+    /// Customize some disposition of single parts that do not define
+    /// a `disposition` property.
+    pub fn set_some_default_disposition(&mut self, disposition: Option<Disposition>) {
+        self.mml_body_compiler
+            .set_some_default_disposition(disposition);
+    }
+
+    /// Customize some disposition of single parts that do not define
+    /// a `disposition` property.
+    pub fn with_some_default_disposition(mut self, disposition: Option<Disposition>) -> Self {
+        self.mml_body_compiler
+            .set_some_default_disposition(disposition);
+        self
+    }
+
+    /// Customize the limits checked before compiling the body.
+    pub fn set_limits(&mut self, limits: MmlCompilerLimits) {
+        self.mml_body_compiler.set_limits(limits);
+    }
+
+    /// Customize the limits checked before compiling the body.
+    pub fn with_limits(mut self, limits: MmlCompilerLimits) -> Self {
+        self.mml_body_compiler.set_limits(limits);
+        self
+    }
+
     /// Build the final [MmlCompiler] based on the defined options.
     pub fn build(self, mml_msg: &str) -> Result<MmlCompiler<'_>> {
-        let mml_msg = MessageParser::new()
-            .parse(mml_msg.as_bytes())
-            .ok_or(Error::ParseMessageError)?;
+        let mml_msg = parser::parse_lenient(mml_msg.as_bytes()).ok_or(Error::ParseMessageError)?;
         let mml_body_compiler = self.mml_body_compiler;
 
         #[cfg(feature = "pgp")]
@@ -86,27 +129,79 @@ impl MmlCompiler<'_> {
     /// The fact to return a intermediate structure allows users to
     /// customize the final form of the desired MIME message.
     pub async fn compile(&self) -> Result<MmlCompileResult<'_>> {
-        let mml_body = self
-            .mml_msg
+        let mml_body = self.mml_body()?;
+        let mml_body_compiler = &self.mml_body_compiler;
+        let mime_msg_builder = mml_body_compiler.compile(mml_body).await?;
+        Ok(self.with_headers(mime_msg_builder))
+    }
+
+    /// Compile the inner MML message, then split the final MIME
+    /// message into its headers and its body.
+    ///
+    /// See [MmlCompileResult::into_parts].
+    pub async fn compile_parts(&self) -> Result<(Vec<(String, String)>, Vec<u8>)> {
+        self.compile().await?.into_parts()
+    }
+
+    /// Compile the inner message into a [MmlCompileResult], without
+    /// interpreting MML.
+    ///
+    /// The whole body is taken verbatim as a single `text/plain`
+    /// part: escaped MML markup like `<#!part>` is not unescaped. An
+    /// error is returned if the body contains unescaped MML
+    /// directives, so they are not sent by accident.
+    pub fn compile_plain(&self) -> Result<MmlCompileResult<'_>> {
+        let body = self.mml_body()?;
+
+        for (n, line) in body.lines().enumerate() {
+            for directive in [PART_BEGIN, PART_END, MULTIPART_BEGIN, MULTIPART_END] {
+                if line.contains(directive) {
+                    let directive = directive.to_owned();
+                    let err = Error::CompilePlainUnexpectedMmlDirectiveError(directive, n + 1);
+                    return Err(err);
+                }
+            }
+        }
+
+        let mime_msg_builder = MessageBuilder::new().text_body(body);
+        Ok(self.with_headers(mime_msg_builder))
+    }
+
+    /// Return the body of the inner MML message.
+    fn mml_body(&self) -> Result<&str> {
+        self.mml_msg
             .text_bodies()
             .next()
             .ok_or(Error::ParseMmlEmptyBodyError)?
             .text_contents()
-            .ok_or(Error::ParseMmlEmptyBodyContentError)?;
-
-        let mml_body_compiler = &self.mml_body_compiler;
-
-        let mut mime_msg_builder = mml_body_compiler.compile(mml_body).await?;
+            .ok_or(Error::ParseMmlEmptyBodyContentError)
+    }
 
-        mime_msg_builder = mime_msg_builder.header("MIME-Version", Text::new("1.0"));
+    /// Add the headers of the inner MML message to the given MIME
+    /// message builder, except the MIME ones.
+    fn with_headers<'a>(&'a self, mime_msg_builder: MessageBuilder<'a>) -> MmlCompileResult<'a> {
+        let mut mime_msg_builder = mime_msg_builder.header("MIME-Version", Text::new("1.0"));
 
         for header in self.mml_msg.headers() {
+            // MIME headers are generated by the body compiler: keeping
+            // the ones of the template (for example the ones of an
+            // interpreted message) would duplicate them, with a stale
+            // multipart boundary
+            if matches!(
+                header.name,
+                HeaderName::MimeVersion
+                    | HeaderName::ContentType
+                    | HeaderName::ContentTransferEncoding
+            ) {
+                continue;
+            }
+
             let key = header.name.as_str();
             let val = super::header::to_builder_val(header);
             mime_msg_builder = mime_msg_builder.header(key, val);
         }
 
-        Ok(MmlCompileResult { mime_msg_builder })
+        MmlCompileResult { mime_msg_builder }
     }
 }
 
@@ -142,6 +237,44 @@ pub fn into_vec(self) -> Result<Vec<u8>> {
             .map_err(Error::CompileMmlMessageToVecError)
     }
 
+    /// Return the final MIME message split into its headers and its
+    /// body.
+    ///
+    /// Headers are the top-level header fields, in order, including
+    /// the MIME ones (`Content-Type` etc.). Values are kept as
+    /// written, encoded and folded. The body is the raw content
+    /// following the blank line. Writing each header as `name: value`
+    /// followed by CRLF, then a CRLF, then the body gives back the
+    /// bytes of [MmlCompileResult::into_vec].
+    pub fn into_parts(self) -> Result<(Vec<(String, String)>, Vec<u8>)> {
+        let msg = self.into_vec()?;
+
+        let (headers, body) = match msg.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(pos) => (&msg[..pos + 2], &msg[pos + 4..]),
+            None => (&msg[..], &[][..]),
+        };
+
+        let mut parts: Vec<(String, String)> = Vec::new();
+
+        for line in String::from_utf8_lossy(headers).split_inclusive("\r\n") {
+            match (line.starts_with([' ', '\t']), parts.last_mut()) {
+                // folded line, which belongs to the previous header
+                (true, Some((_, val))) => {
+                    val.push_str("\r\n");
+                    val.push_str(line.trim_end_matches("\r\n"));
+                }
+                _ => {
+                    let line = line.trim_end_matches("\r\n");
+                    let (key, val) = line.split_once(':').unwrap_or((line, ""));
+                    let val = val.strip_prefix(' ').unwrap_or(val);
+                    parts.push((key.to_owned(), val.to_owned()));
+                }
+            }
+        }
+
+        Ok((parts, body.to_vec()))
+    }
+
     /// Return the final MIME message as a [String].
     pub fn into_string(self) -> Result<String> {
         self.mime_msg_builder
@@ -154,7 +287,7 @@ pub fn into_string(self) -> Result<String> {
 mod tests {
     use concat_with::concat_line;
 
-    use crate::{MimeInterpreterBuilder, MmlCompilerBuilder};
+    use crate::{Error, MimeInterpreterBuilder, MmlCompilerBuilder};
 
     #[tokio::test]
     async fn non_ascii_headers() {
@@ -191,6 +324,61 @@ async fn non_ascii_headers() {
         assert_eq!(mml_msg, expected_mml_msg);
     }
 
+    #[tokio::test]
+    async fn mime_headers_not_duplicated() {
+        let mml = concat_line!(
+            "From: from@localhost",
+            "MIME-Version: 1.0",
+            "Content-Type: multipart/mixed; boundary=stale",
+            "Content-Transfer-Encoding: 7bit",
+            "",
+            "Hello, world!",
+            "",
+        );
+
+        let mml_compiler = MmlCompilerBuilder::new().build(mml).unwrap();
+        let (headers, _) = mml_compiler.compile_parts().await.unwrap();
+
+        for name in ["MIME-Version", "Content-Type"] {
+            let count = headers.iter().filter(|(key, _)| key == name).count();
+            assert_eq!(count, 1, "{name}");
+        }
+
+        assert!(!headers.iter().any(|(_, val)| val.contains("stale")));
+    }
+
+    #[tokio::test]
+    async fn compile_parts() {
+        let mml = concat_line!(
+            "Message-ID: <id@localhost>",
+            "Date: Thu, 1 Jan 1970 00:00:00 +0000",
+            "From: Frȯm <from@localhost>",
+            "To: to@localhost",
+            "Subject: A very long subject, long enough to be folded by the compiler, maybe",
+            "",
+            "Hello, world!",
+            "",
+        );
+
+        let mml_compiler = MmlCompilerBuilder::new().build(mml).unwrap();
+        let msg = mml_compiler.compile().await.unwrap().into_vec().unwrap();
+        let (headers, body) = mml_compiler.compile_parts().await.unwrap();
+
+        assert!(headers.contains(&("MIME-Version".into(), "1.0".into())));
+        assert!(headers.iter().any(|(key, _)| key == "Subject"));
+        assert!(headers.iter().any(|(key, _)| key == "Content-Type"));
+        assert!(body.starts_with(b"Hello, world!"));
+
+        let mut recombined = Vec::new();
+        for (key, val) in headers {
+            recombined.extend(format!("{key}: {val}\r\n").into_bytes());
+        }
+        recombined.extend(b"\r\n");
+        recombined.extend(body);
+
+        assert_eq!(recombined, msg);
+    }
+
     #[tokio::test]
     async fn message_id_with_angles() {
         let mml = concat_line!(
@@ -299,4 +487,81 @@ async fn mml_markup_unescaped() {
 
         assert_eq!(mml_msg, expected_mml_msg);
     }
+
+    #[tokio::test]
+    async fn mml_markup_unescaped_once() {
+        let mml = concat_line!(
+            "From: from@localhost",
+            "To: to@localhost",
+            "Subject: subject",
+            "",
+            "Plain <#!!part> markup.",
+            "<#part type=text/html>",
+            "<p>HTML <#!part> markup.</p>",
+            "<#/part>",
+            "",
+        );
+
+        let mml_compiler = MmlCompilerBuilder::new().build(mml).unwrap();
+        let mime_msg_str = mml_compiler.compile().await.unwrap().into_string().unwrap();
+
+        assert!(mime_msg_str.contains("Plain <#!part> markup."));
+        assert!(mime_msg_str.contains("<p>HTML <#part> markup.</p>"));
+    }
+
+    #[test]
+    fn compile_plain_verbatim() {
+        let mml = concat_line!(
+            "From: from@localhost",
+            "To: to@localhost",
+            "Subject: subject",
+            "",
+            "Is 1 < 2? <b>Yes</b>.",
+            "Escaped markup stays as it is: <#!part>.",
+            "",
+        );
+
+        let mml_compiler = MmlCompilerBuilder::new().build(mml).unwrap();
+        let mime_msg_str = mml_compiler.compile_plain().unwrap().into_string().unwrap();
+
+        assert!(mime_msg_str.contains("Content-Type: text/plain"));
+        assert!(mime_msg_str.contains("Is 1 < 2? <b>Yes</b>."));
+        assert!(mime_msg_str.contains("Escaped markup stays as it is: <#!part>."));
+    }
+
+    #[test]
+    fn compile_plain_unexpected_mml_directive() {
+        let mml = concat_line!(
+            "From: from@localhost",
+            "To: to@localhost",
+            "Subject: subject",
+            "",
+            "Hello!",
+            "<#part type=text/html><h1>Hello!</h1><#/part>",
+            "",
+        );
+
+        let mml_compiler = MmlCompilerBuilder::new().build(mml).unwrap();
+        let err = mml_compiler.compile_plain().unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::CompilePlainUnexpectedMmlDirectiveError(directive, 2) if directive == "<#part"
+        ));
+    }
+
+    #[tokio::test]
+    async fn headerless_template_as_plain_body() {
+        let mml_compiler = MmlCompilerBuilder::new().build("Hello, world!").unwrap();
+        let mime_msg_str = mml_compiler.compile().await.unwrap().into_string().unwrap();
+
+        assert!(mime_msg_str.contains("Content-Type: text/plain"));
+        assert!(mime_msg_str.contains("Hello, world!"));
+    }
+
+    #[test]
+    fn blank_template() {
+        let err = MmlCompilerBuilder::new().build("\n\n").unwrap_err();
+        assert!(matches!(err, Error::ParseMessageError));
+    }
 }
@@ -10,7 +10,7 @@
 use std::borrow::Cow;
 
 pub(super) fn display_value(key: &str, val: &HeaderValue) -> String {
-    match val {
+    let val = match val {
         HeaderValue::Address(Address::List(addrs)) => display_addrs(addrs),
         HeaderValue::Address(Address::Group(groups)) => display_groups(groups),
         HeaderValue::Text(id) if key == "Message-ID" => format!("<{id}>"),
@@ -20,14 +20,38 @@ pub(super) fn display_value(key: &str, val: &HeaderValue) -> String {
         HeaderValue::Text(id) if key == "Content-ID" => format!("<{id}>"),
         HeaderValue::Text(id) if key == "Resent-Message-ID" => format!("<{id}>"),
         HeaderValue::Text(text) => text.to_string(),
+        HeaderValue::TextList(ids) if key == "References" => display_ids(ids),
+        HeaderValue::TextList(ids) if key == "In-Reply-To" => display_ids(ids),
+        HeaderValue::TextList(keywords) if key == "Keywords" => keywords.join(", "),
         HeaderValue::TextList(texts) => display_texts(texts),
         HeaderValue::DateTime(datetime) => datetime.to_rfc822(),
         HeaderValue::ContentType(ctype) => display_content_type(ctype),
         HeaderValue::Received(_) => String::new(),
         HeaderValue::Empty => String::new(),
+    };
+
+    // decoded values may contain line breaks, which would end the
+    // header line of the interpreted message
+    if val.contains(['\r', '\n']) {
+        val.replace("\r\n", " ").replace(['\r', '\n'], " ")
+    } else {
+        val
     }
 }
 
+/// Quote the given display name if it contains special characters
+/// (RFC 5322), so that they are not mistaken for address delimiters.
+fn display_name(name: &str) -> Cow<'_, str> {
+    const SPECIALS: [char; 12] = ['(', ')', '<', '>', '[', ']', ':', ';', '@', '\\', ',', '"'];
+
+    if !name.contains(SPECIALS) {
+        return Cow::Borrowed(name);
+    }
+
+    let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+    Cow::Owned(format!("\"{name}\""))
+}
+
 fn display_addr(addr: &Addr) -> String {
     let email = match &addr.address {
         Some(addr) => addr.to_string(),
@@ -35,7 +59,7 @@ fn display_addr(addr: &Addr) -> String {
     };
 
     match &addr.name {
-        Some(name) => format!("{name} <{email}>"),
+        Some(name) => format!("{} <{email}>", display_name(name)),
         None => email.to_string(),
     }
 }
@@ -52,7 +76,7 @@ fn display_addrs(addrs: &[Addr]) -> String {
 
 fn display_group(group: &Group) -> String {
     let name = match &group.name {
-        Some(name) => name.to_string(),
+        Some(name) => display_name(name).into_owned(),
         None => "unknown".into(),
     };
 
@@ -80,6 +104,16 @@ fn display_texts(texts: &[Cow<str>]) -> String {
     })
 }
 
+fn display_ids(ids: &[Cow<str>]) -> String {
+    ids.iter().fold(String::new(), |mut ids, id| {
+        if !ids.is_empty() {
+            ids.push(' ');
+        }
+        ids.push_str(&format!("<{id}>"));
+        ids
+    })
+}
+
 fn display_content_type(ctype: &ContentType) -> String {
     let attrs = ctype.attributes().unwrap_or_default().iter().fold(
         String::new(),
@@ -145,6 +179,14 @@ pub(crate) fn to_builder_val<'a>(header: &'a Header<'a>) -> HeaderType<'a> {
             HeaderName::ResentMessageId => Text::new(format!("<{text}>")).into(),
             _ => Text::new(text.as_ref()).into(),
         },
+        HeaderValue::TextList(ids)
+            if matches!(header.name, HeaderName::References | HeaderName::InReplyTo) =>
+        {
+            Text::new(display_ids(ids)).into()
+        }
+        HeaderValue::TextList(keywords) if header.name == HeaderName::Keywords => {
+            Text::new(keywords.join(", ")).into()
+        }
         HeaderValue::TextList(texts) => Text::new(texts.join(" ")).into(),
         HeaderValue::DateTime(date) => Date::new(date.to_timestamp()).into(),
         HeaderValue::ContentType(ctype) => {
@@ -212,7 +254,7 @@ pub(super) fn extract_emails(h: Option<&Address>) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
-    use mail_parser::{Addr, ContentType, Group};
+    use mail_parser::{Addr, ContentType, Group, HeaderValue};
 
     #[test]
     fn display_empty_addr() {
@@ -251,6 +293,26 @@ fn display_named_addr() {
         assert_eq!(super::display_addr(&addr), "Test <test@localhost>");
     }
 
+    #[test]
+    fn display_quoted_addr() {
+        let addr = Addr {
+            name: Some("Doe, John".into()),
+            address: Some("test@localhost".into()),
+        };
+
+        assert_eq!(super::display_addr(&addr), "\"Doe, John\" <test@localhost>");
+
+        let addr = Addr {
+            name: Some("Jean \"JJ\" Dupont".into()),
+            address: Some("test@localhost".into()),
+        };
+
+        assert_eq!(
+            super::display_addr(&addr),
+            "\"Jean \\\"JJ\\\" Dupont\" <test@localhost>"
+        );
+    }
+
     #[test]
     fn display_addrs() {
         let addrs = [
@@ -347,6 +409,22 @@ fn display_texts() {
         assert_eq!(super::display_texts(&texts), "test test test");
     }
 
+    #[test]
+    fn display_ids() {
+        let ids = HeaderValue::TextList(vec!["a@localhost".into(), "b@localhost".into()]);
+
+        assert_eq!(
+            super::display_value("References", &ids),
+            "<a@localhost> <b@localhost>"
+        );
+    }
+
+    #[test]
+    fn display_text_with_line_breaks() {
+        let text = HeaderValue::Text("multi\r\nline\nsubject".into());
+        assert_eq!(super::display_value("Subject", &text), "multi line subject");
+    }
+
     #[test]
     fn display_subtypeless_content_type() {
         let ctype = ContentType {
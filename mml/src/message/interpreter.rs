@@ -3,17 +3,17 @@
 //! Module dedicated to MIME → MML message interpretation.
 
 use mail_builder::MessageBuilder;
-use mail_parser::{Message, MessageParser};
+use mail_parser::Message;
 use std::path::PathBuf;
 
 #[cfg(feature = "pgp")]
 use crate::pgp::Pgp;
 use crate::{
-    message::{FilterParts, MimeBodyInterpreter},
+    message::{FilterParts, HtmlSanitizer, MimeBodyInterpreter},
     Error, Result,
 };
 
-use super::header;
+use super::{header, parser};
 
 /// Filters headers to show in the interpreted message.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -47,6 +47,22 @@ pub struct MimeInterpreterBuilder {
 
     /// The internal MIME to MML message body interpreter.
     mime_body_interpreter: MimeBodyInterpreter,
+
+    /// Show the PGP signatures verification outcome.
+    ///
+    /// When `true`, a synthetic `X-Signature` header is added to the
+    /// interpreted message for each `multipart/signed` part, for
+    /// example `X-Signature: valid; signed-by=alice@localhost`.
+    #[cfg(feature = "pgp")]
+    show_signature_status: bool,
+
+    /// Show the PGP decryption outcome.
+    ///
+    /// When `true`, a synthetic `X-Decryption` header is added to
+    /// the interpreted message for each `multipart/encrypted` part,
+    /// for example `X-Decryption: decrypted`.
+    #[cfg(feature = "pgp")]
+    show_decryption_status: bool,
 }
 
 impl MimeInterpreterBuilder {
@@ -188,6 +204,23 @@ pub fn with_save_some_attachments_dir(self, dir: Option<impl Into<PathBuf>>) ->
         }
     }
 
+    /// Sanitize HTML parts before interpreting them.
+    ///
+    /// Scripts, remote images and tracking pixels are removed. Use
+    /// [`MimeInterpreterBuilder::with_html_sanitizer`] to allow
+    /// remote content from some hosts.
+    pub fn with_sanitize_html(mut self, b: bool) -> Self {
+        self.mime_body_interpreter = self.mime_body_interpreter.with_sanitize_html(b);
+        self
+    }
+
+    /// Sanitize HTML parts before interpreting them, using the given
+    /// sanitizer.
+    pub fn with_html_sanitizer(mut self, sanitizer: HtmlSanitizer) -> Self {
+        self.mime_body_interpreter = self.mime_body_interpreter.with_html_sanitizer(sanitizer);
+        self
+    }
+
     /// Customize PGP.
     #[cfg(feature = "pgp")]
     pub fn set_pgp(&mut self, pgp: impl Into<Pgp>) {
@@ -214,6 +247,22 @@ pub fn with_some_pgp(mut self, pgp: Option<impl Into<Pgp>>) -> Self {
         self
     }
 
+    /// Show the PGP signatures verification outcome as synthetic
+    /// `X-Signature` headers.
+    #[cfg(feature = "pgp")]
+    pub fn with_show_signature_status(mut self, b: bool) -> Self {
+        self.show_signature_status = b;
+        self
+    }
+
+    /// Show the PGP decryption outcome as synthetic `X-Decryption`
+    /// headers.
+    #[cfg(feature = "pgp")]
+    pub fn with_show_decryption_status(mut self, b: bool) -> Self {
+        self.show_decryption_status = b;
+        self
+    }
+
     /// Build the final [MimeInterpreter].
     ///
     /// This intermediate step is not necessary for the interpreter,
@@ -222,6 +271,10 @@ pub fn build(self) -> MimeInterpreter {
         MimeInterpreter {
             show_headers: self.show_headers,
             mime_body_interpreter: self.mime_body_interpreter,
+            #[cfg(feature = "pgp")]
+            show_signature_status: self.show_signature_status,
+            #[cfg(feature = "pgp")]
+            show_decryption_status: self.show_decryption_status,
         }
     }
 }
@@ -231,6 +284,10 @@ pub fn build(self) -> MimeInterpreter {
 pub struct MimeInterpreter {
     show_headers: FilterHeaders,
     mime_body_interpreter: MimeBodyInterpreter,
+    #[cfg(feature = "pgp")]
+    show_signature_status: bool,
+    #[cfg(feature = "pgp")]
+    show_decryption_status: bool,
 }
 
 impl MimeInterpreter {
@@ -262,10 +319,6 @@ pub async fn from_msg(self, msg: &Message<'_>) -> Result<String> {
                 }),
         };
 
-        if !mml.is_empty() {
-            mml.push('\n');
-        }
-
         let mime_body_interpreter = self.mime_body_interpreter;
 
         #[cfg(feature = "pgp")]
@@ -273,7 +326,25 @@ pub async fn from_msg(self, msg: &Message<'_>) -> Result<String> {
             .with_pgp_sender(header::extract_first_email(msg.from()))
             .with_pgp_recipient(header::extract_first_email(msg.to()));
 
-        let mml_body = mime_body_interpreter.interpret_msg(msg).await?;
+        let (mml_body, _status) = mime_body_interpreter.interpret_msg_with_status(msg).await?;
+
+        #[cfg(feature = "pgp")]
+        if self.show_decryption_status {
+            for decryption in _status.decryptions {
+                mml.push_str(&format!("X-Decryption: {decryption}\n"));
+            }
+        }
+
+        #[cfg(feature = "pgp")]
+        if self.show_signature_status {
+            for signature in _status.signatures {
+                mml.push_str(&format!("X-Signature: {signature}\n"));
+            }
+        }
+
+        if !mml.is_empty() {
+            mml.push('\n');
+        }
 
         mml.push_str(&mml_body);
 
@@ -282,9 +353,7 @@ pub async fn from_msg(self, msg: &Message<'_>) -> Result<String> {
 
     /// Interpret the given MIME message bytes as a MML [String].
     pub async fn from_bytes(self, bytes: impl AsRef<[u8]>) -> Result<String> {
-        let msg = MessageParser::new()
-            .parse(bytes.as_ref())
-            .ok_or(Error::ParseRawEmailError)?;
+        let msg = parser::parse_lenient(bytes.as_ref()).ok_or(Error::ParseRawEmailError)?;
         self.from_msg(&msg).await
     }
 
@@ -418,4 +487,69 @@ async fn mml_markup_escaped() {
 
         assert_eq!(mml, expected_mml);
     }
+
+    #[tokio::test]
+    async fn mml_markup_escaped_twice() {
+        let msg_builder = MessageBuilder::new()
+            .message_id("id@localhost")
+            .date(0_u64)
+            .from("from@localhost")
+            .to("to@localhost")
+            .subject("subject")
+            .text_body("Literal escaped markup: <#!part>.");
+
+        let mml = MimeInterpreterBuilder::new()
+            .with_show_only_headers(["From", "Subject"])
+            .build()
+            .from_msg_builder(msg_builder)
+            .await
+            .unwrap();
+
+        let expected_mml = concat_line!(
+            "From: from@localhost",
+            "Subject: subject",
+            "",
+            "Literal escaped markup: <#!!part>.",
+        );
+
+        assert_eq!(mml, expected_mml);
+    }
+
+    #[tokio::test]
+    async fn mbox_message_with_bom() {
+        let msg = concat_line!(
+            "\u{feff}From from@localhost Thu Jan  1 00:00:00 1970",
+            "From: from@localhost",
+            "Subject: subject",
+            "",
+            "Hello, world!",
+        );
+
+        let mml = MimeInterpreterBuilder::new()
+            .with_show_only_headers(["From", "Subject"])
+            .build()
+            .from_bytes(msg)
+            .await
+            .unwrap();
+
+        let expected_mml = concat_line!(
+            "From: from@localhost",
+            "Subject: subject",
+            "",
+            "Hello, world!",
+        );
+
+        assert_eq!(mml.trim_end(), expected_mml);
+    }
+
+    #[tokio::test]
+    async fn headerless_message_as_plain_body() {
+        let mml = MimeInterpreterBuilder::new()
+            .build()
+            .from_bytes("Hello, world!")
+            .await
+            .unwrap();
+
+        assert_eq!(mml.trim_end(), "Hello, world!");
+    }
 }
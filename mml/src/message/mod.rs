@@ -18,16 +18,20 @@
 pub(crate) mod header;
 #[cfg(feature = "interpreter")]
 pub mod interpreter;
+pub(crate) mod parser;
 
+#[cfg(all(feature = "interpreter", feature = "pgp"))]
+#[doc(inline)]
+pub use self::body::{DecryptionStatus, SignatureStatus};
 #[cfg(feature = "compiler")]
 #[doc(inline)]
 pub use self::{
-    body::MmlBodyCompiler,
+    body::{Disposition, MmlBodyCompiler, MmlCompilerLimits},
     compiler::{MmlCompileResult, MmlCompiler, MmlCompilerBuilder},
 };
 #[cfg(feature = "interpreter")]
 #[doc(inline)]
 pub use self::{
-    body::{FilterParts, MimeBodyInterpreter},
+    body::{FilterParts, HtmlSanitizer, InterpretStatus, MimeBodyInterpreter},
     interpreter::{FilterHeaders, MimeInterpreter, MimeInterpreterBuilder},
 };
@@ -0,0 +1,88 @@
+//! # Message parser internal module
+//!
+//! This module contains a lenient wrapper around the
+//! [mail_parser::MessageParser], so that real-world messages (and
+//! templates) never fail to parse.
+
+use mail_parser::{Message, MessageParser};
+use tracing::debug;
+
+/// The UTF-8 byte order mark, added by some editors.
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Parse the given raw message leniently.
+///
+/// A leading UTF-8 byte order mark and a leading mbox `From ` line
+/// are skipped. When the message still cannot be parsed, or when
+/// nothing usable is found (neither headers nor body), the whole
+/// input is taken as the `text/plain` body of a message without
+/// headers.
+///
+/// Returns `None` only when the input is blank.
+pub(crate) fn parse_lenient(bytes: &[u8]) -> Option<Message<'_>> {
+    let bytes = bytes.strip_prefix(BOM).unwrap_or(bytes);
+    let bytes = skip_mbox_from_line(bytes);
+
+    if bytes.iter().all(u8::is_ascii_whitespace) {
+        return None;
+    }
+
+    let parser = MessageParser::new();
+
+    match parser.parse(bytes) {
+        Some(msg) if !msg.headers().is_empty() || msg.body_text(0).is_some() => Some(msg),
+        _ => {
+            debug!("cannot parse message, taking it as a text/plain body");
+            let mut body = b"\r\n".to_vec();
+            body.extend_from_slice(bytes);
+            parser.parse(&body).map(Message::into_owned)
+        }
+    }
+}
+
+/// Skip the leading mbox `From ` line of the given raw message, if
+/// any.
+fn skip_mbox_from_line(bytes: &[u8]) -> &[u8] {
+    if !bytes.starts_with(b"From ") {
+        return bytes;
+    }
+
+    match bytes.iter().position(|b| *b == b'\n') {
+        Some(pos) => &bytes[pos + 1..],
+        None => bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use concat_with::concat_line;
+
+    use super::parse_lenient;
+
+    #[test]
+    fn parse_mbox_message_with_bom() {
+        let msg = concat_line!(
+            "\u{feff}From alice@localhost Thu Jan  1 00:00:00 1970",
+            "From: alice@localhost",
+            "Subject: subject",
+            "",
+            "Hello, world!",
+        );
+        let msg = parse_lenient(msg.as_bytes()).unwrap();
+
+        assert_eq!(msg.subject(), Some("subject"));
+        assert_eq!(msg.body_text(0).unwrap().trim_end(), "Hello, world!");
+    }
+
+    #[test]
+    fn parse_headerless_message_as_body() {
+        let msg = parse_lenient(b"Hello, world!").unwrap();
+        assert!(msg.headers().is_empty());
+        assert_eq!(msg.body_text(0).unwrap().trim_end(), "Hello, world!");
+    }
+
+    #[test]
+    fn parse_empty_message() {
+        assert!(parse_lenient(b"").is_none());
+    }
+}
@@ -78,12 +78,31 @@ pub fn default_verify_cmd() -> Command {
         Command::new("gpg --verify --quiet")
     }
 
-    /// Encrypts the given plain bytes using the given recipients.
-    pub async fn encrypt(
-        &self,
-        recipients: impl IntoIterator<Item = String>,
-        plain_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>> {
+    /// Enables or disables the dry-run mode of all the commands.
+    ///
+    /// Commands are logged instead of being executed, and fail with
+    /// [`process::Error::DryRunError`] holding the command that would
+    /// run. See [`Command::set_dry_run`].
+    pub fn with_dry_run(self, dry_run: bool) -> Self {
+        let with_dry_run = |cmd: Option<Command>, default: fn() -> Command| {
+            Some(cmd.unwrap_or_else(default).with_dry_run(dry_run))
+        };
+
+        Self {
+            encrypt_cmd: with_dry_run(self.encrypt_cmd, Self::default_encrypt_cmd),
+            decrypt_cmd: with_dry_run(self.decrypt_cmd, Self::default_decrypt_cmd),
+            sign_cmd: with_dry_run(self.sign_cmd, Self::default_sign_cmd),
+            verify_cmd: with_dry_run(self.verify_cmd, Self::default_verify_cmd),
+            ..self
+        }
+    }
+
+    /// Builds the encrypt command for the given recipients.
+    ///
+    /// The `<recipients>` placeholder is replaced by the formatted
+    /// recipients. The command is not executed, see
+    /// [`Command::resolve`] to get the command that would run.
+    pub fn build_encrypt_cmd(&self, recipients: impl IntoIterator<Item = String>) -> Command {
         let recipient_fmt = self
             .encrypt_recipient_fmt
             .clone()
@@ -103,11 +122,20 @@ pub async fn encrypt(
                     recipients_str
                 });
 
-        let res = self
-            .encrypt_cmd
+        self.encrypt_cmd
             .clone()
             .unwrap_or_else(Self::default_encrypt_cmd)
             .replace("<recipients>", recipients_str)
+    }
+
+    /// Encrypts the given plain bytes using the given recipients.
+    pub async fn encrypt(
+        &self,
+        recipients: impl IntoIterator<Item = String>,
+        plain_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let res = self
+            .build_encrypt_cmd(recipients)
             .run_with(plain_bytes)
             .await
             .map_err(Error::EncryptCommandError)?;
@@ -113,7 +113,7 @@ pub async fn verify(&self, signature_bytes: Vec<u8>, signed_bytes: Vec<u8>) -> R
 
         let res = ctx
             .verify_opaque(signature_bytes, signed_bytes)
-            .map_err(Error::SignGpgError)?;
+            .map_err(Error::VerifyGpgError)?;
         trace!("verify result: {res:#?}");
 
         Ok(())
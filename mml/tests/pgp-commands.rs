@@ -5,7 +5,7 @@
 use concat_with::concat_line;
 use mml::{
     pgp::{Pgp, PgpCommands},
-    MimeInterpreterBuilder, MmlCompilerBuilder,
+    Error, MimeInterpreterBuilder, MmlCompilerBuilder,
 };
 use process::Command;
 #[cfg(feature = "tokio")]
@@ -59,3 +59,31 @@ async fn pgp_cmds() {
 
     assert_eq!(mml, expected_mml);
 }
+
+#[test_log::test(test)]
+async fn pgp_cmds_dry_run() {
+    let pgp = PgpCommands::default().with_dry_run(true);
+
+    let cmd = pgp.build_encrypt_cmd(["alice@localhost".into(), "bob@localhost".into()]);
+    assert!(cmd.is_dry_run());
+    assert_eq!(
+        cmd.resolve().to_string(),
+        "gpg --encrypt --quiet --armor --recipient alice@localhost --recipient bob@localhost",
+    );
+
+    // dry-run commands are not spawned, and never hand back their
+    // input as encrypted output
+    match pgp
+        .encrypt(["alice@localhost".into()], b"plain".to_vec())
+        .await
+        .unwrap_err()
+    {
+        Error::EncryptCommandError(process::Error::DryRunError(cmd)) => {
+            assert_eq!(
+                cmd.to_string(),
+                "gpg --encrypt --quiet --armor --recipient alice@localhost",
+            );
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
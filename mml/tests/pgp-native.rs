@@ -6,7 +6,7 @@
 use async_std::test;
 use concat_with::concat_line;
 use mml::{
-    pgp::{NativePgpPublicKeysResolver, NativePgpSecretKey, Pgp, PgpNative},
+    pgp::{NativePgpPublicKeysResolver, NativePgpSecretKey, Pgp, PgpNative, SignedPublicKey},
     MimeInterpreterBuilder, MmlCompilerBuilder,
 };
 use pgp::gen_key_pair;
@@ -132,3 +132,169 @@ async fn spawn_fake_key_server(pkeys: HashMap<String, String>) -> String {
 
     assert_eq!(mml, expected_mml);
 }
+
+#[test_log::test(test)]
+async fn pgp_native_signature_status() {
+    let (alice_skey, alice_pkey) = gen_key_pair("alice@localhost", "").await.unwrap();
+    let (_, bob_pkey) = gen_key_pair("bob@localhost", "").await.unwrap();
+
+    let mml = concat_line!(
+        "From: alice@localhost",
+        "To: bob@localhost",
+        "Subject: subject",
+        "",
+        "<#part type=text/plain sign=pgpmime>",
+        "Signed message!",
+        "<#/part>",
+    );
+
+    let mml_compiler = MmlCompilerBuilder::new()
+        .with_pgp(Pgp::Native(PgpNative {
+            secret_key: NativePgpSecretKey::Raw(alice_skey),
+            secret_key_passphrase: Secret::new_raw(""),
+            public_keys_resolvers: vec![],
+        }))
+        .build(mml)
+        .unwrap();
+    let msg_builder = mml_compiler.compile().await.unwrap().into_msg_builder();
+
+    let interpret = |pkeys: Vec<(&str, SignedPublicKey)>| {
+        let msg_builder = msg_builder.clone();
+        async move {
+            MimeInterpreterBuilder::new()
+                .with_show_only_headers(["From", "Subject"])
+                .with_show_signature_status(true)
+                .with_pgp(Pgp::Native(PgpNative {
+                    secret_key: NativePgpSecretKey::None,
+                    secret_key_passphrase: Secret::new_raw(""),
+                    public_keys_resolvers: pkeys
+                        .into_iter()
+                        .map(|(email, pkey)| NativePgpPublicKeysResolver::Raw(email.into(), pkey))
+                        .collect(),
+                }))
+                .build()
+                .from_msg_builder(msg_builder)
+                .await
+                .unwrap()
+        }
+    };
+
+    // the public key matches the signer
+
+    let mml = interpret(vec![("alice@localhost", alice_pkey)]).await;
+
+    let expected_mml = concat_line!(
+        "From: alice@localhost",
+        "Subject: subject",
+        "X-Signature: valid; signed-by=alice@localhost",
+        "",
+        "Signed message!",
+        ""
+    );
+
+    assert_eq!(mml, expected_mml);
+
+    // the public key does not match the signer
+
+    let mml = interpret(vec![("alice@localhost", bob_pkey)]).await;
+
+    assert!(mml.contains("X-Signature: invalid; signed-by=alice@localhost\n"));
+    assert!(mml.ends_with("Signed message!\n"));
+
+    // the public key of the signer cannot be found
+
+    let mml = interpret(vec![]).await;
+
+    assert!(mml.contains("X-Signature: missing-key; signed-by=alice@localhost\n"));
+    assert!(mml.ends_with("Signed message!\n"));
+}
+
+#[test_log::test(test)]
+async fn pgp_native_decryption_status() {
+    let (alice_skey, _) = gen_key_pair("alice@localhost", "").await.unwrap();
+    let (bob_skey, bob_pkey) = gen_key_pair("bob@localhost", "").await.unwrap();
+
+    let mml = concat_line!(
+        "From: alice@localhost",
+        "To: bob@localhost",
+        "Subject: subject",
+        "",
+        "<#part type=text/plain encrypt=pgpmime>",
+        "Encrypted message!",
+        "<#/part>",
+    );
+
+    let mml_compiler = MmlCompilerBuilder::new()
+        .with_pgp(Pgp::Native(PgpNative {
+            secret_key: NativePgpSecretKey::None,
+            secret_key_passphrase: Secret::new_raw(""),
+            public_keys_resolvers: vec![NativePgpPublicKeysResolver::Raw(
+                "bob@localhost".into(),
+                bob_pkey,
+            )],
+        }))
+        .build(mml)
+        .unwrap();
+    let msg_builder = mml_compiler.compile().await.unwrap().into_msg_builder();
+
+    let interpret = |secret_key: NativePgpSecretKey| {
+        let msg_builder = msg_builder.clone();
+        async move {
+            MimeInterpreterBuilder::new()
+                .with_show_only_headers(["From", "Subject"])
+                .with_show_decryption_status(true)
+                .with_pgp(Pgp::Native(PgpNative {
+                    secret_key,
+                    secret_key_passphrase: Secret::new_raw(""),
+                    public_keys_resolvers: vec![],
+                }))
+                .build()
+                .from_msg_builder(msg_builder)
+                .await
+                .unwrap()
+        }
+    };
+
+    // the secret key matches the recipient
+
+    let mml = interpret(NativePgpSecretKey::Raw(bob_skey)).await;
+
+    let expected_mml = concat_line!(
+        "From: alice@localhost",
+        "Subject: subject",
+        "X-Decryption: decrypted",
+        "",
+        "Encrypted message!",
+        ""
+    );
+
+    assert_eq!(mml, expected_mml);
+
+    // the secret key does not match the recipient
+
+    let mml = interpret(NativePgpSecretKey::Raw(alice_skey)).await;
+
+    let expected_mml = concat_line!(
+        "From: alice@localhost",
+        "Subject: subject",
+        "X-Decryption: failed; recipient=bob@localhost",
+        "",
+        "",
+    );
+
+    assert_eq!(mml, expected_mml);
+
+    // the secret key of the recipient cannot be found
+
+    let mml = interpret(NativePgpSecretKey::None).await;
+
+    let expected_mml = concat_line!(
+        "From: alice@localhost",
+        "Subject: subject",
+        "X-Decryption: missing-key; recipient=bob@localhost",
+        "",
+        "",
+    );
+
+    assert_eq!(mml, expected_mml);
+}
@@ -0,0 +1,249 @@
+#![cfg(all(feature = "compiler", feature = "interpreter"))]
+
+//! Round-trip property tests.
+//!
+//! Messages with varied headers, bodies and attachments are generated
+//! by [`proptest`], interpreted as MML then compiled back to MIME. The
+//! compiled message must preserve the headers, the body text and the
+//! attachments of the original one. Failing cases are shrunk, and
+//! persisted by proptest so that they are replayed first.
+
+use std::{future::Future, path::Path};
+
+use mail_builder::{headers::address::Address, MessageBuilder};
+use mail_parser::{Message, MessageParser};
+use mml::{MimeInterpreterBuilder, MmlCompilerBuilder};
+use proptest::{collection::vec, option, prelude::*, sample::select, test_runner::TestCaseError};
+use tempfile::tempdir;
+
+const CASES: u32 = 200;
+
+const NAMES: [Option<&str>; 7] = [
+    None,
+    Some("Alice"),
+    Some("Bob Martin"),
+    Some("Doe, John"),
+    Some("Jean \"JJ\" Dupont"),
+    Some("Élodie Lefèvre"),
+    Some("Support (team)"),
+];
+
+const EMAILS: [&str; 4] = [
+    "alice@localhost",
+    "bob.martin@example.org",
+    "john+doe@localhost",
+    "e.lefevre@example.fr",
+];
+
+const SUBJECT_WORDS: [&str; 9] = [
+    "Hello",
+    "world",
+    "réunion",
+    "demain",
+    "<#part>",
+    "Re:",
+    "ünïcödé",
+    "=",
+    "a-very-long-word-which-forces-the-subject-header-to-be-folded",
+];
+
+const FIRST_LINES: [&str; 3] = ["Hello, world!", "Ça va ? Très bien.", "Hi,"];
+
+const LINES: [&str; 9] = [
+    "Hello, world!",
+    "",
+    "> quoted line",
+    "<#part type=text/html>",
+    "<#/part>",
+    "<#!multipart>",
+    "<#!!/part>",
+    "Ça va ? Très bien.",
+    "A line long enough to be wrapped by the quoted-printable encoding, if the builder decides to use it for this part.",
+];
+
+/// A generated address, as an optional name and an email.
+type Addr = (Option<&'static str>, &'static str);
+
+/// A generated message.
+///
+/// Messages are generated as plain data, so that proptest can report
+/// and shrink failing cases, then built with [`Msg::to_vec`].
+#[derive(Clone, Debug)]
+struct Msg {
+    id: u64,
+    from: Addr,
+    to: Vec<Addr>,
+    cc: Option<Vec<Addr>>,
+    subject: Vec<&'static str>,
+    references: usize,
+    first_line: &'static str,
+    lines: Vec<&'static str>,
+    attachments: Vec<Vec<u8>>,
+}
+
+impl Msg {
+    fn to_vec(&self) -> Vec<u8> {
+        let id = self.id;
+
+        let mut builder = MessageBuilder::new()
+            .message_id(format!("{id}@localhost"))
+            .date(id * 86_400)
+            .from(to_address(self.from))
+            .to(to_addresses(&self.to))
+            .subject(self.subject.join(" "));
+
+        if let Some(cc) = &self.cc {
+            builder = builder.cc(to_addresses(cc));
+        }
+
+        if self.references > 0 {
+            let ids: Vec<_> = (0..self.references)
+                .map(|n| format!("{id}.{n}@localhost"))
+                .collect();
+            builder = builder
+                .in_reply_to(ids.last().unwrap().clone())
+                .references(ids);
+        }
+
+        let mut body = format!("{}\n", self.first_line);
+        for line in &self.lines {
+            body.push_str(line);
+            body.push('\n');
+        }
+
+        builder = builder.text_body(body);
+
+        for (n, data) in self.attachments.iter().enumerate() {
+            let name = format!("file-{n}.bin");
+            builder = builder.attachment("application/octet-stream", name, data.clone());
+        }
+
+        builder.write_to_vec().unwrap()
+    }
+}
+
+fn to_address((name, email): Addr) -> Address<'static> {
+    Address::new_address(name, email)
+}
+
+fn to_addresses(addrs: &[Addr]) -> Address<'static> {
+    Address::new_list(addrs.iter().copied().map(to_address).collect())
+}
+
+fn address() -> impl Strategy<Value = Addr> {
+    (select(NAMES.to_vec()), select(EMAILS.to_vec()))
+}
+
+prop_compose! {
+    fn msg()(
+        id in 1..100_000u64,
+        from in address(),
+        to in vec(address(), 1..=3),
+        cc in option::of(vec(address(), 1..=3)),
+        subject in vec(select(SUBJECT_WORDS.to_vec()), 1..=20),
+        references in 0..=3usize,
+        first_line in select(FIRST_LINES.to_vec()),
+        lines in vec(select(LINES.to_vec()), 0..10),
+        attachments in vec(vec(any::<u8>(), 1..=64), 0..3),
+    ) -> Msg {
+        Msg {
+            id,
+            from,
+            to,
+            cc,
+            subject,
+            references,
+            first_line,
+            lines,
+            attachments,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+fn block_on<F: Future>(fut: F) -> F::Output {
+    tokio::runtime::Runtime::new().unwrap().block_on(fut)
+}
+
+#[cfg(feature = "async-std")]
+fn block_on<F: Future>(fut: F) -> F::Output {
+    async_std::task::block_on(fut)
+}
+
+async fn round_trip(msg: &[u8], attachments_dir: &Path) -> Vec<u8> {
+    let mml = MimeInterpreterBuilder::new()
+        .with_show_all_headers()
+        .with_save_attachments(true)
+        .with_save_attachments_dir(attachments_dir)
+        .build()
+        .from_bytes(msg)
+        .await
+        .unwrap();
+
+    MmlCompilerBuilder::new()
+        .build(&mml)
+        .unwrap()
+        .compile()
+        .await
+        .unwrap()
+        .into_vec()
+        .unwrap()
+}
+
+fn body_text(msg: &Message) -> Option<String> {
+    let text = msg.body_text(0)?;
+    Some(text.replace("\r\n", "\n").trim_end().to_owned())
+}
+
+fn attachments(msg: &Message) -> Vec<(Option<String>, Vec<u8>)> {
+    msg.attachments()
+        .map(|part| {
+            let name = part.attachment_name().map(ToOwned::to_owned);
+            (name, part.contents().to_vec())
+        })
+        .collect()
+}
+
+fn check_round_trip(msg: &Msg) -> Result<(), TestCaseError> {
+    let dir = tempdir().unwrap();
+
+    let msg = msg.to_vec();
+    let compiled = block_on(round_trip(&msg, dir.path()));
+
+    let msg = MessageParser::new().parse(&msg).unwrap();
+    let compiled = MessageParser::new().parse(&compiled).unwrap();
+
+    for name in [
+        "Message-ID",
+        "Date",
+        "From",
+        "To",
+        "Cc",
+        "Subject",
+        "In-Reply-To",
+        "References",
+    ] {
+        prop_assert_eq!(compiled.header(name), msg.header(name), "header {}", name);
+    }
+
+    let ctypes = compiled
+        .headers()
+        .iter()
+        .filter(|header| header.name.as_str() == "Content-Type")
+        .count();
+    prop_assert_eq!(ctypes, 1, "duplicated Content-Type");
+
+    prop_assert_eq!(body_text(&compiled), body_text(&msg), "body");
+    prop_assert_eq!(attachments(&compiled), attachments(&msg), "attachments");
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn round_trip_preserves_messages(msg in msg()) {
+        check_round_trip(&msg)?;
+    }
+}
@@ -0,0 +1,169 @@
+//! # Armor
+//!
+//! Module dedicated to the PGP ASCII armor (RFC 4880 §6). This module
+//! exposes helpers to detect armored data, to remove the armor
+//! ([`dearmor`]) and to add it ([`enarmor`]), on top of the armor
+//! reader and writer of rPGP.
+
+use std::io::{Cursor, Read, Write};
+
+use crate::{
+    native::{self, armor, ser::Serialize},
+    Error, Result,
+};
+
+/// The type of an armored block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockType {
+    Message,
+    PublicKey,
+    PrivateKey,
+    Signature,
+}
+
+impl From<BlockType> for armor::BlockType {
+    fn from(block_type: BlockType) -> Self {
+        match block_type {
+            BlockType::Message => Self::Message,
+            BlockType::PublicKey => Self::PublicKey,
+            BlockType::PrivateKey => Self::PrivateKey,
+            BlockType::Signature => Self::Signature,
+        }
+    }
+}
+
+/// Returns `true` if the given data looks like armored data.
+///
+/// Leading whitespaces are ignored.
+pub fn is_armored(data: &[u8]) -> bool {
+    data.trim_ascii_start().starts_with(b"-----BEGIN PGP ")
+}
+
+/// Removes the armor of the given data.
+///
+/// Armor headers (`Version`, `Comment`…) are skipped, and the
+/// checksum is verified when present. Data that is not armored is
+/// returned as is, so that callers can accept both forms.
+pub fn dearmor(data: &[u8]) -> Result<Vec<u8>> {
+    if !is_armored(data) {
+        return Ok(data.to_vec());
+    }
+
+    let mut bytes = Vec::new();
+    armor::Dearmor::new(Cursor::new(data.trim_ascii_start()))
+        .read_to_end(&mut bytes)
+        .map_err(Error::DearmorError)?;
+
+    Ok(bytes)
+}
+
+/// Adds the armor of the given block type to the given binary data.
+pub fn enarmor(data: &[u8], block_type: BlockType) -> Result<String> {
+    let mut armored = Vec::new();
+    armor::write(&RawBytes(data), block_type.into(), &mut armored, None)
+        .map_err(Error::EnarmorError)?;
+
+    // the armor is pure ASCII
+    Ok(String::from_utf8_lossy(&armored).into_owned())
+}
+
+/// Binary data serialized as is, so that it can be armored by
+/// [`armor::write`].
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn to_writer<W: Write>(&self, writer: &mut W) -> native::errors::Result<()> {
+        writer.write_all(self.0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use concat_with::concat_line;
+
+    use super::{dearmor, enarmor, is_armored, BlockType};
+    use crate::{
+        gen_key_pair,
+        native::{ser::Serialize, Deserializable, SignedPublicKey},
+        Error,
+    };
+
+    #[test_log::test(test)]
+    async fn binary_to_armored_to_binary() {
+        let (_, pkey) = gen_key_pair("test@localhost", "").await.unwrap();
+        let binary = pkey.to_bytes().unwrap();
+        assert!(!is_armored(&binary));
+
+        let armored = enarmor(&binary, BlockType::PublicKey).unwrap();
+        assert!(is_armored(armored.as_bytes()));
+        assert!(armored.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+        assert!(armored
+            .trim_end()
+            .ends_with("-----END PGP PUBLIC KEY BLOCK-----"));
+
+        // the armor is readable by rPGP
+        let (parsed, _) = SignedPublicKey::from_string(&armored).unwrap();
+        assert_eq!(parsed, pkey);
+
+        assert_eq!(dearmor(armored.as_bytes()).unwrap(), binary);
+    }
+
+    #[test_log::test(test)]
+    async fn armored_to_binary() {
+        let (_, pkey) = gen_key_pair("test@localhost", "").await.unwrap();
+        let armored = pkey.to_armored_string(None).unwrap();
+        assert!(is_armored(armored.as_bytes()));
+
+        let binary = dearmor(armored.as_bytes()).unwrap();
+        assert_eq!(SignedPublicKey::from_bytes(&binary[..]).unwrap(), pkey);
+    }
+
+    #[test_log::test(test)]
+    async fn dearmor_non_armored() {
+        assert!(!is_armored(b"plain text"));
+        assert!(!is_armored(b"-----BEGIN CERTIFICATE-----"));
+        assert_eq!(dearmor(b"plain text").unwrap(), b"plain text");
+    }
+
+    #[test_log::test(test)]
+    async fn dearmor_without_armor_headers() {
+        let armored = concat_line!(
+            "-----BEGIN PGP MESSAGE-----",
+            "ZGF0YQ==",
+            "=dZTH",
+            "-----END PGP MESSAGE-----",
+        );
+        assert_eq!(dearmor(armored.as_bytes()).unwrap(), b"data");
+
+        let armored = concat_line!(
+            "-----BEGIN PGP MESSAGE-----",
+            "Comment: no blank line after armor headers",
+            "ZGF0YQ==",
+            "=dZTH",
+            "-----END PGP MESSAGE-----",
+        );
+        assert_eq!(dearmor(armored.as_bytes()).unwrap(), b"data");
+    }
+
+    #[test_log::test(test)]
+    async fn dearmor_invalid() {
+        let armored = concat_line!(
+            "-----BEGIN PGP MESSAGE-----",
+            "",
+            "ZGF0YQ==",
+            "=dZTH",
+            "-----END PGP MESSAGE-----",
+        );
+        assert_eq!(dearmor(armored.as_bytes()).unwrap(), b"data");
+
+        let corrupted = armored.replace("ZGF0YQ==", "ZGF1YQ==");
+        let err = dearmor(corrupted.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::DearmorError(_)));
+    }
+}
@@ -3,27 +3,52 @@
 //! Module dedicated to PGP decryption. This module exposes a simple
 //! function [`decrypt`] and its associated [`Error`]s.
 
-use std::io::Cursor;
+use std::{cell::Cell, io::Cursor};
 
 use crate::{
+    armor::is_armored,
     native::{self, Deserializable, Message, SignedSecretKey},
     utils::spawn_blocking,
-    Error, Result,
+    Error, Passphrase, Result,
 };
 
 /// Decrypts bytes using the given secret key and its passphrase.
+///
+/// The encrypted bytes can either be armored or binary.
+///
+/// The passphrase is only resolved if the key needs to be unlocked,
+/// see [`Passphrase`].
 pub async fn decrypt(
     skey: SignedSecretKey,
-    passphrase: impl ToString,
+    passphrase: impl Into<Passphrase>,
     encrypted_bytes: Vec<u8>,
 ) -> Result<Vec<u8>> {
-    let passphrase = passphrase.to_string();
+    let passphrase = passphrase.into();
     spawn_blocking(move || {
-        let (msg, _) = Message::from_armor_single(Cursor::new(&encrypted_bytes))
-            .map_err(Error::ImportMessageFromArmorError)?;
-        let (decryptor, _) = msg
-            .decrypt(|| passphrase, &[&skey])
-            .map_err(Error::DecryptMessageError)?;
+        let msg = if is_armored(&encrypted_bytes) {
+            let (msg, _) = Message::from_armor_single(Cursor::new(&encrypted_bytes))
+                .map_err(Error::ImportMessageFromArmorError)?;
+            msg
+        } else {
+            Message::from_bytes(Cursor::new(&encrypted_bytes))
+                .map_err(Error::ImportMessageFromBytesError)?
+        };
+
+        // the passphrase error cannot be returned from the unlock
+        // callback, so it is kept aside
+        let passphrase_err = Cell::new(None);
+        let get_passphrase = || {
+            passphrase.resolve().unwrap_or_else(|err| {
+                passphrase_err.set(Some(err));
+                String::new()
+            })
+        };
+
+        let (decryptor, _) = msg.decrypt(get_passphrase, &[&skey]).map_err(|err| {
+            passphrase_err
+                .take()
+                .unwrap_or(Error::DecryptMessageError(err))
+        })?;
         let msgs = decryptor
             .collect::<native::errors::Result<Vec<_>>>()
             .map_err(Error::DecryptMessageError)?;
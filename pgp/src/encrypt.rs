@@ -6,14 +6,16 @@
 use std::io;
 
 use rand::{thread_rng, CryptoRng, Rng};
+use tracing::warn;
 
 use crate::{
     native::{
         self,
-        crypto::{hash::HashAlgorithm, public_key::PublicKeyAlgorithm},
+        crypto::{hash::HashAlgorithm, public_key::PublicKeyAlgorithm, sym::SymmetricKeyAlgorithm},
         types::{CompressionAlgorithm, KeyId, KeyTrait, Mpi, PublicKeyTrait},
         Message, SignedPublicKey, SignedPublicSubKey,
     },
+    status::{key_status, KeyStatus},
     utils::spawn_blocking,
     Error, Result,
 };
@@ -99,21 +101,109 @@ fn find_pkey_for_encryption(key: &SignedPublicKey) -> Option<SignedPublicKeyOrSu
     }
 }
 
+/// The symmetric algorithms that can be used to encrypt the message,
+/// by order of preference.
+const SUPPORTED_SYM_ALGS: [SymmetricKeyAlgorithm; 3] = [
+    SymmetricKeyAlgorithm::AES256,
+    SymmetricKeyAlgorithm::AES192,
+    SymmetricKeyAlgorithm::AES128,
+];
+
+/// Get the symmetric algorithms preferred by the given public key.
+///
+/// Preferences are read from the self-signatures of the key users.
+/// Returns an empty list if the key does not express any preference.
+fn find_preferred_sym_algs(key: &SignedPublicKey) -> Vec<SymmetricKeyAlgorithm> {
+    key.details
+        .users
+        .iter()
+        .flat_map(|user| user.signatures.iter())
+        .map(|sig| sig.preferred_symmetric_algs())
+        .find(|algs| !algs.is_empty())
+        .map(|algs| algs.to_vec())
+        .unwrap_or_default()
+}
+
+/// Select the symmetric algorithm used to encrypt the message.
+///
+/// Selects the most preferred supported algorithm accepted by all the
+/// given public keys. Keys without preferences are considered to
+/// accept AES-128, which is the algorithm implementations should
+/// support (see RFC 4880 §9.2). Falls back to AES-128 if no common
+/// preference can be found.
+fn select_sym_alg(keys: &[SignedPublicKey]) -> SymmetricKeyAlgorithm {
+    let prefs: Vec<Vec<SymmetricKeyAlgorithm>> = keys.iter().map(find_preferred_sym_algs).collect();
+
+    let accepts = |prefs: &[SymmetricKeyAlgorithm], alg: &SymmetricKeyAlgorithm| {
+        if prefs.is_empty() {
+            *alg == SymmetricKeyAlgorithm::AES128
+        } else {
+            prefs.contains(alg)
+        }
+    };
+
+    // follows the preferences of the first key, then the supported
+    // algorithms order
+    prefs
+        .first()
+        .into_iter()
+        .flatten()
+        .chain(SUPPORTED_SYM_ALGS.iter())
+        .filter(|alg| SUPPORTED_SYM_ALGS.contains(*alg))
+        .find(|alg| prefs.iter().all(|key_prefs| accepts(key_prefs, *alg)))
+        .copied()
+        .unwrap_or(SymmetricKeyAlgorithm::AES128)
+}
+
 /// Encrypts given bytes using the given list of public keys.
+///
+/// Each public key needs to be able to encrypt, either with its
+/// primary key or one of its subkeys. The symmetric algorithm used
+/// to encrypt the message honors the preferences of the keys. The
+/// encrypted bytes are armored.
+///
+/// The plain bytes are entirely held in memory: rPGP cannot encrypt
+/// a literal data packet by chunks. Only detached signatures can be
+/// streamed, see [`sign_detached_stream`].
+///
+/// [`sign_detached_stream`]: crate::sign::sign_detached_stream
 pub async fn encrypt(pkeys: Vec<SignedPublicKey>, plain_bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if pkeys.is_empty() {
+        return Err(Error::EncryptMessageMissingRecipientsError);
+    }
+
+    for pkey in &pkeys {
+        match key_status(pkey) {
+            KeyStatus::Valid => (),
+            status => warn!(
+                ?status,
+                "encrypting using invalid public key {:?}",
+                pkey.key_id()
+            ),
+        }
+    }
+
     spawn_blocking(move || {
         let mut rng = thread_rng();
 
         let msg = Message::new_literal_bytes("", &plain_bytes);
 
-        let pkeys: Vec<SignedPublicKeyOrSubkey> =
-            pkeys.iter().filter_map(find_pkey_for_encryption).collect();
+        let sym_alg = select_sym_alg(&pkeys);
+
+        let pkeys = pkeys
+            .iter()
+            .map(|pkey| {
+                find_pkey_for_encryption(pkey).ok_or_else(|| {
+                    Error::FindPublicKeyForEncryptionError(format!("{:?}", pkey.key_id()))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
         let pkeys_refs: Vec<&SignedPublicKeyOrSubkey> = pkeys.iter().collect();
 
         let encrypted_bytes = msg
             .compress(CompressionAlgorithm::ZLIB)
             .map_err(Error::CompressMessageError)?
-            .encrypt_to_keys(&mut rng, Default::default(), &pkeys_refs)
+            .encrypt_to_keys(&mut rng, sym_alg, &pkeys_refs)
             .map_err(Error::EncryptMessageError)?
             .to_armored_bytes(None)
             .map_err(Error::ExportEncryptedMessageToArmorError)?;
@@ -122,3 +212,37 @@ pub async fn encrypt(pkeys: Vec<SignedPublicKey>, plain_bytes: Vec<u8>) -> Resul
     })
     .await?
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use super::select_sym_alg;
+    use crate::{encrypt, gen_key_pair, native::crypto::sym::SymmetricKeyAlgorithm, Error};
+
+    #[test_log::test(test)]
+    async fn encrypt_without_recipients() {
+        let err = encrypt(vec![], b"message".to_vec()).await.unwrap_err();
+        assert!(matches!(err, Error::EncryptMessageMissingRecipientsError));
+    }
+
+    #[test_log::test(test)]
+    async fn select_sym_alg_from_preferences() {
+        // generated keys prefer AES-256
+        let (_, alice_pkey) = gen_key_pair("alice@localhost", "").await.unwrap();
+        let (_, bob_pkey) = gen_key_pair("bob@localhost", "").await.unwrap();
+
+        let alg = select_sym_alg(&[alice_pkey.clone(), bob_pkey]);
+        assert_eq!(alg, SymmetricKeyAlgorithm::AES256);
+
+        // keys without preferences only accept AES-128
+        let mut carl_pkey = alice_pkey.clone();
+        carl_pkey.details.users.clear();
+
+        let alg = select_sym_alg(&[alice_pkey, carl_pkey]);
+        assert_eq!(alg, SymmetricKeyAlgorithm::AES128);
+    }
+}
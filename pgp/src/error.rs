@@ -17,6 +17,8 @@
 pub enum Error {
     #[error("cannot import armored pgp message")]
     ImportMessageFromArmorError(#[source] native::errors::Error),
+    #[error("cannot import binary pgp message")]
+    ImportMessageFromBytesError(#[source] native::errors::Error),
     #[error("cannot decrypt pgp message")]
     DecryptMessageError(#[source] native::errors::Error),
     #[error("cannot decompress pgp message")]
@@ -34,8 +36,14 @@ pub enum Error {
     SignMessageError(#[source] native::errors::Error),
     #[error("cannot export signed pgp message as armored string")]
     ExportSignedMessageToArmoredBytesError(#[source] native::errors::Error),
+    #[error("cannot write pgp signature")]
+    WriteSignatureError(#[source] std::io::Error),
     #[error("cannot encrypt message using pgp")]
     EncryptMessageError(#[source] native::errors::Error),
+    #[error("cannot encrypt pgp message: no recipient given")]
+    EncryptMessageMissingRecipientsError,
+    #[error("cannot find pgp public key {0} for encryption")]
+    FindPublicKeyForEncryptionError(String),
     #[error("cannot export encrypted pgp message as armored string")]
     ExportEncryptedMessageToArmorError(#[source] native::errors::Error),
     #[error("cannot compress pgp message")]
@@ -82,6 +90,8 @@ pub enum Error {
     ReadArmoredPublicKeyError(#[source] std::io::Error, PathBuf),
     #[error("cannot parse armored public key from {1}")]
     ParseArmoredPublicKeyError(#[source] native::errors::Error, PathBuf),
+    #[error("cannot parse armored public key from string")]
+    ParseArmoredPublicKeyFromStringError(#[source] native::errors::Error),
 
     #[error("cannot read armored secret key file {1}")]
     ReadArmoredSecretKeyFromPathError(#[source] std::io::Error, PathBuf),
@@ -90,6 +100,11 @@ pub enum Error {
     #[error("cannot parse armored secret key from string")]
     ParseArmoredSecretKeyFromStringError(#[source] native::errors::Error),
 
+    #[error("cannot dearmor pgp data")]
+    DearmorError(#[source] std::io::Error),
+    #[error("cannot enarmor pgp data")]
+    EnarmorError(#[source] native::errors::Error),
+
     #[error("cannot import pgp signature from armor")]
     ReadStandaloneSignatureFromArmoredBytesError(#[source] native::errors::Error),
 
@@ -111,6 +126,10 @@ pub enum Error {
     #[error("cannot parse certificate")]
     ParseCertError(#[source] native::errors::Error),
 
+    #[cfg(feature = "secret")]
+    #[error("cannot get pgp secret key passphrase from secret")]
+    GetPassphraseFromSecretError(#[source] secret::Error),
+
     #[cfg(feature = "tokio")]
     #[error(transparent)]
     JoinError(#[from] tokio::task::JoinError),
@@ -1,12 +1,15 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 #![doc = include_str!("../README.md")]
 
+pub mod armor;
 pub mod decrypt;
 pub mod encrypt;
 mod error;
 #[cfg(feature = "key-discovery")]
 pub mod http;
+pub mod passphrase;
 pub mod sign;
+pub mod status;
 pub mod utils;
 pub mod verify;
 
@@ -14,13 +17,17 @@
 
 #[doc(inline)]
 pub use crate::{
+    armor::{dearmor, enarmor, is_armored},
     decrypt::decrypt,
     encrypt::encrypt,
     error::{Error, Result},
-    sign::sign,
+    passphrase::Passphrase,
+    sign::{sign, sign_detached, sign_detached_stream},
+    status::{key_status, key_status_at, KeyStatus},
     utils::{
-        gen_key_pair, read_pkey_from_path, read_sig_from_bytes, read_skey_from_file,
-        read_skey_from_string,
+        find_primary_email, find_primary_user_id, gen_key_pair, read_pkey_from_path,
+        read_public_key_from_armored, read_public_key_from_path, read_secret_key_from_armored,
+        read_secret_key_from_path, read_sig_from_bytes, read_skey_from_file, read_skey_from_string,
     },
     verify::verify,
 };
@@ -0,0 +1,148 @@
+//! # Passphrase
+//!
+//! Module dedicated to secret key passphrases. This module exposes
+//! the [`Passphrase`] enum, used by [`crate::sign`] and
+//! [`crate::decrypt`] to unlock secret keys.
+
+use std::{fmt, sync::Arc};
+
+use crate::Result;
+
+/// The passphrase source of a secret key.
+///
+/// The passphrase is resolved lazily, only when the secret key
+/// actually needs to be unlocked: unprotected keys never resolve
+/// their passphrase. This prevents callers from prompting the
+/// passphrase eagerly.
+#[derive(Clone)]
+pub enum Passphrase {
+    /// The passphrase is known in advance.
+    Static(String),
+
+    /// The passphrase is given by a callback, for example a prompt
+    /// or a pinentry program.
+    ///
+    /// The callback is called from a blocking thread.
+    Callback(Arc<dyn Fn() -> String + Send + Sync>),
+
+    /// The passphrase is stored as a secret (raw, shell command or
+    /// keyring entry).
+    #[cfg(feature = "secret")]
+    Secret(secret::Secret),
+}
+
+impl Passphrase {
+    /// Creates a new passphrase from the given callback.
+    pub fn callback(f: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(f))
+    }
+
+    /// Resolves the passphrase.
+    ///
+    /// This function blocks the current thread, it should only be
+    /// called from a blocking thread.
+    pub(crate) fn resolve(&self) -> Result<String> {
+        match self {
+            Self::Static(passphrase) => Ok(passphrase.clone()),
+            Self::Callback(f) => Ok(f()),
+            #[cfg(all(feature = "secret", feature = "tokio"))]
+            Self::Secret(secret) => tokio::runtime::Handle::current()
+                .block_on(secret.get())
+                .map_err(crate::Error::GetPassphraseFromSecretError),
+            #[cfg(all(feature = "secret", feature = "async-std"))]
+            Self::Secret(secret) => async_std::task::block_on(secret.get())
+                .map_err(crate::Error::GetPassphraseFromSecretError),
+        }
+    }
+}
+
+impl Default for Passphrase {
+    fn default() -> Self {
+        Self::Static(String::new())
+    }
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Static(_) => f.write_str("Static(..)"),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+            #[cfg(feature = "secret")]
+            Self::Secret(_) => f.write_str("Secret(..)"),
+        }
+    }
+}
+
+impl From<String> for Passphrase {
+    fn from(passphrase: String) -> Self {
+        Self::Static(passphrase)
+    }
+}
+
+impl From<&str> for Passphrase {
+    fn from(passphrase: &str) -> Self {
+        Self::Static(passphrase.to_owned())
+    }
+}
+
+#[cfg(feature = "secret")]
+impl From<secret::Secret> for Passphrase {
+    fn from(secret: secret::Secret) -> Self {
+        Self::Secret(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use super::Passphrase;
+    use crate::{decrypt, encrypt, gen_key_pair, read_sig_from_bytes, sign, verify};
+
+    fn counting_passphrase() -> (Passphrase, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = calls.clone();
+
+        let passphrase = Passphrase::callback(move || {
+            calls_clone.fetch_add(1, Ordering::SeqCst);
+            String::new()
+        });
+
+        (passphrase, calls)
+    }
+
+    #[test_log::test(test)]
+    async fn resolve_callback() {
+        let (passphrase, calls) = counting_passphrase();
+
+        assert_eq!(passphrase.resolve().unwrap(), "");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test_log::test(test)]
+    async fn sign_then_decrypt_without_resolving_passphrase() {
+        // unprotected keys do not need to be unlocked
+        let (skey, pkey) = gen_key_pair("test@localhost", "").await.unwrap();
+        let msg = b"message".to_vec();
+
+        let (passphrase, calls) = counting_passphrase();
+        let raw_sig = sign(skey.clone(), passphrase, msg.clone()).await.unwrap();
+        let sig = read_sig_from_bytes(raw_sig).await.unwrap();
+        verify(pkey.clone(), sig, msg.clone()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let (passphrase, calls) = counting_passphrase();
+        let encrypted_msg = encrypt(vec![pkey], msg.clone()).await.unwrap();
+        let decrypted_msg = decrypt(skey, passphrase, encrypted_msg).await.unwrap();
+        assert_eq!(decrypted_msg, msg);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -3,19 +3,24 @@
 //! Module dedicated to PGP signing. This module exposes a simple
 //! function [`sign`] and its associated [`Error`]s.
 
-use std::io;
+use std::{
+    cell::Cell,
+    io::{self, Read, Write},
+};
 
+use chrono::{SubsecRound, Utc};
 use rand::{CryptoRng, Rng};
 
 use crate::{
     native::{
         self,
         crypto::{hash::HashAlgorithm, public_key::PublicKeyAlgorithm},
+        packet::{SignatureConfig, SignatureType, SignatureVersion, Subpacket},
         types::{KeyId, KeyTrait, Mpi, PublicKeyTrait, SecretKeyRepr, SecretKeyTrait},
-        Message, PublicKey, PublicSubkey, SignedSecretKey, SignedSecretSubKey,
+        Message, PublicKey, PublicSubkey, SignedSecretKey, SignedSecretSubKey, StandaloneSignature,
     },
     utils::spawn_blocking,
-    Error, Result,
+    Error, Passphrase, Result,
 };
 
 #[derive(Debug)]
@@ -139,19 +144,36 @@ fn find_skey_for_signing(key: &SignedSecretKey) -> Option<SignedSecretKeyOrSubke
 }
 
 /// Signs given bytes using the given private key and its passphrase.
+///
+/// The passphrase is only resolved if the key needs to be unlocked,
+/// see [`Passphrase`].
 pub async fn sign(
     skey: SignedSecretKey,
-    passphrase: impl ToString,
+    passphrase: impl Into<Passphrase>,
     plain_bytes: Vec<u8>,
 ) -> Result<Vec<u8>> {
-    let passphrase = passphrase.to_string();
+    let passphrase = passphrase.into();
 
     spawn_blocking(move || {
         let skey = find_skey_for_signing(&skey).ok_or(Error::FindSignedSecretKeyForSigningError)?;
 
+        // the passphrase error cannot be returned from the unlock
+        // callback, so it is kept aside
+        let passphrase_err = Cell::new(None);
+        let get_passphrase = || {
+            passphrase.resolve().unwrap_or_else(|err| {
+                passphrase_err.set(Some(err));
+                String::new()
+            })
+        };
+
         let msg = Message::new_literal_bytes("", &plain_bytes)
-            .sign(&skey, || passphrase, HashAlgorithm::SHA2_256)
-            .map_err(Error::SignMessageError)?;
+            .sign(&skey, get_passphrase, HashAlgorithm::SHA2_256)
+            .map_err(|err| {
+                passphrase_err
+                    .take()
+                    .unwrap_or(Error::SignMessageError(err))
+            })?;
 
         let signature_bytes = msg
             .into_signature()
@@ -162,3 +184,170 @@ pub async fn sign(
     })
     .await?
 }
+
+/// Creates an armored detached signature over the data read from
+/// the given reader.
+///
+/// The data is hashed as is, using the binary signature type: it is
+/// not wrapped into a literal data packet.
+fn create_detached_signature(
+    skey: &SignedSecretKey,
+    passphrase: &Passphrase,
+    hash: HashAlgorithm,
+    reader: impl Read,
+) -> Result<Vec<u8>> {
+    let skey = find_skey_for_signing(skey).ok_or(Error::FindSignedSecretKeyForSigningError)?;
+
+    let hashed_subpackets = vec![
+        Subpacket::SignatureCreationTime(Utc::now().trunc_subsecs(0)),
+        Subpacket::Issuer(skey.key_id()),
+    ];
+
+    let config = SignatureConfig::new_v4(
+        SignatureVersion::V4,
+        SignatureType::Binary,
+        skey.algorithm(),
+        hash,
+        hashed_subpackets,
+        Vec::new(),
+    );
+
+    // the passphrase error cannot be returned from the unlock
+    // callback, so it is kept aside
+    let passphrase_err = Cell::new(None);
+    let get_passphrase = || {
+        passphrase.resolve().unwrap_or_else(|err| {
+            passphrase_err.set(Some(err));
+            String::new()
+        })
+    };
+
+    let sig = config.sign(&skey, get_passphrase, reader).map_err(|err| {
+        passphrase_err
+            .take()
+            .unwrap_or(Error::SignMessageError(err))
+    })?;
+
+    StandaloneSignature::new(sig)
+        .to_armored_bytes(None)
+        .map_err(Error::ExportSignedMessageToArmoredBytesError)
+}
+
+/// Signs given bytes using the given private key, its passphrase
+/// and the given hash algorithm.
+///
+/// The armored signature is detached and computed over the exact
+/// given bytes, which makes it suitable for `multipart/signed`
+/// messages (RFC 3156): the bytes should be the canonical form of
+/// the signed MIME part, including its CRLF line endings.
+pub async fn sign_detached(
+    data: Vec<u8>,
+    skey: SignedSecretKey,
+    passphrase: impl Into<Passphrase>,
+    hash: HashAlgorithm,
+) -> Result<Vec<u8>> {
+    let passphrase = passphrase.into();
+
+    spawn_blocking(move || create_detached_signature(&skey, &passphrase, hash, &data[..])).await?
+}
+
+/// Signs the data read from the given reader using the given private
+/// key and its passphrase, then writes the armored detached signature
+/// to the given writer.
+///
+/// Unlike [`sign`], the signature is detached (see [`sign_detached`])
+/// and the data is hashed while being read, so it is never entirely
+/// loaded in memory. The writer is given back once
+/// the signature is written.
+pub async fn sign_detached_stream<R, W>(
+    skey: SignedSecretKey,
+    passphrase: impl Into<Passphrase>,
+    reader: R,
+    mut writer: W,
+) -> Result<W>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let passphrase = passphrase.into();
+
+    spawn_blocking(move || {
+        let signature_bytes =
+            create_detached_signature(&skey, &passphrase, HashAlgorithm::SHA2_256, reader)?;
+
+        writer
+            .write_all(&signature_bytes)
+            .and_then(|()| writer.flush())
+            .map_err(Error::WriteSignatureError)?;
+
+        Ok(writer)
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, Write};
+
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use crate::{
+        gen_key_pair,
+        native::crypto::hash::HashAlgorithm,
+        read_sig_from_bytes,
+        sign::{sign_detached, sign_detached_stream},
+        verify,
+    };
+
+    #[test_log::test(test)]
+    async fn sign_detached_then_verify() {
+        let (skey, pkey) = gen_key_pair("test@localhost", "").await.unwrap();
+        let data = b"Content-Type: text/plain\r\n\r\nsigned part\r\n".to_vec();
+
+        let raw_sig = sign_detached(data.clone(), skey, "", HashAlgorithm::SHA2_512)
+            .await
+            .unwrap();
+        assert!(raw_sig.starts_with(b"-----BEGIN PGP SIGNATURE-----"));
+
+        let sig = read_sig_from_bytes(raw_sig).await.unwrap();
+
+        verify(pkey.clone(), sig.clone(), data.clone())
+            .await
+            .unwrap();
+
+        // the signature covers the exact bytes
+        let mut lf_data = data;
+        lf_data.retain(|b| *b != b'\r');
+        verify(pkey, sig, lf_data).await.unwrap_err();
+    }
+
+    #[test_log::test(test)]
+    async fn sign_large_file_stream_then_verify() {
+        let (skey, pkey) = gen_key_pair("test@localhost", "").await.unwrap();
+
+        // 8 MiB of non-repeating data
+        let data: Vec<u8> = (0..8 * 1024 * 1024u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+            .collect();
+
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&data).unwrap();
+        file.rewind().unwrap();
+
+        let raw_sig = sign_detached_stream(skey, "", file, Vec::new())
+            .await
+            .unwrap();
+        let sig = read_sig_from_bytes(raw_sig).await.unwrap();
+
+        verify(pkey.clone(), sig.clone(), data.clone())
+            .await
+            .unwrap();
+
+        let mut tampered_data = data;
+        tampered_data[42] ^= 1;
+        verify(pkey, sig, tampered_data).await.unwrap_err();
+    }
+}
@@ -0,0 +1,98 @@
+//! # Key status
+//!
+//! Module dedicated to PGP public keys validity. This module exposes
+//! a simple function [`key_status`] and its associated [`KeyStatus`].
+
+use chrono::{DateTime, Utc};
+
+use crate::native::{packet::SignatureType, SignedPublicKey};
+
+/// The validity status of a public key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeyStatus {
+    /// The key can be used.
+    Valid,
+
+    /// The key expired at the given date.
+    Expired(DateTime<Utc>),
+
+    /// The key has been revoked by its owner.
+    Revoked,
+
+    /// The key has been created in the future.
+    NotYetValid,
+}
+
+impl KeyStatus {
+    /// Returns `true` if the key can be used.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+}
+
+/// Gets the status of the given public key, at the current date.
+///
+/// See [`key_status_at`].
+pub fn key_status(pkey: &SignedPublicKey) -> KeyStatus {
+    key_status_at(pkey, Utc::now())
+}
+
+/// Gets the status of the given public key, at the given date.
+///
+/// Only the primary key is inspected: a key is revoked if one of its
+/// revocation signatures is valid, and expires according to the key
+/// expiration time of its binding signatures.
+pub fn key_status_at(pkey: &SignedPublicKey, now: DateTime<Utc>) -> KeyStatus {
+    let revoked = pkey.details.revocation_signatures.iter().any(|sig| {
+        sig.typ() == SignatureType::KeyRevocation && sig.verify_key(&pkey.primary_key).is_ok()
+    });
+
+    if revoked {
+        return KeyStatus::Revoked;
+    }
+
+    if *pkey.primary_key.created_at() > now {
+        return KeyStatus::NotYetValid;
+    }
+
+    match pkey.expires_at() {
+        Some(expires_at) if expires_at <= now => KeyStatus::Expired(expires_at),
+        _ => KeyStatus::Valid,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{key_status, KeyStatus};
+    use crate::read_public_key_from_armored;
+
+    #[test]
+    fn valid_key() {
+        let pkey = read_public_key_from_armored(include_str!("../tests/fixtures/valid.pub.asc"));
+        assert_eq!(key_status(&pkey.unwrap()), KeyStatus::Valid);
+    }
+
+    #[test]
+    fn expired_key() {
+        let pkey = read_public_key_from_armored(include_str!("../tests/fixtures/expired.pub.asc"));
+        let expected_date = Utc.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap();
+        assert_eq!(
+            key_status(&pkey.unwrap()),
+            KeyStatus::Expired(expected_date)
+        );
+    }
+
+    #[test]
+    fn revoked_key() {
+        let pkey = read_public_key_from_armored(include_str!("../tests/fixtures/revoked.pub.asc"));
+        assert_eq!(key_status(&pkey.unwrap()), KeyStatus::Revoked);
+    }
+
+    #[test]
+    fn not_yet_valid_key() {
+        let pkey = read_public_key_from_armored(include_str!("../tests/fixtures/future.pub.asc"));
+        assert_eq!(key_status(&pkey.unwrap()), KeyStatus::NotYetValid);
+    }
+}
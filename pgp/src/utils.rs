@@ -2,11 +2,16 @@
 //!
 //! Module dedicated to PGP helpers.
 
-use std::{fs, io::Cursor, path::PathBuf};
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 
 use smallvec::smallvec;
 
 use crate::{
+    armor::is_armored,
     native::{
         crypto::{hash::HashAlgorithm, sym::SymmetricKeyAlgorithm},
         types::{CompressionAlgorithm, SecretKeyTrait},
@@ -111,15 +116,109 @@ pub async fn read_skey_from_string(string: String) -> Result<SignedSecretKey> {
     .await?
 }
 
-/// Reads a standalone signature from the given raw bytes.
+/// Reads a signed public key from the given armored string.
 ///
-/// The given raw bytes needs to match a single armored signature,
+/// The given string needs to contain a single armored public key,
 /// otherwise it fails.
+pub fn read_public_key_from_armored(armored: &str) -> Result<SignedPublicKey> {
+    let (pkey, _) = SignedPublicKey::from_armor_single(Cursor::new(armored))
+        .map_err(Error::ParseArmoredPublicKeyFromStringError)?;
+    Ok(pkey)
+}
+
+/// Reads a signed secret key from the given armored string.
+///
+/// The given string needs to contain a single armored secret key,
+/// otherwise it fails.
+pub fn read_secret_key_from_armored(armored: &str) -> Result<SignedSecretKey> {
+    let (skey, _) = SignedSecretKey::from_armor_single(Cursor::new(armored))
+        .map_err(Error::ParseArmoredSecretKeyFromStringError)?;
+    Ok(skey)
+}
+
+/// Reads a signed public key from the given path.
+///
+/// The file can either contain an armored or a binary public key.
+pub fn read_public_key_from_path(path: impl AsRef<Path>) -> Result<SignedPublicKey> {
+    let path = path.as_ref();
+    let data =
+        fs::read(path).map_err(|err| Error::ReadArmoredPublicKeyError(err, path.to_owned()))?;
+
+    let pkey = if is_armored(&data) {
+        SignedPublicKey::from_armor_single(Cursor::new(data)).map(|(pkey, _)| pkey)
+    } else {
+        SignedPublicKey::from_bytes(Cursor::new(data))
+    };
+
+    pkey.map_err(|err| Error::ParseArmoredPublicKeyError(err, path.to_owned()))
+}
+
+/// Reads a signed secret key from the given path.
+///
+/// The file can either contain an armored or a binary secret key.
+pub fn read_secret_key_from_path(path: impl AsRef<Path>) -> Result<SignedSecretKey> {
+    let path = path.as_ref();
+    let data = fs::read(path)
+        .map_err(|err| Error::ReadArmoredSecretKeyFromPathError(err, path.to_owned()))?;
+
+    let skey = if is_armored(&data) {
+        SignedSecretKey::from_armor_single(Cursor::new(data)).map(|(skey, _)| skey)
+    } else {
+        SignedSecretKey::from_bytes(Cursor::new(data))
+    };
+
+    skey.map_err(|err| Error::ParseArmoredSecretKeyFromPathError(err, path.to_owned()))
+}
+
+/// Finds the primary user ID of the given public key.
+///
+/// Falls back to the first user ID if none of them is marked as
+/// primary.
+pub fn find_primary_user_id(pkey: &SignedPublicKey) -> Option<String> {
+    let users = &pkey.details.users;
+
+    users
+        .iter()
+        .find(|user| user.is_primary())
+        .or_else(|| users.first())
+        .map(|user| user.id.id().to_owned())
+}
+
+/// Finds the email address of the primary user ID of the given
+/// public key.
+///
+/// User IDs are usually formatted as `Name <email>`, but can also
+/// contain a bare email address.
+pub fn find_primary_email(pkey: &SignedPublicKey) -> Option<String> {
+    let user_id = find_primary_user_id(pkey)?;
+
+    let email = match (user_id.rfind('<'), user_id.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &user_id[start + 1..end],
+        _ => user_id.as_str(),
+    };
+
+    let email = email.trim();
+
+    if email.contains('@') {
+        Some(email.to_owned())
+    } else {
+        None
+    }
+}
+
+/// Reads a standalone signature from the given raw bytes.
+///
+/// The given raw bytes needs to match a single signature, either
+/// armored or binary, otherwise it fails.
 pub async fn read_sig_from_bytes(bytes: Vec<u8>) -> Result<StandaloneSignature> {
     spawn_blocking(move || {
-        let (sig, _) = StandaloneSignature::from_armor_single(Cursor::new(&bytes))
-            .map_err(Error::ReadStandaloneSignatureFromArmoredBytesError)?;
-        Ok(sig)
+        let sig = if is_armored(&bytes) {
+            StandaloneSignature::from_armor_single(Cursor::new(&bytes)).map(|(sig, _)| sig)
+        } else {
+            StandaloneSignature::from_bytes(Cursor::new(&bytes))
+        };
+
+        sig.map_err(Error::ReadStandaloneSignatureFromArmoredBytesError)
     })
     .await?
 }
@@ -161,3 +260,90 @@ pub(crate) async fn spawn_blocking<F, T>(f: F) -> Result<T>
 {
     Ok(tokio::task::spawn_blocking(f).await?)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    use tempfile::tempdir;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use crate::{
+        gen_key_pair,
+        native::{ser::Serialize, types::KeyTrait},
+        utils::{
+            find_primary_email, find_primary_user_id, read_public_key_from_armored,
+            read_public_key_from_path, read_secret_key_from_armored, read_secret_key_from_path,
+        },
+        Error,
+    };
+
+    #[test_log::test(test)]
+    async fn read_keys() {
+        let (skey, pkey) = gen_key_pair("alice@localhost", "").await.unwrap();
+        let dir = tempdir().unwrap();
+
+        let armored_pkey = pkey.to_armored_string(None).unwrap();
+        let armored_skey = skey.to_armored_string(None).unwrap();
+
+        let parsed_pkey = read_public_key_from_armored(&armored_pkey).unwrap();
+        assert_eq!(parsed_pkey.key_id(), pkey.key_id());
+        let parsed_skey = read_secret_key_from_armored(&armored_skey).unwrap();
+        assert_eq!(parsed_skey.key_id(), skey.key_id());
+
+        let armored_pkey_path = dir.path().join("alice.pub.asc");
+        fs::write(&armored_pkey_path, &armored_pkey).unwrap();
+        let binary_pkey_path = dir.path().join("alice.pub.gpg");
+        fs::write(&binary_pkey_path, pkey.to_bytes().unwrap()).unwrap();
+        let armored_skey_path = dir.path().join("alice.asc");
+        fs::write(&armored_skey_path, &armored_skey).unwrap();
+        let binary_skey_path = dir.path().join("alice.gpg");
+        fs::write(&binary_skey_path, skey.to_bytes().unwrap()).unwrap();
+
+        for path in [armored_pkey_path, binary_pkey_path] {
+            let parsed_pkey = read_public_key_from_path(path).unwrap();
+            assert_eq!(parsed_pkey.key_id(), pkey.key_id());
+        }
+
+        for path in [armored_skey_path, binary_skey_path] {
+            let parsed_skey = read_secret_key_from_path(path).unwrap();
+            assert_eq!(parsed_skey.key_id(), skey.key_id());
+        }
+
+        let err = read_public_key_from_armored(&armored_skey).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ParseArmoredPublicKeyFromStringError(_)
+        ));
+
+        let err = read_public_key_from_path(dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, Error::ReadArmoredPublicKeyError(_, _)));
+    }
+
+    #[test_log::test(test)]
+    async fn find_primary_user() {
+        let (_, pkey) = gen_key_pair("alice@localhost", "").await.unwrap();
+        assert_eq!(
+            find_primary_user_id(&pkey).as_deref(),
+            Some("alice@localhost")
+        );
+        assert_eq!(
+            find_primary_email(&pkey).as_deref(),
+            Some("alice@localhost")
+        );
+
+        let (_, pkey) = gen_key_pair("Bob <bob@localhost>", "").await.unwrap();
+        assert_eq!(
+            find_primary_user_id(&pkey).as_deref(),
+            Some("Bob <bob@localhost>")
+        );
+        assert_eq!(find_primary_email(&pkey).as_deref(), Some("bob@localhost"));
+
+        let (_, pkey) = gen_key_pair("Carl", "").await.unwrap();
+        assert_eq!(find_primary_user_id(&pkey).as_deref(), Some("Carl"));
+        assert_eq!(find_primary_email(&pkey), None);
+    }
+}
@@ -1,17 +1,32 @@
 //! # Command
 //!
-//! Module dedicated to commands. It only exposes the [`Command`]
+//! Module dedicated to commands. It mainly exposes the [`Command`]
 //! struct, and various implementations of transformation.
 
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fmt,
+    future::{poll_fn, Future},
+    io::{self, Read, Write},
     ops::{Deref, DerefMut},
-    process::Stdio,
+    panic,
+    pin::pin,
+    process::{self, Stdio},
+    sync::{Mutex, PoisonError},
+    task::Poll,
+    thread,
 };
 
 #[cfg(feature = "async-std")]
-use async_std::{io::WriteExt, process::Command as AsyncCommand};
+use async_std::{
+    io::{Read as AsyncRead, ReadExt, Write as AsyncWrite, WriteExt},
+    process::{Child, Command as AsyncCommand},
+};
 #[cfg(feature = "tokio")]
-use tokio::{io::AsyncWriteExt, process::Command as AsyncCommand};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    process::{Child, Command as AsyncCommand},
+};
 use tracing::{debug, info};
 
 use crate::{Error, Output, Result};
@@ -34,6 +49,42 @@ pub struct Command {
     /// Defaults to `true`.
     #[cfg_attr(feature = "derive", serde(skip))]
     piped: bool,
+
+    /// The maximum amount of bytes the command can output, on each
+    /// of its standard output and standard error channels.
+    ///
+    /// Only applies to piped outputs. Defaults to `None`, which
+    /// means unlimited.
+    #[cfg_attr(feature = "derive", serde(skip))]
+    max_output_bytes: Option<usize>,
+
+    /// The environment variables set to the command, on top of the
+    /// environment inherited from the current process.
+    ///
+    /// Defaults to none.
+    #[cfg_attr(feature = "derive", serde(skip))]
+    env: BTreeMap<String, String>,
+
+    /// Whenever the command should be resolved and logged instead of
+    /// being executed.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(skip))]
+    dry_run: bool,
+
+    /// Whenever the command should share the terminal of the parent
+    /// process.
+    ///
+    /// Defaults to `false`.
+    #[cfg_attr(feature = "derive", serde(skip))]
+    interactive: bool,
+
+    /// The non-zero exit status codes considered as a success.
+    ///
+    /// Defaults to none, which means that any non-zero exit status
+    /// code is an error.
+    #[cfg_attr(feature = "derive", serde(skip))]
+    acceptable_exit_codes: BTreeSet<i32>,
 }
 
 impl Command {
@@ -45,6 +96,11 @@ pub fn new(cmd: impl ToString) -> Self {
         Self {
             inner: cmd.to_string(),
             piped: true,
+            max_output_bytes: None,
+            env: BTreeMap::new(),
+            dry_run: false,
+            interactive: false,
+            acceptable_exit_codes: BTreeSet::new(),
         }
     }
 
@@ -65,6 +121,153 @@ pub fn with_output_piped(mut self, piped: bool) -> Self {
         self
     }
 
+    /// Defines the maximum amount of bytes the command can output.
+    ///
+    /// When the command exceeds this limit, it is killed and
+    /// [`Error::OutputTooLargeError`] is returned. See
+    /// [`Command::with_max_output_bytes`] for the builder pattern
+    /// alternative.
+    pub fn set_max_output_bytes(&mut self, max: Option<usize>) {
+        self.max_output_bytes = max;
+    }
+
+    /// Defines the maximum amount of bytes the command can output,
+    /// using the builder pattern.
+    ///
+    /// See [`Command::set_max_output_bytes`] for the setter
+    /// alternative.
+    pub fn with_max_output_bytes(mut self, max: Option<usize>) -> Self {
+        self.set_max_output_bytes(max);
+        self
+    }
+
+    /// Defines an environment variable of the command.
+    ///
+    /// The variable is set on top of the environment inherited from
+    /// the current process. See [`Command::with_env`] for the builder
+    /// pattern alternative.
+    pub fn set_env(&mut self, key: impl ToString, val: impl ToString) {
+        self.env.insert(key.to_string(), val.to_string());
+    }
+
+    /// Defines an environment variable of the command, using the
+    /// builder pattern.
+    ///
+    /// See [`Command::set_env`] for the setter alternative.
+    pub fn with_env(mut self, key: impl ToString, val: impl ToString) -> Self {
+        self.set_env(key, val);
+        self
+    }
+
+    /// Defines whenever the command should run in dry-run mode.
+    ///
+    /// In dry-run mode, the command is not executed: the command that
+    /// would run is logged, then [`Error::DryRunError`] is returned
+    /// with the [`ResolvedCommand`]. No output is ever produced. This
+    /// is particularly useful to debug hooks. See
+    /// [`Command::with_dry_run`] for the builder pattern alternative.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Defines whenever the command should run in dry-run mode, using
+    /// the builder pattern.
+    ///
+    /// See [`Command::set_dry_run`] for the setter alternative.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.set_dry_run(dry_run);
+        self
+    }
+
+    /// Returns `true` if the command runs in dry-run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Defines whenever the command should run in interactive mode.
+    ///
+    /// In interactive mode, the standard input, output and error
+    /// channels are all inherited from the parent process, whatever
+    /// the output piping: nothing is captured, and the command can
+    /// prompt the user from the terminal (like `pinentry-curses`
+    /// does). Running an interactive command with input fails with
+    /// [`Error::PipeInputToInteractiveCommandError`]. See
+    /// [`Command::with_interactive`] for the builder pattern
+    /// alternative.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+    }
+
+    /// Defines whenever the command should run in interactive mode,
+    /// using the builder pattern.
+    ///
+    /// See [`Command::set_interactive`] for the setter alternative.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.set_interactive(interactive);
+        self
+    }
+
+    /// Returns `true` if the command runs in interactive mode.
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Defines the non-zero exit status codes considered as a
+    /// success.
+    ///
+    /// A command exiting with one of these codes does not fail with
+    /// [`Error::GetExitStatusCodeNonZeroError`]: its output is
+    /// returned instead, and the exit status code can be read from
+    /// [`Output::code`]. This allows hooks to tell something (like
+    /// "skip") without failing. See
+    /// [`Command::with_acceptable_exit_codes`] for the builder
+    /// pattern alternative.
+    pub fn set_acceptable_exit_codes(&mut self, codes: impl IntoIterator<Item = i32>) {
+        self.acceptable_exit_codes = codes.into_iter().collect();
+    }
+
+    /// Defines the non-zero exit status codes considered as a
+    /// success, using the builder pattern.
+    ///
+    /// See [`Command::set_acceptable_exit_codes`] for the setter
+    /// alternative.
+    pub fn with_acceptable_exit_codes(mut self, codes: impl IntoIterator<Item = i32>) -> Self {
+        self.set_acceptable_exit_codes(codes);
+        self
+    }
+
+    /// Returns `true` if the given exit status code is considered as
+    /// a success.
+    pub fn is_acceptable_exit_code(&self, code: i32) -> bool {
+        code == 0 || self.acceptable_exit_codes.contains(&code)
+    }
+
+    /// Resolves the current command, as it would be spawned.
+    ///
+    /// The environment is the one inherited from the current process,
+    /// overridden by the variables of the command. Nothing is
+    /// executed.
+    pub fn resolve(&self) -> ResolvedCommand {
+        let (shell, arg) = shell();
+
+        let mut env: BTreeMap<String, String> = env::vars_os()
+            .map(|(key, val)| {
+                let key = key.to_string_lossy().into_owned();
+                let val = val.to_string_lossy().into_owned();
+                (key, val)
+            })
+            .collect();
+
+        env.extend(self.env.clone());
+
+        ResolvedCommand {
+            shell: shell.to_owned(),
+            shell_arg: arg.to_owned(),
+            command: self.inner.clone(),
+            env,
+        }
+    }
+
     /// Wrapper around [`alloc::str::replace`].
     ///
     /// This function is particularly useful when you need to replace
@@ -88,50 +291,109 @@ pub async fn run(&self) -> Result<Output> {
     /// standard input channel then waits for the output on the
     /// standard output channel.
     pub async fn run_with(&self, input: impl AsRef<[u8]>) -> Result<Output> {
+        let input = input.as_ref();
+
+        if self.dry_run {
+            return Err(self.dry_run_error());
+        }
+
         info!(cmd = self.inner, "run shell command");
 
+        let child = new_async_command()
+            .arg(&self.inner)
+            .envs(&self.env)
+            .stdin(self.input_stdio(input)?)
+            .stdout(self.output_stdio("stdout"))
+            .stderr(self.output_stdio("stderr"))
+            .spawn()?;
+
+        let output = self.wait_with_output(child, input).await?;
+
+        self.extract_output(output)
+    }
+
+    /// Runs the current command without input, synchronously.
+    ///
+    /// See [`Command::run_with_blocking`] to run command with output.
+    pub fn run_blocking(&self) -> Result<Output> {
+        self.run_with_blocking([])
+    }
+
+    /// Run the command with the given input, synchronously.
+    ///
+    /// This is the blocking alternative of [`Command::run_with`], for
+    /// callers that are not in an async context. It does not rely on
+    /// any async runtime, which means that it can also be called from
+    /// within one without panicking (it blocks the current thread
+    /// though).
+    pub fn run_with_blocking(&self, input: impl AsRef<[u8]>) -> Result<Output> {
         let input = input.as_ref();
 
-        let stdin = if input.is_empty() {
-            debug!("inherit stdin from parent");
-            Stdio::inherit()
-        } else {
-            debug!("stdin piped");
-            Stdio::piped()
-        };
+        if self.dry_run {
+            return Err(self.dry_run_error());
+        }
+
+        info!(cmd = self.inner, "run shell command synchronously");
 
-        let mut cmd = new_async_command()
+        let child = new_command()
             .arg(&self.inner)
-            .stdin(stdin)
-            .stdout(if self.piped {
-                debug!("stdout piped");
-                Stdio::piped()
-            } else {
-                debug!("inherit stdout from parent");
-                Stdio::inherit()
-            })
-            .stderr(if self.piped {
-                debug!("stderr piped");
-                Stdio::piped()
-            } else {
-                debug!("inherit stderr from parent");
-                Stdio::inherit()
-            })
+            .envs(&self.env)
+            .stdin(self.input_stdio(input)?)
+            .stdout(self.output_stdio("stdout"))
+            .stderr(self.output_stdio("stderr"))
             .spawn()?;
 
+        let output = self.wait_with_output_blocking(child, input)?;
+
+        self.extract_output(output)
+    }
+
+    /// Logs the command that would run, then builds the dry-run
+    /// error holding it.
+    fn dry_run_error(&self) -> Error {
+        let cmd = self.resolve();
+        info!(cmd = self.inner, "would run: {cmd}");
+        Error::DryRunError(cmd)
+    }
+
+    /// Returns `true` if the outputs of the command are captured.
+    fn is_output_captured(&self) -> bool {
+        self.piped && !self.interactive
+    }
+
+    /// Builds the standard input channel configuration.
+    ///
+    /// Fails if the command is interactive and the given input is
+    /// not empty.
+    fn input_stdio(&self, input: &[u8]) -> Result<Stdio> {
+        if !self.interactive {
+            return Ok(stdin(input));
+        }
+
         if !input.is_empty() {
-            cmd.stdin
-                .as_mut()
-                .ok_or(Error::GetStdinError)?
-                .write_all(input)
-                .await?;
+            return Err(Error::PipeInputToInteractiveCommandError(self.to_string()));
         }
 
-        #[cfg(feature = "async-std")]
-        let output = cmd.output().await?;
-        #[cfg(feature = "tokio")]
-        let output = cmd.wait_with_output().await?;
+        debug!("inherit stdin from parent (interactive)");
+        Ok(Stdio::inherit())
+    }
+
+    /// Builds the standard output or error channel configuration.
+    fn output_stdio(&self, name: &str) -> Stdio {
+        if self.is_output_captured() {
+            debug!("{name} piped");
+            Stdio::piped()
+        } else {
+            debug!("inherit {name} from parent");
+            Stdio::inherit()
+        }
+    }
 
+    /// Extracts the [`Output`] of the given exited process.
+    ///
+    /// Fails if the process exited with a non-zero status code that
+    /// is not acceptable.
+    fn extract_output(&self, output: process::Output) -> Result<Output> {
         let code = output
             .status
             .code()
@@ -139,6 +401,8 @@ pub async fn run_with(&self, input: impl AsRef<[u8]>) -> Result<Output> {
 
         if code == 0 {
             debug!(code, "shell command gracefully exited");
+        } else if self.is_acceptable_exit_code(code) {
+            debug!(code, "shell command exited with acceptable status code");
         } else {
             let cmd = self.to_string();
             let err = String::from_utf8_lossy(&output.stderr).to_string();
@@ -146,7 +410,105 @@ pub async fn run_with(&self, input: impl AsRef<[u8]>) -> Result<Output> {
             return Err(Error::GetExitStatusCodeNonZeroError(cmd, code, err));
         }
 
-        Ok(Output::from(output.stdout))
+        Ok(Output::from(output.stdout).with_code(code))
+    }
+
+    /// Writes the given input to the given child, then waits for it
+    /// to exit while collecting its outputs.
+    ///
+    /// The input is written while the outputs are read, so that the
+    /// child cannot block on a full pipe. If one of the outputs
+    /// exceeds the maximum amount of bytes, the child is killed
+    /// straight away. The child is always awaited, even on failure,
+    /// so that it does not remain as a zombie process.
+    async fn wait_with_output(&self, mut child: Child, input: &[u8]) -> Result<process::Output> {
+        let max = self.max_output_bytes.unwrap_or(usize::MAX);
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let child = Mutex::new(child);
+
+        let (written, (stdout, stderr)) = join(
+            write_input(&child, stdin, input),
+            join(
+                read_capped(&child, stdout, max),
+                read_capped(&child, stderr, max),
+            ),
+        )
+        .await;
+
+        let mut child = child.into_inner().unwrap_or_else(PoisonError::into_inner);
+
+        #[cfg(feature = "async-std")]
+        let status = child.status().await?;
+        #[cfg(feature = "tokio")]
+        let status = child.wait().await?;
+
+        written?;
+        self.capped_output(max, status, stdout?, stderr?)
+    }
+
+    /// Writes the given input to the given child, then waits for it
+    /// to exit while collecting its outputs, synchronously.
+    ///
+    /// See [`Command::wait_with_output`].
+    fn wait_with_output_blocking(
+        &self,
+        mut child: process::Child,
+        input: &[u8],
+    ) -> Result<process::Output> {
+        let max = self.max_output_bytes.unwrap_or(usize::MAX);
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let child = Mutex::new(child);
+
+        let (written, stdout, stderr) = thread::scope(|scope| {
+            let written = scope.spawn(|| write_input_blocking(&child, stdin, input));
+            let stderr = scope.spawn(|| read_capped_blocking(&child, stderr, max));
+            let stdout = read_capped_blocking(&child, stdout, max);
+            let written = written
+                .join()
+                .unwrap_or_else(|err| panic::resume_unwind(err));
+            let stderr = stderr
+                .join()
+                .unwrap_or_else(|err| panic::resume_unwind(err));
+            (written, stdout, stderr)
+        });
+
+        let mut child = child.into_inner().unwrap_or_else(PoisonError::into_inner);
+        let status = child.wait()?;
+
+        written?;
+        self.capped_output(max, status, stdout?, stderr?)
+    }
+
+    /// Builds the output of an exited process from its capped
+    /// outputs.
+    ///
+    /// Fails if one of the outputs exceeded the given maximum amount
+    /// of bytes.
+    fn capped_output(
+        &self,
+        max: usize,
+        status: process::ExitStatus,
+        (mut stdout, stdout_overflowed): (Vec<u8>, bool),
+        (stderr, stderr_overflowed): (Vec<u8>, bool),
+    ) -> Result<process::Output> {
+        if stdout_overflowed || stderr_overflowed {
+            let cmd = self.to_string();
+            debug!(cmd, max, "shell command exceeded the maximum output size");
+            stdout.truncate(max);
+            return Err(Error::OutputTooLargeError(cmd, max, Output::from(stdout)));
+        }
+
+        Ok(process::Output {
+            status,
+            stdout,
+            stderr,
+        })
     }
 }
 
@@ -182,8 +544,56 @@ fn to_string(&self) -> String {
     }
 }
 
-/// Prepares a new async command.
-fn new_async_command() -> AsyncCommand {
+/// The resolved command structure.
+///
+/// Represents the program, the arguments and the environment that
+/// would be spawned by a [`Command`], see [`Command::resolve`].
+/// Commands are always wrapped by a shell.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedCommand {
+    /// The shell wrapping the command.
+    pub shell: String,
+
+    /// The argument passed to the shell, before the command.
+    pub shell_arg: String,
+
+    /// The command itself, executed by the shell.
+    pub command: String,
+
+    /// The environment of the command.
+    ///
+    /// It contains the environment inherited from the current
+    /// process, overridden by the variables of the command.
+    pub env: BTreeMap<String, String>,
+}
+
+impl ResolvedCommand {
+    /// Returns the program followed by its arguments.
+    pub fn argv(&self) -> [&str; 3] {
+        [&self.shell, &self.shell_arg, &self.command]
+    }
+}
+
+/// Displays the command executed by the shell.
+impl fmt::Display for ResolvedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.command)
+    }
+}
+
+/// Builds the standard input channel configuration.
+fn stdin(input: &[u8]) -> Stdio {
+    if input.is_empty() {
+        debug!("inherit stdin from parent");
+        Stdio::inherit()
+    } else {
+        debug!("stdin piped");
+        Stdio::piped()
+    }
+}
+
+/// Returns the shell and its argument used to wrap commands.
+fn shell() -> (&'static str, &'static str) {
     #[cfg(not(windows))]
     let windows = false;
     #[cfg(windows)]
@@ -191,9 +601,200 @@ fn new_async_command() -> AsyncCommand {
         .map(|env| env.starts_with("MINGW"))
         .unwrap_or_default();
 
-    let (shell, arg) = if windows { ("cmd", "/C") } else { ("sh", "-c") };
+    if windows {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    }
+}
 
+/// Prepares a new async command.
+fn new_async_command() -> AsyncCommand {
+    let (shell, arg) = shell();
     let mut cmd = AsyncCommand::new(shell);
     cmd.arg(arg);
     cmd
 }
+
+/// Prepares a new blocking command.
+fn new_command() -> process::Command {
+    let (shell, arg) = shell();
+    let mut cmd = process::Command::new(shell);
+    cmd.arg(arg);
+    cmd
+}
+
+/// Kills the given child.
+#[cfg(feature = "async-std")]
+fn kill(child: &Mutex<Child>) -> io::Result<()> {
+    child.lock().unwrap_or_else(PoisonError::into_inner).kill()
+}
+
+/// Kills the given child.
+#[cfg(feature = "tokio")]
+fn kill(child: &Mutex<Child>) -> io::Result<()> {
+    child
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .start_kill()
+}
+
+/// Writes the given input to the given child standard input, then
+/// closes it so that the child does not wait for more input.
+///
+/// The child is killed if the input cannot be written.
+async fn write_input(
+    child: &Mutex<Child>,
+    writer: Option<impl AsyncWrite + Unpin>,
+    input: &[u8],
+) -> Result<()> {
+    if input.is_empty() {
+        return Ok(());
+    }
+
+    let mut writer = writer.ok_or(Error::GetStdinError)?;
+
+    if let Err(err) = writer.write_all(input).await {
+        let _ = kill(child);
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+/// Reads the given child output until the end, or until it exceeds
+/// the given maximum amount of bytes.
+///
+/// When the maximum is exceeded, the child is killed and the output
+/// read so far is returned alongside a `true` overflow flag. The
+/// child is also killed if the output cannot be read, so that the
+/// other output does not wait for it forever.
+async fn read_capped(
+    child: &Mutex<Child>,
+    reader: Option<impl AsyncRead + Unpin>,
+    max: usize,
+) -> io::Result<(Vec<u8>, bool)> {
+    let mut output = Vec::new();
+
+    let Some(mut reader) = reader else {
+        return Ok((output, false));
+    };
+
+    let mut chunk = [0; 8192];
+
+    loop {
+        let n = match reader.read(&mut chunk).await {
+            Ok(n) => n,
+            Err(err) => {
+                let _ = kill(child);
+                return Err(err);
+            }
+        };
+
+        if n == 0 {
+            return Ok((output, false));
+        }
+
+        output.extend_from_slice(&chunk[..n]);
+
+        if output.len() > max {
+            kill(child)?;
+            return Ok((output, true));
+        }
+    }
+}
+
+/// Polls the two given futures concurrently, until both of them are
+/// ready.
+async fn join<A: Future, B: Future>(a: A, b: B) -> (A::Output, B::Output) {
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    let (mut a_output, mut b_output) = (None, None);
+
+    poll_fn(|cx| {
+        if a_output.is_none() {
+            if let Poll::Ready(output) = a.as_mut().poll(cx) {
+                a_output = Some(output);
+            }
+        }
+
+        if b_output.is_none() {
+            if let Poll::Ready(output) = b.as_mut().poll(cx) {
+                b_output = Some(output);
+            }
+        }
+
+        if a_output.is_some() && b_output.is_some() {
+            Poll::Ready((a_output.take().unwrap(), b_output.take().unwrap()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Kills the given child, synchronously.
+fn kill_blocking(child: &Mutex<process::Child>) -> io::Result<()> {
+    child.lock().unwrap_or_else(PoisonError::into_inner).kill()
+}
+
+/// Writes the given input to the given child standard input, then
+/// closes it, synchronously.
+///
+/// See [`write_input`].
+fn write_input_blocking(
+    child: &Mutex<process::Child>,
+    writer: Option<impl Write>,
+    input: &[u8],
+) -> Result<()> {
+    if input.is_empty() {
+        return Ok(());
+    }
+
+    let mut writer = writer.ok_or(Error::GetStdinError)?;
+
+    if let Err(err) = writer.write_all(input) {
+        let _ = kill_blocking(child);
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+/// Reads the given child output until the end, or until it exceeds
+/// the given maximum amount of bytes, synchronously.
+///
+/// See [`read_capped`].
+fn read_capped_blocking(
+    child: &Mutex<process::Child>,
+    reader: Option<impl Read>,
+    max: usize,
+) -> io::Result<(Vec<u8>, bool)> {
+    let mut output = Vec::new();
+
+    let Some(mut reader) = reader else {
+        return Ok((output, false));
+    };
+
+    let mut chunk = [0; 8192];
+
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(n) => n,
+            Err(err) => {
+                let _ = kill_blocking(child);
+                return Err(err);
+            }
+        };
+
+        if n == 0 {
+            return Ok((output, false));
+        }
+
+        output.extend_from_slice(&chunk[..n]);
+
+        if output.len() > max {
+            kill_blocking(child)?;
+            return Ok((output, true));
+        }
+    }
+}
@@ -7,6 +7,8 @@
 
 use thiserror::Error;
 
+use crate::{Output, ResolvedCommand};
+
 /// The global `Result` alias of the library.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -19,6 +21,12 @@ pub enum Error {
     GetExitStatusCodeNotAvailableError(String),
     #[error("command {0} returned non-zero exit status code {1}: {2}")]
     GetExitStatusCodeNonZeroError(String, i32, String),
+    #[error("command {0} exceeded the maximum output size of {1} bytes")]
+    OutputTooLargeError(String, usize, Output),
+    #[error("cannot pipe input to interactive command {0}")]
+    PipeInputToInteractiveCommandError(String),
+    #[error("dry-run mode enabled, would run: {0}")]
+    DryRunError(ResolvedCommand),
     #[error("cannot parse command output as string")]
     ParseOutputAsUtf8StringError(#[source] FromUtf8Error),
 
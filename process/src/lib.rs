@@ -8,7 +8,7 @@
 
 #[doc(inline)]
 pub use crate::{
-    command::Command,
+    command::{Command, ResolvedCommand},
     error::{Error, Result},
     output::Output,
     pipeline::Pipeline,
@@ -1,7 +1,8 @@
 //! # Output
 //!
 //! Module dedicated to command output. It only exposes an [`Output`]
-//! struct, a wrapper around raw `Vec<u8>` output.
+//! struct, a wrapper around raw `Vec<u8>` output and its exit status
+//! code.
 
 use std::ops::{Deref, DerefMut};
 
@@ -12,13 +13,42 @@
 /// The only role of this struct is to provide convenient functions to
 /// export command output.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct Output(Vec<u8>);
+pub struct Output {
+    /// The raw output.
+    bytes: Vec<u8>,
+
+    /// The exit status code of the command.
+    ///
+    /// Defaults to `0`.
+    code: i32,
+}
 
 impl Output {
     pub fn new(output: impl IntoIterator<Item = u8>) -> Self {
         Self::from(output.into_iter().collect::<Vec<_>>())
     }
 
+    /// Defines the exit status code of the command, using the builder
+    /// pattern.
+    pub fn with_code(mut self, code: i32) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// Returns the exit status code of the command.
+    ///
+    /// It is always `0`, unless the command has been configured with
+    /// acceptable non-zero exit status codes (see
+    /// [`crate::Command::with_acceptable_exit_codes`]).
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Returns `true` if the command exited with a zero status code.
+    pub fn is_success(&self) -> bool {
+        self.code == 0
+    }
+
     /// Reads the command output as string lossy.
     pub fn to_string_lossy(&self) -> String {
         String::from_utf8_lossy(self).to_string()
@@ -29,25 +59,25 @@ impl Deref for Output {
     type Target = Vec<u8>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.bytes
     }
 }
 
 impl DerefMut for Output {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.bytes
     }
 }
 
 impl From<Vec<u8>> for Output {
-    fn from(output: Vec<u8>) -> Self {
-        Self(output)
+    fn from(bytes: Vec<u8>) -> Self {
+        Self { bytes, code: 0 }
     }
 }
 
 impl From<Output> for Vec<u8> {
     fn from(output: Output) -> Self {
-        output.0
+        output.bytes
     }
 }
 
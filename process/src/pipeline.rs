@@ -52,18 +52,19 @@ pub async fn run(&self) -> Result<Output> {
     /// Run the command pipeline with the given initial input.
     ///
     /// After the first command executes, the input is replaced with
-    /// its output.
+    /// its output. The exit status code of the pipeline is the one of
+    /// its last command.
     pub async fn run_with(&self, input: impl IntoIterator<Item = u8>) -> Result<Output> {
         info!("run pipeline of {} commands", self.len());
 
-        let mut output: Vec<u8> = input.into_iter().collect();
+        let mut output = Output::new(input);
 
         for (i, cmd) in self.iter().enumerate() {
             debug!("run command {} from pipeline", i + 1);
-            output = cmd.run_with(&output).await?.into();
+            output = cmd.run_with(&*output).await?;
         }
 
-        Ok(Output::from(output))
+        Ok(output)
     }
 }
 
@@ -19,3 +19,190 @@ async fn test_command() {
         err => panic!("unexpected error: {err:?}"),
     }
 }
+
+#[test_log::test(test)]
+async fn test_command_large_input_and_outputs() {
+    // the input is larger than the pipe buffers, so this test only
+    // ends if the input is written while the outputs are read
+    let input = "hello, world!\n".repeat(100_000);
+    let cmd = Command::new("tee /dev/stderr");
+    let out = cmd.run_with(&input).await.unwrap().to_string_lossy();
+    assert_eq!(out, input);
+
+    let cmd = Command::new("tee /dev/stderr");
+    let out = cmd.run_with_blocking(&input).unwrap().to_string_lossy();
+    assert_eq!(out, input);
+}
+
+#[test_log::test(test)]
+async fn test_command_max_output_bytes() {
+    let cmd = Command::new("echo hello, world!").with_max_output_bytes(Some(1024));
+    let out = cmd.run().await.unwrap().to_string_lossy();
+    assert_eq!(out, "hello, world!\n");
+
+    // `yes` never stops by itself, so this test only ends if the
+    // command is killed once it exceeds the maximum output size
+    let cmd = Command::new("yes").with_max_output_bytes(Some(1024));
+    match cmd.run().await.unwrap_err() {
+        Error::OutputTooLargeError(cmd, max, out) => {
+            assert_eq!(cmd, "yes");
+            assert_eq!(max, 1024);
+            assert_eq!(out.len(), 1024);
+            assert!(out.to_string_lossy().starts_with("y\ny\n"));
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    let cmd = Command::new("bad").with_max_output_bytes(Some(1024));
+    match cmd.run().await.unwrap_err() {
+        Error::GetExitStatusCodeNonZeroError(cmd, status, err) => {
+            assert_eq!(cmd, "bad");
+            assert_eq!(status, 127);
+            assert_eq!(err, "sh: line 1: bad: command not found\n");
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test_log::test]
+fn test_command_blocking() {
+    let cmd = Command::new("echo hello, world!");
+    let out = cmd.run_blocking().unwrap().to_string_lossy();
+    assert_eq!(out, "hello, world!\n");
+
+    let cmd = Command::new("cat");
+    let out = cmd
+        .run_with_blocking("hello, world!")
+        .unwrap()
+        .to_string_lossy();
+    assert_eq!(out, "hello, world!");
+
+    match Command::new("bad").run_blocking().unwrap_err() {
+        Error::GetExitStatusCodeNonZeroError(cmd, status, err) => {
+            assert_eq!(cmd, "bad");
+            assert_eq!(status, 127);
+            assert_eq!(err, "sh: line 1: bad: command not found\n");
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    let cmd = Command::new("yes").with_max_output_bytes(Some(1024));
+    match cmd.run_blocking().unwrap_err() {
+        Error::OutputTooLargeError(cmd, max, out) => {
+            assert_eq!(cmd, "yes");
+            assert_eq!(max, 1024);
+            assert_eq!(out.len(), 1024);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test_log::test(test)]
+async fn test_command_blocking_within_runtime() {
+    let cmd = Command::new("echo hello, world!");
+    let out = cmd.run_blocking().unwrap().to_string_lossy();
+    assert_eq!(out, "hello, world!\n");
+}
+
+#[test_log::test(test)]
+async fn test_command_dry_run() {
+    // the marker file would be created if the command was spawned
+    let marker = std::env::temp_dir().join(format!("process-dry-run-{}", std::process::id()));
+    let inner = format!("touch {}; exit 1", marker.display());
+    let cmd = Command::new(&inner)
+        .with_env("PROCESS_DRY_RUN", "yes")
+        .with_dry_run(true);
+
+    let resolved = cmd.resolve();
+    assert_eq!(resolved.argv(), ["sh", "-c", inner.as_str()]);
+    assert_eq!(resolved.to_string(), cmd.to_string());
+    assert_eq!(resolved.env.get("PROCESS_DRY_RUN").unwrap(), "yes");
+
+    // dry-run commands never produce any output
+    match cmd.run_with("hello, world!").await.unwrap_err() {
+        Error::DryRunError(cmd) => assert_eq!(cmd, resolved),
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    match cmd.run_blocking().unwrap_err() {
+        Error::DryRunError(cmd) => assert_eq!(cmd, resolved),
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    assert!(!marker.exists());
+}
+
+#[test_log::test(test)]
+async fn test_command_env() {
+    let cmd = Command::new("echo $PROCESS_ENV").with_env("PROCESS_ENV", "hello, world!");
+    let out = cmd.run().await.unwrap().to_string_lossy();
+    assert_eq!(out, "hello, world!\n");
+
+    let out = cmd.run_blocking().unwrap().to_string_lossy();
+    assert_eq!(out, "hello, world!\n");
+}
+
+#[test_log::test(test)]
+async fn test_command_interactive() {
+    // the command prints the terminal it is attached to, or falls
+    // back to a message when stdin is not a terminal
+    let inner = "if [ -t 0 ]; then tty; else echo no tty; fi";
+    let cmd = Command::new(inner).with_interactive(true);
+    assert!(cmd.is_interactive());
+
+    // the output goes straight to the terminal, it is not captured
+    let out = cmd.run().await.unwrap().to_string_lossy();
+    assert_eq!(out, "");
+
+    let out = cmd.run_blocking().unwrap().to_string_lossy();
+    assert_eq!(out, "");
+
+    // the output is not captured, even when asked to
+    let cmd = cmd.with_output_piped(true);
+    let out = cmd.run().await.unwrap().to_string_lossy();
+    assert_eq!(out, "");
+
+    match cmd.run_with("hello, world!").await.unwrap_err() {
+        Error::PipeInputToInteractiveCommandError(cmd) => {
+            assert_eq!(cmd, inner);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[test_log::test(test)]
+async fn test_command_acceptable_exit_codes() {
+    let out = Command::new("echo hello, world!").run().await.unwrap();
+    assert_eq!(out.code(), 0);
+    assert!(out.is_success());
+
+    // non-zero exit status codes fail by default
+    let inner = "echo skipped; exit 3";
+    match Command::new(inner).run().await.unwrap_err() {
+        Error::GetExitStatusCodeNonZeroError(cmd, status, _) => {
+            assert_eq!(cmd, inner);
+            assert_eq!(status, 3);
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    let cmd = Command::new(inner).with_acceptable_exit_codes([3, 4]);
+    assert!(cmd.is_acceptable_exit_code(0));
+    assert!(cmd.is_acceptable_exit_code(3));
+    assert!(!cmd.is_acceptable_exit_code(1));
+
+    let out = cmd.run().await.unwrap();
+    assert_eq!(out.code(), 3);
+    assert!(!out.is_success());
+    assert_eq!(out.to_string_lossy(), "skipped\n");
+
+    let out = cmd.run_blocking().unwrap();
+    assert_eq!(out.code(), 3);
+
+    // other non-zero exit status codes still fail
+    let cmd = Command::new("exit 1").with_acceptable_exit_codes([3]);
+    match cmd.run().await.unwrap_err() {
+        Error::GetExitStatusCodeNonZeroError(_, status, _) => assert_eq!(status, 1),
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
@@ -10,7 +10,7 @@
 #[cfg(feature = "tokio")]
 pub mod tokio;
 
-use tracing::debug;
+use tracing::{debug, trace};
 
 /// The main structure of the IMAP module.
 ///
@@ -58,20 +58,20 @@ fn next(&mut self) -> Option<State> {
             }
             Some(State::DiscardHandshake) => {
                 if let Some(Event::HandshakeDiscarded(line)) = event {
-                    debug!("discarded IMAP greeting: {line:?}");
+                    trace!("discarded IMAP greeting: {line:?}");
                     self.state = Some(State::WriteStarttlsCommand);
                 }
             }
             Some(State::WriteStarttlsCommand) => {
                 if let Some(Event::StarttlsCommandWrote(_)) = event {
                     let cmd = Self::COMMAND;
-                    debug!("wrote IMAP STARTTLS command: {cmd:?}");
+                    trace!("wrote IMAP STARTTLS command: {cmd:?}");
                     self.state = Some(State::DiscardResponse);
                 }
             }
             Some(State::DiscardResponse) => {
                 if let Some(Event::ResponseDiscarded(line)) = event {
-                    debug!("discarded IMAP response: {line:?}");
+                    trace!("discarded IMAP response: {line:?}");
 
                     if line.starts_with("A ") {
                         debug!("stream ready for TLS negociation");
@@ -10,6 +10,8 @@ pub enum Secret {
     #[default]
     Empty,
     Raw(String),
+    #[serde(alias = "env")]
+    EnvVar(String),
     #[cfg(feature = "command")]
     #[serde(alias = "cmd")]
     Command(Command),
@@ -29,6 +31,7 @@ fn from(secret: Secret) -> Self {
         match secret {
             Secret::Empty => Self::Empty,
             Secret::Raw(secret) => Self::Raw(secret),
+            Secret::EnvVar(name) => Self::EnvVar(name),
             #[cfg(feature = "command")]
             Secret::Command(cmd) => Self::Command(cmd),
             #[cfg(not(feature = "command"))]
@@ -3,6 +3,8 @@
 //! Module dedicated to secret errors. It contains an [`Error`] enum
 //! based on [`thiserror::Error`] and a type alias [`Result`].
 
+use std::env::VarError;
+
 use thiserror::Error;
 
 /// The global `Result` alias of the library.
@@ -13,6 +15,8 @@
 pub enum Error {
     #[error("cannot get empty secret")]
     GetEmptySecretError,
+    #[error("cannot get secret from environment variable {1}")]
+    GetSecretFromEnvVarError(#[source] VarError, String),
     #[cfg(feature = "command")]
     #[error("cannot get secret from command")]
     GetSecretFromCommand(#[source] process::Error),
@@ -5,6 +5,8 @@
 pub(crate) mod derive;
 mod error;
 
+use std::env::{self, VarError};
+
 #[cfg(feature = "keyring")]
 pub use keyring;
 #[cfg(feature = "keyring")]
@@ -14,6 +16,8 @@
 #[cfg(feature = "command")]
 use process::Command;
 use tracing::debug;
+pub use zeroize;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[doc(inline)]
 pub use crate::error::{Error, Result};
@@ -32,8 +36,23 @@
 
 /// The secret.
 ///
-/// A secret can be retrieved either from a raw string, from a shell
-/// command or from a keyring entry.
+/// A secret can be retrieved either from a raw string, from an
+/// environment variable, from a shell command or from a keyring
+/// entry.
+///
+/// # Zeroization
+///
+/// Secret values are resolved into [`Zeroizing`] buffers: see
+/// [`Secret::get_zeroizing`] and [`Secret::find_zeroizing`]. The
+/// intermediate buffers used during the resolution (like the output
+/// of the shell command) are wiped as well, and so is the value of
+/// the raw secret when the secret is dropped.
+///
+/// This is a best-effort guarantee. Copies made outside of this
+/// library are not wiped: the environment block of the process, the
+/// buffers used by the keyring or by the shell command runner, and
+/// the [`String`] returned by [`Secret::get`] and [`Secret::find`],
+/// which the caller is responsible for.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[cfg_attr(
     feature = "derive",
@@ -51,6 +70,13 @@ pub enum Secret {
     /// recommended. Yet it works well for testing purpose.
     Raw(String),
 
+    /// The secret is contained in the given environment variable.
+    ///
+    /// The variable is read each time the secret is accessed, which
+    /// makes this variant a good fit for containerized deployments.
+    #[cfg_attr(feature = "derive", serde(alias = "env"))]
+    EnvVar(String),
+
     /// The secret is exposed by the given shell command.
     ///
     /// This variant takes the secret from the first line returned by
@@ -80,6 +106,11 @@ pub fn new_raw(raw: impl ToString) -> Self {
         Self::Raw(raw.to_string())
     }
 
+    /// Creates a new secret from the given environment variable name.
+    pub fn new_env_var(name: impl ToString) -> Self {
+        Self::EnvVar(name.to_string())
+    }
+
     /// Creates a new secret from the given shell command.
     #[cfg(feature = "command")]
     pub fn new_command(cmd: impl ToString) -> Self {
@@ -108,24 +139,35 @@ pub fn is_empty(&self) -> bool {
 
     /// Gets the secret value.
     ///
-    /// The command-based secret execute its shell command and returns
+    /// Like [`Secret::get_zeroizing`], but returns the inner
+    /// [`String`], which is not wiped on drop.
+    pub async fn get(&self) -> Result<String> {
+        let mut secret = self.get_zeroizing().await?;
+        Ok(std::mem::take(&mut *secret))
+    }
+
+    /// Gets the secret value, wiped from memory on drop.
+    ///
+    /// The environment variable-based secret reads its variable, the
+    /// command-based secret execute its shell command and returns
     /// the output, and the keyring-based secret retrieves the value
     /// from the global keyring using its inner key.
-    pub async fn get(&self) -> Result<String> {
+    pub async fn get_zeroizing(&self) -> Result<Zeroizing<String>> {
         match self {
             Self::Empty => {
                 return Err(Error::GetEmptySecretError);
             }
             Self::Raw(secret) => {
-                return Ok(secret.clone());
+                return Ok(Zeroizing::new(secret.clone()));
+            }
+            Self::EnvVar(name) => {
+                let secret = env::var(name)
+                    .map_err(|err| Error::GetSecretFromEnvVarError(err, name.clone()))?;
+                Ok(Zeroizing::new(secret))
             }
             #[cfg(feature = "command")]
             Self::Command(cmd) => {
-                let full_secret = cmd
-                    .run()
-                    .await
-                    .map_err(Error::GetSecretFromCommand)?
-                    .to_string_lossy();
+                let full_secret = run_command(cmd).await?;
 
                 let first_line_secret = full_secret
                     .lines()
@@ -134,44 +176,59 @@ pub async fn get(&self) -> Result<String> {
                     .ok_or(Error::GetSecretFromCommandEmptyOutputError)?
                     .to_owned();
 
-                Ok(first_line_secret)
+                Ok(Zeroizing::new(first_line_secret))
             }
             #[cfg(feature = "keyring")]
             Self::Keyring(entry) => {
                 let secret = entry.get_secret().await?;
-                Ok(secret)
+                Ok(Zeroizing::new(secret))
             }
         }
     }
 
     /// Finds the secret value.
     ///
-    /// Like [`Secret::get`], but returns [`None`] if the secret value
-    /// is not found or empty.
+    /// Like [`Secret::find_zeroizing`], but returns the inner
+    /// [`String`], which is not wiped on drop.
     pub async fn find(&self) -> Result<Option<String>> {
+        let secret = self.find_zeroizing().await?;
+        Ok(secret.map(|mut secret| std::mem::take(&mut *secret)))
+    }
+
+    /// Finds the secret value, wiped from memory on drop.
+    ///
+    /// Like [`Secret::get_zeroizing`], but returns [`None`] if the
+    /// secret value is not found or empty.
+    pub async fn find_zeroizing(&self) -> Result<Option<Zeroizing<String>>> {
         match self {
             Self::Empty => {
                 return Ok(None);
             }
             Self::Raw(secret) => {
-                return Ok(Some(secret.clone()));
+                return Ok(Some(Zeroizing::new(secret.clone())));
             }
+            Self::EnvVar(name) => match env::var(name) {
+                Ok(secret) => Ok(Some(Zeroizing::new(secret))),
+                Err(VarError::NotPresent) => Ok(None),
+                Err(err) => Err(Error::GetSecretFromEnvVarError(err, name.clone())),
+            },
             #[cfg(feature = "command")]
             Self::Command(cmd) => {
-                let full_secret = cmd
-                    .run()
-                    .await
-                    .map_err(Error::GetSecretFromCommand)?
-                    .to_string_lossy();
+                let full_secret = run_command(cmd).await?;
 
-                let first_line_secret = full_secret.lines().take(1).next().map(ToOwned::to_owned);
+                let first_line_secret = full_secret
+                    .lines()
+                    .take(1)
+                    .next()
+                    .map(ToOwned::to_owned)
+                    .map(Zeroizing::new);
 
                 Ok(first_line_secret)
             }
             #[cfg(feature = "keyring")]
             Self::Keyring(entry) => {
                 let secret = entry.find_secret().await?;
-                Ok(secret)
+                Ok(secret.map(Zeroizing::new))
             }
         }
     }
@@ -180,12 +237,17 @@ pub async fn find(&self) -> Result<Option<String>> {
     ///
     /// This is only applicable for raw secrets and keyring-based
     /// secrets. A secret value cannot be changed for command-base
-    /// secrets, since the value is the output of the command.
+    /// secrets, since the value is the output of the command, nor
+    /// for environment variable-based secrets.
     pub async fn set(&mut self, secret: impl ToString) -> Result<String> {
         match self {
             Self::Raw(prev) => {
+                prev.zeroize();
                 *prev = secret.to_string();
             }
+            Self::EnvVar(_) => {
+                debug!("cannot change value of environment variable-based secret");
+            }
             #[cfg(feature = "command")]
             Self::Command(_) => {
                 debug!("cannot change value of command-based secret");
@@ -259,3 +321,35 @@ pub fn replace_with_keyring_if_empty(&mut self, entry: impl ToString) -> Result<
         Ok(())
     }
 }
+
+impl Zeroize for Secret {
+    /// Wipes the value of the raw secret from memory.
+    ///
+    /// This function has no effect on other variants, since they do
+    /// not hold the secret value.
+    fn zeroize(&mut self) {
+        if let Self::Raw(secret) = self {
+            secret.zeroize();
+        }
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Secret {}
+
+/// Runs the given shell command and returns its output as a string.
+///
+/// Both the raw output and the string are wiped from memory on drop.
+#[cfg(feature = "command")]
+async fn run_command(cmd: &Command) -> Result<Zeroizing<String>> {
+    let output: Vec<u8> = cmd.run().await.map_err(Error::GetSecretFromCommand)?.into();
+    let output = Zeroizing::new(output);
+    Ok(Zeroizing::new(
+        String::from_utf8_lossy(&output).into_owned(),
+    ))
+}
@@ -0,0 +1,36 @@
+use std::env;
+
+#[cfg(feature = "async-std")]
+use async_std::test;
+use secret::{Error, Secret};
+#[cfg(feature = "tokio")]
+use tokio::test;
+
+#[test_log::test(test)]
+async fn env_var() {
+    let mut secret = Secret::new_env_var("SECRET_LIB_TEST_ENV_VAR");
+
+    env::set_var("SECRET_LIB_TEST_ENV_VAR", "secret");
+    assert_eq!(secret.get().await.unwrap(), "secret");
+    assert_eq!(secret.find().await.unwrap(), Some("secret".into()));
+
+    secret.set("secret2").await.unwrap();
+    // secret cannot be changed from env var variant
+    assert_eq!(secret.get().await.unwrap(), "secret");
+
+    // secret is read at access time
+    env::set_var("SECRET_LIB_TEST_ENV_VAR", "secret3");
+    assert_eq!(secret.get().await.unwrap(), "secret3");
+
+    env::remove_var("SECRET_LIB_TEST_ENV_VAR");
+    assert_eq!(secret.find().await.unwrap(), None);
+    match secret.get().await.unwrap_err() {
+        Error::GetSecretFromEnvVarError(env::VarError::NotPresent, name) => {
+            assert_eq!(name, "SECRET_LIB_TEST_ENV_VAR");
+        }
+        err => panic!("unexpected error: {err:?}"),
+    }
+
+    secret.delete().await.unwrap();
+    assert_eq!(secret.find().await.unwrap(), None);
+}
@@ -0,0 +1,26 @@
+#[cfg(feature = "async-std")]
+use async_std::test;
+use secret::{
+    zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing},
+    Secret,
+};
+#[cfg(feature = "tokio")]
+use tokio::test;
+
+fn assert_zeroize_on_drop<T: Zeroize + ZeroizeOnDrop>(_: &T) {}
+
+#[test_log::test(test)]
+async fn zeroize() {
+    let mut secret = Secret::new_raw("secret");
+    assert_zeroize_on_drop(&secret);
+
+    let value: Zeroizing<String> = secret.get_zeroizing().await.unwrap();
+    assert_zeroize_on_drop(&value);
+    assert_eq!(*value, "secret");
+
+    let value = secret.find_zeroizing().await.unwrap().unwrap();
+    assert_eq!(*value, "secret");
+
+    secret.zeroize();
+    assert_eq!(secret, Secret::new_raw(""));
+}
@@ -3,14 +3,28 @@
 //! The client connects to the server, sends requests in order to
 //! control the timer and receive responses.
 //!
-//! The client must implement the [`Client`] trait.
+//! The client must implement the [`Client`] trait. See
+//! [`reconnect::ReconnectClient`] to retry requests when the server
+//! restarts.
 
+pub mod reconnect;
 #[cfg(feature = "tcp-client")]
 pub mod tcp;
 
-use std::io::{Error, ErrorKind, Result};
+use std::{
+    io::{Error, ErrorKind, Result},
+    time::Duration,
+};
 
+#[cfg(feature = "async-std")]
+use async_std::task::sleep;
 use async_trait::async_trait;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+#[cfg(feature = "tokio")]
+use tokio::time::sleep;
 use tracing::{info, trace};
 
 use crate::{
@@ -33,13 +47,9 @@ pub trait Client: Send + Sync {
     async fn start(&self) -> Result<()> {
         info!("sending request to start timer");
 
-        match self.send(Request::Start).await {
-            Ok(Response::Ok) => Ok(()),
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+        match self.send(Request::Start).await? {
+            Response::Ok => Ok(()),
+            res => Err(unexpected_response(res)),
         }
     }
 
@@ -47,16 +57,27 @@ async fn start(&self) -> Result<()> {
     async fn get(&self) -> Result<Timer> {
         info!("sending request to get timer");
 
-        match self.send(Request::Get).await {
-            Ok(Response::Timer(timer)) => {
+        match self.send(Request::Get).await? {
+            Response::Timer(timer) => {
                 trace!("timer: {timer:#?}");
                 Ok(timer)
             }
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+            res => Err(unexpected_response(res)),
+        }
+    }
+
+    /// Send the request to get all the timers at once.
+    ///
+    /// Each timer comes with its name.
+    async fn get_all(&self) -> Result<Vec<(String, Timer)>> {
+        info!("sending request to get all timers");
+
+        match self.send(Request::GetAll).await? {
+            Response::Timers(timers) => {
+                trace!("timers: {timers:#?}");
+                Ok(timers)
+            }
+            res => Err(unexpected_response(res)),
         }
     }
 
@@ -64,13 +85,40 @@ async fn get(&self) -> Result<Timer> {
     async fn set(&self, duration: usize) -> Result<()> {
         info!("sending request to set timer duration");
 
-        match self.send(Request::Set(duration)).await {
-            Ok(Response::Ok) => Ok(()),
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+        match self.send(Request::Set(duration)).await? {
+            Response::Ok => Ok(()),
+            res => Err(unexpected_response(res)),
+        }
+    }
+
+    /// Send the request to add the given duration to the current
+    /// timer cycle, and return the updated timer.
+    async fn add(&self, duration: Duration) -> Result<Timer> {
+        info!("sending request to add {duration:?} to timer");
+
+        match self.send(Request::Add(duration.as_secs() as usize)).await? {
+            Response::Timer(timer) => {
+                trace!("timer: {timer:#?}");
+                Ok(timer)
+            }
+            res => Err(unexpected_response(res)),
+        }
+    }
+
+    /// Send the request to subtract the given duration from the
+    /// current timer cycle, and return the updated timer.
+    async fn subtract(&self, duration: Duration) -> Result<Timer> {
+        info!("sending request to subtract {duration:?} from timer");
+
+        match self
+            .send(Request::Subtract(duration.as_secs() as usize))
+            .await?
+        {
+            Response::Timer(timer) => {
+                trace!("timer: {timer:#?}");
+                Ok(timer)
+            }
+            res => Err(unexpected_response(res)),
         }
     }
 
@@ -78,13 +126,9 @@ async fn set(&self, duration: usize) -> Result<()> {
     async fn pause(&self) -> Result<()> {
         info!("sending request to pause timer");
 
-        match self.send(Request::Pause).await {
-            Ok(Response::Ok) => Ok(()),
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+        match self.send(Request::Pause).await? {
+            Response::Ok => Ok(()),
+            res => Err(unexpected_response(res)),
         }
     }
 
@@ -92,13 +136,9 @@ async fn pause(&self) -> Result<()> {
     async fn resume(&self) -> Result<()> {
         info!("sending request to resume timer");
 
-        match self.send(Request::Resume).await {
-            Ok(Response::Ok) => Ok(()),
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+        match self.send(Request::Resume).await? {
+            Response::Ok => Ok(()),
+            res => Err(unexpected_response(res)),
         }
     }
 
@@ -106,15 +146,39 @@ async fn resume(&self) -> Result<()> {
     async fn stop(&self) -> Result<()> {
         info!("sending request to stop timer");
 
-        match self.send(Request::Stop).await {
-            Ok(Response::Ok) => Ok(()),
-            Ok(res) => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("invalid response: {res:?}"),
-            )),
-            Err(err) => Err(Error::new(ErrorKind::Other, err)),
+        match self.send(Request::Stop).await? {
+            Response::Ok => Ok(()),
+            res => Err(unexpected_response(res)),
         }
     }
+
+    /// Watch the timer.
+    ///
+    /// The returned stream sends the get timer request straight
+    /// away, then at every given interval, and yields the associated
+    /// responses. This is useful to render timer ticks. The stream
+    /// never ends: errors are yielded, then the next request is sent
+    /// at the next interval.
+    fn watch(&self, interval: Duration) -> BoxStream<'_, Result<Response>> {
+        info!("watching timer");
+
+        stream::unfold(true, move |first| async move {
+            if !first {
+                sleep(interval).await;
+            }
+
+            Some((self.send(Request::Get).await, false))
+        })
+        .boxed()
+    }
+}
+
+/// Build the error matching the given unexpected response.
+fn unexpected_response(res: Response) -> Error {
+    match res {
+        Response::Error(err) => Error::new(ErrorKind::Other, format!("server error: {err}")),
+        res => Error::new(ErrorKind::InvalidData, format!("invalid response: {res:?}")),
+    }
 }
 
 /// The client stream trait.
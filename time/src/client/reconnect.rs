@@ -0,0 +1,157 @@
+//! # Reconnecting client
+//!
+//! This module contains a [`Client`] wrapper that sends requests
+//! again when the server cannot be reached, for example because it
+//! is restarting.
+
+use std::{
+    io::{ErrorKind, Result},
+    time::Duration,
+};
+
+#[cfg(feature = "async-std")]
+use async_std::task::sleep;
+use async_trait::async_trait;
+#[cfg(feature = "tokio")]
+use tokio::time::sleep;
+use tracing::debug;
+
+use crate::{request::Request, response::Response};
+
+use super::Client;
+
+/// The reconnecting client.
+///
+/// This [`Client`] wraps another client and retries requests that
+/// failed because of a connection error, waiting between attempts
+/// with an exponential backoff.
+///
+/// A request may have been received by the server before the
+/// connection got lost. Idempotent requests are therefore retried
+/// whatever the connection error, while [`Request::Add`] and
+/// [`Request::Subtract`] are only retried when the connection could
+/// not be established, which means that they have not been written:
+/// they are never applied twice.
+pub struct ReconnectClient {
+    /// The wrapped client.
+    client: Box<dyn Client>,
+
+    /// The maximum number of attempts per request.
+    pub max_attempts: usize,
+
+    /// The delay before the first retry.
+    ///
+    /// The delay is doubled after each failed attempt, up to
+    /// [`ReconnectClient::max_backoff`].
+    pub backoff: Duration,
+
+    /// The maximum delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl ReconnectClient {
+    /// The default maximum number of attempts per request.
+    pub const MAX_ATTEMPTS: usize = 5;
+
+    /// The default delay before the first retry.
+    pub const BACKOFF: Duration = Duration::from_millis(100);
+
+    /// The default maximum delay between two attempts.
+    pub const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+    /// Create a new reconnecting client wrapping the given client.
+    pub fn new(client: Box<dyn Client>) -> Self {
+        Self {
+            client,
+            max_attempts: Self::MAX_ATTEMPTS,
+            backoff: Self::BACKOFF,
+            max_backoff: Self::MAX_BACKOFF,
+        }
+    }
+
+    /// Create a new reconnecting client wrapping the given client,
+    /// using default options.
+    pub fn new_boxed(client: Box<dyn Client>) -> Box<dyn Client> {
+        Box::new(Self::new(client))
+    }
+
+    /// Change the maximum number of attempts per request, using the
+    /// builder pattern.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Change the delay before the first retry, using the builder
+    /// pattern.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Change the maximum delay between two attempts, using the
+    /// builder pattern.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+}
+
+#[async_trait]
+impl Client for ReconnectClient {
+    /// Send the given request using the wrapped client, retrying on
+    /// connection errors.
+    async fn send(&self, req: Request) -> Result<Response> {
+        let is_retryable = if is_idempotent(&req) {
+            is_connection_error
+        } else {
+            is_connect_error
+        };
+
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+
+        loop {
+            match self.client.send(req.clone()).await {
+                Err(err) if attempt < self.max_attempts && is_retryable(err.kind()) => {
+                    debug!(attempt, ?backoff, "cannot reach server, retrying: {err}");
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                res => break res,
+            }
+        }
+    }
+}
+
+/// Return `true` if sending the given request more than once has the
+/// same effect as sending it once.
+fn is_idempotent(req: &Request) -> bool {
+    !matches!(req, Request::Add(_) | Request::Subtract(_))
+}
+
+/// Return `true` if the given error kind means that the connection
+/// could not be established, before anything got written.
+fn is_connect_error(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::ConnectionRefused | ErrorKind::AddrNotAvailable
+    )
+}
+
+/// Return `true` if the given error kind means that the server could
+/// not be reached, or that the connection has been lost.
+fn is_connection_error(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::AddrNotAvailable
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+            | ErrorKind::UnexpectedEof
+    )
+}
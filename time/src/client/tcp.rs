@@ -6,14 +6,13 @@
 use std::io::{Error, ErrorKind, Result};
 
 use async_trait::async_trait;
-use futures::{AsyncBufReadExt, AsyncWriteExt};
 use tracing::debug;
 
 use crate::{
+    codec,
     request::{Request, RequestWriter},
     response::{Response, ResponseReader},
-    tcp::{TcpHandler, TcpStream},
-    timer::Timer,
+    tcp::{TcpHandler, TcpHandshake, TcpStream},
 };
 
 use super::{Client, ClientStream};
@@ -21,13 +20,24 @@
 /// The TCP client.
 ///
 /// This [`Client`] uses the TCP protocol to connect to a listener, to
-/// read responses and write requests.
+/// read responses and write requests using the [`codec`] wire
+/// format.
+///
+/// When a token is defined, the client authenticates with it before
+/// sending its request, see [`TcpHandshake`].
 pub struct TcpClient {
     /// The TCP host the client should connect to.
     pub host: String,
 
     /// The TCP port the client should connect to.
     pub port: u16,
+
+    /// The token used to authenticate to the server.
+    ///
+    /// The token should not be hardcoded: it should rather be
+    /// retrieved from a secret store (keyring, password manager
+    /// command etc.) or from an environment variable.
+    pub token: Option<String>,
 }
 
 impl TcpClient {
@@ -36,6 +46,21 @@ pub fn new_boxed(host: impl ToString, port: u16) -> Box<dyn Client> {
         Box::new(Self {
             host: host.to_string(),
             port,
+            token: None,
+        })
+    }
+
+    /// Create a new TCP client using the given host and port,
+    /// authenticating to the server with the given token.
+    pub fn new_boxed_with_token(
+        host: impl ToString,
+        port: u16,
+        token: impl ToString,
+    ) -> Box<dyn Client> {
+        Box::new(Self {
+            host: host.to_string(),
+            port,
+            token: Some(token.to_string()),
         })
     }
 }
@@ -47,56 +72,43 @@ async fn send(&self, req: Request) -> Result<Response> {
         debug!("TCP connection accepted");
         let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
         let mut handler = TcpHandler::new(stream);
+
+        if let Some(token) = &self.token {
+            handler.send_token(token).await?;
+        }
+
         handler.handle(req).await
     }
 }
 
+impl TcpHandler {
+    /// Send the handshake containing the given token, then wait for
+    /// the server to accept it.
+    async fn send_token(&mut self, token: &str) -> Result<()> {
+        let handshake = TcpHandshake::Auth(token.to_owned());
+        codec::write(&mut self.writer, &handshake).await?;
+
+        match codec::read(&mut self.reader).await? {
+            Response::Ok => Ok(()),
+            Response::Error(err) => Err(Error::new(ErrorKind::PermissionDenied, err)),
+            res => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid handshake response: {res:?}"),
+            )),
+        }
+    }
+}
+
 #[async_trait]
 impl RequestWriter for TcpHandler {
     async fn write(&mut self, req: Request) -> Result<()> {
-        let req = match req {
-            Request::Start => "start\n".to_owned(),
-            Request::Get => "get\n".to_owned(),
-            Request::Set(duration) => format!("set {duration}\n"),
-            Request::Pause => "pause\n".to_owned(),
-            Request::Resume => "resume\n".to_owned(),
-            Request::Stop => "stop\n".to_owned(),
-        };
-
-        self.writer.write_all(req.as_bytes()).await?;
-
-        Ok(())
+        codec::write(&mut self.writer, &req).await
     }
 }
 
 #[async_trait]
 impl ResponseReader for TcpHandler {
     async fn read(&mut self) -> Result<Response> {
-        let mut res = String::new();
-        self.reader.read_line(&mut res).await?;
-
-        let mut tokens = res.split_whitespace();
-        match tokens.next() {
-            Some("ok") => Ok(Response::Ok),
-            Some("timer") => match tokens.next().map(serde_json::from_str::<Timer>) {
-                Some(Ok(timer)) => Ok(Response::Timer(timer)),
-                Some(Err(err)) => Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("invalid timer: {err}"),
-                )),
-                None => Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    "missing timer".to_owned(),
-                )),
-            },
-            Some(res) => Err(Error::new(
-                ErrorKind::InvalidInput,
-                format!("invalid response: {res}"),
-            )),
-            None => Err(Error::new(
-                ErrorKind::InvalidInput,
-                "missing response".to_owned(),
-            )),
-        }
+        codec::read(&mut self.reader).await
     }
 }
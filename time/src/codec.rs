@@ -0,0 +1,265 @@
+//! # Codec
+//!
+//! This module contains the wire format of requests and responses,
+//! which lets clients and servers talk to each other over any
+//! transport, from any language.
+//!
+//! Each message is sent as a frame: a JSON object written on a single
+//! line and terminated by a line feed. The object contains the
+//! version of the wire format and the message itself, tagged by its
+//! type:
+//!
+//! ```text
+//! {"version":1,"message":{"type":"start"}}
+//! {"version":1,"message":{"type":"set","value":25}}
+//! {"version":1,"message":{"type":"ok"}}
+//! {"version":1,"message":{"type":"timer","value":{"state":"running",…}}}
+//! ```
+//!
+//! Request types are `start`, `get`, `get-all`, `set`, `add`,
+//! `subtract`, `pause`, `resume` and `stop`. Response types are `ok`,
+//! `timer`, `timers` and `error`.
+//!
+//! Frames from a newer version of the wire format, as well as unknown
+//! message types, are rejected with an [`io::ErrorKind::InvalidData`]
+//! error. Since the whole frame is consumed anyway, the next frame
+//! can still be read from the same stream.
+//!
+//! Frames cannot exceed [`MAX_FRAME_LEN`] bytes, so that a peer
+//! cannot make the other side buffer an endless line. Longer frames
+//! are rejected with an [`io::ErrorKind::InvalidData`] error as well,
+//! but they are not consumed: the stream should be dropped.
+
+use std::io;
+
+use futures::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The current version of the wire format.
+pub const VERSION: u32 = 1;
+
+/// The maximum length of a frame, in bytes, including the trailing
+/// line feed.
+pub const MAX_FRAME_LEN: u64 = 64 * 1024;
+
+#[derive(Serialize)]
+struct EncodeFrame<'a, T> {
+    version: u32,
+    message: &'a T,
+}
+
+#[derive(Deserialize)]
+struct DecodeFrame {
+    version: u32,
+    message: serde_json::Value,
+}
+
+/// Encode the given message into a frame, including the trailing
+/// line feed.
+pub fn encode<T: Serialize>(message: &T) -> io::Result<Vec<u8>> {
+    let frame = EncodeFrame {
+        version: VERSION,
+        message,
+    };
+
+    let mut frame = serde_json::to_vec(&frame)?;
+    frame.push(b'\n');
+
+    Ok(frame)
+}
+
+/// Decode the given frame into a message.
+///
+/// The trailing line feed is optional.
+pub fn decode<T: DeserializeOwned>(frame: &[u8]) -> io::Result<T> {
+    let frame: DecodeFrame = serde_json::from_slice(frame)?;
+
+    if frame.version > VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported wire format version {}", frame.version),
+        ));
+    }
+
+    Ok(serde_json::from_value(frame.message)?)
+}
+
+/// Write the given message as a frame into the given writer.
+pub async fn write<T, W>(writer: &mut W, message: &T) -> io::Result<()>
+where
+    T: Serialize + Sync,
+    W: AsyncWrite + Unpin + Send,
+{
+    writer.write_all(&encode(message)?).await?;
+    writer.flush().await
+}
+
+/// Read the next frame from the given reader, then decode it into a
+/// message.
+///
+/// Fails if the frame exceeds [`MAX_FRAME_LEN`] bytes.
+pub async fn read<T, R>(reader: &mut R) -> io::Result<T>
+where
+    T: DeserializeOwned,
+    R: AsyncBufRead + Unpin + Send,
+{
+    let mut frame = Vec::new();
+
+    let len = reader
+        .take(MAX_FRAME_LEN)
+        .read_until(b'\n', &mut frame)
+        .await?;
+
+    if len == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "missing frame".to_owned(),
+        ));
+    }
+
+    if len as u64 == MAX_FRAME_LEN && frame.last() != Some(&b'\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame exceeds the maximum length of {MAX_FRAME_LEN} bytes"),
+        ));
+    }
+
+    decode(&frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    #[cfg(feature = "async-std")]
+    use async_std::test;
+    use futures::io::Cursor;
+    #[cfg(feature = "tokio")]
+    use tokio::test;
+
+    use crate::{
+        request::Request,
+        response::Response,
+        timer::{Timer, TimerCycle, TimerState},
+    };
+
+    #[test_log::test]
+    fn round_trip_requests() {
+        let reqs = [
+            Request::Start,
+            Request::Get,
+            Request::GetAll,
+            Request::Set(25),
+            Request::Add(60),
+            Request::Subtract(30),
+            Request::Pause,
+            Request::Resume,
+            Request::Stop,
+        ];
+
+        for req in reqs {
+            let frame = super::encode(&req).unwrap();
+            assert_eq!(super::decode::<Request>(&frame).unwrap(), req);
+        }
+    }
+
+    #[test_log::test]
+    fn round_trip_responses() {
+        let timer = Timer {
+            state: TimerState::Running,
+            cycle: TimerCycle::new("Work", 25),
+            ..Timer::default()
+        };
+
+        let timers = vec![("Work".to_owned(), timer.clone())];
+
+        for res in [
+            Response::Ok,
+            Response::Timer(timer),
+            Response::Timers(timers),
+            Response::Error(String::from("unauthorized")),
+        ] {
+            let frame = super::encode(&res).unwrap();
+            assert_eq!(super::decode::<Response>(&frame).unwrap(), res);
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[test_log::test]
+    fn encode_timer_derived_fields() {
+        let timer = Timer {
+            state: TimerState::Running,
+            cycle: TimerCycle::new("Work", 25),
+            ..Timer::default()
+        };
+
+        let frame = super::encode(&Response::Timer(timer)).unwrap();
+        let frame = String::from_utf8(frame).unwrap();
+
+        assert!(frame.contains("\"elapsed\":0,\"remaining\":25,\"percent\":0"));
+    }
+
+    #[test_log::test]
+    fn decode_fixed_frames() {
+        let frame = b"{\"version\":1,\"message\":{\"type\":\"set\",\"value\":25}}\n";
+        assert_eq!(super::encode(&Request::Set(25)).unwrap(), frame);
+        assert_eq!(super::decode::<Request>(frame).unwrap(), Request::Set(25));
+
+        let frame = b"{\"version\":1,\"message\":{\"type\":\"ok\"}}\n";
+        assert_eq!(super::encode(&Response::Ok).unwrap(), frame);
+        assert_eq!(super::decode::<Response>(frame).unwrap(), Response::Ok);
+    }
+
+    #[test_log::test]
+    fn reject_unsupported_frames() {
+        let frame = b"{\"version\":2,\"message\":{\"type\":\"start\"}}\n";
+        let err = super::decode::<Request>(frame).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let frame = b"{\"version\":1,\"message\":{\"type\":\"unknown\"}}\n";
+        let err = super::decode::<Request>(frame).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test_log::test(test)]
+    async fn read_write_frames() {
+        let mut stream = Cursor::new(Vec::new());
+        super::write(&mut stream, &Request::Start).await.unwrap();
+        super::write(&mut stream, &Request::Set(5)).await.unwrap();
+
+        stream.set_position(0);
+        assert_eq!(
+            super::read::<Request, _>(&mut stream).await.unwrap(),
+            Request::Start,
+        );
+        assert_eq!(
+            super::read::<Request, _>(&mut stream).await.unwrap(),
+            Request::Set(5),
+        );
+
+        let err = super::read::<Request, _>(&mut stream).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test_log::test(test)]
+    async fn reject_too_long_frames() {
+        // the frame never ends, it is not buffered beyond the limit
+        let frame = vec![b' '; super::MAX_FRAME_LEN as usize * 2];
+        let mut stream = Cursor::new(frame);
+
+        let err = super::read::<Request, _>(&mut stream).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(stream.position(), super::MAX_FRAME_LEN);
+
+        // a frame of the maximum length is still accepted
+        let msg = super::encode(&Request::Start).unwrap();
+        let mut frame = vec![b' '; super::MAX_FRAME_LEN as usize - msg.len()];
+        frame.extend(msg);
+        let mut stream = Cursor::new(frame);
+
+        assert_eq!(
+            super::read::<Request, _>(&mut stream).await.unwrap(),
+            Request::Start,
+        );
+    }
+}
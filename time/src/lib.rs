@@ -3,6 +3,8 @@
 
 #[cfg(feature = "client")]
 pub mod client;
+#[cfg(feature = "json")]
+pub mod codec;
 pub(crate) mod handler;
 pub mod request;
 pub mod response;
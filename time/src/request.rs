@@ -12,6 +12,11 @@
 ///
 /// Requests are sent by clients and received by servers.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "derive",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "kebab-case")
+)]
 pub enum Request {
     /// Request the timer to start with the first configured cycle.
     Start,
@@ -19,9 +24,30 @@ pub enum Request {
     /// Request the state, the cycle and the value of the timer.
     Get,
 
+    /// Request the state, the cycle and the value of all the timers
+    /// at once.
+    ///
+    /// The server answers with a snapshot of every timer, named. A
+    /// server running a single timer answers with a one-element
+    /// list, named [`DEFAULT_TIMER_NAME`].
+    ///
+    /// [`DEFAULT_TIMER_NAME`]: crate::timer::DEFAULT_TIMER_NAME
+    GetAll,
+
     /// Request to change the current timer duration.
     Set(usize),
 
+    /// Request to add the given number of seconds to the current
+    /// cycle.
+    Add(usize),
+
+    /// Request to subtract the given number of seconds from the
+    /// current cycle.
+    ///
+    /// The remaining duration cannot go below zero: subtracting more
+    /// than the remaining duration ends the current cycle.
+    Subtract(usize),
+
     /// Request to pause the timer.
     ///
     /// A paused timer freezes, which means it keeps its state, cycle
@@ -14,12 +14,25 @@
 ///
 /// Responses are sent by servers and received by clients.
 #[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "derive",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "kebab-case")
+)]
 pub enum Response {
     /// Default response when everything goes as expected.
     Ok,
 
     /// Response containing the current timer.
     Timer(Timer),
+
+    /// Response containing the current state of every timer, along
+    /// with their name.
+    Timers(Vec<(String, Timer)>),
+
+    /// Response sent when the request cannot be processed, for
+    /// example when the client is not authorized.
+    Error(String),
 }
 
 /// Trait to read a server response.
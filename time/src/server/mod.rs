@@ -31,7 +31,7 @@
     handler::{self, Handler},
     request::{Request, RequestReader},
     response::{Response, ResponseWriter},
-    timer::{ThreadSafeTimer, TimerConfig, TimerCycle, TimerEvent, TimerLoop},
+    timer::{ThreadSafeTimer, TimerConfig, TimerCycle, TimerEvent, TimerLoop, DEFAULT_TIMER_NAME},
 };
 
 /// The server state enum.
@@ -157,11 +157,27 @@ async fn handle(&mut self, timer: ThreadSafeTimer) -> Result<()> {
                 trace!("{timer:#?}");
                 Response::Timer(timer)
             }
+            Request::GetAll => {
+                debug!("getting all timers");
+                let timer = timer.get().await;
+                trace!("{timer:#?}");
+                Response::Timers(vec![(DEFAULT_TIMER_NAME.to_owned(), timer)])
+            }
             Request::Set(duration) => {
                 debug!("setting timer");
                 timer.set(duration).await?;
                 Response::Ok
             }
+            Request::Add(duration) => {
+                debug!("adding {duration}s to timer");
+                timer.add(duration).await?;
+                Response::Timer(timer.get().await)
+            }
+            Request::Subtract(duration) => {
+                debug!("subtracting {duration}s from timer");
+                timer.subtract(duration).await?;
+                Response::Timer(timer.get().await)
+            }
             Request::Pause => {
                 debug!("pausing timer");
                 timer.pause().await?;
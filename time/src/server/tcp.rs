@@ -3,20 +3,21 @@
 //! This module contains the implementation of the TCP server binder,
 //! based on [`tokio::net::TcpStream`].
 
-use std::io;
+use std::{fmt, io};
 
 #[cfg(feature = "async-std")]
-use async_std::net::TcpListener;
+use async_std::{net::TcpListener, task::spawn};
 use async_trait::async_trait;
-use futures::{AsyncBufReadExt, AsyncWriteExt};
+use futures::AsyncWriteExt;
 #[cfg(feature = "tokio")]
-use tokio::net::TcpListener;
+use tokio::{net::TcpListener, task::spawn};
 use tracing::debug;
 
 use crate::{
+    codec,
     request::{Request, RequestReader},
     response::{Response, ResponseWriter},
-    tcp::TcpHandler,
+    tcp::{TcpHandler, TcpHandshake},
     timer::ThreadSafeTimer,
 };
 
@@ -25,14 +26,26 @@
 /// The TCP server binder.
 ///
 /// This [`ServerBind`]er uses the TCP protocol to bind a listener, to
-/// read requests and write responses.
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// read requests and write responses using the [`codec`] wire
+/// format. Clients are handled concurrently, and all of them share
+/// the same timer.
+///
+/// When a token is defined, clients need to authenticate with the
+/// same token before sending their request, see [`TcpHandshake`].
+#[derive(Clone, Eq, PartialEq)]
 pub struct TcpBind {
     /// The TCP host of the listener.
     pub host: String,
 
     /// The TCP port of the listener.
     pub port: u16,
+
+    /// The token clients need to authenticate with.
+    ///
+    /// The token should not be hardcoded: it should rather be
+    /// retrieved from a secret store (keyring, password manager
+    /// command etc.) or from an environment variable.
+    pub token: Option<String>,
 }
 
 impl TcpBind {
@@ -41,10 +54,36 @@ pub fn new(host: impl ToString, port: u16) -> Box<dyn ServerBind> {
         Box::new(Self {
             host: host.to_string(),
             port,
+            token: None,
+        })
+    }
+
+    /// Create a new TCP binder using the given host and port,
+    /// requiring clients to authenticate with the given token.
+    pub fn new_with_token(
+        host: impl ToString,
+        port: u16,
+        token: impl ToString,
+    ) -> Box<dyn ServerBind> {
+        Box::new(Self {
+            host: host.to_string(),
+            port,
+            token: Some(token.to_string()),
         })
     }
 }
 
+// The token is a secret, it should not appear in logs.
+impl fmt::Debug for TcpBind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TcpBind")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("token", &self.token.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
 #[async_trait]
 impl ServerBind for TcpBind {
     async fn bind(&self, timer: ThreadSafeTimer) -> io::Result<()> {
@@ -55,11 +94,26 @@ async fn bind(&self, timer: ThreadSafeTimer) -> io::Result<()> {
                 Ok((stream, _)) => {
                     debug!("TCP connection accepted");
 
-                    let mut handler = TcpHandler::new(stream);
-                    if let Err(err) = handler.handle(timer.clone()).await {
-                        debug!("cannot handle request");
-                        debug!("{err:?}");
-                    }
+                    // each connection is handled in its own task, so
+                    // that a slow client does not block other ones
+                    let timer = timer.clone();
+                    let token = self.token.clone();
+                    spawn(async move {
+                        let mut handler = TcpHandler::new(stream);
+
+                        if let Some(token) = token {
+                            if let Err(err) = handler.verify_token(&token).await {
+                                debug!("cannot authenticate client");
+                                debug!("{err:?}");
+                                return;
+                            }
+                        }
+
+                        if let Err(err) = handler.handle(timer).await {
+                            debug!("cannot handle request");
+                            debug!("{err:?}");
+                        }
+                    });
                 }
                 Err(err) => {
                     debug!("cannot get stream from client");
@@ -70,54 +124,53 @@ async fn bind(&self, timer: ThreadSafeTimer) -> io::Result<()> {
     }
 }
 
+impl TcpHandler {
+    /// Read the client handshake and check its token.
+    ///
+    /// On mismatch, an error response is sent back to the client and
+    /// the connection is closed.
+    async fn verify_token(&mut self, token: &str) -> io::Result<()> {
+        let authorized = match codec::read(&mut self.reader).await {
+            Ok(TcpHandshake::Auth(client_token)) => tokens_eq(&client_token, token),
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => false,
+            Err(err) => return Err(err),
+        };
+
+        if authorized {
+            return codec::write(&mut self.writer, &Response::Ok).await;
+        }
+
+        let res = Response::Error(String::from("unauthorized"));
+        codec::write(&mut self.writer, &res).await?;
+        self.writer.close().await?;
+
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "invalid client token",
+        ))
+    }
+}
+
+/// Compare the given tokens in constant time, so that the duration of
+/// the comparison does not leak the expected token.
+fn tokens_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
 #[async_trait]
 impl RequestReader for TcpHandler {
     async fn read(&mut self) -> io::Result<Request> {
-        let mut req = String::new();
-        self.reader.read_line(&mut req).await?;
-
-        let mut tokens = req.split_whitespace();
-        match tokens.next() {
-            Some("start") => Ok(Request::Start),
-            Some("get") => Ok(Request::Get),
-            Some("set") => match tokens.next().map(|duration| duration.parse::<usize>()) {
-                Some(Ok(duration)) => Ok(Request::Set(duration)),
-                Some(Err(err)) => Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    format!("invalid duration: {err}"),
-                )),
-                None => Err(io::Error::new(
-                    io::ErrorKind::InvalidInput,
-                    "missing duration".to_owned(),
-                )),
-            },
-            Some("pause") => Ok(Request::Pause),
-            Some("resume") => Ok(Request::Resume),
-            Some("stop") => Ok(Request::Stop),
-            Some(req) => Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("invalid request: {req}"),
-            )),
-            None => Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "missing request".to_owned(),
-            )),
-        }
+        codec::read(&mut self.reader).await
     }
 }
 
 #[async_trait]
 impl ResponseWriter for TcpHandler {
     async fn write(&mut self, res: Response) -> io::Result<()> {
-        let res = match res {
-            Response::Ok => "ok\n".to_string(),
-            Response::Timer(timer) => {
-                format!("timer {}\n", serde_json::to_string(&timer).unwrap())
-            }
-        };
-
-        self.writer.write_all(res.as_bytes()).await?;
-
-        Ok(())
+        codec::write(&mut self.writer, &res).await
     }
 }
@@ -30,6 +30,23 @@ pub struct TcpConfig {
     pub port: u16,
 }
 
+/// The TCP handshake.
+///
+/// When a token is shared between the client and the server, the
+/// client sends this handshake right after connecting, before its
+/// request. The server answers with [`Response::Ok`] if the token
+/// matches, otherwise with [`Response::Error`] then closes the
+/// connection without handling any request.
+///
+/// [`Response::Ok`]: crate::response::Response::Ok
+/// [`Response::Error`]: crate::response::Response::Error
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "kebab-case")]
+pub enum TcpHandshake {
+    /// Authenticate using the given token.
+    Auth(String),
+}
+
 pub struct TcpHandler {
     pub reader: BufReader<ReadHalf<TcpStream>>,
     pub writer: WriteHalf<TcpStream>,
@@ -90,16 +107,16 @@ fn poll_write(
     }
 
     fn poll_flush(
-        self: Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
     ) -> Poll<std::io::Result<()>> {
-        todo!()
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
     }
 
     fn poll_close(
-        self: Pin<&mut Self>,
-        _cx: &mut std::task::Context<'_>,
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
     ) -> Poll<std::io::Result<()>> {
-        todo!()
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
     }
 }
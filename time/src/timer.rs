@@ -24,6 +24,9 @@
 
 use crate::handler::{self, Handler};
 
+/// The name of the timer of servers running a single timer.
+pub const DEFAULT_TIMER_NAME: &str = "default";
+
 /// The timer loop.
 ///
 /// When the timer reaches its last cycle, it starts again from the
@@ -183,6 +186,23 @@ pub enum TimerEvent {
     Stopped,
 }
 
+/// The timer extension.
+///
+/// Time added to the current cycle, on top of its configured
+/// duration. The cycle runs as configured till its end, then keeps
+/// running for the extension duration before switching to the next
+/// cycle.
+#[cfg(feature = "server")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimerExtension {
+    /// The elapsed time at which the current cycle was supposed to
+    /// end.
+    pub ends_at: usize,
+
+    /// The duration added to the current cycle.
+    pub duration: usize,
+}
+
 /// The timer configuration.
 #[derive(Clone)]
 pub struct TimerConfig {
@@ -228,12 +248,21 @@ fn clone_first_cycle(&self) -> Result<TimerCycle> {
 }
 
 /// The main timer struct.
+///
+/// When serialized by the server, the timer also includes its derived
+/// fields `remaining` and `percent` (see [`Timer::remaining`] and
+/// `Timer::percent`), so that thin clients do not need to compute
+/// them.
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(
     feature = "derive",
     derive(serde::Serialize, serde::Deserialize),
     serde(rename_all = "kebab-case")
 )]
+#[cfg_attr(
+    all(feature = "derive", feature = "server"),
+    serde(into = "SerializedTimer")
+)]
 pub struct Timer {
     /// The current timer configuration.
     #[cfg_attr(feature = "derive", serde(skip))]
@@ -254,10 +283,22 @@ pub struct Timer {
 
     #[cfg(feature = "server")]
     pub elapsed: usize,
+
+    /// The time added to the current cycle, if any.
+    #[cfg(feature = "server")]
+    #[cfg_attr(feature = "derive", serde(skip))]
+    pub extension: Option<TimerExtension>,
 }
 
 impl Eq for Timer {}
 
+impl Timer {
+    /// Get the remaining duration of the current cycle, in seconds.
+    pub fn remaining(&self) -> usize {
+        self.cycle.duration
+    }
+}
+
 #[cfg(feature = "server")]
 impl PartialEq for Timer {
     fn eq(&self, other: &Self) -> bool {
@@ -281,20 +322,110 @@ pub fn elapsed(&self) -> usize {
             + self.elapsed
     }
 
-    pub async fn update(&mut self) {
-        let mut elapsed = self.elapsed();
+    /// Get the percentage of the current cycle already elapsed, from
+    /// 0 to 100.
+    ///
+    /// A cycle without duration is considered as complete.
+    pub fn percent(&self) -> u8 {
+        let configured = self
+            .config
+            .cycles
+            .iter()
+            .find(|cycle| cycle.name == self.cycle.name)
+            .map(|cycle| cycle.duration)
+            .unwrap_or_default();
+        let extension = self
+            .extension
+            .as_ref()
+            .map(|ext| ext.duration)
+            .unwrap_or_default();
+
+        let remaining = self.remaining();
+        let total = (configured + extension).max(remaining);
+
+        if total == 0 {
+            return 100;
+        }
+
+        ((total - remaining) * 100 / total) as u8
+    }
+
+    /// Get the configured cycle at the given elapsed time, with its
+    /// remaining duration.
+    fn cycle_at(&self, elapsed: usize) -> TimerCycle {
+        let (cycles, total_duration) = self.total_cycles();
+        let elapsed = elapsed % total_duration;
+
+        let last_cycle = cycles[cycles.len() - 1].clone();
+        cycles
+            .into_iter()
+            .fold(None, |next_cycle, mut cycle| match next_cycle {
+                None if elapsed < cycle.duration => {
+                    cycle.duration -= elapsed;
+                    Some(cycle)
+                }
+                _ => next_cycle,
+            })
+            .unwrap_or(last_cycle)
+    }
+
+    /// Get the configured cycles with their cumulated durations, as
+    /// well as the total duration of a loop.
+    fn total_cycles(&self) -> (Vec<TimerCycle>, usize) {
+        self.config.cycles.iter().cloned().fold(
+            (Vec::new(), 0),
+            |(mut cycles, mut sum), mut cycle| {
+                cycle.duration += sum;
+                sum = cycle.duration;
+                cycles.push(cycle);
+                (cycles, sum)
+            },
+        )
+    }
 
+    /// Get the remaining duration of the current cycle at the given
+    /// elapsed time, including its extension.
+    fn remaining_at(&self, elapsed: usize) -> usize {
+        match &self.extension {
+            Some(ext) => (ext.ends_at + ext.duration).saturating_sub(elapsed),
+            None => self.cycle_at(elapsed).duration,
+        }
+    }
+
+    /// Move the accumulated running time to the elapsed field, so
+    /// that it can be adjusted.
+    fn rebase(&mut self) {
+        self.elapsed = self.elapsed();
+
+        if self.started_at.is_some() {
+            self.started_at = Some(Instant::now());
+        }
+    }
+
+    pub async fn update(&mut self) {
         match self.state {
             TimerState::Running => {
-                let (cycles, total_duration) = self.config.cycles.iter().cloned().fold(
-                    (Vec::new(), 0),
-                    |(mut cycles, mut sum), mut cycle| {
-                        cycle.duration += sum;
-                        sum = cycle.duration;
-                        cycles.push(cycle);
-                        (cycles, sum)
-                    },
-                );
+                let mut elapsed = self.elapsed();
+
+                if let Some(ext) = self.extension.clone() {
+                    if elapsed < ext.ends_at + ext.duration {
+                        // the current cycle is extended, it keeps
+                        // running whatever the configured cycles
+                        self.cycle.duration = ext.ends_at + ext.duration - elapsed;
+                        self.fire_event(TimerEvent::Running(self.cycle.clone()))
+                            .await;
+                        return;
+                    }
+
+                    // the extension is over, the time spent in it
+                    // should not count for the next cycles
+                    self.rebase();
+                    self.elapsed -= ext.duration;
+                    self.extension = None;
+                    elapsed = self.elapsed;
+                }
+
+                let total_duration = self.total_cycles().1;
 
                 if let TimerLoop::Fixed(cycles_count) = self.cycles_count {
                     if elapsed >= (total_duration * cycles_count) {
@@ -303,19 +434,7 @@ pub async fn update(&mut self) {
                     }
                 }
 
-                elapsed %= total_duration;
-
-                let last_cycle = cycles[cycles.len() - 1].clone();
-                let next_cycle = cycles
-                    .into_iter()
-                    .fold(None, |next_cycle, mut cycle| match next_cycle {
-                        None if elapsed < cycle.duration => {
-                            cycle.duration -= elapsed;
-                            Some(cycle)
-                        }
-                        _ => next_cycle,
-                    })
-                    .unwrap_or(last_cycle);
+                let next_cycle = self.cycle_at(elapsed);
 
                 self.fire_event(TimerEvent::Running(self.cycle.clone()))
                     .await;
@@ -363,6 +482,7 @@ pub async fn start(&mut self) -> Result<()> {
             self.cycles_count = self.config.cycles_count.clone();
             self.started_at = Some(Instant::now());
             self.elapsed = 0;
+            self.extension = None;
             self.fire_events([TimerEvent::Started, TimerEvent::Began(self.cycle.clone())])
                 .await;
         }
@@ -375,6 +495,58 @@ pub async fn set(&mut self, duration: usize) -> Result<()> {
         Ok(())
     }
 
+    /// Add the given duration to the current cycle.
+    ///
+    /// Has no effect if the timer is stopped.
+    pub async fn add(&mut self, duration: usize) -> Result<()> {
+        if matches!(self.state, TimerState::Stopped) {
+            return Ok(());
+        }
+
+        self.rebase();
+
+        match &mut self.extension {
+            Some(ext) => ext.duration += duration,
+            None => {
+                let ends_at = self.elapsed + self.cycle_at(self.elapsed).duration;
+                self.extension = Some(TimerExtension { ends_at, duration });
+            }
+        }
+
+        self.cycle.duration = self.remaining_at(self.elapsed);
+        self.fire_event(TimerEvent::Set(self.cycle.clone())).await;
+        Ok(())
+    }
+
+    /// Subtract the given duration from the current cycle.
+    ///
+    /// The remaining duration cannot go below zero: subtracting more
+    /// than the remaining duration ends the current cycle. Has no
+    /// effect if the timer is stopped.
+    pub async fn subtract(&mut self, duration: usize) -> Result<()> {
+        if matches!(self.state, TimerState::Stopped) {
+            return Ok(());
+        }
+
+        self.rebase();
+
+        let remaining = self.remaining_at(self.elapsed);
+        let mut duration = duration.min(remaining);
+        self.cycle.duration = remaining - duration;
+
+        // the extension is consumed first
+        if let Some(ext) = &mut self.extension {
+            let consumed = duration.min(ext.duration);
+            ext.duration -= consumed;
+            duration -= consumed;
+        }
+
+        self.elapsed += duration;
+        self.fire_event(TimerEvent::Set(self.cycle.clone())).await;
+        self.update().await;
+        Ok(())
+    }
+
     pub async fn pause(&mut self) -> Result<()> {
         if matches!(self.state, TimerState::Running) {
             self.state = TimerState::Paused;
@@ -405,11 +577,40 @@ pub async fn stop(&mut self) -> Result<()> {
             self.cycles_count = self.config.cycles_count.clone();
             self.started_at = None;
             self.elapsed = 0;
+            self.extension = None;
         }
         Ok(())
     }
 }
 
+/// The serialized version of the [`Timer`], including its derived
+/// fields.
+#[cfg(all(feature = "derive", feature = "server"))]
+#[derive(serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct SerializedTimer {
+    state: TimerState,
+    cycle: TimerCycle,
+    cycles_count: TimerLoop,
+    elapsed: usize,
+    remaining: usize,
+    percent: u8,
+}
+
+#[cfg(all(feature = "derive", feature = "server"))]
+impl From<Timer> for SerializedTimer {
+    fn from(timer: Timer) -> Self {
+        Self {
+            remaining: timer.remaining(),
+            percent: timer.percent(),
+            state: timer.state,
+            cycle: timer.cycle,
+            cycles_count: timer.cycles_count,
+            elapsed: timer.elapsed,
+        }
+    }
+}
+
 /// Thread safe version of the [`Timer`].
 ///
 /// The server does not manipulate directly the [`Timer`], it uses
@@ -447,6 +648,14 @@ pub async fn set(&self, duration: usize) -> Result<()> {
         self.0.lock().await.set(duration).await
     }
 
+    pub async fn add(&self, duration: usize) -> Result<()> {
+        self.0.lock().await.add(duration).await
+    }
+
+    pub async fn subtract(&self, duration: usize) -> Result<()> {
+        self.0.lock().await.subtract(duration).await
+    }
+
     pub async fn pause(&self) -> Result<()> {
         self.0.lock().await.pause().await
     }
@@ -605,6 +814,77 @@ async fn stopped_timer_not_impacted_by_iterator() {
         assert_eq!(prev_timer, timer);
     }
 
+    #[cfg(feature = "server")]
+    #[test_log::test(test)]
+    async fn add_to_running_timer() {
+        let mut timer = testing_timer();
+
+        MockClock::advance(Duration::from_secs(2));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("a", 1));
+
+        // the added time goes beyond the time already spent in the
+        // cycle, the timer should not go back to the previous cycle
+        timer.add(5).await.unwrap();
+        assert_eq!(timer.state, TimerState::Running);
+        assert_eq!(timer.cycle, TimerCycle::new("a", 6));
+
+        MockClock::advance(Duration::from_secs(5));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("a", 1));
+
+        // once the extended cycle is over, next cycles keep their
+        // configured duration
+        MockClock::advance(Duration::from_secs(1));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("b", 2));
+
+        MockClock::advance(Duration::from_secs(2));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("c", 1));
+    }
+
+    #[cfg(feature = "server")]
+    #[test_log::test(test)]
+    async fn subtract_past_zero_from_running_timer() {
+        static EVENTS: Lazy<Mutex<Vec<TimerEvent>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+        let mut timer = testing_timer();
+
+        timer.config.handler = Arc::new(|evt| {
+            Box::pin(async {
+                EVENTS.lock().await.push(evt);
+                Ok(())
+            })
+        });
+
+        MockClock::advance(Duration::from_secs(1));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("a", 2));
+
+        // subtracting more than the remaining duration completes the
+        // current cycle
+        timer.subtract(10).await.unwrap();
+        assert_eq!(timer.state, TimerState::Running);
+        assert_eq!(timer.cycle, TimerCycle::new("b", 2));
+
+        MockClock::advance(Duration::from_secs(1));
+        timer.update().await;
+        assert_eq!(timer.cycle, TimerCycle::new("b", 1));
+
+        assert_eq!(
+            *EVENTS.lock().await,
+            vec![
+                TimerEvent::Running(TimerCycle::new("a", 3)),
+                TimerEvent::Set(TimerCycle::new("a", 0)),
+                TimerEvent::Running(TimerCycle::new("a", 0)),
+                TimerEvent::Ended(TimerCycle::new("a", 0)),
+                TimerEvent::Began(TimerCycle::new("b", 2)),
+                TimerEvent::Running(TimerCycle::new("b", 2)),
+            ]
+        );
+    }
+
     #[cfg(feature = "server")]
     #[test_log::test(test)]
     async fn thread_safe_timer() {
@@ -695,4 +975,44 @@ async fn thread_safe_timer() {
             ]
         );
     }
+
+    #[test_log::test(test)]
+    async fn derived_fields() {
+        let mut timer = Timer {
+            config: TimerConfig {
+                cycles: TimerCycles::from([TimerCycle::new("a", 4), TimerCycle::new("b", 2)]),
+                ..Default::default()
+            },
+            state: TimerState::Running,
+            cycle: TimerCycle::new("a", 4),
+            started_at: Some(Instant::now()),
+            ..Default::default()
+        };
+
+        // fresh timer
+
+        assert_eq!(timer.elapsed(), 0);
+        assert_eq!(timer.remaining(), 4);
+        assert_eq!(timer.percent(), 0);
+
+        // half-done timer
+
+        MockClock::advance(Duration::from_secs(2));
+        timer.update().await;
+
+        assert_eq!(timer.elapsed(), 2);
+        assert_eq!(timer.remaining(), 2);
+        assert_eq!(timer.percent(), 50);
+
+        // completed timer
+
+        timer.set(0).await.unwrap();
+
+        assert_eq!(timer.remaining(), 0);
+        assert_eq!(timer.percent(), 100);
+
+        // timer without duration
+
+        assert_eq!(Timer::default().percent(), 100);
+    }
 }
@@ -0,0 +1,123 @@
+#![cfg(feature = "tokio")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::StreamExt;
+use time::{
+    client::{reconnect::ReconnectClient, tcp::TcpClient, Client},
+    codec,
+    request::Request,
+    response::Response,
+    tcp::TcpHandler,
+    timer::Timer,
+};
+use tokio::{net::TcpListener, task::JoinHandle, time::sleep};
+
+static HOST: &str = "127.0.0.1";
+
+/// Spawn a mock server answering the given number of requests, then
+/// stopping.
+fn spawn_mock_server(listener: TcpListener, requests: usize) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        for _ in 0..requests {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut handler = TcpHandler::new(stream);
+
+            let res = match codec::read(&mut handler.reader).await.unwrap() {
+                Request::Get => Response::Timer(Timer::default()),
+                _ => Response::Ok,
+            };
+
+            codec::write(&mut handler.writer, &res).await.unwrap();
+        }
+    })
+}
+
+#[test_log::test(tokio::test)]
+async fn reconnect_tcp_client() {
+    let listener = TcpListener::bind((HOST, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let client = ReconnectClient::new(TcpClient::new_boxed(HOST, port))
+        .with_max_attempts(10)
+        .with_backoff(Duration::from_millis(50))
+        .with_max_backoff(Duration::from_millis(200));
+
+    // the server stops after the first request
+    spawn_mock_server(listener, 1);
+    client.start().await.unwrap();
+
+    // the server restarts in the middle of the session
+    let server = tokio::spawn(async move {
+        sleep(Duration::from_millis(300)).await;
+        let listener = TcpListener::bind((HOST, port)).await.unwrap();
+        spawn_mock_server(listener, 3).await.unwrap();
+    });
+
+    assert_eq!(client.get().await.unwrap(), Timer::default());
+
+    let responses: Vec<_> = client
+        .watch(Duration::from_millis(10))
+        .take(2)
+        .collect()
+        .await;
+    assert_eq!(responses.len(), 2);
+    for res in responses {
+        assert_eq!(res.unwrap(), Response::Timer(Timer::default()));
+    }
+
+    server.await.unwrap();
+
+    // the server is gone for good, attempts are bounded
+    let client = ReconnectClient::new(TcpClient::new_boxed(HOST, port))
+        .with_max_attempts(2)
+        .with_backoff(Duration::from_millis(10));
+
+    assert!(client.stop().await.is_err());
+}
+
+/// Spawn a mock server reading requests, then closing connections
+/// without answering.
+fn spawn_mute_server(listener: TcpListener, requests: Arc<AtomicUsize>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut handler = TcpHandler::new(stream);
+            codec::read(&mut handler.reader).await.unwrap();
+            requests.fetch_add(1, Ordering::SeqCst);
+        }
+    })
+}
+
+#[test_log::test(tokio::test)]
+async fn reconnect_tcp_client_non_idempotent_requests() {
+    let listener = TcpListener::bind((HOST, 0)).await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = spawn_mute_server(listener, requests.clone());
+
+    let client = ReconnectClient::new(TcpClient::new_boxed(HOST, port))
+        .with_max_attempts(3)
+        .with_backoff(Duration::from_millis(10));
+
+    // the connection is lost after the request got written, which
+    // means that the server may have applied it: it is not retried
+    assert!(client.add(Duration::from_secs(60)).await.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    assert!(client.subtract(Duration::from_secs(60)).await.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    // idempotent requests are retried
+    assert!(client.get().await.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 5);
+
+    server.abort();
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "tokio")]
+
+use std::{io::ErrorKind, net, time::Duration};
+
+use time::{
+    client::tcp::TcpClient,
+    server::{tcp::TcpBind, ServerBuilder},
+    timer::TimerState,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::sleep,
+};
+
+static HOST: &str = "127.0.0.1";
+static TOKEN: &str = "secret-token";
+
+/// Find an available ephemeral port.
+fn ephemeral_port() -> u16 {
+    let listener = net::TcpListener::bind((HOST, 0)).unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+#[test_log::test(tokio::test)]
+async fn tcp_auth() {
+    let port = ephemeral_port();
+
+    let server = ServerBuilder::new()
+        .with_binder(TcpBind::new_with_token(HOST, port, TOKEN))
+        .with_cycle(("Work", 10))
+        .build()
+        .unwrap();
+
+    server
+        .bind_with(move || async move {
+            sleep(Duration::from_millis(500)).await;
+
+            // a client with a wrong token is rejected
+            let client = TcpClient::new_boxed_with_token(HOST, port, "wrong-token");
+            let err = client.start().await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+            // a client without token is rejected
+            let client = TcpClient::new_boxed(HOST, port);
+            let err = client.start().await.unwrap_err();
+            assert!(err.to_string().contains("unauthorized"));
+
+            // the server sends back an error then closes the connection
+            let mut stream = BufReader::new(TcpStream::connect((HOST, port)).await.unwrap());
+            let handshake = r#"{"version":1,"message":{"type":"auth","value":"wrong-token"}}"#;
+            stream.write_all(handshake.as_bytes()).await.unwrap();
+            stream.write_all(b"\n").await.unwrap();
+
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            assert!(line.contains(r#""type":"error""#));
+            assert_eq!(stream.read(&mut [0; 1]).await.unwrap(), 0);
+
+            // rejected clients did not change the timer
+            let client = TcpClient::new_boxed_with_token(HOST, port, TOKEN);
+            assert_eq!(client.get().await.unwrap().state, TimerState::Stopped);
+
+            // a client with the right token is accepted
+            client.start().await.unwrap();
+            assert_eq!(client.get().await.unwrap().state, TimerState::Running);
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
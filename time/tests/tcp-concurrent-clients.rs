@@ -0,0 +1,70 @@
+use std::{io::ErrorKind, net, time::Duration};
+
+#[cfg(feature = "async-std")]
+use async_std::{future::timeout, task::sleep, test};
+use time::{
+    client::tcp::TcpClient,
+    server::{tcp::TcpBind, ServerBuilder},
+    timer::TimerState,
+};
+#[cfg(feature = "tokio")]
+use tokio::{
+    test,
+    time::{sleep, timeout},
+};
+
+static HOST: &str = "127.0.0.1";
+
+/// Find an available ephemeral port.
+fn ephemeral_port() -> u16 {
+    let listener = net::TcpListener::bind((HOST, 0)).unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+#[test_log::test(test)]
+async fn tcp_concurrent_clients() {
+    let port = ephemeral_port();
+
+    let server = ServerBuilder::new()
+        .with_binder(TcpBind::new(HOST, port))
+        .with_cycle(("Work", 10))
+        .build()
+        .unwrap();
+
+    server
+        .bind_with(move || async move {
+            sleep(Duration::from_millis(500)).await;
+
+            // an idle connection should not block other clients
+            let _idle = net::TcpStream::connect((HOST, port)).unwrap();
+
+            let client1 = TcpClient::new_boxed(HOST, port);
+            let client2 = TcpClient::new_boxed(HOST, port);
+
+            timeout(Duration::from_secs(5), client1.start())
+                .await
+                .unwrap()
+                .unwrap();
+
+            let (timer1, timer2) = futures::join!(client1.get(), client2.get());
+            let (timer1, timer2) = (timer1.unwrap(), timer2.unwrap());
+
+            assert_eq!(timer1.state, TimerState::Running);
+            assert_eq!(timer2.state, TimerState::Running);
+            assert_eq!(timer1.cycle.name, "Work");
+            assert_eq!(timer2.cycle.name, "Work");
+
+            Ok(())
+        })
+        .await
+        .unwrap();
+}
+
+#[test_log::test(test)]
+async fn tcp_connection_refused() {
+    let port = ephemeral_port();
+    let client = TcpClient::new_boxed(HOST, port);
+
+    let err = client.get().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+}
@@ -0,0 +1,77 @@
+use std::{net, time::Duration};
+
+#[cfg(feature = "async-std")]
+use async_std::{task::sleep, test};
+use time::{
+    client::tcp::TcpClient,
+    server::{tcp::TcpBind, Server, ServerBuilder},
+    timer::{TimerState, DEFAULT_TIMER_NAME},
+};
+#[cfg(feature = "tokio")]
+use tokio::{test, time::sleep};
+
+static HOST: &str = "127.0.0.1";
+
+/// Find an available ephemeral port.
+fn ephemeral_port() -> u16 {
+    let listener = net::TcpListener::bind((HOST, 0)).unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+fn build_server(port: u16) -> Server {
+    ServerBuilder::new()
+        .with_binder(TcpBind::new(HOST, port))
+        .with_cycle(("Work", 10))
+        .build()
+        .unwrap()
+}
+
+#[test_log::test(test)]
+async fn tcp_get_all_timers() {
+    let (port1, port2) = (ephemeral_port(), ephemeral_port());
+
+    // the second server only runs alongside the first one
+    let server2 = build_server(port2).bind_with(|| async {
+        sleep(Duration::from_secs(5)).await;
+        Ok(())
+    });
+
+    let server1 = build_server(port1).bind_with(move || async move {
+        sleep(Duration::from_millis(500)).await;
+
+        let client1 = TcpClient::new_boxed(HOST, port1);
+        let client2 = TcpClient::new_boxed(HOST, port2);
+
+        client1.start().await.unwrap();
+        sleep(Duration::from_secs(2)).await;
+        client2.start().await.unwrap();
+        sleep(Duration::from_secs(1)).await;
+
+        let (timers1, timers2) = futures::join!(client1.get_all(), client2.get_all());
+        let (timers1, timers2) = (timers1.unwrap(), timers2.unwrap());
+
+        // single timer servers answer with a one-element snapshot
+
+        assert_eq!(timers1.len(), 1);
+        assert_eq!(timers2.len(), 1);
+
+        let (name1, timer1) = &timers1[0];
+        let (name2, timer2) = &timers2[0];
+
+        assert_eq!(name1, DEFAULT_TIMER_NAME);
+        assert_eq!(name2, DEFAULT_TIMER_NAME);
+        assert_eq!(timer1.state, TimerState::Running);
+        assert_eq!(timer2.state, TimerState::Running);
+
+        // the snapshots reflect the distinct remaining durations of
+        // the timers, started 2 seconds apart
+
+        assert!(timer1.cycle.duration < timer2.cycle.duration);
+
+        Ok(())
+    });
+
+    let (res1, res2) = futures::join!(server1, server2);
+    res1.unwrap();
+    res2.unwrap();
+}